@@ -0,0 +1,302 @@
+use crate::VERSION;
+use ansi_term::Colour;
+use compiler::hir::very_concrete_program::{Wire, VCP};
+use constraint_generation::{build_dag, BuildConfig};
+use constraint_writers::graph_writer::GraphFormat;
+use constraint_writers::ConstraintExporter;
+use dag::{InstanceConstraints, SimplificationFlags};
+use json::JsonValue;
+use program_structure::ast::{AssignOp, Expression, SignalType, Statement, VariableType};
+use program_structure::ast_shortcuts::{BINARY, MAXBIT};
+use program_structure::error_definition::Report;
+use program_structure::file_definition::{FileID, FileLibrary, FileLocation};
+use program_structure::lint;
+use program_structure::program_archive::ProgramArchive;
+use std::collections::{BTreeMap, BTreeSet};
+
+pub struct AuditConfig {
+    pub output: String,
+    pub no_rounds: usize,
+    pub flag_s: bool,
+    pub flag_f: bool,
+    pub flag_old_heuristics: bool,
+    pub inspect_witness_flag: bool,
+    pub inspect_determinism_flag: bool,
+    pub flag_enforce_tags: bool,
+    pub function_steps: usize,
+    pub template_recursion: usize,
+    pub flag_verbose: bool,
+    pub prime: String,
+}
+
+// What the code of the templates of the circuit does that the constraints
+// may not check: its assignments with <--, the tags its inputs assume and its
+// calls to extern functions
+#[derive(Default)]
+struct Findings {
+    assignments: Vec<JsonValue>,
+    tags: Vec<JsonValue>,
+    extern_calls: BTreeMap<String, Vec<JsonValue>>,
+}
+
+// The file and the line of a place of the code
+fn location(files: &FileLibrary, file_id: FileID, range: &FileLocation) -> String {
+    let name = files.get_file_name(file_id).unwrap_or_default().trim_matches('"');
+    match files.get_line(range.start, file_id) {
+        Some(line) => format!("{}:{}", name, line),
+        None => name.to_string(),
+    }
+}
+
+fn collect_findings(
+    statement: &Statement,
+    template: &str,
+    file_id: FileID,
+    program: &ProgramArchive,
+    config: &AuditConfig,
+    findings: &mut Findings,
+) {
+    let files = &program.file_library;
+    match statement {
+        Statement::Declaration { meta, xtype, name, .. } => {
+            let tags = match xtype {
+                VariableType::Signal(SignalType::Input, tags) | VariableType::Bus(_, SignalType::Input, tags) => tags,
+                _ => return,
+            };
+            if tags.is_empty() {
+                return;
+            }
+            // --enforce_tags checks the tags binary and maxbit, and the
+            // other ones are always assumed
+            let checked: Vec<_> =
+                tags.iter().filter(|tag| config.flag_enforce_tags && (*tag == BINARY || *tag == MAXBIT)).cloned().collect();
+            findings.tags.push(json::object! {
+                "template" => template,
+                "signal" => name.clone(),
+                "tags" => tags.clone(),
+                "checked" => checked,
+                "location" => location(files, file_id, &meta.location),
+            });
+        }
+        Statement::Substitution { meta, var, op: AssignOp::AssignSignal, rhe, .. } => {
+            let mut calls = Vec::new();
+            rhe.collect_calls(&mut calls);
+            let mut externs = Vec::new();
+            for call in calls {
+                if let Expression::Call { meta: call_meta, id, .. } = call {
+                    if program.contains_function(id) && program.get_function_data(id).is_extern() {
+                        findings.extern_calls.entry(id.clone()).or_default().push(json::object! {
+                            "template" => template,
+                            "location" => location(files, file_id, &call_meta.location),
+                        });
+                        externs.push(id.clone());
+                    }
+                }
+            }
+            findings.assignments.push(json::object! {
+                "template" => template,
+                "signal" => var.clone(),
+                "extern_functions" => externs,
+                "location" => location(files, file_id, &meta.location),
+            });
+        }
+        Statement::IfThenElse { if_case, else_case, .. } => {
+            collect_findings(if_case, template, file_id, program, config, findings);
+            if let Some(else_case) = else_case {
+                collect_findings(else_case, template, file_id, program, config, findings);
+            }
+        }
+        Statement::While { stmt, .. } => collect_findings(stmt, template, file_id, program, config, findings),
+        Statement::InitializationBlock { initializations: stmts, .. } | Statement::Block { stmts, .. } => {
+            for statement in stmts {
+                collect_findings(statement, template, file_id, program, config, findings);
+            }
+        }
+        _ => {}
+    }
+}
+
+// The signals of the main component, which are the inputs and the outputs of
+// the proofs, in the order they are declared
+fn interface(vcp: &VCP, program: &ProgramArchive) -> JsonValue {
+    let main = &vcp.templates[vcp.main_id];
+    let body = program.get_template_data(&main.template_name).get_body();
+    let public = program.get_public_inputs_main_component();
+    let signal = |wire: &Wire| {
+        let tags = match body.find_declaration(wire.name()) {
+            Some(Statement::Declaration { xtype: VariableType::Signal(_, tags) | VariableType::Bus(_, _, tags), .. }) => {
+                tags.clone()
+            }
+            _ => Vec::new(),
+        };
+        let kind = match wire.bus_id() {
+            Some(bus_id) => vcp.buses[bus_id].name.clone(),
+            None => "signal".to_string(),
+        };
+        json::object! {
+            "name" => wire.name().clone(),
+            "type" => kind,
+            "dimensions" => wire.lengths().clone(),
+            "size" => wire.size(),
+            "tags" => tags,
+        }
+    };
+    let wires = |xtype: SignalType, is_public: bool| -> Vec<JsonValue> {
+        main.wires
+            .iter()
+            .filter(|wire| wire.xtype() == xtype && (xtype == SignalType::Output || public.contains(wire.name()) == is_public))
+            .map(signal)
+            .collect()
+    };
+    json::object! {
+        "public_outputs" => wires(SignalType::Output, true),
+        "public_inputs" => wires(SignalType::Input, true),
+        "private_inputs" => wires(SignalType::Input, false),
+    }
+}
+
+// The constraints generated by the components of each template, before the
+// simplification, from the template that generates the most
+fn template_constraints(instances: &[InstanceConstraints]) -> Vec<JsonValue> {
+    let mut templates: BTreeMap<&str, (usize, usize, usize)> = BTreeMap::new();
+    for instance in instances {
+        let (count, components, constraints) = templates.entry(&instance.template).or_default();
+        *count += 1;
+        *components += instance.components;
+        *constraints += instance.components * instance.constraints;
+    }
+    let mut templates: Vec<_> = templates.into_iter().collect();
+    templates.sort_by(|(_, (_, _, c0)), (_, (_, _, c1))| c1.cmp(c0));
+    templates
+        .into_iter()
+        .map(|(template, (count, components, constraints))| {
+            json::object! {
+                "template" => template,
+                "instances" => count,
+                "components" => components,
+                "constraints" => constraints,
+            }
+        })
+        .collect()
+}
+
+// The warnings sorted by their place, as their analyses do not report them
+// in the same order in each compilation
+fn sort_warnings(warnings: &mut [JsonValue]) {
+    let key = |warning: &JsonValue| {
+        let location = warning["location"].as_str().unwrap_or_default().to_string();
+        let (file, line) = location.rsplit_once(':').unwrap_or((location.as_str(), "0"));
+        (file.to_string(), line.parse::<usize>().unwrap_or(0), warning["code"].to_string(), warning["message"].to_string())
+    };
+    warnings.sort_by_key(key);
+}
+
+// The report of the circuit of the main component for its audit, which
+// gathers the results of the analyses of the compiler in a json file whose
+// entries are sorted, so that the reports of two versions can be compared
+pub fn audit(program_archive: ProgramArchive, config: AuditConfig) -> Result<(), ()> {
+    let program = program_archive.clone();
+    let build_config = BuildConfig {
+        no_rounds: config.no_rounds,
+        function_steps: config.function_steps,
+        template_recursion: config.template_recursion,
+        flag_json_sub: false,
+        json_substitutions: String::new(),
+        flag_s: config.flag_s,
+        flag_f: config.flag_f,
+        flag_p: false,
+        flag_verbose: config.flag_verbose,
+        inspect_constraints: true,
+        inspect_witness: config.inspect_witness_flag,
+        inspect_determinism: config.inspect_determinism_flag,
+        deny_unconstrained: false,
+        suggest_parallel: false,
+        flag_template_report: false,
+        template_report: String::new(),
+        flag_component_graph: false,
+        component_graph_format: GraphFormat::Json,
+        component_graph: String::new(),
+        flag_old_heuristics: config.flag_old_heuristics,
+        prime: config.prime.clone(),
+    };
+    let (dag, vcp) = build_dag(program_archive, &build_config).map_err(|error| error.report())?;
+    let instances = dag.instance_constraints();
+    let log = if config.flag_f {
+        dag.log()
+    } else {
+        dag.map_to_list(SimplificationFlags {
+            no_rounds: config.no_rounds,
+            flag_s: config.flag_s,
+            parallel_flag: false,
+            port_substitution: false,
+            json_substitutions: String::new(),
+            keep_substitutions: false,
+            flag_old_heuristics: config.flag_old_heuristics,
+            prime: config.prime.clone(),
+        })
+        .log()
+    };
+
+    let mut findings = Findings::default();
+    let templates: BTreeSet<&str> = instances.iter().map(|instance| instance.template.as_str()).collect();
+    for template in templates.iter() {
+        let data = program.get_template_data(template);
+        collect_findings(data.get_body(), template, data.get_file_id(), &program, &config, &mut findings);
+    }
+    let mut names: Vec<_> = program.get_function_names().iter().collect();
+    names.sort();
+    let mut extern_functions = Vec::new();
+    for name in names {
+        let function = program.get_function_data(name);
+        if function.is_extern() {
+            extern_functions.push(json::object! {
+                "name" => name.clone(),
+                "parameters" => function.get_name_of_params().clone(),
+                "calls" => findings.extern_calls.remove(name).unwrap_or_default(),
+                "location" => location(&program.file_library, function.get_file_id(), &function.get_param_location()),
+            });
+        }
+    }
+    let (mut underconstrained, mut warnings): (Vec<_>, Vec<_>) = Report::take_audit()
+        .into_iter()
+        .partition(|warning| lint::selects("unconstrained", warning["code"].as_str().unwrap_or_default()));
+    sort_warnings(&mut underconstrained);
+    sort_warnings(&mut warnings);
+
+    let main = instances.last().map(|instance| {
+        let parameters: Vec<_> = instance.parameters.iter().map(|parameter| parameter.to_str_radix(10)).collect();
+        format!("{}({})", instance.template, parameters.join(", "))
+    });
+    let summary = format!(
+        "{} under-constrained signals, {} assignments with <--, {} tagged inputs and {} extern functions",
+        underconstrained.len(),
+        findings.assignments.len(),
+        findings.tags.len(),
+        extern_functions.len()
+    );
+    let report = json::object! {
+        "circuit" => main,
+        "compiler" => format!("circom {}", VERSION),
+        "prime" => config.prime.clone(),
+        "interface" => interface(&vcp, &program),
+        "constraints" => json::object! {
+            "total" => log.no_linear + log.no_non_linear,
+            "linear" => log.no_linear,
+            "non_linear" => log.no_non_linear,
+            "wires" => log.no_wires,
+            "templates" => template_constraints(&instances),
+        },
+        "underconstrained_signals" => underconstrained,
+        "signal_assignments" => findings.assignments,
+        "tag_assumptions" => findings.tags,
+        "extern_functions" => extern_functions,
+        "warnings" => warnings,
+    };
+    if std::fs::write(&config.output, report.pretty(4)).is_err() {
+        eprintln!("{}", Colour::Red.paint(format!("Could not write the audit report in {}", config.output)));
+        return Result::Err(());
+    }
+    println!("{} {}", Colour::Green.paint("Written successfully:"), config.output);
+    println!("The audit found {}", summary);
+    Result::Ok(())
+}
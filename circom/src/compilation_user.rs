@@ -1,6 +1,8 @@
 use ansi_term::Colour;
+use compiler::circuit_design::circuit::Circuit;
 use compiler::compiler_interface;
-use compiler::compiler_interface::{Config, VCP};
+use compiler::compiler_interface::{Config, OptimizationLevel, Pass, PassConfig, VCP};
+use program_structure::constants::UsefulConstants;
 use program_structure::error_definition::Report;
 use program_structure::error_code::ReportCode;
 use program_structure::file_definition::FileLibrary;
@@ -25,13 +27,56 @@ pub struct CompilerConfig {
     pub vcp: VCP,
     pub no_asm_flag: bool,
     pub prime: String,
+    pub ir_optimization_level: OptimizationLevel,
+    pub disabled_passes: Vec<Pass>,
+    pub pass_timing_flag: bool,
+    pub opt_report_flag: bool,
+    pub opt_report_file: String,
+    pub verify_ir_flag: bool,
+    pub lazy_components_flag: bool,
+    pub ir_cache: Option<String>,
+    pub emit_ir_flag: bool,
+    pub ir_file: String,
+    pub load_ir: Option<String>,
+    pub fuzz_flag: bool,
+    pub bench_flag: bool,
+    pub trace_flag: bool,
+    pub profile_flag: bool,
+    pub r1cs_file: String,
 }
 
-pub fn compile(config: CompilerConfig) -> Result<(), ()> {
+// The circuit of the witness generation code, when it is generated
+fn write_error(file: &str, error: std::io::Error) {
+    eprintln!("{}", Colour::Red.paint(format!("Could not write {}: {}", file, error)));
+}
+
+pub fn compile(config: CompilerConfig) -> Result<Option<Circuit>, ()> {
 
 
-    if config.c_flag || config.wat_flag || config.wasm_flag{
-        let circuit = compiler_interface::run_compiler(
+    if config.fuzz_flag && config.prime == "goldilocks" {
+        eprintln!("{}", Colour::Red.paint("The fuzzing harness is not available for the prime goldilocks"));
+        return Err(());
+    }
+    if config.bench_flag && config.prime == "goldilocks" {
+        eprintln!("{}", Colour::Red.paint("The benchmark of the witness generator is not available for the prime goldilocks"));
+        return Err(());
+    }
+    if config.trace_flag && config.c_flag && config.prime == "goldilocks" {
+        eprintln!("{}", Colour::Red.paint("The witness trace of the C++ code is not available for the prime goldilocks"));
+        return Err(());
+    }
+    if (config.wat_flag || config.wasm_flag) && !UsefulConstants::is_prime_name(&config.prime) {
+        eprintln!(
+            "{}",
+            Colour::Red.paint(format!(
+                "The wasm witness generator is only available for the primes {}, use --c for the prime of the pragma",
+                UsefulConstants::prime_names().join(", ")
+            ))
+        );
+        return Err(());
+    }
+    if config.c_flag || config.wat_flag || config.wasm_flag || config.emit_ir_flag {
+        let mut circuit = compiler_interface::run_compiler(
             config.vcp,
             Config { 
                 debug_output: config.debug_output, 
@@ -40,9 +85,50 @@ pub fn compile(config: CompilerConfig) -> Result<(), ()> {
 
                 constraint_assert_disabled_flag: config.constraint_assert_disabled_flag,
                 no_asm_flag: config.no_asm_flag,
+                passes: PassConfig {
+                    level: config.ir_optimization_level,
+                    disabled_passes: config.disabled_passes,
+                    report_timing: config.pass_timing_flag,
+                    report_effects: config.opt_report_flag,
+                    verify: config.verify_ir_flag,
+                },
+                lazy_components_flag: config.lazy_components_flag,
+                trace_signals_flag: config.trace_flag,
+                profile_templates_flag: config.profile_flag,
+                ir_cache: config.ir_cache,
+                prime: config.prime.clone(),
             },
             VERSION
         )?;
+        for timing in &circuit.pass_timings {
+            println!(
+                "{} {} ({} runs): {:.3} ms",
+                Colour::Green.paint("Pass"),
+                timing.pass.name(),
+                timing.runs,
+                timing.time.as_secs_f64() * 1000.0
+            );
+        }
+        if let Some((runs, time)) = circuit.verify_timing {
+            println!("{} ({} runs): {:.3} ms", Colour::Green.paint("Verification"), runs, time.as_secs_f64() * 1000.0);
+        }
+        if config.opt_report_flag {
+            let file = &config.opt_report_file;
+            compiler_interface::write_opt_report(&circuit, file).map_err(|error| write_error(file, error))?;
+            println!("{} {}", Colour::Green.paint("Written successfully:"), config.opt_report_file);
+        }
+        if let Some(file) = &config.load_ir {
+            if let Err(error) = compiler_interface::load_ir(&mut circuit, file) {
+                eprintln!("{} {}", Colour::Red.paint("Invalid IR file:"), error);
+                return Err(());
+            }
+            println!("{} {}", Colour::Green.paint("Loaded IR:"), file);
+        }
+        if config.emit_ir_flag {
+            let file = &config.ir_file;
+            compiler_interface::write_ir(&circuit, file, VERSION).map_err(|error| write_error(file, error))?;
+            println!("{} {}", Colour::Green.paint("Written successfully:"), config.ir_file);
+        }
     
         if config.c_flag {
             compiler_interface::write_c(&circuit, &config.c_folder, &config.c_run_name, &config.c_file, &config.dat_file)?;
@@ -96,6 +182,23 @@ pub fn compile(config: CompilerConfig) -> Result<(), ()> {
                 }
             }
         }
+        if config.fuzz_flag {
+            let r1cs_name = std::path::Path::new(&config.r1cs_file).file_name().unwrap().to_str().unwrap();
+            let folder = &config.c_folder;
+            compiler_interface::write_fuzz_harness(&circuit, folder, &config.c_run_name, r1cs_name)
+                .map_err(|error| write_error(folder, error))?;
+            println!(
+                "{} {}/fuzz.cpp and {}/fuzz_corpus",
+                Colour::Green.paint("Written successfully:"),
+                &config.c_folder,
+                &config.c_folder
+            );
+        }
+        if config.bench_flag {
+            let folder = &config.c_folder;
+            compiler_interface::write_bench_harness(&circuit, folder, &config.c_run_name).map_err(|error| write_error(folder, error))?;
+            println!("{} {}/bench.cpp", Colour::Green.paint("Written successfully:"), &config.c_folder);
+        }
         match (config.wat_flag, config.wasm_flag) {
             (true, true) => {
                 compiler_interface::write_wasm(&circuit, &config.js_folder, &config.wasm_name, &config.wat_file)?;
@@ -131,10 +234,11 @@ pub fn compile(config: CompilerConfig) -> Result<(), ()> {
             }
             (false, false) => {}
         }
+        return Ok(Some(circuit));
     }
     
 
-    Ok(())
+    Ok(None)
 }
 
 
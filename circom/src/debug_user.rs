@@ -0,0 +1,461 @@
+use ansi_term::Colour;
+use circom_algebra::num_bigint::BigInt;
+use program_structure::error_definition::Report;
+use program_structure::file_definition::{FileID, FileLibrary};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, IsTerminal, Lines, StdinLock, Write};
+use std::path::{Path, PathBuf};
+use type_analysis::check_types::check_types;
+use witness_calculation::Observer;
+use crate::explain_user::constraint_to_string;
+use crate::repl_user::constraint_holds;
+use crate::test_user::{instantiate, parse_program, witness_failure, Instance};
+use crate::VERSION;
+
+const HELP: &str = "\
+The witness calculation stops before its first statement, at the breakpoints
+and after the stores that change a watched signal. The commands are:
+  step, s              runs until the next line, entering components and functions
+  next, n              runs until the next line of this template or function,
+                       running the components and functions that it calls
+  finish, f            runs until this template or function ends
+  continue, c          runs until a breakpoint or a watched signal changes
+  break LINE, b        stops at the line, of any template or function
+  break T:LINE         stops at the line of the template or function T
+  break T              stops when a component of the template T, or a call of
+                       the function T, starts running
+  break constraint     stops after the store that makes a constraint fail
+  delete N             removes the breakpoint N
+  watch SIGNAL         stops when the signal, or a signal of the array or bus,
+                       changes
+  print SIGNAL, p      prints the value of the signal, or the ones of the array
+                       or bus
+  where, w             prints the templates and functions that are running
+  breakpoints          lists the breakpoints and the watched signals
+  help, h              prints this help
+  quit, q              ends the session
+
+The signals are named as in the sym file, as main.c[1].out, or from the
+component that is running, as c[1].out.";
+
+pub struct DebugConfig {
+    pub file: PathBuf,
+    pub inputs: PathBuf,
+    pub link_libraries: Vec<PathBuf>,
+    pub prime: String,
+}
+
+// How the calculation runs until the next stop, from the frame of the last one
+#[derive(Copy, Clone)]
+enum Resume {
+    Step,
+    Next,
+    Finish,
+    Continue,
+}
+
+enum Breakpoint {
+    Line(Option<String>, usize),
+    Definition(String),
+    Constraint,
+}
+
+impl std::fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Breakpoint::Line(None, line) => write!(f, "line {}", line),
+            Breakpoint::Line(Some(definition), line) => write!(f, "{} line {}", definition, line),
+            Breakpoint::Definition(definition) => write!(f, "{}", definition),
+            Breakpoint::Constraint => write!(f, "the first constraint that fails"),
+        }
+    }
+}
+
+// A template or a function that is running, with the line of its last
+// instruction
+struct Running {
+    id: usize,
+    definition: String,
+    component: String,
+    line: usize,
+}
+
+struct Debugger<'a> {
+    instance: &'a Instance,
+    file_library: FileLibrary,
+    // the files of the templates and the functions
+    files: HashMap<String, FileID>,
+    // the names of the signals with their positions in the memory
+    names: Vec<(String, usize)>,
+    // the constraints of each position in the memory
+    constraints: HashMap<usize, Vec<usize>>,
+    failed: HashSet<usize>,
+    stack: Vec<Running>,
+    entered: usize,
+    breakpoints: Vec<Option<Breakpoint>>,
+    watched: Vec<(String, Vec<usize>)>,
+    values: HashMap<usize, Option<BigInt>>,
+    // why the calculation stops at the next instruction
+    reasons: Vec<String>,
+    resume: Resume,
+    // the frame of the last stop, by its id and its depth
+    stop: (usize, usize),
+    lines: Lines<StdinLock<'static>>,
+    interactive: bool,
+    quit: bool,
+}
+
+// Computes the witness of the main component of the file for the inputs of
+// the json file, stopping where the commands of the standard input ask for
+pub fn run(config: DebugConfig) -> Result<(), ()> {
+    let source = std::fs::read_to_string(&config.file).map_err(|e| {
+        eprintln!("{}", Colour::Red.paint(format!("Could not read {}: {}", config.file.display(), e)))
+    })?;
+    let mut program_archive = parse_program(&config.file, source, &config.link_libraries, &config.prime)?;
+    if let Err(reports) = check_types(&mut program_archive) {
+        Report::print_reports(&reports, &program_archive.file_library);
+        return Result::Err(());
+    }
+    let file_library = program_archive.file_library.clone();
+    let mut files = HashMap::new();
+    for (name, data) in program_archive.get_templates() {
+        files.insert(name.clone(), data.get_file_id());
+    }
+    for (name, data) in program_archive.get_functions() {
+        files.insert(name.clone(), data.get_file_id());
+    }
+    let instance = instantiate(program_archive, &config.prime)
+        .map_err(|message| eprintln!("{}", Colour::Red.paint(format!("The circuit could not be instantiated: {}", message))))?;
+    let json = std::fs::read_to_string(&config.inputs).map_err(|e| {
+        eprintln!("{}", Colour::Red.paint(format!("Could not read {}: {}", config.inputs.display(), e)))
+    })?;
+    let inputs = witness_calculation::read_inputs(&instance.circuit, &json).map_err(|message| {
+        eprintln!("{}", Colour::Red.paint(format!("Invalid inputs {}: {}", config.inputs.display(), message)))
+    })?;
+
+    let position = |signal: &usize| *instance.dag.memory_positions.get(signal).unwrap_or(signal);
+    let mut names: Vec<_> = instance.names.iter().map(|(signal, name)| (name.clone(), position(signal))).collect();
+    names.sort_by_key(|(_, position)| *position);
+    let mut constraints: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (index, constraint) in instance.constraints.iter().enumerate() {
+        let signals: HashSet<_> = constraint.a().keys().chain(constraint.b().keys()).chain(constraint.c().keys()).map(position).collect();
+        for signal in signals {
+            constraints.entry(signal).or_default().push(index);
+        }
+    }
+    let interactive = std::io::stdin().is_terminal();
+    if interactive {
+        println!("circom {} debugger, enter help for help", VERSION);
+    }
+    let mut debugger = Debugger {
+        instance: &instance,
+        file_library,
+        files,
+        names,
+        constraints,
+        failed: HashSet::new(),
+        stack: Vec::new(),
+        entered: 0,
+        breakpoints: Vec::new(),
+        watched: Vec::new(),
+        values: HashMap::new(),
+        reasons: vec!["the witness calculation starts".to_string()],
+        resume: Resume::Continue,
+        stop: (0, 0),
+        lines: std::io::stdin().lock().lines(),
+        interactive,
+        quit: false,
+    };
+    let calculation = witness_calculation::calculate_observed_witness(&instance.circuit, &inputs, &mut debugger);
+    if debugger.quit {
+        return Result::Ok(());
+    }
+    for reason in std::mem::take(&mut debugger.reasons) {
+        println!("{}", Colour::Yellow.paint(reason));
+    }
+    match witness_failure(&calculation) {
+        Some(failure) => println!("{}", Colour::Red.paint(failure)),
+        None => println!("{}", Colour::Green.paint("The witness calculation ends")),
+    }
+    debugger.stack.clear();
+    while debugger.command(&calculation.signals).is_some() {
+        println!("the witness calculation has ended");
+    }
+    Result::Ok(())
+}
+
+impl Debugger<'_> {
+    fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    fn top_id(&self) -> usize {
+        self.stack.last().map_or(0, |running| running.id)
+    }
+
+    // Prints the reasons of the stop and where it is, and runs the commands
+    // until one of them resumes the calculation. It stops the calculation
+    // when the session is quit
+    fn stop(&mut self, signals: &[Option<BigInt>]) -> bool {
+        for reason in std::mem::take(&mut self.reasons) {
+            println!("{}", Colour::Yellow.paint(reason));
+        }
+        if let Some(running) = self.stack.last() {
+            println!("{} line {}, component {}", running.definition, running.line, running.component);
+            if let Some(code) = self.code(&running.definition, running.line) {
+                println!("{:>5} | {}", running.line, code);
+            }
+        }
+        match self.command(signals) {
+            Some(resume) => {
+                self.resume = resume;
+                self.stop = (self.top_id(), self.depth());
+                true
+            }
+            None => {
+                self.quit = true;
+                false
+            }
+        }
+    }
+
+    // The code of the line of the file of the template or function
+    fn code(&self, definition: &str, line: usize) -> Option<&str> {
+        let file_id = *self.files.get(definition)?;
+        let location = self.file_library.get_line_location(line, file_id)?;
+        self.file_library.get_source(file_id)?.get(location)
+    }
+
+    // Runs the commands until one of them resumes the calculation, or none
+    // if the session is quit
+    fn command(&mut self, signals: &[Option<BigInt>]) -> Option<Resume> {
+        loop {
+            if self.interactive {
+                print!("(debug) ");
+                let _ = std::io::stdout().flush();
+            }
+            let line = match self.lines.next() {
+                Some(Ok(line)) => line,
+                _ => return None,
+            };
+            let mut words = line.split_whitespace();
+            let command = words.next().unwrap_or("");
+            let argument = words.collect::<Vec<_>>().join(" ");
+            match command {
+                "" => {}
+                "step" | "s" => return Some(Resume::Step),
+                "next" | "n" => return Some(Resume::Next),
+                "finish" | "f" => return Some(Resume::Finish),
+                "continue" | "c" => return Some(Resume::Continue),
+                "break" | "b" => self.add_breakpoint(&argument),
+                "delete" | "d" => match argument.parse::<usize>().ok().and_then(|n| self.breakpoints.get_mut(n.wrapping_sub(1))) {
+                    Some(breakpoint) if breakpoint.is_some() => *breakpoint = None,
+                    _ => println!("there is no breakpoint {}", argument),
+                },
+                "watch" => {
+                    let positions = self.positions(&argument);
+                    if positions.is_empty() {
+                        println!("there is no signal {}", argument);
+                    } else {
+                        for position in &positions {
+                            self.values.insert(*position, signals.get(*position).cloned().flatten());
+                        }
+                        println!("watching {} signals of {}", positions.len(), argument);
+                        self.watched.push((argument, positions));
+                    }
+                }
+                "print" | "p" => {
+                    let positions = self.positions(&argument);
+                    if positions.is_empty() {
+                        println!("there is no signal {}", argument);
+                    }
+                    for position in positions {
+                        match signals.get(position).cloned().flatten() {
+                            Some(value) => println!("{} = {}", self.name(position), value),
+                            None => println!("{} is not assigned", self.name(position)),
+                        }
+                    }
+                }
+                "where" | "w" => {
+                    for (depth, running) in self.stack.iter().rev().enumerate() {
+                        println!("#{} {} line {}, component {}", depth, running.definition, running.line, running.component);
+                    }
+                }
+                "breakpoints" => {
+                    for (number, breakpoint) in self.breakpoints.iter().enumerate() {
+                        if let Some(breakpoint) = breakpoint {
+                            println!("breakpoint {} at {}", number + 1, breakpoint);
+                        }
+                    }
+                    for (name, _) in &self.watched {
+                        println!("watching {}", name);
+                    }
+                }
+                "help" | "h" => println!("{}", HELP),
+                "quit" | "q" => return None,
+                _ => println!("unknown command {}, enter help for the commands", command),
+            }
+        }
+    }
+
+    fn add_breakpoint(&mut self, argument: &str) {
+        let circuit = &self.instance.circuit;
+        let is_definition = |name: &str| {
+            circuit.templates.iter().any(|template| template.name == name)
+                || circuit.functions.iter().any(|function| function.name == name)
+        };
+        let breakpoint = match argument.split_once(':') {
+            _ if argument == "constraint" => Breakpoint::Constraint,
+            Some((definition, line)) if is_definition(definition) && line.parse::<usize>().is_ok() => {
+                Breakpoint::Line(Some(definition.to_string()), line.parse().unwrap())
+            }
+            None if argument.parse::<usize>().is_ok() => Breakpoint::Line(None, argument.parse().unwrap()),
+            None if is_definition(argument) => Breakpoint::Definition(argument.to_string()),
+            _ => {
+                println!("there is no line, template or function {} in the code of the circuit", argument);
+                return;
+            }
+        };
+        println!("breakpoint {} at {}", self.breakpoints.len() + 1, breakpoint);
+        self.breakpoints.push(Some(breakpoint));
+    }
+
+    fn breakpoint_reasons(&mut self, matches: impl Fn(&Breakpoint) -> bool) {
+        for (number, breakpoint) in self.breakpoints.iter().enumerate() {
+            if let Some(breakpoint) = breakpoint.as_ref().filter(|breakpoint| matches(breakpoint)) {
+                self.reasons.push(format!("breakpoint {} at {}", number + 1, breakpoint));
+            }
+        }
+    }
+
+    // The positions of the signals with the name, or of the arrays and buses
+    // with it, from the component that is running or from the main one
+    fn positions(&self, name: &str) -> Vec<usize> {
+        let mut candidates = Vec::new();
+        if let Some(running) = self.stack.last() {
+            candidates.push(format!("{}.{}", running.component, name));
+        }
+        candidates.push(name.to_string());
+        candidates.push(format!("main.{}", name));
+        for candidate in candidates {
+            let mut positions: Vec<_> = self
+                .names
+                .iter()
+                .filter(|(signal_name, _)| {
+                    *signal_name == candidate
+                        || signal_name.strip_prefix(&candidate).is_some_and(|rest| rest.starts_with('[') || rest.starts_with('.'))
+                })
+                .map(|(_, position)| *position)
+                .collect();
+            positions.dedup();
+            if !positions.is_empty() {
+                return positions;
+            }
+        }
+        Vec::new()
+    }
+
+    fn name(&self, position: usize) -> String {
+        self.names
+            .iter()
+            .find(|(_, signal_position)| *signal_position == position)
+            .map_or_else(|| format!("signal {}", position), |(name, _)| name.clone())
+    }
+
+    // The constraint with the statement that generates it
+    fn describe_constraint(&self, index: usize) -> String {
+        let instance = self.instance;
+        let constraint = constraint_to_string(&instance.field, &instance.names, &instance.constraints[index]);
+        match &instance.origins[index] {
+            Some((file_id, location)) => {
+                let file = self.file_library.get_file_name(*file_id).unwrap_or("").trim_matches('"');
+                let file = Path::new(file).file_name().map_or(file.to_string(), |file| file.to_string_lossy().to_string());
+                let line = self.file_library.get_line(location.start, *file_id).unwrap_or(0);
+                format!("the constraint {} of {}:{} does not hold", constraint, file, line)
+            }
+            None => format!("the constraint {} does not hold", constraint),
+        }
+    }
+}
+
+impl Observer for Debugger<'_> {
+    fn instruction(&mut self, line: usize, signals: &[Option<BigInt>]) -> bool {
+        // the instructions without a line are the ones that the compiler adds
+        if line == 0 {
+            return true;
+        }
+        let depth = self.depth();
+        let (stop_id, stop_depth) = self.stop;
+        let running = match self.stack.last_mut() {
+            Some(running) => running,
+            None => return true,
+        };
+        let new_line = running.line != line;
+        running.line = line;
+        let new_frame = running.id != stop_id;
+        let definition = running.definition.clone();
+        if new_line {
+            self.breakpoint_reasons(|breakpoint| match breakpoint {
+                Breakpoint::Line(None, breakpoint_line) => *breakpoint_line == line,
+                Breakpoint::Line(Some(name), breakpoint_line) => *name == definition && *breakpoint_line == line,
+                _ => false,
+            });
+        }
+        let stops = match self.resume {
+            Resume::Step => new_line || new_frame,
+            Resume::Next => depth < stop_depth || (depth == stop_depth && (new_line || new_frame)),
+            Resume::Finish => depth < stop_depth,
+            Resume::Continue => false,
+        };
+        if stops || !self.reasons.is_empty() {
+            self.stop(signals)
+        } else {
+            true
+        }
+    }
+
+    fn enter(&mut self, definition: &str, component: &str, _is_template: bool) {
+        self.entered += 1;
+        self.stack.push(Running {
+            id: self.entered,
+            definition: definition.to_string(),
+            component: component.to_string(),
+            line: 0,
+        });
+        self.breakpoint_reasons(|breakpoint| matches!(breakpoint, Breakpoint::Definition(name) if name == definition));
+    }
+
+    fn leave(&mut self) {
+        self.stack.pop();
+    }
+
+    fn stored(&mut self, position: usize, signals: &[Option<BigInt>]) {
+        let value = signals.get(position).cloned().flatten();
+        if let Some(previous) = self.values.get(&position) {
+            if *previous != value {
+                let previous = previous.as_ref().map_or("not assigned".to_string(), |previous| previous.to_string());
+                let current = value.as_ref().map_or("not assigned".to_string(), |value| value.to_string());
+                self.reasons.push(format!("{} changes from {} to {}", self.name(position), previous, current));
+                self.values.insert(position, value);
+            }
+        }
+        if !self.breakpoints.iter().any(|breakpoint| matches!(breakpoint, Some(Breakpoint::Constraint))) {
+            return;
+        }
+        let instance = self.instance;
+        let memory_value = |signal: &usize| {
+            let position = *instance.dag.memory_positions.get(signal).unwrap_or(signal);
+            signals.get(position).cloned().flatten()
+        };
+        for index in self.constraints.get(&position).cloned().unwrap_or_default() {
+            if self.failed.contains(&index) {
+                continue;
+            }
+            if constraint_holds(&instance.field, &instance.constraints[index], memory_value) == Some(false) {
+                self.failed.insert(index);
+                let reason = self.describe_constraint(index);
+                self.reasons.push(reason);
+            }
+        }
+    }
+}
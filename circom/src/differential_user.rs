@@ -0,0 +1,238 @@
+use ansi_term::Colour;
+use circom_algebra::num_bigint::{BigInt, Sign};
+use compiler::circuit_design::circuit::Circuit;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use crate::test_user::witness_failure;
+
+// The differences listed before the rest are only counted
+const MAX_DIFFERENCES: usize = 20;
+
+pub struct DifferentialConfig {
+    pub inputs: PathBuf,
+    pub c_folder: String,
+    pub c_run_name: String,
+    pub js_folder: String,
+    pub wasm_file: String,
+    pub sym_file: String,
+    pub c_witness: String,
+    pub wasm_witness: String,
+}
+
+// The witness that a generator computes, by the positions of the wtns file,
+// or the reason why it fails
+struct Run {
+    generator: &'static str,
+    witness: Result<Vec<BigInt>, String>,
+}
+
+// Runs the C and the wasm witness generators with the inputs and compares
+// their witnesses, signal by signal, with the one of the witness calculator
+// of the compiler, which runs the same code. A generator that cannot be
+// built or run in this machine, as the C one without gmp or nasm, is not
+// compared, and any other difference fails
+pub fn compare_backends(circuit: &Circuit, config: &DifferentialConfig) -> Result<(), ()> {
+    let json = std::fs::read_to_string(&config.inputs).map_err(|e| {
+        eprintln!("{}", Colour::Red.paint(format!("Could not read {}: {}", config.inputs.display(), e)))
+    })?;
+    let inputs = witness_calculation::read_inputs(circuit, &json).map_err(|message| {
+        eprintln!("{}", Colour::Red.paint(format!("Invalid inputs {}: {}", config.inputs.display(), message)))
+    })?;
+    let calculation = witness_calculation::calculate_witness(circuit, &inputs);
+    let compiler = match witness_failure(&calculation) {
+        Some(failure) => Err(failure),
+        None => Ok(circuit
+            .c_producer
+            .get_witness_to_signal_list()
+            .iter()
+            .map(|position| calculation.signals[*position].clone().unwrap_or_default())
+            .collect()),
+    };
+    let mut runs = vec![Run { generator: "compiler", witness: compiler }];
+    for (generator, witness) in [("c", run_c(config)), ("wasm", run_wasm(config))] {
+        if let Some(witness) = witness {
+            runs.push(Run { generator, witness });
+        }
+    }
+    if runs.len() == 1 {
+        eprintln!("{}", Colour::Red.paint("Neither the C nor the wasm witness generator could be run to compare them"));
+        return Result::Err(());
+    }
+    let generators: Vec<_> = runs.iter().map(|run| run.generator).collect();
+    let generators = generators.join(", ");
+
+    if runs.iter().all(|run| run.witness.is_err()) {
+        println!("{} {}", Colour::Green.paint("The witness generation fails in all the generators:"), generators);
+        for run in &runs {
+            println!("  {}: {}", run.generator, run.witness.as_ref().unwrap_err());
+        }
+        return Result::Ok(());
+    }
+    if runs.iter().any(|run| run.witness.is_err()) {
+        eprintln!("{}", Colour::Red.paint("The witness generation fails only in some of the generators:"));
+        for run in &runs {
+            match &run.witness {
+                Ok(witness) => eprintln!("  {}: computes {} signals", run.generator, witness.len()),
+                Err(failure) => eprintln!("  {}: {}", run.generator, failure),
+            }
+        }
+        return Result::Err(());
+    }
+
+    let witnesses: Vec<_> = runs.iter().map(|run| run.witness.as_ref().unwrap()).collect();
+    let length = witnesses[0].len();
+    if witnesses.iter().any(|witness| witness.len() != length) {
+        eprintln!("{}", Colour::Red.paint("The witnesses have different numbers of signals:"));
+        for (run, witness) in runs.iter().zip(&witnesses) {
+            eprintln!("  {}: {}", run.generator, witness.len());
+        }
+        return Result::Err(());
+    }
+    let names = read_names(&config.sym_file);
+    let differences: Vec<_> = (0..length)
+        .filter(|index| witnesses.iter().any(|witness| witness[*index] != witnesses[0][*index]))
+        .collect();
+    if differences.is_empty() {
+        println!(
+            "{} {} ({} signals)",
+            Colour::Green.paint("The witnesses are equal:"),
+            generators,
+            length
+        );
+        return Result::Ok(());
+    }
+    eprintln!(
+        "{}",
+        Colour::Red.paint(format!("The witnesses differ in {} of {} signals:", differences.len(), length))
+    );
+    for index in differences.iter().take(MAX_DIFFERENCES) {
+        let name = names.get(index).cloned().unwrap_or_else(|| format!("signal {}", index));
+        let values: Vec<_> =
+            runs.iter().zip(&witnesses).map(|(run, witness)| format!("{} {}", run.generator, witness[*index])).collect();
+        eprintln!("  {}: {}", name, values.join(", "));
+    }
+    if differences.len() > MAX_DIFFERENCES {
+        eprintln!("  and {} more", differences.len() - MAX_DIFFERENCES);
+    }
+    Result::Err(())
+}
+
+// The C witness generator is built with its Makefile, and it is not
+// compared if it cannot be built
+fn run_c(config: &DifferentialConfig) -> Option<Result<Vec<BigInt>, String>> {
+    let build = Command::new("make").arg("-C").arg(&config.c_folder).output();
+    match build {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => {
+            println!("{} {}", Colour::Yellow.paint("The C witness generator is not compared, it could not be built:"), error_of(&output));
+            return None;
+        }
+        Err(e) => {
+            println!("{} {}", Colour::Yellow.paint("The C witness generator is not compared, make could not be run:"), e);
+            return None;
+        }
+    }
+    let program = Path::new(&config.c_folder).join(&config.c_run_name);
+    let run = Command::new(&program).arg(&config.inputs).arg(&config.c_witness).output();
+    Some(run_result(run, &config.c_witness))
+}
+
+// The wasm witness generator is run with node, and it is not compared if
+// node is not installed
+fn run_wasm(config: &DifferentialConfig) -> Option<Result<Vec<BigInt>, String>> {
+    let script = Path::new(&config.js_folder).join("generate_witness.js");
+    let run = Command::new("node")
+        .arg(&script)
+        .arg(&config.wasm_file)
+        .arg(&config.inputs)
+        .arg(&config.wasm_witness)
+        .output();
+    if let Err(e) = &run {
+        println!("{} {}", Colour::Yellow.paint("The wasm witness generator is not compared, node could not be run:"), e);
+        return None;
+    }
+    Some(run_result(run, &config.wasm_witness))
+}
+
+fn run_result(run: std::io::Result<Output>, witness: &str) -> Result<Vec<BigInt>, String> {
+    let output = run.map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!("the witness generation fails: {}", error_of(&output)));
+    }
+    read_witness(Path::new(witness))
+}
+
+// The lines of the errors of a command, as the ones of the compiler or of an
+// assertion of a generator, without the ones of make and node around them
+fn error_of(output: &Output) -> String {
+    let text = String::from_utf8_lossy(&output.stderr).to_string() + &String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<_> = text.lines().map(|line| line.trim()).filter(|line| !line.is_empty()).collect();
+    let errors: Vec<_> = lines
+        .iter()
+        .filter(|line| line.contains("rror") && !line.contains("throw") && !line.starts_with("make"))
+        .cloned()
+        .collect();
+    if errors.is_empty() {
+        lines.last().unwrap_or(&"").to_string()
+    } else {
+        errors.join(" ")
+    }
+}
+
+// The values of a wtns file: a header section with the size of the field
+// elements, the prime and the number of values, and a section with them
+fn read_witness(file: &Path) -> Result<Vec<BigInt>, String> {
+    let bytes = std::fs::read(file).map_err(|e| format!("could not read {}: {}", file.display(), e))?;
+    let invalid = || format!("{} is not a valid wtns file", file.display());
+    let number_at = |position: usize, size: usize| -> Option<usize> {
+        bytes.get(position..position + size).map(|b| b.iter().rev().fold(0, |number, byte| (number << 8) | *byte as usize))
+    };
+    let u32_at = |position: usize| number_at(position, 4);
+    let u64_at = |position: usize| number_at(position, 8);
+    if bytes.get(0..4) != Some(b"wtns".as_slice()) {
+        return Err(invalid());
+    }
+    let sections = u32_at(8).ok_or_else(invalid)?;
+    let mut position = 12;
+    let mut header = None;
+    let mut values = None;
+    for _ in 0..sections {
+        let kind = u32_at(position).ok_or_else(invalid)?;
+        let size = u64_at(position + 4).ok_or_else(invalid)?;
+        let start = position + 12;
+        match kind {
+            1 => {
+                let n8 = u32_at(start).ok_or_else(invalid)?;
+                let length = u32_at(start + 4 + n8).ok_or_else(invalid)?;
+                header = Some((n8, length));
+            }
+            2 => values = Some(start),
+            _ => {}
+        }
+        position = start + size;
+    }
+    let ((n8, length), start) = header.zip(values).ok_or_else(invalid)?;
+    let mut witness = Vec::with_capacity(length);
+    for index in 0..length {
+        let value = bytes.get(start + index * n8..start + (index + 1) * n8).ok_or_else(invalid)?;
+        witness.push(BigInt::from_bytes_le(Sign::Plus, value));
+    }
+    Ok(witness)
+}
+
+// The names of the signals by their positions in the witness, the first one
+// of the sym file for the signals that are equal
+fn read_names(sym_file: &str) -> HashMap<usize, String> {
+    let mut names = HashMap::new();
+    let content = std::fs::read_to_string(sym_file).unwrap_or_default();
+    for line in content.lines() {
+        let fields: Vec<_> = line.splitn(4, ',').collect();
+        if let [_, witness, _, name] = fields.as_slice() {
+            if let Ok(witness) = witness.parse::<usize>() {
+                names.entry(witness).or_insert_with(|| name.to_string());
+            }
+        }
+    }
+    names
+}
@@ -0,0 +1,429 @@
+use ansi_term::Colour;
+use constraint_generation::{build_dag, BuildConfig};
+use constraint_writers::graph_writer::GraphFormat;
+use dag::InstanceConstraints;
+use program_structure::ast::{Definition, Expression, SignalType, Statement, VariableType, AST};
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::file_definition::FileLibrary;
+use program_structure::program_archive::ProgramArchive;
+use std::path::{Path, PathBuf};
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum DocsFormat {
+    Markdown,
+    Html,
+}
+
+pub struct DocsConfig {
+    pub input: PathBuf,
+    pub link_libraries: Vec<PathBuf>,
+    pub features: Vec<String>,
+    pub format: DocsFormat,
+    pub output: String,
+    pub no_rounds: usize,
+    pub function_steps: usize,
+    pub template_recursion: usize,
+    pub flag_verbose: bool,
+    pub prime: String,
+}
+
+struct SourceFile {
+    path: PathBuf,
+    source: String,
+    ast: AST,
+}
+
+// A signal of the interface of a template, or a field of a bus
+struct SignalDoc {
+    kind: String,
+    name: String,
+    dimensions: String,
+    tags: Vec<String>,
+    description: String,
+}
+
+struct DefinitionDoc {
+    kind: &'static str,
+    name: String,
+    signature: String,
+    description: Vec<String>,
+    parameters: Vec<(String, String)>,
+    inputs: Vec<SignalDoc>,
+    outputs: Vec<SignalDoc>,
+    fields: Vec<SignalDoc>,
+}
+
+// The file and the files it includes, directly or not, in the order they are
+// found. The program does not need a main component to be documented
+fn read_files(config: &DocsConfig) -> Result<Vec<SourceFile>, ()> {
+    let mut files: Vec<SourceFile> = Vec::new();
+    let mut file_library = FileLibrary::new();
+    let mut reports: ReportCollection = Vec::new();
+    let mut pending = vec![config.input.clone()];
+    while let Some(path) = pending.pop() {
+        let path = std::fs::canonicalize(&path).unwrap_or(path);
+        if files.iter().any(|file| file.path == path) {
+            continue;
+        }
+        let source = std::fs::read_to_string(&path)
+            .map_err(|e| eprintln!("{}", Colour::Red.paint(format!("Could not read {}: {}", path.display(), e))))?;
+        let file_id = file_library.add_file(path.display().to_string(), source.clone());
+        match parser::parse_definitions(&source, file_id, &config.prime, &config.features) {
+            Ok(ast) => {
+                for include in ast.includes.iter().rev() {
+                    match parser::resolve_include(&path, &include.path, &config.link_libraries) {
+                        Some(included) => pending.push(included),
+                        None => {
+                            eprintln!(
+                                "{}",
+                                Colour::Red.paint(format!("Could not find {} included in {}", include.path, path.display()))
+                            );
+                            return Result::Err(());
+                        }
+                    }
+                }
+                files.push(SourceFile { path, source, ast });
+            }
+            Err(mut errors) => reports.append(&mut errors),
+        }
+    }
+    if reports.is_empty() {
+        Result::Ok(files)
+    } else {
+        Report::print_reports(&reports, &file_library);
+        Result::Err(())
+    }
+}
+
+// The lines of the /// comments right before the line of the offset, which
+// may be separated from it by attributes as #[cfg(feature = "debug")]
+fn doc_comment(source: &str, offset: usize) -> Vec<String> {
+    let line_start = source[..offset].rfind('\n').map_or(0, |position| position + 1);
+    let mut lines = Vec::new();
+    for line in source[..line_start].lines().rev() {
+        let line = line.trim();
+        if let Some(comment) = line.strip_prefix("///") {
+            lines.push(comment.strip_prefix(' ').unwrap_or(comment).trim_end().to_string());
+        } else if !line.starts_with("#[") {
+            break;
+        }
+    }
+    lines.reverse();
+    lines
+}
+
+// The description of each parameter is given in a line @param name description
+fn split_parameters(lines: Vec<String>) -> (Vec<String>, Vec<(String, String)>) {
+    let mut description = Vec::new();
+    let mut parameters = Vec::new();
+    for line in lines {
+        match line.trim().strip_prefix("@param ") {
+            Some(parameter) => {
+                let (name, text) = parameter.trim().split_once(' ').unwrap_or((parameter.trim(), ""));
+                parameters.push((name.to_string(), text.trim().to_string()));
+            }
+            None => description.push(line),
+        }
+    }
+    (description, parameters)
+}
+
+fn code_of<'a>(source: &'a str, expression: &Expression) -> &'a str {
+    let meta = expression.get_meta();
+    source.get(meta.start..meta.end).unwrap_or("?")
+}
+
+fn declarations<'a>(statement: &'a Statement, found: &mut Vec<&'a Statement>) {
+    match statement {
+        Statement::Declaration { .. } => found.push(statement),
+        Statement::IfThenElse { if_case, else_case, .. } => {
+            declarations(if_case, found);
+            if let Some(else_case) = else_case {
+                declarations(else_case, found);
+            }
+        }
+        Statement::While { stmt, .. } => declarations(stmt, found),
+        Statement::InitializationBlock { initializations: stmts, .. } | Statement::Block { stmts, .. } => {
+            for statement in stmts {
+                declarations(statement, found);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn signal_doc(source: &str, declaration: &Statement) -> Option<(SignalType, SignalDoc)> {
+    let (meta, xtype, name, dimensions) = match declaration {
+        Statement::Declaration { meta, xtype, name, dimensions, .. } => (meta, xtype, name, dimensions),
+        _ => return None,
+    };
+    let (signal_type, kind, tags) = match xtype {
+        VariableType::Signal(signal_type, tags) => (signal_type, "signal".to_string(), tags),
+        VariableType::Bus(bus, signal_type, tags) => (signal_type, bus.clone(), tags),
+        _ => return None,
+    };
+    let dimensions = dimensions.iter().map(|dimension| format!("[{}]", code_of(source, dimension))).collect();
+    let description = doc_comment(source, meta.start).join(" ");
+    Some((*signal_type, SignalDoc { kind, name: name.clone(), dimensions, tags: tags.clone(), description }))
+}
+
+fn definition_doc(source: &str, definition: &Definition) -> Option<DefinitionDoc> {
+    let (kind, meta, name, args, body) = match definition {
+        Definition::Template { meta, name, args, body, .. } => ("template", meta, name, args, body),
+        Definition::Bus { meta, name, args, body, .. } => ("bus", meta, name, args, body),
+        Definition::Function { meta, name, args, body, .. } => ("function", meta, name, args, body),
+        _ => return None,
+    };
+    // the last parameters of a template can have default values
+    let mut arguments = args.clone();
+    if let Definition::Template { defaults, .. } = definition {
+        let first = arguments.len() - defaults.len();
+        for (argument, default) in arguments[first..].iter_mut().zip(defaults) {
+            *argument = format!("{} = {}", argument, code_of(source, default));
+        }
+    }
+    let prefix = match definition {
+        Definition::Template { parallel: true, .. } => "template parallel",
+        Definition::Template { is_custom_gate: true, .. } => "template custom",
+        Definition::Function { is_extern: true, .. } => "extern function",
+        _ => kind,
+    };
+    let (description, documented) = split_parameters(doc_comment(source, meta.start));
+    let parameters = args
+        .iter()
+        .map(|arg| {
+            let text = documented.iter().find(|(name, _)| name == arg).map(|(_, text)| text.clone());
+            (arg.clone(), text.unwrap_or_default())
+        })
+        .collect();
+    let mut doc = DefinitionDoc {
+        kind,
+        name: name.clone(),
+        signature: format!("{} {}({})", prefix, name, arguments.join(", ")),
+        description,
+        parameters,
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        fields: Vec::new(),
+    };
+    let mut found = Vec::new();
+    declarations(body, &mut found);
+    for (signal_type, signal) in found.into_iter().filter_map(|declaration| signal_doc(source, declaration)) {
+        match (kind, signal_type) {
+            ("bus", _) => doc.fields.push(signal),
+            (_, SignalType::Input) => doc.inputs.push(signal),
+            (_, SignalType::Output) => doc.outputs.push(signal),
+            _ => {}
+        }
+    }
+    Some(doc)
+}
+
+// The instances of the templates in the circuit of the main component, with
+// the constraints they generate before the simplification
+fn instances_of(program_archive: ProgramArchive, config: &DocsConfig) -> Result<Vec<InstanceConstraints>, ()> {
+    let build_config = BuildConfig {
+        no_rounds: config.no_rounds,
+        function_steps: config.function_steps,
+        template_recursion: config.template_recursion,
+        flag_json_sub: false,
+        json_substitutions: String::new(),
+        flag_s: false,
+        flag_f: true,
+        flag_p: false,
+        flag_verbose: config.flag_verbose,
+        inspect_constraints: false,
+        inspect_witness: false,
+        inspect_determinism: false,
+        deny_unconstrained: false,
+        suggest_parallel: false,
+        flag_template_report: false,
+        template_report: String::new(),
+        flag_component_graph: false,
+        component_graph_format: GraphFormat::Json,
+        component_graph: String::new(),
+        flag_old_heuristics: false,
+        prime: config.prime.clone(),
+    };
+    let (dag, _) = build_dag(program_archive, &build_config).map_err(|error| error.report())?;
+    Result::Ok(dag.instance_constraints())
+}
+
+fn instance_name(instance: &InstanceConstraints) -> String {
+    let parameters: Vec<_> = instance.parameters.iter().map(|parameter| parameter.to_str_radix(10)).collect();
+    format!("{}({})", instance.template, parameters.join(", "))
+}
+
+struct Page {
+    format: DocsFormat,
+    text: String,
+}
+
+impl Page {
+    fn escape(&self, text: &str) -> String {
+        match self.format {
+            DocsFormat::Markdown => text.replace('|', "\\|"),
+            DocsFormat::Html => {
+                text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+            }
+        }
+    }
+
+    fn heading(&mut self, level: usize, text: &str, code: bool) {
+        let text = self.escape(text);
+        let line = match (self.format, code) {
+            (DocsFormat::Markdown, true) => format!("{} `{}`\n\n", "#".repeat(level), text),
+            (DocsFormat::Markdown, false) => format!("{} {}\n\n", "#".repeat(level), text),
+            (DocsFormat::Html, true) => format!("<h{0} id=\"{2}\"><code>{1}</code></h{0}>\n", level, text, self.anchor(&text)),
+            (DocsFormat::Html, false) => format!("<h{0}>{1}</h{0}>\n", level, text),
+        };
+        self.text.push_str(&line);
+    }
+
+    fn anchor(&self, text: &str) -> String {
+        let name = text.split('(').next().unwrap_or_default();
+        name.split_whitespace().last().unwrap_or_default().to_string()
+    }
+
+    fn paragraph(&mut self, lines: &[String]) {
+        if lines.iter().all(|line| line.trim().is_empty()) {
+            return;
+        }
+        match self.format {
+            DocsFormat::Markdown => self.text.push_str(&format!("{}\n\n", lines.join("\n").trim())),
+            DocsFormat::Html => {
+                // the blank lines of a comment separate its paragraphs
+                for paragraph in lines.join("\n").split("\n\n").filter(|paragraph| !paragraph.trim().is_empty()) {
+                    self.text.push_str(&format!("<p>{}</p>\n", self.escape(paragraph.trim())));
+                }
+            }
+        }
+    }
+
+    fn table(&mut self, title: &str, headers: &[&str], rows: &[Vec<String>]) {
+        if rows.is_empty() {
+            return;
+        }
+        match self.format {
+            DocsFormat::Markdown => {
+                self.text.push_str(&format!("**{}**\n\n", title));
+                self.text.push_str(&format!("| {} |\n", headers.join(" | ")));
+                self.text.push_str(&format!("|{}\n", " --- |".repeat(headers.len())));
+                for row in rows {
+                    let cells: Vec<_> = row.iter().map(|cell| self.escape(cell)).collect();
+                    self.text.push_str(&format!("| {} |\n", cells.join(" | ")));
+                }
+                self.text.push('\n');
+            }
+            DocsFormat::Html => {
+                self.text.push_str(&format!("<h4>{}</h4>\n<table>\n<tr>", title));
+                for header in headers {
+                    self.text.push_str(&format!("<th>{}</th>", header));
+                }
+                self.text.push_str("</tr>\n");
+                for row in rows {
+                    self.text.push_str("<tr>");
+                    for cell in row {
+                        self.text.push_str(&format!("<td>{}</td>", self.escape(cell)));
+                    }
+                    self.text.push_str("</tr>\n");
+                }
+                self.text.push_str("</table>\n");
+            }
+        }
+    }
+}
+
+fn signal_rows(signals: &[SignalDoc]) -> Vec<Vec<String>> {
+    signals
+        .iter()
+        .map(|signal| {
+            let tags = signal.tags.join(", ");
+            vec![signal.name.clone(), signal.kind.clone(), signal.dimensions.clone(), tags, signal.description.clone()]
+        })
+        .collect()
+}
+
+fn write_definition(page: &mut Page, doc: &DefinitionDoc, instances: Option<&[InstanceConstraints]>) {
+    page.heading(3, &doc.signature, true);
+    page.paragraph(&doc.description);
+    let parameters: Vec<_> =
+        doc.parameters.iter().map(|(name, text)| vec![name.clone(), text.clone()]).collect();
+    page.table("Parameters", &["Name", "Description"], &parameters);
+    let headers = ["Name", "Type", "Dimensions", "Tags", "Description"];
+    page.table("Inputs", &headers, &signal_rows(&doc.inputs));
+    page.table("Outputs", &headers, &signal_rows(&doc.outputs));
+    page.table("Fields", &headers, &signal_rows(&doc.fields));
+    if doc.kind != "template" {
+        return;
+    }
+    if let Some(instances) = instances {
+        let rows: Vec<_> = instances
+            .iter()
+            .filter(|instance| instance.template == doc.name)
+            .map(|instance| {
+                vec![
+                    instance_name(instance),
+                    instance.components.to_string(),
+                    instance.constraints.to_string(),
+                    instance.total_constraints.to_string(),
+                ]
+            })
+            .collect();
+        let headers = ["Instance", "Components", "Constraints", "With subcomponents"];
+        page.table("Constraints", &headers, &rows);
+    }
+}
+
+fn write_page(files: &[SourceFile], instances: Option<&[InstanceConstraints]>, config: &DocsConfig) -> String {
+    let mut page = Page { format: config.format, text: String::new() };
+    let title = config.input.file_name().map_or(String::new(), |name| name.to_string_lossy().to_string());
+    if config.format == DocsFormat::Html {
+        page.text.push_str(&format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n",
+            page.escape(&title)
+        ));
+    }
+    page.heading(1, &title, false);
+    if instances.is_some() {
+        page.paragraph(&["The constraints of each instance of a template are the ones it generates in the circuit of the main component before the simplification, in each of its components and with the ones of its subcomponents.".to_string()]);
+    }
+    for file in files {
+        let docs: Vec<_> =
+            file.ast.definitions.iter().filter_map(|definition| definition_doc(&file.source, definition)).collect();
+        if docs.is_empty() {
+            continue;
+        }
+        page.heading(2, &file.path.display().to_string(), false);
+        for doc in &docs {
+            write_definition(&mut page, doc, instances);
+        }
+    }
+    if config.format == DocsFormat::Html {
+        page.text.push_str("</body>\n</html>\n");
+    }
+    page.text
+}
+
+pub fn has_main_component(file: &Path, config: &DocsConfig) -> bool {
+    let source = std::fs::read_to_string(file).unwrap_or_default();
+    parser::parse_definitions(&source, 0, &config.prime, &config.features)
+        .is_ok_and(|ast| !ast.main_components.is_empty())
+}
+
+// The documentation of the templates, buses and functions of the file and
+// the files it includes, from the /// comments that precede them and their
+// signals, with the constraints of each instance of the templates in the
+// circuit of the main component, if the program has one
+pub fn generate_docs(config: DocsConfig, program_archive: Option<ProgramArchive>) -> Result<(), ()> {
+    let files = read_files(&config)?;
+    let instances = match program_archive {
+        Some(program_archive) => Some(instances_of(program_archive, &config)?),
+        None => None,
+    };
+    let page = write_page(&files, instances.as_deref(), &config);
+    std::fs::write(&config.output, page)
+        .map_err(|e| eprintln!("{}", Colour::Red.paint(format!("Could not write {}: {}", config.output, e))))?;
+    println!("{} {}", Colour::Green.paint("Written successfully:"), config.output);
+    Result::Ok(())
+}
@@ -0,0 +1,480 @@
+use ansi_term::Colour;
+use circom_algebra::modular_arithmetic;
+use circom_algebra::num_bigint::BigInt;
+use constraint_writers::r1cs_reader::{read_r1cs, R1CSData};
+use constraint_writers::smt_writer::{Constraint, SmtEncoding, SmtFile};
+use constraint_writers::sym_reader::read_sym;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// A * B - C expanded, where the monomials of degree 1 are paired with the
+// signal 0, the constant 1
+type Polynomial = BTreeMap<(usize, usize), BigInt>;
+
+#[derive(Copy, Clone)]
+pub enum Solver {
+    Cvc5,
+    Z3,
+}
+
+impl Solver {
+    fn command(&self) -> &'static str {
+        match self {
+            Solver::Cvc5 => "cvc5",
+            Solver::Z3 => "z3",
+        }
+    }
+
+    fn encoding(&self) -> SmtEncoding {
+        match self {
+            Solver::Cvc5 => SmtEncoding::FiniteField,
+            Solver::Z3 => SmtEncoding::Integer,
+        }
+    }
+}
+
+pub struct EquivalenceConfig {
+    pub old_r1cs: PathBuf,
+    pub new_r1cs: PathBuf,
+    pub max_diff: usize,
+    pub solver: Option<Solver>,
+}
+
+enum Verdict {
+    Equivalent(String),
+    Different(String),
+    Unknown(String),
+}
+
+// A compiled circuit, with the names of the sym file next to its r1cs
+struct Circuit {
+    data: R1CSData,
+    names: Option<HashMap<usize, String>>,
+}
+
+impl Circuit {
+    fn read(r1cs: &Path) -> Result<Circuit, ()> {
+        let file = r1cs.to_str().unwrap();
+        let data = read_r1cs(file).map_err(|e| eprintln!("{}", Colour::Red.paint(format!("Could not read {}: {}", file, e))))?;
+        let sym = r1cs.with_extension("sym");
+        let names = if sym.is_file() {
+            let file = sym.to_str().unwrap();
+            Some(read_sym(file).map_err(|e| eprintln!("{}", Colour::Red.paint(format!("Could not read {}: {}", file, e))))?)
+        } else {
+            None
+        };
+        Ok(Circuit { data, names })
+    }
+
+    // the outputs, the public inputs and the private inputs of the main
+    // component, which are the wires after the constant
+    fn interface(&self) -> usize {
+        let header = self.data.header();
+        header.public_outputs + header.public_inputs + header.private_inputs
+    }
+
+    fn wires(&self) -> usize {
+        self.data.header().total_wires
+    }
+
+    fn name(&self, wire: usize) -> Option<&String> {
+        let label = self.data.signals().get(wire)?;
+        self.names.as_ref()?.get(label)
+    }
+}
+
+// The signals of both circuits are numbered alike: the constant and the
+// signals of the interface by their position, the internal signals by their
+// name when both circuits have it, and the rest apart
+struct Renaming {
+    old: Vec<usize>,
+    new: Vec<usize>,
+    names: HashMap<usize, String>,
+    // the internal signals that only one of the circuits has
+    own: HashSet<usize>,
+}
+
+fn rename_signals(old: &Circuit, new: &Circuit) -> Renaming {
+    let interface = old.interface();
+    let mut renaming = Renaming { old: Vec::new(), new: vec![0; new.wires()], names: HashMap::new(), own: HashSet::new() };
+    for wire in 0..=interface {
+        let name = old.name(wire).or_else(|| new.name(wire)).cloned();
+        renaming.old.push(wire);
+        renaming.new[wire] = wire;
+        renaming.names.insert(wire, name.unwrap_or_else(|| format!("interface[{}]", wire)));
+    }
+    let mut new_wires = HashMap::new();
+    for wire in interface + 1..new.wires() {
+        if let Some(name) = new.name(wire) {
+            new_wires.insert(name.clone(), wire);
+        }
+    }
+    let mut next = interface + 1;
+    for wire in interface + 1..old.wires() {
+        renaming.old.push(next);
+        match old.name(wire) {
+            Some(name) if new_wires.contains_key(name) => {
+                renaming.new[new_wires[name]] = next;
+                renaming.names.insert(next, name.clone());
+            }
+            name => {
+                let name = name.cloned().unwrap_or_else(|| wire.to_string());
+                renaming.names.insert(next, format!("old.{}", name));
+                renaming.own.insert(next);
+            }
+        }
+        next += 1;
+    }
+    for wire in interface + 1..new.wires() {
+        if renaming.new[wire] == 0 {
+            renaming.new[wire] = next;
+            let name = new.name(wire).cloned().unwrap_or_else(|| wire.to_string());
+            renaming.names.insert(next, format!("new.{}", name));
+            renaming.own.insert(next);
+            next += 1;
+        }
+    }
+    renaming
+}
+
+fn check_interface(old: &Circuit, new: &Circuit) -> Option<String> {
+    let (old_header, new_header) = (old.data.header(), new.data.header());
+    if old_header.field != new_header.field {
+        return Some("the circuits are compiled for different primes".to_string());
+    }
+    let old_interface = (old_header.public_outputs, old_header.public_inputs, old_header.private_inputs);
+    let new_interface = (new_header.public_outputs, new_header.public_inputs, new_header.private_inputs);
+    if old_interface != new_interface {
+        return Some(format!(
+            "the circuits have different numbers of public outputs, public inputs and private inputs: {:?} and {:?}",
+            old_interface, new_interface
+        ));
+    }
+    for wire in 1..=old.interface() {
+        if let (Some(old_name), Some(new_name)) = (old.name(wire), new.name(wire)) {
+            if old_name != new_name {
+                return Some(format!("the signal {} of the old circuit is in the place of {} in the new one", old_name, new_name));
+            }
+        }
+    }
+    None
+}
+
+fn rename(linear: &HashMap<usize, BigInt>, renaming: &[usize]) -> HashMap<usize, BigInt> {
+    linear.iter().map(|(signal, value)| (renaming[*signal], value.clone())).collect()
+}
+
+fn add_term(polynomial: &mut Polynomial, monomial: (usize, usize), value: &BigInt, field: &BigInt) {
+    let monomial = if monomial.0 <= monomial.1 { monomial } else { (monomial.1, monomial.0) };
+    let entry = polynomial.entry(monomial).or_insert_with(|| BigInt::from(0));
+    *entry = modular_arithmetic::add(entry, value, field);
+}
+
+// The canonical form of a constraint: its polynomial divided by the
+// coefficient of its first monomial, so the order of the signals and the
+// constraints that are multiples of each other do not matter
+fn canonical_form((a, b, c): &Constraint, field: &BigInt) -> Polynomial {
+    let mut polynomial = Polynomial::new();
+    for (left, x) in a {
+        for (right, y) in b {
+            add_term(&mut polynomial, (*left, *right), &modular_arithmetic::mul(x, y, field), field);
+        }
+    }
+    for (signal, z) in c {
+        add_term(&mut polynomial, (0, *signal), &modular_arithmetic::prefix_sub(z, field), field);
+    }
+    let zero = BigInt::from(0);
+    polynomial.retain(|_, value| *value != zero);
+    make_monic(polynomial, field)
+}
+
+fn make_monic(mut polynomial: Polynomial, field: &BigInt) -> Polynomial {
+    if let Some(leading) = polynomial.values().next().cloned() {
+        let inverse = modular_arithmetic::div(&BigInt::from(1), &leading, field).ok().unwrap();
+        for value in polynomial.values_mut() {
+            *value = modular_arithmetic::mul(value, &inverse, field);
+        }
+    }
+    polynomial
+}
+
+// Gaussian elimination: each polynomial of the basis is monic and indexed
+// by its first monomial
+fn reduce(mut polynomial: Polynomial, basis: &BTreeMap<(usize, usize), Polynomial>, field: &BigInt) -> Polynomial {
+    let zero = BigInt::from(0);
+    for (pivot, row) in basis {
+        if let Some(factor) = polynomial.get(pivot).cloned() {
+            let factor = modular_arithmetic::prefix_sub(&factor, field);
+            for (monomial, value) in row {
+                add_term(&mut polynomial, *monomial, &modular_arithmetic::mul(&factor, value, field), field);
+            }
+            polynomial.retain(|_, value| *value != zero);
+        }
+    }
+    polynomial
+}
+
+fn is_linear(polynomial: &Polynomial) -> bool {
+    polynomial.keys().all(|(left, _)| *left == 0)
+}
+
+fn occurs_linearly(polynomial: &Polynomial, signal: usize) -> bool {
+    polynomial.keys().all(|(left, right)| *left != signal && (*right != signal || *left == 0))
+}
+
+// A constraint k*x + R = 0 of an internal signal x that only one circuit has,
+// when replacing x by -R/k in the rest of the constraints keeps them
+// quadratic
+fn find_definition(polynomials: &[Polynomial], own: &HashSet<usize>) -> Option<(usize, usize)> {
+    for (index, polynomial) in polynomials.iter().enumerate() {
+        let linear = is_linear(polynomial);
+        for (_, signal) in polynomial.keys().filter(|(left, right)| *left == 0 && own.contains(right)) {
+            if occurs_linearly(polynomial, *signal) && polynomials.iter().all(|other| linear || occurs_linearly(other, *signal)) {
+                return Some((index, *signal));
+            }
+        }
+    }
+    None
+}
+
+fn substitute(polynomial: Polynomial, signal: usize, definition: &Polynomial, field: &BigInt) -> Polynomial {
+    let inverse = modular_arithmetic::div(&BigInt::from(1), &definition[&(0, signal)], field).ok().unwrap();
+    let factor = modular_arithmetic::prefix_sub(&inverse, field);
+    // signal = factor * rest
+    let rest: Vec<_> = definition
+        .iter()
+        .filter(|(monomial, _)| **monomial != (0, signal))
+        .map(|(monomial, value)| (*monomial, modular_arithmetic::mul(value, &factor, field)))
+        .collect();
+    let expand = |factor: usize| -> Vec<(usize, BigInt)> {
+        if factor == signal {
+            rest.iter().map(|((_, other), value)| (*other, value.clone())).collect()
+        } else {
+            vec![(factor, BigInt::from(1))]
+        }
+    };
+    let mut result = Polynomial::new();
+    for (monomial, value) in polynomial {
+        match monomial {
+            (0, right) if right == signal => {
+                for (other, coefficient) in &rest {
+                    add_term(&mut result, *other, &modular_arithmetic::mul(&value, coefficient, field), field);
+                }
+            }
+            // the definition is linear, since the signal is multiplied
+            (left, right) if left == signal || right == signal => {
+                for (left, x) in expand(left) {
+                    for (right, y) in expand(right) {
+                        let coefficient = modular_arithmetic::mul(&value, &modular_arithmetic::mul(&x, &y, field), field);
+                        add_term(&mut result, (left, right), &coefficient, field);
+                    }
+                }
+            }
+            _ => add_term(&mut result, monomial, &value, field),
+        }
+    }
+    let zero = BigInt::from(0);
+    result.retain(|_, value| *value != zero);
+    result
+}
+
+// The internal signals that only one circuit has and that its differing
+// constraints define are replaced by their definitions, which removes them
+// from the constraints as the simplification of the compiler does. A signal
+// that appears in a single constraint, and linearly, takes a value for any
+// values of the rest, so that constraint is also removed
+fn eliminate_own_signals(mut polynomials: Vec<Polynomial>, own: &HashSet<usize>, field: &BigInt) -> Vec<Polynomial> {
+    while let Some((index, signal)) = find_definition(&polynomials, own) {
+        let definition = polynomials.swap_remove(index);
+        polynomials = polynomials
+            .into_iter()
+            .map(|polynomial| substitute(polynomial, signal, &definition, field))
+            .filter(|polynomial| !polynomial.is_empty())
+            .map(|polynomial| make_monic(polynomial, field))
+            .collect();
+    }
+    polynomials
+}
+
+// Whether every polynomial of the second list is a linear combination of
+// the ones of the first list
+fn spans(first: &[Polynomial], second: &[Polynomial], field: &BigInt) -> bool {
+    let mut basis = BTreeMap::new();
+    for polynomial in first {
+        let reduced = make_monic(reduce(polynomial.clone(), &basis, field), field);
+        if let Some(pivot) = reduced.keys().next().cloned() {
+            basis.insert(pivot, reduced);
+        }
+    }
+    second.iter().all(|polynomial| reduce(polynomial.clone(), &basis, field).is_empty())
+}
+
+fn signals_of((a, b, c): &Constraint) -> impl Iterator<Item = usize> + '_ {
+    a.keys().chain(b.keys()).chain(c.keys()).cloned().filter(|signal| *signal != 0)
+}
+
+// The common constraints that share signals with the differing ones, directly
+// or through other common constraints. The rest do not change whether the
+// differing constraints hold
+fn connected_constraints<'a>(common: &[&'a Constraint], differing: &[&Constraint]) -> Vec<&'a Constraint> {
+    let mut by_signal: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (index, constraint) in common.iter().enumerate() {
+        for signal in signals_of(constraint) {
+            by_signal.entry(signal).or_default().push(index);
+        }
+    }
+    let mut reached: HashSet<usize> = differing.iter().flat_map(|constraint| signals_of(constraint)).collect();
+    let mut pending: Vec<usize> = reached.iter().cloned().collect();
+    let mut included = HashSet::new();
+    while let Some(signal) = pending.pop() {
+        for index in by_signal.get(&signal).into_iter().flatten() {
+            if included.insert(*index) {
+                for other in signals_of(common[*index]) {
+                    if reached.insert(other) {
+                        pending.push(other);
+                    }
+                }
+            }
+        }
+    }
+    let mut included: Vec<usize> = included.into_iter().collect();
+    included.sort_unstable();
+    included.into_iter().map(|index| common[index]).collect()
+}
+
+fn write_query(
+    file: &Path,
+    field: &BigInt,
+    encoding: SmtEncoding,
+    renaming: &Renaming,
+    common: &[&Constraint],
+    old_only: &[Constraint],
+    new_only: &[Constraint],
+) -> std::io::Result<()> {
+    let mut smt = SmtFile::new(file.to_str().unwrap(), field, encoding)?;
+    let mut signals: Vec<usize> = common.iter().cloned().chain(old_only).chain(new_only).flat_map(signals_of).collect();
+    signals.sort_unstable();
+    signals.dedup();
+    for signal in signals {
+        SmtFile::declare_signal(&mut smt, signal, &renaming.names[&signal])?;
+    }
+    for (a, b, c) in common {
+        SmtFile::write_constraint(&mut smt, a, b, c)?;
+    }
+    SmtFile::write_distinct_constraints(&mut smt, old_only, new_only)?;
+    SmtFile::write_check_sat(&mut smt)?;
+    SmtFile::finish_writing(smt)
+}
+
+fn run_solver(solver: Solver, file: &Path) -> Option<String> {
+    let output = Command::new(solver.command()).arg(file).output().ok()?;
+    String::from_utf8_lossy(&output.stdout).lines().next().map(|line| line.trim().to_string())
+}
+
+fn compare(old: &Circuit, new: &Circuit, config: &EquivalenceConfig) -> Result<Verdict, ()> {
+    if let Some(difference) = check_interface(old, new) {
+        return Ok(Verdict::Different(difference));
+    }
+    if old.data.has_custom_gates() || new.data.has_custom_gates() {
+        return Ok(Verdict::Unknown("the circuits with custom templates are not compared".to_string()));
+    }
+    let field = old.data.header().field.clone();
+    let renaming = rename_signals(old, new);
+    let canonicalize = |data: &R1CSData, wires: &[usize]| -> BTreeMap<Polynomial, Constraint> {
+        let mut constraints = BTreeMap::new();
+        for (a, b, c) in data.constraints() {
+            let constraint = (rename(a, wires), rename(b, wires), rename(c, wires));
+            let form = canonical_form(&constraint, &field);
+            if !form.is_empty() {
+                constraints.entry(form).or_insert(constraint);
+            }
+        }
+        constraints
+    };
+    let old_constraints = canonicalize(&old.data, &renaming.old);
+    let new_constraints = canonicalize(&new.data, &renaming.new);
+    let old_only: Vec<_> = old_constraints.iter().filter(|(form, _)| !new_constraints.contains_key(*form)).collect();
+    let new_only: Vec<_> = new_constraints.iter().filter(|(form, _)| !old_constraints.contains_key(*form)).collect();
+    if old_only.is_empty() && new_only.is_empty() {
+        return Ok(Verdict::Equivalent(format!(
+            "their {} constraints are the same up to the order of the signals and the constraints",
+            old_constraints.len()
+        )));
+    }
+    let old_forms = eliminate_own_signals(old_only.iter().map(|(form, _)| (*form).clone()).collect(), &renaming.own, &field);
+    let new_forms = eliminate_own_signals(new_only.iter().map(|(form, _)| (*form).clone()).collect(), &renaming.own, &field);
+    if spans(&old_forms, &new_forms, &field) && spans(&new_forms, &old_forms, &field) {
+        return Ok(Verdict::Equivalent(format!(
+            "the {} constraints of the old circuit and the {} of the new one that differ are linear combinations of each other, once the internal signals that only one circuit has are replaced by their definitions",
+            old_only.len(),
+            new_only.len()
+        )));
+    }
+    let summary = format!("{} constraints are only in the old circuit and {} only in the new one", old_only.len(), new_only.len());
+    if old_only.len() + new_only.len() > config.max_diff {
+        return Ok(Verdict::Unknown(format!("{}, more than the {} that are given to a solver", summary, config.max_diff)));
+    }
+    let differing: Vec<&Constraint> = old_only.iter().chain(&new_only).map(|(_, constraint)| *constraint).collect();
+    let own = differing.iter().flat_map(|constraint| signals_of(constraint)).find(|signal| renaming.own.contains(signal));
+    if let Some(signal) = own {
+        return Ok(Verdict::Unknown(format!(
+            "{}, and they use internal signals that only one of the circuits has, as {}",
+            summary, renaming.names[&signal]
+        )));
+    }
+    let common: Vec<&Constraint> = old_constraints.iter().filter(|(form, _)| new_constraints.contains_key(*form)).map(|(_, constraint)| constraint).collect();
+    let common = connected_constraints(&common, &differing);
+    let old_only: Vec<Constraint> = old_only.iter().map(|(_, constraint)| (*constraint).clone()).collect();
+    let new_only: Vec<Constraint> = new_only.iter().map(|(_, constraint)| (*constraint).clone()).collect();
+    let stem = config.new_r1cs.file_stem().unwrap().to_str().unwrap();
+    let query = config.new_r1cs.with_file_name(format!("{}_equivalence.smt2", stem));
+    let encoding = config.solver.map_or(SmtEncoding::FiniteField, |solver| solver.encoding());
+    write_query(&query, &field, encoding, &renaming, &common, &old_only, &new_only)
+        .map_err(|error| eprintln!("{}", Colour::Red.paint(format!("Could not write {}: {}", query.display(), error))))?;
+    let solver = match config.solver {
+        Some(solver) => solver,
+        None => {
+            return Ok(Verdict::Unknown(format!("{}: run an SMT solver on {}, where unsat means that they are equivalent", summary, query.display())));
+        }
+    };
+    let verdict = match run_solver(solver, &query).as_deref() {
+        Some("unsat") => Verdict::Equivalent(format!("{}, and {} proves that they hold for the same values", summary, solver.command())),
+        Some("sat") => Verdict::Different(format!(
+            "{}, and {} finds values of the signals that satisfy the ones of a circuit and not the ones of the other (see {})",
+            summary,
+            solver.command(),
+            query.display()
+        )),
+        answer => Verdict::Unknown(format!(
+            "{}, and {} does not decide {}: {}",
+            summary,
+            solver.command(),
+            query.display(),
+            answer.unwrap_or("it could not be run")
+        )),
+    };
+    Ok(verdict)
+}
+
+pub fn check_equivalence(config: EquivalenceConfig) -> Result<(), ()> {
+    let old = Circuit::read(&config.old_r1cs)?;
+    let new = Circuit::read(&config.new_r1cs)?;
+    if old.names.is_none() || new.names.is_none() {
+        println!("{}", Colour::Yellow.paint("Without the sym files of both circuits, only the signals of their interface are identified"));
+    }
+    match compare(&old, &new, &config)? {
+        Verdict::Equivalent(reason) => {
+            println!("{} {}", Colour::Green.paint("The circuits are equivalent:"), reason);
+            Ok(())
+        }
+        Verdict::Different(reason) => {
+            eprintln!("{} {}", Colour::Red.paint("The circuits are not equivalent:"), reason);
+            Err(())
+        }
+        Verdict::Unknown(reason) => {
+            eprintln!("{} {}", Colour::Yellow.paint("The equivalence of the circuits is not decided:"), reason);
+            Err(())
+        }
+    }
+}
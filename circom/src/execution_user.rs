@@ -1,37 +1,72 @@
+use crate::VERSION;
 use ansi_term::Colour;
+use compiler::hir::hir_export::{self, HirFormat};
 use compiler::hir::very_concrete_program::VCP;
 use constraint_writers::debug_writer::DebugWriter;
+use constraint_writers::graph_writer::GraphFormat;
+use constraint_writers::heatmap_writer::Heatmap;
+use constraint_writers::log_writer::Log;
+use constraint_writers::smt_writer::SmtEncoding;
 use constraint_writers::ConstraintExporter;
+use program_structure::file_definition::FileLibrary;
 use program_structure::program_archive::ProgramArchive;
 
 
 pub struct ExecutionConfig {
     pub r1cs: String,
     pub sym: String,
+    pub smt: String,
+    pub source_map: String,
+    pub heatmap: String,
     pub json_constraints: String,
     pub json_substitutions: String,
     pub no_rounds: usize,
+    pub function_steps: usize,
+    pub template_recursion: usize,
     pub flag_s: bool,
     pub flag_f: bool,
     pub flag_p: bool,
     pub flag_old_heuristics:bool,
     pub flag_verbose: bool,
     pub inspect_constraints_flag: bool,
+    pub inspect_witness_flag: bool,
+    pub inspect_determinism_flag: bool,
+    pub deny_unconstrained_flag: bool,
+    pub suggest_parallel_flag: bool,
+    pub template_report_flag: bool,
+    pub template_report: String,
+    pub component_graph_flag: bool,
+    pub component_graph_format: GraphFormat,
+    pub component_graph: String,
+    pub constraint_graph_flag: bool,
+    pub constraint_graph_format: GraphFormat,
+    pub constraint_graph_by_template: bool,
+    pub constraint_graph: String,
+    pub hir_flag: bool,
+    pub hir_format: HirFormat,
+    pub hir: String,
     pub sym_flag: bool,
+    pub smt_flag: bool,
+    pub smt_encoding: SmtEncoding,
+    pub source_map_flag: bool,
+    pub heatmap_flag: bool,
     pub r1cs_flag: bool,
     pub json_substitution_flag: bool,
     pub json_constraint_flag: bool,
+    pub stats_flag: bool,
     pub prime: String,
 }
 
 pub fn execute_project(
     program_archive: ProgramArchive,
     config: ExecutionConfig,
-) -> Result<VCP, ()> {
+) -> Result<(VCP, Option<Log>), ()> {
     use constraint_generation::{build_circuit, BuildConfig};
     let debug = DebugWriter::new(config.json_constraints).unwrap();
     let build_config = BuildConfig {
         no_rounds: config.no_rounds,
+        function_steps: config.function_steps,
+        template_recursion: config.template_recursion,
         flag_json_sub: config.json_substitution_flag,
         json_substitutions: config.json_substitutions,
         flag_s: config.flag_s,
@@ -39,21 +74,50 @@ pub fn execute_project(
         flag_p: config.flag_p,
         flag_verbose: config.flag_verbose,
         inspect_constraints: config.inspect_constraints_flag,
+        inspect_witness: config.inspect_witness_flag,
+        inspect_determinism: config.inspect_determinism_flag,
+        deny_unconstrained: config.deny_unconstrained_flag,
+        suggest_parallel: config.suggest_parallel_flag,
+        flag_template_report: config.template_report_flag,
+        template_report: config.template_report,
+        flag_component_graph: config.component_graph_flag,
+        component_graph_format: config.component_graph_format,
+        component_graph: config.component_graph,
         flag_old_heuristics: config.flag_old_heuristics,
         prime : config.prime,
     };
     let custom_gates = program_archive.custom_gates;
-    let (exporter, vcp) = build_circuit(program_archive, build_config)?;
+    let files = if config.source_map_flag || config.heatmap_flag { Some(program_archive.file_library.clone()) } else { None };
+    let (exporter, vcp) = build_circuit(program_archive, build_config).map_err(|error| error.report())?;
     if config.r1cs_flag {
         generate_output_r1cs(&config.r1cs, exporter.as_ref(), custom_gates)?;
     }
     if config.sym_flag {
         generate_output_sym(&config.sym, exporter.as_ref())?;
     }
+    if config.smt_flag {
+        generate_output_smt(&config.smt, exporter.as_ref(), config.smt_encoding)?;
+    }
+    if let Some(files) = &files {
+        if config.source_map_flag {
+            generate_output_source_map(&config.source_map, exporter.as_ref(), files)?;
+        }
+        if config.heatmap_flag {
+            generate_output_heatmap(&config.heatmap, exporter.as_ref(), files)?;
+        }
+    }
+    if config.constraint_graph_flag {
+        let (format, by_template) = (config.constraint_graph_format, config.constraint_graph_by_template);
+        generate_output_constraint_graph(&config.constraint_graph, exporter.as_ref(), format, by_template)?;
+    }
     if config.json_constraint_flag {
         generate_json_constraints(&debug, exporter.as_ref())?;
     }
-    Result::Ok(vcp)
+    if config.hir_flag {
+        generate_output_hir(&config.hir, &vcp, config.hir_format)?;
+    }
+    let log = if config.stats_flag { Some(exporter.log()) } else { None };
+    Result::Ok((vcp, log))
 }
 
 fn generate_output_r1cs(file: &str, exporter: &dyn ConstraintExporter, custom_gates: bool) -> Result<(), ()> {
@@ -76,6 +140,65 @@ fn generate_output_sym(file: &str, exporter: &dyn ConstraintExporter) -> Result<
     }
 }
 
+fn generate_output_smt(file: &str, exporter: &dyn ConstraintExporter, encoding: SmtEncoding) -> Result<(), ()> {
+    if let Result::Ok(()) = exporter.smt(file, encoding) {
+        println!("{} {}", Colour::Green.paint("Written successfully:"), file);
+        Result::Ok(())
+    } else {
+        eprintln!("{}", Colour::Red.paint("Could not write the output in the given path"));
+        Result::Err(())
+    }
+}
+
+fn generate_output_source_map(file: &str, exporter: &dyn ConstraintExporter, files: &FileLibrary) -> Result<(), ()> {
+    if let Result::Ok(()) = exporter.source_map(files).write(file) {
+        println!("{} {}", Colour::Green.paint("Written successfully:"), file);
+        Result::Ok(())
+    } else {
+        eprintln!("{}", Colour::Red.paint("Could not write the output in the given path"));
+        Result::Err(())
+    }
+}
+
+fn generate_output_heatmap(file: &str, exporter: &dyn ConstraintExporter, files: &FileLibrary) -> Result<(), ()> {
+    if let Result::Ok(()) = Heatmap::new(&exporter.source_map(files), files).write(file) {
+        println!("{} {}", Colour::Green.paint("Written successfully:"), file);
+        Result::Ok(())
+    } else {
+        eprintln!("{}", Colour::Red.paint("Could not write the output in the given path"));
+        Result::Err(())
+    }
+}
+
+fn generate_output_constraint_graph(
+    file: &str,
+    exporter: &dyn ConstraintExporter,
+    format: GraphFormat,
+    by_template: bool,
+) -> Result<(), ()> {
+    if let Result::Ok(()) = exporter.constraint_graph().write(file, format, by_template) {
+        println!("{} {}", Colour::Green.paint("Written successfully:"), file);
+        Result::Ok(())
+    } else {
+        eprintln!("{}", Colour::Red.paint("Could not write the output in the given path"));
+        Result::Err(())
+    }
+}
+
+fn generate_output_hir(file: &str, vcp: &VCP, format: HirFormat) -> Result<(), ()> {
+    let content = match format {
+        HirFormat::Text => hir_export::hir_text(vcp, VERSION),
+        HirFormat::Json => hir_export::hir_json(vcp).to_string(),
+    };
+    if let Result::Ok(()) = std::fs::write(file, content) {
+        println!("{} {}", Colour::Green.paint("Written successfully:"), file);
+        Result::Ok(())
+    } else {
+        eprintln!("{}", Colour::Red.paint("Could not write the output in the given path"));
+        Result::Err(())
+    }
+}
+
 fn generate_json_constraints(
     debug: &DebugWriter,
     exporter: &dyn ConstraintExporter,
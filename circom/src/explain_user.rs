@@ -0,0 +1,379 @@
+use ansi_term::Colour;
+use circom_algebra::algebra::Constraint;
+use circom_algebra::num_bigint::BigInt;
+use compiler::compiler_interface;
+use compiler::compiler_interface::{Config, OptimizationLevel, Pass, PassConfig};
+use compiler::intermediate_representation::ir_interface::{Instruction, ObtainMeta};
+use compiler::intermediate_representation::InstructionList;
+use constraint_generation::{build_dag, BuildConfig};
+use constraint_writers::graph_writer::GraphFormat;
+use dag::{ConstraintOrigin, SimplificationFlags, Tree, DAG};
+use program_structure::constants::UsefulConstants;
+use program_structure::file_definition::FileLibrary;
+use program_structure::program_archive::ProgramArchive;
+use std::collections::{HashMap, HashSet};
+use crate::VERSION;
+
+// What is explained: a constraint of the r1cs file, by its number, or a
+// signal, by its full name as in the sym file
+#[derive(Clone)]
+pub enum Explanation {
+    Constraint(usize),
+    Signal(String),
+}
+
+pub struct ExplanationConfig {
+    pub explanation: Explanation,
+    pub no_rounds: usize,
+    pub flag_s: bool,
+    pub flag_f: bool,
+    pub flag_old_heuristics: bool,
+    pub function_steps: usize,
+    pub template_recursion: usize,
+    pub flag_verbose: bool,
+    pub prime: String,
+}
+
+// A constraint as it was generated by a component, before the simplification
+struct Generated {
+    component: String,
+    node: usize,
+    // its position among the non-linear constraints of the component
+    position: Option<usize>,
+    constraint: Constraint<usize>,
+    origin: ConstraintOrigin,
+}
+
+// A constraint of the r1cs file, with the generated one it comes from
+struct Final {
+    constraint: Constraint<usize>,
+    generated: Option<usize>,
+}
+
+struct Circuit {
+    field: BigInt,
+    names: HashMap<usize, String>,
+    generated: Vec<Generated>,
+    constraints: Vec<Final>,
+    witness: HashMap<usize, usize>,
+    substitutions: HashMap<usize, HashMap<usize, BigInt>>,
+    templates: Vec<String>,
+    code: Vec<InstructionList>,
+}
+
+fn collect_generated(tree: &Tree, dag: &DAG, names: &mut HashMap<usize, String>, generated: &mut Vec<Generated>) {
+    for (id, name) in &tree.id_to_name {
+        names.insert(*id, format!("{}.{}", tree.path, name));
+    }
+    let node = &dag.nodes[tree.node_id];
+    let is_main = tree.node_id == dag.main_id();
+    let mut position = 0;
+    for (constraint, origin) in node.constraints().iter().zip(node.constraint_origins()) {
+        let non_linear = !Constraint::is_linear(constraint);
+        // the empty constraints are only written for the main component
+        if is_main || !constraint.is_empty() {
+            generated.push(Generated {
+                component: tree.path.clone(),
+                node: tree.node_id,
+                position: if non_linear { Some(position) } else { None },
+                constraint: constraint.apply_offset(tree.offset),
+                origin: origin.clone(),
+            });
+        }
+        if non_linear {
+            position += 1;
+        }
+    }
+    for edge in Tree::get_edges(tree) {
+        let subtree = Tree::go_to_subtree(tree, edge);
+        collect_generated(&subtree, dag, names, generated);
+    }
+}
+
+fn build_circuit(program_archive: ProgramArchive, config: &ExplanationConfig) -> Result<Circuit, ()> {
+    let build_config = BuildConfig {
+        no_rounds: config.no_rounds,
+        function_steps: config.function_steps,
+        template_recursion: config.template_recursion,
+        flag_json_sub: false,
+        json_substitutions: String::new(),
+        flag_s: config.flag_s,
+        flag_f: true,
+        flag_p: false,
+        flag_verbose: config.flag_verbose,
+        inspect_constraints: false,
+        inspect_witness: false,
+        inspect_determinism: false,
+        deny_unconstrained: false,
+        suggest_parallel: false,
+        flag_template_report: false,
+        template_report: String::new(),
+        flag_component_graph: false,
+        component_graph_format: GraphFormat::Json,
+        component_graph: String::new(),
+        flag_old_heuristics: config.flag_old_heuristics,
+        prime: config.prime.clone(),
+    };
+    let (dag, vcp) = build_dag(program_archive, &build_config).map_err(|error| error.report())?;
+    let templates: Vec<String> = dag.nodes.iter().map(|node| node.template_name().to_string()).collect();
+    // the code of the templates as it is generated, without the passes that change it
+    let circuit = compiler_interface::run_compiler(
+        vcp,
+        Config {
+            debug_output: false,
+            produce_input_log: false,
+            wat_flag: false,
+            no_asm_flag: false,
+            constraint_assert_disabled_flag: false,
+            passes: PassConfig {
+                level: OptimizationLevel::O0,
+                disabled_passes: Pass::ALL.to_vec(),
+                report_timing: false,
+                report_effects: false,
+                verify: false,
+            },
+            lazy_components_flag: false,
+            trace_signals_flag: false,
+            profile_templates_flag: false,
+            ir_cache: None,
+            prime: config.prime.clone(),
+        },
+        VERSION,
+    )?;
+    let code = circuit.templates.iter().map(|template| template.body.clone()).collect();
+
+    let mut names = HashMap::new();
+    let mut generated = Vec::new();
+    collect_generated(&Tree::new(&dag), &dag, &mut names, &mut generated);
+    let field = UsefulConstants::new(&config.prime).get_p().clone();
+    if config.flag_f {
+        let constraints = generated
+            .iter()
+            .enumerate()
+            .map(|(position, g)| Final { constraint: g.constraint.clone(), generated: Some(position) })
+            .collect();
+        let witness = dag.produce_witness().into_iter().enumerate().map(|(position, signal)| (signal, position)).collect();
+        return Ok(Circuit { field, names, generated, constraints, witness, substitutions: HashMap::new(), templates, code });
+    }
+
+    let list = dag.map_to_list(SimplificationFlags {
+        no_rounds: config.no_rounds,
+        flag_s: config.flag_s,
+        parallel_flag: false,
+        port_substitution: false,
+        json_substitutions: String::new(),
+        keep_substitutions: true,
+        flag_old_heuristics: config.flag_old_heuristics,
+        prime: config.prime.clone(),
+    });
+    let mut by_position = HashMap::new();
+    for (index, g) in generated.iter().enumerate() {
+        if let Some(position) = g.position {
+            by_position.insert((g.component.clone(), position), index);
+        }
+    }
+    let mut constraints = Vec::new();
+    for c_id in list.constraints.get_ids() {
+        let constraint = list.constraints.read_constraint(c_id).unwrap();
+        let generated = list
+            .sources
+            .get(c_id)
+            .and_then(|(component, _, position)| by_position.get(&(component.to_string(), position)).cloned());
+        constraints.push(Final { constraint, generated });
+    }
+    let mut substitutions = HashMap::new();
+    for substitution in &list.substitutions {
+        substitutions.insert(*substitution.from(), substitution.to().clone());
+    }
+    Ok(Circuit { field, names, generated, constraints, witness: list.signal_map, substitutions, templates, code })
+}
+
+fn name(names: &HashMap<usize, String>, signal: usize) -> String {
+    names.get(&signal).cloned().unwrap_or_else(|| format!("signal {}", signal))
+}
+
+// The coefficients over half of the field are written as negative numbers,
+// and the signals by their names
+pub fn linear_expression(field: &BigInt, names: &HashMap<usize, String>, expression: &HashMap<usize, BigInt>) -> String {
+    let mut signals: Vec<_> = expression.keys().cloned().collect();
+    signals.sort();
+    let mut terms = Vec::new();
+    for signal in signals {
+        let coefficient = &expression[&signal];
+        let (negative, magnitude) = if coefficient * 2 > *field {
+            (true, field - coefficient)
+        } else {
+            (false, coefficient.clone())
+        };
+        let term = if signal == Constraint::<usize>::constant_coefficient() {
+            magnitude.to_string()
+        } else if magnitude == BigInt::from(1) {
+            name(names, signal)
+        } else {
+            format!("{}*{}", magnitude, name(names, signal))
+        };
+        let sign = match (negative, terms.is_empty()) {
+            (true, true) => "-",
+            (true, false) => " - ",
+            (false, true) => "",
+            (false, false) => " + ",
+        };
+        terms.push(format!("{}{}", sign, term));
+    }
+    if terms.is_empty() {
+        "0".to_string()
+    } else {
+        terms.concat()
+    }
+}
+
+pub fn constraint_to_string(field: &BigInt, names: &HashMap<usize, String>, constraint: &Constraint<usize>) -> String {
+    let c = linear_expression(field, names, constraint.c());
+    if Constraint::is_linear(constraint) {
+        format!("{} = 0", c)
+    } else {
+        let a = linear_expression(field, names, constraint.a());
+        let b = linear_expression(field, names, constraint.b());
+        format!("({}) * ({}) = {}", a, b, c)
+    }
+}
+
+// The instructions of the code that come from a line, where the ones inside
+// a branch or a loop of another line are searched in their bodies
+fn instructions_of_line<'a>(code: &'a InstructionList, line: usize, found: &mut Vec<&'a Instruction>) {
+    for instruction in code {
+        if instruction.get_line() == line {
+            found.push(instruction);
+            continue;
+        }
+        match &**instruction {
+            Instruction::Branch(branch) => {
+                instructions_of_line(&branch.if_branch, line, found);
+                instructions_of_line(&branch.else_branch, line, found);
+            }
+            Instruction::Loop(body) => instructions_of_line(&body.body, line, found),
+            Instruction::Switch(switch) => {
+                for case in &switch.cases {
+                    instructions_of_line(&case.body, line, found);
+                }
+                instructions_of_line(&switch.default_branch, line, found);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn signals_of(constraint: &Constraint<usize>) -> HashSet<usize> {
+    let mut signals = HashSet::new();
+    for expression in [constraint.a(), constraint.b(), constraint.c()] {
+        signals.extend(expression.keys().cloned());
+    }
+    signals.remove(&Constraint::<usize>::constant_coefficient());
+    signals
+}
+
+// The substitutions of the signals that the simplification removed from the
+// constraint, followed by the ones of the signals they were replaced by
+fn print_substitutions(circuit: &Circuit, signals: &HashSet<usize>, visited: &mut HashSet<usize>, depth: usize) {
+    let mut signals: Vec<_> = signals.iter().cloned().collect();
+    signals.sort();
+    for signal in signals {
+        if let Some(to) = circuit.substitutions.get(&signal) {
+            if !visited.insert(signal) {
+                continue;
+            }
+            println!("{}{} = {}", "  ".repeat(depth), name(&circuit.names, signal), linear_expression(&circuit.field, &circuit.names, to));
+            let mut replaced_by: HashSet<usize> = to.keys().cloned().collect();
+            replaced_by.remove(&Constraint::<usize>::constant_coefficient());
+            print_substitutions(circuit, &replaced_by, visited, depth + 1);
+        }
+    }
+}
+
+fn explain_constraint(circuit: &Circuit, files: &FileLibrary, number: usize) -> Result<(), ()> {
+    let constraint = match circuit.constraints.get(number) {
+        Some(constraint) => constraint,
+        None => {
+            let message = format!("The circuit has {} constraints, and there is no constraint {}", circuit.constraints.len(), number);
+            eprintln!("{}", Colour::Red.paint(message));
+            return Result::Err(());
+        }
+    };
+    println!("{} {}: {}", Colour::Green.paint("constraint"), number, constraint_to_string(&circuit.field, &circuit.names, &constraint.constraint));
+    let generated = match constraint.generated {
+        Some(generated) => &circuit.generated[generated],
+        None => {
+            println!("  it is a linear constraint that results from the simplification of the linear constraints of the circuit");
+            return Result::Ok(());
+        }
+    };
+    let template = &circuit.templates[generated.node];
+    println!("{} {} ({})", Colour::Green.paint("component"), generated.component, template);
+    if let Some((file_id, location)) = &generated.origin {
+        let file = files.get_file_name(*file_id).unwrap_or_default().trim_matches('"');
+        if let Some(line) = files.get_line(location.start, *file_id) {
+            println!("{} {}:{}", Colour::Green.paint("source"), file, line);
+            let text = files.get_line_location(line, *file_id).and_then(|l| files.get_source(*file_id).map(|s| &s[l]));
+            if let Some(text) = text {
+                println!("  {}", text);
+            }
+            let mut instructions = Vec::new();
+            instructions_of_line(&circuit.code[generated.node], line, &mut instructions);
+            println!("{}", Colour::Green.paint("code"));
+            for instruction in instructions {
+                println!("  {}", instruction.to_string());
+            }
+        }
+    }
+    println!("{} {}", Colour::Green.paint("generated"), constraint_to_string(&circuit.field, &circuit.names, &generated.constraint));
+    let removed: HashSet<usize> =
+        signals_of(&generated.constraint).difference(&signals_of(&constraint.constraint)).cloned().collect();
+    if removed.is_empty() {
+        println!("{} no signal of the constraint was removed", Colour::Green.paint("simplification"));
+    } else {
+        println!("{}", Colour::Green.paint("simplification"));
+        print_substitutions(circuit, &removed, &mut HashSet::new(), 1);
+    }
+    Result::Ok(())
+}
+
+fn explain_signal(circuit: &Circuit, files: &FileLibrary, signal_name: &str) -> Result<(), ()> {
+    let signal = match circuit.names.iter().find(|(_, name)| name.as_str() == signal_name) {
+        Some((signal, _)) => *signal,
+        None => {
+            eprintln!("{}", Colour::Red.paint(format!("The signal {} does not exist", signal_name)));
+            return Result::Err(());
+        }
+    };
+    match (circuit.witness.get(&signal), circuit.substitutions.get(&signal)) {
+        (_, Some(to)) => println!(
+            "{} {} was removed by the simplification: {} = {}",
+            Colour::Green.paint("signal"),
+            signal_name,
+            signal_name,
+            linear_expression(&circuit.field, &circuit.names, to)
+        ),
+        (Some(position), None) => println!("{} {} is the signal {} of the witness", Colour::Green.paint("signal"), signal_name, position),
+        (None, None) => println!("{} {} was removed by the simplification", Colour::Green.paint("signal"), signal_name),
+    }
+    let constraints: Vec<usize> = (0..circuit.constraints.len())
+        .filter(|number| signals_of(&circuit.constraints[*number].constraint).contains(&signal))
+        .collect();
+    if constraints.is_empty() {
+        println!("  it is in no constraint");
+    }
+    for number in constraints {
+        println!();
+        explain_constraint(circuit, files, number)?;
+    }
+    Result::Ok(())
+}
+
+pub fn explain(program_archive: ProgramArchive, config: ExplanationConfig) -> Result<(), ()> {
+    let files = program_archive.file_library.clone();
+    let circuit = build_circuit(program_archive, &config)?;
+    match &config.explanation {
+        Explanation::Constraint(number) => explain_constraint(&circuit, &files, *number),
+        Explanation::Signal(name) => explain_signal(&circuit, &files, name),
+    }
+}
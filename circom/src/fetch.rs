@@ -0,0 +1,91 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn git(args: &[&str], dir: Option<&Path>) -> Result<String, String> {
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+        command.arg("-C").arg(dir);
+    }
+    let output = command.args(args).output().map_err(|e| format!("cannot run git: {}", e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(format!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()))
+    }
+}
+
+// The directory where the fetched libraries are kept
+pub fn cache() -> PathBuf {
+    match (std::env::var_os("CIRCOM_CACHE"), std::env::var_os("HOME")) {
+        (Some(cache), _) => PathBuf::from(cache),
+        (None, home) => PathBuf::from(home.unwrap_or_default()).join(".circom").join("cache"),
+    }
+}
+
+// The repositories are mirrored in cache/git/db/name, and each revision is
+// checked out in cache/git/checkouts/name/revision
+fn database(cache: &Path, name: &str, url: &str) -> Result<PathBuf, String> {
+    let db = cache.join("git").join("db").join(name);
+    if db.is_dir() {
+        git(&["remote", "set-url", "origin", url], Some(&db))?;
+    } else {
+        std::fs::create_dir_all(&db).map_err(|e| format!("{}: {}", db.display(), e))?;
+        git(&["init", "--bare", "--quiet"], Some(&db))?;
+        git(&["remote", "add", "origin", url], Some(&db))?;
+    }
+    Ok(db)
+}
+
+fn update(db: &Path) -> Result<(), String> {
+    git(&["fetch", "--quiet", "--force", "--tags", "origin", "+refs/heads/*:refs/heads/*", "+HEAD:refs/remotes/origin/HEAD"], Some(db))
+        .map(|_| ())
+}
+
+fn commit(db: &Path, reference: &str) -> Result<String, String> {
+    git(&["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", reference)], Some(db))
+}
+
+// Fetches the revision of the library, which is the one of the lockfile if
+// there is one, or the commit pointed by the reference otherwise. Returns the
+// directory of the checkout and the exact revision
+pub fn fetch_git(
+    cache: &Path,
+    name: &str,
+    url: &str,
+    reference: &str,
+    locked: Option<&str>,
+) -> Result<(PathBuf, String), String> {
+    let checkouts = cache.join("git").join("checkouts").join(name);
+    if let Some(revision) = locked {
+        if checkouts.join(revision).is_dir() {
+            return Ok((checkouts.join(revision), revision.to_string()));
+        }
+    }
+    let db = database(cache, name, url)?;
+    let revision = match locked {
+        Some(revision) => match commit(&db, revision) {
+            Ok(revision) => revision,
+            Err(_) => {
+                update(&db)?;
+                commit(&db, revision).map_err(|_| format!("the revision {} is not found in {}", revision, url))?
+            }
+        },
+        None => {
+            update(&db)?;
+            let reference = if reference.is_empty() { "origin/HEAD" } else { reference };
+            commit(&db, reference).map_err(|_| format!("the reference {} is not found in {}", reference, url))?
+        }
+    };
+    let checkout = checkouts.join(&revision);
+    if !checkout.is_dir() {
+        // the checkout is built aside, so that an interrupted fetch is not taken as done
+        let partial = checkouts.join(format!("{}.partial", revision));
+        let _ = std::fs::remove_dir_all(&partial);
+        std::fs::create_dir_all(&checkouts).map_err(|e| format!("{}: {}", checkouts.display(), e))?;
+        let partial_str = partial.to_string_lossy();
+        git(&["clone", "--quiet", "--no-checkout", &db.to_string_lossy(), &partial_str], None)?;
+        git(&["checkout", "--quiet", "--detach", &revision], Some(&partial))?;
+        std::fs::rename(&partial, &checkout).map_err(|e| format!("{}: {}", checkout.display(), e))?;
+    }
+    Ok((checkout, revision))
+}
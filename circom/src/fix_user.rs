@@ -0,0 +1,66 @@
+use super::compile_program;
+use super::input_user::Input;
+use ansi_term::Colour;
+use program_structure::error_definition::{Report, Suggestion};
+
+// A fix can uncover the errors of the code after it, as the ones of the
+// type analysis after the missing semicolons, so the circuit is compiled
+// again after each round of fixes
+const MAX_ROUNDS: usize = 10;
+
+// Compiles the circuit and applies the suggestions of its errors and
+// warnings to the files, until the compilation has no suggestions that apply
+pub fn fix(mut user_input: Input) -> Result<(), ()> {
+    let mut round = 0;
+    loop {
+        Report::start_fixes();
+        let result = compile_program(&mut user_input);
+        let fixes = Report::take_fixes();
+        round += 1;
+        let mut fixed = 0;
+        for (file, (source, suggestions)) in fixes {
+            fixed += fix_file(&file, &source, suggestions)?;
+        }
+        if fixed == 0 || round == MAX_ROUNDS {
+            return result;
+        }
+        // the reports of each compilation are in their own log, and only
+        // the codes of the last one are explained
+        Report::write_sarif_log();
+        Report::take_explained_codes();
+        println!("{}", Colour::Yellow.paint("Compiling the fixed code again"));
+    }
+}
+
+// Applies the suggestions that do not overlap to the file, when it has not
+// changed since it was compiled, and returns the number of them applied
+fn fix_file(file: &str, source: &str, mut suggestions: Vec<Suggestion>) -> Result<usize, ()> {
+    match std::fs::read_to_string(file) {
+        Ok(current) if current == source => {}
+        // the builtin code, the files changed during the compilation
+        _ => return Result::Ok(0),
+    }
+    suggestions.sort_by_key(|suggestion| (suggestion.location.start, suggestion.location.end));
+    let mut applied: Vec<Suggestion> = Vec::new();
+    for suggestion in suggestions {
+        let overlaps = applied.last().is_some_and(|last| {
+            suggestion.location.start < last.location.end || suggestion.location.start == last.location.start
+        });
+        if !overlaps {
+            applied.push(suggestion);
+        }
+    }
+    let mut fixed = source.to_string();
+    for suggestion in applied.iter().rev() {
+        fixed.replace_range(suggestion.location.clone(), &suggestion.replacement);
+    }
+    if let Err(error) = std::fs::write(file, fixed) {
+        eprintln!("{}", Colour::Red.paint(format!("Could not write {}: {}", file, error)));
+        return Result::Err(());
+    }
+    for suggestion in applied.iter() {
+        let line = source[..suggestion.location.start].matches('\n').count() + 1;
+        println!("{}", Colour::Green.paint(format!("Fixed {}:{}: {}", file, line, suggestion.message)));
+    }
+    Result::Ok(applied.len())
+}
@@ -0,0 +1,106 @@
+use ansi_term::Colour;
+use formatter::FormatConfig;
+use program_structure::error_definition::Report;
+use program_structure::file_definition::FileLibrary;
+use std::path::{Path, PathBuf};
+
+pub struct FormatterConfig {
+    pub files: Vec<PathBuf>,
+    pub check: bool,
+    pub line_width: usize,
+}
+
+// The circom files of a directory and its subdirectories, in order
+pub fn circom_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), ()> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Result::Ok(());
+    }
+    let entries = std::fs::read_dir(path).map_err(|e| {
+        eprintln!("{}", Colour::Red.paint(format!("Could not read {}: {}", path.display(), e)))
+    })?;
+    let mut entries: Vec<_> =
+        entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect();
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() || entry.extension().is_some_and(|extension| extension == "circom") {
+            circom_files(&entry, files)?;
+        }
+    }
+    Result::Ok(())
+}
+
+// The code is parsed before it is formatted, so that only valid programs are
+// formatted and their errors are reported as in a compilation
+fn format_file(file: &Path, config: &FormatConfig) -> Result<(String, String), ()> {
+    let source = std::fs::read_to_string(file).map_err(|e| {
+        eprintln!("{}", Colour::Red.paint(format!("Could not read {}: {}", file.display(), e)))
+    })?;
+    let mut file_library = FileLibrary::new();
+    let file_id = file_library.add_file(file.display().to_string(), source.clone());
+    if let Err(reports) = parser::parse_definitions(&source, file_id, "bn128", &[]) {
+        Report::print_reports(&reports, &file_library);
+        return Result::Err(());
+    }
+    match formatter::format_source(&source, config) {
+        Ok(formatted) => Result::Ok((source, formatted)),
+        Err(error) => {
+            eprintln!(
+                "{}",
+                Colour::Red.paint(format!("Could not format {}: {}", file.display(), error))
+            );
+            Result::Err(())
+        }
+    }
+}
+
+// Formats the files in place, or only lists the ones that are not formatted
+// with check, which fails if there is any of them
+pub fn format_files(config: FormatterConfig) -> Result<(), ()> {
+    let mut files = Vec::new();
+    for path in &config.files {
+        circom_files(path, &mut files)?;
+    }
+    let format_config = FormatConfig { line_width: config.line_width };
+    let mut failed = false;
+    let mut unformatted = 0;
+    for file in &files {
+        let (source, formatted) = match format_file(file, &format_config) {
+            Ok(result) => result,
+            Err(()) => {
+                failed = true;
+                continue;
+            }
+        };
+        if source == formatted {
+            continue;
+        }
+        if config.check {
+            println!("{} {}", Colour::Yellow.paint("Not formatted:"), file.display());
+            unformatted += 1;
+        } else if let Err(e) = std::fs::write(file, formatted) {
+            eprintln!(
+                "{}",
+                Colour::Red.paint(format!("Could not write {}: {}", file.display(), e))
+            );
+            failed = true;
+        } else {
+            println!("{} {}", Colour::Green.paint("Formatted:"), file.display());
+        }
+    }
+    if unformatted > 0 {
+        eprintln!(
+            "{}",
+            Colour::Red.paint(format!(
+                "{} of {} files are not formatted",
+                unformatted,
+                files.len()
+            ))
+        );
+    }
+    if failed || unformatted > 0 {
+        Result::Err(())
+    } else {
+        Result::Ok(())
+    }
+}
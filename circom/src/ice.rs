@@ -0,0 +1,52 @@
+use ansi_term::Colour;
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::panic::PanicHookInfo;
+use std::sync::Mutex;
+
+// The line of the report that circom reduce looks for in the output of the
+// compilations of the reduced programs
+pub const ICE_MARKER: &str = "internal compiler error";
+
+// The phase of the compiler that is running, printed when it panics
+static STAGE: Mutex<&str> = Mutex::new("running the command");
+
+pub fn set_stage(stage: &'static str) {
+    *STAGE.lock().unwrap() = stage;
+}
+
+// Replaces the message of the panics with a report of the bug, with what is
+// needed to reproduce it and the command that shrinks the program
+pub fn install_reporter(version: &'static str) {
+    std::panic::set_hook(Box::new(move |info| report(info, version)));
+}
+
+fn report(info: &PanicHookInfo, version: &str) {
+    let message = if let Some(message) = info.payload().downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = info.payload().downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    };
+    let location = info.location().map_or("unknown".to_string(), |location| location.to_string());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let stage = *STAGE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    eprintln!("{}", Colour::Red.paint(format!("error: {}: {}", ICE_MARKER, message)));
+    eprintln!("  --> {}", location);
+    eprintln!();
+    eprintln!("note: the compiler unexpectedly panicked, this is a bug");
+    eprintln!("note: please report it at https://github.com/iden3/circom/issues with a program that reproduces it");
+    eprintln!("note: circom {} on {}-{}", version, std::env::consts::OS, std::env::consts::ARCH);
+    eprintln!("note: stage: {}", stage);
+    eprintln!("note: command: circom {}", args.join(" "));
+    if args.first().map(String::as_str) != Some("reduce") {
+        eprintln!("note: to shrink the program to a minimal reproducer, run:");
+        eprintln!("      circom reduce -- {}", args.join(" "));
+    }
+    let backtrace = Backtrace::capture();
+    if backtrace.status() == BacktraceStatus::Captured {
+        eprintln!("{}", backtrace);
+    } else {
+        eprintln!("note: run with RUST_BACKTRACE=1 to print the backtrace of the compiler");
+    }
+}
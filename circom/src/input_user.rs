@@ -1,7 +1,18 @@
+use compiler::compiler_interface::{OptimizationLevel, Pass};
+use compiler::hir::hir_export::HirFormat;
+use constraint_writers::graph_writer::GraphFormat;
+use constraint_writers::smt_writer::SmtEncoding;
+use program_structure::error_definition::MessageFormat;
+use program_structure::lint::LintLevels;
 use std::path::PathBuf;
+use crate::equivalence_user::Solver;
+use crate::docs_user::DocsFormat;
+use crate::explain_user::Explanation;
 
+#[derive(Clone)]
 pub struct Input {
     pub input_program: PathBuf,
+    pub output_path: PathBuf,
     pub out_r1cs: PathBuf,
     pub out_json_constraints: PathBuf,
     pub out_json_substitutions: PathBuf,
@@ -14,6 +25,20 @@ pub struct Input {
     pub out_c_code: PathBuf,
     pub out_c_dat: PathBuf,
     pub out_sym: PathBuf,
+    pub out_smt: PathBuf,
+    pub out_opt_report: PathBuf,
+    pub out_ir: PathBuf,
+    pub out_hir: PathBuf,
+    pub out_template_report: PathBuf,
+    pub out_component_graph: PathBuf,
+    pub out_constraint_graph: PathBuf,
+    pub out_stats: PathBuf,
+    pub out_source_map: PathBuf,
+    pub out_heatmap: PathBuf,
+    pub out_docs: PathBuf,
+    pub out_audit: PathBuf,
+    pub out_c_witness: PathBuf,
+    pub out_wasm_witness: PathBuf,
     //pub field: &'static str,
     pub c_flag: bool,
     pub wasm_flag: bool,
@@ -21,6 +46,30 @@ pub struct Input {
     pub no_asm_flag: bool,
     pub r1cs_flag: bool,
     pub sym_flag: bool,
+    pub smt_flag: bool,
+    pub smt_encoding: SmtEncoding,
+    pub template_report_flag: bool,
+    pub component_graph_flag: bool,
+    pub component_graph_format: GraphFormat,
+    pub constraint_graph_flag: bool,
+    pub constraint_graph_format: GraphFormat,
+    pub constraint_graph_by_template: bool,
+    pub hir_flag: bool,
+    pub hir_format: HirFormat,
+    pub stats_flag: bool,
+    pub snapshot_flag: bool,
+    pub check_snapshots_flag: bool,
+    pub watch_flag: bool,
+    pub fix_flag: bool,
+    pub template_call: Option<String>,
+    pub snapshot_file: PathBuf,
+    pub diff_backends: Option<PathBuf>,
+    pub fuzz_flag: bool,
+    pub bench_flag: bool,
+    pub trace_flag: bool,
+    pub profile_flag: bool,
+    pub source_map_flag: bool,
+    pub heatmap_flag: bool,
     pub json_constraint_flag: bool,
     pub json_substitution_flag: bool,
     pub main_inputs_flag: bool,
@@ -31,88 +80,231 @@ pub struct Input {
     pub constraint_assert_disabled_flag: bool,
     pub flag_old_heuristics: bool,
     pub inspect_constraints_flag: bool,
+    pub inspect_witness_flag: bool,
+    pub inspect_determinism_flag: bool,
+    pub deny_unconstrained_flag: bool,
+    pub suggest_parallel_flag: bool,
     pub no_rounds: usize,
+    pub function_steps: usize,
+    pub template_recursion: usize,
     pub flag_verbose: bool,
     pub flag_no_init: bool,
+    pub flag_enforce_tags: bool,
+    pub lint_levels: LintLevels,
+    pub message_format: MessageFormat,
     pub prime: String,
-    pub link_libraries : Vec<PathBuf>
+    pub prime_flag: bool,
+    pub ir_optimization_level: OptimizationLevel,
+    pub disabled_passes: Vec<Pass>,
+    pub pass_timing_flag: bool,
+    pub opt_report_flag: bool,
+    pub verify_ir_flag: bool,
+    pub lazy_components_flag: bool,
+    pub ir_cache: Option<String>,
+    pub emit_ir_flag: bool,
+    pub load_ir: Option<String>,
+    pub link_libraries : Vec<PathBuf>,
+    pub features: Vec<String>,
+    pub symex_flag: bool,
+    pub symex_max_paths: usize,
+    pub symex_paths_flag: bool,
+    pub symex_taint_flag: bool,
+    pub explanation: Option<Explanation>,
+    pub docs_format: Option<DocsFormat>,
+    pub audit_flag: bool,
 }
 
 
-const R1CS: &'static str = "r1cs";
-const WAT: &'static str = "wat";
-const WASM: &'static str = "wasm";
-const CPP: &'static str = "cpp";
-const JS: &'static str = "js";
-const DAT: &'static str = "dat";
-const SYM: &'static str = "sym";
-const JSON: &'static str = "json";
+const R1CS: &str = "r1cs";
+const WAT: &str = "wat";
+const WASM: &str = "wasm";
+const CPP: &str = "cpp";
+const JS: &str = "js";
+const DAT: &str = "dat";
+const SYM: &str = "sym";
+const SMT: &str = "smt2";
+const JSON: &str = "json";
+const MD: &str = "md";
+const HTML: &str = "html";
+const IR: &str = "ir";
+const WTNS: &str = "wtns";
 
 
 impl Input {
     pub fn new() -> Result<Input, ()> {
-        use ansi_term::Colour;
-        use input_processing::SimplificationStyle;
-        let matches = input_processing::view();
+        use input_processing::{Command, SimplificationStyle};
+        let (matches, command) = input_processing::view(input_processing::get_arguments()?);
         let input = input_processing::get_input(&matches)?;
-        let mut file_name = input.file_stem().unwrap().to_str().unwrap().to_string();
+        let file_name = input.file_stem().unwrap().to_str().unwrap().to_string();
         let output_path = input_processing::get_output_path(&matches)?;
 
-        let c_flag = input_processing::get_c(&matches);
+        // the differential testing of the backends compiles both of them,
+        // with the names of the signals of the sym file
+        let diff_backends = input_processing::get_diff_backends(&matches)?;
+        let c_flag = input_processing::get_c(&matches) || diff_backends.is_some();
+        // the fuzzing harness is written with the C++ code, and it checks the
+        // witnesses against the constraints of the r1cs file
+        let fuzz_flag = input_processing::get_fuzz(&matches);
+        // and so is the benchmark of the witness generator
+        let bench_flag = input_processing::get_bench(&matches);
+        // the traces name the signals with the sym file
+        let trace_flag = input_processing::get_trace(&matches);
 
-        if c_flag && (file_name == "main" || file_name == "fr" || file_name == "calcwit"){
-            println!("{}", Colour::Yellow.paint(format!("The name {} is reserved in Circom when using de --c flag. The files generated for your circuit will use the name {}_c instead of {}.", file_name, file_name, file_name)));
-            file_name = format!("{}_c", file_name)
-        };
-        let output_c_path = Input::build_folder(&output_path, &file_name, CPP);
-        let output_js_path = Input::build_folder(&output_path, &file_name, JS);
         let o_style = input_processing::get_simplification_style(&matches)?;
+        let inspect_level = input_processing::get_inspect_level(&matches)?;
+        let smt_encoding = input_processing::get_smt(&matches)?;
+        let component_graph_format = input_processing::get_component_graph(&matches)?;
+        let constraint_graph_format = input_processing::get_constraint_graph(&matches)?;
+        let hir_format = input_processing::get_emit_hir(&matches)?;
         let link_libraries = input_processing::get_link_libraries(&matches);
-        Result::Ok(Input {
+        let mut input_info = Input {
             //field: P_BN128,
             input_program: input,
-            out_r1cs: Input::build_output(&output_path, &file_name, R1CS),
-            out_wat_code: Input::build_output(&output_js_path, &file_name, WAT),
-            out_wasm_code: Input::build_output(&output_js_path, &file_name, WASM),
-	        out_js_folder: output_js_path.clone(),
-	        out_wasm_name: file_name.clone(),
-	        out_c_folder: output_c_path.clone(),
-	        out_c_run_name: file_name.clone(),
-            out_c_code: Input::build_output(&output_c_path, &file_name, CPP),
-            out_c_dat: Input::build_output(&output_c_path, &file_name, DAT),
-            out_sym: Input::build_output(&output_path, &file_name, SYM),
-            out_json_constraints: Input::build_output(
-                &output_path,
-                &format!("{}_constraints", file_name),
-                JSON,
-            ),
-            out_json_substitutions: Input::build_output(
-                &output_path,
-                &format!("{}_substitutions", file_name),
-                JSON,
-            ),
+            output_path,
+            out_r1cs: PathBuf::new(),
+            out_wat_code: PathBuf::new(),
+            out_wasm_code: PathBuf::new(),
+            out_js_folder: PathBuf::new(),
+            out_wasm_name: String::new(),
+            out_c_folder: PathBuf::new(),
+            out_c_run_name: String::new(),
+            out_c_code: PathBuf::new(),
+            out_c_dat: PathBuf::new(),
+            out_sym: PathBuf::new(),
+            out_smt: PathBuf::new(),
+            out_json_constraints: PathBuf::new(),
+            out_json_substitutions: PathBuf::new(),
+            out_opt_report: PathBuf::new(),
+            out_ir: PathBuf::new(),
+            out_hir: PathBuf::new(),
+            out_template_report: PathBuf::new(),
+            out_component_graph: PathBuf::new(),
+            out_constraint_graph: PathBuf::new(),
+            out_stats: PathBuf::new(),
+            out_source_map: PathBuf::new(),
+            out_heatmap: PathBuf::new(),
+            out_docs: PathBuf::new(),
+            out_audit: PathBuf::new(),
+            out_c_witness: PathBuf::new(),
+            out_wasm_witness: PathBuf::new(),
             wat_flag:input_processing::get_wat(&matches),
-            wasm_flag: input_processing::get_wasm(&matches),
-            c_flag: c_flag,
+            wasm_flag: input_processing::get_wasm(&matches) || diff_backends.is_some(),
+            c_flag: c_flag || fuzz_flag || bench_flag,
             no_asm_flag:input_processing::get_no_asm(&matches),
-            r1cs_flag: input_processing::get_r1cs(&matches),
-            sym_flag: input_processing::get_sym(&matches),
+            r1cs_flag: input_processing::get_r1cs(&matches) || fuzz_flag,
+            sym_flag: input_processing::get_sym(&matches) || diff_backends.is_some() || trace_flag,
+            smt_flag: smt_encoding.is_some(),
+            smt_encoding: smt_encoding.unwrap_or(SmtEncoding::FiniteField),
+            template_report_flag: input_processing::get_template_report(&matches),
+            component_graph_flag: component_graph_format.is_some(),
+            component_graph_format: component_graph_format.unwrap_or(GraphFormat::Json),
+            constraint_graph_flag: constraint_graph_format.is_some(),
+            constraint_graph_format: constraint_graph_format.unwrap_or(GraphFormat::Json),
+            constraint_graph_by_template: input_processing::get_constraint_graph_by_template(&matches),
+            hir_flag: hir_format.is_some(),
+            hir_format: hir_format.unwrap_or(HirFormat::Text),
+            stats_flag: input_processing::get_stats(&matches),
+            snapshot_flag: input_processing::get_snapshot(&matches),
+            check_snapshots_flag: input_processing::get_check_snapshots(&matches),
+            watch_flag: input_processing::get_watch(&matches),
+            fix_flag: input_processing::get_fix(&matches),
+            template_call: input_processing::get_template_call(&matches),
+            snapshot_file: input_processing::get_snapshot_file(&matches),
+            diff_backends,
+            fuzz_flag,
+            bench_flag,
+            trace_flag,
+            profile_flag: input_processing::get_profile(&matches),
+            source_map_flag: input_processing::get_source_map(&matches),
+            heatmap_flag: input_processing::get_heatmap(&matches),
             main_inputs_flag: input_processing::get_main_inputs_log(&matches),
             json_constraint_flag: input_processing::get_json_constraints(&matches),
             json_substitution_flag: input_processing::get_json_substitutions(&matches),
             print_ir_flag: input_processing::get_ir(&matches),
             no_rounds: if let SimplificationStyle::O2(r) = o_style { r } else { 0 },
+            function_steps: input_processing::get_function_steps(&matches)?,
+            template_recursion: input_processing::get_template_recursion(&matches)?,
             fast_flag: o_style == SimplificationStyle::O0,
             reduced_simplification_flag: o_style == SimplificationStyle::O1,
             parallel_simplification_flag: input_processing::get_parallel_simplification(&matches),
             constraint_assert_disabled_flag: input_processing::get_constraint_assert_disabled(&matches),
-            inspect_constraints_flag: input_processing::get_inspect_constraints(&matches),
+            inspect_constraints_flag: inspect_level >= 1,
+            inspect_witness_flag: inspect_level == 2,
+            inspect_determinism_flag: inspect_level == 3,
+            deny_unconstrained_flag: input_processing::get_deny_unconstrained(&matches),
+            suggest_parallel_flag: input_processing::get_suggest_parallel(&matches),
             flag_old_heuristics: input_processing::get_flag_old_heuristics(&matches),
             flag_verbose: input_processing::get_flag_verbose(&matches), 
             flag_no_init: input_processing::get_flag_no_init(&matches), 
+            flag_enforce_tags: input_processing::get_flag_enforce_tags(&matches),
+            lint_levels: input_processing::get_lint_levels(&matches)?,
+            message_format: input_processing::get_message_format(&matches),
             prime: input_processing::get_prime(&matches)?,
-            link_libraries
-        })
+            prime_flag: input_processing::get_prime_flag(&matches),
+            ir_optimization_level: input_processing::get_ir_optimization_level(&matches)?,
+            disabled_passes: input_processing::get_disabled_passes(&matches)?,
+            pass_timing_flag: input_processing::get_pass_timing(&matches),
+            opt_report_flag: input_processing::get_opt_report(&matches),
+            verify_ir_flag: input_processing::get_verify_ir(&matches),
+            lazy_components_flag: input_processing::get_lazy_components(&matches),
+            ir_cache: input_processing::get_ir_cache(&matches),
+            emit_ir_flag: input_processing::get_emit_ir(&matches),
+            load_ir: input_processing::get_load_ir(&matches),
+            link_libraries,
+            features: input_processing::get_features(&matches),
+            symex_flag: command == Command::Symex,
+            symex_max_paths: input_processing::get_symex_max_paths(&matches)?,
+            symex_paths_flag: input_processing::get_symex_paths(&matches),
+            symex_taint_flag: input_processing::get_symex_taint(&matches),
+            explanation: if command == Command::Explain { Some(input_processing::get_explanation(&matches)?) } else { None },
+            docs_format: if command == Command::Docs { Some(input_processing::get_docs_format(&matches)) } else { None },
+            audit_flag: command == Command::Audit,
+        };
+        input_info.set_output_name(&file_name);
+        Result::Ok(input_info)
+    }
+
+    // The files of the circuit are named after the input file, or after the
+    // main component when the program has several of them
+    pub fn set_output_name(&mut self, file_name: &str) {
+        use ansi_term::Colour;
+        let mut file_name = file_name.to_string();
+        if self.c_flag && (file_name == "main" || file_name == "fr" || file_name == "calcwit"){
+            println!("{}", Colour::Yellow.paint(format!("The name {} is reserved in Circom when using de --c flag. The files generated for your circuit will use the name {}_c instead of {}.", file_name, file_name, file_name)));
+            file_name = format!("{}_c", file_name)
+        };
+        let output_path = &self.output_path;
+        let output_c_path = Input::build_folder(output_path, &file_name, CPP);
+        let output_js_path = Input::build_folder(output_path, &file_name, JS);
+        self.out_r1cs = Input::build_output(output_path, &file_name, R1CS);
+        self.out_wat_code = Input::build_output(&output_js_path, &file_name, WAT);
+        self.out_wasm_code = Input::build_output(&output_js_path, &file_name, WASM);
+        self.out_js_folder = output_js_path;
+        self.out_wasm_name = file_name.clone();
+        self.out_c_code = Input::build_output(&output_c_path, &file_name, CPP);
+        self.out_c_dat = Input::build_output(&output_c_path, &file_name, DAT);
+        self.out_c_folder = output_c_path;
+        self.out_c_run_name = file_name.clone();
+        self.out_sym = Input::build_output(output_path, &file_name, SYM);
+        self.out_smt = Input::build_output(output_path, &file_name, SMT);
+        self.out_json_constraints = Input::build_output(output_path, &format!("{}_constraints", file_name), JSON);
+        self.out_json_substitutions = Input::build_output(output_path, &format!("{}_substitutions", file_name), JSON);
+        self.out_opt_report = Input::build_output(output_path, &format!("{}_opt_report", file_name), JSON);
+        self.out_ir = Input::build_output(output_path, &file_name, IR);
+        self.out_hir = Input::build_output(output_path, &format!("{}_hir", file_name), self.hir_format.extension());
+        self.out_template_report = Input::build_output(output_path, &format!("{}_template_report", file_name), JSON);
+        let graph_extension = self.component_graph_format.extension();
+        self.out_component_graph = Input::build_output(output_path, &format!("{}_component_graph", file_name), graph_extension);
+        let graph_extension = self.constraint_graph_format.extension();
+        self.out_constraint_graph = Input::build_output(output_path, &format!("{}_constraint_graph", file_name), graph_extension);
+        self.out_stats = Input::build_output(output_path, &format!("{}_stats", file_name), JSON);
+        self.out_source_map = Input::build_output(output_path, &format!("{}_source_map", file_name), JSON);
+        self.out_heatmap = Input::build_output(output_path, &format!("{}_heatmap", file_name), HTML);
+        let docs_extension = if self.docs_format == Some(DocsFormat::Html) { HTML } else { MD };
+        self.out_docs = Input::build_output(output_path, &format!("{}_docs", file_name), docs_extension);
+        self.out_audit = Input::build_output(output_path, &format!("{}_audit", file_name), JSON);
+        self.out_c_witness = Input::build_output(output_path, &format!("{}_c", file_name), WTNS);
+        self.out_wasm_witness = Input::build_output(output_path, &format!("{}_wasm", file_name), WTNS);
     }
 
     fn build_folder(output_path: &PathBuf, filename: &str, ext: &str) -> PathBuf {
@@ -132,6 +324,10 @@ impl Input {
         &self.link_libraries
     }
 
+    pub fn features(&self) -> &[String] {
+        &self.features
+    }
+
     pub fn input_file(&self) -> &str {
         &self.input_program.to_str().unwrap()
     }
@@ -141,6 +337,9 @@ impl Input {
     pub fn sym_file(&self) -> &str {
         self.out_sym.to_str().unwrap()
     }
+    pub fn smt_file(&self) -> &str {
+        self.out_smt.to_str().unwrap()
+    }
     pub fn wat_file(&self) -> &str {
         self.out_wat_code.to_str().unwrap()
     }
@@ -203,18 +402,158 @@ impl Input {
     pub fn sym_flag(&self) -> bool {
         self.sym_flag
     }
+    pub fn smt_flag(&self) -> bool {
+        self.smt_flag
+    }
+    pub fn smt_encoding(&self) -> SmtEncoding {
+        self.smt_encoding
+    }
+    pub fn template_report_flag(&self) -> bool {
+        self.template_report_flag
+    }
+    pub fn template_report_file(&self) -> &str {
+        self.out_template_report.to_str().unwrap()
+    }
+    pub fn component_graph_flag(&self) -> bool {
+        self.component_graph_flag
+    }
+    pub fn component_graph_format(&self) -> GraphFormat {
+        self.component_graph_format
+    }
+    pub fn component_graph_file(&self) -> &str {
+        self.out_component_graph.to_str().unwrap()
+    }
+    pub fn constraint_graph_flag(&self) -> bool {
+        self.constraint_graph_flag
+    }
+    pub fn constraint_graph_format(&self) -> GraphFormat {
+        self.constraint_graph_format
+    }
+    pub fn constraint_graph_by_template(&self) -> bool {
+        self.constraint_graph_by_template
+    }
+    pub fn constraint_graph_file(&self) -> &str {
+        self.out_constraint_graph.to_str().unwrap()
+    }
+    pub fn stats_flag(&self) -> bool {
+        self.stats_flag
+    }
+    pub fn stats_file(&self) -> &str {
+        self.out_stats.to_str().unwrap()
+    }
+    pub fn snapshot_flag(&self) -> bool {
+        self.snapshot_flag
+    }
+    pub fn check_snapshots_flag(&self) -> bool {
+        self.check_snapshots_flag
+    }
+    pub fn watch_flag(&self) -> bool {
+        self.watch_flag
+    }
+    pub fn fix_flag(&self) -> bool {
+        self.fix_flag
+    }
+    pub fn template_call(&self) -> Option<&str> {
+        self.template_call.as_deref()
+    }
+    // The name of the main component of the template given with --template,
+    // as Num2Bits_8 for Num2Bits(8), after which its files are named
+    pub fn template_main(&self) -> Option<String> {
+        let call = self.template_call.as_ref()?;
+        let name: String = call.chars().map(|c| if c.is_alphanumeric() { c } else { '_' }).collect();
+        let name = name.split('_').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("_");
+        Some(name)
+    }
+    pub fn snapshot_file(&self) -> &PathBuf {
+        &self.snapshot_file
+    }
+    pub fn diff_backends(&self) -> Option<&PathBuf> {
+        self.diff_backends.as_ref()
+    }
+    pub fn fuzz_flag(&self) -> bool {
+        self.fuzz_flag
+    }
+    pub fn bench_flag(&self) -> bool {
+        self.bench_flag
+    }
+    pub fn trace_flag(&self) -> bool {
+        self.trace_flag
+    }
+    pub fn profile_flag(&self) -> bool {
+        self.profile_flag
+    }
+    pub fn c_witness_file(&self) -> &str {
+        self.out_c_witness.to_str().unwrap()
+    }
+    pub fn wasm_witness_file(&self) -> &str {
+        self.out_wasm_witness.to_str().unwrap()
+    }
+    // The name of the files of the circuit, which names it in the snapshot
+    pub fn output_name(&self) -> &str {
+        &self.out_wasm_name
+    }
+    // The files of the circuit that the compilation writes, by their kind
+    pub fn generated_files(&self) -> Vec<(&'static str, String)> {
+        let files = [
+            ("r1cs", self.r1cs_flag(), self.r1cs_file()),
+            ("sym", self.sym_flag(), self.sym_file()),
+            ("smt2", self.smt_flag(), self.smt_file()),
+            ("wasm", self.wasm_flag(), self.wasm_file()),
+            ("wat", self.wat_flag(), self.wat_file()),
+            ("cpp", self.c_flag(), self.c_file()),
+            ("dat", self.c_flag(), self.dat_file()),
+        ];
+        files.iter().filter(|(_, flag, _)| *flag).map(|(kind, _, file)| (*kind, file.to_string())).collect()
+    }
+    pub fn source_map_flag(&self) -> bool {
+        self.source_map_flag
+    }
+    pub fn source_map_file(&self) -> &str {
+        self.out_source_map.to_str().unwrap()
+    }
+    pub fn heatmap_flag(&self) -> bool {
+        self.heatmap_flag
+    }
+    pub fn heatmap_file(&self) -> &str {
+        self.out_heatmap.to_str().unwrap()
+    }
     pub fn print_ir_flag(&self) -> bool {
         self.print_ir_flag
     }
     pub fn inspect_constraints_flag(&self) -> bool {
         self.inspect_constraints_flag
     }
+
+    pub fn inspect_witness_flag(&self) -> bool {
+        self.inspect_witness_flag
+    }
+
+    pub fn inspect_determinism_flag(&self) -> bool {
+        self.inspect_determinism_flag
+    }
+
+    pub fn deny_unconstrained_flag(&self) -> bool {
+        self.deny_unconstrained_flag
+    }
+
+    pub fn suggest_parallel_flag(&self) -> bool {
+        self.suggest_parallel_flag
+    }
     pub fn flag_verbose(&self) -> bool {
         self.flag_verbose
     }
     pub fn flag_no_init(&self) -> bool {
         self.flag_no_init
     }
+    pub fn flag_enforce_tags(&self) -> bool {
+        self.flag_enforce_tags
+    }
+    pub fn lint_levels(&self) -> &LintLevels {
+        &self.lint_levels
+    }
+    pub fn message_format(&self) -> MessageFormat {
+        self.message_format
+    }
     pub fn reduced_simplification_flag(&self) -> bool {
         self.reduced_simplification_flag
     }
@@ -233,13 +572,382 @@ impl Input {
     pub fn prime(&self) -> String{
         self.prime.clone()
     }
+    pub fn prime_flag(&self) -> bool {
+        self.prime_flag
+    }
+    pub fn function_steps(&self) -> usize {
+        self.function_steps
+    }
+    pub fn template_recursion(&self) -> usize {
+        self.template_recursion
+    }
+    pub fn ir_optimization_level(&self) -> OptimizationLevel {
+        self.ir_optimization_level
+    }
+    pub fn disabled_passes(&self) -> Vec<Pass> {
+        self.disabled_passes.clone()
+    }
+    pub fn pass_timing_flag(&self) -> bool {
+        self.pass_timing_flag
+    }
+    pub fn opt_report_flag(&self) -> bool {
+        self.opt_report_flag
+    }
+    pub fn verify_ir_flag(&self) -> bool {
+        self.verify_ir_flag
+    }
+    pub fn lazy_components_flag(&self) -> bool {
+        self.lazy_components_flag
+    }
+    pub fn opt_report_file(&self) -> &str {
+        self.out_opt_report.to_str().unwrap()
+    }
+    pub fn ir_cache(&self) -> Option<String> {
+        self.ir_cache.clone()
+    }
+    pub fn emit_ir_flag(&self) -> bool {
+        self.emit_ir_flag
+    }
+    pub fn ir_file(&self) -> &str {
+        self.out_ir.to_str().unwrap()
+    }
+    pub fn load_ir(&self) -> Option<String> {
+        self.load_ir.clone()
+    }
+    pub fn hir_flag(&self) -> bool {
+        self.hir_flag
+    }
+    pub fn hir_format(&self) -> HirFormat {
+        self.hir_format
+    }
+    pub fn hir_file(&self) -> &str {
+        self.out_hir.to_str().unwrap()
+    }
+    pub fn symex_flag(&self) -> bool {
+        self.symex_flag
+    }
+    pub fn symex_max_paths(&self) -> usize {
+        self.symex_max_paths
+    }
+    pub fn symex_paths_flag(&self) -> bool {
+        self.symex_paths_flag
+    }
+    pub fn symex_taint_flag(&self) -> bool {
+        self.symex_taint_flag
+    }
+    pub fn explanation(&self) -> Option<Explanation> {
+        self.explanation.clone()
+    }
+    pub fn docs_format(&self) -> Option<DocsFormat> {
+        self.docs_format
+    }
+    pub fn docs_file(&self) -> &str {
+        self.out_docs.to_str().unwrap()
+    }
+    pub fn audit_flag(&self) -> bool {
+        self.audit_flag
+    }
+    pub fn audit_file(&self) -> &str {
+        self.out_audit.to_str().unwrap()
+    }
+}
+// The option `circom --explain CODE`, which prints the explanation of the
+// code instead of compiling a circuit
+pub struct ExplainCodeInput {
+    pub code: String,
+}
+
+impl ExplainCodeInput {
+    pub fn new() -> Option<ExplainCodeInput> {
+        input_processing::get_explain_code().map(|code| ExplainCodeInput { code })
+    }
+}
+
+// The options of `circom equiv`, which reads two compiled circuits instead
+// of compiling one
+pub struct EquivalenceInput {
+    pub old_r1cs: PathBuf,
+    pub new_r1cs: PathBuf,
+    pub max_diff: usize,
+    pub solver: Option<Solver>,
+}
+
+impl EquivalenceInput {
+    pub fn new() -> Result<Option<EquivalenceInput>, ()> {
+        let matches = input_processing::equivalence_matches();
+        let matches = match &matches {
+            Some(matches) => matches,
+            None => return Result::Ok(None),
+        };
+        Result::Ok(Some(EquivalenceInput {
+            old_r1cs: input_processing::get_r1cs_file(matches, "old_r1cs")?,
+            new_r1cs: input_processing::get_r1cs_file(matches, "new_r1cs")?,
+            max_diff: input_processing::get_max_diff(matches)?,
+            solver: input_processing::get_solver(matches),
+        }))
+    }
+}
+
+// The options of `circom lsp`, which runs the language server instead of
+// compiling a circuit
+pub struct LanguageServerInput {
+    pub link_libraries: Vec<PathBuf>,
+    pub prime: String,
+}
+
+impl LanguageServerInput {
+    pub fn new() -> Result<Option<LanguageServerInput>, ()> {
+        let matches = input_processing::language_server_matches();
+        let matches = match &matches {
+            Some(matches) => matches,
+            None => return Result::Ok(None),
+        };
+        Result::Ok(Some(LanguageServerInput {
+            link_libraries: input_processing::get_link_libraries(matches),
+            prime: input_processing::get_prime(matches)?,
+        }))
+    }
+}
+
+// The options of `circom fmt`, which formats the given files or the circom
+// files of the given directories
+pub struct FormatInput {
+    pub files: Vec<PathBuf>,
+    pub check: bool,
+    pub line_width: usize,
+}
+
+impl FormatInput {
+    pub fn new() -> Result<Option<FormatInput>, ()> {
+        let matches = input_processing::format_matches();
+        let matches = match &matches {
+            Some(matches) => matches,
+            None => return Result::Ok(None),
+        };
+        Result::Ok(Some(FormatInput {
+            files: input_processing::get_format_files(matches)?,
+            check: matches.is_present("check"),
+            line_width: input_processing::get_line_width(matches)?,
+        }))
+    }
+}
+
+// The options of `circom repl`, which evaluates the expressions and runs the
+// templates typed by the user with the definitions of the given files
+pub struct ReplInput {
+    pub files: Vec<PathBuf>,
+    pub link_libraries: Vec<PathBuf>,
+    pub prime: String,
+}
+
+impl ReplInput {
+    pub fn new() -> Result<Option<ReplInput>, ()> {
+        let matches = input_processing::repl_matches();
+        let matches = match &matches {
+            Some(matches) => matches,
+            None => return Result::Ok(None),
+        };
+        Result::Ok(Some(ReplInput {
+            files: input_processing::get_repl_files(matches)?,
+            link_libraries: input_processing::get_link_libraries(matches),
+            prime: input_processing::get_prime(matches)?,
+        }))
+    }
+}
+
+// The options of `circom debug`, which computes the witness of the main
+// component of a file for the given inputs, stopping where the user asks for
+pub struct DebugInput {
+    pub file: PathBuf,
+    pub inputs: PathBuf,
+    pub link_libraries: Vec<PathBuf>,
+    pub prime: String,
+}
+
+impl DebugInput {
+    pub fn new() -> Result<Option<DebugInput>, ()> {
+        let matches = input_processing::debug_matches();
+        let matches = match &matches {
+            Some(matches) => matches,
+            None => return Result::Ok(None),
+        };
+        Result::Ok(Some(DebugInput {
+            file: input_processing::get_existing_file(matches, "input")?,
+            inputs: input_processing::get_existing_file(matches, "inputs")?,
+            link_libraries: input_processing::get_link_libraries(matches),
+            prime: input_processing::get_prime(matches)?,
+        }))
+    }
+}
+
+// The options of `circom test`, which runs the tests of the given files, or
+// of the circom files in the given directories
+pub struct TestInput {
+    pub files: Vec<PathBuf>,
+    pub link_libraries: Vec<PathBuf>,
+    pub prime: String,
+}
+
+impl TestInput {
+    pub fn new() -> Result<Option<TestInput>, ()> {
+        let matches = input_processing::test_matches();
+        let matches = match &matches {
+            Some(matches) => matches,
+            None => return Result::Ok(None),
+        };
+        Result::Ok(Some(TestInput {
+            files: input_processing::get_format_files(matches)?,
+            link_libraries: input_processing::get_link_libraries(matches),
+            prime: input_processing::get_prime(matches)?,
+        }))
+    }
 }
+
+// The options of `circom mutate`, which runs the tests of the given files
+// with their constraints weakened
+pub struct MutateInput {
+    pub files: Vec<PathBuf>,
+    pub link_libraries: Vec<PathBuf>,
+    pub prime: String,
+}
+
+impl MutateInput {
+    pub fn new() -> Result<Option<MutateInput>, ()> {
+        let matches = input_processing::mutate_matches();
+        let matches = match &matches {
+            Some(matches) => matches,
+            None => return Result::Ok(None),
+        };
+        Result::Ok(Some(MutateInput {
+            files: input_processing::get_format_files(matches)?,
+            link_libraries: input_processing::get_link_libraries(matches),
+            prime: input_processing::get_prime(matches)?,
+        }))
+    }
+}
+
+// The options of `circom proptest`, which checks the ensure statements of the
+// templates of the given files with random inputs
+pub struct ProptestInput {
+    pub files: Vec<PathBuf>,
+    pub link_libraries: Vec<PathBuf>,
+    pub prime: String,
+    pub cases: usize,
+    pub seed: Option<u64>,
+    pub templates: Vec<String>,
+}
+
+impl ProptestInput {
+    pub fn new() -> Result<Option<ProptestInput>, ()> {
+        let matches = input_processing::proptest_matches();
+        let matches = match &matches {
+            Some(matches) => matches,
+            None => return Result::Ok(None),
+        };
+        Result::Ok(Some(ProptestInput {
+            files: input_processing::get_format_files(matches)?,
+            link_libraries: input_processing::get_link_libraries(matches),
+            prime: input_processing::get_prime(matches)?,
+            cases: input_processing::get_cases(matches)?,
+            seed: input_processing::get_seed(matches)?,
+            templates: matches.values_of("template").into_iter().flatten().map(|call| call.to_string()).collect(),
+        }))
+    }
+}
+
+// The options of `circom reduce`, which shrinks the program of a compilation
+// that makes the compiler panic
+pub struct ReduceInput {
+    pub command: Vec<String>,
+    pub input: PathBuf,
+    pub output: Option<PathBuf>,
+}
+
+impl ReduceInput {
+    pub fn new() -> Result<Option<ReduceInput>, ()> {
+        let matches = input_processing::reduce_matches();
+        let matches = match &matches {
+            Some(matches) => matches,
+            None => return Result::Ok(None),
+        };
+        let command: Vec<String> = matches.values_of("command").unwrap().map(str::to_string).collect();
+        let (compilation, _) = input_processing::view(std::iter::once("circom".to_string()).chain(command.clone()).collect());
+        Result::Ok(Some(ReduceInput {
+            input: input_processing::get_input(&compilation)?,
+            command,
+            output: matches.value_of("output").map(PathBuf::from),
+        }))
+    }
+}
+
 mod input_processing {
     use ansi_term::Colour;
-    use clap::{App, Arg, ArgMatches};
+    use clap::{App, AppSettings, Arg, ArgMatches, ErrorKind, SubCommand};
+    use compiler::compiler_interface::{OptimizationLevel, Pass};
+    use compiler::hir::hir_export::HirFormat;
+    use constraint_writers::graph_writer::GraphFormat;
+    use constraint_writers::smt_writer::SmtEncoding;
+    use program_structure::error_definition::MessageFormat;
+    use program_structure::lint::{is_lint, lint_names, LintLevel, LintLevels};
     use std::path::{Path, PathBuf};
+    use crate::docs_user::DocsFormat;
+    use crate::equivalence_user::Solver;
+    use crate::explain_user::Explanation;
+    use crate::manifest::{Manifest, MANIFEST};
     use crate::VERSION;
 
+    const SIMPLIFICATION: [&str; 4] = ["no_simplification", "reduced_simplification", "full_simplification", "simplification_rounds"];
+
+    fn manifest_error<T>(message: String) -> Result<T, ()> {
+        eprintln!("{}", Colour::Red.paint(format!("invalid {}: {}", MANIFEST, message)));
+        Result::Err(())
+    }
+
+    // The arguments of the command line, completed with the main file, the
+    // options and the libraries of the manifest of the current directory.
+    // The options given in the command line take precedence
+    pub fn get_arguments() -> Result<Vec<String>, ()> {
+        let mut args: Vec<String> = std::env::args().collect();
+        let manifest = match Manifest::find() {
+            Ok(Some(manifest)) => manifest,
+            Ok(None) => return Ok(args),
+            Err(e) => return manifest_error(e),
+        };
+        let (user, _) = command_matches(parse(&args).unwrap_or_else(|e| e.exit()));
+        let user_simplification = SIMPLIFICATION.iter().any(|name| user.occurrences_of(name) > 0);
+        for option in manifest.options() {
+            let mut probe = args.clone();
+            probe.push(option.clone());
+            match parse(&probe) {
+                Ok(matches) => {
+                    let (matches, _) = command_matches(matches);
+                    let simplification = SIMPLIFICATION.iter().any(|name| matches.occurrences_of(name) > user.occurrences_of(name));
+                    if !(simplification && user_simplification) {
+                        args.push(option);
+                    }
+                }
+                // the option is also given in the command line
+                Err(e) if e.kind == ErrorKind::UnexpectedMultipleUsage => {}
+                Err(e) => {
+                    return manifest_error(format!("invalid option {}: {}", option, e.message));
+                }
+            }
+        }
+        match manifest.libraries() {
+            Ok(libraries) => {
+                for library in libraries {
+                    args.push("-l".to_string());
+                    args.push(library.display().to_string());
+                }
+            }
+            Err(e) => return manifest_error(e),
+        }
+        if let Some(main) = manifest.main().filter(|_| user.occurrences_of("input") == 0) {
+            args.push(main.display().to_string());
+        }
+        Ok(args)
+    }
+
     pub fn get_input(matches: &ArgMatches) -> Result<PathBuf, ()> {
         let route = Path::new(matches.value_of("input").unwrap()).to_path_buf();
         if route.is_file() {
@@ -295,6 +1003,123 @@ mod input_processing {
         matches.is_present("print_sym")
     }
 
+    // --smt2 writes the constraints over the finite fields of SMT-LIB, and
+    // --smt2=int over the integers modulo the prime
+    pub fn get_smt(matches: &ArgMatches) -> Result<Option<SmtEncoding>, ()> {
+        match matches.value_of("print_smt") {
+            _ if !matches.is_present("print_smt") => Ok(None),
+            None | Some("ff") => Ok(Some(SmtEncoding::FiniteField)),
+            Some("int") => Ok(Some(SmtEncoding::Integer)),
+            Some(_) => {
+                eprintln!("{}", Colour::Red.paint("invalid SMT-LIB encoding, expected ff or int"));
+                Result::Err(())
+            }
+        }
+    }
+
+    pub fn get_template_report(matches: &ArgMatches) -> bool {
+        matches.is_present("template_report")
+    }
+
+    // --component_graph writes the graph in json, and --component_graph=dot
+    // in the format of graphviz
+    pub fn get_component_graph(matches: &ArgMatches) -> Result<Option<GraphFormat>, ()> {
+        match matches.value_of("component_graph") {
+            _ if !matches.is_present("component_graph") => Ok(None),
+            None | Some("json") => Ok(Some(GraphFormat::Json)),
+            Some("dot") => Ok(Some(GraphFormat::Dot)),
+            Some(_) => {
+                eprintln!("{}", Colour::Red.paint("invalid graph format, expected json or dot"));
+                Result::Err(())
+            }
+        }
+    }
+
+    // --constraint_graph writes the graph in json, --constraint_graph=dot in
+    // the format of graphviz and --constraint_graph=bin in binary
+    pub fn get_constraint_graph(matches: &ArgMatches) -> Result<Option<GraphFormat>, ()> {
+        match matches.value_of("constraint_graph") {
+            _ if !matches.is_present("constraint_graph") => Ok(None),
+            None | Some("json") => Ok(Some(GraphFormat::Json)),
+            Some("dot") => Ok(Some(GraphFormat::Dot)),
+            Some("bin") => Ok(Some(GraphFormat::Binary)),
+            Some(_) => {
+                eprintln!("{}", Colour::Red.paint("invalid graph format, expected json, dot or bin"));
+                Result::Err(())
+            }
+        }
+    }
+
+    pub fn get_constraint_graph_by_template(matches: &ArgMatches) -> bool {
+        matches.is_present("constraint_graph_by_template")
+    }
+
+    pub fn get_source_map(matches: &ArgMatches) -> bool {
+        matches.is_present("source_map")
+    }
+
+    pub fn get_heatmap(matches: &ArgMatches) -> bool {
+        matches.is_present("heatmap")
+    }
+
+    pub fn get_stats(matches: &ArgMatches) -> bool {
+        matches.is_present("stats")
+    }
+
+    pub fn get_snapshot(matches: &ArgMatches) -> bool {
+        matches.is_present("snapshot")
+    }
+
+    pub fn get_check_snapshots(matches: &ArgMatches) -> bool {
+        matches.is_present("check_snapshots")
+    }
+
+    pub fn get_watch(matches: &ArgMatches) -> bool {
+        matches.is_present("watch")
+    }
+
+    pub fn get_template_call(matches: &ArgMatches) -> Option<String> {
+        matches.value_of("template_call").map(str::to_string)
+    }
+
+    pub fn get_fix(matches: &ArgMatches) -> bool {
+        matches.is_present("fix")
+    }
+
+    // The snapshot is written with --snapshot, or read with --check_snapshots,
+    // in circom.snapshot of the current directory unless a file is given
+    pub fn get_snapshot_file(matches: &ArgMatches) -> PathBuf {
+        PathBuf::from(matches.value_of("snapshot").unwrap_or(crate::manifest::SNAPSHOT))
+    }
+
+    // The inputs of the witness generators compared with --diff_backends
+    pub fn get_diff_backends(matches: &ArgMatches) -> Result<Option<PathBuf>, ()> {
+        match matches.value_of("diff_backends") {
+            None => Result::Ok(None),
+            Some(file) if Path::new(file).is_file() => Result::Ok(Some(PathBuf::from(file))),
+            Some(file) => {
+                eprintln!("{}", Colour::Red.paint(format!("Input file does not exist: {}", file)));
+                Result::Err(())
+            }
+        }
+    }
+
+    pub fn get_fuzz(matches: &ArgMatches) -> bool {
+        matches.is_present("fuzz")
+    }
+
+    pub fn get_bench(matches: &ArgMatches) -> bool {
+        matches.is_present("bench")
+    }
+
+    pub fn get_trace(matches: &ArgMatches) -> bool {
+        matches.is_present("trace")
+    }
+
+    pub fn get_profile(matches: &ArgMatches) -> bool {
+        matches.is_present("profile")
+    }
+
     pub fn get_r1cs(matches: &ArgMatches) -> bool {
         matches.is_present("print_r1cs")
     }
@@ -327,11 +1152,31 @@ mod input_processing {
         matches.is_present("constraint_assert_disabled")
     }
 
+    pub fn get_deny_unconstrained(matches: &ArgMatches) -> bool {
+        matches.is_present("deny_unconstrained")
+    }
+
+    pub fn get_suggest_parallel(matches: &ArgMatches) -> bool {
+        matches.is_present("suggest_parallel")
+    }
+
     pub fn get_ir(matches: &ArgMatches) -> bool {
         matches.is_present("print_ir")
     }
-    pub fn get_inspect_constraints(matches: &ArgMatches) -> bool {
-        matches.is_present("inspect_constraints")
+    // --inspect is the level 1, --inspect=2 also checks that the signals
+    // assigned with <-- are determined by the constraints, and --inspect=3
+    // checks that all the signals are determined by the inputs
+    pub fn get_inspect_level(matches: &ArgMatches) -> Result<usize, ()> {
+        match matches.value_of("inspect_constraints") {
+            _ if !matches.is_present("inspect_constraints") => Ok(0),
+            None | Some("1") => Ok(1),
+            Some("2") => Ok(2),
+            Some("3") => Ok(3),
+            Some(_) => {
+                eprintln!("{}", Colour::Red.paint("invalid inspect level, expected 1, 2 or 3"));
+                Result::Err(())
+            }
+        }
     }
 
     pub fn get_flag_verbose(matches: &ArgMatches) -> bool {
@@ -342,6 +1187,41 @@ mod input_processing {
         matches.is_present("flag_no_init")
     }
 
+    pub fn get_flag_enforce_tags(matches: &ArgMatches) -> bool {
+        matches.is_present("flag_enforce_tags")
+    }
+
+    // The levels of the lints in the order of the command line, so the last
+    // one that selects a warning decides its level
+    pub fn get_lint_levels(matches: &ArgMatches) -> Result<LintLevels, ()> {
+        let mut levels = Vec::new();
+        for (option, level) in [("lint_allow", LintLevel::Allow), ("lint_warn", LintLevel::Warn), ("lint_deny", LintLevel::Deny)].iter() {
+            if let (Some(indices), Some(lints)) = (matches.indices_of(option), matches.values_of(option)) {
+                for (index, lint) in indices.zip(lints) {
+                    if !is_lint(lint) {
+                        eprintln!("{}", Colour::Red.paint(format!("unknown lint {}, the lints are {}", lint, lint_names().join(", "))));
+                        return Result::Err(());
+                    }
+                    levels.push((index, lint.to_string(), *level));
+                }
+            }
+        }
+        levels.sort_by_key(|(index, _, _)| *index);
+        let mut lint_levels = LintLevels::new();
+        for (_, lint, level) in levels {
+            lint_levels.set(lint, level);
+        }
+        Result::Ok(lint_levels)
+    }
+
+    pub fn get_message_format(matches: &ArgMatches) -> MessageFormat {
+        match matches.value_of("message_format") {
+            Some("json") => MessageFormat::Json,
+            Some("sarif") => MessageFormat::Sarif,
+            _ => MessageFormat::Human,
+        }
+    }
+
     pub fn get_flag_old_heuristics(matches: &ArgMatches) -> bool {
         matches.is_present("flag_old_heuristics")
     }
@@ -371,49 +1251,789 @@ mod input_processing {
         }
     }
 
-    pub fn view() -> ArgMatches<'static> {
-        App::new("circom compiler")
-            .version(VERSION)
-            .author("IDEN3")
-            .about("Compiler for the circom programming language")
-            .arg(
-                Arg::with_name("input")
-                    .multiple(false)
-                    .default_value("./circuit.circom")
-                    .help("Path to a circuit with a main component"),
-            )
-            .arg(
-                Arg::with_name("no_simplification")
-                    .long("O0")
-                    .hidden(false)
-                    .takes_value(false)
-                    .help("No simplification is applied")
-                    .display_order(420)
-            )
-            .arg(
-                Arg::with_name("reduced_simplification")
-                    .long("O1")
-                    .hidden(false)
-                    .takes_value(false)
-                    .help("Only applies signal to signal and signal to constant simplification. This is the default option")
-                    .display_order(460)
-            )
-            .arg(
-                Arg::with_name("full_simplification")
-                    .long("O2")
-                    .takes_value(false)
-                    .hidden(false)
-                    .help("Full constraint simplification")
-                    .display_order(480)
-            )
-            .arg(
-                Arg::with_name("simplification_rounds")
+    // --prime has a default value, so it is given when it appears in the command
+    pub fn get_prime_flag(matches: &ArgMatches) -> bool {
+        matches.occurrences_of("prime") > 0
+    }
+
+    pub fn get_function_steps(matches: &ArgMatches) -> Result<usize, ()> {
+        let steps = matches.value_of("function_steps").unwrap();
+        match steps.parse::<usize>() {
+            Ok(steps) if steps > 0 => Ok(steps),
+            _ => {
+                eprintln!("{}", Colour::Red.paint("invalid number of function steps"));
+                Result::Err(())
+            }
+        }
+    }
+
+    pub fn get_template_recursion(matches: &ArgMatches) -> Result<usize, ()> {
+        let instances = matches.value_of("template_recursion").unwrap();
+        match instances.parse::<usize>() {
+            Ok(instances) if instances > 0 => Ok(instances),
+            _ => {
+                eprintln!("{}", Colour::Red.paint("invalid number of template instances"));
+                Result::Err(())
+            }
+        }
+    }
+
+    pub fn get_ir_optimization_level(matches: &ArgMatches) -> Result<OptimizationLevel, ()> {
+        let level = matches.value_of("ir_optimization_level").unwrap();
+        match OptimizationLevel::from_name(level) {
+            Some(level) => Ok(level),
+            None => {
+                eprintln!("{}", Colour::Red.paint("invalid IR optimization level, expected 0, 1 or 2"));
+                Result::Err(())
+            }
+        }
+    }
+
+    pub fn get_disabled_passes(matches: &ArgMatches) -> Result<Vec<Pass>, ()> {
+        let mut passes = Vec::new();
+        if let Some(names) = matches.values_of("disabled_passes") {
+            for name in names {
+                match Pass::from_name(name) {
+                    Some(pass) => passes.push(pass),
+                    None => {
+                        let valid: Vec<&str> = Pass::ALL.iter().map(|pass| pass.name()).collect();
+                        let msg = format!("unknown pass {}, the passes are: {}", name, valid.join(", "));
+                        eprintln!("{}", Colour::Red.paint(msg));
+                        return Result::Err(());
+                    }
+                }
+            }
+        }
+        Ok(passes)
+    }
+
+    pub fn get_pass_timing(matches: &ArgMatches) -> bool {
+        matches.is_present("pass_timing")
+    }
+
+    pub fn get_opt_report(matches: &ArgMatches) -> bool {
+        matches.is_present("opt_report")
+    }
+
+    pub fn get_verify_ir(matches: &ArgMatches) -> bool {
+        matches.is_present("verify_ir")
+    }
+
+    pub fn get_lazy_components(matches: &ArgMatches) -> bool {
+        matches.is_present("lazy_components")
+    }
+
+    pub fn get_ir_cache(matches: &ArgMatches) -> Option<String> {
+        matches.value_of("ir_cache").map(String::from)
+    }
+
+    pub fn get_emit_ir(matches: &ArgMatches) -> bool {
+        matches.is_present("emit_ir")
+    }
+
+    // --emit_hir writes the program in text, close to the circom code, and
+    // --emit_hir=json in json
+    pub fn get_emit_hir(matches: &ArgMatches) -> Result<Option<HirFormat>, ()> {
+        match matches.value_of("emit_hir") {
+            _ if !matches.is_present("emit_hir") => Ok(None),
+            None | Some("text") => Ok(Some(HirFormat::Text)),
+            Some("json") => Ok(Some(HirFormat::Json)),
+            Some(_) => {
+                eprintln!("{}", Colour::Red.paint("invalid hir format, expected text or json"));
+                Result::Err(())
+            }
+        }
+    }
+
+    pub fn get_load_ir(matches: &ArgMatches) -> Option<String> {
+        matches.value_of("load_ir").map(String::from)
+    }
+
+    pub fn get_symex_max_paths(matches: &ArgMatches) -> Result<usize, ()> {
+        match matches.value_of("symex_max_paths").unwrap_or("64").parse::<usize>() {
+            Ok(paths) if paths > 0 => Ok(paths),
+            _ => {
+                eprintln!("{}", Colour::Red.paint("invalid number of paths"));
+                Result::Err(())
+            }
+        }
+    }
+
+    pub fn get_symex_paths(matches: &ArgMatches) -> bool {
+        matches.is_present("symex_paths")
+    }
+
+    pub fn get_symex_taint(matches: &ArgMatches) -> bool {
+        matches.is_present("symex_taint")
+    }
+
+    pub fn get_explanation(matches: &ArgMatches) -> Result<Explanation, ()> {
+        if let Some(signal) = matches.value_of("explain_signal") {
+            return Result::Ok(Explanation::Signal(signal.to_string()));
+        }
+        match matches.value_of("explain_constraint").unwrap().parse::<usize>() {
+            Ok(constraint) => Result::Ok(Explanation::Constraint(constraint)),
+            Err(_) => {
+                eprintln!("{}", Colour::Red.paint("invalid number of constraint"));
+                Result::Err(())
+            }
+        }
+    }
+
+    pub fn get_docs_format(matches: &ArgMatches) -> DocsFormat {
+        match matches.value_of("docs_format") {
+            Some("html") => DocsFormat::Html,
+            _ => DocsFormat::Markdown,
+        }
+    }
+
+    // The options of `circom equiv`, which are read from the command line
+    // without the manifest, since they do not compile a circuit
+    pub fn equivalence_matches() -> Option<ArgMatches<'static>> {
+        matches().subcommand_matches("equiv").cloned()
+    }
+
+    pub fn get_explain_code() -> Option<String> {
+        matches().value_of("explain_code").map(str::to_string)
+    }
+
+    pub fn language_server_matches() -> Option<ArgMatches<'static>> {
+        matches().subcommand_matches("lsp").cloned()
+    }
+
+    pub fn format_matches() -> Option<ArgMatches<'static>> {
+        matches().subcommand_matches("fmt").cloned()
+    }
+
+    pub fn repl_matches() -> Option<ArgMatches<'static>> {
+        matches().subcommand_matches("repl").cloned()
+    }
+
+    pub fn debug_matches() -> Option<ArgMatches<'static>> {
+        matches().subcommand_matches("debug").cloned()
+    }
+
+    pub fn test_matches() -> Option<ArgMatches<'static>> {
+        matches().subcommand_matches("test").cloned()
+    }
+
+    pub fn mutate_matches() -> Option<ArgMatches<'static>> {
+        matches().subcommand_matches("mutate").cloned()
+    }
+
+    pub fn proptest_matches() -> Option<ArgMatches<'static>> {
+        matches().subcommand_matches("proptest").cloned()
+    }
+
+    pub fn reduce_matches() -> Option<ArgMatches<'static>> {
+        matches().subcommand_matches("reduce").cloned()
+    }
+
+    pub fn get_repl_files(matches: &ArgMatches) -> Result<Vec<PathBuf>, ()> {
+        let mut files = Vec::new();
+        for file in matches.values_of("files").into_iter().flatten() {
+            let file = Path::new(file).to_path_buf();
+            if !file.is_file() {
+                eprintln!("{}", Colour::Red.paint(format!("Input file does not exist: {}", file.display())));
+                return Result::Err(());
+            }
+            files.push(file);
+        }
+        Result::Ok(files)
+    }
+
+    pub fn get_existing_file(matches: &ArgMatches, name: &str) -> Result<PathBuf, ()> {
+        let file = Path::new(matches.value_of(name).unwrap()).to_path_buf();
+        if !file.is_file() {
+            eprintln!("{}", Colour::Red.paint(format!("Input file does not exist: {}", file.display())));
+            return Result::Err(());
+        }
+        Result::Ok(file)
+    }
+
+    pub fn get_format_files(matches: &ArgMatches) -> Result<Vec<PathBuf>, ()> {
+        let mut files = Vec::new();
+        for route in matches.values_of("files").unwrap() {
+            let route = Path::new(route).to_path_buf();
+            if !route.exists() {
+                eprintln!("{}", Colour::Red.paint(format!("Input file does not exist: {}", route.display())));
+                return Result::Err(());
+            }
+            files.push(route);
+        }
+        Result::Ok(files)
+    }
+
+    pub fn get_line_width(matches: &ArgMatches) -> Result<usize, ()> {
+        match matches.value_of("line_width").unwrap_or("100").parse::<usize>() {
+            Ok(width) if width > 0 => Ok(width),
+            _ => {
+                eprintln!("{}", Colour::Red.paint("invalid line width"));
+                Result::Err(())
+            }
+        }
+    }
+
+    pub fn get_cases(matches: &ArgMatches) -> Result<usize, ()> {
+        match matches.value_of("cases").unwrap_or("100").parse::<usize>() {
+            Ok(cases) if cases > 0 => Ok(cases),
+            _ => {
+                eprintln!("{}", Colour::Red.paint("invalid number of cases"));
+                Result::Err(())
+            }
+        }
+    }
+
+    pub fn get_seed(matches: &ArgMatches) -> Result<Option<u64>, ()> {
+        match matches.value_of("seed").map(|seed| seed.parse::<u64>()) {
+            None => Ok(None),
+            Some(Ok(seed)) => Ok(Some(seed)),
+            Some(Err(_)) => {
+                eprintln!("{}", Colour::Red.paint("invalid seed"));
+                Result::Err(())
+            }
+        }
+    }
+
+    pub fn get_r1cs_file(matches: &ArgMatches, name: &str) -> Result<PathBuf, ()> {
+        let route = Path::new(matches.value_of(name).unwrap()).to_path_buf();
+        if route.is_file() {
+            Result::Ok(route)
+        } else {
+            eprintln!("{}", Colour::Red.paint(format!("Input file does not exist: {}", route.display())));
+            Result::Err(())
+        }
+    }
+
+    pub fn get_max_diff(matches: &ArgMatches) -> Result<usize, ()> {
+        match matches.value_of("max_diff").unwrap_or("64").parse::<usize>() {
+            Ok(constraints) => Ok(constraints),
+            Err(_) => {
+                eprintln!("{}", Colour::Red.paint("invalid number of constraints"));
+                Result::Err(())
+            }
+        }
+    }
+
+    pub fn get_solver(matches: &ArgMatches) -> Option<Solver> {
+        match matches.value_of("solver") {
+            Some("cvc5") => Some(Solver::Cvc5),
+            Some("z3") => Some(Solver::Z3),
+            _ => None,
+        }
+    }
+
+    // The subcommands that compile the circuit, and the compilation itself
+    #[derive(Copy, Clone, PartialEq, Eq)]
+    pub enum Command {
+        Compile,
+        Symex,
+        Explain,
+        Docs,
+        Audit,
+    }
+
+    // The options of the command, with the command they are given to
+    pub fn view(args: Vec<String>) -> (ArgMatches<'static>, Command) {
+        command_matches(parse(&args).unwrap_or_else(|e| e.exit()))
+    }
+
+    fn command_matches(matches: ArgMatches<'static>) -> (ArgMatches<'static>, Command) {
+        if let Some(symex) = matches.subcommand_matches("symex") {
+            (symex.clone(), Command::Symex)
+        } else if let Some(explain) = matches.subcommand_matches("explain") {
+            (explain.clone(), Command::Explain)
+        } else if let Some(docs) = matches.subcommand_matches("docs") {
+            (docs.clone(), Command::Docs)
+        } else if let Some(audit) = matches.subcommand_matches("audit") {
+            (audit.clone(), Command::Audit)
+        } else {
+            (matches, Command::Compile)
+        }
+    }
+
+    // The options of the command line. A subcommand is only its exact name as
+    // the first argument, so that an input as test.circom or tests/main.circom
+    // is compiled instead of read as a misspelled subcommand
+    fn parse(args: &[String]) -> Result<ArgMatches<'static>, clap::Error> {
+        let first = args.get(1).map(String::as_str).unwrap_or_default();
+        let subcommands = subcommands();
+        let is_subcommand = first == "help" || subcommands.iter().any(|subcommand| subcommand.get_name() == first);
+        if is_subcommand || first.is_empty() || first.starts_with('-') {
+            compilation().setting(AppSettings::ArgsNegateSubcommands).subcommands(subcommands).get_matches_from_safe(args)
+        } else {
+            compilation().get_matches_from_safe(args)
+        }
+    }
+
+    fn matches() -> ArgMatches<'static> {
+        parse(&std::env::args().collect::<Vec<_>>()).unwrap_or_else(|e| e.exit())
+    }
+
+    fn subcommands() -> Vec<App<'static, 'static>> {
+        let symex = SubCommand::with_name("symex")
+            .about("Runs the witness generation of each template with symbolic inputs, and reports the values it computes that the constraints do not imply")
+            .arg(
+                Arg::with_name("symex_max_paths")
+                    .long("max_paths")
+                    .takes_value(true)
+                    .default_value("64")
+                    .display_order(10)
+                    .help("Maximum number of paths followed in each template"),
+            )
+            .arg(
+                Arg::with_name("symex_paths")
+                    .long("paths")
+                    .takes_value(false)
+                    .display_order(11)
+                    .help("Prints the conditions of each path and the values it computes for the outputs"),
+            )
+            .arg(
+                Arg::with_name("symex_taint")
+                    .long("taint")
+                    .takes_value(false)
+                    .display_order(12)
+                    .help("Prints the private inputs that each public output and public input depends on"),
+            );
+        let explain = SubCommand::with_name("explain")
+            .about("Explains a constraint of the r1cs file, or the constraints of a signal, from the code that generates it to its simplification")
+            .arg(
+                Arg::with_name("explain_constraint")
+                    .long("constraint")
+                    .takes_value(true)
+                    .required_unless("explain_signal")
+                    .conflicts_with("explain_signal")
+                    .display_order(10)
+                    .help("Number of the constraint in the r1cs file"),
+            )
+            .arg(
+                Arg::with_name("explain_signal")
+                    .long("signal")
+                    .takes_value(true)
+                    .display_order(11)
+                    .help("Full name of the signal, as in the sym file"),
+            );
+        let docs = SubCommand::with_name("docs")
+            .about("Writes the documentation of the templates, buses and functions of the circuit and its includes, from their /// comments")
+            .arg(
+                Arg::with_name("docs_format")
+                    .long("format")
+                    .takes_value(true)
+                    .possible_values(&["markdown", "html"])
+                    .default_value("markdown")
+                    .display_order(10)
+                    .help("Format of the documentation, written in the file <name>_docs.md or <name>_docs.html"),
+            );
+        let audit = SubCommand::with_name("audit")
+            .about("Writes in <name>_audit.json a report of the circuit for its audit: its public interface, its constraints, its under-constrained signals, its <-- assignments, the tags its inputs assume and its extern functions");
+        let equivalence = SubCommand::with_name("equiv")
+            .about("Checks that two compiled circuits with the same inputs and outputs have equivalent constraints")
+            .arg(
+                Arg::with_name("old_r1cs")
+                    .required(true)
+                    .index(1)
+                    .help("Path to the r1cs file of the old circuit, next to its sym file"),
+            )
+            .arg(
+                Arg::with_name("new_r1cs")
+                    .required(true)
+                    .index(2)
+                    .help("Path to the r1cs file of the new circuit, next to its sym file"),
+            )
+            .arg(
+                Arg::with_name("max_diff")
+                    .long("max_diff")
+                    .takes_value(true)
+                    .default_value("64")
+                    .display_order(10)
+                    .help("Maximum number of differing constraints that are given to an SMT solver"),
+            )
+            .arg(
+                Arg::with_name("solver")
+                    .long("solver")
+                    .takes_value(true)
+                    .possible_values(&["cvc5", "z3"])
+                    .display_order(11)
+                    .help("SMT solver that decides the differing constraints, which are written in a file with extension smt2 otherwise"),
+            );
+        let language_server = SubCommand::with_name("lsp")
+            .about("Runs the language server of circom, which communicates with the editor through the standard input and output")
+            .arg(
+                Arg::with_name("link_libraries")
+                    .short("l")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .display_order(10)
+                    .help("Adds directory to library search path"),
+            )
+            .arg(
+                Arg::with_name("prime")
+                    .long("prime")
+                    .takes_value(true)
+                    .default_value("bn128")
+                    .display_order(11)
+                    .help("To choose the prime number of the circuits, when their main file has no pragma prime. Options: bn128, bls12381, goldilocks, grumpkin, secq256r1, pallas, vesta, bls12377"),
+            );
+        let format = SubCommand::with_name("fmt")
+            .about("Formats circom files in place, keeping their comments")
+            .arg(
+                Arg::with_name("files")
+                    .multiple(true)
+                    .default_value(".")
+                    .help("Paths to the files to format, or to directories with circom files"),
+            )
+            .arg(
+                Arg::with_name("check")
+                    .long("check")
+                    .takes_value(false)
+                    .display_order(10)
+                    .help("Only lists the files that are not formatted, and fails if there is any"),
+            )
+            .arg(
+                Arg::with_name("line_width")
+                    .long("line_width")
+                    .takes_value(true)
+                    .default_value("100")
+                    .display_order(11)
+                    .help("Maximum width of the lines, which are broken by their lists or their operators"),
+            );
+        let repl = SubCommand::with_name("repl")
+            .about("Evaluates expressions and instantiates templates with the definitions of the given files, reading them from the standard input")
+            .arg(
+                Arg::with_name("files")
+                    .multiple(true)
+                    .help("Paths to the files whose definitions are included in the session"),
+            )
+            .arg(
+                Arg::with_name("link_libraries")
+                    .short("l")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .display_order(10)
+                    .help("Adds directory to library search path"),
+            )
+            .arg(
+                Arg::with_name("prime")
+                    .long("prime")
+                    .takes_value(true)
+                    .default_value("bn128")
+                    .display_order(11)
+                    .help("To choose the prime number of the session. Options: bn128, bls12381, goldilocks, grumpkin, secq256r1, pallas, vesta, bls12377"),
+            );
+        let debug = SubCommand::with_name("debug")
+            .about("Computes the witness of a circuit for the given inputs, reading from the standard input the commands that stop it and inspect its signals")
+            .arg(
+                Arg::with_name("input")
+                    .required(true)
+                    .help("Path to a circuit with a main component"),
+            )
+            .arg(
+                Arg::with_name("inputs")
+                    .required(true)
+                    .help("Path to the json file with the inputs of the main component"),
+            )
+            .arg(
+                Arg::with_name("link_libraries")
+                    .short("l")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .display_order(10)
+                    .help("Adds directory to library search path"),
+            )
+            .arg(
+                Arg::with_name("prime")
+                    .long("prime")
+                    .takes_value(true)
+                    .default_value("bn128")
+                    .display_order(11)
+                    .help("To choose the prime number of the circuit, when its file has no pragma prime. Options: bn128, bls12381, goldilocks, grumpkin, secq256r1, pallas, vesta, bls12377"),
+            );
+        let test = SubCommand::with_name("test")
+            .about("Runs the test blocks of circom files, checking the outputs of each template for the inputs given and that its constraints hold")
+            .arg(
+                Arg::with_name("files")
+                    .multiple(true)
+                    .default_value(".")
+                    .help("Paths to the files with tests, or to directories with circom files"),
+            )
+            .arg(
+                Arg::with_name("link_libraries")
+                    .short("l")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .display_order(10)
+                    .help("Adds directory to library search path"),
+            )
+            .arg(
+                Arg::with_name("prime")
+                    .long("prime")
+                    .takes_value(true)
+                    .default_value("bn128")
+                    .display_order(11)
+                    .help("To choose the prime number of the tests, when their file has no pragma prime. Options: bn128, bls12381, goldilocks, grumpkin, secq256r1, pallas, vesta, bls12377"),
+            );
+        let mutate = SubCommand::with_name("mutate")
+            .about("Runs the test blocks of circom files with each statement that generates constraints dropped or changed, and lists the changes that no test detects")
+            .arg(
+                Arg::with_name("files")
+                    .multiple(true)
+                    .default_value(".")
+                    .help("Paths to the files with tests, or to directories with circom files"),
+            )
+            .arg(
+                Arg::with_name("link_libraries")
+                    .short("l")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .display_order(10)
+                    .help("Adds directory to library search path"),
+            )
+            .arg(
+                Arg::with_name("prime")
+                    .long("prime")
+                    .takes_value(true)
+                    .default_value("bn128")
+                    .display_order(11)
+                    .help("To choose the prime number of the tests, when their file has no pragma prime. Options: bn128, bls12381, goldilocks, grumpkin, secq256r1, pallas, vesta, bls12377"),
+            );
+        let proptest = SubCommand::with_name("proptest")
+            .about("Checks the ensure statements and the constraints of the templates of circom files with random inputs in the ranges that the constraints give to them")
+            .arg(
+                Arg::with_name("files")
+                    .multiple(true)
+                    .default_value(".")
+                    .help("Paths to the files with templates, or to directories with circom files"),
+            )
+            .arg(
+                Arg::with_name("link_libraries")
+                    .short("l")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .display_order(10)
+                    .help("Adds directory to library search path"),
+            )
+            .arg(
+                Arg::with_name("prime")
+                    .long("prime")
+                    .takes_value(true)
+                    .default_value("bn128")
+                    .display_order(11)
+                    .help("To choose the prime number of the templates, when their file has no pragma prime. Options: bn128, bls12381, goldilocks, grumpkin, secq256r1, pallas, vesta, bls12377"),
+            )
+            .arg(
+                Arg::with_name("cases")
+                    .long("cases")
+                    .takes_value(true)
+                    .default_value("100")
+                    .display_order(12)
+                    .help("Number of random inputs of each template"),
+            )
+            .arg(
+                Arg::with_name("seed")
+                    .long("seed")
+                    .takes_value(true)
+                    .display_order(13)
+                    .help("Seed of the random inputs, to repeat a run. By default it is taken from the clock and printed"),
+            )
+            .arg(
+                Arg::with_name("template")
+                    .long("template")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .display_order(14)
+                    .help("A template to check with its arguments, as Num2Bits(8), instead of the templates without parameters"),
+            );
+        let reduce = SubCommand::with_name("reduce")
+            .about("Removes the lines of the files of a program that makes the compiler panic while it keeps panicking at the same place, and writes the smallest program found")
+            .arg(
+                Arg::with_name("output")
+                    .short("o")
+                    .long("output")
+                    .takes_value(true)
+                    .display_order(1)
+                    .help("Directory of the reduced program, by default the name of the main file with _reduced"),
+            )
+            .arg(
+                Arg::with_name("command")
+                    .multiple(true)
+                    .required(true)
+                    .last(true)
+                    .help("The arguments of the compilation that panics, after --"),
+            );
+        vec![
+            arguments(symex),
+            arguments(explain),
+            arguments(docs),
+            arguments(audit),
+            debug,
+            equivalence,
+            language_server,
+            format,
+            mutate,
+            proptest,
+            reduce,
+            repl,
+            test,
+        ]
+    }
+
+    fn compilation() -> App<'static, 'static> {
+        arguments(
+            App::new("circom compiler")
+                .version(VERSION)
+                .author("IDEN3")
+                .about("Compiler for the circom programming language"),
+        )
+        .arg(
+            Arg::with_name("explain_code")
+                .long("explain")
+                .takes_value(true)
+                .value_name("CODE")
+                .display_order(808)
+                .help("Prints the explanation of the code of an error or a warning, as P1008 or T2021, with examples of the code that produces it"),
+        )
+    }
+
+    fn arguments(app: App<'static, 'static>) -> App<'static, 'static> {
+        app
+            .arg(
+                Arg::with_name("input")
+                    .multiple(false)
+                    .default_value("./circuit.circom")
+                    .help("Path to a circuit with a main component"),
+            )
+            .arg(
+                Arg::with_name("no_simplification")
+                    .long("O0")
+                    .hidden(false)
+                    .takes_value(false)
+                    .help("No simplification is applied")
+                    .display_order(420)
+            )
+            .arg(
+                Arg::with_name("reduced_simplification")
+                    .long("O1")
+                    .hidden(false)
+                    .takes_value(false)
+                    .help("Only applies signal to signal and signal to constant simplification. This is the default option")
+                    .display_order(460)
+            )
+            .arg(
+                Arg::with_name("full_simplification")
+                    .long("O2")
+                    .takes_value(false)
+                    .hidden(false)
+                    .help("Full constraint simplification")
+                    .display_order(480)
+            )
+            .arg(
+                Arg::with_name("simplification_rounds")
                     .long("O2round")
                     .takes_value(true)
                     .hidden(false)
                     .help("Maximum number of rounds of the simplification process")
                     .display_order(500)
             )
+            .arg(
+                Arg::with_name("function_steps")
+                    .long("function_steps")
+                    .takes_value(true)
+                    .default_value("10000000")
+                    .display_order(505)
+                    .help("Maximum number of loop iterations and calls of a function evaluated by the compiler"),
+            )
+            .arg(
+                Arg::with_name("template_recursion")
+                    .long("template_recursion")
+                    .takes_value(true)
+                    .default_value("100")
+                    .display_order(506)
+                    .help("Maximum number of instances of a template inside itself"),
+            )
+            .arg(
+                Arg::with_name("ir_optimization_level")
+                    .long("ir_opt")
+                    .takes_value(true)
+                    .default_value("0")
+                    .display_order(510)
+                    .help("Optimization level of the witness generation code (0, 1 or 2)"),
+            )
+            .arg(
+                Arg::with_name("disabled_passes")
+                    .long("disable_pass")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .display_order(520)
+                    .help("Disables one of the optimization passes of the witness generation code"),
+            )
+            .arg(
+                Arg::with_name("pass_timing")
+                    .long("pass_timing")
+                    .takes_value(false)
+                    .display_order(530)
+                    .help("Shows the time spent in each optimization pass of the witness generation code"),
+            )
+            .arg(
+                Arg::with_name("opt_report")
+                    .long("opt_report")
+                    .takes_value(false)
+                    .display_order(535)
+                    .help("Outputs in json format the instructions, loops and branches that each IR pass removes from every template"),
+            )
+            .arg(
+                Arg::with_name("verify_ir")
+                    .long("verify_ir")
+                    .takes_value(false)
+                    .display_order(537)
+                    .help("Checks the witness generation code after each optimization pass"),
+            )
+            .arg(
+                Arg::with_name("lazy_components")
+                    .long("lazy_components")
+                    .takes_value(false)
+                    .display_order(538)
+                    .help("Runs each subcomponent of the witness generation code when one of its outputs is first read"),
+            )
+            .arg(
+                Arg::with_name("ir_cache")
+                    .long("ir_cache")
+                    .takes_value(true)
+                    .display_order(540)
+                    .help("Directory where the witness generation code of the template instances is kept to skip its translation in the following compilations"),
+            )
+            .arg(
+                Arg::with_name("emit_ir")
+                    .long("emit_ir")
+                    .takes_value(false)
+                    .display_order(545)
+                    .help("Outputs in text format the witness generation code of the templates and functions"),
+            )
+            .arg(
+                Arg::with_name("load_ir")
+                    .long("load_ir")
+                    .takes_value(true)
+                    .value_name("FILE")
+                    .display_order(546)
+                    .help("Replaces the witness generation code by the one of a file written with --emit_ir"),
+            )
+            .arg(
+                Arg::with_name("emit_hir")
+                    .long("emit_hir")
+                    .takes_value(true)
+                    .min_values(0)
+                    .require_equals(true)
+                    .value_name("FORMAT")
+                    .display_order(547)
+                    .help("Outputs the program after the type analysis, with the instances of the templates, in text format or in json format with --emit_hir=json"),
+            )
             .arg(
                 Arg::with_name("output")
                     .short("o")
@@ -441,9 +2061,26 @@ mod input_processing {
             .arg(
                 Arg::with_name("inspect_constraints")
                     .long("inspect")
-                    .takes_value(false)
+                    .takes_value(true)
+                    .min_values(0)
+                    .require_equals(true)
+                    .value_name("LEVEL")
                     .display_order(801)
-                    .help("Does an additional check over the constraints produced"),
+                    .help("Does an additional check over the constraints produced. With --inspect=2, it also reports the signals assigned with <-- that no constraint determines, and with --inspect=3, the signals that the inputs do not determine"),
+            )
+            .arg(
+                Arg::with_name("deny_unconstrained")
+                    .long("deny_unconstrained")
+                    .takes_value(false)
+                    .display_order(802)
+                    .help("Stops the compilation when a signal assigned with <-- does not appear in any constraint of its template"),
+            )
+            .arg(
+                Arg::with_name("suggest_parallel")
+                    .long("suggest_parallel")
+                    .takes_value(false)
+                    .display_order(802)
+                    .help("Reports the components that the C++ witness generator can compute in parallel, and the parallel ones that are too small or that depend on the other components"),
             )
             .arg(
                 Arg::with_name("print_json_sub")
@@ -459,6 +2096,149 @@ mod input_processing {
                     .display_order(60)
                     .help("Outputs witness in sym format"),
             )
+            .arg(
+                Arg::with_name("print_smt")
+                    .long("smt2")
+                    .takes_value(true)
+                    .min_values(0)
+                    .require_equals(true)
+                    .value_name("ENCODING")
+                    .display_order(61)
+                    .help("Outputs the constraints in SMT-LIB format, over the finite field of the prime, or over the integers with --smt2=int"),
+            )
+            .arg(
+                Arg::with_name("source_map")
+                    .long("source_map")
+                    .takes_value(false)
+                    .display_order(62)
+                    .help("Outputs in json format the component, the file and the line of each constraint of the r1cs file"),
+            )
+            .arg(
+                Arg::with_name("heatmap")
+                    .long("heatmap")
+                    .takes_value(false)
+                    .display_order(62)
+                    .help("Outputs in html format the code of the circuit with the number of constraints of the r1cs file that each line generates"),
+            )
+            .arg(
+                Arg::with_name("template_report")
+                    .long("template_report")
+                    .takes_value(false)
+                    .display_order(63)
+                    .help("Outputs in json format the constraints and signals that each template adds to the circuit"),
+            )
+            .arg(
+                Arg::with_name("component_graph")
+                    .long("component_graph")
+                    .takes_value(true)
+                    .min_values(0)
+                    .require_equals(true)
+                    .value_name("FORMAT")
+                    .display_order(63)
+                    .help("Outputs the tree of components of the circuit with the instances of their templates, in json format or in dot format with --component_graph=dot"),
+            )
+            .arg(
+                Arg::with_name("constraint_graph")
+                    .long("constraint_graph")
+                    .takes_value(true)
+                    .min_values(0)
+                    .require_equals(true)
+                    .value_name("FORMAT")
+                    .display_order(63)
+                    .help("Outputs the graph of the constraints of the r1cs file and their signals, in json format, in dot format with --constraint_graph=dot or in binary format with --constraint_graph=bin"),
+            )
+            .arg(
+                Arg::with_name("constraint_graph_by_template")
+                    .long("constraint_graph_by_template")
+                    .takes_value(false)
+                    .requires("constraint_graph")
+                    .display_order(63)
+                    .help("Collapses the constraints of each template in a single node of the constraint graph, with the shared signals in its edges"),
+            )
+            .arg(
+                Arg::with_name("stats")
+                    .long("stats")
+                    .takes_value(false)
+                    .display_order(64)
+                    .help("Outputs in json format the statistics of the circuit and the sizes of the generated files"),
+            )
+            .arg(
+                Arg::with_name("snapshot")
+                    .long("snapshot")
+                    .takes_value(true)
+                    .min_values(0)
+                    .require_equals(true)
+                    .value_name("FILE")
+                    .display_order(65)
+                    .help("Records the hashes of the files generated for each circuit in a snapshot file, circom.snapshot by default or the one given with --snapshot=FILE"),
+            )
+            .arg(
+                Arg::with_name("check_snapshots")
+                    .long("check_snapshots")
+                    .takes_value(false)
+                    .display_order(65)
+                    .help("Fails if the hashes of the files generated for each circuit differ from the ones of the snapshot file"),
+            )
+            .arg(
+                Arg::with_name("template_call")
+                    .long("template")
+                    .takes_value(true)
+                    .value_name("CALL")
+                    .display_order(64)
+                    .help("Compiles the template with the given arguments, as Num2Bits(8), as the only main component of the input file, which may have none, and names the files generated after it"),
+            )
+            .arg(
+                Arg::with_name("watch")
+                    .long("watch")
+                    .takes_value(false)
+                    .conflicts_with_all(&["snapshot", "check_snapshots"])
+                    .display_order(65)
+                    .help("Compiles the circuit again each time one of its files changes, only parsing the files that change and generating the outputs of the main components whose code changes"),
+            )
+            .arg(
+                Arg::with_name("fix")
+                    .long("fix")
+                    .takes_value(false)
+                    .conflicts_with("watch")
+                    .display_order(65)
+                    .help("Applies to the files the suggested fixes of the errors and warnings, as missing semicolons and misspelled names, and compiles the circuit again until no fix applies"),
+            )
+            .arg(
+                Arg::with_name("diff_backends")
+                    .long("diff_backends")
+                    .takes_value(true)
+                    .value_name("INPUTS")
+                    .display_order(66)
+                    .help("Compiles the circuit to C++ and wasm, runs both witness generators with the inputs of the json file, and compares their witnesses with the one of the compiler"),
+            )
+            .arg(
+                Arg::with_name("fuzz")
+                    .long("fuzz")
+                    .takes_value(false)
+                    .display_order(66)
+                    .help("Writes with the C++ code a libFuzzer harness that computes the witnesses of mutated json inputs and checks them against the constraints of the r1cs file"),
+            )
+            .arg(
+                Arg::with_name("bench")
+                    .long("bench")
+                    .takes_value(false)
+                    .display_order(66)
+                    .help("Writes with the C++ code a benchmark that runs the witness generator with json inputs, after warmup runs, and reports the mean and the percentiles of its times and its peak memory"),
+            )
+            .arg(
+                Arg::with_name("trace")
+                    .long("trace")
+                    .takes_value(false)
+                    .display_order(66)
+                    .help("Makes the C++ and wasm witness generators report each value stored in a signal, with its name and the line of the store, in the file given by CIRCOM_TRACE"),
+            )
+            .arg(
+                Arg::with_name("profile")
+                    .long("profile")
+                    .takes_value(false)
+                    .display_order(66)
+                    .help("Makes the C++ and wasm witness generators measure the calls and the time of the runs of each template, written as a flame graph profile in the file given by CIRCOM_PROFILE"),
+            )
             .arg(
                 Arg::with_name("print_r1cs")
                     .long("r1cs")
@@ -496,13 +2276,22 @@ mod input_processing {
                 .display_order(330) 
                 .help("Adds directory to library search path"),
             )
+            .arg(
+                Arg::with_name("features")
+                .long("feature")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .display_order(340)
+                .help("Enables a feature, compiling the code under #[cfg(feature = \"name\")]"),
+            )
             .arg(
                 Arg::with_name("print_c")
                     .long("c")
                     .short("c")
                     .takes_value(false)
                     .display_order(150)
-                    .help("Compiles the circuit to C++"),
+                    .help("Compiles the circuit to C++, whose witness calculator releases the subcomponents after their last use except for their signals"),
             )
             .arg(
                 Arg::with_name("parallel_simplification")
@@ -542,6 +2331,51 @@ mod input_processing {
                     .display_order(999)
                     .help("Removes initializations to 0 of variables (\"var\") in the witness generation code"),
             )
+            .arg(
+                Arg::with_name("flag_enforce_tags")
+                    .long("enforce_tags")
+                    .takes_value(false)
+                    .display_order(803)
+                    .help("Checks the tags binary and maxbit of the inputs of the templates with constraints instead of assuming them"),
+            )
+            .arg(
+                Arg::with_name("lint_allow")
+                    .long("allow")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .value_name("LINT")
+                    .display_order(804)
+                    .help("Does not report the warnings of a lint, given by its name or its code, or of a group of lints"),
+            )
+            .arg(
+                Arg::with_name("lint_warn")
+                    .long("warn")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .value_name("LINT")
+                    .display_order(805)
+                    .help("Reports the warnings of a lint or of a group of lints as warnings"),
+            )
+            .arg(
+                Arg::with_name("lint_deny")
+                    .long("deny")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .value_name("LINT")
+                    .display_order(806)
+                    .help("Reports the warnings of a lint or of a group of lints as errors, which stop the compilation"),
+            )
+            .arg(
+                Arg::with_name("message_format")
+                    .long("message_format")
+                    .takes_value(true)
+                    .possible_values(&["human", "json", "sarif"])
+                    .display_order(807)
+                    .help("Prints the errors and warnings as a JSON object in each line with --message_format json, or as a SARIF log when the compiler ends with --message_format sarif"),
+            )
             .arg(
                 Arg::with_name("flag_old_heuristics")
                     .long("use_old_simplification_heuristics")
@@ -556,9 +2390,8 @@ mod input_processing {
                     .takes_value(true)
                     .default_value("bn128")
                     .display_order(300)
-                    .help("To choose the prime number to use to generate the circuit. Receives the name of the curve (bn128, bls12377, bls12381, goldilocks, grumpkin, pallas, secq256r1, vesta)"),
+                    .help("To choose the prime number to use to generate the circuit. Receives the name of the curve (bn128, bls12377, bls12381, goldilocks, grumpkin, pallas, secq256r1, vesta). Without it, the prime is the one of the pragma prime of the main file, or bn128"),
             )
-            .get_matches()
     }
 
     pub fn get_link_libraries(matches: &ArgMatches) -> Vec<PathBuf> {
@@ -571,4 +2404,8 @@ mod input_processing {
         }
         link_libraries
     }
+
+    pub fn get_features(matches: &ArgMatches) -> Vec<String> {
+        matches.values_of("features").map_or(Vec::new(), |features| features.map(String::from).collect())
+    }
 }
@@ -1,18 +1,47 @@
+mod audit_user;
 mod compilation_user;
+mod debug_user;
+mod differential_user;
+mod docs_user;
+mod equivalence_user;
 mod execution_user;
+mod explain_user;
 mod input_user;
+mod fetch;
+mod fix_user;
+mod format_user;
+mod ice;
+mod manifest;
+mod mutate_user;
 mod parser_user;
+mod proptest_user;
+mod reduce_user;
+mod repl_user;
+mod snapshot_user;
+mod stats_user;
+mod symex_user;
+mod test_user;
 mod type_analysis_user;
+mod watch_user;
 
 const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
 
 use ansi_term::Colour;
-use input_user::Input;
+use input_user::{DebugInput, EquivalenceInput, ExplainCodeInput, FormatInput, Input, LanguageServerInput, MutateInput, ProptestInput, ReduceInput, ReplInput, TestInput};
+use program_structure::error_definition::{MessageFormat, Report};
+use program_structure::error_explanation;
+use program_structure::program_archive::ProgramArchive;
 fn main() {
+    ice::install_reporter(VERSION);
     let result = start();
+    // the SARIF log is the only output of the errors
+    let sarif = Report::write_sarif_log();
     if result.is_err() {
-        eprintln!("{}", Colour::Red.paint("previous errors were found"));
+        if !sarif {
+            eprintln!("{}", Colour::Red.paint("previous errors were found"));
+            print_explanation_hint();
+        }
         std::process::exit(1);
     } else {
         println!("{}", Colour::Green.paint("Everything went okay"));
@@ -21,31 +50,301 @@ fn main() {
 }
 
 fn start() -> Result<(), ()> {
+    if let Some(input) = ExplainCodeInput::new() {
+        // the explanation is the only output, as the one of rustc --explain
+        let result = explain_code(&input.code);
+        std::process::exit(if result.is_ok() { 0 } else { 1 });
+    }
+    if let Some(input) = EquivalenceInput::new()? {
+        let config = equivalence_user::EquivalenceConfig {
+            old_r1cs: input.old_r1cs,
+            new_r1cs: input.new_r1cs,
+            max_diff: input.max_diff,
+            solver: input.solver,
+        };
+        return equivalence_user::check_equivalence(config);
+    }
+    if let Some(input) = LanguageServerInput::new()? {
+        let config = language_server::ServerConfig {
+            version: VERSION.to_string(),
+            libraries: input.link_libraries,
+            prime: input.prime,
+        };
+        // the standard output is the one of the protocol, without the messages
+        // of the compiler when the server exits
+        let result = language_server::run(config);
+        if let Err(error) = &result {
+            eprintln!("{}", Colour::Red.paint(error.to_string()));
+        }
+        std::process::exit(if result.is_ok() { 0 } else { 1 });
+    }
+    if let Some(input) = FormatInput::new()? {
+        let config = format_user::FormatterConfig {
+            files: input.files,
+            check: input.check,
+            line_width: input.line_width,
+        };
+        return format_user::format_files(config);
+    }
+    if let Some(input) = ReplInput::new()? {
+        let config = repl_user::ReplConfig {
+            files: input.files,
+            link_libraries: input.link_libraries,
+            prime: input.prime,
+        };
+        return repl_user::run(config);
+    }
+    if let Some(input) = DebugInput::new()? {
+        let config = debug_user::DebugConfig {
+            file: input.file,
+            inputs: input.inputs,
+            link_libraries: input.link_libraries,
+            prime: input.prime,
+        };
+        return debug_user::run(config);
+    }
+    if let Some(input) = TestInput::new()? {
+        let config = test_user::TestConfig {
+            files: input.files,
+            link_libraries: input.link_libraries,
+            prime: input.prime,
+        };
+        return test_user::run_tests(config);
+    }
+    if let Some(input) = MutateInput::new()? {
+        let config = test_user::TestConfig {
+            files: input.files,
+            link_libraries: input.link_libraries,
+            prime: input.prime,
+        };
+        return mutate_user::run_mutations(config);
+    }
+    if let Some(input) = ProptestInput::new()? {
+        let config = proptest_user::ProptestConfig {
+            files: input.files,
+            link_libraries: input.link_libraries,
+            prime: input.prime,
+            cases: input.cases,
+            seed: input.seed,
+            templates: input.templates,
+        };
+        return proptest_user::run_properties(config);
+    }
+    if let Some(input) = ReduceInput::new()? {
+        let config = reduce_user::ReduceConfig {
+            command: input.command,
+            input: input.input,
+            output: input.output,
+        };
+        return reduce_user::reduce(config);
+    }
+    let mut user_input = Input::new()?;
+    if user_input.message_format() == MessageFormat::Sarif {
+        Report::start_sarif_log(VERSION);
+    }
+    // the warnings of the parsing are also in the report of the audit
+    if user_input.audit_flag() {
+        Report::start_audit();
+    }
+    if let Some(format) = user_input.docs_format() {
+        return generate_docs(&user_input, format);
+    }
+    if user_input.watch_flag() {
+        return watch_user::watch(user_input);
+    }
+    if user_input.fix_flag() {
+        return fix_user::fix(user_input);
+    }
+    compile_program(&mut user_input)
+}
+
+fn compile_program(user_input: &mut Input) -> Result<(), ()> {
+    ice::set_stage("parsing");
+    let mut program_archive = parser_user::parse_project(user_input)?;
+    // the template given with --template is the only main component compiled
+    if let Some(template) = user_input.template_main() {
+        program_archive.keep_main_component(&template);
+    }
+    // the program is parsed and checked once for all its main components, and
+    // the one without a name keeps the names of the files given by the input
+    ice::set_stage("type analysis");
+    type_analysis_user::analyse_project(&mut program_archive)?;
+    let names: Vec<_> = program_archive.get_main_components().iter().map(|(name, _)| name.clone()).collect();
+    let mut order: Vec<_> = (0..names.len()).collect();
+    order.sort_by_key(|index| names[*index].is_some());
+    let mut snapshot = manifest::Snapshot::new();
+    for index in order {
+        let mut program_archive = program_archive.clone();
+        program_archive.select_main_component(index);
+        if let Some(name) = &names[index] {
+            user_input.set_output_name(name);
+        }
+        compile_main_component(user_input, program_archive)?;
+        if user_input.snapshot_flag() || user_input.check_snapshots_flag() {
+            let hashes = snapshot_user::hash_files(&user_input.generated_files())?;
+            snapshot.insert(user_input.output_name().to_string(), hashes);
+        }
+    }
+    if user_input.check_snapshots_flag() {
+        snapshot_user::check_snapshot(&snapshot, user_input.snapshot_file())
+    } else if user_input.snapshot_flag() {
+        snapshot_user::record_snapshot(snapshot, user_input.snapshot_file())
+    } else {
+        Result::Ok(())
+    }
+}
+
+fn explain_code(code: &str) -> Result<(), ()> {
+    match error_explanation::explanation(code) {
+        Some(explanation) => {
+            println!("{}", explanation);
+            Result::Ok(())
+        }
+        None => {
+            eprintln!("{}", Colour::Red.paint(format!("{} is not a code of the errors or warnings of the compiler", code)));
+            Result::Err(())
+        }
+    }
+}
+
+// Points to circom --explain for the codes of the errors printed
+fn print_explanation_hint() {
+    let codes = Report::take_explained_codes();
+    match codes.as_slice() {
+        [] => {}
+        [code] => eprintln!("For more information about this error, try `circom --explain {}`.", code),
+        [first, ..] => {
+            eprintln!("Some errors have detailed explanations: {}.", codes.join(", "));
+            eprintln!("For more information about an error, try `circom --explain {}`.", first);
+        }
+    }
+}
+
+// The constraints of the templates are only documented for a program with
+// a main component, which is compiled to count them
+fn generate_docs(user_input: &Input, format: docs_user::DocsFormat) -> Result<(), ()> {
+    let mut config = docs_user::DocsConfig {
+        input: user_input.input_program.clone(),
+        link_libraries: user_input.get_link_libraries().to_vec(),
+        features: user_input.features().to_vec(),
+        format,
+        output: user_input.docs_file().to_string(),
+        no_rounds: user_input.no_rounds(),
+        function_steps: user_input.function_steps(),
+        template_recursion: user_input.template_recursion(),
+        flag_verbose: user_input.flag_verbose(),
+        prime: user_input.prime(),
+    };
+    let program_archive = if docs_user::has_main_component(&config.input, &config) {
+        let mut program_archive = parser_user::parse_project(user_input)?;
+        type_analysis_user::analyse_project(&mut program_archive)?;
+        config.prime = program_archive.prime.clone();
+        Some(program_archive)
+    } else {
+        None
+    };
+    docs_user::generate_docs(config, program_archive)
+}
+
+// Compiles the selected main component of a program that is already checked
+fn compile_main_component(user_input: &Input, program_archive: ProgramArchive) -> Result<(), ()> {
     use compilation_user::CompilerConfig;
     use execution_user::ExecutionConfig;
-    let user_input = Input::new()?;
-    let mut program_archive = parser_user::parse_project(&user_input)?;
-    type_analysis_user::analyse_project(&mut program_archive)?;
+    let prime = program_archive.prime.clone();
+    if user_input.symex_flag() {
+        ice::set_stage("symbolic execution");
+        let config = symex_user::SymbolicExecutionConfig {
+            max_paths: user_input.symex_max_paths(),
+            print_paths: user_input.symex_paths_flag(),
+            print_taint: user_input.symex_taint_flag(),
+            function_steps: user_input.function_steps(),
+            template_recursion: user_input.template_recursion(),
+            flag_verbose: user_input.flag_verbose(),
+            prime,
+        };
+        return symex_user::execute_symbolically(program_archive, config);
+    }
+    if let Some(explanation) = user_input.explanation() {
+        ice::set_stage("explanation of the constraints");
+        let config = explain_user::ExplanationConfig {
+            explanation,
+            no_rounds: user_input.no_rounds(),
+            flag_s: user_input.reduced_simplification_flag(),
+            flag_f: user_input.unsimplified_flag(),
+            flag_old_heuristics: user_input.flag_old_heuristics(),
+            function_steps: user_input.function_steps(),
+            template_recursion: user_input.template_recursion(),
+            flag_verbose: user_input.flag_verbose(),
+            prime,
+        };
+        return explain_user::explain(program_archive, config);
+    }
+    if user_input.audit_flag() {
+        ice::set_stage("audit");
+        let config = audit_user::AuditConfig {
+            output: user_input.audit_file().to_string(),
+            no_rounds: user_input.no_rounds(),
+            flag_s: user_input.reduced_simplification_flag(),
+            flag_f: user_input.unsimplified_flag(),
+            flag_old_heuristics: user_input.flag_old_heuristics(),
+            inspect_witness_flag: user_input.inspect_witness_flag(),
+            inspect_determinism_flag: user_input.inspect_determinism_flag(),
+            flag_enforce_tags: user_input.flag_enforce_tags(),
+            function_steps: user_input.function_steps(),
+            template_recursion: user_input.template_recursion(),
+            flag_verbose: user_input.flag_verbose(),
+            prime,
+        };
+        return audit_user::audit(program_archive, config);
+    }
 
     let config = ExecutionConfig {
         no_rounds: user_input.no_rounds(),
+        function_steps: user_input.function_steps(),
+        template_recursion: user_input.template_recursion(),
         flag_p: user_input.parallel_simplification_flag(),
         flag_s: user_input.reduced_simplification_flag(),
         flag_f: user_input.unsimplified_flag(),
         flag_old_heuristics: user_input.flag_old_heuristics(),
         flag_verbose: user_input.flag_verbose(),
         inspect_constraints_flag: user_input.inspect_constraints_flag(),
+        inspect_witness_flag: user_input.inspect_witness_flag(),
+        inspect_determinism_flag: user_input.inspect_determinism_flag(),
+        deny_unconstrained_flag: user_input.deny_unconstrained_flag(),
+        suggest_parallel_flag: user_input.suggest_parallel_flag(),
         r1cs_flag: user_input.r1cs_flag(),
         json_constraint_flag: user_input.json_constraints_flag(),
         json_substitution_flag: user_input.json_substitutions_flag(),
         sym_flag: user_input.sym_flag(),
         sym: user_input.sym_file().to_string(),
+        smt_flag: user_input.smt_flag(),
+        smt_encoding: user_input.smt_encoding(),
+        smt: user_input.smt_file().to_string(),
+        source_map_flag: user_input.source_map_flag(),
+        source_map: user_input.source_map_file().to_string(),
+        heatmap_flag: user_input.heatmap_flag(),
+        heatmap: user_input.heatmap_file().to_string(),
+        template_report_flag: user_input.template_report_flag(),
+        template_report: user_input.template_report_file().to_string(),
+        component_graph_flag: user_input.component_graph_flag(),
+        component_graph_format: user_input.component_graph_format(),
+        component_graph: user_input.component_graph_file().to_string(),
+        constraint_graph_flag: user_input.constraint_graph_flag(),
+        constraint_graph_format: user_input.constraint_graph_format(),
+        constraint_graph_by_template: user_input.constraint_graph_by_template(),
+        constraint_graph: user_input.constraint_graph_file().to_string(),
+        hir_flag: user_input.hir_flag(),
+        hir_format: user_input.hir_format(),
+        hir: user_input.hir_file().to_string(),
         r1cs: user_input.r1cs_file().to_string(),
         json_constraints: user_input.json_constraints_file().to_string(),
         json_substitutions: user_input.json_substitutions_file().to_string(),
-        prime: user_input.prime(),        
+        stats_flag: user_input.stats_flag(),
+        prime: prime.clone(),        
     };
-    let circuit = execution_user::execute_project(program_archive, config)?;
+    ice::set_stage("constraint generation");
+    let (circuit, log) = execution_user::execute_project(program_archive, config)?;
+    let statistics = log.map(|log| stats_user::CircuitStatistics::new(log, &circuit));
     let compilation_config = CompilerConfig {
         vcp: circuit,
         debug_output: user_input.print_ir_flag(),
@@ -64,8 +363,41 @@ fn start() -> Result<(), ()> {
 
         no_asm_flag: user_input.no_asm_flag(),
         constraint_assert_disabled_flag: user_input.constraint_assert_disabled_flag(),
-        prime: user_input.prime(),        
+        prime,        
+        ir_optimization_level: user_input.ir_optimization_level(),
+        disabled_passes: user_input.disabled_passes(),
+        pass_timing_flag: user_input.pass_timing_flag(),
+        opt_report_flag: user_input.opt_report_flag(),
+        opt_report_file: user_input.opt_report_file().to_string(),
+        verify_ir_flag: user_input.verify_ir_flag(),
+        lazy_components_flag: user_input.lazy_components_flag(),
+        ir_cache: user_input.ir_cache(),
+        emit_ir_flag: user_input.emit_ir_flag(),
+        ir_file: user_input.ir_file().to_string(),
+        load_ir: user_input.load_ir(),
+        fuzz_flag: user_input.fuzz_flag(),
+        bench_flag: user_input.bench_flag(),
+        trace_flag: user_input.trace_flag(),
+        profile_flag: user_input.profile_flag(),
+        r1cs_file: user_input.r1cs_file().to_string(),
     };
-    compilation_user::compile(compilation_config)?;
+    ice::set_stage("code generation");
+    let circuit = compilation_user::compile(compilation_config)?;
+    if let Some(statistics) = statistics {
+        stats_user::write_statistics(&statistics, &user_input.generated_files(), user_input.stats_file())?;
+    }
+    if let (Some(inputs), Some(circuit)) = (user_input.diff_backends(), &circuit) {
+        let config = differential_user::DifferentialConfig {
+            inputs: inputs.clone(),
+            c_folder: user_input.c_folder().to_string(),
+            c_run_name: user_input.c_run_name(),
+            js_folder: user_input.js_folder().to_string(),
+            wasm_file: user_input.wasm_file().to_string(),
+            sym_file: user_input.sym_file().to_string(),
+            c_witness: user_input.c_witness_file().to_string(),
+            wasm_witness: user_input.wasm_witness_file().to_string(),
+        };
+        differential_user::compare_backends(circuit, &config)?;
+    }
     Result::Ok(())
 }
@@ -0,0 +1,623 @@
+use crate::fetch;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+use std::str::Chars;
+
+pub const MANIFEST: &str = "circom.toml";
+pub const LOCKFILE: &str = "circom.lock";
+pub const SNAPSHOT: &str = "circom.snapshot";
+
+// The hashes of the files generated for each circuit, by their kind, as r1cs
+pub type Snapshot = BTreeMap<String, BTreeMap<String, String>>;
+
+// The values of toml used by the manifests, without dates
+enum Value {
+    Str(String),
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    List(Vec<Value>),
+    Table(Vec<(String, Value)>),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Str(s) => write!(f, "{:?}", s),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(x) => write!(f, "{}", x),
+            Value::List(values) => {
+                let values: Vec<_> = values.iter().map(Value::to_string).collect();
+                write!(f, "[{}]", values.join(", "))
+            }
+            Value::Table(entries) => {
+                let entries: Vec<_> = entries.iter().map(|(key, value)| format!("{} = {}", key, value)).collect();
+                write!(f, "{{ {} }}", entries.join(", "))
+            }
+        }
+    }
+}
+
+// The number of a toml integer, which can have a sign or a prefix 0x, 0o or
+// 0b, and _ between its digits
+fn integer(word: &str) -> Option<i64> {
+    let (negative, digits) = match word.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, word.strip_prefix('+').unwrap_or(word)),
+    };
+    let (radix, digits) = match digits.get(..2) {
+        Some("0x") => (16, &digits[2..]),
+        Some("0o") => (8, &digits[2..]),
+        Some("0b") => (2, &digits[2..]),
+        _ => (10, digits),
+    };
+    // the numbers with a prefix have no sign
+    if radix != 10 && word.starts_with(['+', '-']) {
+        return None;
+    }
+    if digits.starts_with('_') || digits.ends_with('_') || digits.contains("__") || digits.starts_with(['+', '-']) {
+        return None;
+    }
+    let digits = digits.replace('_', "");
+    let value = i64::from_str_radix(&digits, radix).ok()?;
+    Some(if negative { -value } else { value })
+}
+
+fn float(word: &str) -> Option<f64> {
+    let digits = word.trim_start_matches(['+', '-']);
+    if digits.starts_with(['.', '_']) || digits.ends_with(['.', '_']) || digits.contains("__") || digits.contains("._") {
+        return None;
+    }
+    match digits {
+        "inf" | "nan" => word.parse().ok(),
+        _ if digits.starts_with(char::is_alphabetic) => None,
+        _ => word.replace('_', "").parse().ok(),
+    }
+}
+
+struct Reader<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error<T>(&self, message: &str) -> Result<T, String> {
+        Err(format!("line {}: {}", self.line, message))
+    }
+
+    // Skips the spaces and the comments, and the ends of line if they are allowed
+    fn skip(&mut self, newlines: bool) {
+        while let Some(c) = self.chars.peek().copied() {
+            match c {
+                ' ' | '\t' | '\r' => {}
+                '\n' if newlines => self.line += 1,
+                '#' => {
+                    while self.chars.peek().is_some_and(|c| *c != '\n') {
+                        self.chars.next();
+                    }
+                    continue;
+                }
+                _ => return,
+            }
+            self.chars.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => self.error(&format!("expected {}", expected)),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => match self.chars.next() {
+                    Some('n') => string.push('\n'),
+                    Some('t') => string.push('\t'),
+                    Some(c @ ('"' | '\\')) => string.push(c),
+                    _ => return self.error("invalid escape sequence"),
+                },
+                Some('\n') | None => return self.error("unterminated string"),
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn bare_word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(c) = self.chars.peek().copied().filter(|c| c.is_alphanumeric() || *c == '_' || *c == '-') {
+            word.push(c);
+            self.chars.next();
+        }
+        word
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        match self.chars.peek() {
+            Some('"') => self.string(),
+            _ => match self.bare_word() {
+                word if word.is_empty() => self.error("expected a key"),
+                word => Ok(word),
+            },
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.chars.peek() {
+            Some('"') => Ok(Value::Str(self.string()?)),
+            Some('[') => {
+                self.chars.next();
+                let mut values = Vec::new();
+                loop {
+                    self.skip(true);
+                    if self.chars.peek() == Some(&']') {
+                        self.chars.next();
+                        return Ok(Value::List(values));
+                    }
+                    values.push(self.value()?);
+                    self.skip(true);
+                    match self.chars.next() {
+                        Some(',') => {}
+                        Some(']') => return Ok(Value::List(values)),
+                        _ => return self.error("expected , or ]"),
+                    }
+                }
+            }
+            Some('{') => {
+                self.chars.next();
+                let mut entries = Vec::new();
+                loop {
+                    self.skip(false);
+                    if self.chars.peek() == Some(&'}') {
+                        self.chars.next();
+                        return Ok(Value::Table(entries));
+                    }
+                    let key = self.key()?;
+                    self.skip(false);
+                    self.expect('=')?;
+                    self.skip(false);
+                    entries.push((key, self.value()?));
+                    self.skip(false);
+                    match self.chars.next() {
+                        Some(',') => {}
+                        Some('}') => return Ok(Value::Table(entries)),
+                        _ => return self.error("expected , or }"),
+                    }
+                }
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(c) = self.chars.peek().copied().filter(|c| c.is_alphanumeric() || "_+-.".contains(*c)) {
+                    word.push(c);
+                    self.chars.next();
+                }
+                match word.as_str() {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    _ => match (integer(&word), float(&word)) {
+                        (Some(i), _) => Ok(Value::Int(i)),
+                        (None, Some(x)) => Ok(Value::Float(x)),
+                        _ => self.error("expected a string, a boolean, a number, a list or a table"),
+                    },
+                }
+            }
+        }
+    }
+
+    // The entries of each section of the document
+    fn document(&mut self) -> Result<HashMap<String, Vec<(String, Value)>>, String> {
+        let mut sections: HashMap<String, Vec<(String, Value)>> = HashMap::new();
+        let mut section = String::new();
+        loop {
+            self.skip(true);
+            if self.chars.peek().is_none() {
+                return Ok(sections);
+            }
+            if self.chars.peek() == Some(&'[') {
+                self.chars.next();
+                section = self.key()?;
+                self.expect(']')?;
+            } else {
+                let key = self.key()?;
+                self.skip(false);
+                self.expect('=')?;
+                self.skip(false);
+                let value = self.value()?;
+                sections.entry(section.clone()).or_default().push((key, value));
+            }
+            self.skip(false);
+            match self.chars.peek() {
+                Some('\n') | None => {}
+                _ => return self.error("expected the end of the line"),
+            }
+        }
+    }
+}
+
+enum Source {
+    Path(String),
+    Registry(String),
+    // the url of the repository and the branch, tag or revision to use
+    Git(String, String),
+}
+
+struct Dependency {
+    name: String,
+    source: Source,
+}
+
+// A project with its sources, the default options of its compilation and
+// the circuit libraries it depends on
+pub struct Manifest {
+    dir: PathBuf,
+    main: Option<String>,
+    prime: Option<String>,
+    output: Option<String>,
+    registry: Option<String>,
+    roots: Vec<String>,
+    flags: Vec<String>,
+    dependencies: Vec<Dependency>,
+}
+
+fn string(key: &str, value: Value) -> Result<String, String> {
+    match value {
+        Value::Str(s) => Ok(s),
+        _ => Err(format!("{} must be a string, not {}", key, value)),
+    }
+}
+
+fn strings(key: &str, value: Value) -> Result<Vec<String>, String> {
+    match value {
+        Value::List(values) => values.into_iter().map(|v| string(key, v)).collect(),
+        _ => Err(format!("{} must be a list of strings, not {}", key, value)),
+    }
+}
+
+fn dependency(name: String, value: Value) -> Result<Dependency, String> {
+    let entries = match value {
+        Value::Str(version) => return Ok(Dependency { name, source: Source::Registry(version) }),
+        Value::Table(entries) => entries,
+        value => return Err(format!("the dependency {} must be a version or a table, not {}", name, value)),
+    };
+    let mut keys = HashMap::new();
+    for (key, value) in entries {
+        keys.insert(key.clone(), string(&key, value)?);
+    }
+    let mut remove = |key: &str| keys.remove(key);
+    let source = match (remove("path"), remove("version"), remove("git")) {
+        (Some(path), None, None) => Source::Path(path),
+        (None, Some(version), None) => Source::Registry(version),
+        (None, None, Some(url)) => {
+            let reference = match (remove("rev"), remove("tag"), remove("branch")) {
+                (None, None, None) => String::new(),
+                (Some(reference), None, None) | (None, Some(reference), None) | (None, None, Some(reference)) => reference,
+                _ => return Err(format!("the dependency {} must have only one of rev, tag and branch", name)),
+            };
+            Source::Git(url, reference)
+        }
+        _ => return Err(format!("the dependency {} must have a path, a version or a git repository", name)),
+    };
+    match keys.keys().next() {
+        Some(key) => Err(format!("unknown key {} in the dependency {}", key, name)),
+        None => Ok(Dependency { name, source }),
+    }
+}
+
+// A library fetched from a repository, as recorded in the lockfile
+#[derive(Clone, PartialEq)]
+struct Locked {
+    url: String,
+    reference: String,
+    revision: String,
+}
+
+fn read_lockfile(path: &Path) -> Result<BTreeMap<String, Locked>, String> {
+    let mut locked = BTreeMap::new();
+    if !path.is_file() {
+        return Ok(locked);
+    }
+    let in_file = |e: String| format!("{}: {}", path.display(), e);
+    let src = std::fs::read_to_string(path).map_err(|e| in_file(e.to_string()))?;
+    let mut reader = Reader { chars: src.chars().peekable(), line: 1 };
+    let mut sections = reader.document().map_err(in_file)?;
+    for (name, value) in sections.remove("git").unwrap_or_default() {
+        let mut entry = Locked { url: String::new(), reference: String::new(), revision: String::new() };
+        match value {
+            Value::Table(entries) => {
+                for (key, value) in entries {
+                    let value = string(&key, value).map_err(in_file)?;
+                    match key.as_str() {
+                        "url" => entry.url = value,
+                        "reference" => entry.reference = value,
+                        "revision" => entry.revision = value,
+                        _ => return Err(in_file(format!("unknown key {} in {}", key, name))),
+                    }
+                }
+            }
+            _ => return Err(in_file(format!("{} must be a table", name))),
+        }
+        locked.insert(name, entry);
+    }
+    Ok(locked)
+}
+
+fn write_lockfile(path: &Path, locked: &BTreeMap<String, Locked>) -> Result<(), String> {
+    let quoted = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut src = String::from("# This file is generated by circom to fetch the same revisions of the libraries\n");
+    src.push_str("# in every build. It is updated when the dependencies of circom.toml change.\n\n[git]\n");
+    for (name, entry) in locked {
+        src.push_str(&format!(
+            "{} = {{ url = {}, reference = {}, revision = {} }}\n",
+            quoted(name),
+            quoted(&entry.url),
+            quoted(&entry.reference),
+            quoted(&entry.revision)
+        ));
+    }
+    std::fs::write(path, src).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// The snapshot has a section for each circuit with the hashes of its files
+pub fn read_snapshot(path: &Path) -> Result<Snapshot, String> {
+    let mut snapshot = Snapshot::new();
+    let in_file = |e: String| format!("{}: {}", path.display(), e);
+    let src = std::fs::read_to_string(path).map_err(|e| in_file(e.to_string()))?;
+    let mut reader = Reader { chars: src.chars().peekable(), line: 1 };
+    for (circuit, entries) in reader.document().map_err(in_file)? {
+        let mut hashes = BTreeMap::new();
+        for (kind, value) in entries {
+            hashes.insert(kind.clone(), string(&kind, value).map_err(in_file)?);
+        }
+        snapshot.insert(circuit, hashes);
+    }
+    Ok(snapshot)
+}
+
+pub fn write_snapshot(path: &Path, snapshot: &Snapshot) -> Result<(), String> {
+    let quoted = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut src = String::from("# This file is generated by circom --snapshot with the hashes of the files of each\n");
+    src.push_str("# circuit. circom --check_snapshots fails if any of them changes.\n");
+    let key = |s: &str| {
+        let bare = !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+        if bare { s.to_string() } else { quoted(s) }
+    };
+    for (circuit, hashes) in snapshot {
+        src.push_str(&format!("\n[{}]\n", key(circuit)));
+        for (kind, hash) in hashes {
+            src.push_str(&format!("{} = {}\n", kind, quoted(hash)));
+        }
+    }
+    std::fs::write(path, src).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// The state of the resolution of the dependencies of a project
+struct Resolver {
+    registry: PathBuf,
+    cache: PathBuf,
+    locked: BTreeMap<String, Locked>,
+    // the revisions fetched in this resolution, which replace the lockfile
+    fetched: BTreeMap<String, Locked>,
+    resolved: HashMap<String, PathBuf>,
+    libraries: Vec<PathBuf>,
+}
+
+impl Resolver {
+    fn fetch(&mut self, name: &str, url: &str, reference: &str) -> Result<PathBuf, String> {
+        let locked = self
+            .locked
+            .get(name)
+            .filter(|locked| locked.url == url && locked.reference == reference)
+            .map(|locked| locked.revision.as_str());
+        let (dir, revision) = fetch::fetch_git(&self.cache, name, url, reference, locked)
+            .map_err(|e| format!("cannot fetch the dependency {}: {}", name, e))?;
+        let entry = Locked { url: url.to_string(), reference: reference.to_string(), revision };
+        self.fetched.insert(name.to_string(), entry);
+        Ok(dir)
+    }
+}
+
+impl Manifest {
+    pub fn read(path: &Path) -> Result<Manifest, String> {
+        let src = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut reader = Reader { chars: src.chars().peekable(), line: 1 };
+        let mut sections = reader.document().map_err(|e| format!("{}: {}", path.display(), e))?;
+        let dir = path.parent().map_or(PathBuf::new(), Path::to_path_buf);
+        let mut manifest = Manifest {
+            dir,
+            main: None,
+            prime: None,
+            output: None,
+            registry: None,
+            roots: Vec::new(),
+            flags: Vec::new(),
+            dependencies: Vec::new(),
+        };
+        let in_file = |e: String| format!("{}: {}", path.display(), e);
+        for (key, value) in sections.remove("package").unwrap_or_default() {
+            match key.as_str() {
+                "name" | "version" => {}
+                "main" => manifest.main = Some(string(&key, value).map_err(in_file)?),
+                "prime" => manifest.prime = Some(string(&key, value).map_err(in_file)?),
+                "output" => manifest.output = Some(string(&key, value).map_err(in_file)?),
+                "registry" => manifest.registry = Some(string(&key, value).map_err(in_file)?),
+                "roots" => manifest.roots = strings(&key, value).map_err(in_file)?,
+                "flags" => manifest.flags = strings(&key, value).map_err(in_file)?,
+                _ => return Err(in_file(format!("unknown key {} in [package]", key))),
+            }
+        }
+        for (name, value) in sections.remove("dependencies").unwrap_or_default() {
+            manifest.dependencies.push(dependency(name, value).map_err(in_file)?);
+        }
+        if let Some(section) = sections.keys().find(|s| !s.is_empty()) {
+            return Err(in_file(format!("unknown section [{}]", section)));
+        }
+        if sections.contains_key("") {
+            return Err(in_file("the keys must be inside a section".to_string()));
+        }
+        Ok(manifest)
+    }
+
+    // The manifest of the current directory, if there is one
+    pub fn find() -> Result<Option<Manifest>, String> {
+        let path = Path::new(MANIFEST);
+        if path.is_file() {
+            Manifest::read(path).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn path(&self, relative: &str) -> PathBuf {
+        self.dir.join(relative)
+    }
+
+    pub fn main(&self) -> Option<PathBuf> {
+        self.main.as_ref().map(|main| self.path(main))
+    }
+
+    // The options of the command line given by the manifest
+    pub fn options(&self) -> Vec<String> {
+        let mut options = Vec::new();
+        if let Some(prime) = &self.prime {
+            options.push(format!("--prime={}", prime));
+        }
+        if let Some(output) = &self.output {
+            options.push(format!("--output={}", self.path(output).display()));
+        }
+        options.extend(self.flags.iter().cloned());
+        options
+    }
+
+    fn source_roots(&self) -> Vec<PathBuf> {
+        if self.roots.is_empty() {
+            vec![self.dir.clone()]
+        } else {
+            self.roots.iter().map(|root| self.path(root)).collect()
+        }
+    }
+
+    // The directories of the registry are name/version
+    fn registry(&self) -> PathBuf {
+        match (&self.registry, std::env::var_os("CIRCOM_REGISTRY"), std::env::var_os("HOME")) {
+            (Some(registry), _, _) => self.path(registry),
+            (None, Some(registry), _) => PathBuf::from(registry),
+            (None, None, home) => PathBuf::from(home.unwrap_or_default()).join(".circom").join("registry"),
+        }
+    }
+
+    // The directories where the includes are searched: the source roots of
+    // the project and of every library it depends on. The revisions of the
+    // libraries fetched from repositories are recorded in the lockfile
+    pub fn libraries(&self) -> Result<Vec<PathBuf>, String> {
+        let lockfile = self.path(LOCKFILE);
+        let mut resolver = Resolver {
+            registry: self.registry(),
+            cache: fetch::cache(),
+            locked: read_lockfile(&lockfile)?,
+            fetched: BTreeMap::new(),
+            resolved: HashMap::new(),
+            libraries: self.source_roots(),
+        };
+        self.resolve(&mut resolver)?;
+        if resolver.fetched != resolver.locked && (lockfile.exists() || !resolver.fetched.is_empty()) {
+            write_lockfile(&lockfile, &resolver.fetched)?;
+        }
+        Ok(resolver.libraries)
+    }
+
+    fn resolve(&self, resolver: &mut Resolver) -> Result<(), String> {
+        for dependency in &self.dependencies {
+            let dir = match &dependency.source {
+                Source::Path(path) => self.path(path),
+                Source::Registry(version) => resolver.registry.join(&dependency.name).join(version),
+                Source::Git(url, reference) => match resolver.fetched.get(&dependency.name) {
+                    // it is only fetched the first time it is found
+                    Some(f) if f.url == *url && f.reference == *reference => resolver.resolved[&dependency.name].clone(),
+                    _ => resolver.fetch(&dependency.name, url, reference)?,
+                },
+            };
+            let dir = match std::fs::canonicalize(&dir) {
+                Ok(dir) if dir.is_dir() => dir,
+                _ => return Err(format!("the dependency {} is not found in {}", dependency.name, dir.display())),
+            };
+            match resolver.resolved.get(&dependency.name) {
+                Some(previous) if *previous == dir => continue,
+                Some(previous) => {
+                    return Err(format!(
+                        "the dependency {} is found both in {} and in {}",
+                        dependency.name,
+                        previous.display(),
+                        dir.display()
+                    ))
+                }
+                None => {}
+            }
+            resolver.resolved.insert(dependency.name.clone(), dir.clone());
+            let manifest = dir.join(MANIFEST);
+            if manifest.is_file() {
+                let manifest = Manifest::read(&manifest)?;
+                resolver.libraries.extend(manifest.source_roots());
+                manifest.resolve(resolver)?;
+            } else {
+                resolver.libraries.push(dir);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Reader, Value};
+
+    fn value(src: &str) -> Result<Value, String> {
+        let mut reader = Reader { chars: src.chars().peekable(), line: 1 };
+        reader.value()
+    }
+
+    #[test]
+    fn scalars_are_read() {
+        for (src, expected) in [
+            ("\"bn128\"", "\"bn128\""),
+            ("true", "true"),
+            ("false", "false"),
+            ("42", "42"),
+            ("-17", "-17"),
+            ("+1_000", "1000"),
+            ("0xff", "255"),
+            ("0o17", "15"),
+            ("0b101", "5"),
+            ("3.5", "3.5"),
+            ("-1e3", "-1000"),
+            ("inf", "inf"),
+            ("[1, true, \"a\"]", "[1, true, \"a\"]"),
+            ("{ version = 2, optional = false }", "{ version = 2, optional = false }"),
+        ] {
+            assert_eq!(value(src).map(|value| value.to_string()), Ok(expected.to_string()), "{}", src);
+        }
+    }
+
+    #[test]
+    fn invalid_scalars_are_errors() {
+        for src in ["tru", "1_", "1__0", "_1", "-0xff", "1.", ".5", "0x", "0.1.0", "nil"] {
+            assert!(value(src).is_err(), "{} is a value", src);
+        }
+    }
+
+    #[test]
+    fn manifests_report_the_values_of_the_wrong_type() {
+        let src = "[package]\nversion = 1\nprime = 2\n";
+        let mut reader = Reader { chars: src.chars().peekable(), line: 1 };
+        let mut sections = reader.document().unwrap();
+        let (key, prime) = sections.remove("package").unwrap().pop().unwrap();
+        assert_eq!(super::string(&key, prime), Err("prime must be a string, not 2".to_string()));
+    }
+}
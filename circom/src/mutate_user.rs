@@ -0,0 +1,169 @@
+use ansi_term::Colour;
+use circom_algebra::algebra::Constraint;
+use circom_algebra::modular_arithmetic as field_ops;
+use circom_algebra::num_bigint::BigInt;
+use circom_algebra::num_traits::Zero;
+use dag::ConstraintOrigin;
+use program_structure::file_definition::FileLibrary;
+use std::collections::BTreeSet;
+use std::path::Path;
+use crate::format_user::circom_files;
+use crate::test_user::{prepare_test, read_tests, test_program, TestConfig, TestRun};
+
+// The ways of weakening the constraints of a statement
+#[derive(Copy, Clone)]
+enum Mutation {
+    Drop,
+    NegateCoefficient,
+}
+
+impl Mutation {
+    const ALL: [Mutation; 2] = [Mutation::Drop, Mutation::NegateCoefficient];
+
+    fn description(&self) -> &'static str {
+        match self {
+            Mutation::Drop => "drop",
+            Mutation::NegateCoefficient => "negate a coefficient of",
+        }
+    }
+}
+
+struct Summary {
+    killed: usize,
+    survived: usize,
+}
+
+// Weakens each statement that generates constraints in the tests of the
+// files, or of the circom files in the directories, and runs the tests with
+// the weakened constraints. A mutant that no test detects is a change of
+// the constraints that the tests would not catch
+pub fn run_mutations(config: TestConfig) -> Result<(), ()> {
+    let mut files = Vec::new();
+    for file in &config.files {
+        circom_files(file, &mut files)?;
+    }
+    let mut summary = Summary { killed: 0, survived: 0 };
+    for file in &files {
+        mutate_file(file, &config, &mut summary)?;
+    }
+    let total = summary.killed + summary.survived;
+    if total == 0 {
+        println!("{}", Colour::Yellow.paint("There are no constraints generated by passing tests to mutate"));
+        return Result::Ok(());
+    }
+    let result = format!(
+        "mutation score: {} of {} mutants killed ({:.1}%)",
+        summary.killed,
+        total,
+        100.0 * summary.killed as f64 / total as f64
+    );
+    if summary.survived > 0 {
+        println!("{}", Colour::Yellow.paint(result));
+    } else {
+        println!("{}", Colour::Green.paint(result));
+    }
+    Result::Ok(())
+}
+
+fn mutate_file(file: &Path, config: &TestConfig, summary: &mut Summary) -> Result<(), ()> {
+    let (source, tests) = read_tests(file, &config.prime)?;
+    if tests.is_empty() {
+        return Result::Ok(());
+    }
+    let program_archive = test_program(file, &source, &tests, config)?;
+    // only the tests that pass can detect a mutant
+    let mut runs = Vec::new();
+    for (index, test) in tests.iter().enumerate() {
+        match prepare_test(&program_archive, index, test, &config.prime) {
+            Ok(run) if run.check(&run.instance.constraints).is_empty() => runs.push((&test.name, run)),
+            _ => println!("test {} ... {}, it is not used", test.name, Colour::Yellow.paint("FAILED")),
+        }
+    }
+    let origins: BTreeSet<_> = runs
+        .iter()
+        .flat_map(|(_, run)| run.instance.origins.iter())
+        .filter_map(|origin| origin.as_ref().map(|(file_id, location)| (*file_id, location.start, location.end)))
+        .collect();
+    if origins.is_empty() {
+        return Result::Ok(());
+    }
+    println!("mutating {} constraint statements of the tests of {}", origins.len(), file.display());
+    for (file_id, start, end) in origins {
+        let origin: ConstraintOrigin = Some((file_id, start..end));
+        let statement = describe(&program_archive.file_library, file_id, start, end);
+        for mutation in Mutation::ALL {
+            let verdicts: Vec<_> = runs.iter().map(|(name, run)| (name, is_killed(run, &origin, mutation))).collect();
+            if verdicts.iter().all(|(_, killed)| killed.is_none()) {
+                continue;
+            }
+            match verdicts.iter().find(|(_, killed)| *killed == Some(true)) {
+                Some((name, _)) => {
+                    summary.killed += 1;
+                    println!("mutant {} {} ... {} by test {}", mutation.description(), statement, Colour::Green.paint("killed"), name);
+                }
+                None => {
+                    summary.survived += 1;
+                    println!("mutant {} {} ... {}", mutation.description(), statement, Colour::Red.paint("SURVIVED"));
+                }
+            }
+        }
+    }
+    Result::Ok(())
+}
+
+// Whether the test fails with the constraints of the statement mutated, if
+// the test has them and the mutation changes them
+fn is_killed(run: &TestRun, origin: &ConstraintOrigin, mutation: Mutation) -> Option<bool> {
+    let instance = &run.instance;
+    let mut constraints = Vec::new();
+    let mut mutated = false;
+    for (constraint, constraint_origin) in instance.constraints.iter().zip(&instance.origins) {
+        if constraint_origin != origin {
+            constraints.push(constraint.clone());
+            continue;
+        }
+        match mutation {
+            Mutation::Drop => mutated = true,
+            Mutation::NegateCoefficient => match negate_coefficient(constraint, &instance.field) {
+                Some(negated) => {
+                    constraints.push(negated);
+                    mutated = true;
+                }
+                None => constraints.push(constraint.clone()),
+            },
+        }
+    }
+    if mutated {
+        Some(!run.check(&constraints).is_empty())
+    } else {
+        None
+    }
+}
+
+// The constraint with the coefficient of the first term of a sum negated,
+// in its linear part or else in a factor of its product. Negating a factor
+// without a sum keeps the signals that satisfy the constraint, and it is
+// not a mutant
+fn negate_coefficient(constraint: &Constraint<usize>, field: &BigInt) -> Option<Constraint<usize>> {
+    let mut parts = [constraint.c().clone(), constraint.b().clone(), constraint.a().clone()];
+    let part = parts.iter_mut().find(|part| part.len() > 1)?;
+    let signal = *part.keys().min()?;
+    let coefficient = field_ops::sub(&BigInt::zero(), &part[&signal], field);
+    part.insert(signal, coefficient);
+    let [c, b, a] = parts;
+    Some(Constraint::new(a, b, c))
+}
+
+// The file, the line and the first line of the code of a statement
+fn describe(file_library: &FileLibrary, file_id: usize, start: usize, end: usize) -> String {
+    let name = file_library.get_file_name(file_id).unwrap_or("").trim_matches('"');
+    let name = Path::new(name).file_name().map_or(name.to_string(), |name| name.to_string_lossy().to_string());
+    let line = file_library.get_line(start, file_id).unwrap_or(0);
+    let code = file_library
+        .get_source(file_id)
+        .and_then(|source| source.get(start..end))
+        .and_then(|code| code.lines().next())
+        .unwrap_or("")
+        .trim();
+    format!("the constraint of {}:{} `{}`", name, line, code)
+}
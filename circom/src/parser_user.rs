@@ -1,30 +1,61 @@
 use super::input_user::Input;
-use program_structure::constants::UsefulConstants;
+use ansi_term::Colour;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use parser::{ParseCache, ParserConfig};
 use program_structure::error_definition::Report;
 use program_structure::program_archive::ProgramArchive;
 use crate::VERSION;
 
 
 pub fn parse_project(input_info: &Input) -> Result<ProgramArchive, ()> {
+    parse_project_with_cache(input_info, &mut ParseCache::new())
+}
+
+// The parsing of the project that reuses the files of the previous ones that
+// do not change, as in the compilations of --watch
+pub fn parse_project_with_cache(input_info: &Input, cache: &mut ParseCache) -> Result<ProgramArchive, ()> {
     let initial_file = input_info.input_file().to_string();
-    //We get the prime number from the input
-    let prime = UsefulConstants::new(&input_info.prime()).get_p().clone();
     let flag_no_init = input_info.flag_no_init();
-    let result_program_archive = parser::run_parser(
-        initial_file, 
-        VERSION, 
-        input_info.get_link_libraries().to_vec(), 
-        &prime,
-        flag_no_init
-    );
+    let sources = template_sources(input_info)?;
+    let prime = input_info.prime();
+    let config = ParserConfig {
+        version: VERSION,
+        link_libraries: input_info.get_link_libraries().to_vec(),
+        prime: &prime,
+        flag_prime: input_info.prime_flag(),
+        flag_no_init,
+        flag_enforce_tags: input_info.flag_enforce_tags(),
+        features: input_info.features(),
+    };
+    let result_program_archive = parser::run_parser_with_cache(initial_file, &config, &sources, cache);
     match result_program_archive {
-        Result::Err((file_library, report_collection)) => {
+        Result::Err((mut file_library, report_collection)) => {
+            file_library.set_lint_levels(input_info.lint_levels().clone());
+            file_library.set_message_format(input_info.message_format());
             Report::print_reports(&report_collection, &file_library);
             Result::Err(())
         }
-        Result::Ok((program_archive, warnings)) => {
-            Report::print_reports(&warnings, &program_archive.file_library);
+        Result::Ok((mut program_archive, warnings)) => {
+            program_archive.file_library.set_lint_levels(input_info.lint_levels().clone());
+            program_archive.file_library.set_message_format(input_info.message_format());
+            Report::print_warnings(&warnings, &program_archive.file_library).map_err(|_denied| {})?;
             Result::Ok(program_archive)
         }
     }
 }
+
+// The input file with the template given with --template as a main component
+// of it, named so that it is the only one compiled
+fn template_sources(input_info: &Input) -> Result<HashMap<PathBuf, String>, ()> {
+    let mut sources = HashMap::new();
+    if let (Some(call), Some(name)) = (input_info.template_call(), input_info.template_main()) {
+        let file = input_info.input_file();
+        let mut source = std::fs::read_to_string(file).map_err(|error| {
+            eprintln!("{}", Colour::Red.paint(format!("Could not read {}: {}", file, error)));
+        })?;
+        source.push_str(&format!("\ncomponent main {} = {};\n", name, call));
+        sources.insert(std::fs::canonicalize(file).unwrap_or_else(|_| PathBuf::from(file)), source);
+    }
+    Result::Ok(sources)
+}
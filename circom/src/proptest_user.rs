@@ -0,0 +1,296 @@
+use ansi_term::Colour;
+use circom_algebra::modular_arithmetic as field_ops;
+use circom_algebra::num_bigint::BigInt;
+use circom_algebra::num_traits::{One, Zero};
+use circom_algebra::range_analysis::Range;
+use program_structure::ast::Definition;
+use program_structure::error_definition::Report;
+use program_structure::file_definition::FileLibrary;
+use program_structure::program_archive::ProgramArchive;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::format_user::circom_files;
+use crate::test_user::{instantiate, parse_program, select, witness_failure, Instance, ENSURE};
+
+// The number of witnesses computed to shrink a counterexample
+const SHRINK_ATTEMPTS: usize = 1000;
+
+pub struct ProptestConfig {
+    pub files: Vec<PathBuf>,
+    pub link_libraries: Vec<PathBuf>,
+    pub prime: String,
+    pub cases: usize,
+    pub seed: Option<u64>,
+    pub templates: Vec<String>,
+}
+
+struct Summary {
+    passed: usize,
+    failed: usize,
+    skipped: usize,
+}
+
+// The generator of the random inputs (splitmix64), which is enough for them
+// and repeats a run with its seed
+struct Random {
+    state: u64,
+}
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // A number in [0, bound), with 64 more bits than the bound so that the
+    // bias of the modulo is negligible
+    fn below(&mut self, bound: &BigInt) -> BigInt {
+        let mut value = BigInt::zero();
+        for _ in 0..bound.bits() / 64 + 2 {
+            value = (value << 64) + BigInt::from(self.next());
+        }
+        value % bound
+    }
+}
+
+// An input of the main component: its position in the witness, its name
+// and the range that the constraints give to it
+struct Input {
+    position: usize,
+    name: String,
+    range: Option<Range>,
+}
+
+fn property_main(index: usize) -> String {
+    format!("$property_{}", index)
+}
+
+// Checks the properties of the templates of the files, or of the circom files
+// in the directories, and fails if any of them does not hold
+pub fn run_properties(config: ProptestConfig) -> Result<(), ()> {
+    let mut files = Vec::new();
+    for file in &config.files {
+        circom_files(file, &mut files)?;
+    }
+    let seed = config.seed.unwrap_or_else(|| {
+        SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_nanos() as u64).unwrap_or_default()
+    });
+    let mut random = Random { state: seed };
+    let mut summary = Summary { passed: 0, failed: 0, skipped: 0 };
+    for file in &files {
+        run_file_properties(file, &config, &mut random, &mut summary);
+    }
+    let result = format!(
+        "proptest result: {} passed, {} failed, {} skipped (seed {})",
+        summary.passed, summary.failed, summary.skipped, seed
+    );
+    if summary.failed > 0 {
+        eprintln!("{}", Colour::Red.paint(result));
+        Result::Err(())
+    } else {
+        println!("{}", Colour::Green.paint(result));
+        Result::Ok(())
+    }
+}
+
+// The templates of a file are checked as main components of it, the ones
+// given or else the ones that need no arguments
+fn run_file_properties(file: &Path, config: &ProptestConfig, random: &mut Random, summary: &mut Summary) {
+    let source = match std::fs::read_to_string(file) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("{}", Colour::Red.paint(format!("Could not read {}: {}", file.display(), e)));
+            summary.failed += 1;
+            return;
+        }
+    };
+    let definitions = match parser::parse_definitions(&source, 0, &config.prime, &[ENSURE.to_string()]) {
+        Ok(ast) => ast.definitions,
+        Err(reports) => {
+            let mut file_library = FileLibrary::new();
+            file_library.add_file(file.display().to_string(), source);
+            Report::print_reports(&reports, &file_library);
+            summary.failed += 1;
+            return;
+        }
+    };
+    let mut calls = config.templates.clone();
+    if calls.is_empty() {
+        for definition in &definitions {
+            if let Definition::Template { name, args, defaults, .. } = definition {
+                if args.len() == defaults.len() {
+                    calls.push(format!("{}()", name));
+                } else {
+                    println!("property {} ... skipped, it has parameters", name);
+                    summary.skipped += 1;
+                }
+            }
+        }
+    }
+    if calls.is_empty() {
+        return;
+    }
+    println!("checking {} templates of {}", calls.len(), file.display());
+    let mut program = source.clone();
+    for (index, call) in calls.iter().enumerate() {
+        program.push_str(&format!("\ncomponent main {} = {};", property_main(index), call));
+    }
+    let program_archive = match parse_program(file, program, &config.link_libraries, &config.prime) {
+        Ok(program_archive) => program_archive,
+        Err(()) => {
+            for call in &calls {
+                println!("property {} ... {}", call, Colour::Red.paint("FAILED"));
+            }
+            summary.failed += calls.len();
+            return;
+        }
+    };
+    for (index, call) in calls.iter().enumerate() {
+        if check_property(&program_archive, index, call, config, random) {
+            summary.passed += 1;
+        } else {
+            summary.failed += 1;
+        }
+    }
+}
+
+// Computes the witness of the template for random inputs until one of them
+// fails, which is shrunk and printed as a counterexample
+fn check_property(program_archive: &ProgramArchive, index: usize, call: &str, config: &ProptestConfig, random: &mut Random) -> bool {
+    let fail = |reasons: &[String]| {
+        println!("property {} ... {}", call, Colour::Red.paint("FAILED"));
+        for reason in reasons {
+            println!("  {}", reason);
+        }
+        false
+    };
+    let program_archive = match select(program_archive, &property_main(index)) {
+        Ok(program_archive) => program_archive,
+        Err(()) => return fail(&["the template could not be instantiated".to_string()]),
+    };
+    let instance = match instantiate(program_archive, &config.prime) {
+        Ok(instance) => instance,
+        Err(message) => return fail(&[message]),
+    };
+    let inputs = main_inputs(&instance);
+    // without inputs there is only one witness to compute
+    let cases = if inputs.is_empty() { 1 } else { config.cases };
+    for case in 1..=cases {
+        let values: Vec<_> = inputs.iter().map(|input| sample(random, input.range.as_ref(), &instance.field)).collect();
+        let failures = run_case(&instance, &inputs, &values);
+        if !failures.is_empty() {
+            let (values, failures) = shrink(&instance, &inputs, values, failures);
+            println!("property {} ... {} after {} cases", call, Colour::Red.paint("FAILED"), case);
+            println!("  counterexample:");
+            for (input, value) in inputs.iter().zip(&values) {
+                println!("    {} = {}", input.name, value);
+            }
+            for failure in failures {
+                println!("  {}", failure);
+            }
+            return false;
+        }
+    }
+    println!("property {} ... {}, {} cases", call, Colour::Green.paint("ok"), cases);
+    true
+}
+
+// The inputs of the main component by their positions, where the signals
+// of the main component are the first ones of the dag after the entry
+fn main_inputs(instance: &Instance) -> Vec<Input> {
+    let positions: BTreeSet<usize> = instance
+        .circuit
+        .c_producer
+        .get_main_input_list()
+        .iter()
+        .flat_map(|input| input.start..input.start + input.size)
+        .collect();
+    let signals: HashMap<usize, usize> =
+        instance.dag.memory_positions.iter().map(|(signal, position)| (*position, *signal)).collect();
+    let entry = instance.dag.get_entry().unwrap();
+    let mut ranges = instance.dag.signal_ranges().remove(&entry.get_goes_to()).unwrap_or_default();
+    positions
+        .into_iter()
+        .map(|position| {
+            let signal = *signals.get(&position).unwrap_or(&position);
+            let name = instance.names.get(&signal).cloned().unwrap_or_else(|| format!("signal {}", signal));
+            let range = signal.checked_sub(entry.get_in()).and_then(|local| ranges.remove(&local));
+            Input { position, name, range: range.filter(|range| range.min <= range.max) }
+        })
+        .collect()
+}
+
+// A value in the range of the input, or else in the field, where the ends
+// and the small values are more likely than the others
+fn sample(random: &mut Random, range: Option<&Range>, field: &BigInt) -> BigInt {
+    let value = match range {
+        Some(range) => match random.next() % 8 {
+            0 => range.min.clone(),
+            1 => range.max.clone(),
+            _ => &range.min + random.below(&(&range.max - &range.min + BigInt::one())),
+        },
+        None => match random.next() % 8 {
+            0 => {
+                let edges = [BigInt::zero(), BigInt::one(), BigInt::from(2), field - 1, field - 2];
+                edges[(random.next() % 5) as usize].clone()
+            }
+            1..=3 => random.below(&BigInt::from(256)),
+            _ => random.below(field),
+        },
+    };
+    field_ops::add(&value, &BigInt::zero(), field)
+}
+
+// The reasons why the witness of the values fails: an assertion, as the
+// ones of the ensure statements, or a constraint that does not hold
+fn run_case(instance: &Instance, inputs: &[Input], values: &[BigInt]) -> Vec<String> {
+    let given: Vec<_> = inputs.iter().map(|input| input.position).zip(values.iter().cloned()).collect();
+    let calculation = witness_calculation::calculate_witness(&instance.circuit, &given);
+    let mut failures: Vec<_> = witness_failure(&calculation).into_iter().collect();
+    failures.extend(instance.failed_constraints(&calculation));
+    failures
+}
+
+// Moves each value towards the lower end of its range, or towards 0 from
+// either side for the ones of the whole field, while the witness still
+// fails, to find a smaller counterexample
+fn shrink(instance: &Instance, inputs: &[Input], mut values: Vec<BigInt>, mut failures: Vec<String>) -> (Vec<BigInt>, Vec<String>) {
+    let field = &instance.field;
+    let mut attempts = 0;
+    let mut changed = true;
+    while changed && attempts < SHRINK_ATTEMPTS {
+        changed = false;
+        for index in 0..values.len() {
+            let (value, target) = match &inputs[index].range {
+                Some(range) => (values[index].clone(), range.min.clone()),
+                None if values[index] > field / 2 => (&values[index] - field, BigInt::zero()),
+                None => (values[index].clone(), BigInt::zero()),
+            };
+            if value == target {
+                continue;
+            }
+            let step = if value > target { BigInt::one() } else { -BigInt::one() };
+            let candidates = [target.clone(), &target + (&value - &target) / 2, &value - step];
+            for candidate in candidates.iter().filter(|candidate| **candidate != value) {
+                if attempts >= SHRINK_ATTEMPTS {
+                    break;
+                }
+                attempts += 1;
+                let mut smaller = values.clone();
+                smaller[index] = field_ops::add(candidate, &BigInt::zero(), field);
+                let smaller_failures = run_case(instance, inputs, &smaller);
+                if !smaller_failures.is_empty() {
+                    values = smaller;
+                    failures = smaller_failures;
+                    changed = true;
+                    break;
+                }
+            }
+        }
+    }
+    (values, failures)
+}
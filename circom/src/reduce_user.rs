@@ -0,0 +1,207 @@
+use super::ice::ICE_MARKER;
+use ansi_term::Colour;
+use std::collections::VecDeque;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+pub struct ReduceConfig {
+    // the arguments of the compilation that panics, without circom
+    pub command: Vec<String>,
+    pub input: PathBuf,
+    pub output: Option<PathBuf>,
+}
+
+// Copies the files of the program in the output directory and removes their
+// lines while the compiler keeps panicking with the same message at the same
+// place of its code
+pub fn reduce(config: ReduceConfig) -> Result<(), ()> {
+    let input = config.input;
+    let root = input.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new(".")).to_path_buf();
+    let files = program_files(&input, &root);
+    let output = match config.output {
+        Some(output) => output,
+        None => PathBuf::from(format!("{}_reduced", input.file_stem().unwrap().to_string_lossy())),
+    };
+    for file in files.iter() {
+        let copy = output.join(file);
+        let copied = std::fs::create_dir_all(copy.parent().unwrap()).and_then(|_| std::fs::copy(root.join(file), &copy));
+        if let Err(error) = copied {
+            eprintln!("{}", Colour::Red.paint(format!("Could not copy {} to {}: {}", file.display(), output.display(), error)));
+            return Result::Err(());
+        }
+    }
+    let build = output.join("build");
+    if std::fs::create_dir_all(&build).is_err() {
+        eprintln!("{}", Colour::Red.paint(format!("Could not create {}", build.display())));
+        return Result::Err(());
+    }
+    let args = reduced_command(&config.command, &input, &output.join(&files[0]), &root);
+    let mut compile_args = args.clone();
+    compile_args.extend(["-o".to_string(), build.to_string_lossy().to_string()]);
+    let compiler = std::env::current_exe().map_err(|error| {
+        eprintln!("{}", Colour::Red.paint(format!("Could not find the compiler: {}", error)));
+    })?;
+
+    let start = Instant::now();
+    let signature = run_compiler(&compiler, &compile_args, None).as_deref().and_then(panic_signature);
+    let Some(signature) = signature else {
+        eprintln!("{}", Colour::Red.paint("The compiler does not panic with the program, there is nothing to reduce"));
+        let _ = std::fs::remove_dir_all(&build);
+        return Result::Err(());
+    };
+    // the reductions that make the compiler loop forever are discarded
+    let timeout = (start.elapsed() * 10).max(Duration::from_secs(5));
+    println!("Reducing the program that makes the compiler panic with:\n{}", signature);
+    let mut still_panics = || {
+        run_compiler(&compiler, &compile_args, Some(timeout)).as_deref().and_then(panic_signature).as_ref() == Some(&signature)
+    };
+    // the lines of a file can be needed only once the other files are reduced
+    let mut reduced = true;
+    while reduced {
+        reduced = false;
+        for file in files.iter() {
+            let (before, after) = reduce_file(&output.join(file), &mut still_panics)?;
+            if after < before {
+                println!("{}", Colour::Green.paint(format!("Reduced {} from {} to {} lines", file.display(), before, after)));
+                reduced = true;
+            }
+        }
+    }
+    let _ = std::fs::remove_dir_all(&build);
+    println!("The program that reproduces the panic is in {}, compiled with:", output.display());
+    println!("  circom {}", args.join(" "));
+    Result::Ok(())
+}
+
+// The main file and the files it includes that are in its directory, relative
+// to it, as the ones outside are found through the directory of the main file
+fn program_files(main: &Path, root: &Path) -> Vec<PathBuf> {
+    let root = std::fs::canonicalize(root).unwrap_or(root.to_path_buf());
+    let mut files = vec![main.file_name().unwrap().into()];
+    let mut pending: VecDeque<PathBuf> = files.iter().cloned().collect();
+    while let Some(file) = pending.pop_front() {
+        let Ok(code) = std::fs::read_to_string(root.join(&file)) else {
+            continue;
+        };
+        for line in code.lines().map(str::trim).filter(|line| line.starts_with("include")) {
+            let Some(included) = line.split('"').nth(1) else {
+                continue;
+            };
+            let path = root.join(&file).parent().unwrap().join(included);
+            let relative = std::fs::canonicalize(&path).ok().and_then(|path| {
+                path.strip_prefix(&root).ok().map(Path::to_path_buf)
+            });
+            if let Some(relative) = relative {
+                if !files.contains(&relative) {
+                    files.push(relative.clone());
+                    pending.push_back(relative);
+                }
+            }
+        }
+    }
+    files
+}
+
+// The command with the reduced main file, whose includes that are not copied
+// are found in the directory of the original one, and without the output
+// directory, which is the one of the reduction
+fn reduced_command(command: &[String], input: &Path, main: &Path, root: &Path) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut replaced = false;
+    let mut iter = command.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "-o" || arg == "--output" {
+            iter.next();
+        } else if !replaced && Path::new(arg) == input {
+            args.push(main.to_string_lossy().to_string());
+            replaced = true;
+        } else if !arg.starts_with("--output=") {
+            args.push(arg.clone());
+        }
+    }
+    if !replaced {
+        args.insert(0, main.to_string_lossy().to_string());
+    }
+    args.extend(["-l".to_string(), root.to_string_lossy().to_string()]);
+    args
+}
+
+// Removes chunks of lines of the file, halving their size when none can be
+// removed, and returns its numbers of lines before and after
+fn reduce_file(file: &Path, still_panics: &mut dyn FnMut() -> bool) -> Result<(usize, usize), ()> {
+    let write = |lines: &[&str]| {
+        std::fs::write(file, lines.join("\n")).map_err(|error| {
+            eprintln!("{}", Colour::Red.paint(format!("Could not write {}: {}", file.display(), error)));
+        })
+    };
+    let code = std::fs::read_to_string(file).map_err(|_| {
+        eprintln!("{}", Colour::Red.paint(format!("Could not read {}", file.display())));
+    })?;
+    let mut lines: Vec<&str> = code.lines().collect();
+    let before = lines.len();
+    let mut chunk = (lines.len() / 2).max(1);
+    loop {
+        let mut removed = false;
+        let mut start = 0;
+        while start < lines.len() {
+            let end = (start + chunk).min(lines.len());
+            let candidate: Vec<&str> = lines[..start].iter().chain(&lines[end..]).cloned().collect();
+            write(&candidate)?;
+            if still_panics() {
+                lines = candidate;
+                removed = true;
+            } else {
+                start = end;
+            }
+        }
+        if !removed && chunk == 1 {
+            break;
+        } else if !removed {
+            chunk /= 2;
+        }
+    }
+    write(&lines)?;
+    Result::Ok((before, lines.len()))
+}
+
+// The output of the compiler, when it ends before the timeout
+fn run_compiler(compiler: &Path, args: &[String], timeout: Option<Duration>) -> Option<String> {
+    let mut child = Command::new(compiler)
+        .args(args)
+        .env("RUST_BACKTRACE", "0")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .ok()?;
+    let mut stderr = child.stderr.take()?;
+    let reader = std::thread::spawn(move || {
+        let mut output = String::new();
+        let _ = stderr.read_to_string(&mut output);
+        output
+    });
+    let start = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if timeout.is_none_or(|timeout| start.elapsed() < timeout) => {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    }
+    reader.join().ok()
+}
+
+// The message of the panic and the place of the code of the compiler where
+// it happens
+fn panic_signature(output: &str) -> Option<String> {
+    let mut lines = output.lines().skip_while(|line| !line.contains(ICE_MARKER));
+    let message = lines.next()?;
+    Some(format!("{}\n{}", message, lines.next().unwrap_or_default()))
+}
@@ -0,0 +1,464 @@
+use ansi_term::Colour;
+use circom_algebra::algebra::Constraint;
+use circom_algebra::modular_arithmetic as field_ops;
+use circom_algebra::num_bigint::BigInt;
+use circom_algebra::num_traits::Zero;
+use compiler::circuit_design::circuit::Circuit;
+use compiler::compiler_interface;
+use compiler::compiler_interface::{Config, OptimizationLevel, Pass, PassConfig};
+use compiler::hir::very_concrete_program::VCP;
+use constraint_generation::{build_dag, main_arguments, BuildConfig};
+use constraint_writers::graph_writer::GraphFormat;
+use dag::{ConstraintOrigin, Tree, DAG};
+use parser::ParserConfig;
+use program_structure::ast::SignalType;
+use program_structure::error_definition::Report;
+use program_structure::program_archive::ProgramArchive;
+use std::collections::HashMap;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+use type_analysis::check_types::check_types;
+use crate::explain_user::constraint_to_string;
+use crate::VERSION;
+
+// The file with the definitions of the session, which is not on the disk
+const SESSION_FILE: &str = "<repl>";
+// The template whose argument is the expression that is evaluated
+const VALUE_TEMPLATE: &str = "$Repl";
+// the steps of the functions run at compile time, as the default of --function_steps
+const FUNCTION_STEPS: usize = 10_000_000;
+// the instances of a template inside itself, as the default of --template_recursion
+const TEMPLATE_RECURSION: usize = 100;
+const DEFINITIONS: [&str; 8] = ["pragma", "include", "template", "function", "bus", "extern", "const", "enum"];
+
+const HELP: &str = "\
+Enter an expression to evaluate it, as sq(3) or [1, 2] ** 2, or a definition
+to add it to the session, as an include, a template, a bus, a function or a
+constant. The commands are:
+  :run T(args)         instantiates the template and prints its constraints
+  :run T(args) inputs  also computes its witness for the inputs, given as a
+                       json object as {\"in\": 11}, and prints its outputs
+  :defs                prints the definitions of the session
+  :reset               removes the definitions added to the session
+  :help                prints this help
+  :quit                ends the session";
+
+pub struct ReplConfig {
+    pub files: Vec<PathBuf>,
+    pub link_libraries: Vec<PathBuf>,
+    pub prime: String,
+}
+
+struct Session {
+    config: ReplConfig,
+    // the includes of the files given by the input, and the definitions added
+    included: usize,
+    definitions: Vec<String>,
+}
+
+// Reads entries from the standard input until it ends or the session is
+// quit. An entry is complete when its parentheses, brackets and braces are
+// closed, and a definition when it ends with a semicolon or a brace
+pub fn run(config: ReplConfig) -> Result<(), ()> {
+    let mut definitions = Vec::new();
+    for file in &config.files {
+        let path = std::fs::canonicalize(file).unwrap_or_else(|_| file.clone());
+        definitions.push(format!("include \"{}\";", path.display()));
+    }
+    let mut session = Session { config, included: definitions.len(), definitions: Vec::new() };
+    for definition in definitions {
+        session.define(definition)?;
+    }
+    let interactive = std::io::stdin().is_terminal();
+    if interactive {
+        println!("circom {} repl, enter :help for help", VERSION);
+    }
+    let mut entry = String::new();
+    let mut lines = std::io::stdin().lock().lines();
+    loop {
+        if interactive {
+            print!("{}", if entry.is_empty() { "> " } else { "... " });
+            let _ = std::io::stdout().flush();
+        }
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => break,
+        };
+        entry.push_str(&line);
+        entry.push('\n');
+        if !is_complete(&entry) {
+            continue;
+        }
+        let text = std::mem::take(&mut entry);
+        let text = text.trim();
+        if text == ":quit" || text == ":q" {
+            break;
+        }
+        session.enter(text);
+    }
+    Result::Ok(())
+}
+
+fn first_word(text: &str) -> &str {
+    let code = text.lines().map(str::trim).find(|line| !line.is_empty() && !line.starts_with("//") && !line.starts_with("#["));
+    let code = code.unwrap_or("");
+    code.split(|c: char| !c.is_alphanumeric() && c != '_').next().unwrap_or("")
+}
+
+fn is_definition(text: &str) -> bool {
+    DEFINITIONS.contains(&first_word(text))
+}
+
+// The depth of the parentheses, brackets and braces at the end of the text,
+// out of its strings and comments
+fn depth(text: &str) -> i32 {
+    let mut depth = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            '"' => {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            _ => {}
+        }
+    }
+    depth
+}
+
+fn is_complete(text: &str) -> bool {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return true;
+    }
+    if depth(text) > 0 {
+        return false;
+    }
+    !is_definition(trimmed) || trimmed.ends_with(';') || trimmed.ends_with('}')
+}
+
+// The call to the template and the inputs that follow it, if any
+fn split_call(text: &str) -> Option<(&str, Option<&str>)> {
+    let open = text.find('(')?;
+    let mut depth = 0;
+    for (position, c) in text[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            let end = open + position + 1;
+            let inputs = text[end..].trim();
+            return Some((&text[..end], if inputs.is_empty() { None } else { Some(inputs) }));
+        }
+    }
+    None
+}
+
+// The value of an argument, as a list for an array
+fn format_value(lengths: &[usize], values: &[BigInt]) -> String {
+    match lengths.split_first() {
+        None => values.first().map_or_else(String::new, |value| value.to_string()),
+        Some((length, rest)) => {
+            let size = values.len() / (*length).max(1);
+            let elements: Vec<String> = values.chunks(size.max(1)).map(|chunk| format_value(rest, chunk)).collect();
+            format!("[{}]", elements.join(", "))
+        }
+    }
+}
+
+fn print_error(message: &str) {
+    eprintln!("{}", Colour::Red.paint(message));
+}
+
+impl Session {
+    fn enter(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some(command) = text.strip_prefix(':') {
+            let (name, argument) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+            match name {
+                "help" | "h" => println!("{}", HELP),
+                "defs" => {
+                    for definition in &self.definitions {
+                        println!("{}", definition);
+                    }
+                }
+                "reset" => self.definitions.truncate(self.included),
+                "run" => match split_call(argument.trim()) {
+                    Some((call, inputs)) => {
+                        let _ = self.instantiate(call, inputs);
+                    }
+                    None => print_error("expected the call to a template, as :run Num2Bits(8)"),
+                },
+                _ => print_error(&format!("unknown command :{}, enter :help for the commands", name)),
+            }
+        } else if first_word(text) == "component" {
+            print_error("the main component is given by :run, as :run Num2Bits(8)");
+        } else if is_definition(text) {
+            let _ = self.define(text.to_string());
+        } else {
+            let _ = self.evaluate(text.trim_end_matches(';'));
+        }
+    }
+
+    // Parses and checks the definitions of the session with the given main
+    // component, where the warnings of the definitions are only printed
+    // when they are added
+    fn program(&self, main: &str, definition: Option<&str>, print_warnings: bool) -> Result<ProgramArchive, ()> {
+        let mut source = format!("pragma circom {};\n", VERSION);
+        for definition in self.definitions.iter().map(String::as_str).chain(definition) {
+            source.push_str(definition);
+            source.push('\n');
+        }
+        source.push_str(main);
+        source.push('\n');
+        let mut sources = HashMap::new();
+        sources.insert(PathBuf::from(SESSION_FILE), source);
+        let config = ParserConfig {
+            version: VERSION,
+            link_libraries: self.config.link_libraries.clone(),
+            prime: &self.config.prime,
+            flag_prime: false,
+            flag_no_init: false,
+            flag_enforce_tags: false,
+            features: &[],
+        };
+        let result = parser::run_parser_with_sources(SESSION_FILE.to_string(), &config, &sources);
+        let mut program_archive = match result {
+            Result::Err((file_library, reports)) => {
+                Report::print_reports(&reports, &file_library);
+                return Result::Err(());
+            }
+            Result::Ok((program_archive, warnings)) => {
+                if print_warnings {
+                    Report::print_warnings(&warnings, &program_archive.file_library).map_err(|_denied| {})?;
+                }
+                program_archive
+            }
+        };
+        match check_types(&mut program_archive) {
+            Err(reports) => {
+                Report::print_reports(&reports, &program_archive.file_library);
+                Result::Err(())
+            }
+            Ok(warnings) if print_warnings => {
+                Report::print_warnings(&warnings, &program_archive.file_library).map_err(|_denied| {})?;
+                Result::Ok(program_archive)
+            }
+            Ok(_) => Result::Ok(program_archive),
+        }
+    }
+
+    fn build_config(&self) -> BuildConfig {
+        build_config(&self.config.prime)
+    }
+
+    // A definition is kept when the definitions of the session are correct
+    // with it
+    fn define(&mut self, definition: String) -> Result<(), ()> {
+        let main = format!("template {}() {{}}\ncomponent main = {}();", VALUE_TEMPLATE, VALUE_TEMPLATE);
+        self.program(&main, Some(&definition), true)?;
+        self.definitions.push(definition);
+        Result::Ok(())
+    }
+
+    // The expression is evaluated as the argument of a main component
+    fn evaluate(&self, expression: &str) -> Result<(), ()> {
+        let main = format!("template {}(value) {{}}\ncomponent main = {}({});", VALUE_TEMPLATE, VALUE_TEMPLATE, expression);
+        let program_archive = self.program(&main, None, false)?;
+        let arguments = main_arguments(&program_archive, &self.build_config()).map_err(|error| error.report())?;
+        if let Some((lengths, values)) = arguments.first() {
+            println!("{}", format_value(lengths, values));
+        }
+        Result::Ok(())
+    }
+
+    // Instantiates the template as the main component, and runs its witness
+    // generation when the inputs are given
+    fn instantiate(&self, call: &str, inputs: Option<&str>) -> Result<(), ()> {
+        let program_archive = self.program(&format!("component main = {};", call), None, false)?;
+        let (dag, vcp) = build_dag(program_archive, &self.build_config()).map_err(|error| error.report())?;
+        let mut names = HashMap::new();
+        let mut constraints = Vec::new();
+        collect_constraints(&Tree::new(&dag), &dag, &mut names, &mut constraints, &mut Vec::new());
+        let main = &vcp.templates[vcp.main_id];
+        let mut outputs = Vec::new();
+        for wire in main.wires.iter().filter(|wire| wire.xtype() == SignalType::Output) {
+            outputs.extend(wire.dag_local_id()..wire.dag_local_id() + wire.size());
+        }
+        let field = Tree::new(&dag).field;
+
+        let signals = match inputs {
+            Some(inputs) => Some(calculate(vcp, &self.config.prime, inputs)?),
+            None => None,
+        };
+        let value = |signal: &usize| {
+            let position = *dag.memory_positions.get(signal).unwrap_or(signal);
+            signals.as_ref().and_then(|signals| signals.get(position).cloned().flatten())
+        };
+        if signals.is_some() {
+            println!("{}", Colour::Green.paint("outputs"));
+            for signal in &outputs {
+                let value = value(signal).map_or("not computed".to_string(), |value| value.to_string());
+                println!("  {} = {}", names.get(signal).cloned().unwrap_or_default(), value);
+            }
+        }
+        println!("{} {}", Colour::Green.paint("constraints"), constraints.len());
+        for constraint in &constraints {
+            let holds = signals.as_ref().and_then(|_| constraint_holds(&field, constraint, value));
+            let text = constraint_to_string(&field, &names, constraint);
+            if holds == Some(false) {
+                println!("  {} {}", text, Colour::Red.paint("does not hold"));
+            } else {
+                println!("  {}", text);
+            }
+        }
+        Result::Ok(())
+    }
+}
+
+// The options of the constraint generation, without the simplification
+pub fn build_config(prime: &str) -> BuildConfig {
+    BuildConfig {
+        no_rounds: 0,
+        function_steps: FUNCTION_STEPS,
+        template_recursion: TEMPLATE_RECURSION,
+        flag_json_sub: false,
+        json_substitutions: String::new(),
+        flag_s: false,
+        flag_f: true,
+        flag_p: false,
+        flag_verbose: false,
+        inspect_constraints: false,
+        inspect_witness: false,
+        inspect_determinism: false,
+        deny_unconstrained: false,
+        suggest_parallel: false,
+        flag_template_report: false,
+        template_report: String::new(),
+        flag_component_graph: false,
+        component_graph_format: GraphFormat::Json,
+        component_graph: String::new(),
+        flag_old_heuristics: false,
+        prime: prime.to_string(),
+    }
+}
+
+// Whether the values of the signals satisfy the constraint, if all of them
+// are known
+pub fn constraint_holds(
+    field: &BigInt,
+    constraint: &Constraint<usize>,
+    value: impl Fn(&usize) -> Option<BigInt>,
+) -> Option<bool> {
+    let evaluate = |expression: &HashMap<usize, BigInt>| {
+        let mut result = BigInt::zero();
+        for (signal, coefficient) in expression {
+            let term = field_ops::mul(coefficient, &value(signal)?, field);
+            result = field_ops::add(&result, &term, field);
+        }
+        Some(result)
+    };
+    let (a, b, c) = (evaluate(constraint.a())?, evaluate(constraint.b())?, evaluate(constraint.c())?);
+    Some(field_ops::sub(&field_ops::mul(&a, &b, field), &c, field).is_zero())
+}
+
+// The constraints of the components as they are generated, with the names
+// of their signals and the statements that generate them
+pub fn collect_constraints(
+    tree: &Tree,
+    dag: &DAG,
+    names: &mut HashMap<usize, String>,
+    constraints: &mut Vec<Constraint<usize>>,
+    origins: &mut Vec<ConstraintOrigin>,
+) {
+    for (id, name) in &tree.id_to_name {
+        names.insert(*id, format!("{}.{}", tree.path, name));
+    }
+    let node = &dag.nodes[tree.node_id];
+    for (constraint, origin) in node.constraints().iter().zip(node.constraint_origins()) {
+        if !constraint.is_empty() {
+            constraints.push(constraint.apply_offset(tree.offset));
+            origins.push(origin.clone());
+        }
+    }
+    for edge in Tree::get_edges(tree) {
+        let subtree = Tree::go_to_subtree(tree, edge);
+        collect_constraints(&subtree, dag, names, constraints, origins);
+    }
+}
+
+// The circuit of the witness generation code as it is generated, without
+// the optimizations, which is the one the witness is calculated with
+pub fn compile_circuit(vcp: VCP, prime: &str) -> Result<Circuit, ()> {
+    compiler_interface::run_compiler(
+        vcp,
+        Config {
+            debug_output: false,
+            produce_input_log: false,
+            wat_flag: false,
+            no_asm_flag: false,
+            constraint_assert_disabled_flag: false,
+            passes: PassConfig {
+                level: OptimizationLevel::O0,
+                disabled_passes: Pass::ALL.to_vec(),
+                report_timing: false,
+                report_effects: false,
+                verify: false,
+            },
+            lazy_components_flag: false,
+            trace_signals_flag: false,
+            profile_templates_flag: false,
+            ir_cache: None,
+            prime: prime.to_string(),
+        },
+        VERSION,
+    )
+}
+
+// The values of the signals computed by the code of the circuit for the
+// inputs
+fn calculate(vcp: VCP, prime: &str, inputs: &str) -> Result<Vec<Option<BigInt>>, ()> {
+    let circuit = compile_circuit(vcp, prime)?;
+    let inputs = witness_calculation::read_inputs(&circuit, inputs).map_err(|message| print_error(&message))?;
+    let calculation = witness_calculation::calculate_witness(&circuit, &inputs);
+    for log in &calculation.logs {
+        println!("{}", log);
+    }
+    if let Some(failure) = &calculation.failure {
+        print_error(&format!(
+            "the witness generation fails in {} line {}, component {}: {}",
+            failure.definition, failure.line, failure.component, failure.message
+        ));
+    }
+    Result::Ok(calculation.signals)
+}
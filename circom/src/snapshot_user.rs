@@ -0,0 +1,66 @@
+use ansi_term::Colour;
+use sha1::{Digest, Sha1};
+use std::collections::BTreeMap;
+use std::path::Path;
+use crate::manifest::{read_snapshot, write_snapshot, Snapshot};
+
+// The hashes of the files generated for a circuit, by their kind
+pub fn hash_files(files: &[(&str, String)]) -> Result<BTreeMap<String, String>, ()> {
+    let mut hashes = BTreeMap::new();
+    for (kind, file) in files {
+        let content = std::fs::read(file)
+            .map_err(|e| eprintln!("{}", Colour::Red.paint(format!("Could not read {}: {}", file, e))))?;
+        let hash: String = Sha1::digest(&content).iter().map(|byte| format!("{:02x}", byte)).collect();
+        hashes.insert(kind.to_string(), hash);
+    }
+    Result::Ok(hashes)
+}
+
+// The circuits compiled replace their entries of the snapshot, and the
+// entries of the other circuits are kept
+pub fn record_snapshot(circuits: Snapshot, file: &Path) -> Result<(), ()> {
+    let mut snapshot = if file.is_file() {
+        read_snapshot(file).map_err(|e| eprintln!("{}", Colour::Red.paint(e)))?
+    } else {
+        Snapshot::new()
+    };
+    snapshot.extend(circuits);
+    write_snapshot(file, &snapshot).map_err(|e| eprintln!("{}", Colour::Red.paint(e)))?;
+    println!("{} {}", Colour::Green.paint("Written successfully:"), file.display());
+    Result::Ok(())
+}
+
+// Fails if a file of a circuit compiled differs from the snapshot, or it is
+// not in the snapshot, or a file in the snapshot is not generated
+pub fn check_snapshot(circuits: &Snapshot, file: &Path) -> Result<(), ()> {
+    let snapshot = read_snapshot(file).map_err(|e| eprintln!("{}", Colour::Red.paint(e)))?;
+    let mut changes = Vec::new();
+    for (circuit, hashes) in circuits {
+        let recorded = match snapshot.get(circuit) {
+            Some(recorded) => recorded,
+            None => {
+                changes.push(format!("the circuit {} is not in the snapshot", circuit));
+                continue;
+            }
+        };
+        for (kind, hash) in hashes {
+            match recorded.get(kind) {
+                Some(recorded) if recorded == hash => {}
+                Some(_) => changes.push(format!("the {} file of the circuit {} changed", kind, circuit)),
+                None => changes.push(format!("the {} file of the circuit {} is not in the snapshot", kind, circuit)),
+            }
+        }
+        for kind in recorded.keys().filter(|kind| !hashes.contains_key(*kind)) {
+            changes.push(format!("the {} file of the circuit {} is not generated", kind, circuit));
+        }
+    }
+    if changes.is_empty() {
+        println!("{} {}", Colour::Green.paint("The files match the snapshot:"), file.display());
+        return Result::Ok(());
+    }
+    for change in &changes {
+        eprintln!("{}", Colour::Red.paint(change));
+    }
+    eprintln!("{}", Colour::Red.paint(format!("The files differ from the snapshot {}, which is updated with --snapshot", file.display())));
+    Result::Err(())
+}
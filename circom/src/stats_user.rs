@@ -0,0 +1,102 @@
+use ansi_term::Colour;
+use compiler::hir::very_concrete_program::VCP;
+use constraint_writers::log_writer::Log;
+use json::JsonValue;
+use program_structure::constants::UsefulConstants;
+use std::collections::HashMap;
+
+// The statistics of a circuit, written in json format with --stats to follow
+// their changes between versions of the circuit
+pub struct CircuitStatistics {
+    log: Log,
+    instances: usize,
+    components: usize,
+    depth: usize,
+    signals: usize,
+    witness: usize,
+    component_tree: usize,
+    field_size: usize,
+}
+
+impl CircuitStatistics {
+    pub fn new(log: Log, vcp: &VCP) -> CircuitStatistics {
+        let field = UsefulConstants::new(&vcp.prime).get_p().clone();
+        // the bytes of a field element, as in the r1cs file
+        let field_size = if field.bits().is_multiple_of(64) { field.bits() / 8 } else { (field.bits() / 64 + 1) * 8 };
+        let mut depths = HashMap::new();
+        CircuitStatistics {
+            log,
+            instances: vcp.templates.len(),
+            components: vcp.stats.all_created_components,
+            depth: depth(vcp, vcp.main_id, &mut depths),
+            signals: vcp.stats.all_signals + 1,
+            witness: vcp.witness_list.len(),
+            component_tree: vcp.stats.all_created_components * 3 + vcp.stats.all_needed_subcomponents_indexes,
+            field_size,
+        }
+    }
+}
+
+// The depth of the tree of components of an instance, where the instances
+// without subcomponents have depth 1
+fn depth(vcp: &VCP, template: usize, depths: &mut HashMap<usize, usize>) -> usize {
+    if let Some(depth) = depths.get(&template) {
+        return *depth;
+    }
+    let mut deepest = 0;
+    for trigger in &vcp.templates[template].triggers {
+        deepest = std::cmp::max(deepest, depth(vcp, trigger.template_id, depths));
+    }
+    depths.insert(template, deepest + 1);
+    deepest + 1
+}
+
+fn statistics_as_json(statistics: &CircuitStatistics, files: &[(&str, String)]) -> JsonValue {
+    let log = &statistics.log;
+    let mut code = json::object! {};
+    for (kind, file) in files {
+        if let Ok(metadata) = std::fs::metadata(file) {
+            code[*kind] = metadata.len().into();
+        }
+    }
+    json::object! {
+        "constraints" => json::object! {
+            "total" => log.no_linear + log.no_non_linear,
+            "linear" => log.no_linear,
+            "non_linear" => log.no_non_linear,
+        },
+        "signals" => json::object! {
+            "public_outputs" => log.no_public_outputs,
+            "public_inputs" => log.no_public_inputs,
+            "private_inputs" => log.no_private_inputs,
+            "private_inputs_in_witness" => log.no_private_inputs_witness,
+            "wires" => log.no_wires,
+            "labels" => log.no_labels,
+        },
+        "components" => json::object! {
+            "template_instances" => statistics.instances,
+            "components" => statistics.components,
+            "depth" => statistics.depth,
+        },
+        "memory" => json::object! {
+            "field_element" => statistics.field_size,
+            "signals" => statistics.signals * statistics.field_size,
+            "witness" => statistics.witness * statistics.field_size,
+            "component_tree" => statistics.component_tree * 4,
+        },
+        "code" => code,
+    }
+}
+
+// The sizes of the code are the ones of the files that were generated, given
+// by their kind and their path
+pub fn write_statistics(statistics: &CircuitStatistics, files: &[(&str, String)], file: &str) -> Result<(), ()> {
+    let report = statistics_as_json(statistics, files);
+    if std::fs::write(file, report.pretty(4)).is_ok() {
+        println!("{} {}", Colour::Green.paint("Written successfully:"), file);
+        Result::Ok(())
+    } else {
+        eprintln!("{}", Colour::Red.paint("Could not write the output in the given path"));
+        Result::Err(())
+    }
+}
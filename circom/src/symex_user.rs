@@ -0,0 +1,126 @@
+use ansi_term::Colour;
+use compiler::compiler_interface;
+use compiler::compiler_interface::{Config, OptimizationLevel, Pass, PassConfig};
+use constraint_generation::{build_dag, BuildConfig};
+use constraint_writers::graph_writer::GraphFormat;
+use program_structure::error_definition::Report;
+use program_structure::program_archive::ProgramArchive;
+use std::collections::{HashMap, HashSet};
+use symbolic_execution::SymexConfig;
+use crate::VERSION;
+
+// the most instructions that are run in a path of a template
+const MAX_STEPS: usize = 1_000_000;
+
+pub struct SymbolicExecutionConfig {
+    pub max_paths: usize,
+    pub print_paths: bool,
+    pub print_taint: bool,
+    pub function_steps: usize,
+    pub template_recursion: usize,
+    pub flag_verbose: bool,
+    pub prime: String,
+}
+
+pub fn execute_symbolically(program_archive: ProgramArchive, config: SymbolicExecutionConfig) -> Result<(), ()> {
+    let files = program_archive.file_library.clone();
+    let mut definitions = HashMap::new();
+    for (name, data) in program_archive.get_templates() {
+        definitions.insert(name.clone(), data.get_file_id());
+    }
+    for (name, data) in program_archive.get_functions() {
+        definitions.insert(name.clone(), data.get_file_id());
+    }
+    let build_config = BuildConfig {
+        no_rounds: 0,
+        function_steps: config.function_steps,
+        template_recursion: config.template_recursion,
+        flag_json_sub: false,
+        json_substitutions: String::new(),
+        flag_s: false,
+        flag_f: true,
+        flag_p: false,
+        flag_verbose: config.flag_verbose,
+        inspect_constraints: false,
+        inspect_witness: false,
+        inspect_determinism: false,
+        deny_unconstrained: false,
+        suggest_parallel: false,
+        flag_template_report: false,
+        template_report: String::new(),
+        flag_component_graph: false,
+        component_graph_format: GraphFormat::Json,
+        component_graph: String::new(),
+        flag_old_heuristics: false,
+        prime: config.prime.clone(),
+    };
+    let (dag, vcp) = build_dag(program_archive, &build_config).map_err(|error| error.report())?;
+    // the code is run as it is written, without the passes that change it
+    let circuit = compiler_interface::run_compiler(
+        vcp,
+        Config {
+            debug_output: false,
+            produce_input_log: false,
+            wat_flag: false,
+            no_asm_flag: false,
+            constraint_assert_disabled_flag: false,
+            passes: PassConfig {
+                level: OptimizationLevel::O0,
+                disabled_passes: Pass::ALL.to_vec(),
+                report_timing: false,
+                report_effects: false,
+                verify: false,
+            },
+            lazy_components_flag: false,
+            trace_signals_flag: false,
+            profile_templates_flag: false,
+            ir_cache: None,
+            prime: config.prime,
+        },
+        VERSION,
+    )?;
+    let templates = symbolic_execution::execute_circuit(
+        &circuit,
+        &dag,
+        &SymexConfig { max_paths: config.max_paths, max_steps: MAX_STEPS },
+    );
+
+    let mut shown = HashSet::new();
+    for template in &templates {
+        if !shown.insert(template.name.clone()) {
+            continue;
+        }
+        if config.print_paths {
+            println!("{} {}: {} paths", Colour::Green.paint("template"), template.name, template.paths.len());
+            for (position, path) in template.paths.iter().enumerate() {
+                let conditions = if path.conditions.is_empty() { "always".to_string() } else { path.conditions.join(", ") };
+                println!("  path {}: {}", position + 1, conditions);
+                for (signal, value) in &path.witness {
+                    println!("    {} = {}", signal, value);
+                }
+            }
+        }
+        if let Some(reason) = &template.stopped {
+            println!("{} {}, since {}", Colour::Yellow.paint("not all the paths were followed in template"), template.name, reason);
+        }
+    }
+    let paths: usize = templates.iter().map(|template| template.paths.len()).sum();
+    println!("{} {}", Colour::Green.paint("paths followed:"), paths);
+    if config.print_taint {
+        for flow in symbolic_execution::information_flows(&templates, &dag) {
+            let (kind, relation) = if flow.is_input {
+                ("public input", "is related by the constraints to")
+            } else {
+                ("public output", "depends on")
+            };
+            if flow.private_inputs.is_empty() {
+                println!("{} {} {} no private input", Colour::Green.paint(kind), flow.signal, relation);
+            } else {
+                println!("{} {} {} the private inputs {}", Colour::Green.paint(kind), flow.signal, relation, flow.private_inputs.join(", "));
+            }
+        }
+    }
+    let mut reports = symbolic_execution::divergence_reports(&templates, &dag);
+    reports.append(&mut symbolic_execution::overflow_reports(&templates, &dag, &files, &definitions));
+    Report::print_warnings(&reports, &files).map_err(|_denied| {})
+}
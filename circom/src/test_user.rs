@@ -0,0 +1,375 @@
+use ansi_term::Colour;
+use circom_algebra::algebra::Constraint;
+use circom_algebra::modular_arithmetic as field_ops;
+use circom_algebra::num_bigint::BigInt;
+use constraint_generation::{build_dag, main_arguments};
+use dag::{ConstraintOrigin, Tree, DAG};
+use compiler::circuit_design::circuit::Circuit;
+use parser::ParserConfig;
+use program_structure::ast::TestBlock;
+use program_structure::error_definition::Report;
+use program_structure::file_definition::FileLibrary;
+use program_structure::program_archive::ProgramArchive;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use type_analysis::check_types::check_types;
+use witness_calculation::Calculation;
+use crate::explain_user::constraint_to_string;
+use crate::format_user::circom_files;
+use crate::repl_user::{build_config, collect_constraints, compile_circuit, constraint_holds};
+use crate::VERSION;
+
+// The feature that compiles the ensure statements
+pub const ENSURE: &str = "ensure";
+
+// The template whose argument is a value of an input or an output of a test
+const VALUE_TEMPLATE: &str = "$TestValue";
+
+pub struct TestConfig {
+    pub files: Vec<PathBuf>,
+    pub link_libraries: Vec<PathBuf>,
+    pub prime: String,
+}
+
+struct Summary {
+    passed: usize,
+    failed: usize,
+}
+
+// The name of the main component of the test, and of its values
+fn test_main(test: usize) -> String {
+    format!("$test_{}", test)
+}
+
+fn value_main(test: usize, value: usize) -> String {
+    format!("$test_{}_{}", test, value)
+}
+
+// Runs the tests of the files, or of the circom files in the directories,
+// and fails if any of them fails
+pub fn run_tests(config: TestConfig) -> Result<(), ()> {
+    let mut files = Vec::new();
+    for file in &config.files {
+        circom_files(file, &mut files)?;
+    }
+    let mut summary = Summary { passed: 0, failed: 0 };
+    for file in &files {
+        run_file_tests(file, &config, &mut summary);
+    }
+    let result = format!("test result: {} passed, {} failed", summary.passed, summary.failed);
+    if summary.failed > 0 {
+        eprintln!("{}", Colour::Red.paint(result));
+        Result::Err(())
+    } else {
+        println!("{}", Colour::Green.paint(result));
+        Result::Ok(())
+    }
+}
+
+// The source of a file with its tests
+pub fn read_tests(file: &Path, prime: &str) -> Result<(String, Vec<TestBlock>), ()> {
+    let source = std::fs::read_to_string(file).map_err(|e| {
+        eprintln!("{}", Colour::Red.paint(format!("Could not read {}: {}", file.display(), e)))
+    })?;
+    match parser::parse_definitions(&source, 0, prime, &[ENSURE.to_string()]) {
+        Ok(ast) => Result::Ok((source, ast.tests)),
+        Err(reports) => {
+            let mut file_library = FileLibrary::new();
+            file_library.add_file(file.display().to_string(), source);
+            Report::print_reports(&reports, &file_library);
+            Result::Err(())
+        }
+    }
+}
+
+// The tests of a file are compiled with it, as main components with their
+// templates and the arguments given, and their values as the arguments of
+// other main components, so that they can use its constants and functions
+fn run_file_tests(file: &Path, config: &TestConfig, summary: &mut Summary) {
+    let (source, tests) = match read_tests(file, &config.prime) {
+        Ok(result) => result,
+        Err(()) => {
+            summary.failed += 1;
+            return;
+        }
+    };
+    if tests.is_empty() {
+        return;
+    }
+    println!("running {} tests of {}", tests.len(), file.display());
+    let program_archive = match test_program(file, &source, &tests, config) {
+        Ok(program_archive) => program_archive,
+        Err(()) => {
+            for test in &tests {
+                println!("test {} ... {}", test.name, Colour::Red.paint("FAILED"));
+            }
+            summary.failed += tests.len();
+            return;
+        }
+    };
+    for (index, test) in tests.iter().enumerate() {
+        let failures = run_test(&program_archive, index, test, &config.prime);
+        if failures.is_empty() {
+            summary.passed += 1;
+        } else {
+            println!("test {} ... {}", test.name, Colour::Red.paint("FAILED"));
+            for failure in failures {
+                println!("  {}", failure);
+            }
+            summary.failed += 1;
+        }
+    }
+}
+
+// The program of the file with the main components of its tests. The
+// template of the values is added after the last test, in its line, so that
+// the lines of the reports are the ones of the file
+pub fn test_program(file: &Path, source: &str, tests: &[TestBlock], config: &TestConfig) -> Result<ProgramArchive, ()> {
+    let text = |expression: &program_structure::ast::Expression| {
+        let meta = expression.get_meta();
+        source[meta.start..meta.end].to_string()
+    };
+    let end = tests.iter().map(|test| test.meta.end).max().unwrap_or(source.len());
+    let mut program = format!("{} template {}(value) {{}}{}\n", &source[..end], VALUE_TEMPLATE, &source[end..]);
+    for (index, test) in tests.iter().enumerate() {
+        program.push_str(&format!("component main {} = {};\n", test_main(index), text(&test.call)));
+        for (position, (_, value)) in test.inputs.iter().chain(&test.expect).chain(&test.reject).enumerate() {
+            program.push_str(&format!(
+                "component main {} = {}({});\n",
+                value_main(index, position),
+                VALUE_TEMPLATE,
+                text(value)
+            ));
+        }
+    }
+    parse_program(file, program, &config.link_libraries, &config.prime)
+}
+
+// The program of the file with the given source, with the feature ensure so
+// that the properties of its templates are checked
+pub fn parse_program(file: &Path, source: String, link_libraries: &[PathBuf], prime: &str) -> Result<ProgramArchive, ()> {
+    let path = std::fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf());
+    let mut sources = HashMap::new();
+    sources.insert(path.clone(), source);
+    let config = ParserConfig {
+        version: VERSION,
+        link_libraries: link_libraries.to_vec(),
+        prime,
+        flag_prime: false,
+        flag_no_init: false,
+        flag_enforce_tags: false,
+        features: &[ENSURE.to_string()],
+    };
+    let result = parser::run_parser_with_sources(path.display().to_string(), &config, &sources);
+    match result {
+        Result::Err((file_library, reports)) => {
+            Report::print_reports(&reports, &file_library);
+            Result::Err(())
+        }
+        Result::Ok((program_archive, _)) => Result::Ok(program_archive),
+    }
+}
+
+// The program with the given main component, checked
+pub fn select(program_archive: &ProgramArchive, name: &str) -> Result<ProgramArchive, ()> {
+    let mut program_archive = program_archive.clone();
+    let index = program_archive
+        .get_main_components()
+        .iter()
+        .position(|(main, _)| main.as_deref() == Some(name))
+        .ok_or(())?;
+    program_archive.select_main_component(index);
+    if let Err(reports) = check_types(&mut program_archive) {
+        Report::print_reports(&reports, &program_archive.file_library);
+        return Result::Err(());
+    }
+    Result::Ok(program_archive)
+}
+
+// The values of the inputs and of the outputs of the test, flattened
+fn test_values(program_archive: &ProgramArchive, index: usize, test: &TestBlock, prime: &str) -> Result<Vec<(String, Vec<BigInt>)>, ()> {
+    let mut values = Vec::new();
+    for (position, (name, _)) in test.inputs.iter().chain(&test.expect).chain(&test.reject).enumerate() {
+        let program_archive = select(program_archive, &value_main(index, position))?;
+        let arguments = main_arguments(&program_archive, &build_config(prime)).map_err(|error| error.report())?;
+        let value = arguments.into_iter().next().map(|(_, value)| value).unwrap_or_default();
+        values.push((name.clone(), value));
+    }
+    Result::Ok(values)
+}
+
+// A main component with its constraints and the code of its witness
+pub struct Instance {
+    pub dag: DAG,
+    pub names: HashMap<usize, String>,
+    pub constraints: Vec<Constraint<usize>>,
+    // the statements that generate each constraint
+    pub origins: Vec<ConstraintOrigin>,
+    pub circuit: Circuit,
+    pub field: BigInt,
+}
+
+impl Instance {
+    // The value of a signal of the dag in the witness
+    pub fn value(&self, calculation: &Calculation, signal: &usize) -> Option<BigInt> {
+        let position = *self.dag.memory_positions.get(signal).unwrap_or(signal);
+        calculation.signals.get(position).cloned().flatten()
+    }
+
+    pub fn failed_constraints(&self, calculation: &Calculation) -> Vec<String> {
+        failed_constraints(self, &self.constraints, |signal| self.value(calculation, signal))
+    }
+}
+
+pub fn witness_failure(calculation: &Calculation) -> Option<String> {
+    calculation.failure.as_ref().map(|failure| {
+        format!(
+            "the witness generation fails in {} line {}, component {}: {}",
+            failure.definition, failure.line, failure.component, failure.message
+        )
+    })
+}
+
+// The program with its main component selected, instantiated, or the reason
+// why it cannot be
+pub fn instantiate(program_archive: ProgramArchive, prime: &str) -> Result<Instance, String> {
+    let (dag, vcp) = build_dag(program_archive, &build_config(prime))
+        .map_err(|_| "the constraints of the template could not be generated".to_string())?;
+    let tree = Tree::new(&dag);
+    let field = tree.field.clone();
+    let mut names = HashMap::new();
+    let mut constraints = Vec::new();
+    let mut origins = Vec::new();
+    collect_constraints(&tree, &dag, &mut names, &mut constraints, &mut origins);
+    let circuit = compile_circuit(vcp, prime)
+        .map_err(|()| "the witness generation code of the template could not be generated".to_string())?;
+    Result::Ok(Instance { dag, names, constraints, origins, circuit, field })
+}
+
+// A test with the witness of its inputs, whose constraints are checked as
+// they are generated, or as circom mutate changes them
+pub struct TestRun {
+    pub instance: Instance,
+    calculation: Calculation,
+    // the reasons why the test fails whatever its constraints are
+    failures: Vec<String>,
+    // the values of reject by their signals, if the test has them
+    rejected: Option<HashMap<usize, BigInt>>,
+}
+
+impl TestRun {
+    // The reasons why the test fails with the constraints given
+    pub fn check(&self, constraints: &[Constraint<usize>]) -> Vec<String> {
+        let instance = &self.instance;
+        let value = |signal: &usize| instance.value(&self.calculation, signal);
+        let mut failures = self.failures.clone();
+        failures.extend(failed_constraints(instance, constraints, value));
+        if let Some(rejected) = &self.rejected {
+            let forged = |signal: &usize| rejected.get(signal).cloned().or_else(|| value(signal));
+            let rejects = constraints.iter().any(|constraint| constraint_holds(&instance.field, constraint, forged) == Some(false));
+            if !rejects {
+                failures.push("the constraints hold for the values of reject".to_string());
+            }
+        }
+        failures
+    }
+
+    pub fn logs(&self) -> &[String] {
+        &self.calculation.logs
+    }
+}
+
+// The signals of the main component with the name, sorted
+fn signals_named(names: &HashMap<usize, String>, name: &str) -> Vec<usize> {
+    let full_name = format!("main.{}", name);
+    let mut signals: Vec<_> = names
+        .iter()
+        .filter(|(_, signal_name)| {
+            signal_name.as_str() == full_name
+                || signal_name.strip_prefix(&full_name).is_some_and(|rest| rest.starts_with('[') || rest.starts_with('.'))
+        })
+        .map(|(signal, _)| *signal)
+        .collect();
+    signals.sort();
+    signals
+}
+
+fn failed_constraints(instance: &Instance, constraints: &[Constraint<usize>], value: impl Fn(&usize) -> Option<BigInt>) -> Vec<String> {
+    constraints
+        .iter()
+        .filter(|constraint| constraint_holds(&instance.field, constraint, &value) == Some(false))
+        .map(|constraint| format!("the constraint {} does not hold", constraint_to_string(&instance.field, &instance.names, constraint)))
+        .collect()
+}
+
+// Instantiates the template of the test and computes its witness, or gives
+// the reason why it cannot
+pub fn prepare_test(program_archive: &ProgramArchive, index: usize, test: &TestBlock, prime: &str) -> Result<TestRun, String> {
+    let mut values = test_values(program_archive, index, test, prime)
+        .map_err(|()| "the values of the test could not be computed".to_string())?;
+    let reject = values.split_off(test.inputs.len() + test.expect.len());
+    let expect = values.split_off(test.inputs.len());
+    let inputs = values;
+    let program_archive = select(program_archive, &test_main(index))
+        .map_err(|()| "the template of the test could not be instantiated".to_string())?;
+    let instance = instantiate(program_archive, prime)?;
+    let Instance { names, circuit, field, .. } = &instance;
+    let inputs = witness_calculation::place_inputs(circuit, inputs)?;
+    let calculation = witness_calculation::calculate_witness(circuit, &inputs);
+    let value = |signal: &usize| instance.value(&calculation, signal);
+
+    let mut failures = Vec::new();
+    failures.extend(witness_failure(&calculation));
+    for (name, expected) in &expect {
+        let signals = signals_named(names, name);
+        if signals.is_empty() {
+            failures.push(format!("the main component has no signal {}", name));
+        } else if signals.len() != expected.len() {
+            failures.push(format!("the signal {} has {} values, and {} were expected", name, signals.len(), expected.len()));
+        } else {
+            for (signal, expected) in signals.iter().zip(expected) {
+                let expected = field_ops::add(expected, &BigInt::from(0), field);
+                match value(signal) {
+                    Some(value) if value == expected => {}
+                    Some(value) => failures.push(format!("{} is {}, and {} was expected", names[signal], value, expected)),
+                    None => failures.push(format!("{} is not computed, and {} was expected", names[signal], expected)),
+                }
+            }
+        }
+    }
+    let mut rejected = HashMap::new();
+    for (name, forged) in &reject {
+        let signals = signals_named(names, name);
+        if signals.is_empty() {
+            failures.push(format!("the main component has no signal {}", name));
+        } else if signals.len() != forged.len() {
+            failures.push(format!("the signal {} has {} values, and {} were rejected", name, signals.len(), forged.len()));
+        } else {
+            for (signal, forged) in signals.into_iter().zip(forged) {
+                rejected.insert(signal, field_ops::add(forged, &BigInt::from(0), field));
+            }
+        }
+    }
+    let rejected = if reject.is_empty() { None } else { Some(rejected) };
+    Result::Ok(TestRun { instance, calculation, failures, rejected })
+}
+
+// The reasons why the test fails, which passes when there are none
+fn run_test(program_archive: &ProgramArchive, index: usize, test: &TestBlock, prime: &str) -> Vec<String> {
+    let run = match prepare_test(program_archive, index, test, prime) {
+        Ok(run) => run,
+        Err(message) => return vec![message],
+    };
+    let mut failures = run.check(&run.instance.constraints);
+    if failures.is_empty() {
+        println!(
+            "test {} ... {}, {} constraints hold",
+            test.name,
+            Colour::Green.paint("ok"),
+            run.instance.constraints.len()
+        );
+    } else {
+        failures.extend(run.logs().iter().map(|log| format!("log: {}", log)));
+    }
+    failures
+}
@@ -9,9 +9,6 @@ pub fn analyse_project(program_archive: &mut ProgramArchive) -> Result<(), ()> {
             Report::print_reports(&errs, program_archive.get_file_library());
             Err(())
         }
-        Ok(warns) => {
-            Report::print_reports(&warns, program_archive.get_file_library());
-            Ok(())
-        }
+        Ok(warns) => Report::print_warnings(&warns, program_archive.get_file_library()).map_err(|_denied| {}),
     }
 }
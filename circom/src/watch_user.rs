@@ -0,0 +1,272 @@
+use super::input_user::Input;
+use super::{compile_main_component, parser_user, print_explanation_hint, type_analysis_user};
+use ansi_term::Colour;
+use parser::ParseCache;
+use program_structure::error_definition::Report;
+use program_structure::ast::{Access, Expression, LogArgument, Statement, VariableType};
+use program_structure::program_archive::ProgramArchive;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+// How often the files are checked, and how long they must stay the same
+// after a change before the circuit is compiled again
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+// The modification time and the size of each file, or None when it cannot
+// be read, as while an editor replaces it
+type FileStates = BTreeMap<String, Option<(SystemTime, u64)>>;
+
+// Compiles the circuit each time one of the files of the program changes.
+// The files that do not change are not parsed again, the templates compiled
+// before are reused from the ir cache, a temporary one when --ir_cache is not
+// given, and only the main components whose code changes are compiled again
+pub fn watch(mut user_input: Input) -> Result<(), ()> {
+    if user_input.ir_cache.is_none() {
+        let folder = std::env::temp_dir().join(format!("circom_watch_{}", std::process::id()));
+        user_input.ir_cache = Some(folder.to_string_lossy().to_string());
+    }
+    let mut cache = ParseCache::new();
+    // the files of the code of each main component compiled without errors
+    let mut compiled = HashMap::new();
+    let mut files = BTreeSet::new();
+    files.insert(user_input.input_file().to_string());
+    let mut changed = None;
+    loop {
+        let start = std::time::Instant::now();
+        let result = compile_changes(&user_input, &mut cache, changed.as_ref(), &mut compiled, &mut files);
+        let elapsed = start.elapsed().as_secs_f64();
+        match result {
+            Ok(()) => {
+                println!("{}", Colour::Green.paint(format!("Compiled in {:.2}s", elapsed)));
+            }
+            Err(()) => {
+                // the changes of a compilation that fails are not known by
+                // the next one, which compiles all the main components. The
+                // files of a program that cannot be parsed are the ones of
+                // the last program parsed, with the input file
+                compiled.clear();
+                eprintln!("{}", Colour::Red.paint(format!("The compilation failed in {:.2}s", elapsed)));
+                print_explanation_hint();
+            }
+        }
+        Report::write_sarif_log();
+        println!("Watching {} files for changes", files.len());
+        changed = Some(wait_for_changes(&files));
+        let names: Vec<_> = changed.iter().flatten().map(String::as_str).collect();
+        println!("{}", Colour::Yellow.paint(format!("Changed: {}", names.join(", "))));
+    }
+}
+
+// Compiles the main components that use the changed files, or all of them
+// when the changes are not known, and keeps the files of the program
+fn compile_changes(
+    user_input: &Input,
+    cache: &mut ParseCache,
+    changed: Option<&BTreeSet<String>>,
+    compiled: &mut HashMap<Option<String>, BTreeSet<String>>,
+    files: &mut BTreeSet<String>,
+) -> Result<(), ()> {
+    let mut program_archive = parser_user::parse_project_with_cache(user_input, cache)?;
+    if cache.reused_files() > 0 {
+        println!("Reused {} parsed files", cache.reused_files());
+    }
+    let library = program_archive.get_file_library();
+    *files = (0..)
+        .map_while(|file_id| library.get_file_name(file_id))
+        .map(|name| name.trim_matches('"').to_string())
+        .filter(|name| Path::new(name).is_file())
+        .collect();
+    type_analysis_user::analyse_project(&mut program_archive)?;
+    let names: Vec<_> = program_archive.get_main_components().iter().map(|(name, _)| name.clone()).collect();
+    compiled.retain(|name, _| names.contains(name));
+    let mut order: Vec<_> = (0..names.len()).collect();
+    order.sort_by_key(|index| names[*index].is_some());
+    for index in order {
+        let mut program_archive = program_archive.clone();
+        program_archive.select_main_component(index);
+        let used = used_files(&program_archive);
+        let name = names[index].clone();
+        let mut user_input = user_input.clone();
+        if let Some(name) = &name {
+            user_input.set_output_name(name);
+        }
+        let unchanged = match (changed, compiled.get(&name)) {
+            (Some(changed), Some(previous)) => *previous == used && used.is_disjoint(changed),
+            _ => false,
+        };
+        if unchanged {
+            println!("The files of {} are up to date", user_input.output_name());
+            continue;
+        }
+        compiled.remove(&name);
+        compile_main_component(&user_input, program_archive)?;
+        compiled.insert(name, used);
+    }
+    Ok(())
+}
+
+fn file_states(files: &BTreeSet<String>) -> FileStates {
+    files
+        .iter()
+        .map(|file| {
+            let state = std::fs::metadata(file).ok().and_then(|metadata| Some((metadata.modified().ok()?, metadata.len())));
+            (file.clone(), state)
+        })
+        .collect()
+}
+
+fn wait_for_changes(files: &BTreeSet<String>) -> BTreeSet<String> {
+    let states = file_states(files);
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let mut current = file_states(files);
+        if current == states {
+            continue;
+        }
+        // an editor may write a file in several steps
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let next = file_states(files);
+            if next == current {
+                break;
+            }
+            current = next;
+        }
+        return files.iter().filter(|file| states.get(*file) != current.get(*file)).cloned().collect();
+    }
+}
+
+// The files of the code of the selected main component: the ones of the
+// templates, functions and buses that its call reaches, with the tag rules
+// and the file of the main component
+fn used_files(program_archive: &ProgramArchive) -> BTreeSet<String> {
+    let mut file_ids = BTreeSet::new();
+    file_ids.insert(*program_archive.get_file_id_main());
+    file_ids.extend(program_archive.get_main_expression().get_meta().file_id);
+    let mut pending = Vec::new();
+    calls_in_expression(program_archive.get_main_expression(), &mut pending);
+    for rules in program_archive.get_tag_rules().values() {
+        for (file_id, rule) in rules {
+            file_ids.insert(*file_id);
+            pending.extend(rule.function.clone());
+        }
+    }
+    let mut visited = HashSet::new();
+    while let Some(name) = pending.pop() {
+        if !visited.insert(name.clone()) {
+            continue;
+        }
+        let (file_id, body) = if program_archive.contains_template(&name) {
+            let data = program_archive.get_template_data(&name);
+            (data.get_file_id(), data.get_body())
+        } else if program_archive.contains_function(&name) {
+            let data = program_archive.get_function_data(&name);
+            (data.get_file_id(), data.get_body())
+        } else if program_archive.contains_bus(&name) {
+            let data = program_archive.get_bus_data(&name);
+            (data.get_file_id(), data.get_body())
+        } else {
+            continue;
+        };
+        file_ids.insert(file_id);
+        calls_in_statement(body, &mut pending);
+    }
+    let library = program_archive.get_file_library();
+    file_ids
+        .into_iter()
+        .filter_map(|file_id| library.get_file_name(file_id))
+        .map(|name| name.trim_matches('"').to_string())
+        .collect()
+}
+
+fn calls_in_statement(stmt: &Statement, calls: &mut Vec<String>) {
+    use Statement::*;
+    match stmt {
+        IfThenElse { cond, if_case, else_case, .. } => {
+            calls_in_expression(cond, calls);
+            calls_in_statement(if_case, calls);
+            if let Some(else_case) = else_case {
+                calls_in_statement(else_case, calls);
+            }
+        }
+        While { cond, stmt, .. } => {
+            calls_in_expression(cond, calls);
+            calls_in_statement(stmt, calls);
+        }
+        Return { value, .. } => calls_in_expression(value, calls),
+        InitializationBlock { initializations, .. } => {
+            initializations.iter().for_each(|stmt| calls_in_statement(stmt, calls));
+        }
+        Declaration { xtype, dimensions, .. } => {
+            if let VariableType::Bus(name, ..) = xtype {
+                calls.push(name.clone());
+            }
+            dimensions.iter().for_each(|dimension| calls_in_expression(dimension, calls));
+        }
+        Substitution { access, rhe, .. } => {
+            calls_in_access(access, calls);
+            calls_in_expression(rhe, calls);
+        }
+        MultSubstitution { lhe, rhe, .. } | ConstraintEquality { lhe, rhe, .. } => {
+            calls_in_expression(lhe, calls);
+            calls_in_expression(rhe, calls);
+        }
+        UnderscoreSubstitution { rhe, .. } => calls_in_expression(rhe, calls),
+        LogCall { args, .. } => calls_in_log(args, calls),
+        Block { stmts, .. } => stmts.iter().for_each(|stmt| calls_in_statement(stmt, calls)),
+        Assert { arg, message, .. } => {
+            calls_in_expression(arg, calls);
+            calls_in_log(message, calls);
+        }
+    }
+}
+
+fn calls_in_expression(expr: &Expression, calls: &mut Vec<String>) {
+    use Expression::*;
+    match expr {
+        InfixOp { lhe, rhe, .. } => {
+            calls_in_expression(lhe, calls);
+            calls_in_expression(rhe, calls);
+        }
+        PrefixOp { rhe, .. } | ParallelOp { rhe, .. } => calls_in_expression(rhe, calls),
+        InlineSwitchOp { cond, if_true, if_false, .. } => {
+            calls_in_expression(cond, calls);
+            calls_in_expression(if_true, calls);
+            calls_in_expression(if_false, calls);
+        }
+        Variable { access, .. } => calls_in_access(access, calls),
+        Number(..) => {}
+        Call { id, args, .. } | BusCall { id, args, .. } => {
+            calls.push(id.clone());
+            args.iter().for_each(|arg| calls_in_expression(arg, calls));
+        }
+        AnonymousComp { id, params, signals, .. } => {
+            calls.push(id.clone());
+            params.iter().chain(signals).for_each(|arg| calls_in_expression(arg, calls));
+        }
+        ArrayInLine { values, .. } | Tuple { values, .. } => {
+            values.iter().for_each(|value| calls_in_expression(value, calls));
+        }
+        UniformArray { value, dimension, .. } => {
+            calls_in_expression(value, calls);
+            calls_in_expression(dimension, calls);
+        }
+    }
+}
+
+fn calls_in_access(access: &[Access], calls: &mut Vec<String>) {
+    for access in access {
+        if let Access::ArrayAccess(index) = access {
+            calls_in_expression(index, calls);
+        }
+    }
+}
+
+fn calls_in_log(args: &[LogArgument], calls: &mut Vec<String>) {
+    for arg in args {
+        if let LogArgument::LogExp(expr) = arg {
+            calls_in_expression(expr, calls);
+        }
+    }
+}
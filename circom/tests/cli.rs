@@ -0,0 +1,54 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+const CIRCUIT: &str = "pragma circom 2.0.0;
+
+template Square() {
+    signal input in;
+    signal output out;
+    out <== in * in;
+}
+
+component main = Square();
+";
+
+// A directory of the temporary directory with the given files, each one with
+// the circuit
+fn project(name: &str, files: &[&str]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("circom_cli_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    for file in files {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, CIRCUIT).unwrap();
+    }
+    dir
+}
+
+fn circom(dir: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_circom")).current_dir(dir).args(args).output().unwrap()
+}
+
+#[test]
+fn inputs_named_as_subcommands_are_compiled() {
+    let files = ["test.circom", "tests/foo.circom", "debug.circom", "debug/main.circom", "audit.circom", "fmt/t2.circom"];
+    let dir = project("inputs", &files);
+    for file in files {
+        let output = circom(&dir, &[file, "--r1cs", "--O2"]);
+        assert!(output.status.success(), "{}: {}", file, String::from_utf8_lossy(&output.stderr));
+    }
+    assert!(dir.join("test.r1cs").exists());
+    assert!(dir.join("foo.r1cs").exists());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn subcommands_are_their_exact_names() {
+    let dir = project("subcommands", &["main.circom"]);
+    let output = circom(&dir, &["audit", "main.circom", "--O2"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let output = circom(&dir, &["test", "--help"]);
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("circom-test"));
+    fs::remove_dir_all(dir).unwrap();
+}
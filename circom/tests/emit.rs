@@ -0,0 +1,67 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// The files written by --emit_ir and --emit_hir for the circuit of the
+// snapshots folder must be the ones kept there, where the version of circom
+// is written as {version}
+fn snapshots() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("snapshots")
+}
+
+fn emit(name: &str, args: &[&str]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("circom_emit_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::copy(snapshots().join("emit.circom"), dir.join("emit.circom")).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_circom"))
+        .current_dir(&dir)
+        .arg("emit.circom")
+        .arg("--O2")
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    dir
+}
+
+fn check(dir: &Path, file: &str) {
+    let written = fs::read_to_string(dir.join(file)).unwrap().replace(env!("CARGO_PKG_VERSION"), "{version}");
+    let expected = fs::read_to_string(snapshots().join(file)).unwrap();
+    assert!(written == expected, "{} is not the one of the snapshots folder:\n{}", file, written);
+}
+
+#[test]
+fn the_ir_is_written_as_in_its_snapshot() {
+    let dir = emit("ir", &["--emit_ir"]);
+    check(&dir, "emit.ir");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn the_ir_of_the_snapshot_is_read_back() {
+    let dir = emit("load_ir", &["--emit_ir"]);
+    fs::rename(dir.join("emit.ir"), dir.join("loaded.ir")).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_circom"))
+        .current_dir(&dir)
+        .args(["emit.circom", "--O2", "--load_ir", "loaded.ir", "--emit_ir"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    check(&dir, "emit.ir");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn the_hir_text_is_written_as_in_its_snapshot() {
+    let dir = emit("hir", &["--emit_hir"]);
+    check(&dir, "emit_hir.txt");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn the_hir_json_is_written_as_in_its_snapshot() {
+    let dir = emit("hir_json", &["--emit_hir=json"]);
+    check(&dir, "emit_hir.json");
+    fs::remove_dir_all(dir).unwrap();
+}
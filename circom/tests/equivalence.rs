@@ -0,0 +1,91 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const OLD: &str = "pragma circom 2.0.0;
+
+template Mad() {
+    signal input a;
+    signal input b;
+    signal input c;
+    signal output out;
+    out <== a * b + c;
+}
+
+component main = Mad();
+";
+
+// The same circuit with the product in a signal of its own
+const REFACTORED: &str = "pragma circom 2.0.0;
+
+template Mad() {
+    signal input a;
+    signal input b;
+    signal input c;
+    signal output out;
+    signal product;
+    product <== b * a;
+    out <== c + product;
+}
+
+component main = Mad();
+";
+
+fn folder(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("circom_equiv_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Compiles the circuit in its own folder and gives the path of its r1cs file
+fn compile(dir: &Path, name: &str, circuit: &str) -> PathBuf {
+    let dir = dir.join(name);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("main.circom"), circuit).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_circom"))
+        .current_dir(&dir)
+        .args(["main.circom", "--r1cs", "--sym", "--O2"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    dir.join("main.r1cs")
+}
+
+fn equiv(old: &Path, new: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_circom")).arg("equiv").arg(old).arg(new).output().unwrap()
+}
+
+#[test]
+fn a_refactored_circuit_is_equivalent() {
+    let dir = folder("refactored");
+    let output = equiv(&compile(&dir, "old", OLD), &compile(&dir, "new", REFACTORED));
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("The circuits are equivalent"));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn circuits_with_different_public_inputs_differ() {
+    let dir = folder("public");
+    let public = OLD.replace("component main = Mad();", "component main {public [c]} = Mad();");
+    let output = equiv(&compile(&dir, "old", OLD), &compile(&dir, "new", &public));
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("The circuits are not equivalent"), "{}", stderr);
+    assert!(stderr.contains("different numbers of public outputs, public inputs and private inputs"), "{}", stderr);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// Without a solver, the constraints that differ are written in a query for it
+#[test]
+fn circuits_with_different_constraints_are_left_to_a_solver() {
+    let dir = folder("constraints");
+    let new = compile(&dir, "new", &OLD.replace("a * b + c", "a * b - c"));
+    let output = equiv(&compile(&dir, "old", OLD), &new);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("1 constraints are only in the old circuit and 1 only in the new one"), "{}", stderr);
+    assert!(new.with_file_name("main_equivalence.smt2").exists());
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,61 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+const CIRCUIT: &str = "pragma circom 2.0.0;
+
+template Square() {
+    signal input in;
+    signal output out;
+    out <== in * in;
+}
+
+template Main() {
+    signal input in;
+    signal output out;
+    component square = Square();
+    square.in <== in;
+    out <== square.out + 1;
+}
+
+component main = Main();
+";
+
+fn compile(dir: &Path, cache: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_circom"))
+        .current_dir(dir)
+        .args(["main.circom", "--c", "--O2", "--ir_cache", cache])
+        .output()
+        .unwrap()
+}
+
+fn folder(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("circom_ir_cache_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("main.circom"), CIRCUIT).unwrap();
+    dir
+}
+
+#[test]
+fn the_cached_instances_give_the_same_code() {
+    let dir = folder("reuse");
+    let first = compile(&dir, "cache");
+    assert!(first.status.success(), "{}", String::from_utf8_lossy(&first.stderr));
+    let code = fs::read_to_string(dir.join("main_c_cpp").join("main_c.cpp")).unwrap();
+    assert_eq!(fs::read_dir(dir.join("cache")).unwrap().count(), 2);
+    let second = compile(&dir, "cache");
+    assert!(second.status.success(), "{}", String::from_utf8_lossy(&second.stderr));
+    assert_eq!(fs::read_to_string(dir.join("main_c_cpp").join("main_c.cpp")).unwrap(), code);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn a_cache_that_cannot_be_created_is_not_used() {
+    let dir = folder("file");
+    fs::write(dir.join("cache"), "").unwrap();
+    let output = compile(&dir, "cache");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("The IR cache cache is not used"));
+    fs::remove_dir_all(dir).unwrap();
+}
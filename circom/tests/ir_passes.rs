@@ -0,0 +1,136 @@
+use std::fs;
+use std::process::Command;
+
+const CIRCUIT: &str = "pragma circom 2.0.0;
+
+function sum(n) {
+    var s = 0;
+    for (var i = 0; i < n; i++) {
+        s += i;
+    }
+    return s;
+}
+
+template Square() {
+    signal input in;
+    signal output out;
+    out <== in * in + sum(3);
+}
+
+component main = Square();
+";
+
+const O1_PASSES: [&str; 10] = [
+    "eliminate_tail_calls",
+    "reuse_variable_slots",
+    "propagate_copies",
+    "lower_array_copies",
+    "build_jump_tables",
+    "select_branches",
+    "reduce_strength",
+    "mark_vectorizable_loops",
+    "fuse_reductions",
+    "share_constants",
+];
+
+const O2_PASSES: [&str; 4] = ["fold_constants", "propagate_conditional_constants", "propagate_constant_inputs", "merge_instances"];
+
+// The passes that run and whether the code is verified, as given by
+// --pass_timing, or the error of the compiler
+fn run(name: &str, args: &[&str]) -> Result<(Vec<String>, bool), String> {
+    let dir = std::env::temp_dir().join(format!("circom_passes_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("main.circom"), CIRCUIT).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_circom"))
+        .current_dir(&dir)
+        .args(["main.circom", "--c", "--O2", "--pass_timing"])
+        .args(args)
+        .output()
+        .unwrap();
+    fs::remove_dir_all(dir).unwrap();
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut passes = Vec::new();
+    let mut verified = false;
+    for line in stdout.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if line.contains("Pass") && words.len() > 1 {
+            passes.push(words[1].to_string());
+        }
+        verified |= line.contains("Verification");
+    }
+    Ok((passes, verified))
+}
+
+#[test]
+fn no_pass_runs_by_default() {
+    let (passes, verified) = run("default", &[]).unwrap();
+    assert!(passes.is_empty(), "{:?}", passes);
+    assert!(!verified);
+}
+
+#[test]
+fn the_level_selects_the_passes() {
+    let (passes, _) = run("o1", &["--ir_opt", "1"]).unwrap();
+    assert_eq!(passes, O1_PASSES);
+    let (passes, _) = run("o2", &["--ir_opt", "2"]).unwrap();
+    assert_eq!(passes.len(), O1_PASSES.len() + O2_PASSES.len());
+    assert!(O1_PASSES.iter().chain(&O2_PASSES).all(|pass| passes.iter().any(|p| p == pass)));
+}
+
+#[test]
+fn disabled_passes_do_not_run() {
+    let (passes, _) = run("disabled", &["--ir_opt", "2", "--disable_pass", "fold_constants", "--disable_pass", "propagate_copies"]).unwrap();
+    assert_eq!(passes.len(), O1_PASSES.len() + O2_PASSES.len() - 2);
+    assert!(!passes.iter().any(|pass| pass == "fold_constants" || pass == "propagate_copies"));
+}
+
+#[test]
+fn unknown_passes_are_rejected() {
+    let error = run("unknown", &["--disable_pass", "unroll_loops"]).unwrap_err();
+    assert!(error.contains("unknown pass unroll_loops"), "{}", error);
+}
+
+#[test]
+fn unknown_levels_are_rejected() {
+    let error = run("level", &["--ir_opt", "3"]).unwrap_err();
+    assert!(error.contains("invalid IR optimization level"), "{}", error);
+}
+
+#[test]
+fn the_code_is_verified_with_verify_ir() {
+    let (passes, verified) = run("verify", &["--verify_ir"]).unwrap();
+    assert!(passes.is_empty());
+    assert!(verified);
+    let (_, verified) = run("verify_o2", &["--ir_opt", "2", "--verify_ir"]).unwrap();
+    assert!(verified);
+}
+
+#[test]
+fn the_report_gives_the_effect_of_each_pass_on_each_template() {
+    let dir = std::env::temp_dir().join(format!("circom_passes_report_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("main.circom"), CIRCUIT).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_circom"))
+        .current_dir(&dir)
+        .args(["main.circom", "--c", "--O2", "--ir_opt", "2", "--opt_report"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report = json::parse(&fs::read_to_string(dir.join("main_c_opt_report.json")).unwrap()).unwrap();
+    fs::remove_dir_all(dir).unwrap();
+    let names: Vec<_> = report.members().map(|code| code["name"].to_string()).collect();
+    assert!(names.iter().any(|name| name.starts_with("Square")), "{:?}", names);
+    for code in report.members() {
+        assert!(code["instructions_after"].as_usize().unwrap() <= code["instructions_before"].as_usize().unwrap());
+        for pass in code["passes"].members() {
+            for field in ["pass", "runs", "instructions_removed", "loops_removed", "branches_removed"] {
+                assert!(pass.has_key(field), "{} has no {}", pass, field);
+            }
+        }
+    }
+}
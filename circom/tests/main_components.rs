@@ -0,0 +1,57 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+// Main components of different templates, each one used by one of them
+const CIRCUITS: &str = "pragma circom 2.2.0;
+
+template Mul(n) {
+    signal input in;
+    signal output out;
+    out <== in * n;
+}
+
+template Square() {
+    signal input in;
+    signal output out;
+    out <== in * in;
+}
+
+component main = Mul(1);
+component main mul2 {public [in]} = Mul(2);
+component main square = Square();
+";
+
+fn compile(name: &str, circuit: &str) -> (PathBuf, Output) {
+    let dir = std::env::temp_dir().join(format!("circom_mains_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("circuits.circom"), circuit).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_circom"))
+        .current_dir(&dir)
+        .args(["circuits.circom", "--r1cs", "--O2"])
+        .output()
+        .unwrap();
+    (dir, output)
+}
+
+#[test]
+fn every_main_component_is_compiled() {
+    let (dir, output) = compile("all", CIRCUITS);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    for file in ["circuits.r1cs", "mul2.r1cs", "square.r1cs"] {
+        assert!(dir.join(file).exists(), "{} is not written", file);
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn the_public_signals_of_every_main_component_are_checked() {
+    let circuits = CIRCUITS.replace("component main square = Square();", "component main square {public [out]} = Square();");
+    let (dir, output) = compile("public", &circuits);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("out is not an input signal"), "{}", stderr);
+    assert!(!dir.join("circuits.r1cs").exists());
+    fs::remove_dir_all(dir).unwrap();
+}
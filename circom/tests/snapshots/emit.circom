@@ -0,0 +1,22 @@
+pragma circom 2.0.0;
+
+template Square() {
+    signal input in;
+    signal output out;
+    out <== in * in;
+}
+
+template Main(n) {
+    signal input in[n];
+    signal output out;
+    component squares[n];
+    var total = 0;
+    for (var i = 0; i < n; i++) {
+        squares[i] = Square();
+        squares[i].in <== in[i];
+        total += squares[i].out;
+    }
+    out <== total;
+}
+
+component main = Main(2);
@@ -0,0 +1,148 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+// A template that instantiates itself with a greater parameter until it gets
+// to the depth given by the main component
+const GROWING: &str = "pragma circom 2.0.0;
+
+template Chain(depth, max) {
+    signal input in;
+    signal output out;
+    if (depth < max) {
+        component next = Chain(depth + 1, max);
+        next.in <== in * in;
+        out <== next.out;
+    } else {
+        out <== in * in;
+    }
+}
+
+component main = Chain(0, 10);
+";
+
+// A template that instantiates itself with the same parameter
+const ENDLESS: &str = "pragma circom 2.0.0;
+
+template Loop(n) {
+    signal input in;
+    signal output out;
+    component next = Loop(n);
+    next.in <== in;
+    out <== next.out;
+}
+
+component main = Loop(1);
+";
+
+// A Merkle tree whose levels are built by the template of the tree with one
+// level less
+const MERKLE: &str = "pragma circom 2.0.0;
+
+template Hash() {
+    signal input left;
+    signal input right;
+    signal output out;
+    out <== left * right + left;
+}
+
+template MerkleTree(levels) {
+    signal input leaves[2 ** levels];
+    signal output root;
+    if (levels == 0) {
+        root <== leaves[0];
+    } else {
+        component left = MerkleTree(levels - 1);
+        component right = MerkleTree(levels - 1);
+        for (var i = 0; i < 2 ** (levels - 1); i++) {
+            left.leaves[i] <== leaves[i];
+            right.leaves[i] <== leaves[i + 2 ** (levels - 1)];
+        }
+        component hash = Hash();
+        hash.left <== left.root;
+        hash.right <== right.root;
+        root <== hash.out;
+    }
+}
+
+component main = MerkleTree(4);
+";
+
+// The same tree, where the last level does not stop the recursion
+const MERKLE_WITHOUT_LEAVES: &str = "pragma circom 2.0.0;
+
+template MerkleTree(levels, arity) {
+    signal input in;
+    signal output root;
+    component left = MerkleTree(levels - 1, arity);
+    left.in <== in;
+    root <== left.root * in;
+}
+
+component main = MerkleTree(30, 2);
+";
+
+fn compile(name: &str, circuit: &str, args: &[&str]) -> Output {
+    let dir = std::env::temp_dir().join(format!("circom_recursion_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("main.circom"), circuit).unwrap();
+    let output = run(&dir, args);
+    fs::remove_dir_all(dir).unwrap();
+    output
+}
+
+fn run(dir: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_circom"))
+        .current_dir(dir)
+        .arg("main.circom")
+        .args(["--r1cs", "--O2"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn recursion_with_greater_parameters_is_accepted() {
+    let output = compile("growing", GROWING, &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn recursion_deeper_than_the_limit_is_rejected() {
+    let output = compile("limit", GROWING, &["--template_recursion", "5"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("The template Chain is instantiated inside itself more than 5 times"), "{}", stderr);
+}
+
+#[test]
+fn recursion_that_does_not_end_is_rejected() {
+    let output = compile("endless", ENDLESS, &["--template_recursion", "10"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("The template Loop is instantiated inside itself more than 10 times"), "{}", stderr);
+    assert!(stderr.contains("the last time with n = 1"), "{}", stderr);
+}
+
+#[test]
+fn the_limit_must_be_positive() {
+    let output = compile("zero", GROWING, &["--template_recursion", "0"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid number of template instances"));
+}
+
+#[test]
+fn merkle_trees_are_built_by_recursion() {
+    let output = compile("merkle", MERKLE, &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn merkle_trees_without_leaves_name_the_parameters() {
+    let output = compile("merkle_without_leaves", MERKLE_WITHOUT_LEAVES, &["--template_recursion", "20"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("The template MerkleTree is instantiated inside itself more than 20 times"), "{}", stderr);
+    assert!(stderr.contains("the last time with levels = 9, arity = 2"), "{}", stderr);
+}
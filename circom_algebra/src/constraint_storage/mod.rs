@@ -46,17 +46,25 @@ impl ConstraintStorage {
     }
 
     pub fn extract_with(&mut self, filter: &dyn Fn(&C) -> bool) -> LinkedList<C> {
+        self.extract_with_ids(filter).0
+    }
+
+    // The constraints that are kept are renumbered in order, and the ids that
+    // they had before are returned with the extracted constraints
+    pub fn extract_with_ids(&mut self, filter: &dyn Fn(&C) -> bool) -> (LinkedList<C>, Vec<ConstraintID>) {
         let old = std::mem::take(&mut self.constraints);
         let mut removed = LinkedList::new();
-        for c in old {
+        let mut kept = Vec::new();
+        for (id, c) in old.into_iter().enumerate() {
             let decoded = logic::decode_constraint(&c, &self.field_tracker);
             if filter(&decoded) {
                 removed.push_back(decoded);
             } else {
                 self.constraints.push(c);
+                kept.push(id);
             }
         }
-        removed
+        (removed, kept)
     }
 
     pub fn get_ids(&self) -> Vec<ConstraintID> {
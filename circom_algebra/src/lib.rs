@@ -3,4 +3,5 @@ pub extern crate num_traits;
 pub mod algebra;
 pub mod constraint_storage;
 pub mod modular_arithmetic;
+pub mod range_analysis;
 pub mod simplification_utils;
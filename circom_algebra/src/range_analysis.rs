@@ -0,0 +1,226 @@
+use crate::algebra::Constraint;
+use crate::num_bigint::BigInt;
+use crate::num_traits::{One, Zero};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Display;
+use std::hash::Hash;
+
+// The most binary constraints that the support of a range keeps, beyond
+// which it is unknown
+const MAX_SUPPORT: usize = 64;
+// The most passes over the constraints of a circuit to propagate the ranges
+pub const MAX_ROUNDS: usize = 16;
+
+// The signal of a constraint whose only solutions are 0 and 1
+pub fn bit_signal<C>(constraint: &Constraint<C>, field: &BigInt) -> Option<C>
+where
+    C: Default + Clone + Display + Hash + Eq,
+{
+    let signals = constraint.take_cloned_signals();
+    let signal = match signals.iter().collect::<Vec<_>>().as_slice() {
+        [signal] => (*signal).clone(),
+        _ => return None,
+    };
+    let constant = Constraint::constant_coefficient();
+    let evaluate = |coefficients: &HashMap<C, BigInt>, value: u32| {
+        let coefficient = coefficients.get(&signal).cloned().unwrap_or_default();
+        coefficient * value + coefficients.get(&constant).cloned().unwrap_or_default()
+    };
+    let is_quadratic = [constraint.a(), constraint.b()]
+        .iter()
+        .all(|coefficients| coefficients.get(&signal).is_some_and(|value| !(value % field).is_zero()));
+    let is_root = |value| (evaluate(constraint.a(), value) * evaluate(constraint.b(), value) - evaluate(constraint.c(), value)) % field == BigInt::zero();
+    if is_quadratic && is_root(0) && is_root(1) {
+        Some(signal)
+    } else {
+        None
+    }
+}
+
+// The interval of integers between 0 and the prime that contains the value
+// of a signal, with the binary constraints that it follows from, by their
+// ids, or None when they are not known, as for the ranges that come from
+// outside the constraints
+#[derive(Clone)]
+pub struct Range {
+    pub min: BigInt,
+    pub max: BigInt,
+    pub support: Option<BTreeSet<usize>>,
+}
+
+impl Range {
+    pub fn is_binary(&self) -> bool {
+        self.min >= BigInt::zero() && self.max <= BigInt::one()
+    }
+}
+
+// The ranges of the signals, derived from the binary constraints and the
+// constraints that compute a signal from others with known ranges, as long
+// as the computation does not wrap around the prime
+pub struct Ranges {
+    field: BigInt,
+    ranges: HashMap<usize, Range>,
+    // the ids of the binary constraints of each signal
+    binary: HashMap<usize, BTreeSet<usize>>,
+}
+
+impl Ranges {
+    pub fn new(field: &BigInt) -> Ranges {
+        Ranges { field: field.clone(), ranges: HashMap::new(), binary: HashMap::new() }
+    }
+
+    pub fn get(&self, signal: usize) -> Option<&Range> {
+        self.ranges.get(&signal)
+    }
+
+    // Narrows the range of the signal, and returns whether it changed. A
+    // range as narrow as the current one replaces it when it does not follow
+    // from the binary constraints of the signal and the current one does
+    pub fn bound(&mut self, signal: usize, range: Range) -> bool {
+        let current = match self.ranges.get(&signal) {
+            Some(current) => current,
+            None => {
+                self.ranges.insert(signal, range);
+                return true;
+            }
+        };
+        let min = std::cmp::max(&current.min, &range.min).clone();
+        let max = std::cmp::min(&current.max, &range.max).clone();
+        if min > max {
+            // the constraints cannot be satisfied, which other analyses report
+            return false;
+        }
+        if min != current.min || max != current.max {
+            let support = if min == range.min && max == range.max { range.support } else { join(&current.support, &range.support) };
+            self.ranges.insert(signal, Range { min, max, support });
+            true
+        } else if min == range.min && max == range.max && self.is_circular(signal, current) && !self.is_circular(signal, &range) {
+            self.ranges.insert(signal, range);
+            true
+        } else {
+            false
+        }
+    }
+
+    // whether the range may follow from a binary constraint of its signal
+    pub fn is_circular(&self, signal: usize, range: &Range) -> bool {
+        match (&range.support, self.binary.get(&signal)) {
+            (Some(support), Some(constraints)) => !support.is_disjoint(constraints),
+            (Some(_), None) => false,
+            (None, _) => true,
+        }
+    }
+
+    // The integers that the linear expression takes, with its coefficients
+    // as the integers closest to 0 and the signals shifted by the offset,
+    // without the signal skipped
+    pub fn linear_range(&self, coefficients: &HashMap<usize, BigInt>, offset: usize, skip: Option<usize>) -> Option<Range> {
+        let constant = Constraint::<usize>::constant_coefficient();
+        let mut range = Range { min: BigInt::zero(), max: BigInt::zero(), support: Some(BTreeSet::new()) };
+        for (signal, coefficient) in coefficients {
+            let coefficient = self.signed(coefficient);
+            if coefficient.is_zero() {
+                continue;
+            }
+            if *signal == constant {
+                range.min += &coefficient;
+                range.max += &coefficient;
+                continue;
+            }
+            if Some(*signal) == skip {
+                continue;
+            }
+            let term = self.ranges.get(&(signal + offset))?;
+            let (low, high) = (&coefficient * &term.min, &coefficient * &term.max);
+            let (low, high) = if coefficient > BigInt::zero() { (low, high) } else { (high, low) };
+            range.min += low;
+            range.max += high;
+            range.support = join(&range.support, &term.support);
+        }
+        Some(range)
+    }
+
+    // The range that a value of these integers takes in the field, if it
+    // does not wrap around the prime
+    pub fn to_field(&self, range: Range) -> Option<Range> {
+        if range.min >= BigInt::zero() && range.max < self.field {
+            Some(range)
+        } else if range.max < BigInt::zero() && range.min > -&self.field {
+            Some(Range { min: range.min + &self.field, max: range.max + &self.field, support: range.support })
+        } else {
+            None
+        }
+    }
+
+    // Narrows the ranges of the signals of the constraint, given with the
+    // offset of its signals and its id, and returns whether any of them
+    // changed. A binary constraint gives its signal the range [0, 1], and
+    // the rest give a range to each signal of C with coefficient 1 or -1 that
+    // is not in A or B, from the ranges of the rest of the signals
+    pub fn derive(&mut self, constraint: &Constraint<usize>, offset: usize, id: usize) -> bool {
+        if let Some(signal) = bit_signal(constraint, &self.field) {
+            let range = Range { min: BigInt::zero(), max: BigInt::one(), support: Some(BTreeSet::from([id])) };
+            self.binary.entry(signal + offset).or_default().insert(id);
+            return self.bound(signal + offset, range);
+        }
+        let product = if constraint.a().is_empty() || constraint.b().is_empty() {
+            Some(Range { min: BigInt::zero(), max: BigInt::zero(), support: Some(BTreeSet::new()) })
+        } else {
+            match (self.linear_range(constraint.a(), offset, None), self.linear_range(constraint.b(), offset, None)) {
+                (Some(a), Some(b)) => Some(multiply(&a, &b)),
+                _ => None,
+            }
+        };
+        let product = match product {
+            Some(product) => product,
+            None => return false,
+        };
+        let mut changed = false;
+        for (signal, coefficient) in constraint.c() {
+            let coefficient = self.signed(coefficient);
+            let appears = |side: &HashMap<usize, BigInt>| side.get(signal).is_some_and(|value| !(value % &self.field).is_zero());
+            if *signal == Constraint::<usize>::constant_coefficient() || appears(constraint.a()) || appears(constraint.b()) {
+                continue;
+            }
+            if coefficient != BigInt::one() && coefficient != -BigInt::one() {
+                continue;
+            }
+            let rest = match self.linear_range(constraint.c(), offset, Some(*signal)) {
+                Some(rest) => rest,
+                None => continue,
+            };
+            // coefficient * signal = product - rest
+            let support = join(&product.support, &rest.support);
+            let (min, max) = (&product.min - &rest.max, &product.max - &rest.min);
+            let (min, max) = if coefficient == BigInt::one() { (min, max) } else { (-max, -min) };
+            if let Some(range) = self.to_field(Range { min, max, support }) {
+                changed |= self.bound(signal + offset, range);
+            }
+        }
+        changed
+    }
+
+    // the representative of the value between -p/2 and p/2
+    fn signed(&self, value: &BigInt) -> BigInt {
+        let value = ((value % &self.field) + &self.field) % &self.field;
+        if value > &self.field / 2 {
+            value - &self.field
+        } else {
+            value
+        }
+    }
+}
+
+fn multiply(a: &Range, b: &Range) -> Range {
+    let products = [&a.min * &b.min, &a.min * &b.max, &a.max * &b.min, &a.max * &b.max];
+    let min = products.iter().min().unwrap().clone();
+    let max = products.iter().max().unwrap().clone();
+    Range { min, max, support: join(&a.support, &b.support) }
+}
+
+fn join(a: &Option<BTreeSet<usize>>, b: &Option<BTreeSet<usize>>) -> Option<BTreeSet<usize>> {
+    match (a, b) {
+        (Some(a), Some(b)) if a.len() + b.len() <= MAX_SUPPORT => Some(a.union(b).cloned().collect()),
+        _ => None,
+    }
+}
@@ -237,6 +237,54 @@ pub fn function_table() -> CInstruction {
     format!("{}", FUNCTION_TABLE)
 }
 
+// input counter of a component that has already been run
+pub const RUN_COMPONENT_COUNTER: &str = "0xFFFFFFFF";
+
+// Runs the component if all its inputs are set and it has not been run yet
+pub fn run_deferred_component(component: &str) -> Vec<CInstruction> {
+    let counter = format!("{}->componentMemory[{}].inputCounter", CIRCOM_CALC_WIT, component);
+    let template_id = format!("{}->componentMemory[{}].templateId", CIRCOM_CALC_WIT, component);
+    let run = build_call(
+        format!("(*{}[{}])", FUNCTION_TABLE, template_id),
+        vec![component.to_string(), CIRCOM_CALC_WIT.to_string()],
+    );
+    vec![
+        format!("if ({} == 0) {{", counter),
+        format!("{} = {};", counter, RUN_COMPONENT_COUNTER),
+        format!("{};", run),
+        "}".to_string(),
+    ]
+}
+
+// Runs the created subcomponents that have not been run by the loads of
+// their outputs
+pub fn run_deferred_subcomponents(number_of_components: usize) -> Vec<CInstruction> {
+    let mut instructions = vec![];
+    instructions.push(format!("for (uint i = 0; i < {}; i++) {{", number_of_components));
+    instructions.push(format!("uint index_subc = {}[i];", MY_SUBCOMPONENTS));
+    instructions.push("if (index_subc != 0) {".to_string());
+    instructions.append(&mut run_deferred_component("index_subc"));
+    instructions.push("}".to_string());
+    instructions.push("}".to_string());
+    instructions
+}
+
+// Releases the memory of a subcomponent that is not used anymore by the
+// component once it has been run: its arrays of subcomponents, outputs,
+// mutexes and threads. Its signals are kept, as they are part of the witness
+pub fn release_subcomponent(component: usize, run_counter: &str) -> Vec<CInstruction> {
+    let position = format!("{}[{}]", MY_SUBCOMPONENTS, component);
+    vec![
+        format!(
+            "if ({} != 0 && {}->componentMemory[{}].inputCounter == {}) {{",
+            position, CIRCOM_CALC_WIT, position, run_counter
+        ),
+        format!("{};", build_call("release_memory_component".to_string(), vec![CIRCOM_CALC_WIT.to_string(), position.clone()])),
+        format!("{} = 0;", position),
+        "}".to_string(),
+    ]
+}
+
 pub const FUNCTION_TABLE_PARALLEL: &str = "_functionTableParallel";
 pub fn function_table_parallel() -> CInstruction {
     format!("{}", FUNCTION_TABLE_PARALLEL)
@@ -431,12 +479,10 @@ pub fn generate_my_trace() -> String {
 }
 
 pub fn build_failed_assert_message(line: usize) -> String{
-    
-    format!("std::cout << \"Failed assert in template/function \" << {} << \" line {}. \" <<  \"Followed trace of components: \" << {} << std::endl" ,
-        MY_TEMPLATE_NAME,
-        line,
-        generate_my_trace()
-     )
+    build_call(
+        "failed_assert".to_string(),
+        vec![CIRCOM_CALC_WIT.to_string(), MY_TEMPLATE_NAME.to_string(), MY_ID.to_string(), line.to_string()],
+    )
 }
 
 
@@ -871,6 +917,49 @@ pub fn generate_function_release_memory_component() -> Vec<String>{
     instructions
 }
 
+// The message of a failed assert is built out of the code of the templates
+pub fn generate_function_failed_assert() -> Vec<String>{
+    let mut instructions = vec![];
+    instructions.push("__attribute__((cold, noinline))\n".to_string());
+    instructions.push(format!("void failed_assert(Circom_CalcWit* {}, const std::string& {}, u64 {}, uint line) {{\n", CIRCOM_CALC_WIT, MY_TEMPLATE_NAME, MY_ID));
+    // the fuzzing harness counts the failed asserts and checks the witness
+    instructions.push("#ifdef CIRCOM_FUZZ\nextern uint circom_failed_asserts;\ncircom_failed_asserts++;\nreturn;\n#endif\n".to_string());
+    instructions.push(format!("std::cout << \"Failed assert in template/function \" << {} << \" line \" << line << \". \" <<  \"Followed trace of components: \" << {} << std::endl;\n", MY_TEMPLATE_NAME, generate_my_trace()));
+    instructions.push("}\n\n".to_string());
+    instructions
+}
+
+// The loops that add elements, or products of elements, of arrays are replaced
+// by calls to these functions
+pub fn generate_function_fused_field_operations(producer: &CProducer) -> Vec<String>{
+    let mut instructions = vec![];
+    if producer.prime_str != "goldilocks" {
+        instructions.push("void Fr_add_n(PFrElement r, PFrElement a, uint stride_a, uint n) {\n".to_string());
+        instructions.push("FrElement sum;\n".to_string());
+        instructions.push("for (uint i = 0; i < n; i++) {\n".to_string());
+        instructions.push("Fr_add(&sum, r, &a[i * stride_a]);\n".to_string());
+        instructions.push("Fr_copy(r, &sum);\n".to_string());
+        instructions.push("}\n}\n\n".to_string());
+        instructions.push("void Fr_mul_add_n(PFrElement r, PFrElement a, uint stride_a, PFrElement b, uint stride_b, uint n) {\n".to_string());
+        instructions.push("FrElement product;\nFrElement sum;\n".to_string());
+        instructions.push("for (uint i = 0; i < n; i++) {\n".to_string());
+        instructions.push("Fr_mul(&product, &a[i * stride_a], &b[i * stride_b]);\n".to_string());
+        instructions.push("Fr_add(&sum, r, &product);\n".to_string());
+        instructions.push("Fr_copy(r, &sum);\n".to_string());
+        instructions.push("}\n}\n\n".to_string());
+    } else {
+        instructions.push("uint64_t Fr_add_n(uint64_t r, uint64_t* a, uint stride_a, uint n) {\n".to_string());
+        instructions.push("for (uint i = 0; i < n; i++) {\n".to_string());
+        instructions.push("r = Fr_add(r, a[i * stride_a]);\n".to_string());
+        instructions.push("}\nreturn r;\n}\n\n".to_string());
+        instructions.push("uint64_t Fr_mul_add_n(uint64_t r, uint64_t* a, uint stride_a, uint64_t* b, uint stride_b, uint n) {\n".to_string());
+        instructions.push("for (uint i = 0; i < n; i++) {\n".to_string());
+        instructions.push("r = Fr_add(r, Fr_mul(a[i * stride_a], b[i * stride_b]));\n".to_string());
+        instructions.push("}\nreturn r;\n}\n\n".to_string());
+    }
+    instructions
+}
+
 pub fn generate_function_release_memory_circuit() -> Vec<String>{ 
     // deleting each one of the components
     let mut instructions = vec![];
@@ -1170,6 +1259,81 @@ pub fn generate_make_file(
     Ok(())
 }
 
+// The libFuzzer harness of the witness generator, its target of the
+// Makefile and a corpus with an input of zeros to start from
+pub fn generate_fuzz_files(c_folder: &std::path::Path, run_name: &str, r1cs_file: &str, producer: &CProducer) -> std::io::Result<()> {
+    let template = handlebars::Handlebars::new();
+    let code = template
+        .render_template(
+            include_str!("common/fuzz.cpp"),
+            &json!({
+                "run_name": run_name,
+                "r1cs_file": r1cs_file,
+            }),
+        )
+        .expect("must render");
+    std::fs::write(c_folder.join("fuzz.cpp"), code)?;
+
+    let fr_asm = if producer.no_asm { "" } else { " fr_asm.o" };
+    let mut makefile = std::fs::OpenOptions::new().append(true).open(c_folder.join("Makefile"))?;
+    write!(
+        makefile,
+        "\n# the fuzzing harness, built with clang and libFuzzer, where the circuit is\n\
+         # compiled without the asserts that stop the witness generation\n\
+         FUZZ_CC=clang++\n\
+         FUZZ_FLAGS=-std=c++11 -O1 -g -I. -Wno-return-type -fsanitize=fuzzer,address\n\
+         \n\
+         fuzz: {run}_fuzz\n\
+         \n\
+         {run}_fuzz: fuzz.cpp main.cpp {run}.cpp calcwit.cpp fr.cpp $(DEPS_HPP){asm}\n\
+         \tmkdir -p fuzz_build\n\
+         \t$(FUZZ_CC) $(FUZZ_FLAGS) -DNDEBUG -DCIRCOM_FUZZ -c fuzz.cpp -o fuzz_build/fuzz.o\n\
+         \t$(FUZZ_CC) $(FUZZ_FLAGS) -DNDEBUG -DCIRCOM_FUZZ -c {run}.cpp -o fuzz_build/{run}.o\n\
+         \t$(FUZZ_CC) $(FUZZ_FLAGS) -o {run}_fuzz fuzz_build/*.o calcwit.cpp fr.cpp{asm} -lgmp -pthread\n",
+        run = run_name,
+        asm = fr_asm
+    )?;
+
+    let mut zeros = serde_json::Map::new();
+    for input in producer.get_main_input_list().iter().filter(|input| input.bus_id.is_none()) {
+        let value = if input.size == 1 { json!("0") } else { json!(vec!["0"; input.size]) };
+        zeros.insert(input.name.clone(), value);
+    }
+    let corpus = c_folder.join("fuzz_corpus");
+    std::fs::create_dir_all(&corpus)?;
+    std::fs::write(corpus.join("zeros.json"), serde_json::Value::Object(zeros).to_string())
+}
+
+// The benchmark of the witness generator and its target of the Makefile,
+// built with the flags of the witness generator so that it measures its code
+pub fn generate_bench_files(c_folder: &std::path::Path, run_name: &str, producer: &CProducer) -> std::io::Result<()> {
+    let template = handlebars::Handlebars::new();
+    let code = template
+        .render_template(
+            include_str!("common/bench.cpp"),
+            &json!({
+                "run_name": run_name,
+            }),
+        )
+        .expect("must render");
+    std::fs::write(c_folder.join("bench.cpp"), code)?;
+
+    let fr_asm = if producer.no_asm { "" } else { " fr_asm.o" };
+    let mut makefile = std::fs::OpenOptions::new().append(true).open(c_folder.join("Makefile"))?;
+    write!(
+        makefile,
+        "\n# the benchmark of the witness generator, linked without main.o so that\n\
+         # the objects of the folder still build the witness generator\n\
+         .PHONY: bench\n\
+         bench: {run}_bench\n\
+         \n\
+         {run}_bench: bench.cpp main.cpp {run}.o calcwit.o fr.o{asm} $(DEPS_HPP)\n\
+         \t$(CC) $(CFLAGS) -o {run}_bench bench.cpp {run}.o calcwit.o fr.o{asm} -lgmp -pthread\n",
+        run = run_name,
+        asm = fr_asm
+    )
+}
+
 pub fn generate_json2bin64(c_folder: &PathBuf, producer: &CProducer) -> std::io::Result<()> {
     use std::io::BufWriter;
     let mut file_path = c_folder.clone();
@@ -0,0 +1,186 @@
+// The benchmark of the witness generator: for each json file of inputs, the
+// witness is computed a number of times after some warmup runs, which are
+// not measured, and it reports the mean and the percentiles of the times of
+// the runs and the peak resident memory of the process.
+//
+//   {{run_name}}_bench [-n runs] [-w warmup] <input.json>...
+//
+// When CIRCOM_BENCH gives a file, the results are also written to it as json,
+// to compare them between versions of the circuit or of the compiler.
+
+#include <cmath>
+#include <sys/resource.h>
+
+// the functions of the witness generator, without its main
+#define main witness_generator_main
+#include "main.cpp"
+#undef main
+
+struct BenchInput {
+  u64 hash;
+  std::vector<FrElement> values;
+};
+
+struct BenchResult {
+  std::string file;
+  std::vector<double> milliseconds;
+};
+
+// The inputs of the json file, which are read once so that the runs only
+// measure the witness generation
+static std::vector<BenchInput> readInputs(std::string const &fileName) {
+  std::ifstream inStream(fileName);
+  if (!inStream) {
+    std::cerr << "Input file not found: " << fileName << "\n";
+    exit(EXIT_FAILURE);
+  }
+  json jin = json::parse(inStream, nullptr, false);
+  if (jin.is_discarded() || !jin.is_object()) {
+    std::cerr << "The input file is not a json object: " << fileName << "\n";
+    exit(EXIT_FAILURE);
+  }
+  json j;
+  qualify_input("", jin, j);
+  std::vector<BenchInput> inputs;
+  for (json::iterator it = j.begin(); it != j.end(); ++it) {
+    BenchInput input;
+    input.hash = fnv1a(it.key());
+    json2FrElements(it.value(), input.values);
+    inputs.push_back(input);
+  }
+  return inputs;
+}
+
+// The milliseconds of a witness generation, from the creation of its
+// context to the last of its signals
+static double runWitness(Circom_Circuit *circuit, std::vector<BenchInput> &inputs) {
+  auto start = std::chrono::steady_clock::now();
+  Circom_CalcWit *ctx = new Circom_CalcWit(circuit);
+  if (inputs.size() == 0) {
+    ctx->tryRunCircuit();
+  }
+  for (uint i = 0; i < inputs.size(); i++) {
+    for (uint k = 0; k < inputs[i].values.size(); k++) {
+      ctx->setInputSignal(inputs[i].hash, k, inputs[i].values[k]);
+    }
+  }
+  auto end = std::chrono::steady_clock::now();
+  if (ctx->getRemaingInputsToBeSet() != 0) {
+    std::cerr << "Not all inputs have been set. Only " << get_main_input_signal_no() - ctx->getRemaingInputsToBeSet()
+              << " out of " << get_main_input_signal_no() << std::endl;
+    exit(EXIT_FAILURE);
+  }
+  delete[] ctx->signalValues;
+  delete[] ctx->componentMemory;
+  delete ctx;
+  return std::chrono::duration<double, std::milli>(end - start).count();
+}
+
+// The nearest rank percentile of the sorted times
+static double percentile(std::vector<double> const &sorted, double p) {
+  size_t rank = (size_t)std::ceil(p / 100.0 * sorted.size());
+  return sorted[std::max<size_t>(rank, 1) - 1];
+}
+
+static double mean(std::vector<double> const &values) {
+  double sum = 0;
+  for (double value : values) sum += value;
+  return sum / values.size();
+}
+
+// The peak resident memory of the process in megabytes, which getrusage
+// gives in kilobytes on Linux and in bytes on macOS
+static double peakRss() {
+  struct rusage usage;
+  getrusage(RUSAGE_SELF, &usage);
+#ifdef __APPLE__
+  return usage.ru_maxrss / (1024.0 * 1024.0);
+#else
+  return usage.ru_maxrss / 1024.0;
+#endif
+}
+
+static void writeResults(std::string const &fileName, std::vector<BenchResult> const &results, uint runs, uint warmup) {
+  json jout;
+  jout["circuit"] = "{{run_name}}";
+  jout["runs"] = runs;
+  jout["warmup"] = warmup;
+  jout["peak_rss_mb"] = peakRss();
+  jout["inputs"] = json::array();
+  for (auto const &result : results) {
+    std::vector<double> sorted(result.milliseconds);
+    std::sort(sorted.begin(), sorted.end());
+    jout["inputs"].push_back({
+      {"file", result.file},
+      {"mean_ms", mean(sorted)},
+      {"min_ms", sorted.front()},
+      {"p50_ms", percentile(sorted, 50)},
+      {"p90_ms", percentile(sorted, 90)},
+      {"p99_ms", percentile(sorted, 99)},
+      {"max_ms", sorted.back()},
+    });
+  }
+  std::ofstream outStream(fileName);
+  if (!outStream) {
+    std::cerr << "Could not write the benchmark file: " << fileName << "\n";
+    exit(EXIT_FAILURE);
+  }
+  outStream << jout.dump(2) << "\n";
+}
+
+static void usage(std::string const &program) {
+  std::cerr << "Usage: " << program << " [-n runs] [-w warmup] <input.json>...\n";
+  exit(EXIT_FAILURE);
+}
+
+int main(int argc, char *argv[]) {
+  std::string program(argv[0]);
+  uint runs = 100;
+  uint warmup = 10;
+  std::vector<std::string> files;
+  for (int i = 1; i < argc; i++) {
+    std::string arg(argv[i]);
+    if ((arg == "-n" || arg == "-w") && i + 1 < argc) {
+      int value = atoi(argv[++i]);
+      if (value < 0 || (arg == "-n" && value == 0)) usage(program);
+      (arg == "-n" ? runs : warmup) = value;
+    } else if (arg.size() > 0 && arg[0] == '-') {
+      usage(program);
+    } else {
+      files.push_back(arg);
+    }
+  }
+  if (files.size() == 0) usage(program);
+
+  Circom_Circuit *circuit = loadCircuit(program + ".dat");
+  std::vector<BenchResult> results;
+  std::cout << std::left << std::setw(32) << "input" << std::right << std::setw(12) << "mean (ms)"
+            << std::setw(12) << "p50" << std::setw(12) << "p90" << std::setw(12) << "p99"
+            << std::setw(12) << "min" << std::setw(12) << "max" << "\n";
+  for (auto const &file : files) {
+    std::vector<BenchInput> inputs = readInputs(file);
+    for (uint i = 0; i < warmup; i++) {
+      runWitness(circuit, inputs);
+    }
+    BenchResult result;
+    result.file = file;
+    for (uint i = 0; i < runs; i++) {
+      result.milliseconds.push_back(runWitness(circuit, inputs));
+    }
+    std::vector<double> sorted(result.milliseconds);
+    std::sort(sorted.begin(), sorted.end());
+    std::cout << std::left << std::setw(32) << file << std::right << std::fixed << std::setprecision(3)
+              << std::setw(12) << mean(sorted) << std::setw(12) << percentile(sorted, 50)
+              << std::setw(12) << percentile(sorted, 90) << std::setw(12) << percentile(sorted, 99)
+              << std::setw(12) << sorted.front() << std::setw(12) << sorted.back() << "\n";
+    results.push_back(result);
+  }
+  std::cout << runs << " runs of each input after " << warmup << " warmup runs, peak RSS "
+            << std::fixed << std::setprecision(1) << peakRss() << " MB\n";
+
+  const char *benchFile = getenv("CIRCOM_BENCH");
+  if (benchFile != nullptr) {
+    writeResults(benchFile, results, runs, warmup);
+  }
+  return 0;
+}
@@ -6,6 +6,7 @@
 #include <functional>
 #include <atomic>
 #include <memory>
+#include <chrono>
 
 #include "circom.hpp"
 #include "fr.hpp"
@@ -67,4 +68,42 @@ private:
 
 typedef void (*Circom_TemplateFunction)(uint __cIdx, Circom_CalcWit* __ctx); 
 
+typedef void (*Circom_ExternFunction)(FrElement* args, uint nArgs, FrElement* result, uint resultSize);
+
+bool registerExternFunction(std::string name, Circom_ExternFunction f);
+
+void callExternFunction(std::string name, FrElement* args, uint nArgs, FrElement* result, uint resultSize);
+
+// Receives each value stored in a signal by a circuit compiled with --trace,
+// with the position of the signal and the line of the store
+typedef void (*Circom_SignalTracer)(u64 signal, FrElement* value, uint line);
+
+void registerSignalTracer(Circom_SignalTracer f);
+
+void traceSignals(Circom_CalcWit* ctx, u64 signal, uint size, uint line);
+
+// Measures a run of a template of a circuit compiled with --profile, from the
+// start of its run function to the end, in the thread that runs it
+class Circom_Profile {
+  Circom_Profile *parent;
+  size_t stackLength;
+  u64 childrenNanoseconds;
+  std::chrono::steady_clock::time_point start;
+
+public:
+  Circom_Profile(const char *templateName);
+  ~Circom_Profile();
+};
+
+// The calls and the time of the runs of a stack of templates, without the
+// time of the runs of its subcomponents in the same thread
+struct Circom_ProfileEntry {
+  u64 calls;
+  u64 nanoseconds;
+};
+
+// The profile by the stacks of templates of each thread, from the first
+// template run in it, as "thread 0;Main_0;Num2Bits_1"
+std::map<std::string, Circom_ProfileEntry> getProfile();
+
 #endif // CIRCOM_CALCWIT_H
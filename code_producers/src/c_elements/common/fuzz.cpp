@@ -0,0 +1,203 @@
+// The libFuzzer harness of the witness generator: each input of the fuzzer
+// is read as the json object of the inputs of the circuit, and its witness
+// is checked against the constraints of the r1cs file. It reports:
+//  - the crashes of the witness generation,
+//  - the witnesses whose generation fails an assertion while the constraints
+//    hold for them, which are assertions that the constraints do not enforce,
+//  - and the witnesses whose generation does not fail while a constraint
+//    does not hold for them.
+// The inputs that are not valid json, or that do not give the inputs of the
+// circuit, are skipped.
+
+// the functions of the witness generator, without its main
+#define main witness_generator_main
+#include "main.cpp"
+#undef main
+
+// the assertions that fail in the witness generation of an input, which
+// the circuit compiled with CIRCOM_FUZZ counts instead of stopping
+uint circom_failed_asserts = 0;
+
+struct R1csTerm {
+  u32 wire;
+  FrElement coefficient;
+};
+
+struct R1csConstraint {
+  std::vector<R1csTerm> a;
+  std::vector<R1csTerm> b;
+  std::vector<R1csTerm> c;
+};
+
+static Circom_Circuit *circuit;
+static std::vector<R1csConstraint> constraints;
+
+static u64 readNumber(std::vector<u8> &bytes, u64 &pos, uint size) {
+  if (pos + size > bytes.size()) {
+    std::cerr << "The r1cs file is not valid\n";
+    exit(EXIT_FAILURE);
+  }
+  u64 n = 0;
+  for (uint i = size; i > 0; i--) {
+    n = (n << 8) | bytes[pos + i - 1];
+  }
+  pos += size;
+  return n;
+}
+
+static void readLinearCombination(std::vector<u8> &bytes, u64 &pos, uint n8, std::vector<R1csTerm> &terms) {
+  u64 n = readNumber(bytes, pos, 4);
+  for (u64 i = 0; i < n; i++) {
+    R1csTerm term;
+    term.wire = readNumber(bytes, pos, 4);
+    term.coefficient.type = Fr_LONG;
+    term.coefficient.shortVal = 0;
+    memset(term.coefficient.longVal, 0, sizeof(term.coefficient.longVal));
+    if (pos + n8 > bytes.size() || n8 > sizeof(term.coefficient.longVal)) {
+      std::cerr << "The r1cs file is not valid\n";
+      exit(EXIT_FAILURE);
+    }
+    memcpy(term.coefficient.longVal, &bytes[pos], n8);
+    pos += n8;
+    terms.push_back(term);
+  }
+}
+
+// The constraints of the r1cs file: a header section with the size of the
+// field elements and the number of constraints, and a section with them
+static void loadR1cs(std::string const &r1csFileName) {
+  std::ifstream inStream(r1csFileName, std::ios::binary);
+  if (!inStream) {
+    std::cerr << "r1cs file not found: " << r1csFileName << "\n";
+    exit(EXIT_FAILURE);
+  }
+  std::vector<u8> bytes((std::istreambuf_iterator<char>(inStream)), std::istreambuf_iterator<char>());
+  u64 pos = 8;
+  u64 nSections = readNumber(bytes, pos, 4);
+  uint n8 = 0;
+  u64 nConstraints = 0;
+  u64 constraintsPos = 0;
+  for (u64 i = 0; i < nSections; i++) {
+    u64 type = readNumber(bytes, pos, 4);
+    u64 size = readNumber(bytes, pos, 8);
+    if (type == 1) {
+      u64 header = pos;
+      n8 = readNumber(bytes, header, 4);
+      header += n8 + 4 * 4 + 8;
+      nConstraints = readNumber(bytes, header, 4);
+    } else if (type == 2) {
+      constraintsPos = pos;
+    }
+    pos += size;
+  }
+  if (n8 == 0 || constraintsPos == 0) {
+    std::cerr << "The r1cs file is not valid: " << r1csFileName << "\n";
+    exit(EXIT_FAILURE);
+  }
+  pos = constraintsPos;
+  for (u64 i = 0; i < nConstraints; i++) {
+    R1csConstraint constraint;
+    readLinearCombination(bytes, pos, n8, constraint.a);
+    readLinearCombination(bytes, pos, n8, constraint.b);
+    readLinearCombination(bytes, pos, n8, constraint.c);
+    constraints.push_back(constraint);
+  }
+}
+
+static void evaluate(Circom_CalcWit *ctx, std::vector<R1csTerm> &terms, FrElement *result) {
+  Fr_str2element(result, "0", 10);
+  for (uint i = 0; i < terms.size(); i++) {
+    FrElement value, product, sum;
+    ctx->getWitness(terms[i].wire, &value);
+    Fr_mul(&product, &terms[i].coefficient, &value);
+    Fr_add(&sum, result, &product);
+    Fr_copy(result, &sum);
+  }
+}
+
+// The first constraint that does not hold for the witness, or -1
+static long failedConstraint(Circom_CalcWit *ctx) {
+  for (uint i = 0; i < constraints.size(); i++) {
+    FrElement a, b, c, product, difference;
+    evaluate(ctx, constraints[i].a, &a);
+    evaluate(ctx, constraints[i].b, &b);
+    evaluate(ctx, constraints[i].c, &c);
+    Fr_mul(&product, &a, &b);
+    Fr_sub(&difference, &product, &c);
+    if (Fr_isTrue(&difference)) return i;
+  }
+  return -1;
+}
+
+// The position of an input in the hash map of the circuit, or -1 when the
+// circuit has no input with that name
+static long inputPosition(u64 h) {
+  uint n = get_size_of_input_hashmap();
+  for (uint i = 0; i < n; i++) {
+    uint pos = (uint)((h + i) % (u64)n);
+    if (circuit->InputHashMap[pos].hash == h) return pos;
+    if (circuit->InputHashMap[pos].signalid == 0) return -1;
+  }
+  return -1;
+}
+
+// Sets the inputs of the json object, and fails if it does not give each
+// input of the circuit with its size
+static bool setInputs(Circom_CalcWit *ctx, json &jin) {
+  json j;
+  qualify_input("", jin, j);
+  if (j.size() == 0) {
+    ctx->tryRunCircuit();
+  }
+  for (json::iterator it = j.begin(); it != j.end(); ++it) {
+    long pos = inputPosition(fnv1a(it.key()));
+    if (pos < 0) return false;
+    std::vector<FrElement> v;
+    json2FrElements(it.value(), v);
+    if (v.size() != circuit->InputHashMap[pos].signalsize) return false;
+    for (uint i = 0; i < v.size(); i++) {
+      ctx->setInputSignal(circuit->InputHashMap[pos].hash, i, v[i]);
+    }
+  }
+  return ctx->getRemaingInputsToBeSet() == 0;
+}
+
+// The witness generator is loaded from the dat file next to the harness,
+// and the constraints from the r1cs file of the circuit
+extern "C" int LLVMFuzzerInitialize(int *argc, char ***argv) {
+  std::string program((*argv)[0]);
+  size_t slash = program.find_last_of('/');
+  std::string folder = slash == std::string::npos ? "." : program.substr(0, slash);
+  circuit = loadCircuit(folder + "/{{run_name}}.dat");
+  loadR1cs(folder + "/../{{r1cs_file}}");
+  return 0;
+}
+
+extern "C" int LLVMFuzzerTestOneInput(const uint8_t *data, size_t size) {
+  json jin = json::parse(data, data + size, nullptr, false);
+  if (jin.is_discarded() || !jin.is_object()) return 0;
+  Circom_CalcWit *ctx = new Circom_CalcWit(circuit);
+  circom_failed_asserts = 0;
+  bool valid;
+  try {
+    valid = setInputs(ctx, jin);
+  } catch (std::exception &e) {
+    valid = false;
+  }
+  if (valid) {
+    long failed = failedConstraint(ctx);
+    if (failed >= 0 && circom_failed_asserts == 0) {
+      std::cerr << "The constraint " << failed << " of the r1cs file does not hold, and the witness generation does not fail\n";
+      abort();
+    }
+    if (failed < 0 && circom_failed_asserts > 0) {
+      std::cerr << "The witness generation fails in " << circom_failed_asserts
+                << " assertion(s), and all the constraints hold: the constraints do not enforce them\n";
+      abort();
+    }
+  }
+  delete[] ctx->signalValues;
+  delete[] ctx->componentMemory;
+  delete ctx;
+  return 0;
+}
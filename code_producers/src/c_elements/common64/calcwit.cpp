@@ -1,3 +1,4 @@
+#include <iostream>
 #include <iomanip>
 #include <sstream>
 #include <assert.h>
@@ -129,3 +130,60 @@ std::string Circom_CalcWit::generate_position_array(uint* dimensions, uint size_
   return positions;
 }
 
+static std::map<std::string, Circom_ExternFunction>& externFunctions() {
+  static std::map<std::string, Circom_ExternFunction> functions;
+  return functions;
+}
+
+bool registerExternFunction(std::string name, Circom_ExternFunction f) {
+  externFunctions()[name] = f;
+  return true;
+}
+
+void callExternFunction(std::string name, u64* args, uint nArgs, u64* result, uint resultSize) {
+  auto f = externFunctions().find(name);
+  if (f == externFunctions().end()) {
+    std::cerr << "The extern function " << name << " is not registered" << std::endl;
+    assert(false);
+  }
+  f->second(args, nArgs, result, resultSize);
+}
+
+static std::mutex profileMutex;
+static std::map<std::string, Circom_ProfileEntry> profileStacks;
+static std::atomic<uint> profileThreads(0);
+static thread_local std::string profileStack;
+static thread_local Circom_Profile *profileTop = nullptr;
+
+Circom_Profile::Circom_Profile(const char *templateName) {
+  if (profileStack.empty()) {
+    profileStack = "thread " + std::to_string(profileThreads++);
+  }
+  parent = profileTop;
+  profileTop = this;
+  stackLength = profileStack.size();
+  profileStack += ";";
+  profileStack += templateName;
+  childrenNanoseconds = 0;
+  start = std::chrono::steady_clock::now();
+}
+
+Circom_Profile::~Circom_Profile() {
+  u64 nanoseconds = std::chrono::duration_cast<std::chrono::nanoseconds>(std::chrono::steady_clock::now() - start).count();
+  {
+    std::lock_guard<std::mutex> lock(profileMutex);
+    Circom_ProfileEntry &entry = profileStacks[profileStack];
+    entry.calls++;
+    entry.nanoseconds += nanoseconds > childrenNanoseconds ? nanoseconds - childrenNanoseconds : 0;
+  }
+  if (parent != nullptr) {
+    parent->childrenNanoseconds += nanoseconds;
+  }
+  profileTop = parent;
+  profileStack.resize(stackLength);
+}
+
+std::map<std::string, Circom_ProfileEntry> getProfile() {
+  std::lock_guard<std::mutex> lock(profileMutex);
+  return profileStacks;
+}
@@ -9,6 +9,7 @@
 #include <nlohmann/json.hpp>
 #include <vector>
 #include <chrono>
+#include <algorithm>
 
 using json = nlohmann::json;
 
@@ -358,6 +359,41 @@ void writeBinWitness(Circom_CalcWit *ctx, std::string wtnsFileName) {
     fclose(write_ptr);
 }
 
+// The profile of a circuit compiled with --profile: the file has a line for
+// each stack of templates with the microseconds of its runs, as the folded
+// stacks of the flame graph tools, and the templates that take the most
+// time are listed with their calls
+static void writeProfile(std::string const &profileFile) {
+  std::ofstream profileStream(profileFile);
+  if (!profileStream) {
+    std::cerr << "Could not write the profile file: " << profileFile << "\n";
+    exit(EXIT_FAILURE);
+  }
+  std::map<std::string, Circom_ProfileEntry> templates;
+  u64 total = 0;
+  for (auto const &stack : getProfile()) {
+    profileStream << stack.first << " " << stack.second.nanoseconds / 1000 << "\n";
+    Circom_ProfileEntry &entry = templates[stack.first.substr(stack.first.find_last_of(';') + 1)];
+    entry.calls += stack.second.calls;
+    entry.nanoseconds += stack.second.nanoseconds;
+    total += stack.second.nanoseconds;
+  }
+  std::vector<std::pair<std::string, Circom_ProfileEntry>> sorted(templates.begin(), templates.end());
+  std::sort(sorted.begin(), sorted.end(), [](std::pair<std::string, Circom_ProfileEntry> const &a, std::pair<std::string, Circom_ProfileEntry> const &b) { return a.second.nanoseconds > b.second.nanoseconds; });
+  std::cerr << std::left << std::setw(40) << "template" << std::right << std::setw(12) << "calls"
+            << std::setw(14) << "time (ms)" << std::setw(8) << "%" << "\n";
+  uint shown = std::min<size_t>(sorted.size(), 20);
+  for (uint i = 0; i < shown; i++) {
+    double percentage = total == 0 ? 0 : 100.0 * sorted[i].second.nanoseconds / total;
+    std::cerr << std::left << std::setw(40) << sorted[i].first << std::right << std::setw(12) << sorted[i].second.calls
+              << std::setw(14) << std::fixed << std::setprecision(3) << sorted[i].second.nanoseconds / 1e6
+              << std::setw(8) << std::setprecision(1) << percentage << "\n";
+  }
+  if (sorted.size() > shown) {
+    std::cerr << "and " << sorted.size() - shown << " more templates\n";
+  }
+}
+
 int main (int argc, char *argv[]) {
   std::string cl(argv[0]);
   if (argc!=3) {
@@ -395,6 +431,11 @@ int main (int argc, char *argv[]) {
    //std::cout << std::chrono::duration<double, std::milli>(t_mid-t_start).count()<<std::endl;
 
    writeBinWitness(ctx,wtnsfile);
+
+   const char *profileFile = getenv("CIRCOM_PROFILE");
+   if (profileFile != nullptr) {
+     writeProfile(profileFile);
+   }
   
    //auto t_end = std::chrono::high_resolution_clock::now();
    //std::cout << std::chrono::duration<double, std::milli>(t_end-t_mid).count()<<std::endl;
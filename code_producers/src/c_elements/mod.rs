@@ -35,6 +35,11 @@ pub struct CProducer {
     //pub size_of_bus_fields: usize,  //total number of fields in all differen bus intances
     pub busid_field_info: FieldMap, //for every busId (0..num-1) provides de offset, size, dimensions and busId of each field (0..n-1) in it
     pub no_asm: bool,
+    pub lazy_components: bool,
+    // each store in a signal calls the signal tracer of the witness generator
+    pub trace_signals: bool,
+    // the runs of the templates are measured by the profiler of the witness generator
+    pub profile_templates: bool,
 }
 
 impl Default for CProducer {
@@ -122,6 +127,9 @@ impl Default for CProducer {
 //	        size_of_bus_fields: 0,
 	    busid_field_info: Vec::new(),
             no_asm: false,
+            lazy_components: false,
+            trace_signals: false,
+            profile_templates: false,
         }
     }
 }
@@ -200,6 +208,20 @@ impl CProducer {
         self.size_32_bit
     }
 
+    // the loads of the outputs of parallel subcomponents wait for their
+    // threads, so with parallelism they are still run when their inputs are set
+    pub fn defers_components(&self) -> bool {
+        self.lazy_components && !self.has_parallelism
+    }
+
+    pub fn traces_signals(&self) -> bool {
+        self.trace_signals
+    }
+
+    pub fn profiles_templates(&self) -> bool {
+        self.profile_templates
+    }
+
     pub fn get_string_table(&self) -> &Vec<String> {
         &self.string_table
     }
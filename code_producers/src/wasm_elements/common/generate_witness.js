@@ -1,21 +1,97 @@
 const wc  = require("./witness_calculator.js");
-const { readFileSync, writeFile } = require("fs");
+const { readFileSync, writeFile, appendFileSync, writeFileSync, existsSync } = require("fs");
+const path = require("path");
 
-if (process.argv.length != 5) {
-    console.log("Usage: node generate_witness.js <file.wasm> <input.json> <output.wtns>");
+if (process.argv.length != 5 && process.argv.length != 6) {
+    console.log("Usage: node generate_witness.js <file.wasm> <input.json> <output.wtns> [<extern_functions.js>]");
 } else {
     const input = JSON.parse(readFileSync(process.argv[3], "utf8"));
+    // a module that exports the extern functions of the circuit by their names
+    const externFunctions = process.argv.length == 6 ? require(path.resolve(process.argv[5])) : {};
+    // a circuit compiled with --trace writes each value stored in a signal
+    // to the file of CIRCOM_TRACE, with the names of the sym file of the circuit
+    const traceSignal = process.env.CIRCOM_TRACE ? signalTracer(process.argv[2], process.env.CIRCOM_TRACE) : undefined;
+    // a circuit compiled with --profile writes the time of the runs of its
+    // templates to the file of CIRCOM_PROFILE
+    const profile = process.env.CIRCOM_PROFILE ? new Profiler() : undefined;
     
     const buffer = readFileSync(process.argv[2]);
-    wc(buffer).then(async witnessCalculator => {
-	    const w= await witnessCalculator.calculateWitness(input,0);
-		/*
-	    for (let i=0; i< w.length; i++){
-		console.log(w[i]);
-	    }*/
+    wc(buffer, { externFunctions, traceSignal, profile }).then(async witnessCalculator => {
+	// the witness is computed once, so that the trace has each store once
 	const buff= await witnessCalculator.calculateWTNSBin(input,0);
+	if (profile) {
+	    profile.write(process.env.CIRCOM_PROFILE);
+	}
 	writeFile(process.argv[4], buff, function(err) {
 	    if (err) throw err;
 	});
     });
 }
+
+// The sym file is next to the folder of the wasm file, with its name
+function signalTracer(wasmFile, traceFile) {
+    const names = {};
+    const symFile = path.join(path.dirname(wasmFile), "..", path.basename(wasmFile, ".wasm") + ".sym");
+    if (existsSync(symFile)) {
+	for (const line of readFileSync(symFile, "utf8").split("\n")) {
+	    const fields = line.split(",");
+	    if (fields.length >= 4) {
+		names[fields[0]] = fields.slice(3).join(",");
+	    }
+	}
+    }
+    writeFileSync(traceFile, "");
+    return function(signal, value, line) {
+	const name = names[signal] !== undefined ? names[signal] : "signal " + signal;
+	appendFileSync(traceFile, name + "," + value + "," + line + "\n");
+    };
+}
+
+// The calls and the time of the runs of each stack of templates, without the
+// time of the runs of their subcomponents. The wasm code runs in one thread
+function Profiler() {
+    this.stacks = {};
+    this.running = [{ stack: "thread 0", children: 0n }];
+}
+
+Profiler.prototype.enter = function(name) {
+    const parent = this.running[this.running.length - 1];
+    this.running.push({ stack: parent.stack + ";" + name, start: process.hrtime.bigint(), children: 0n });
+};
+
+Profiler.prototype.leave = function() {
+    const run = this.running.pop();
+    const nanoseconds = process.hrtime.bigint() - run.start;
+    const entry = this.stacks[run.stack] || (this.stacks[run.stack] = { calls: 0, nanoseconds: 0n });
+    entry.calls++;
+    entry.nanoseconds += nanoseconds > run.children ? nanoseconds - run.children : 0n;
+    this.running[this.running.length - 1].children += nanoseconds;
+};
+
+// The file has a line for each stack of templates with the microseconds of
+// its runs, as the folded stacks of the flame graph tools, and the templates
+// that take the most time are listed with their calls
+Profiler.prototype.write = function(profileFile) {
+    const templates = {};
+    let total = 0n;
+    let folded = "";
+    for (const stack of Object.keys(this.stacks).sort()) {
+	const entry = this.stacks[stack];
+	folded += stack + " " + entry.nanoseconds / 1000n + "\n";
+	const name = stack.substring(stack.lastIndexOf(";") + 1);
+	const template = templates[name] || (templates[name] = { calls: 0, nanoseconds: 0n });
+	template.calls += entry.calls;
+	template.nanoseconds += entry.nanoseconds;
+	total += entry.nanoseconds;
+    }
+    writeFileSync(profileFile, folded);
+    const sorted = Object.entries(templates).sort((a, b) => (b[1].nanoseconds > a[1].nanoseconds ? 1 : b[1].nanoseconds < a[1].nanoseconds ? -1 : 0));
+    console.error("template".padEnd(40) + "calls".padStart(12) + "time (ms)".padStart(14) + "%".padStart(8));
+    for (const [name, template] of sorted.slice(0, 20)) {
+	const percentage = total == 0n ? 0 : 100 * Number(template.nanoseconds) / Number(total);
+	console.error(name.padEnd(40) + String(template.calls).padStart(12) + (Number(template.nanoseconds) / 1e6).toFixed(3).padStart(14) + percentage.toFixed(1).padStart(8));
+    }
+    if (sorted.length > 20) {
+	console.error("and " + (sorted.length - 20) + " more templates");
+    }
+};
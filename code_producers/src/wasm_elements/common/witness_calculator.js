@@ -15,6 +15,15 @@ module.exports = async function builder(code, options) {
 
     let errStr = "";
     let msgStr = "";
+    // the arguments and the result of the extern function being called
+    let externArgs = [];
+    let externResult = [];
+    const externFunctions = options.externFunctions || {};
+    // receives the values stored in the signals by a circuit compiled with --trace
+    const traceSignal = options.traceSignal || function() {};
+    // receives the runs of the templates of a circuit compiled with --profile
+    const profile = options.profile || { enter: function() {}, leave: function() {} };
+    let templateNames = [];
     
     const instance = await WebAssembly.instantiate(wasmModule, {
         runtime: {
@@ -48,8 +57,9 @@ module.exports = async function builder(code, options) {
 				console.log(msgStr);
 				msgStr = "";
 			} else {
-				// If we've buffered other content, put a space in between the items
-				if (msgStr !== "") {
+				// If we've buffered other content, put a space in between the items,
+				// unless the log comes from a format string
+				if (msgStr !== "" && !isLogFormatted()) {
 					msgStr += " "
 				}
 				// Then append the message to the message we are creating
@@ -58,7 +68,31 @@ module.exports = async function builder(code, options) {
 	    },
 	    showSharedRWMemory : function() {
 		printSharedRWMemory ();
-            }
+            },
+	    externArgument : function() {
+		externArgs.push(readSharedRWMemory());
+	    },
+	    externCall : function() {
+		const name = getMessage();
+		if (externFunctions[name] === undefined) {
+		    throw new Error("The extern function " + name + " is not given.\n");
+		}
+		const result = externFunctions[name](...externArgs);
+		externArgs = [];
+		externResult = Array.isArray(result) ? result : [result];
+	    },
+	    externResult : function(i) {
+		writeSharedRWMemory(BigInt(externResult[i]));
+	    },
+	    traceSignal : function(signal, line) {
+		traceSignal(signal, readSharedRWMemory(), line);
+	    },
+	    profileEnter : function(template) {
+		profile.enter(templateName(template));
+	    },
+	    profileLeave : function() {
+		profile.leave();
+	    }
 
         }
     });
@@ -78,6 +112,16 @@ module.exports = async function builder(code, options) {
     wc = new WitnessCalculator(instance, sanityCheck);
     return wc;
 
+    // The header of a template is in the message of its errors, which has the
+    // same id as the template
+    function templateName(template) {
+	if (templateNames[template] === undefined) {
+	    instance.exports.buildBufferMessage(template, 0);
+	    templateNames[template] = getMessage().replace(/^Error in template /, "").replace(/ line: 0$/, "");
+	}
+	return templateNames[template];
+    }
+
     function getMessage() {
         var message = "";
 	var c = instance.exports.getMessageChar();
@@ -88,6 +132,27 @@ module.exports = async function builder(code, options) {
         return message;
     }
 	
+    function isLogFormatted() {
+        return instance.exports.getLogFormatted !== undefined && instance.exports.getLogFormatted() != 0;
+    }
+
+    function readSharedRWMemory() {
+	const n32 = instance.exports.getFieldNumLen32();
+	const arr = new Uint32Array(n32);
+	for (let j=0; j<n32; j++) {
+	    arr[n32-1-j] = instance.exports.readSharedRWMemory(j);
+	}
+	return fromArray32(arr);
+    }
+
+    function writeSharedRWMemory(value) {
+	const n32 = instance.exports.getFieldNumLen32();
+	const arr = toArray32(normalize(value, wc.prime), n32);
+	for (let j=0; j<n32; j++) {
+	    instance.exports.writeSharedRWMemory(j, arr[n32-1-j]);
+	}
+    }
+
     function printSharedRWMemory () {
 	const shared_rw_memory_size = instance.exports.getFieldNumLen32();
 	const arr = new Uint32Array(shared_rw_memory_size);
@@ -95,8 +160,9 @@ module.exports = async function builder(code, options) {
 	    arr[shared_rw_memory_size-1-j] = instance.exports.readSharedRWMemory(j);
 	}
 
-	// If we've buffered other content, put a space in between the items
-	if (msgStr !== "") {
+	// If we've buffered other content, put a space in between the items,
+	// unless the log comes from a format string
+	if (msgStr !== "" && !isLogFormatted()) {
 		msgStr += " "
 	}
 	// Then append the value to the message we are creating
@@ -25,6 +25,11 @@ pub struct WASMProducer {
     pub message_list: MessageList,
     pub field_tracking: Vec<String>,
     pub wat_flag: bool,
+    pub lazy_components: bool,
+    // each store in a signal calls the traceSignal function of the runtime
+    pub trace_signals: bool,
+    // the runs of the templates call the profileEnter and profileLeave functions of the runtime
+    pub profile_templates: bool,
     pub major_version: usize,
     pub minor_version: usize,
     pub patch_version: usize,
@@ -38,6 +43,7 @@ pub struct WASMProducer {
     sub_cmp_tag: String,
     sub_cmp_src_tag: String,
     sub_cmp_load_tag: String,
+    deferred_cmp_tag: String,
     io_info_tag: String,
     result_address_tag: String,
     result_size_tag: String,
@@ -57,6 +63,8 @@ pub struct WASMProducer {
     create_loop_offset_tag: String,
     create_loop_counter_tag: String,
     merror_tag: String,
+    error_site_tag: String,
+    trace_address_tag: String,
     string_table:  Vec<String>,
     //New for buses
     pub num_of_bus_instances: usize,  //total number of different bus instances
@@ -107,6 +115,9 @@ impl Default for WASMProducer {
             template_instance_list: [].to_vec(),
             field_tracking: [].to_vec(),
             wat_flag: true,
+            lazy_components: false,
+            trace_signals: false,
+            profile_templates: false,
             major_version: 0,
             minor_version: 0,
             patch_version: 0,
@@ -120,6 +131,7 @@ impl Default for WASMProducer {
             sub_cmp_tag: "$subcmp".to_string(),
             sub_cmp_src_tag: "$subcmpsrc".to_string(),
             sub_cmp_load_tag: "$subcmpload".to_string(),
+            deferred_cmp_tag: "$deferredcmp".to_string(),
             io_info_tag: "$ioinfo".to_string(),
             result_address_tag: "$resultaddress".to_string(),
             result_size_tag: "$resultsize".to_string(),
@@ -139,6 +151,8 @@ impl Default for WASMProducer {
             create_loop_offset_tag: "$createloopoffset".to_string(),
             create_loop_counter_tag: "$createloopcounter".to_string(),
 	        merror_tag: "$merror".to_string(),
+            error_site_tag: "$errorsite".to_string(),
+            trace_address_tag: "$traceaddress".to_string(),
             string_table: Vec::new(),
 	    //New for buses
 	    num_of_bus_instances: 0,
@@ -432,6 +446,9 @@ impl WASMProducer {
     pub fn get_sub_cmp_load_tag(&self) -> &str {
         &self.sub_cmp_load_tag
     }
+    pub fn get_deferred_cmp_tag(&self) -> &str {
+        &self.deferred_cmp_tag
+    }
     pub fn get_io_info_tag(&self) -> &str {
         &self.io_info_tag
     }
@@ -489,9 +506,24 @@ impl WASMProducer {
     pub fn get_merror_tag(&self) -> &str {
 	&self.merror_tag
     }
+    pub fn get_error_site_tag(&self) -> &str {
+        &self.error_site_tag
+    }
     pub fn needs_comments(&self) -> bool{
         self.wat_flag
     }
+    pub fn defers_components(&self) -> bool {
+        self.lazy_components
+    }
+    pub fn traces_signals(&self) -> bool {
+        self.trace_signals
+    }
+    pub fn get_trace_address_tag(&self) -> &str {
+        &self.trace_address_tag
+    }
+    pub fn profiles_templates(&self) -> bool {
+        self.profile_templates
+    }
 
     pub fn get_string_table(&self) -> &Vec<String> {
         &self.string_table
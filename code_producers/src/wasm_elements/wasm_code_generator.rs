@@ -39,6 +39,12 @@ pub fn get_local(value: &str) -> WasmInstruction {
 pub fn set_local(value: &str) -> WasmInstruction {
     format!("local.set {}", value)
 }
+pub fn get_global(value: &str) -> WasmInstruction {
+    format!("global.get {}", value)
+}
+pub fn set_global(value: &str) -> WasmInstruction {
+    format!("global.set {}", value)
+}
 pub fn tee_local(value: &str) -> WasmInstruction {
     format!("local.tee {}", value)
 }
@@ -129,6 +135,9 @@ pub fn shl32() -> WasmInstruction {
 pub fn shl64() -> WasmInstruction {
     "i64.shl".to_string()
 }
+pub fn shr64_u() -> WasmInstruction {
+    "i64.shr_u".to_string()
+}
 pub fn call(to: &str) -> WasmInstruction {
     format!("call {}", to)
 }
@@ -180,6 +189,12 @@ pub fn br_if(value: &str) -> WasmInstruction {
 pub fn br(value: &str) -> WasmInstruction {
     format!("br {}", value)
 }
+pub fn br_table(targets: &[String]) -> WasmInstruction {
+    format!("br_table {}", targets.join(" "))
+}
+pub fn select() -> WasmInstruction {
+    "select".to_string()
+}
 pub fn add_if() -> WasmInstruction {
     "if".to_string()
 }
@@ -633,9 +648,36 @@ pub fn generate_imports_list() -> Vec<WasmInstruction> {
     imports
 }
 
+// The functions of the host that compute the extern functions, which are only
+// imported by the programs that have them
+pub fn generate_extern_imports_list() -> Vec<WasmInstruction> {
+    vec![
+        "(import \"runtime\" \"externArgument\" (func $externArgument (type $_t_void)))".to_string(),
+        "(import \"runtime\" \"externCall\" (func $externCall (type $_t_void)))".to_string(),
+        "(import \"runtime\" \"externResult\" (func $externResult (type $_t_i32)))".to_string(),
+    ]
+}
+
+// The function of the host that receives the values stored in the signals,
+// which is only imported by the circuits compiled with --trace
+pub fn generate_trace_imports_list() -> Vec<WasmInstruction> {
+    vec!["(import \"runtime\" \"traceSignal\" (func $traceSignal (type $_t_i32i32)))".to_string()]
+}
+
+// The functions of the host that measure the runs of the templates, which are
+// only imported by the circuits compiled with --profile
+pub fn generate_profile_imports_list() -> Vec<WasmInstruction> {
+    vec![
+        "(import \"runtime\" \"profileEnter\" (func $profileEnter (type $_t_i32)))".to_string(),
+        "(import \"runtime\" \"profileLeave\" (func $profileLeave (type $_t_void)))".to_string(),
+    ]
+}
+
 pub fn generate_memory_def_list(producer: &WASMProducer) -> Vec<WasmInstruction> {
     let mut wmemory = vec![];
     wmemory.push(format!("(memory {})", get_initial_size_of_memory(&producer)));
+    // whether the log being written comes from a format string
+    wmemory.push("(global $logFormatted (mut i32) (i32.const 0))".to_string());
     wmemory
 }
 
@@ -649,6 +691,8 @@ pub fn generate_types_list() -> Vec<WasmInstruction> {
     types.push("(type $_t_i32i32 (func  (param i32 i32)))".to_string());
     types.push("(type $_t_i32i32ri32 (func  (param i32 i32) (result i32)))".to_string());
     types.push("(type $_t_i32i32i32  (func  (param i32 i32 i32)))".to_string());
+    types.push("(type $_t_i32i32i32i32  (func  (param i32 i32 i32 i32)))".to_string());
+    types.push("(type $_t_i32i32i32i32i32i32i32  (func  (param i32 i32 i32 i32 i32 i32 i32)))".to_string());
     types
 }
 
@@ -670,9 +714,16 @@ pub fn generate_exports_list() -> Vec<WasmInstruction> {
     exports.push("(export \"getInputSize\" (func $getInputSize))".to_string());
     exports.push("(export \"getWitness\" (func $getWitness))".to_string());
     exports.push("(export \"getMessageChar\" (func $getMessageChar))".to_string());
+    exports.push("(export \"getLogFormatted\" (func $getLogFormatted))".to_string());
     exports
 }
 
+// The host of a circuit compiled with --profile names the templates with the
+// messages of their errors, which start with the header of the template
+pub fn generate_profile_exports_list() -> Vec<WasmInstruction> {
+    vec!["(export \"buildBufferMessage\" (func $buildBufferMessage))".to_string()]
+}
+
 pub fn generate_data_list(producer: &WASMProducer) -> Vec<WasmInstruction> {
     let mut wdata = vec![];
     wdata.push(format!(
@@ -854,6 +905,119 @@ pub fn free_stack(producer: &WASMProducer) -> Vec<WasmInstruction> {
     instructions
 }
 
+// ------ subcomponents run on demand
+
+// input counter of a component that has already been run
+pub const RUN_COMPONENT_COUNTER: &str = "-1";
+
+// The error code on the top of the stack is kept in merror and, if it is not
+// zero, the site of the error is passed to the error block of the function
+pub fn check_error(producer: &WASMProducer, message_id: usize, line: usize) -> Vec<WasmInstruction> {
+    let mut instructions = vec![tee_local(producer.get_merror_tag()), add_if()];
+    instructions.append(&mut raise_error(message_id, line));
+    instructions.push(add_end());
+    instructions
+}
+
+// Leaves the error block with the site of the error in merror
+pub fn raise_error(message_id: usize, line: usize) -> Vec<WasmInstruction> {
+    let site = ((line as u64) << 32) | message_id as u64;
+    vec![set_constant_64(&site.to_string()), br("$error")]
+}
+
+// The code of the function is placed in the error block, which is only left
+// by the errors of the calls checked with check_error
+pub fn open_error_block() -> Vec<WasmInstruction> {
+    vec!["block $error (result i64)".to_string()]
+}
+
+// Shows the message of the site left on the stack by the error block and
+// returns the error code
+pub fn close_error_block(producer: &WASMProducer) -> Vec<WasmInstruction> {
+    vec![
+        set_constant("0"),
+        add_return(),
+        add_end(),
+        tee_local(producer.get_error_site_tag()),
+        wrap_i6432(), // message id
+        get_local(producer.get_error_site_tag()),
+        set_constant_64("32"),
+        shr64_u(),
+        wrap_i6432(), // line
+        call("$buildBufferMessage"),
+        call("$printErrorMessage"),
+        get_local(producer.get_merror_tag()),
+    ]
+}
+
+// Runs the component whose address is in the local if all its inputs are set
+// and it has not been run yet, the error is shown with the message if any
+pub fn run_deferred_component(
+    producer: &WASMProducer,
+    component: &str,
+    message: Option<(usize, usize)>,
+) -> Vec<WasmInstruction> {
+    let counter = producer.get_input_counter_address_in_component().to_string();
+    let mut instructions = vec![
+        get_local(component),
+        load32(Some(&counter)),
+        eqz32(),
+        add_if(),
+        get_local(component),
+        set_constant(RUN_COMPONENT_COUNTER),
+        store32(Some(&counter)),
+        get_local(component),
+        get_local(component),
+        load32(None), // get template id
+        call_indirect("$runsmap", "(type $_t_i32ri32)"),
+    ];
+    if let Some((message_id, line)) = message {
+        instructions.append(&mut check_error(producer, message_id, line));
+    } else {
+        instructions.push(tee_local(producer.get_merror_tag()));
+        instructions.push(add_if());
+        instructions.push(get_local(producer.get_merror_tag()));
+        instructions.push(add_return());
+        instructions.push(add_end());
+    }
+    instructions.push(add_end());
+    instructions
+}
+
+// Runs the created subcomponents that have not been run by the loads of
+// their outputs
+pub fn run_deferred_subcomponents(producer: &WASMProducer, number_of_components: usize) -> Vec<WasmInstruction> {
+    let mut instructions = vec![
+        set_constant("0"),
+        set_local(producer.get_create_loop_counter_tag()),
+        add_block(),
+        add_loop(),
+        get_local(producer.get_create_loop_counter_tag()),
+        set_constant(&number_of_components.to_string()),
+        eq32(),
+        br_if("1"),
+        get_local(producer.get_offset_tag()),
+        get_local(producer.get_create_loop_counter_tag()),
+        set_constant("4"),
+        mul32(),
+        add32(),
+        load32(Some(&producer.get_sub_component_start_in_component().to_string())),
+        tee_local(producer.get_deferred_cmp_tag()),
+        add_if(), // the subcomponent has been created
+    ];
+    let mut run = run_deferred_component(producer, producer.get_deferred_cmp_tag(), None);
+    instructions.append(&mut run);
+    instructions.push(add_end());
+    instructions.push(get_local(producer.get_create_loop_counter_tag()));
+    instructions.push(set_constant("1"));
+    instructions.push(add32());
+    instructions.push(set_local(producer.get_create_loop_counter_tag()));
+    instructions.push(br("0"));
+    instructions.push(add_end());
+    instructions.push(add_end());
+    instructions
+}
+
 // ---------------------- functions ------------------------
 
 pub fn desp_io_subcomponent_generator(producer: &WASMProducer) -> Vec<WasmInstruction> {
@@ -1299,6 +1463,45 @@ pub fn copy_fr_in_shared_rw_memory_generator(producer: &WASMProducer) -> Vec<Was
     instructions
 }
 
+// Gives to the host each of the signals stored from an address, with its
+// position in the signal memory and the line of the store
+pub fn trace_signals_generator(producer: &WASMProducer) -> Vec<WasmInstruction> {
+    let mut instructions = vec![];
+    let header = "(func $traceSignals (type $_t_i32i32i32)".to_string();
+    instructions.push(header);
+    instructions.push(" (param $a i32)".to_string());
+    instructions.push(" (param $n i32)".to_string());
+    instructions.push(" (param $line i32)".to_string());
+    let size = producer.get_size_32_bit() * 4 + 8;
+    instructions.push(add_block());
+    instructions.push(add_loop());
+    instructions.push(get_local("$n"));
+    instructions.push(eqz32());
+    instructions.push(br_if("1"));
+    instructions.push(get_local("$a"));
+    instructions.push(call("$copyFr2SharedRWMemory"));
+    instructions.push(get_local("$a"));
+    instructions.push(set_constant(&producer.get_signal_memory_start().to_string()));
+    instructions.push(sub32());
+    instructions.push(set_constant(&size.to_string()));
+    instructions.push(div32_u());
+    instructions.push(get_local("$line"));
+    instructions.push(call("$traceSignal"));
+    instructions.push(get_local("$a"));
+    instructions.push(set_constant(&size.to_string()));
+    instructions.push(add32());
+    instructions.push(set_local("$a"));
+    instructions.push(get_local("$n"));
+    instructions.push(set_constant("1"));
+    instructions.push(sub32());
+    instructions.push(set_local("$n"));
+    instructions.push(br("0"));
+    instructions.push(add_end());
+    instructions.push(add_end());
+    instructions.push(")".to_string());
+    instructions
+}
+
 pub fn get_witness_generator(producer: &WASMProducer) -> Vec<WasmInstruction> {
     let mut instructions = vec![];
     let header = "(func $getWitness (type $_t_i32)".to_string();
@@ -1326,6 +1529,10 @@ pub fn get_witness_generator(producer: &WASMProducer) -> Vec<WasmInstruction> {
     instructions
 }
 
+pub fn get_log_formatted_generator() -> Vec<WasmInstruction> {
+    vec!["(func $getLogFormatted (type $_t_ri32)".to_string(), get_global("$logFormatted"), ")".to_string()]
+}
+
 pub fn get_message_char_generator(producer: &WASMProducer) -> Vec<WasmInstruction> {
     let mut instructions = vec![];
     let header = "(func $getMessageChar (type $_t_ri32)".to_string();
@@ -1357,6 +1564,56 @@ pub fn get_message_char_generator(producer: &WASMProducer) -> Vec<WasmInstructio
     instructions
 }
 
+// The loops that add elements, or products of elements, of arrays are replaced
+// by calls to these functions
+pub fn fused_field_operations_generator() -> Vec<WasmInstruction> {
+    let mut instructions = vec![
+        "(func $Fr_add_n (type $_t_i32i32i32i32)".to_string(),
+        " (param $r i32)".to_string(),
+        " (param $a i32)".to_string(),
+        " (param $sa i32)".to_string(), // bytes between elements
+        " (param $n i32)".to_string(),
+    ];
+    instructions.append(&mut fused_loop(vec![
+        get_local("$r"),
+        get_local("$r"),
+        get_local("$a"),
+        call("$Fr_add"),
+    ], &[("$a", "$sa")]));
+    instructions.push(")".to_string());
+    instructions.push("(func $Fr_mul_add_n (type $_t_i32i32i32i32i32i32i32)".to_string());
+    instructions.push(" (param $r i32)".to_string());
+    instructions.push(" (param $a i32)".to_string());
+    instructions.push(" (param $sa i32)".to_string());
+    instructions.push(" (param $b i32)".to_string());
+    instructions.push(" (param $sb i32)".to_string());
+    instructions.push(" (param $n i32)".to_string());
+    instructions.push(" (param $t i32)".to_string()); // keeps the product
+    instructions.append(&mut fused_loop(vec![
+        get_local("$t"),
+        get_local("$a"),
+        get_local("$b"),
+        call("$Fr_mul"),
+        get_local("$r"),
+        get_local("$r"),
+        get_local("$t"),
+        call("$Fr_add"),
+    ], &[("$a", "$sa"), ("$b", "$sb")]));
+    instructions.push(")".to_string());
+    instructions
+}
+
+// Repeats the code n times, moving each array by its stride
+fn fused_loop(mut code: Vec<WasmInstruction>, arrays: &[(&str, &str)]) -> Vec<WasmInstruction> {
+    let mut instructions = vec![add_block(), add_loop(), get_local("$n"), eqz32(), br_if("1")];
+    instructions.append(&mut code);
+    for (array, stride) in arrays {
+        instructions.extend([get_local(array), get_local(stride), add32(), set_local(array)]);
+    }
+    instructions.extend([get_local("$n"), set_constant("1"), sub32(), set_local("$n"), br("0"), add_end(), add_end()]);
+    instructions
+}
+
 pub fn build_log_message_generator(producer: &WASMProducer) -> Vec<WasmInstruction> {
     let mut instructions = vec![];
     let header = "(func $buildLogMessage (type $_t_i32)".to_string();
@@ -1,13 +1,19 @@
+use ansi_term::Colour;
 use crate::circuit_design::circuit::{Circuit, CompilationFlags};
 use crate::circuit_design::function::FunctionCodeInfo;
+use crate::circuit_design::ir_cache::IrCache;
+use crate::circuit_design::merge_instances::{interface_of, merge_instances, Interface};
 use crate::circuit_design::template::TemplateCodeInfo;
 use crate::hir::very_concrete_program::*;
 use crate::intermediate_representation::translate;
 use crate::intermediate_representation::translate::{CodeInfo, FieldTracker, TemplateDB, ParallelClusters};
+use crate::ir_processing::{CodeSize, Pass, PassEffect, PassManager, VerifyInfo};
 use code_producers::c_elements::*;
 use code_producers::wasm_elements::*;
+use num_bigint_dig::BigInt;
 use program_structure::file_definition::FileLibrary;
 use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 
 #[cfg(debug_assertions)]
 fn matching_lengths_and_offsets(list: &InputOutputList) {
@@ -25,7 +31,9 @@ fn build_template_instances(
     c_info: &CircuitInfo,
     ti: Vec<TemplateInstance>,
     mut field_tracker: FieldTracker,
-    constraint_assert_dissabled_flag: bool
+    constraint_assert_dissabled_flag: bool,
+    passes: &mut PassManager,
+    ir_cache: Option<&IrCache>,
 ) -> (FieldTracker, HashMap<String,usize>) {
 
     fn compute_jump(lengths: &Vec<usize>, indexes: &[usize]) -> usize {
@@ -43,7 +51,18 @@ fn build_template_instances(
     let mut cmp_id = 0;
     let mut tmp_id = 0;
     let mut string_table = HashMap::new();
+    let fingerprints: Vec<Option<String>> = ti
+        .iter()
+        .enumerate()
+        .map(|(id, template)| {
+            // an instance without a fingerprint is translated again
+            ir_cache.and_then(|cache| {
+                cache.fingerprint(id, template, &ti, &c_info.template_database, &c_info.buses, &c_info.file_library).ok()
+            })
+        })
+        .collect();
     for template in ti {
+        let fingerprint = &fingerprints[tmp_id];
         let header = template.template_header;
         let name = template.template_name;
         let instance_values = template.header;
@@ -81,26 +100,42 @@ fn build_template_instances(
             }
         }
         
-        let code_info = CodeInfo {
-            cmp_to_type,
-            field_tracker,
-            component_to_parallel,
-            message_id: tmp_id,
-            params: Vec::new(),
-            header: header.clone(),
-            wires: template.wires,
-            constants: instance_values,
-            files: &c_info.file_library,
-            triggers: template.triggers,
-            clusters: template.clusters,
-            functions: &c_info.functions,
-            buses: &c_info.buses,
-            fresh_cmp_id: cmp_id,
-            components: template.components,
-            template_database: &c_info.template_database,
-            string_table : string_table,
-            signals_to_tags: template.signals_to_tags,
-            constraint_assert_dissabled_flag
+        let cached = match (ir_cache, fingerprint) {
+            (Some(cache), Some(fingerprint)) => cache.load(fingerprint, cmp_id, field_tracker, string_table),
+            _ => Err((field_tracker, string_table)),
+        };
+        let out = match cached {
+            Ok(out) => out,
+            Err((field_tracker, string_table)) => {
+                let code_info = CodeInfo {
+                    cmp_to_type,
+                    field_tracker,
+                    component_to_parallel,
+                    message_id: tmp_id,
+                    params: Vec::new(),
+                    header: header.clone(),
+                    wires: template.wires,
+                    constants: instance_values,
+                    files: &c_info.file_library,
+                    triggers: template.triggers,
+                    clusters: template.clusters,
+                    functions: &c_info.functions,
+                    buses: &c_info.buses,
+                    fresh_cmp_id: cmp_id,
+                    components: template.components,
+                    template_database: &c_info.template_database,
+                    string_table,
+                    signals_to_tags: template.signals_to_tags,
+                    constraint_assert_dissabled_flag,
+                    prime: &c_info.prime,
+                    passes: &mut *passes,
+                };
+                let out = translate::translate_code(template.code, code_info);
+                if let (Some(cache), Some(fingerprint)) = (ir_cache, fingerprint) {
+                    cache.store(fingerprint, cmp_id, &out);
+                }
+                out
+            }
         };
         let mut template_info = TemplateCodeInfo {
             name,
@@ -116,8 +151,6 @@ fn build_template_instances(
             has_parallel_sub_cmp: template.has_parallel_sub_cmp,
             ..TemplateCodeInfo::default()
         };
-        let code = template.code;
-        let out = translate::translate_code(code, code_info);
         field_tracker = out.constant_tracker;
         template_info.body = out.code;
         template_info.expression_stack_depth = out.expression_depth;
@@ -138,6 +171,7 @@ fn build_function_instances(
     mut field_tracker: FieldTracker,
     mut string_table : HashMap<String,usize>,
     constraint_assert_dissabled_flag: bool,
+    passes: &mut PassManager,
 ) -> (FieldTracker, HashMap<String, usize>, HashMap<String, usize>) {
     let mut function_to_arena_size = HashMap::new();
     for instance in instances {
@@ -146,6 +180,7 @@ fn build_function_instances(
         let name = instance.name;
         let params = instance.params_types;
         let returns = instance.return_type;
+        let is_extern = instance.is_extern;
         let id = circuit.wasm_producer.message_list.len();
         circuit.wasm_producer.message_list.push(msg);
         let code_info = CodeInfo {
@@ -167,18 +202,26 @@ fn build_function_instances(
             string_table : string_table,
             signals_to_tags: HashMap::new(),
             buses: &c_info.buses,
-            constraint_assert_dissabled_flag
+            constraint_assert_dissabled_flag,
+            prime: &c_info.prime,
+            passes: &mut *passes,
         };
         let mut function_info = FunctionCodeInfo {
             name,
             params,
             returns,
+            is_extern,
             header: header.clone(),
             ..FunctionCodeInfo::default()
         };
         let code = instance.body;
         let out = translate::translate_code(code, code_info);
         string_table = out.string_table;
+        if is_extern {
+            // the wasm code gives the name of the function to the host
+            let next = string_table.len();
+            string_table.entry(function_info.name.clone()).or_insert(next);
+        }
         field_tracker = out.constant_tracker;
         function_info.body = out.code;
         function_info.max_number_of_ops_in_expression = out.expression_depth;
@@ -190,7 +233,7 @@ fn build_function_instances(
 }
 
 // WASM producer builder
-fn initialize_wasm_producer(vcp: &VCP, database: &TemplateDB, wat_flag:bool, version: &str) -> WASMProducer {
+fn initialize_wasm_producer(vcp: &VCP, database: &TemplateDB, wat_flag:bool, lazy_components: bool, version: &str) -> WASMProducer {
     use program_structure::utils::constants::UsefulConstants;
     let initial_node = vcp.get_main_id();
     let prime = UsefulConstants::new(&vcp.prime).get_p().clone();
@@ -209,7 +252,8 @@ fn initialize_wasm_producer(vcp: &VCP, database: &TemplateDB, wat_flag:bool, ver
         "vesta" => 1948,
         "secq256r1" => 1948,
         "bls12377" => 1948,
-        _ => unreachable!()
+        // the primes of a pragma prime have no wasm code
+        _ => 0
     };
     //producer.fr_memory_size = 412 if goldilocks and 1948 for bn128 and bls12381
     // for each created component we store three u32, for each son we store a u32 in its father
@@ -240,12 +284,13 @@ fn initialize_wasm_producer(vcp: &VCP, database: &TemplateDB, wat_flag:bool, ver
     producer.template_instance_list = build_template_list(vcp);
     producer.field_tracking.clear();
     producer.wat_flag = wat_flag;
+    producer.lazy_components = lazy_components;
 
     (producer.major_version, producer.minor_version, producer.patch_version) = get_number_version(version);
     producer
 }
 
-fn initialize_c_producer(vcp: &VCP, database: &TemplateDB, no_asm_flag: bool, version: &str) -> CProducer {
+fn initialize_c_producer(vcp: &VCP, database: &TemplateDB, no_asm_flag: bool, lazy_components: bool, version: &str) -> CProducer {
     use program_structure::utils::constants::UsefulConstants;
     let initial_node = vcp.get_main_id();
     let prime = UsefulConstants::new(&vcp.prime).get_p().clone();
@@ -280,7 +325,9 @@ fn initialize_c_producer(vcp: &VCP, database: &TemplateDB, no_asm_flag: bool, ve
     producer.io_map = build_io_map(vcp, database);
     producer.template_instance_list = build_template_list_parallel(vcp);
     producer.field_tracking.clear();
-    producer.no_asm = no_asm_flag;
+    // only the primes of the curves have assembly code for their field
+    producer.no_asm = no_asm_flag || !UsefulConstants::is_prime_name(&vcp.prime);
+    producer.lazy_components = lazy_components;
     
     (producer.major_version, producer.minor_version, producer.patch_version) = get_number_version(version);
     producer
@@ -580,11 +627,13 @@ struct CircuitInfo {
     file_library: FileLibrary,
     functions: HashMap<String, Vec<usize>>,
     template_database: TemplateDB,
-    buses: Vec<BusInstance>
+    buses: Vec<BusInstance>,
+    prime: BigInt,
 }
 
 pub fn build_circuit(vcp: VCP, flag: CompilationFlags, version: &str) -> Circuit {
-    use crate::ir_processing::set_arena_size_in_calls;
+    use crate::ir_processing::{propagate_constant_inputs, set_arena_size_in_calls, share_constants};
+    use program_structure::utils::constants::UsefulConstants;
     if flag.main_inputs_log {
         write_main_inputs_log_old(&vcp);
         write_main_inputs_log_new(&vcp);
@@ -592,21 +641,108 @@ pub fn build_circuit(vcp: VCP, flag: CompilationFlags, version: &str) -> Circuit
     }
     let template_database = TemplateDB::build(&vcp.templates);
     let mut circuit = Circuit::default();
-    circuit.wasm_producer = initialize_wasm_producer(&vcp, &template_database, flag.wat_flag, version);
-    circuit.c_producer = initialize_c_producer(&vcp, &template_database, flag.no_asm_flag, version);
+    circuit.wasm_producer = initialize_wasm_producer(&vcp, &template_database, flag.wat_flag, flag.lazy_components_flag, version);
+    circuit.c_producer = initialize_c_producer(&vcp, &template_database, flag.no_asm_flag, flag.lazy_components_flag, version);
+    circuit.wasm_producer.trace_signals = flag.trace_signals_flag;
+    circuit.c_producer.trace_signals = flag.trace_signals_flag;
+    circuit.wasm_producer.profile_templates = flag.profile_templates_flag;
+    circuit.c_producer.profile_templates = flag.profile_templates_flag;
+    let interfaces: Vec<Interface> = vcp
+        .templates
+        .iter()
+        .map(|template| interface_of(&build_input_output_list(template, &template_database)))
+        .collect();
 
     let field_tracker = FieldTracker::new();
+    let main_header = vcp.get_main_instance().unwrap().template_header.clone();
     let circuit_info = CircuitInfo {
         template_database,
         file_library: vcp.file_library,
         functions: vcp.quick_knowledge,
-        buses: vcp.buses
+        buses: vcp.buses,
+        prime: UsefulConstants::new(&vcp.prime).get_p().clone(),
     };
 
+    let mut passes = PassManager::new(&flag.passes);
+    let options = format!(
+        "{} {} {:?} {:?} {}",
+        version, vcp.prime, flag.passes.level, flag.passes.disabled_passes, flag.constraint_assert_disabled_flag
+    );
+    // the compilation goes on without the cache if its folder cannot be created
+    let ir_cache = flag.ir_cache.as_ref().and_then(|folder| match IrCache::new(PathBuf::from(folder), options) {
+        Ok(cache) => Some(cache),
+        Err(error) => {
+            eprintln!("{}", Colour::Yellow.paint(format!("The IR cache {} is not used: {}", folder, error)));
+            None
+        }
+    });
     let (field_tracker, string_table) =
-        build_template_instances(&mut circuit, &circuit_info, vcp.templates, field_tracker, flag.constraint_assert_disabled_flag);
-    let (field_tracker, function_to_arena_size, table_string_to_usize) =
-        build_function_instances(&mut circuit, &circuit_info, vcp.functions, field_tracker,string_table, flag.constraint_assert_disabled_flag);
+        build_template_instances(&mut circuit, &circuit_info, vcp.templates, field_tracker, flag.constraint_assert_disabled_flag, &mut passes, ir_cache.as_ref());
+    let (mut field_tracker, function_to_arena_size, table_string_to_usize) =
+        build_function_instances(&mut circuit, &circuit_info, vcp.functions, field_tracker,string_table, flag.constraint_assert_disabled_flag, &mut passes);
+    let sizes_before: Vec<CodeSize> = if passes.reports_effects() {
+        circuit.templates.iter().map(|template| CodeSize::of(&template.body)).collect()
+    } else {
+        Vec::new()
+    };
+    let templates = &mut circuit.templates;
+    let propagated = passes.run(Pass::PropagateConstantInputs, || {
+        propagate_constant_inputs(templates, &main_header, &mut field_tracker, &circuit_info.prime)
+    });
+    if propagated.is_some() {
+        for (template, before) in circuit.templates.iter().zip(sizes_before) {
+            let after = CodeSize::of(&template.body);
+            passes.record_effect(PassEffect {
+                code: template.header.clone(),
+                pass: Pass::PropagateConstantInputs,
+                before,
+                after,
+            });
+        }
+    }
+    let (templates, functions) = (&mut circuit.templates, &mut circuit.functions);
+    let shared = passes.run(Pass::ShareConstants, || {
+        share_constants(templates, functions, &mut field_tracker, &circuit_info.prime)
+    });
+    if let Some(shared) = shared {
+        field_tracker = shared;
+    }
+    let merged = passes.run(Pass::MergeInstances, || merge_instances(&mut circuit, &interfaces, &main_header));
+    if passes.reports_effects() {
+        for template in merged.unwrap_or_default() {
+            passes.record_effect(PassEffect {
+                code: template.header.clone(),
+                pass: Pass::MergeInstances,
+                before: CodeSize::of(&template.body),
+                after: CodeSize::default(),
+            });
+        }
+    }
+    for template in &circuit.templates {
+        let info = VerifyInfo {
+            variable_stack_depth: template.var_stack_depth,
+            signal_stack_depth: template.signal_stack_depth,
+            max_message_id: template.id,
+            number_of_constants: field_tracker.next_id(),
+            number_of_strings: table_string_to_usize.len(),
+        };
+        passes.verify(&template.header, "the circuit passes", &template.body, &info);
+    }
+    for function in &circuit.functions {
+        let info = VerifyInfo {
+            variable_stack_depth: function.max_number_of_vars,
+            signal_stack_depth: 0,
+            max_message_id: circuit.wasm_producer.message_list.len(),
+            number_of_constants: field_tracker.next_id(),
+            number_of_strings: table_string_to_usize.len(),
+        };
+        passes.verify(&function.header, "the circuit passes", &function.body, &info);
+    }
+    if flag.passes.report_timing {
+        circuit.pass_timings = passes.timings();
+        circuit.verify_timing = passes.verify_timing();
+    }
+    circuit.pass_effects = passes.effects();
 
     let table_usize_to_string = create_table_usize_to_string(table_string_to_usize);
     circuit.wasm_producer.set_string_table(table_usize_to_string.clone());
@@ -2,17 +2,28 @@ use super::function::{FunctionCode, FunctionCodeInfo};
 use super::template::{TemplateCode, TemplateCodeInfo};
 use super::types::*;
 use crate::hir::very_concrete_program::VCP;
+use crate::ir_processing::{PassConfig, PassEffect, PassTiming};
 use crate::translating_traits::*;
 use code_producers::c_elements::*;
 use code_producers::wasm_elements::*;
 use std::io::Write;
+use std::time::Duration;
 
 pub struct CompilationFlags {
     pub main_inputs_log: bool,
     pub wat_flag:bool,
 
     pub no_asm_flag: bool,
-    pub constraint_assert_disabled_flag: bool
+    pub constraint_assert_disabled_flag: bool,
+    pub passes: PassConfig,
+    // subcomponents are run when their outputs are read instead of when their inputs are set
+    pub lazy_components_flag: bool,
+    // the witness generators report each value stored in a signal
+    pub trace_signals_flag: bool,
+    // the witness generators measure the time of the runs of the templates
+    pub profile_templates_flag: bool,
+    // folder where the lowered code of the templates is kept between compilations
+    pub ir_cache: Option<String>,
 }
 
 pub struct Circuit {
@@ -20,6 +31,12 @@ pub struct Circuit {
     pub c_producer: CProducer,
     pub templates: Vec<TemplateCode>,
     pub functions: Vec<FunctionCode>,
+    // time spent in each of the passes applied to the IR
+    pub pass_timings: Vec<PassTiming>,
+    // checks of the code made after the passes, and the time spent in them
+    pub verify_timing: Option<(usize, Duration)>,
+    // changes in the size of the code made by each pass
+    pub pass_effects: Vec<PassEffect>,
 }
 
 impl Default for Circuit {
@@ -29,6 +46,9 @@ impl Default for Circuit {
             wasm_producer: WASMProducer::default(),
             templates: Vec::new(),
             functions: Vec::new(),
+            pass_timings: Vec::new(),
+            verify_timing: None,
+            pass_effects: Vec::new(),
         }
     }
 }
@@ -40,6 +60,18 @@ impl WriteWasm for Circuit {
         code.push("(module".to_string());
         let mut code_aux = generate_imports_list();
         code.append(&mut code_aux);
+        if self.functions.iter().any(|f| f.is_extern) {
+            code_aux = generate_extern_imports_list();
+            code.append(&mut code_aux);
+        }
+        if producer.traces_signals() {
+            code_aux = generate_trace_imports_list();
+            code.append(&mut code_aux);
+        }
+        if producer.profiles_templates() {
+            code_aux = generate_profile_imports_list();
+            code.append(&mut code_aux);
+        }
         code_aux = generate_memory_def_list(&producer);
         code.append(&mut code_aux);
 
@@ -50,6 +82,10 @@ impl WriteWasm for Circuit {
         code.append(&mut code_aux);
         code_aux = generate_exports_list();
         code.append(&mut code_aux);
+        if producer.profiles_templates() {
+            code_aux = generate_profile_exports_list();
+            code.append(&mut code_aux);
+        }
 
         code_aux = fr_code(&producer.prime_str);
         code.append(&mut code_aux);
@@ -102,15 +138,26 @@ impl WriteWasm for Circuit {
         code_aux = copy_fr_in_shared_rw_memory_generator(&producer);
         code.append(&mut code_aux);
 
+        if producer.traces_signals() {
+            code_aux = trace_signals_generator(producer);
+            code.append(&mut code_aux);
+        }
+
         code_aux = get_message_char_generator(&producer);
         code.append(&mut code_aux);
 
+        code_aux = get_log_formatted_generator();
+        code.append(&mut code_aux);
+
         code_aux = build_buffer_message_generator(&producer);
         code.append(&mut code_aux);
 
         code_aux = build_log_message_generator(&producer);
         code.append(&mut code_aux);
 
+        code_aux = fused_field_operations_generator();
+        code.append(&mut code_aux);
+
         // Actual code from the program
 
         for f in &self.functions {
@@ -143,6 +190,24 @@ impl WriteWasm for Circuit {
         let mut code_aux = generate_imports_list();
         let mut code = merge_code(code_aux);
         writer.write_all(code.as_bytes()).map_err(|_| {})?;
+
+        if self.functions.iter().any(|f| f.is_extern) {
+            code_aux = generate_extern_imports_list();
+            code = merge_code(code_aux);
+            writer.write_all(code.as_bytes()).map_err(|_| {})?;
+        }
+
+        if producer.traces_signals() {
+            code_aux = generate_trace_imports_list();
+            code = merge_code(code_aux);
+            writer.write_all(code.as_bytes()).map_err(|_| {})?;
+        }
+
+        if producer.profiles_templates() {
+            code_aux = generate_profile_imports_list();
+            code = merge_code(code_aux);
+            writer.write_all(code.as_bytes()).map_err(|_| {})?;
+        }
         //writer.flush().map_err(|_| {})?;
 
         code_aux = generate_memory_def_list(&producer);
@@ -165,6 +230,12 @@ impl WriteWasm for Circuit {
         writer.write_all(code.as_bytes()).map_err(|_| {})?;
         //writer.flush().map_err(|_| {})?;
 
+        if producer.profiles_templates() {
+            code_aux = generate_profile_exports_list();
+            code = merge_code(code_aux);
+            writer.write_all(code.as_bytes()).map_err(|_| {})?;
+        }
+
         code_aux = fr_code(&producer.prime_str);
         code = merge_code(code_aux);
         writer.write_all(code.as_bytes()).map_err(|_| {})?;
@@ -250,11 +321,21 @@ impl WriteWasm for Circuit {
         writer.write_all(code.as_bytes()).map_err(|_| {})?;
         //writer.flush().map_err(|_| {})?;
 
+        if producer.traces_signals() {
+            code_aux = trace_signals_generator(producer);
+            code = merge_code(code_aux);
+            writer.write_all(code.as_bytes()).map_err(|_| {})?;
+        }
+
         code_aux = get_message_char_generator(&producer);
         code = merge_code(code_aux);
         writer.write_all(code.as_bytes()).map_err(|_| {})?;
         //writer.flush().map_err(|_| {})?;
 
+        code_aux = get_log_formatted_generator();
+        code = merge_code(code_aux);
+        writer.write_all(code.as_bytes()).map_err(|_| {})?;
+
         code_aux = build_buffer_message_generator(&producer);
         code = merge_code(code_aux);
         writer.write_all(code.as_bytes()).map_err(|_| {})?;
@@ -265,6 +346,10 @@ impl WriteWasm for Circuit {
         writer.write_all(code.as_bytes()).map_err(|_| {})?;
         //writer.flush().map_err(|_| {})?;
 
+        code_aux = fused_field_operations_generator();
+        code = merge_code(code_aux);
+        writer.write_all(code.as_bytes()).map_err(|_| {})?;
+
         // Actual code from the program
 
         for f in &self.functions {
@@ -372,6 +457,10 @@ impl WriteC for Circuit {
         // Functions to release the memory
         let mut release_component_code = generate_function_release_memory_component();
         code.append(&mut release_component_code);
+        let mut failed_assert_code = generate_function_failed_assert();
+        code.append(&mut failed_assert_code);
+        let mut fused_operations_code = generate_function_fused_field_operations(producer);
+        code.append(&mut fused_operations_code);
 
         // Actual code of the circuit
         code.push("// function declarations".to_string());
@@ -497,6 +586,10 @@ impl WriteC for Circuit {
         // Functions to release the memory
         let mut release_component_code = generate_function_release_memory_component();
         code.append(&mut release_component_code);
+        let mut failed_assert_code = generate_function_failed_assert();
+        code.append(&mut failed_assert_code);
+        let mut fused_operations_code = generate_function_fused_field_operations(producer);
+        code.append(&mut fused_operations_code);
 
         // Actual code of the circuit
 
@@ -16,6 +16,8 @@ pub struct FunctionCodeInfo {
     pub body: InstructionList,
     pub max_number_of_vars: usize,
     pub max_number_of_ops_in_expression: usize,
+    // the body is given by the host when the witness is computed
+    pub is_extern: bool,
 }
 
 impl ToString for FunctionCodeInfo {
@@ -31,6 +33,9 @@ impl ToString for FunctionCodeInfo {
 impl WriteWasm for FunctionCodeInfo {
     fn produce_wasm(&self, producer: &WASMProducer) -> Vec<String> {
         use code_producers::wasm_elements::wasm_code_generator::*;
+        if self.is_extern {
+            return self.produce_extern_wasm(producer);
+        }
         //to be revised
         let mut instructions = vec![];
         let funcdef = format!("(func ${} (type $_t_i32i32ri32)", self.header);
@@ -51,6 +56,7 @@ impl WriteWasm for FunctionCodeInfo {
         instructions.push(format!("(local {} i32)", producer.get_copy_counter_tag()));
         instructions.push(format!("(local {} i32)", producer.get_call_lvar_tag()));
         instructions.push(format!(" (local {} i32)", producer.get_merror_tag()));
+        instructions.push(format!(" (local {} i64)", producer.get_error_site_tag()));
         let local_info_size_u32 = producer.get_local_info_size_u32();
         //set lvar (start of auxiliar memory for vars)
         instructions.push(set_constant("0"));
@@ -77,12 +83,13 @@ impl WriteWasm for FunctionCodeInfo {
             instructions.push(";; start of the function code".to_string());
 	}
         //generate code
+        instructions.append(&mut open_error_block());
 
         for t in &self.body {
             let mut instructions_body = t.produce_wasm(producer);
             instructions.append(&mut instructions_body);
         }
-        instructions.push(set_constant("0"));	
+        instructions.append(&mut close_error_block(producer));
         instructions.push(")".to_string());
         instructions
     }
@@ -92,21 +99,10 @@ impl WriteC for FunctionCodeInfo {
     fn produce_c(&self, producer: &CProducer, _parallel: Option<bool>) -> (Vec<String>, String) {
         use c_code_generator::*;
         let header = format!("void {}", self.header);
-        let params = vec![
-            declare_circom_calc_wit(),
-            if producer.prime_str != "goldilocks" {
-                declare_lvar_pointer()
-            } else {
-                declare_64bit_lvar_array()
-            },
-            declare_component_father(),
-            if producer.prime_str != "goldilocks" {
-                declare_dest_pointer()
-            } else {
-                declare_64bit_dest_reference()
-            },
-            declare_dest_size(),
-        ];
+        let params = c_params(producer);
+        if self.is_extern {
+            return (vec![self.produce_extern_c(producer, header, params)], "".to_string());
+        }
         let mut body = vec![];
         if producer.prime_str != "goldilocks" {
             body.push(format!("{};", declare_circuit_constants()));
@@ -126,6 +122,25 @@ impl WriteC for FunctionCodeInfo {
     }
 }
 
+fn c_params(producer: &CProducer) -> Vec<String> {
+    use c_code_generator::*;
+    vec![
+        declare_circom_calc_wit(),
+        if producer.prime_str != "goldilocks" {
+            declare_lvar_pointer()
+        } else {
+            declare_64bit_lvar_array()
+        },
+        declare_component_father(),
+        if producer.prime_str != "goldilocks" {
+            declare_dest_pointer()
+        } else {
+            declare_64bit_dest_reference()
+        },
+        declare_dest_size(),
+    ]
+}
+
 impl FunctionCodeInfo {
     pub fn wrap(self) -> FunctionCode {
         FunctionCode::new(self)
@@ -133,4 +148,76 @@ impl FunctionCodeInfo {
     pub fn is_linked(&self, name: &str, params: &Vec<Param>) -> bool {
         self.name.eq(name) && self.params.eq(params)
     }
+
+    // The number of values of the arguments, which are in the first variables
+    fn number_of_argument_values(&self) -> usize {
+        self.params.iter().map(|p| p.length.iter().product::<usize>()).sum()
+    }
+
+    // The host is given the arguments one by one through the shared memory and
+    // then the name of the function in the message buffer, and it gives back
+    // each value of the result through the shared memory
+    fn produce_extern_wasm(&self, producer: &WASMProducer) -> Vec<String> {
+        use code_producers::wasm_elements::wasm_code_generator::*;
+        let mut instructions = vec![];
+        instructions.push(format!("(func ${} (type $_t_i32i32ri32)", self.header));
+        instructions.push(format!("(param {} i32)", producer.get_result_address_tag()));
+        instructions.push(format!("(param {} i32)", producer.get_result_size_tag()));
+        instructions.push("(result i32)".to_string());
+        instructions.push(format!("(local {} i32)", producer.get_lvar_tag()));
+        instructions.push(set_constant("0"));
+        instructions.push(load32(None)); // current stack size
+        let var_start = producer.get_local_info_size_u32() * 4;
+        instructions.push(set_constant(&var_start.to_string()));
+        instructions.push(add32());
+        instructions.push(set_local(producer.get_lvar_tag()));
+        let size = producer.get_size_32_bits_in_memory() * 4;
+        for i in 0..self.number_of_argument_values() {
+            instructions.push(get_local(producer.get_lvar_tag()));
+            instructions.push(set_constant(&(i * size).to_string()));
+            instructions.push(add32());
+            instructions.push(call("$copyFr2SharedRWMemory"));
+            instructions.push(call("$externArgument"));
+        }
+        let string_id = producer.get_string_table().iter().position(|s| *s == self.name).unwrap();
+        let pos = producer.get_string_list_start() + string_id * producer.get_size_of_message_in_bytes();
+        instructions.push(set_constant(&pos.to_string()));
+        instructions.push(call("$buildLogMessage"));
+        instructions.push(call("$externCall"));
+        let shared_rw_memory = producer.get_shared_rw_memory_start() - 8; // the shared memory as Fr
+        for i in 0..self.returns.iter().product::<usize>() {
+            instructions.push(set_constant(&i.to_string()));
+            instructions.push(call("$externResult"));
+            instructions.push(get_local(producer.get_result_address_tag()));
+            instructions.push(set_constant(&(i * size).to_string()));
+            instructions.push(add32());
+            instructions.push(set_constant(&shared_rw_memory.to_string()));
+            instructions.push(call("$Fr_copy"));
+        }
+        instructions.push(set_constant("0"));
+        instructions.push(")".to_string());
+        instructions
+    }
+
+    // The host function registered with the name of the function is called
+    // with the arguments, which are in the first variables
+    fn produce_extern_c(&self, producer: &CProducer, header: String, params: Vec<String>) -> String {
+        use c_code_generator::*;
+        let destination = if producer.prime_str != "goldilocks" {
+            FUNCTION_DESTINATION.to_string()
+        } else {
+            format!("&{}", FUNCTION_DESTINATION)
+        };
+        let call = build_call(
+            "callExternFunction".to_string(),
+            vec![
+                format!("\"{}\"", self.name),
+                L_VAR_STORAGE.to_string(),
+                self.number_of_argument_values().to_string(),
+                destination,
+                FUNCTION_DESTINATION_SIZE.to_string(),
+            ],
+        );
+        build_callable(header, params, vec![format!("{};", call)])
+    }
 }
@@ -0,0 +1,364 @@
+use crate::hir::very_concrete_program::*;
+use crate::intermediate_representation::ir_interface::*;
+use crate::intermediate_representation::translate::{CodeOutput, FieldTracker, TemplateDB};
+use program_structure::file_definition::FileLibrary;
+use serde_derive::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::PathBuf;
+
+// Lowered code of the template instances of previous compilations. Each
+// instance is stored in a file named after the fingerprint of everything
+// its translation depends on. The instances are only known after the
+// constraint generation, so the cache skips their translation and the
+// passes run on each one, not the parsing, the type analysis or the
+// constraint generation
+pub struct IrCache {
+    folder: PathBuf,
+    // compilation options that change the produced code
+    options: String,
+}
+
+// The constant, string and component ids are local to the cached template
+#[derive(Serialize, Deserialize)]
+struct CachedTemplate {
+    code: InstructionList,
+    constants: Vec<String>,
+    strings: Vec<String>,
+    number_of_cmp_ids: usize,
+    stack_depth: usize,
+    signal_depth: usize,
+    expression_depth: usize,
+}
+
+#[derive(Serialize)]
+struct TemplateKey<'a> {
+    message_id: usize,
+    instance: InstanceKey<'a>,
+    // the lines of the code depend on the source that precedes it
+    source: &'a str,
+    subcomponents: Vec<(&'a str, &'a Vec<Wire>)>,
+    mixed_signals: Vec<(&'a str, Vec<(&'a String, &'a usize)>)>,
+    buses: &'a Vec<BusInstance>,
+}
+
+#[derive(Serialize)]
+struct InstanceKey<'a> {
+    name: &'a str,
+    header: &'a str,
+    template_id: usize,
+    arguments: &'a Vec<Argument>,
+    wires: &'a Vec<Wire>,
+    signals_to_tags: Vec<(&'a Vec<String>, &'a num_bigint_dig::BigInt)>,
+    components: &'a Vec<Component>,
+    triggers: &'a Vec<Trigger>,
+    clusters: &'a Vec<TriggerCluster>,
+    parallel: (bool, bool, bool, bool),
+    code: &'a Code,
+}
+
+impl IrCache {
+    pub fn new(folder: PathBuf, options: String) -> io::Result<IrCache> {
+        std::fs::create_dir_all(&folder)?;
+        Ok(IrCache { folder, options })
+    }
+
+    pub fn fingerprint(
+        &self,
+        message_id: usize,
+        instance: &TemplateInstance,
+        templates: &[TemplateInstance],
+        database: &TemplateDB,
+        buses: &Vec<BusInstance>,
+        files: &FileLibrary,
+    ) -> serde_json::Result<String> {
+        let meta = instance.code.get_meta();
+        let source = meta
+            .file_id
+            .and_then(|id| files.get_source(id))
+            .and_then(|source| source.get(..meta.end))
+            .unwrap_or("");
+        let mut subcomponents = Vec::new();
+        let mut mixed_signals = Vec::new();
+        for trigger in &instance.triggers {
+            let sub = &templates[trigger.template_id];
+            subcomponents.push((sub.template_header.as_str(), &sub.wires));
+        }
+        for cluster in &instance.clusters {
+            match &cluster.xtype {
+                ClusterType::Uniform { instance_id, .. } => {
+                    let sub = &templates[*instance_id];
+                    subcomponents.push((sub.template_header.as_str(), &sub.wires));
+                }
+                ClusterType::Mixed { tmp_name } => {
+                    let index = database.indexes[tmp_name];
+                    let mut signals: Vec<_> = database.signals_id[index].iter().collect();
+                    signals.sort();
+                    mixed_signals.push((tmp_name.as_str(), signals));
+                }
+            }
+        }
+        let mut signals_to_tags: Vec<_> = instance.signals_to_tags.iter().collect();
+        signals_to_tags.sort();
+        let key = TemplateKey {
+            message_id,
+            instance: InstanceKey {
+                name: &instance.template_name,
+                header: &instance.template_header,
+                template_id: instance.template_id,
+                arguments: &instance.header,
+                wires: &instance.wires,
+                signals_to_tags,
+                components: &instance.components,
+                triggers: &instance.triggers,
+                clusters: &instance.clusters,
+                parallel: (
+                    instance.is_parallel,
+                    instance.is_parallel_component,
+                    instance.is_not_parallel_component,
+                    instance.has_parallel_sub_cmp,
+                ),
+                code: &instance.code,
+            },
+            source,
+            subcomponents,
+            mixed_signals,
+            buses,
+        };
+        let mut hasher = Sha1::new();
+        hasher.update(self.options.as_bytes());
+        hasher.update(serde_json::to_vec(&key)?);
+        Ok(hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect())
+    }
+
+    fn file(&self, fingerprint: &str) -> PathBuf {
+        self.folder.join(format!("{}.json", fingerprint))
+    }
+
+    // Returns the cached code of the instance with the ids of this compilation
+    pub fn load(
+        &self,
+        fingerprint: &str,
+        first_cmp_id: usize,
+        mut field_tracker: FieldTracker,
+        mut string_table: HashMap<String, usize>,
+    ) -> Result<CodeOutput, (FieldTracker, HashMap<String, usize>)> {
+        let cached: Option<CachedTemplate> = File::open(self.file(fingerprint))
+            .ok()
+            .and_then(|file| serde_json::from_reader(BufReader::new(file)).ok());
+        let mut cached = match cached {
+            Some(cached) => cached,
+            None => return Err((field_tracker, string_table)),
+        };
+        let mut ids = ImportIds {
+            field_tracker: &mut field_tracker,
+            string_table: &mut string_table,
+            constants: &cached.constants,
+            strings: &cached.strings,
+            first_cmp_id,
+        };
+        map_list(&mut cached.code, &mut ids);
+        Ok(CodeOutput {
+            code: cached.code,
+            stack_depth: cached.stack_depth,
+            signal_depth: cached.signal_depth,
+            expression_depth: cached.expression_depth,
+            next_cmp_id: first_cmp_id + cached.number_of_cmp_ids,
+            constant_tracker: field_tracker,
+            string_table,
+        })
+    }
+
+    // Failing to store an instance only makes the next compilation slower
+    pub fn store(&self, fingerprint: &str, first_cmp_id: usize, out: &CodeOutput) {
+        let mut table = vec![String::new(); out.string_table.len()];
+        for (string, id) in &out.string_table {
+            table[*id] = string.clone();
+        }
+        let mut ids = ExportIds {
+            field_tracker: &out.constant_tracker,
+            string_table: &table,
+            constants: HashMap::new(),
+            strings: HashMap::new(),
+            first_cmp_id,
+        };
+        let mut code = out.code.clone();
+        map_list(&mut code, &mut ids);
+        let cached = CachedTemplate {
+            code,
+            constants: sorted_by_id(ids.constants),
+            strings: sorted_by_id(ids.strings),
+            number_of_cmp_ids: out.next_cmp_id - first_cmp_id,
+            stack_depth: out.stack_depth,
+            signal_depth: out.signal_depth,
+            expression_depth: out.expression_depth,
+        };
+        if let Ok(file) = File::create(self.file(fingerprint)) {
+            let _ = serde_json::to_writer(BufWriter::new(file), &cached);
+        }
+    }
+}
+
+fn sorted_by_id(values: HashMap<String, usize>) -> Vec<String> {
+    let mut values: Vec<_> = values.into_iter().collect();
+    values.sort_by_key(|(_, id)| *id);
+    values.into_iter().map(|(value, _)| value).collect()
+}
+
+trait IdMap {
+    fn constant(&mut self, id: usize) -> usize;
+    fn string(&mut self, id: usize) -> usize;
+    fn component(&self, id: usize) -> usize;
+}
+
+struct ExportIds<'a> {
+    field_tracker: &'a FieldTracker,
+    string_table: &'a [String],
+    constants: HashMap<String, usize>,
+    strings: HashMap<String, usize>,
+    first_cmp_id: usize,
+}
+
+fn local_id(values: &mut HashMap<String, usize>, value: &str) -> usize {
+    let next = values.len();
+    *values.entry(value.to_string()).or_insert(next)
+}
+
+impl IdMap for ExportIds<'_> {
+    fn constant(&mut self, id: usize) -> usize {
+        local_id(&mut self.constants, self.field_tracker.get_constant(id).unwrap())
+    }
+    fn string(&mut self, id: usize) -> usize {
+        local_id(&mut self.strings, &self.string_table[id])
+    }
+    fn component(&self, id: usize) -> usize {
+        id - self.first_cmp_id
+    }
+}
+
+struct ImportIds<'a> {
+    field_tracker: &'a mut FieldTracker,
+    string_table: &'a mut HashMap<String, usize>,
+    constants: &'a [String],
+    strings: &'a [String],
+    first_cmp_id: usize,
+}
+
+impl IdMap for ImportIds<'_> {
+    fn constant(&mut self, id: usize) -> usize {
+        self.field_tracker.insert(self.constants[id].clone())
+    }
+    fn string(&mut self, id: usize) -> usize {
+        let next = self.string_table.len();
+        *self.string_table.entry(self.strings[id].clone()).or_insert(next)
+    }
+    fn component(&self, id: usize) -> usize {
+        id + self.first_cmp_id
+    }
+}
+
+fn map_list<M: IdMap>(instructions: &mut InstructionList, ids: &mut M) {
+    for i in instructions {
+        map_instruction(i, ids);
+    }
+}
+
+fn map_instruction<M: IdMap>(instruction: &mut Instruction, ids: &mut M) {
+    use Instruction::*;
+    match instruction {
+        Value(b) => {
+            if b.parse_as == ValueType::BigInt {
+                b.value = ids.constant(b.value);
+            }
+        }
+        Load(b) => {
+            map_location(&mut b.src, ids);
+            map_address_type(&mut b.address_type, ids);
+        }
+        Store(b) => {
+            map_instruction(&mut b.src, ids);
+            map_location(&mut b.dest, ids);
+            map_address_type(&mut b.dest_address_type, ids);
+            if let Some(address) = &mut b.src_address_type {
+                map_instruction(address, ids);
+            }
+        }
+        ArrayCopy(b) => {
+            map_instruction(&mut b.dest, ids);
+            map_instruction(&mut b.src, ids);
+        }
+        Compute(b) => map_list(&mut b.stack, ids),
+        Select(b) => {
+            map_instruction(&mut b.cond, ids);
+            map_instruction(&mut b.if_true, ids);
+            map_instruction(&mut b.if_false, ids);
+        }
+        Call(b) => {
+            map_list(&mut b.arguments, ids);
+            if let ReturnType::Final(data) = &mut b.return_info {
+                map_location(&mut data.dest, ids);
+                map_address_type(&mut data.dest_address_type, ids);
+            }
+        }
+        Branch(b) => {
+            map_instruction(&mut b.cond, ids);
+            map_list(&mut b.if_branch, ids);
+            map_list(&mut b.else_branch, ids);
+        }
+        Switch(b) => {
+            map_instruction(&mut b.in_range, ids);
+            map_instruction(&mut b.selector, ids);
+            for case in &mut b.cases {
+                map_list(&mut case.body, ids);
+            }
+            map_list(&mut b.default_branch, ids);
+        }
+        Loop(b) => {
+            map_instruction(&mut b.continue_condition, ids);
+            map_list(&mut b.body, ids);
+        }
+        Return(b) => map_instruction(&mut b.value, ids),
+        Assert(b) => {
+            map_instruction(&mut b.evaluate, ids);
+            for part in &mut b.message {
+                if let AssertMessagePart::Str(id) = part {
+                    *id = ids.string(*id);
+                }
+            }
+            map_list(&mut b.values, ids);
+        }
+        CreateCmp(b) => {
+            b.cmp_unique_id = ids.component(b.cmp_unique_id);
+            map_instruction(&mut b.sub_cmp_id, ids);
+        }
+        Log(b) => {
+            for print in &mut b.argsprint {
+                match print {
+                    LogBucketArg::LogExp(exp) => map_instruction(exp, ids),
+                    LogBucketArg::LogStr(id) => *id = ids.string(*id),
+                }
+            }
+        }
+    }
+}
+
+fn map_location<M: IdMap>(location: &mut LocationRule, ids: &mut M) {
+    match location {
+        LocationRule::Indexed { location, .. } => map_instruction(location, ids),
+        LocationRule::Mapped { indexes, .. } => {
+            for access in indexes {
+                if let AccessType::Indexed(instr) = access {
+                    map_list(&mut instr.indexes, ids);
+                }
+            }
+        }
+    }
+}
+
+fn map_address_type<M: IdMap>(xtype: &mut AddressType, ids: &mut M) {
+    if let AddressType::SubcmpSignal { cmp_address, .. } = xtype {
+        map_instruction(cmp_address, ids);
+    }
+}
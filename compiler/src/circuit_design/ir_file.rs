@@ -0,0 +1,119 @@
+use super::circuit::Circuit;
+use crate::intermediate_representation::ir_text::{from_text, to_text};
+use crate::intermediate_representation::InstructionList;
+use crate::ir_processing::{verify_list, VerifyInfo};
+use serde_derive::{Deserialize, Serialize};
+
+// The code of the circuit after the passes, as it is given to the producers of
+// wasm and C. The templates and functions are identified by their headers
+#[derive(Serialize, Deserialize)]
+struct CircuitIr {
+    constants: Vec<String>,
+    strings: Vec<String>,
+    templates: Vec<TemplateIr>,
+    functions: Vec<FunctionIr>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TemplateIr {
+    header: String,
+    var_stack_depth: usize,
+    expression_stack_depth: usize,
+    number_of_components: usize,
+    body: InstructionList,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FunctionIr {
+    header: String,
+    max_number_of_vars: usize,
+    max_number_of_ops_in_expression: usize,
+    body: InstructionList,
+}
+
+impl Circuit {
+    pub fn produce_ir_text(&self, version: &str) -> String {
+        let templates = self
+            .templates
+            .iter()
+            .map(|template| TemplateIr {
+                header: template.header.clone(),
+                var_stack_depth: template.var_stack_depth,
+                expression_stack_depth: template.expression_stack_depth,
+                number_of_components: template.number_of_components,
+                body: template.body.clone(),
+            })
+            .collect();
+        let functions = self
+            .functions
+            .iter()
+            .map(|function| FunctionIr {
+                header: function.header.clone(),
+                max_number_of_vars: function.max_number_of_vars,
+                max_number_of_ops_in_expression: function.max_number_of_ops_in_expression,
+                body: function.body.clone(),
+            })
+            .collect();
+        let ir = CircuitIr {
+            constants: self.c_producer.field_tracking.clone(),
+            strings: self.c_producer.get_string_table().clone(),
+            templates,
+            functions,
+        };
+        format!("// IR of the circuit, written by circom {}\n{}\n", version, to_text(&ir))
+    }
+
+    // Replaces the code of the templates and functions that are in the text,
+    // which must be the ones of this circuit, and the tables of constants and
+    // strings. The code is checked before it is taken
+    pub fn load_ir_text(&mut self, text: &str) -> Result<(), String> {
+        let ir: CircuitIr = from_text(text)?;
+        let max_message_id = self.wasm_producer.message_list.len();
+        let mut templates = Vec::new();
+        for loaded in &ir.templates {
+            let id = self.templates.iter().position(|template| template.header == loaded.header);
+            let id = id.ok_or_else(|| format!("the circuit has no template {}", loaded.header))?;
+            let info = VerifyInfo {
+                variable_stack_depth: loaded.var_stack_depth,
+                signal_stack_depth: self.templates[id].signal_stack_depth,
+                max_message_id,
+                number_of_constants: ir.constants.len(),
+                number_of_strings: ir.strings.len(),
+            };
+            verify_list(&loaded.body, &info).map_err(|error| format!("code of {}, {}", loaded.header, error))?;
+            templates.push(id);
+        }
+        let mut functions = Vec::new();
+        for loaded in &ir.functions {
+            let id = self.functions.iter().position(|function| function.header == loaded.header);
+            let id = id.ok_or_else(|| format!("the circuit has no function {}", loaded.header))?;
+            let info = VerifyInfo {
+                variable_stack_depth: loaded.max_number_of_vars,
+                signal_stack_depth: 0,
+                max_message_id,
+                number_of_constants: ir.constants.len(),
+                number_of_strings: ir.strings.len(),
+            };
+            verify_list(&loaded.body, &info).map_err(|error| format!("code of {}, {}", loaded.header, error))?;
+            functions.push(id);
+        }
+        for (id, loaded) in templates.into_iter().zip(ir.templates) {
+            let template = &mut self.templates[id];
+            template.var_stack_depth = loaded.var_stack_depth;
+            template.expression_stack_depth = loaded.expression_stack_depth;
+            template.number_of_components = loaded.number_of_components;
+            template.body = loaded.body;
+        }
+        for (id, loaded) in functions.into_iter().zip(ir.functions) {
+            let function = &mut self.functions[id];
+            function.max_number_of_vars = loaded.max_number_of_vars;
+            function.max_number_of_ops_in_expression = loaded.max_number_of_ops_in_expression;
+            function.body = loaded.body;
+        }
+        self.wasm_producer.field_tracking = ir.constants.clone();
+        self.c_producer.field_tracking = ir.constants;
+        self.wasm_producer.set_string_table(ir.strings.clone());
+        self.c_producer.set_string_table(ir.strings);
+        Ok(())
+    }
+}
@@ -0,0 +1,336 @@
+use super::circuit::Circuit;
+use super::template::TemplateCode;
+use crate::intermediate_representation::ir_interface::*;
+use code_producers::components::{IODef, TemplateInstanceIOMap};
+use std::collections::HashMap;
+
+// Code, offset, lengths, size and bus of each input and output signal of an instance
+pub type Interface = Vec<(usize, usize, Vec<usize>, usize, Option<usize>)>;
+
+pub fn interface_of(io_list: &[IODef]) -> Interface {
+    io_list.iter().map(|io| (io.code, io.offset, io.lengths.clone(), io.size, io.bus_id)).collect()
+}
+
+// Removes the instances whose code is the same as the one of another instance
+// of the template, making their components be created as components of the
+// instance that is kept. The instances that create the removed ones may become
+// the same in turn, so it is repeated until no instance is removed. Returns
+// the removed instances
+pub fn merge_instances(circuit: &mut Circuit, interfaces: &[Interface], main_header: &str) -> Vec<TemplateCode> {
+    let mut removed = Vec::new();
+    let mut merged_into = HashMap::new();
+    loop {
+        // the main component is visited first so that it is always kept
+        let mut order: Vec<usize> = (0..circuit.templates.len()).collect();
+        order.sort_by_key(|i| circuit.templates[*i].header != main_header);
+        let mut kept: HashMap<String, usize> = HashMap::new();
+        let mut renaming: HashMap<String, (usize, String)> = HashMap::new();
+        for i in order {
+            let template = &circuit.templates[i];
+            let key = instance_key(template, &interfaces[template.id]);
+            match kept.get(&key) {
+                Some(other) => {
+                    let other = &circuit.templates[*other];
+                    merged_into.insert(template.id, other.id);
+                    renaming.insert(template.header.clone(), (other.id, other.header.clone()));
+                }
+                None => {
+                    kept.insert(key, i);
+                }
+            }
+        }
+        if renaming.is_empty() {
+            break;
+        }
+        for template in std::mem::take(&mut circuit.templates) {
+            if renaming.contains_key(&template.header) {
+                removed.push(template);
+            } else {
+                circuit.templates.push(template);
+            }
+        }
+        for template in &mut circuit.templates {
+            visit_list(&mut template.body, &mut |i| rename_instances(i, &renaming));
+        }
+    }
+
+    // the tables of the producers keep an entry for every instance, the
+    // removed ones refer to the code of the instance that replaces them
+    let headers: HashMap<usize, String> =
+        circuit.templates.iter().map(|t| (t.id, t.header.clone())).collect();
+    for (id, mut target) in merged_into.clone() {
+        while let Some(next) = merged_into.get(&target) {
+            target = *next;
+        }
+        circuit.wasm_producer.template_instance_list[id] = headers[&target].clone();
+        circuit.c_producer.template_instance_list[id].name = headers[&target].clone();
+        copy_io_list(&mut circuit.wasm_producer.io_map, id, target);
+        copy_io_list(&mut circuit.c_producer.io_map, id, target);
+    }
+    removed
+}
+
+// The mapped accesses to the signals of a component use the io list of the
+// instance that created it
+fn copy_io_list(io_map: &mut TemplateInstanceIOMap, from: usize, to: usize) {
+    if io_map.contains_key(&from) && !io_map.contains_key(&to) {
+        let copy = io_map[&from]
+            .iter()
+            .map(|io| IODef { code: io.code, offset: io.offset, lengths: io.lengths.clone(), size: io.size, bus_id: io.bus_id })
+            .collect();
+        io_map.insert(to, copy);
+    }
+}
+
+// Two instances get the same key if they run the same code over components
+// of the same shape
+fn instance_key(template: &TemplateCode, interface: &Interface) -> String {
+    let mut body = template.body.clone();
+    visit_list(&mut body, &mut clear_ids);
+    format!(
+        "{} {} {} {} {} {} {} {} {} {} {} {} {:?} {}",
+        template.name,
+        template.is_parallel,
+        template.is_parallel_component,
+        template.is_not_parallel_component,
+        template.has_parallel_sub_cmp,
+        template.number_of_inputs,
+        template.number_of_outputs,
+        template.number_of_intermediates,
+        template.number_of_components,
+        template.var_stack_depth,
+        template.expression_stack_depth,
+        template.signal_stack_depth,
+        interface,
+        serde_json::to_string(&body).unwrap(),
+    )
+}
+
+// The ids that are different in every instance
+fn clear_ids(instruction: &mut Instruction) {
+    use Instruction::*;
+    match instruction {
+        Value(b) => b.message_id = 0,
+        Load(b) => b.message_id = 0,
+        Store(b) => b.message_id = 0,
+        ArrayCopy(b) => b.message_id = 0,
+        Compute(b) => b.message_id = 0,
+        Select(b) => b.message_id = 0,
+        Call(b) => b.message_id = 0,
+        Branch(b) => b.message_id = 0,
+        Switch(b) => b.message_id = 0,
+        Return(b) => b.message_id = 0,
+        Assert(b) => b.message_id = 0,
+        Log(b) => b.message_id = 0,
+        Loop(b) => b.message_id = 0,
+        CreateCmp(b) => {
+            b.message_id = 0;
+            b.cmp_unique_id = 0;
+        }
+    }
+}
+
+fn rename_instances(instruction: &mut Instruction, renaming: &HashMap<String, (usize, String)>) {
+    use Instruction::*;
+    let location = match instruction {
+        CreateCmp(b) => {
+            if let Some((id, header)) = renaming.get(&b.symbol) {
+                b.template_id = *id;
+                b.symbol = header.clone();
+            }
+            return;
+        }
+        Load(b) => &mut b.src,
+        Store(b) => &mut b.dest,
+        Call(b) => match &mut b.return_info {
+            ReturnType::Final(data) => &mut data.dest,
+            ReturnType::Intermediate { .. } => return,
+        },
+        _ => return,
+    };
+    if let LocationRule::Indexed { template_header: Some(header), .. } = location {
+        if let Some((_, new_header)) = renaming.get(header) {
+            *header = new_header.clone();
+        }
+    }
+}
+
+fn visit_list<F: FnMut(&mut Instruction)>(instructions: &mut InstructionList, f: &mut F) {
+    for i in instructions {
+        visit_instruction(i, f);
+    }
+}
+
+fn visit_instruction<F: FnMut(&mut Instruction)>(instruction: &mut Instruction, f: &mut F) {
+    use Instruction::*;
+    match instruction {
+        Value(_) => {}
+        Load(b) => {
+            visit_location(&mut b.src, f);
+            visit_address_type(&mut b.address_type, f);
+        }
+        Store(b) => {
+            visit_instruction(&mut b.src, f);
+            visit_location(&mut b.dest, f);
+            visit_address_type(&mut b.dest_address_type, f);
+            if let Some(address) = &mut b.src_address_type {
+                visit_instruction(address, f);
+            }
+        }
+        ArrayCopy(b) => {
+            visit_instruction(&mut b.dest, f);
+            visit_instruction(&mut b.src, f);
+        }
+        Compute(b) => visit_list(&mut b.stack, f),
+        Select(b) => {
+            visit_instruction(&mut b.cond, f);
+            visit_instruction(&mut b.if_true, f);
+            visit_instruction(&mut b.if_false, f);
+        }
+        Call(b) => {
+            visit_list(&mut b.arguments, f);
+            if let ReturnType::Final(data) = &mut b.return_info {
+                visit_location(&mut data.dest, f);
+                visit_address_type(&mut data.dest_address_type, f);
+            }
+        }
+        Branch(b) => {
+            visit_instruction(&mut b.cond, f);
+            visit_list(&mut b.if_branch, f);
+            visit_list(&mut b.else_branch, f);
+        }
+        Switch(b) => {
+            visit_instruction(&mut b.in_range, f);
+            visit_instruction(&mut b.selector, f);
+            for case in &mut b.cases {
+                visit_list(&mut case.body, f);
+            }
+            visit_list(&mut b.default_branch, f);
+        }
+        Loop(b) => {
+            visit_instruction(&mut b.continue_condition, f);
+            visit_list(&mut b.body, f);
+        }
+        Return(b) => visit_instruction(&mut b.value, f),
+        Assert(b) => {
+            visit_instruction(&mut b.evaluate, f);
+            visit_list(&mut b.values, f);
+        }
+        CreateCmp(b) => visit_instruction(&mut b.sub_cmp_id, f),
+        Log(b) => {
+            for print in &mut b.argsprint {
+                if let LogBucketArg::LogExp(exp) = print {
+                    visit_instruction(exp, f);
+                }
+            }
+        }
+    }
+    f(instruction);
+}
+
+fn visit_location<F: FnMut(&mut Instruction)>(location: &mut LocationRule, f: &mut F) {
+    match location {
+        LocationRule::Indexed { location, .. } => visit_instruction(location, f),
+        LocationRule::Mapped { indexes, .. } => {
+            for access in indexes {
+                if let AccessType::Indexed(instr) = access {
+                    visit_list(&mut instr.indexes, f);
+                }
+            }
+        }
+    }
+}
+
+fn visit_address_type<F: FnMut(&mut Instruction)>(xtype: &mut AddressType, f: &mut F) {
+    if let AddressType::SubcmpSignal { cmp_address, .. } = xtype {
+        visit_instruction(cmp_address, f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_instances, Interface};
+    use crate::circuit_design::circuit::Circuit;
+    use crate::circuit_design::template::TemplateCodeInfo;
+    use crate::intermediate_representation::ir_interface::*;
+    use crate::ir_processing::test_code::*;
+    use code_producers::components::InfoParallel;
+
+    // The templates given by id, name and body
+    fn circuit(templates: Vec<(usize, &str, InstructionList)>) -> Circuit {
+        let mut circuit = Circuit::default();
+        for (id, name, body) in templates {
+            let header = format!("{}_{}", name, id);
+            let info = InfoParallel { name: header.clone(), is_parallel: false, is_not_parallel: false };
+            circuit.c_producer.template_instance_list.push(info);
+            circuit.wasm_producer.template_instance_list.push(header.clone());
+            let template = TemplateCodeInfo { id, header, name: name.to_string(), body, ..Default::default() };
+            circuit.templates.push(Box::new(template));
+        }
+        circuit
+    }
+
+    fn merged(circuit: &mut Circuit) -> Vec<String> {
+        let interfaces = vec![Interface::new(); circuit.templates.len()];
+        let removed = merge_instances(circuit, &interfaces, "Main_0");
+        removed.iter().map(|t| t.header.clone()).collect()
+    }
+
+    fn headers(circuit: &Circuit) -> Vec<&str> {
+        circuit.templates.iter().map(|t| t.header.as_str()).collect()
+    }
+
+    fn doubled(message_id: usize) -> InstructionList {
+        let mut product = compute(OperatorType::Mul, vec![load_signal(value(1)), value(2)]);
+        if let Instruction::Compute(b) = &mut *product {
+            b.message_id = message_id;
+        }
+        vec![set_signal(value(0), product)]
+    }
+
+    #[test]
+    fn instances_with_the_same_code_are_merged() {
+        let mut circuit = circuit(vec![
+            (0, "Main", vec![create(1, "Sub_1", value(0), Vec::new()), create(2, "Sub_2", value(1), Vec::new())]),
+            (1, "Sub", doubled(1)),
+            (2, "Sub", doubled(2)),
+        ]);
+        assert_eq!(merged(&mut circuit), ["Sub_2"]);
+        assert_eq!(headers(&circuit), ["Main_0", "Sub_1"]);
+        match &*circuit.templates[0].body[1] {
+            Instruction::CreateCmp(b) => assert_eq!((b.template_id, b.symbol.as_str()), (1, "Sub_1")),
+            _ => unreachable!(),
+        }
+        // the tables of the producers send the removed instance to the kept one
+        assert_eq!(circuit.wasm_producer.template_instance_list[2], "Sub_1");
+        assert_eq!(circuit.c_producer.template_instance_list[2].name, "Sub_1");
+    }
+
+    #[test]
+    fn instances_with_different_code_are_kept() {
+        let mut tripled = doubled(2);
+        if let Instruction::Store(b) = &mut *tripled[0] {
+            b.src = compute(OperatorType::Mul, vec![load_signal(value(1)), value(3)]);
+        }
+        let mut circuit = circuit(vec![
+            (0, "Main", vec![create(1, "Sub_1", value(0), Vec::new()), create(2, "Sub_2", value(1), Vec::new())]),
+            (1, "Sub", doubled(1)),
+            (2, "Sub", tripled),
+        ]);
+        assert!(merged(&mut circuit).is_empty());
+        assert_eq!(headers(&circuit), ["Main_0", "Sub_1", "Sub_2"]);
+    }
+
+    #[test]
+    fn instances_that_become_the_same_are_merged_in_turn() {
+        let mut circuit = circuit(vec![
+            (0, "Main", vec![create(1, "Outer_1", value(0), Vec::new()), create(2, "Outer_2", value(1), Vec::new())]),
+            (1, "Outer", vec![create(3, "Sub_3", value(0), Vec::new())]),
+            (2, "Outer", vec![create(4, "Sub_4", value(0), Vec::new())]),
+            (3, "Sub", doubled(3)),
+            (4, "Sub", doubled(4)),
+        ]);
+        assert_eq!(merged(&mut circuit), ["Sub_4", "Outer_2"]);
+        assert_eq!(headers(&circuit), ["Main_0", "Outer_1", "Sub_3"]);
+    }
+}
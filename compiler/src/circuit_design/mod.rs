@@ -1,5 +1,8 @@
 mod build;
 pub mod circuit;
 pub mod function;
+mod ir_cache;
+mod ir_file;
+mod merge_instances;
 pub mod template;
 pub mod types;
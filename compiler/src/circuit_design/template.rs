@@ -1,4 +1,5 @@
 use crate::intermediate_representation::InstructionList;
+use crate::ir_processing::last_uses_of_components;
 use crate::translating_traits::*;
 use code_producers::c_elements::*;
 use code_producers::wasm_elements::*;
@@ -58,7 +59,7 @@ impl WriteWasm for TemplateCodeInfo {
             .push(store32(Some(&producer.get_signal_start_address_in_component().to_string())));
         //set component inputs_to_be_set
         instructions.push(get_local(producer.get_offset_tag()));
-        instructions.push(set_constant(&self.number_of_inputs.to_string()));
+        instructions.push(set_constant(&self.initial_input_counter(producer.defers_components(), RUN_COMPONENT_COUNTER)));
         instructions
             .push(store32(Some(&producer.get_input_counter_address_in_component().to_string())));
         //reserve memory for component
@@ -83,6 +84,7 @@ impl WriteWasm for TemplateCodeInfo {
         instructions.push(format!(" (local {} i32)", producer.get_signal_start_tag()));
         instructions.push(format!(" (local {} i32)", producer.get_sub_cmp_tag()));
         instructions.push(format!(" (local {} i32)", producer.get_sub_cmp_load_tag()));
+        instructions.push(format!(" (local {} i32)", producer.get_deferred_cmp_tag()));
         instructions.push(format!(" (local {} i32)", producer.get_io_info_tag()));
         instructions.push(format!(" (local {} i32)", producer.get_lvar_tag()));
         instructions.push(format!(" (local {} i32)", producer.get_expaux_tag()));
@@ -100,6 +102,10 @@ impl WriteWasm for TemplateCodeInfo {
         instructions.push(format!(" (local {} i32)", producer.get_create_loop_counter_tag()));
         instructions.push(format!(" (local {} i32)", producer.get_merror_tag()));
         instructions.push(format!(" (local {} i32)", producer.get_result_size_tag())); // used when calling functions assigned to inputs of subcomponents
+        instructions.push(format!(" (local {} i64)", producer.get_error_site_tag()));
+        if producer.traces_signals() {
+            instructions.push(format!(" (local {} i32)", producer.get_trace_address_tag()));
+        }
         let local_info_size_u32 = producer.get_local_info_size_u32(); // in the future we can add some info like pointer to run father or text father
                                                                       //set lvar (start of auxiliar memory for vars)
         instructions.push(set_constant("0"));
@@ -122,6 +128,10 @@ impl WriteWasm for TemplateCodeInfo {
             + self.expression_stack_depth * 4 * (producer.get_size_32_bit() + 2);
         let mut reserve_stack_fr_code = reserve_stack_fr(producer, needed_stack_bytes);
         instructions.append(&mut reserve_stack_fr_code);
+        if producer.profiles_templates() {
+            instructions.push(set_constant(&self.id.to_string()));
+            instructions.push(call("$profileEnter"));
+        }
         if producer.needs_comments() {
             instructions.push(";; start of the template code".to_string());
 	}
@@ -133,16 +143,28 @@ impl WriteWasm for TemplateCodeInfo {
         instructions.push(load32(None));
         instructions.push(set_local(producer.get_signal_start_tag()));
         //generate code
+        instructions.append(&mut open_error_block());
 
         for t in &self.body {
             let mut instructions_body = t.produce_wasm(producer);
             instructions.append(&mut instructions_body);
         }
 
+        if producer.defers_components() && self.number_of_components > 0 {
+            if producer.needs_comments() {
+                instructions.push(";; run the subcomponents whose outputs have not been read".to_string());
+            }
+            let mut run_code = run_deferred_subcomponents(producer, self.number_of_components);
+            instructions.append(&mut run_code);
+        }
         //free stack
         let mut free_stack_code = free_stack(producer);
         instructions.append(&mut free_stack_code);
-        instructions.push(set_constant("0"));	
+        // the runs that fail are not measured, since the witness is not computed
+        if producer.profiles_templates() {
+            instructions.push(call("$profileLeave"));
+        }
+        instructions.append(&mut close_error_block(producer));
         instructions.push(")".to_string());
         instructions
     }
@@ -163,6 +185,15 @@ impl WriteC for TemplateCodeInfo {
 
 
 impl TemplateCodeInfo {
+    // a component without inputs is run when it is created
+    fn initial_input_counter(&self, defers_components: bool, run_counter: &str) -> String {
+        if defers_components && self.number_of_inputs == 0 {
+            run_counter.to_string()
+        } else {
+            self.number_of_inputs.to_string()
+        }
+    }
+
     fn produce_c_parallel_case(&self, producer: &CProducer, parallel: bool) -> Vec<String> {
         use c_code_generator::*;
 
@@ -198,7 +229,7 @@ impl TemplateCodeInfo {
             "{}->componentMemory[{}].inputCounter = {};",
             CIRCOM_CALC_WIT,
 	        component_offset(),
-            &self.number_of_inputs.to_string()
+            self.initial_input_counter(producer.defers_components(), RUN_COMPONENT_COUNTER)
         ));
         create_body.push(format!(
             "{}->componentMemory[{}].componentName = {};",
@@ -297,10 +328,28 @@ impl TemplateCodeInfo {
         run_body.push(format!("{};", declare_sub_component_aux()));
         run_body.push(format!("{};", declare_index_multiple_eq()));
         run_body.push(format!("int cmp_index_ref_load = -1;"));
+        if producer.profiles_templates() {
+            // the run is measured until the profile is destroyed at the end of the function
+            run_body.push(format!("Circom_Profile circom_profile(\"{}\");", self.header));
+        }
 
-        for t in &self.body {
+        // the threads of parallel subcomponents may still be running
+        let released = if self.has_parallel_sub_cmp {
+            vec![Vec::new(); self.body.len()]
+        } else {
+            last_uses_of_components(&self.body, self.number_of_components)
+        };
+        let run_counter = if producer.defers_components() { RUN_COMPONENT_COUNTER } else { "0" };
+        for (t, components) in self.body.iter().zip(released) {
             let (mut instructions_body, _) = t.produce_c(producer, Some(parallel));
             run_body.append(&mut instructions_body);
+            for component in components {
+                run_body.append(&mut release_subcomponent(component, run_counter));
+            }
+        }
+        if producer.defers_components() && self.number_of_components > 0 {
+            run_body.push("// run the subcomponents whose outputs have not been read".to_string());
+            run_body.append(&mut run_deferred_subcomponents(self.number_of_components));
         }
 	// parallelism (join at the end of the function)
 	if self.number_of_components > 0 && self.has_parallel_sub_cmp {
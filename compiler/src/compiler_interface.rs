@@ -1,5 +1,6 @@
 pub use crate::circuit_design::circuit::{Circuit, CompilationFlags};
 pub use crate::hir::very_concrete_program::VCP;
+pub use crate::ir_processing::{OptimizationLevel, Pass, PassConfig, PassTiming};
 use std::fs::File;
 use std::io::BufWriter;
 
@@ -9,7 +10,15 @@ pub struct Config {
     pub wat_flag: bool,
 
     pub no_asm_flag: bool,
-    pub constraint_assert_disabled_flag: bool
+    pub constraint_assert_disabled_flag: bool,
+    pub passes: PassConfig,
+    pub lazy_components_flag: bool,
+    pub trace_signals_flag: bool,
+    pub profile_templates_flag: bool,
+    pub ir_cache: Option<String>,
+    // the name of the prime of the circuit, given by --prime or by the pragma
+    // prime of the main file
+    pub prime: String,
 }
 
 pub fn run_compiler(vcp: VCP, config: Config, version: &str) -> Result<Circuit, ()> {
@@ -18,7 +27,12 @@ pub fn run_compiler(vcp: VCP, config: Config, version: &str) -> Result<Circuit,
         wat_flag: config.wat_flag,
 
         no_asm_flag: config.no_asm_flag,
-        constraint_assert_disabled_flag: config.constraint_assert_disabled_flag
+        constraint_assert_disabled_flag: config.constraint_assert_disabled_flag,
+        passes: config.passes,
+        lazy_components_flag: config.lazy_components_flag,
+        trace_signals_flag: config.trace_signals_flag,
+        profile_templates_flag: config.profile_templates_flag,
+        ir_cache: config.ir_cache,
     };
     let circuit = Circuit::build(vcp, flags, version);
     if config.debug_output {
@@ -51,6 +65,35 @@ pub fn write_c(circuit: &Circuit, c_folder: &str, c_run_name: &str, c_file: &str
     circuit.produce_c(c_folder, c_run_name, &mut c_file, &mut dat_file)
 }
 
+pub fn write_fuzz_harness(circuit: &Circuit, c_folder: &str, c_run_name: &str, r1cs_file: &str) -> std::io::Result<()> {
+    use std::path::Path;
+    code_producers::c_elements::c_code_generator::generate_fuzz_files(Path::new(c_folder), c_run_name, r1cs_file, &circuit.c_producer)
+}
+
+pub fn write_bench_harness(circuit: &Circuit, c_folder: &str, c_run_name: &str) -> std::io::Result<()> {
+    use std::path::Path;
+    code_producers::c_elements::c_code_generator::generate_bench_files(Path::new(c_folder), c_run_name, &circuit.c_producer)
+}
+
+pub fn write_opt_report(circuit: &Circuit, file: &str) -> std::io::Result<()> {
+    use crate::ir_processing::build_report;
+    let report = build_report(&circuit.pass_effects);
+    let file = File::create(file)?;
+    serde_json::to_writer_pretty(BufWriter::new(file), &report)?;
+    Ok(())
+}
+
+pub fn write_ir(circuit: &Circuit, file: &str, version: &str) -> std::io::Result<()> {
+    std::fs::write(file, circuit.produce_ir_text(version))
+}
+
+// the code of the circuit is replaced by the one of the file, which is
+// usually a file written by write_ir and then edited
+pub fn load_ir(circuit: &mut Circuit, file: &str) -> Result<(), String> {
+    let text = std::fs::read_to_string(file).map_err(|_err| format!("could not read {}", file))?;
+    circuit.load_ir_text(&text)
+}
+
 fn produce_debug_output(circuit: &Circuit) -> Result<(), ()> {
     use std::io::Write;
     use std::path::Path;
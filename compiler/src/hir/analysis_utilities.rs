@@ -18,6 +18,7 @@ pub struct GenericFunction {
     pub name: String,
     pub params_names: Vec<String>,
     pub body: Statement,
+    pub is_extern: bool,
     pub concrete_instances: Vec<usize>,
 }
 
@@ -32,12 +33,14 @@ pub struct State {
 pub fn build_function_knowledge(program: ProgramArchive) -> State {
     let function_info = program.get_functions();
     let mut generic_functions = HashMap::new();
-    for f in function_info.values() {
+    // the functions that are only evaluated by the compiler have no code
+    for f in function_info.values().filter(|f| !f.is_compile_time()) {
         let name = f.get_name().to_string();
         let gen_function = GenericFunction {
             name: name.clone(),
             params_names: f.get_name_of_params().clone(),
             body: f.get_body().clone(),
+            is_extern: f.is_extern(),
             concrete_instances: Vec::new(),
         };
         generic_functions.insert(name, gen_function);
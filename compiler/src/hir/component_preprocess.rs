@@ -75,7 +75,10 @@ fn rm_init(stmt: &mut Statement) {
             let work = std::mem::take(initializations);
             for mut i in work {
                 if i.is_substitution() {
-                    initializations.push(i);
+                    // the assignments of the tags given in the declaration are removed
+                    if !should_be_removed(&i) {
+                        initializations.push(i);
+                    }
                 } else if i.is_block(){
                     rm_block(&mut i);
                     initializations.push(i);
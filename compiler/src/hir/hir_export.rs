@@ -0,0 +1,582 @@
+use super::very_concrete_program::*;
+use program_structure::ast::*;
+use program_structure::file_definition::FileLibrary;
+use serde_json::{json, Value};
+
+// The program after the type analysis and the instantiation of the templates,
+// which is the one translated to the IR. Each template instance has the values
+// of its parameters, its signals and its components with their lengths, and
+// the code of its template, where the variables know whether they are signals,
+// components, buses, tags or variables
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum HirFormat {
+    Text,
+    Json,
+}
+
+impl HirFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            HirFormat::Text => "txt",
+            HirFormat::Json => "json",
+        }
+    }
+}
+
+fn assign_op(op: AssignOp) -> &'static str {
+    match op {
+        AssignOp::AssignVar => "=",
+        AssignOp::AssignSignal => "<--",
+        AssignOp::AssignConstraintSignal => "<==",
+    }
+}
+
+fn infix_op(op: ExpressionInfixOpcode) -> &'static str {
+    use ExpressionInfixOpcode::*;
+    match op {
+        Mul => "*",
+        Div => "/",
+        Add => "+",
+        Sub => "-",
+        Pow => "**",
+        IntDiv => "\\",
+        Mod => "%",
+        ShiftL => "<<",
+        ShiftR => ">>",
+        LesserEq => "<=",
+        GreaterEq => ">=",
+        Lesser => "<",
+        Greater => ">",
+        Eq => "==",
+        NotEq => "!=",
+        BoolOr => "||",
+        BoolAnd => "&&",
+        BitOr => "|",
+        BitAnd => "&",
+        BitXor => "^",
+    }
+}
+
+fn prefix_op(op: ExpressionPrefixOpcode) -> &'static str {
+    match op {
+        ExpressionPrefixOpcode::Sub => "-",
+        ExpressionPrefixOpcode::BoolNot => "!",
+        ExpressionPrefixOpcode::Complement => "~",
+    }
+}
+
+fn signal_type(xtype: SignalType) -> &'static str {
+    match xtype {
+        SignalType::Input => "input",
+        SignalType::Output => "output",
+        SignalType::Intermediate => "intermediate",
+    }
+}
+
+fn reduces_to(meta: &Meta) -> Option<&'static str> {
+    let knowledge = meta.get_type_knowledge();
+    if !knowledge.is_initialized() {
+        return None;
+    }
+    let reduction = match knowledge.get_reduces_to() {
+        TypeReduction::Variable => "variable",
+        TypeReduction::Component(_) => "component",
+        TypeReduction::Bus(_) => "bus",
+        TypeReduction::Signal => "signal",
+        TypeReduction::Tag => "tag",
+    };
+    Some(reduction)
+}
+
+fn list(values: &[Expression]) -> String {
+    values.iter().map(expression_text).collect::<Vec<_>>().join(", ")
+}
+
+fn access_text(access: &[Access]) -> String {
+    access
+        .iter()
+        .map(|access| match access {
+            Access::ComponentAccess(name) => format!(".{}", name),
+            Access::ArrayAccess(index) => format!("[{}]", expression_text(index)),
+        })
+        .collect()
+}
+
+// the operands that are operations go between parentheses
+fn operand_text(expression: &Expression) -> String {
+    match expression {
+        Expression::InfixOp { .. } | Expression::InlineSwitchOp { .. } => format!("({})", expression_text(expression)),
+        _ => expression_text(expression),
+    }
+}
+
+fn log_text(args: &[LogArgument]) -> String {
+    let args: Vec<_> = args
+        .iter()
+        .map(|arg| match arg {
+            LogArgument::LogStr(text) => format!("{:?}", text),
+            LogArgument::LogExp(expression) => expression_text(expression),
+        })
+        .collect();
+    args.join(", ")
+}
+
+pub fn expression_text(expression: &Expression) -> String {
+    use Expression::*;
+    match expression {
+        InfixOp { lhe, infix_op: op, rhe, .. } => {
+            format!("{} {} {}", operand_text(lhe), infix_op(*op), operand_text(rhe))
+        }
+        PrefixOp { prefix_op: op, rhe, .. } => format!("{}{}", prefix_op(*op), operand_text(rhe)),
+        InlineSwitchOp { cond, if_true, if_false, .. } => {
+            format!("{} ? {} : {}", operand_text(cond), operand_text(if_true), operand_text(if_false))
+        }
+        ParallelOp { rhe, .. } => format!("parallel {}", expression_text(rhe)),
+        Variable { name, access, .. } => format!("{}{}", name, access_text(access)),
+        Number(_, value) => value.to_str_radix(10),
+        Call { id, args, .. } | BusCall { id, args, .. } => format!("{}({})", id, list(args)),
+        AnonymousComp { id, is_parallel, params, signals, names, .. } => {
+            let signals = match names {
+                Some(names) => {
+                    let signals = names.iter().zip(signals).map(|((op, name), signal)| {
+                        format!("{} {} {}", name, assign_op(*op), expression_text(signal))
+                    });
+                    signals.collect::<Vec<_>>().join(", ")
+                }
+                None => list(signals),
+            };
+            let parallel = if *is_parallel { "parallel " } else { "" };
+            format!("{}{}({})({})", parallel, id, list(params), signals)
+        }
+        ArrayInLine { values, .. } => format!("[{}]", list(values)),
+        Tuple { values, .. } => format!("({})", list(values)),
+        UniformArray { value, dimension, .. } => {
+            format!("[{}; {}]", expression_text(value), expression_text(dimension))
+        }
+    }
+}
+
+fn declaration_text(xtype: &VariableType, name: &str, dimensions: &[Expression]) -> String {
+    let tags = |tags: &TagList| if tags.is_empty() { String::new() } else { format!(" {{{}}}", tags.join(", ")) };
+    let kind = match xtype {
+        VariableType::Var => "var".to_string(),
+        VariableType::Component => "component".to_string(),
+        VariableType::AnonymousComponent => "anonymous component".to_string(),
+        VariableType::Signal(SignalType::Intermediate, list) => format!("signal{}", tags(list)),
+        VariableType::Signal(xtype, list) => format!("signal {}{}", signal_type(*xtype), tags(list)),
+        VariableType::Bus(bus, SignalType::Intermediate, list) => format!("{}{}", bus, tags(list)),
+        VariableType::Bus(bus, xtype, list) => format!("{} {}{}", signal_type(*xtype), bus, tags(list)),
+    };
+    let dimensions: String = dimensions.iter().map(|d| format!("[{}]", expression_text(d))).collect();
+    format!("{} {}{}", kind, name, dimensions)
+}
+
+struct Printer {
+    out: String,
+    depth: usize,
+}
+
+impl Printer {
+    fn line(&mut self, text: &str) {
+        self.out.push_str(&"    ".repeat(self.depth));
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn block(&mut self, head: &str, statement: &Statement) {
+        if head.is_empty() {
+            self.line("{");
+        } else {
+            self.line(&format!("{} {{", head));
+        }
+        self.depth += 1;
+        match statement {
+            Statement::Block { stmts, .. } => stmts.iter().for_each(|s| self.statement(s)),
+            _ => self.statement(statement),
+        }
+        self.depth -= 1;
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        use Statement::*;
+        match statement {
+            IfThenElse { cond, if_case, else_case, .. } => {
+                self.block(&format!("if ({})", expression_text(cond)), if_case);
+                if let Some(else_case) = else_case {
+                    self.block("} else", else_case);
+                }
+                self.line("}");
+            }
+            While { cond, stmt, .. } => {
+                self.block(&format!("while ({})", expression_text(cond)), stmt);
+                self.line("}");
+            }
+            Return { value, .. } => self.line(&format!("return {};", expression_text(value))),
+            InitializationBlock { initializations, .. } => initializations.iter().for_each(|s| self.statement(s)),
+            Declaration { xtype, name, dimensions, .. } => {
+                self.line(&format!("{};", declaration_text(xtype, name, dimensions)))
+            }
+            Substitution { var, access, op, rhe, .. } => {
+                self.line(&format!("{}{} {} {};", var, access_text(access), assign_op(*op), expression_text(rhe)))
+            }
+            MultSubstitution { lhe, op, rhe, .. } => {
+                self.line(&format!("{} {} {};", expression_text(lhe), assign_op(*op), expression_text(rhe)))
+            }
+            UnderscoreSubstitution { op, rhe, .. } => {
+                self.line(&format!("_ {} {};", assign_op(*op), expression_text(rhe)))
+            }
+            ConstraintEquality { lhe, rhe, .. } => {
+                self.line(&format!("{} === {};", expression_text(lhe), expression_text(rhe)))
+            }
+            LogCall { args, .. } => self.line(&format!("log({});", log_text(args))),
+            Block { stmts, .. } if stmts.is_empty() => self.line("{}"),
+            Block { .. } => {
+                self.block("", statement);
+                self.line("}");
+            }
+            Assert { arg, message, .. } => {
+                let message = if message.is_empty() { String::new() } else { format!(", {}", log_text(message)) };
+                self.line(&format!("assert({}{});", expression_text(arg), message))
+            }
+        }
+    }
+}
+
+fn lengths_text(lengths: &[usize]) -> String {
+    lengths.iter().map(|l| format!("[{}]", l)).collect()
+}
+
+fn arguments_text(arguments: &[Argument]) -> String {
+    let arguments: Vec<_> = arguments
+        .iter()
+        .map(|argument| {
+            let values: Vec<_> = argument.values.iter().map(|v| v.to_str_radix(10)).collect();
+            let value = if argument.lengths.is_empty() && values.len() == 1 {
+                values[0].clone()
+            } else {
+                format!("[{}]", values.join(", "))
+            };
+            format!("{} = {}", argument.name, value)
+        })
+        .collect();
+    arguments.join(", ")
+}
+
+fn wire_text(wire: &Wire, buses: &[BusInstance]) -> String {
+    match wire {
+        Wire::TSignal(signal) => format!("signal {} {}{}", signal_type(signal.xtype), signal.name, lengths_text(&signal.lengths)),
+        Wire::TBus(bus) => {
+            let name = buses.get(bus.bus_id).map_or("bus", |bus| bus.name.as_str());
+            format!("{} {} {}{}", signal_type(bus.xtype), name, bus.name, lengths_text(&bus.lengths))
+        }
+    }
+}
+
+pub fn hir_text(vcp: &VCP, version: &str) -> String {
+    let mut printer = Printer { out: String::new(), depth: 0 };
+    printer.line(&format!("// HIR of the circuit, written by circom {}", version));
+    for template in &vcp.templates {
+        let parallel = if template.is_parallel { "parallel " } else { "" };
+        printer.line("");
+        printer.line(&format!(
+            "template {}{} = {}({}) {{",
+            parallel,
+            template.template_header,
+            template.template_name,
+            arguments_text(&template.header)
+        ));
+        printer.depth += 1;
+        for wire in &template.wires {
+            printer.line(&format!("// {}", wire_text(wire, &vcp.buses)));
+        }
+        for trigger in &template.triggers {
+            let indexes: String = trigger.indexed_with.iter().map(|i| format!("[{}]", i)).collect();
+            let header = &vcp.templates[trigger.template_id].template_header;
+            printer.line(&format!("// component {}{} = {}", trigger.component_name, indexes, header));
+        }
+        let mut tags: Vec<_> = template.signals_to_tags.iter().collect();
+        tags.sort();
+        for (path, value) in tags {
+            printer.line(&format!("// tag {} = {}", path.join("."), value.to_str_radix(10)));
+        }
+        match &template.code {
+            Statement::Block { stmts, .. } => stmts.iter().for_each(|s| printer.statement(s)),
+            code => printer.statement(code),
+        }
+        printer.depth -= 1;
+        printer.line("}");
+    }
+    for function in &vcp.functions {
+        let params: Vec<_> = function
+            .params_types
+            .iter()
+            .map(|param| format!("{}{}", param.name, lengths_text(&param.length)))
+            .collect();
+        let returns = lengths_text(&function.return_type);
+        let returns = if returns.is_empty() { String::new() } else { format!(" -> {}", returns) };
+        let head = format!("function {} = {}({}){}", function.header, function.name, params.join(", "), returns);
+        printer.line("");
+        if function.is_extern {
+            printer.line(&format!("extern {};", head));
+        } else {
+            printer.block(&head, &function.body);
+            printer.line("}");
+        }
+    }
+    printer.out
+}
+
+struct JsonWriter<'a> {
+    files: &'a FileLibrary,
+}
+
+impl<'a> JsonWriter<'a> {
+    fn line(&self, meta: &Meta) -> Value {
+        meta.file_id.and_then(|file_id| self.files.get_line(meta.get_start(), file_id)).into()
+    }
+
+    fn file(&self, meta: &Meta) -> Value {
+        let file = meta.file_id.and_then(|file_id| self.files.get_file_name(file_id));
+        file.map(|name| name.trim_matches('"')).into()
+    }
+
+    fn expressions(&self, expressions: &[Expression]) -> Value {
+        expressions.iter().map(|e| self.expression(e)).collect::<Vec<_>>().into()
+    }
+
+    fn access(&self, access: &[Access]) -> Value {
+        let access: Vec<_> = access
+            .iter()
+            .map(|access| match access {
+                Access::ComponentAccess(name) => json!({ "field": name }),
+                Access::ArrayAccess(index) => json!({ "index": self.expression(index) }),
+            })
+            .collect();
+        access.into()
+    }
+
+    fn log(&self, args: &[LogArgument]) -> Value {
+        let args: Vec<_> = args
+            .iter()
+            .map(|arg| match arg {
+                LogArgument::LogStr(text) => json!({ "string": text }),
+                LogArgument::LogExp(expression) => json!({ "expression": self.expression(expression) }),
+            })
+            .collect();
+        args.into()
+    }
+
+    fn expression(&self, expression: &Expression) -> Value {
+        use Expression::*;
+        let meta = expression.get_meta();
+        let mut value = match expression {
+            InfixOp { lhe, infix_op: op, rhe, .. } => json!({
+                "kind": "infix",
+                "op": infix_op(*op),
+                "lhe": self.expression(lhe),
+                "rhe": self.expression(rhe),
+            }),
+            PrefixOp { prefix_op: op, rhe, .. } => json!({
+                "kind": "prefix",
+                "op": prefix_op(*op),
+                "rhe": self.expression(rhe),
+            }),
+            InlineSwitchOp { cond, if_true, if_false, .. } => json!({
+                "kind": "switch",
+                "cond": self.expression(cond),
+                "if_true": self.expression(if_true),
+                "if_false": self.expression(if_false),
+            }),
+            ParallelOp { rhe, .. } => json!({ "kind": "parallel", "rhe": self.expression(rhe) }),
+            Variable { name, access, .. } => json!({
+                "kind": "variable",
+                "name": name,
+                "access": self.access(access),
+                "type": reduces_to(meta),
+            }),
+            Number(_, value) => json!({ "kind": "number", "value": value.to_str_radix(10) }),
+            Call { id, args, .. } => json!({ "kind": "call", "id": id, "args": self.expressions(args) }),
+            BusCall { id, args, .. } => json!({ "kind": "bus", "id": id, "args": self.expressions(args) }),
+            AnonymousComp { id, is_parallel, params, signals, names, .. } => {
+                let names: Option<Vec<_>> =
+                    names.as_ref().map(|names| names.iter().map(|(op, name)| json!([assign_op(*op), name])).collect());
+                json!({
+                    "kind": "anonymous_component",
+                    "id": id,
+                    "parallel": is_parallel,
+                    "params": self.expressions(params),
+                    "signals": self.expressions(signals),
+                    "names": names,
+                })
+            }
+            ArrayInLine { values, .. } => json!({ "kind": "array", "values": self.expressions(values) }),
+            Tuple { values, .. } => json!({ "kind": "tuple", "values": self.expressions(values) }),
+            UniformArray { value, dimension, .. } => json!({
+                "kind": "uniform_array",
+                "value": self.expression(value),
+                "dimension": self.expression(dimension),
+            }),
+        };
+        value["line"] = self.line(meta);
+        value
+    }
+
+    fn statements(&self, statements: &[Statement]) -> Value {
+        statements.iter().map(|s| self.statement(s)).collect::<Vec<_>>().into()
+    }
+
+    fn statement(&self, statement: &Statement) -> Value {
+        use Statement::*;
+        let mut value = match statement {
+            IfThenElse { cond, if_case, else_case, .. } => json!({
+                "kind": "if",
+                "cond": self.expression(cond),
+                "if_case": self.statement(if_case),
+                "else_case": else_case.as_ref().map(|s| self.statement(s)),
+            }),
+            While { cond, stmt, .. } => json!({
+                "kind": "while",
+                "cond": self.expression(cond),
+                "stmt": self.statement(stmt),
+            }),
+            Return { value, .. } => json!({ "kind": "return", "value": self.expression(value) }),
+            InitializationBlock { initializations, .. } => json!({
+                "kind": "initialization_block",
+                "initializations": self.statements(initializations),
+            }),
+            Declaration { xtype, name, dimensions, is_constant, .. } => {
+                let (kind, bus, signal_kind, tags) = match xtype {
+                    VariableType::Var => ("var", None, None, None),
+                    VariableType::Component => ("component", None, None, None),
+                    VariableType::AnonymousComponent => ("anonymous_component", None, None, None),
+                    VariableType::Signal(xtype, tags) => ("signal", None, Some(signal_type(*xtype)), Some(tags)),
+                    VariableType::Bus(bus, xtype, tags) => ("bus", Some(bus), Some(signal_type(*xtype)), Some(tags)),
+                };
+                json!({
+                    "kind": "declaration",
+                    "type": kind,
+                    "bus": bus,
+                    "signal_type": signal_kind,
+                    "tags": tags,
+                    "name": name,
+                    "dimensions": self.expressions(dimensions),
+                    "constant": is_constant,
+                })
+            }
+            Substitution { var, access, op, rhe, .. } => json!({
+                "kind": "substitution",
+                "var": var,
+                "access": self.access(access),
+                "op": assign_op(*op),
+                "rhe": self.expression(rhe),
+            }),
+            MultSubstitution { lhe, op, rhe, .. } => json!({
+                "kind": "mult_substitution",
+                "lhe": self.expression(lhe),
+                "op": assign_op(*op),
+                "rhe": self.expression(rhe),
+            }),
+            UnderscoreSubstitution { op, rhe, .. } => json!({
+                "kind": "underscore_substitution",
+                "op": assign_op(*op),
+                "rhe": self.expression(rhe),
+            }),
+            ConstraintEquality { lhe, rhe, .. } => json!({
+                "kind": "constraint_equality",
+                "lhe": self.expression(lhe),
+                "rhe": self.expression(rhe),
+            }),
+            LogCall { args, .. } => json!({ "kind": "log", "args": self.log(args) }),
+            Block { stmts, .. } => json!({ "kind": "block", "stmts": self.statements(stmts) }),
+            Assert { arg, message, is_static, .. } => json!({
+                "kind": "assert",
+                "arg": self.expression(arg),
+                "message": self.log(message),
+                "static": is_static,
+            }),
+        };
+        value["line"] = self.line(statement.get_meta());
+        value
+    }
+
+    fn template(&self, template: &TemplateInstance, vcp: &VCP) -> Value {
+        let arguments: Vec<_> = template
+            .header
+            .iter()
+            .map(|argument| {
+                let values: Vec<_> = argument.values.iter().map(|v| v.to_str_radix(10)).collect();
+                json!({ "name": argument.name, "values": values, "lengths": argument.lengths })
+            })
+            .collect();
+        let wires: Vec<_> = template
+            .wires
+            .iter()
+            .map(|wire| {
+                let bus = match wire {
+                    Wire::TBus(bus) => vcp.buses.get(bus.bus_id).map(|bus| bus.name.clone()),
+                    Wire::TSignal(_) => None,
+                };
+                json!({
+                    "name": wire.name(),
+                    "type": signal_type(wire.xtype()),
+                    "lengths": wire.lengths(),
+                    "bus": bus,
+                })
+            })
+            .collect();
+        let components: Vec<_> = template
+            .triggers
+            .iter()
+            .map(|trigger| {
+                json!({
+                    "name": trigger.component_name,
+                    "indexes": trigger.indexed_with,
+                    "instance": vcp.templates[trigger.template_id].template_header,
+                    "parallel": trigger.is_parallel,
+                })
+            })
+            .collect();
+        let mut tags: Vec<_> = template.signals_to_tags.iter().collect();
+        tags.sort();
+        let tags: Vec<_> =
+            tags.iter().map(|(path, value)| json!({ "path": path, "value": value.to_str_radix(10) })).collect();
+        json!({
+            "header": template.template_header,
+            "template": template.template_name,
+            "file": self.file(template.code.get_meta()),
+            "parallel": template.is_parallel,
+            "arguments": arguments,
+            "signals": wires,
+            "components": components,
+            "tags": tags,
+            "body": self.statement(&template.code),
+        })
+    }
+
+    fn function(&self, function: &VCF) -> Value {
+        let params: Vec<_> =
+            function.params_types.iter().map(|param| json!({ "name": param.name, "lengths": param.length })).collect();
+        json!({
+            "header": function.header,
+            "function": function.name,
+            "file": self.file(function.body.get_meta()),
+            "params": params,
+            "returns": function.return_type,
+            "extern": function.is_extern,
+            "body": self.statement(&function.body),
+        })
+    }
+}
+
+pub fn hir_json(vcp: &VCP) -> Value {
+    let writer = JsonWriter { files: &vcp.file_library };
+    let templates: Vec<_> = vcp.templates.iter().map(|template| writer.template(template, vcp)).collect();
+    let functions: Vec<_> = vcp.functions.iter().map(|function| writer.function(function)).collect();
+    json!({
+        "prime": vcp.prime,
+        "main": vcp.get_main_instance().map(|main| main.template_header.clone()),
+        "templates": templates,
+        "functions": functions,
+        "buses": serde_json::to_value(&vcp.buses).unwrap(),
+    })
+}
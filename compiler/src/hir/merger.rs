@@ -3,6 +3,7 @@ use super::component_preprocess;
 use super::sugar_cleaner;
 use super::very_concrete_program::*;
 use program_structure::ast::*;
+use program_structure::builtin_calls::{unroll_builtin_call, BuiltinCall};
 use program_structure::program_archive::ProgramArchive;
 use num_traits::ToPrimitive;
 
@@ -60,12 +61,17 @@ fn add_instance(name: &str, args: Vec<Param>, state: &mut State) {
         let inferred = infer_function_result(name, args.clone(), state);
         let id = state.vcf_collector.len();
         let body = state.generic_functions.get(name).unwrap().body.clone();
+        let is_extern = state.generic_functions.get(name).unwrap().is_extern;
+        // the aliases of the included files are not valid in the names of the
+        // generated code
+        let header_name = name.replace('.', "_");
         let new_vcf = VCF {
             name: name.to_string(),
-            header: format!("{}_{}", name, state.vcf_collector.len()),
+            header: format!("{}_{}", header_name, state.vcf_collector.len()),
             params_types: args.to_vec(),
             return_type: inferred,
             body,
+            is_extern,
         };
         state.quick_knowledge.insert(new_vcf.header.clone(), new_vcf.return_type.clone());
         state.vcf_collector.push(new_vcf);
@@ -119,8 +125,9 @@ fn produce_vcf_stmt(stmt: &Statement, state: &mut State, environment: &mut E) {
 }
 
 fn produce_vcf_expr(expr: &Expression, state: &mut State, environment: &E) {
-
-    if expr.is_infix() {
+    if let Some(unrolled) = unroll_builtin(expr, state, environment) {
+        produce_vcf_expr(&unrolled, state, environment);
+    } else if expr.is_infix() {
         produce_vcf_infix(expr, state, environment);
     } else if expr.is_prefix() {
         produce_vcf_prefix(expr, state, environment);
@@ -154,8 +161,13 @@ fn produce_vcf_return(stmt: &Statement, state: &mut State, environment: &E) {
 
 fn produce_vcf_assert(stmt: &Statement, state: &mut State, environment: &E) {
     use Statement::Assert;
-    if let Assert { arg, .. } = stmt {
+    if let Assert { arg, message, .. } = stmt {
         produce_vcf_expr(arg, state, environment);
+        for arglog in message {
+            if let LogArgument::LogExp(arg) = arglog {
+                produce_vcf_expr(arg, state, environment);
+            }
+        }
     } else {
         unreachable!();
     }
@@ -447,8 +459,13 @@ fn link_log_call(stmt: &mut Statement, state: &State, env: &mut E) {
 
 fn link_assert(stmt: &mut Statement, state: &State, env: &mut E) {
     use Statement::Assert;
-    if let Assert { arg, .. } = stmt {
+    if let Assert { arg, message, .. } = stmt {
         link_expression(arg, state, env);
+        for arglog in message {
+            if let LogArgument::LogExp(arg) = arglog {
+                link_expression(arg, state, env);
+            }
+        }
     } else {
         unreachable!();
     }
@@ -499,6 +516,9 @@ fn link_substitution(stmt: &mut Statement, state: &State, env: &mut E) {
 }
 
 fn link_expression(expr: &mut Expression, state: &State, env: &E) {
+    if let Some(unrolled) = unroll_builtin(expr, state, env) {
+        *expr = unrolled;
+    }
     if expr.is_call() {
         link_call(expr, state, env);
     } else if expr.is_array() {
@@ -727,7 +747,9 @@ fn cast_type_array(expr: &Expression, state: &State, environment: &E) -> VCT {
 
 fn cast_type_call(expr: &Expression, state: &State, environment: &E) -> VCT {
     use Expression::Call;
-    if let Call { id, args, .. } = expr {
+    if let Some(unrolled) = unroll_builtin(expr, state, environment) {
+        cast_type_expression(&unrolled, state, environment)
+    } else if let Call { id, args, .. } = expr {
         if let Option::Some(returns) = state.quick_knowledge.get(id) {
             returns.clone()
         } else if !state.generic_functions.contains_key(id) {
@@ -741,6 +763,18 @@ fn cast_type_call(expr: &Expression, state: &State, environment: &E) -> VCT {
     }
 }
 
+// The calls to map, fold and sum are unrolled once the lengths of their
+// arrays are known
+fn unroll_builtin(expr: &Expression, state: &State, environment: &E) -> Option<Expression> {
+    match expr {
+        Expression::Call { meta, id, args } if BuiltinCall::from_id(id).is_some() => {
+            let mut length = |array: &Expression| cast_type_expression(array, state, environment)[0];
+            Some(unroll_builtin_call(meta, id, args, &mut length))
+        }
+        _ => None,
+    }
+}
+
 fn cast_type_switch(expr: &Expression, state: &State, environment: &E) -> VCT {
     use Expression::InlineSwitchOp;
     if let InlineSwitchOp { if_true, .. } = expr {
@@ -3,4 +3,5 @@ mod component_preprocess;
 mod merger;
 mod sugar_cleaner;
 mod type_inference;
+pub mod hir_export;
 pub mod very_concrete_program;
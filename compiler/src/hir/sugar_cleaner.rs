@@ -207,8 +207,15 @@ fn extend_log_call(stmt: &mut Statement, state: &mut State, context: &Context) -
 
 fn extend_assert(stmt: &mut Statement, state: &mut State, context: &Context) -> Vec<Statement> {
     use Statement::Assert;
-    if let Assert { arg, .. } = stmt {
-        extend_expression(arg, state, context).initializations
+    if let Assert { arg, message, .. } = stmt {
+        let mut initializations = extend_expression(arg, state, context).initializations;
+        for arglog in message {
+            if let LogArgument::LogExp(arg) = arglog {
+                let mut exp = extend_expression(arg, state, context);
+                initializations.append(&mut exp.initializations);
+            }
+        }
+        initializations
     } else {
         unreachable!()
     }
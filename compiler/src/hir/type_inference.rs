@@ -1,6 +1,7 @@
 use super::analysis_utilities::*;
 use super::very_concrete_program::*;
 use program_structure::ast::*;
+use program_structure::builtin_calls::{unroll_builtin_call, BuiltinCall};
 use std::collections::HashSet;
 use num_traits::ToPrimitive;
 
@@ -235,20 +236,26 @@ fn infer_type_array(expr: &Expression, state: &State, context: &mut SearchInfo)
 
 fn infer_type_call(expr: &Expression, state: &State, context: &mut SearchInfo) -> Option<VCT> {
     use Expression::Call;
-    if let Call { id, args, .. } = expr {
-        if context.open_calls.contains(id) {
+    if let Call { meta, id, args } = expr {
+        if BuiltinCall::from_id(id).is_some() {
+            let mut length = |array: &Expression| infer_type_expresion(array, state, context).map_or(0, |t| t[0]);
+            let unrolled = unroll_builtin_call(meta, id, args, &mut length);
+            infer_type_expresion(&unrolled, state, context)
+        } else if context.open_calls.contains(id) {
             Option::None
         } else {
-            context.open_calls.insert(id.clone());
-            context.environment.add_variable_block();
-            let mut index = 0;
-            let body = &state.generic_functions.get(id).unwrap().body;
-            let names = &state.generic_functions.get(id).unwrap().params_names;
+            // the arguments are inferred before the call is open, as they
+            // can call the same function
             let arg_types = infer_args(args, state, context);
             if arg_types.is_none() {
                 return Option::None;
             }
             let arg_types = arg_types.unwrap();
+            context.open_calls.insert(id.clone());
+            context.environment.add_variable_block();
+            let mut index = 0;
+            let body = &state.generic_functions.get(id).unwrap().body;
+            let names = &state.generic_functions.get(id).unwrap().params_names;
             for arg_type in arg_types {
                 context.environment.add_variable(&names[index], arg_type);
                 index += 1;
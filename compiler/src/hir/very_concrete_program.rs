@@ -2,6 +2,7 @@ use num_bigint_dig::BigInt;
 use program_structure::ast::{SignalType, Statement};
 use program_structure::program_archive::ProgramArchive;
 use program_structure::program_library::file_definition::FileLibrary;
+use serde_derive::Serialize;
 use std::collections::{BTreeMap, HashMap};
 use std::ops::Range;
 use std::rc::Rc;
@@ -12,7 +13,7 @@ pub type Code = Statement;
 
 pub type TagInfo = BTreeMap<String, Option<BigInt>>;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub struct Argument {
     pub name: String,
     pub values: Vec<BigInt>,
@@ -24,7 +25,7 @@ impl PartialEq for Argument {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub enum Wire{
     TSignal(Signal),
     TBus(Bus)
@@ -103,7 +104,7 @@ impl Wire {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub struct Signal {
     pub name: String,
     pub lengths: Vec<Length>,
@@ -114,7 +115,7 @@ pub struct Signal {
 }
 
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub struct Bus{
     pub name: String,
     pub lengths: Vec<Length>,
@@ -125,7 +126,7 @@ pub struct Bus{
     pub size: usize,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct FieldInfo{
     pub field_id: usize,
     pub offset: usize,
@@ -136,14 +137,14 @@ pub struct FieldInfo{
 
 
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct BusInstance{
     pub name: String,
     pub size: usize,
     pub fields: BTreeMap<String, FieldInfo>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub struct Component {
     pub name: String,
     pub lengths: Vec<Length>,
@@ -155,7 +156,7 @@ impl Component {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub struct Trigger {
     pub runs: String,
     pub offset: usize,
@@ -168,12 +169,12 @@ pub struct Trigger {
     pub is_parallel: bool,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub enum ClusterType {
     Mixed { tmp_name: String },
     Uniform { offset_jump: usize, component_offset_jump:usize, instance_id: usize, header: String },
 }
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub struct TriggerCluster {
     pub cmp_name: String,
     pub slice: Range<usize>,
@@ -273,6 +274,7 @@ pub struct VCF {
     pub params_types: Vec<Param>,
     pub return_type: VCT,
     pub body: Statement,
+    pub is_extern: bool,
 }
 
 #[derive(Clone)]
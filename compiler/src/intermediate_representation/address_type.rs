@@ -1,13 +1,14 @@
 use super::ir_interface::*;
+use serde_derive::{Deserialize, Serialize};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum StatusInput {
     Last,
     NoLast,
     Unknown,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum InputInformation {
     NoInput,
     Input {status: StatusInput},
@@ -29,7 +30,7 @@ impl ToString for InputInformation {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum AddressType {
     Variable,
     Signal,
@@ -0,0 +1,148 @@
+use super::ir_interface::*;
+use crate::translating_traits::*;
+use code_producers::c_elements::*;
+use code_producers::wasm_elements::*;
+use serde_derive::{Deserialize, Serialize};
+
+// Copies size consecutive elements of the variables or signals of the
+// component as a single move. Subcomponent signals are always stored with
+// a StoreBucket since their stores may run the subcomponent
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ArrayCopyBucket {
+    pub line: usize,
+    pub message_id: usize,
+    pub size: usize,
+    pub dest_is_output: bool,
+    pub dest_address_type: AddressType,
+    pub dest: InstructionPointer,
+    pub src_address_type: AddressType,
+    pub src: InstructionPointer,
+}
+
+impl IntoInstruction for ArrayCopyBucket {
+    fn into_instruction(self) -> Instruction {
+        Instruction::ArrayCopy(self)
+    }
+}
+
+impl Allocate for ArrayCopyBucket {
+    fn allocate(self) -> InstructionPointer {
+        InstructionPointer::new(self.into_instruction())
+    }
+}
+
+impl ObtainMeta for ArrayCopyBucket {
+    fn get_line(&self) -> usize {
+        self.line
+    }
+    fn get_message_id(&self) -> usize {
+        self.message_id
+    }
+}
+
+impl std::fmt::Display for ArrayCopyBucket {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let line = self.line.to_string();
+        let template_id = self.message_id.to_string();
+        let dest_type = self.dest_address_type.to_string();
+        let dest = self.dest.to_string();
+        let src_type = self.src_address_type.to_string();
+        let src = self.src.to_string();
+        write!(
+            f,
+            "ARRAY_COPY(line:{},template_id:{},size:{},dest_type:{},dest:{},src_type:{},src:{})",
+            line, template_id, self.size, dest_type, dest, src_type, src
+        )
+    }
+}
+
+fn address_wasm(xtype: &AddressType, location: &Instruction, producer: &WASMProducer) -> Vec<String> {
+    use code_producers::wasm_elements::wasm_code_generator::*;
+    let mut instructions = location.produce_wasm(producer);
+    let size = producer.get_size_32_bits_in_memory() * 4;
+    instructions.push(set_constant(&size.to_string()));
+    instructions.push(mul32());
+    match xtype {
+        AddressType::Variable => instructions.push(get_local(producer.get_lvar_tag())),
+        AddressType::Signal => instructions.push(get_local(producer.get_signal_start_tag())),
+        AddressType::SubcmpSignal { .. } => unreachable!(),
+    }
+    instructions.push(add32());
+    instructions
+}
+
+impl WriteWasm for ArrayCopyBucket {
+    fn produce_wasm(&self, producer: &WASMProducer) -> Vec<String> {
+        use code_producers::wasm_elements::wasm_code_generator::*;
+        let mut instructions = vec![];
+        if producer.needs_comments() {
+            instructions.push(format!(";; array copy bucket. Line {}", self.line));
+        }
+        let traces_signals = producer.traces_signals() && matches!(self.dest_address_type, AddressType::Signal);
+        let mut instructions_dest = address_wasm(&self.dest_address_type, &self.dest, producer);
+        instructions.append(&mut instructions_dest);
+        if traces_signals {
+            instructions.push(tee_local(producer.get_trace_address_tag()));
+        }
+        let mut instructions_src = address_wasm(&self.src_address_type, &self.src, producer);
+        instructions.append(&mut instructions_src);
+        instructions.push(set_constant(&self.size.to_string()));
+        instructions.push(call("$Fr_copyn"));
+        if traces_signals {
+            instructions.push(get_local(producer.get_trace_address_tag()));
+            instructions.push(set_constant(&self.size.to_string()));
+            instructions.push(set_constant(&self.line.to_string()));
+            instructions.push(call("$traceSignals"));
+        }
+        if producer.needs_comments() {
+            instructions.push(";; end of array copy bucket".to_string());
+        }
+        instructions
+    }
+}
+
+fn address_c(xtype: &AddressType, index: String) -> String {
+    use c_code_generator::*;
+    match xtype {
+        AddressType::Variable => format!("&{}", lvar(index)),
+        AddressType::Signal => format!("&{}", signal_values(index)),
+        AddressType::SubcmpSignal { .. } => unreachable!(),
+    }
+}
+
+impl WriteC for ArrayCopyBucket {
+    fn produce_c(&self, producer: &CProducer, parallel: Option<bool>) -> (Vec<String>, String) {
+        use c_code_generator::*;
+        let mut prologue = vec![];
+        let (mut dest_prologue, dest_index) = self.dest.produce_c(producer, parallel);
+        prologue.append(&mut dest_prologue);
+        let (mut src_prologue, src_index) = self.src.produce_c(producer, parallel);
+        prologue.append(&mut src_prologue);
+        let copy_arguments = vec![
+            address_c(&self.dest_address_type, dest_index.clone()),
+            address_c(&self.src_address_type, src_index),
+            self.size.to_string(),
+        ];
+        prologue.push(format!("{};", build_call("Fr_copyn".to_string(), copy_arguments)));
+        if let AddressType::Signal = &self.dest_address_type {
+            if producer.traces_signals() {
+                let trace_arguments = vec![
+                    CIRCOM_CALC_WIT.to_string(),
+                    format!("{} + {}", my_signal_start(), dest_index),
+                    self.size.to_string(),
+                    self.line.to_string(),
+                ];
+                prologue.push(format!("{};", build_call("traceSignals".to_string(), trace_arguments)));
+            }
+            if parallel.unwrap() && self.dest_is_output {
+                prologue.push(format!("for (int i = 0; i < {}; i++) {{", self.size));
+                prologue.push(format!("{}->componentMemory[{}].mutexes[{}+i].lock();", CIRCOM_CALC_WIT, CTX_INDEX, dest_index));
+                prologue.push(format!("{}->componentMemory[{}].outputIsSet[{}+i]=true;", CIRCOM_CALC_WIT, CTX_INDEX, dest_index));
+                prologue.push(format!("{}->componentMemory[{}].mutexes[{}+i].unlock();", CIRCOM_CALC_WIT, CTX_INDEX, dest_index));
+                prologue.push(format!("{}->componentMemory[{}].cvs[{}+i].notify_all();", CIRCOM_CALC_WIT, CTX_INDEX, dest_index));
+                prologue.push("}".to_string());
+            }
+        }
+        (prologue, "".to_string())
+    }
+}
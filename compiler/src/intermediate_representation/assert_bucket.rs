@@ -2,12 +2,28 @@ use super::ir_interface::*;
 use crate::translating_traits::*;
 use code_producers::c_elements::*;
 use code_producers::wasm_elements::*;
+use serde_derive::{Deserialize, Serialize};
 
-#[derive(Clone)]
+// A part of the message shown when the assertion fails
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum AssertMessagePart {
+    // an id of the string table
+    Str(usize),
+    // the position of the value in the values of the assertion
+    Value(usize),
+}
+
+// When the assertion fails its message is shown in a line, like a log, along
+// with the line of the assertion
+#[derive(Clone, Serialize, Deserialize)]
 pub struct AssertBucket {
     pub line: usize,
     pub message_id: usize,
     pub evaluate: InstructionPointer,
+    pub message: Vec<AssertMessagePart>,
+    pub values: InstructionList,
+    // the assertion checks a constraint given with === instead of an assert
+    pub is_constraint: bool,
 }
 
 impl IntoInstruction for AssertBucket {
@@ -36,7 +52,21 @@ impl ToString for AssertBucket {
         let line = self.line.to_string();
         let template_id = self.message_id.to_string();
         let evaluate = self.evaluate.to_string();
-        format!("ASSERT(line: {},template_id: {},evaluate: {})", line, template_id, evaluate)
+        let mut message = "".to_string();
+        for part in &self.message {
+            match part {
+                AssertMessagePart::Str(id) => message = format!("{}STR({});", message, id),
+                AssertMessagePart::Value(index) => message = format!("{}VALUE({});", message, index),
+            }
+        }
+        let mut values = "".to_string();
+        for v in &self.values {
+            values = format!("{}{};", values, v.to_string());
+        }
+        format!(
+            "ASSERT(line: {},template_id: {},evaluate: {},message: {},values: {})",
+            line, template_id, evaluate, message, values
+        )
     }
 }
 
@@ -56,6 +86,32 @@ impl WriteWasm for AssertBucket {
         instructions.push(set_constant(&self.line.to_string()));
         instructions.push(call("$buildBufferMessage"));
         instructions.push(call("$printErrorMessage"));
+        if !self.message.is_empty() {
+            // the message is logged in a line before the error is raised
+            for part in &self.message {
+                match part {
+                    AssertMessagePart::Str(id) => {
+                        let pos = producer.get_string_list_start() + id * producer.get_size_of_message_in_bytes();
+                        instructions.push(set_constant(&pos.to_string()));
+                        instructions.push(call("$buildLogMessage"));
+                        instructions.push(call("$writeBufferMessage"));
+                    }
+                    AssertMessagePart::Value(index) => {
+                        let mut instructions_value = self.values[*index].produce_wasm(producer);
+                        instructions.append(&mut instructions_value);
+                        instructions.push(call("$copyFr2SharedRWMemory"));
+                        instructions.push(call("$showSharedRWMemory"));
+                    }
+                }
+            }
+            instructions.push(set_constant(&producer.get_message_buffer_start().to_string()));
+            instructions.push(set_constant("0x0000000a"));
+            instructions.push(store32(None)); // stores \n000
+            instructions.push(set_constant(&producer.get_message_buffer_counter_position().to_string()));
+            instructions.push(set_constant("0"));
+            instructions.push(store32(None));
+            instructions.push(call("$writeBufferMessage"));
+        }
         instructions.push(set_constant(&exception_code_assert_fail().to_string()));
         instructions.push(add_return());
         instructions.push(add_end());
@@ -70,14 +126,39 @@ impl WriteC for AssertBucket {
     fn produce_c(&self, producer: &CProducer, parallel: Option<bool>) -> (Vec<String>, String) {
         use c_code_generator::*;
         let (mut prologue, value) = self.evaluate.produce_c(producer, parallel);
-        let is_true = build_call("Fr_isTrue".to_string(), vec![value]);
-        let if_condition = format!("if (!{}) {};", is_true, build_failed_assert_message(self.line));    
-        let assertion = format!("{};", build_call("assert".to_string(), vec![is_true]));
+        let mut is_true = build_call("Fr_isTrue".to_string(), vec![value]);
         let mut assert_c = vec![];
         assert_c.push(format!("{{"));
         assert_c.append(&mut prologue);
-        assert_c.push(if_condition);
-        assert_c.push(assertion);
+        if self.message.is_empty() {
+            assert_c.push(format!("if (!{}) {};", is_true, build_failed_assert_message(self.line)));
+        } else {
+            // the values may reuse the auxiliary slot of the condition
+            assert_c.push(format!("bool assert_holds = {};", is_true));
+            is_true = "assert_holds".to_string();
+            let mut payload = vec![format!("{};", build_failed_assert_message(self.line))];
+            for (i, part) in self.message.iter().enumerate() {
+                let separator = if i + 1 < self.message.len() { " << \" \"" } else { "" };
+                match part {
+                    AssertMessagePart::Str(id) => {
+                        let message = &producer.get_string_table()[*id];
+                        payload.push(format!("std::cout << \"{}\"{};", message, separator));
+                    }
+                    AssertMessagePart::Value(index) => {
+                        let (mut value_code, value) = self.values[*index].produce_c(producer, parallel);
+                        payload.append(&mut value_code);
+                        payload.push("{".to_string());
+                        payload.push(format!("char* temp = {};", build_call("Fr_element2str".to_string(), vec![value])));
+                        payload.push(format!("std::cout << temp{};", separator));
+                        payload.push("delete [] temp;".to_string());
+                        payload.push("}".to_string());
+                    }
+                }
+            }
+            payload.push("std::cout << std::endl;".to_string());
+            assert_c.push(format!("if (!{}) {{\n{}}}", is_true, merge_code(payload)));
+        }
+        assert_c.push(format!("{};", build_call("assert".to_string(), vec![is_true])));
         assert_c.push(format!("}}"));
         (assert_c, "".to_string())
     }
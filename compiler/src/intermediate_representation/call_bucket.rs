@@ -2,8 +2,9 @@ use super::ir_interface::*;
 use crate::translating_traits::*;
 use code_producers::c_elements::*;
 use code_producers::wasm_elements::*;
+use serde_derive::{Deserialize, Serialize};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct FinalData {
     // greater than one only with signals.
     pub context: InstrContext,
@@ -12,13 +13,13 @@ pub struct FinalData {
     pub dest: LocationRule,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum ReturnType {
     Intermediate { op_aux_no: usize },
     Final(FinalData),
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CallBucket {
     pub line: usize,
     pub message_id: usize,
@@ -367,15 +368,7 @@ impl WriteWasm for CallBucket {
 		    }
 		};
                 instructions.push(call(&format!("${}", self.symbol)));
-                instructions.push(tee_local(producer.get_merror_tag()));
-		instructions.push(add_if());
-                instructions.push(set_constant(&self.message_id.to_string()));
-                instructions.push(set_constant(&self.line.to_string()));
-                instructions.push(call("$buildBufferMessage"));
-                instructions.push(call("$printErrorMessage"));
-		instructions.push(get_local(producer.get_merror_tag()));    
-                instructions.push(add_return());
-                instructions.push(add_end());
+                instructions.append(&mut check_error(producer, self.message_id, self.line));
                 match &data.dest_address_type {
                     AddressType::SubcmpSignal { .. } => {
                         // if subcomponent input check if run needed
@@ -399,58 +392,48 @@ impl WriteWasm for CallBucket {
                         instructions.push(store32(Some(
                             &producer.get_input_counter_address_in_component().to_string(),
                         ))); // update remaining inputs to be set
+                        if producer.defers_components() {
+                            if producer.needs_comments() {
+                                instructions.push(";; run when one of its outputs is read".to_string());
+                            }
+                        } else {
 			if producer.needs_comments() {
-                            instructions.push(";; check if run is needed".to_string());
+                                instructions.push(";; check if run is needed".to_string());
 			}
-                        instructions.push(get_local(producer.get_sub_cmp_tag()));
-                        instructions.push(load32(Some(
-                            &producer.get_input_counter_address_in_component().to_string(),
-                        )));
-                        instructions.push(eqz32());
-                        instructions.push(add_if());
+                            instructions.push(get_local(producer.get_sub_cmp_tag()));
+                            instructions.push(load32(Some(
+                                &producer.get_input_counter_address_in_component().to_string(),
+                            )));
+                            instructions.push(eqz32());
+                            instructions.push(add_if());
 			if producer.needs_comments() {
-                            instructions.push(";; run sub component".to_string());
+                                instructions.push(";; run sub component".to_string());
 			}
-                        instructions.push(get_local(producer.get_sub_cmp_tag()));
-                        match &data.dest {
-                            LocationRule::Indexed { .. } => {
-                                if let Some(name) = &my_template_header {
-                                    instructions.push(call(&format!("${}_run", name)));
-                                    instructions.push(tee_local(producer.get_merror_tag()));
-                                    instructions.push(add_if());
-                                    instructions.push(set_constant(&self.message_id.to_string()));
-                                    instructions.push(set_constant(&self.line.to_string()));
-                                    instructions.push(call("$buildBufferMessage"));
-                                    instructions.push(call("$printErrorMessage"));
-                                    instructions.push(get_local(producer.get_merror_tag()));    
-                                    instructions.push(add_return());
-                                    instructions.push(add_end());
-                                } else {
-                                    assert!(false);
+                            instructions.push(get_local(producer.get_sub_cmp_tag()));
+                            match &data.dest {
+                                LocationRule::Indexed { .. } => {
+                                    if let Some(name) = &my_template_header {
+                                        instructions.push(call(&format!("${}_run", name)));
+                                        instructions.append(&mut check_error(producer, self.message_id, self.line));
+                                    } else {
+                                        assert!(false);
+                                    }
+                                }
+                                LocationRule::Mapped { .. } => {
+                                    instructions.push(get_local(producer.get_sub_cmp_tag()));
+                                    instructions.push(load32(None)); // get template id
+                                    instructions.push(call_indirect(
+                                        &"$runsmap".to_string(),
+                                        &"(type $_t_i32ri32)".to_string(),
+                                    ));
+                                    instructions.append(&mut check_error(producer, self.message_id, self.line));
                                 }
                             }
-                            LocationRule::Mapped { .. } => {
-                                instructions.push(get_local(producer.get_sub_cmp_tag()));
-                                instructions.push(load32(None)); // get template id
-                                instructions.push(call_indirect(
-                                    &"$runsmap".to_string(),
-                                    &"(type $_t_i32ri32)".to_string(),
-                                ));
-                                instructions.push(tee_local(producer.get_merror_tag()));
-                                instructions.push(add_if());
-                                instructions.push(set_constant(&self.message_id.to_string()));
-                                instructions.push(set_constant(&self.line.to_string()));
-                                instructions.push(call("$buildBufferMessage"));
-                                instructions.push(call("$printErrorMessage"));
-                                instructions.push(get_local(producer.get_merror_tag()));    
-                                instructions.push(add_return());
-                                instructions.push(add_end());
-                            }
-                        }
 			if producer.needs_comments() {
-                            instructions.push(";; end run sub component".to_string());
+                                instructions.push(";; end run sub component".to_string());
 			}
-                        instructions.push(add_end());
+                            instructions.push(add_end());
+                        }
                     }
                     _ => (),
                 }
@@ -601,12 +584,12 @@ impl WriteC for CallBucket {
 					// must be last access
 					assert!(idxpos+1 == indexes.len());
 					for i in index_list.len()..dim {
-					    map_index = format!("{}*cur_def->lengths[{}]",
+					    map_index = format!("({})*cur_def->lengths[{}]",
 								map_index, (i-1).to_string());
 					} // after this we have multiplied by the remaining dimensions
 				    }
 				    // multiply the offset in the array (after multiplying by the missing dimensions) by the size of the elements
-				    map_prologue.push(format!("map_accesses_aux[{}] = {}*cur_def->size;", idxpos.to_string(), map_index));
+				    map_prologue.push(format!("map_accesses_aux[{}] = ({})*cur_def->size;", idxpos, map_index));
 				    map_prologue.push(format!("}}"));
 				} else if let AccessType::Qualified(field_no) = &indexes[idxpos] {
 				    map_prologue.push(format!("cur_def = &({}->{}[cur_def->busId].defs[{}]);",
@@ -740,10 +723,14 @@ impl WriteC for CallBucket {
                         } else {
                             format!("(*{}[{}])", function_table(), my_template_header.unwrap())
                         };
-                        vec![format!(
-                            "{};",
-                            build_call(sub_cmp_call_name, sub_cmp_call_arguments)
-                        )]
+                        if producer.defers_components() {
+                            vec!["// run when one of its outputs is read".to_string()]
+                        } else {
+                            vec![format!(
+                                "{};",
+                                build_call(sub_cmp_call_name, sub_cmp_call_arguments)
+                            )]
+                        }
                     };
                     if let StatusInput::Unknown = status {
                         let sub_cmp_counter_decrease_andcheck = format!("!({})",sub_cmp_counter_decrease);
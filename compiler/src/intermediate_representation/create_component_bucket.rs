@@ -2,8 +2,9 @@ use super::ir_interface::*;
 use crate::translating_traits::*;
 use code_producers::c_elements::*;
 use code_producers::wasm_elements::*;
+use serde_derive::{Deserialize, Serialize};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CreateCmpBucket {
     pub line: usize,
     pub message_id: usize,
@@ -95,15 +96,7 @@ impl WriteWasm for CreateCmpBucket {
                 instructions.push(store32(None)); //store the offset given by create in the subcomponent address
                 instructions.push(get_local(producer.get_temp_tag()));
                 instructions.push(call(&format!("${}_run", self.symbol)));
-                instructions.push(tee_local(producer.get_merror_tag()));
-                instructions.push(add_if());
-                instructions.push(set_constant(&self.message_id.to_string()));
-                instructions.push(set_constant(&self.line.to_string()));
-                instructions.push(call("$buildBufferMessage"));
-                instructions.push(call("$printErrorMessage"));		
-                instructions.push(get_local(producer.get_merror_tag()));    
-                instructions.push(add_return());
-                instructions.push(add_end());
+                instructions.append(&mut check_error(producer, self.message_id, self.line));
             }
             else {
                 instructions.push(store32(None)); //store the offset given by create in the subcomponent address
@@ -124,15 +117,7 @@ impl WriteWasm for CreateCmpBucket {
                     instructions.push(store32(None)); //store the offset given by create in the subcomponent address
                     instructions.push(get_local(producer.get_temp_tag()));
                     instructions.push(call(&format!("${}_run", self.symbol)));
-                    instructions.push(tee_local(producer.get_merror_tag()));
-                    instructions.push(add_if());
-                    instructions.push(set_constant(&self.message_id.to_string()));
-                    instructions.push(set_constant(&self.line.to_string()));
-                    instructions.push(call("$buildBufferMessage"));
-                    instructions.push(call("$printErrorMessage"));
-                    instructions.push(get_local(producer.get_merror_tag()));    
-                    instructions.push(add_return());
-                    instructions.push(add_end());
+                    instructions.append(&mut check_error(producer, self.message_id, self.line));
 		}
                 else {
                     instructions.push(store32(None)); //store the offset given by create in the subcomponent address
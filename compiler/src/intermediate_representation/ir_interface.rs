@@ -1,5 +1,6 @@
 pub use super::address_type::{AddressType, InputInformation, StatusInput};
-pub use super::assert_bucket::AssertBucket;
+pub use super::array_copy_bucket::ArrayCopyBucket;
+pub use super::assert_bucket::{AssertBucket, AssertMessagePart};
 pub use super::branch_bucket::BranchBucket;
 pub use super::call_bucket::{CallBucket, FinalData, ReturnType};
 pub use super::compute_bucket::{ComputeBucket, OperatorType};
@@ -9,9 +10,11 @@ pub use super::location_rule::LocationRule;
 pub use super::location_rule::AccessType;
 pub use super::location_rule::IndexedInfo;
 pub use super::log_bucket::LogBucket;
-pub use super::loop_bucket::LoopBucket;
+pub use super::loop_bucket::{LoopBucket, Reduction, ReductionOperand};
 pub use super::return_bucket::ReturnBucket;
+pub use super::select_bucket::SelectBucket;
 pub use super::store_bucket::StoreBucket;
+pub use super::switch_bucket::{SwitchBucket, SwitchCase};
 pub use super::log_bucket::LogBucketArg;
 pub use super::types::{InstrContext, ValueType, SizeOption};
 pub use super::value_bucket::ValueBucket;
@@ -19,6 +22,7 @@ pub use super::value_bucket::ValueBucket;
 use crate::translating_traits::*;
 use code_producers::c_elements::*;
 use code_producers::wasm_elements::*;
+use serde_derive::{Deserialize, Serialize};
 
 pub trait IntoInstruction {
     fn into_instruction(self) -> Instruction;
@@ -39,14 +43,17 @@ pub trait CheckCompute {
 pub type InstructionList = Vec<InstructionPointer>;
 pub type InstructionPointer = Box<Instruction>;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Instruction {
     Value(ValueBucket),
     Load(LoadBucket),
     Store(StoreBucket),
+    ArrayCopy(ArrayCopyBucket),
     Compute(ComputeBucket),
+    Select(SelectBucket),
     Call(CallBucket),
     Branch(BranchBucket),
+    Switch(SwitchBucket),
     Return(ReturnBucket),
     Assert(AssertBucket),
     Log(LogBucket),
@@ -67,9 +74,12 @@ impl ObtainMeta for Instruction {
             Value(v) => v.get_line(),
             Load(v) => v.get_line(),
             Store(v) => v.get_line(),
+            ArrayCopy(v) => v.get_line(),
             Compute(v) => v.get_line(),
+            Select(v) => v.get_line(),
             Call(v) => v.get_line(),
             Branch(v) => v.get_line(),
+            Switch(v) => v.get_line(),
             Return(v) => v.get_line(),
             Loop(v) => v.get_line(),
             Assert(v) => v.get_line(),
@@ -84,9 +94,12 @@ impl ObtainMeta for Instruction {
             Value(v) => v.get_message_id(),
             Load(v) => v.get_message_id(),
             Store(v) => v.get_message_id(),
+            ArrayCopy(v) => v.get_message_id(),
             Compute(v) => v.get_message_id(),
+            Select(v) => v.get_message_id(),
             Call(v) => v.get_message_id(),
             Branch(v) => v.get_message_id(),
+            Switch(v) => v.get_message_id(),
             Return(v) => v.get_message_id(),
             Loop(v) => v.get_message_id(),
             Assert(v) => v.get_message_id(),
@@ -115,9 +128,12 @@ impl WriteWasm for Instruction {
             Value(v) => v.produce_wasm(producer),
             Load(v) => v.produce_wasm(producer),
             Store(v) => v.produce_wasm(producer),
+            ArrayCopy(v) => v.produce_wasm(producer),
             Compute(v) => v.produce_wasm(producer),
+            Select(v) => v.produce_wasm(producer),
             Call(v) => v.produce_wasm(producer),
             Branch(v) => v.produce_wasm(producer),
+            Switch(v) => v.produce_wasm(producer),
             Return(v) => v.produce_wasm(producer),
             Loop(v) => v.produce_wasm(producer),
             Assert(v) => v.produce_wasm(producer),
@@ -135,9 +151,12 @@ impl WriteC for Instruction {
             Value(v) => v.produce_c(producer, parallel),
             Load(v) => v.produce_c(producer, parallel),
             Store(v) => v.produce_c(producer, parallel),
+            ArrayCopy(v) => v.produce_c(producer, parallel),
             Compute(v) => v.produce_c(producer, parallel),
+            Select(v) => v.produce_c(producer, parallel),
             Call(v) => v.produce_c(producer, parallel),
             Branch(v) => v.produce_c(producer, parallel),
+            Switch(v) => v.produce_c(producer, parallel),
             Return(v) => v.produce_c(producer, parallel),
             Loop(v) => v.produce_c(producer, parallel),
             Assert(v) => v.produce_c(producer, parallel),
@@ -154,9 +173,12 @@ impl ToString for Instruction {
             Value(v) => v.to_string(),
             Load(v) => v.to_string(),
             Store(v) => v.to_string(),
+            ArrayCopy(v) => v.to_string(),
             Compute(v) => v.to_string(),
+            Select(v) => v.to_string(),
             Call(v) => v.to_string(),
             Branch(v) => v.to_string(),
+            Switch(v) => v.to_string(),
             Return(v) => v.to_string(),
             Loop(v) => v.to_string(),
             Assert(v) => v.to_string(),
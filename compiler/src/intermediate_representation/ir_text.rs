@@ -0,0 +1,318 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+// Textual syntax of the IR. The buckets are written through their serde
+// representation, so the text keeps every field of the code and follows the
+// changes of the buckets without changes here:
+//     Store { line: 3, message_id: 0, context: { size: Single(1) }, ... }
+// A variant is written as its name followed by its fields, or by its value
+// between parentheses, and the strings that are names are written unquoted.
+// The text may have comments, from // to the end of the line.
+
+const LINE_WIDTH: usize = 100;
+const INDENT: &str = "    ";
+
+pub fn to_text<T: Serialize>(value: &T) -> String {
+    let value = serde_json::to_value(value).unwrap();
+    let mut text = String::new();
+    write_value(&value, 0, &mut text);
+    text
+}
+
+pub fn from_text<T: DeserializeOwned>(text: &str) -> Result<T, String> {
+    let mut parser = Parser { text, position: 0 };
+    let value = parser.value()?;
+    parser.skip_blanks();
+    if parser.position < text.len() {
+        return parser.error("expected the end of the text");
+    }
+    serde_json::from_value(value).map_err(|error| error.to_string())
+}
+
+fn is_name(text: &str) -> bool {
+    let mut chars = text.chars();
+    let starts = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_');
+    starts && chars.all(|c| c.is_ascii_alphanumeric() || c == '_') && !matches!(text, "true" | "false" | "null")
+}
+
+// a variant is an object with a single field named after it, as {"Load": ...}
+fn as_variant(object: &Map<String, Value>) -> Option<(&String, &Value)> {
+    let (name, value) = object.iter().next()?;
+    let is_variant = object.len() == 1 && is_name(name) && name.starts_with(|c: char| c.is_ascii_uppercase());
+    if is_variant {
+        Some((name, value))
+    } else {
+        None
+    }
+}
+
+// the value of a variant goes between parentheses unless it is a list of fields
+fn is_fields(value: &Value) -> bool {
+    matches!(value, Value::Object(object) if as_variant(object).is_none())
+}
+
+fn write_string(text: &str, out: &mut String) {
+    if is_name(text) {
+        out.push_str(text);
+    } else {
+        out.push_str(&serde_json::to_string(text).unwrap());
+    }
+}
+
+// the value in a single line, when it takes less than the given width
+fn inline(value: &Value, width: usize, out: &mut String) -> bool {
+    let start = out.len();
+    match value {
+        Value::Null | Value::Bool(_) | Value::Number(_) => out.push_str(&value.to_string()),
+        Value::String(text) => write_string(text, out),
+        Value::Array(values) => {
+            out.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                if !inline(value, width.saturating_sub(out.len() - start), out) {
+                    return false;
+                }
+            }
+            out.push(']');
+        }
+        Value::Object(object) => {
+            if let Some((name, value)) = as_variant(object) {
+                out.push_str(name);
+                let is_fields = is_fields(value);
+                out.push_str(if is_fields { " " } else { "(" });
+                if !inline(value, width.saturating_sub(out.len() - start), out) {
+                    return false;
+                }
+                if !is_fields {
+                    out.push(')');
+                }
+            } else if object.is_empty() {
+                out.push_str("{}");
+            } else {
+                out.push_str("{ ");
+                for (i, (key, value)) in object.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    write_string(key, out);
+                    out.push_str(": ");
+                    if !inline(value, width.saturating_sub(out.len() - start), out) {
+                        return false;
+                    }
+                }
+                out.push_str(" }");
+            }
+        }
+    }
+    out.len() - start <= width
+}
+
+fn write_value(value: &Value, depth: usize, out: &mut String) {
+    let line_start = out.rfind('\n').map_or(0, |position| position + 1);
+    let width = LINE_WIDTH.saturating_sub(out.len() - line_start);
+    let start = out.len();
+    if inline(value, width, out) {
+        return;
+    }
+    out.truncate(start);
+    let indent = INDENT.repeat(depth + 1);
+    match value {
+        Value::Array(values) => {
+            out.push_str("[\n");
+            for value in values {
+                out.push_str(&indent);
+                write_value(value, depth + 1, out);
+                out.push_str(",\n");
+            }
+            out.push_str(&INDENT.repeat(depth));
+            out.push(']');
+        }
+        Value::Object(object) => {
+            if let Some((name, value)) = as_variant(object) {
+                out.push_str(name);
+                if is_fields(value) {
+                    out.push(' ');
+                    write_value(value, depth, out);
+                } else {
+                    out.push('(');
+                    write_value(value, depth, out);
+                    out.push(')');
+                }
+                return;
+            }
+            out.push_str("{\n");
+            for (key, value) in object {
+                out.push_str(&indent);
+                write_string(key, out);
+                out.push_str(": ");
+                write_value(value, depth + 1, out);
+                out.push_str(",\n");
+            }
+            out.push_str(&INDENT.repeat(depth));
+            out.push('}');
+        }
+        _ => {
+            inline(value, usize::MAX, out);
+        }
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, message: &str) -> Result<T, String> {
+        let line = self.text[..self.position].matches('\n').count() + 1;
+        Err(format!("line {}: {}", line, message))
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.position..]
+    }
+
+    fn skip_blanks(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.position += rest.len() - trimmed.len();
+            if !trimmed.starts_with("//") {
+                return;
+            }
+            self.position += trimmed.find('\n').unwrap_or(trimmed.len());
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_blanks();
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(symbol) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), String> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            self.error(&format!("expected '{}'", symbol))
+        }
+    }
+
+    fn name(&mut self) -> &'a str {
+        let rest = self.rest();
+        let end = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(rest.len());
+        self.position += end;
+        &rest[..end]
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let rest = self.rest();
+        let mut escaped = false;
+        for (i, c) in rest.char_indices().skip(1) {
+            match c {
+                '\\' if !escaped => escaped = true,
+                '"' if !escaped => {
+                    self.position += i + 1;
+                    return serde_json::from_str(&rest[..=i]).map_err(|_err| "invalid string".to_string());
+                }
+                _ => escaped = false,
+            }
+        }
+        self.error("unterminated string")
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let rest = self.rest();
+        let end = rest
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| !c.is_ascii_digit() && !matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+            .map_or(rest.len(), |(i, _)| i);
+        match serde_json::from_str::<Number>(&rest[..end]) {
+            Ok(number) => {
+                self.position += end;
+                Ok(Value::Number(number))
+            }
+            Err(_) => self.error("invalid number"),
+        }
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some('"') => self.string(),
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => Ok(self.name().to_string()),
+            _ => self.error("expected the name of a field"),
+        }
+    }
+
+    fn fields(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut object = Map::new();
+        while !self.eat('}') {
+            let key = self.key()?;
+            self.expect(':')?;
+            let value = self.value()?;
+            object.insert(key, value);
+            if !self.eat(',') {
+                self.expect('}')?;
+                break;
+            }
+        }
+        Ok(Value::Object(object))
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('{') => self.fields(),
+            Some('[') => {
+                self.position += 1;
+                let mut values = Vec::new();
+                while !self.eat(']') {
+                    values.push(self.value()?);
+                    if !self.eat(',') {
+                        self.expect(']')?;
+                        break;
+                    }
+                }
+                Ok(Value::Array(values))
+            }
+            Some('"') => self.string().map(Value::String),
+            Some(c) if c.is_ascii_digit() || c == '-' => self.number(),
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let name = self.name();
+                let value = match name {
+                    "true" => Value::Bool(true),
+                    "false" => Value::Bool(false),
+                    "null" => Value::Null,
+                    _ if self.peek() == Some('{') => variant(name, self.fields()?),
+                    _ if self.eat('(') => {
+                        let value = self.value()?;
+                        self.expect(')')?;
+                        variant(name, value)
+                    }
+                    _ => Value::String(name.to_string()),
+                };
+                Ok(value)
+            }
+            Some(_) => self.error("expected a value"),
+            None => self.error("unexpected end of the text"),
+        }
+    }
+}
+
+fn variant(name: &str, value: Value) -> Value {
+    let mut object = Map::new();
+    object.insert(name.to_string(), value);
+    Value::Object(object)
+}
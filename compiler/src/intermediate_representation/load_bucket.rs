@@ -2,8 +2,9 @@ use super::ir_interface::*;
 use crate::translating_traits::*;
 use code_producers::c_elements::*;
 use code_producers::wasm_elements::*;
+use serde_derive::{Deserialize, Serialize};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct LoadBucket {
     pub line: usize,
     pub message_id: usize,
@@ -52,6 +53,25 @@ impl WriteWasm for LoadBucket {
         if producer.needs_comments() {
             instructions.push(";; load bucket".to_string());
 	}
+        if let AddressType::SubcmpSignal { cmp_address, is_output: true, .. } = &self.address_type {
+            if producer.defers_components() {
+                instructions.push(get_local(producer.get_offset_tag()));
+                instructions.push(set_constant(
+                    &producer.get_sub_component_start_in_component().to_string(),
+                ));
+                instructions.push(add32());
+                let mut instructions_sci = cmp_address.produce_wasm(producer);
+                instructions.append(&mut instructions_sci);
+                instructions.push(set_constant("4")); //size in byte of i32
+                instructions.push(mul32());
+                instructions.push(add32());
+                instructions.push(load32(None)); //subcomponent block
+                instructions.push(set_local(producer.get_deferred_cmp_tag()));
+                let message = Some((self.message_id, self.line));
+                let mut run_code = run_deferred_component(producer, producer.get_deferred_cmp_tag(), message);
+                instructions.append(&mut run_code);
+            }
+        }
         match &self.src {
             LocationRule::Indexed { location, .. } => {
                 let mut instructions_src = location.produce_wasm(producer);
@@ -296,7 +316,7 @@ impl WriteC for LoadBucket {
 				// must be last access
 				assert!(idxpos+1 == indexes.len());
 				for i in index_list.len()..dim {
-				    map_index = format!("{}*{}.lengths[{}]",
+				    map_index = format!("({})*{}.lengths[{}]",
 							map_index, cur_def, (i-1).to_string());
 				} // after this we have multiplied by the remaining dimensions
 			    }
@@ -437,6 +457,10 @@ impl WriteC for LoadBucket {
                 prologue.push(format!("}}"));
             }
         }
+                if *is_output && producer.defers_components() {
+                    let sub_cmp_pos = format!("{}[{}]", MY_SUBCOMPONENTS, cmp_index_ref);
+                    prologue.append(&mut run_deferred_component(&sub_cmp_pos));
+                }
                 let sub_cmp_start = format!(
                     "{}->componentMemory[{}[{}]].signalStart",
                     CIRCOM_CALC_WIT, MY_SUBCOMPONENTS, cmp_index_ref
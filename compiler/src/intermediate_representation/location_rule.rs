@@ -1,12 +1,13 @@
 use super::ir_interface::*;
+use serde_derive::{Deserialize, Serialize};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct IndexedInfo{
     pub indexes: Vec<InstructionPointer>,
     pub symbol_dim: usize
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum AccessType{
     Indexed(IndexedInfo), // Case accessing an array
     Qualified(usize), // Case accessing a field -> id field
@@ -29,7 +30,7 @@ impl ToString for AccessType {
 // Example: accessing a[2][3].b[2].c
 // [Indexed([2, 3]), Qualified(id_b), Indexed([2]), Qualified(id_c)]
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum LocationRule {
     Indexed { location: InstructionPointer, template_header: Option<String> },
     Mapped { signal_code: usize, indexes: Vec<AccessType> },
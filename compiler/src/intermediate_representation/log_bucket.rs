@@ -2,9 +2,10 @@ use super::ir_interface::*;
 use crate::translating_traits::*;
 use code_producers::c_elements::*;
 use code_producers::wasm_elements::*;
+use serde_derive::{Deserialize, Serialize};
 
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum LogBucketArg {
     LogExp(InstructionPointer),
     LogStr(usize)
@@ -19,11 +20,13 @@ impl LogBucketArg{
 }
 
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct LogBucket {
     pub line: usize,
     pub message_id: usize,
     pub argsprint: Vec<LogBucketArg>,
+    // the arguments are printed without spaces between them
+    pub is_formatted: bool,
 }
 
 impl IntoInstruction for LogBucket {
@@ -70,6 +73,10 @@ impl WriteWasm for LogBucket {
         if producer.needs_comments() {
             instructions.push(";; log bucket".to_string());
 	    }
+        if self.is_formatted {
+            instructions.push(set_constant("1"));
+            instructions.push(set_global("$logFormatted"));
+        }
         for logarg in self.argsprint.clone() {
 	    match &logarg {
                 LogBucketArg::LogExp(exp) => {
@@ -95,6 +102,10 @@ impl WriteWasm for LogBucket {
         instructions.push(set_constant("0"));
         instructions.push(store32(None));
         instructions.push(call("$writeBufferMessage"));
+        if self.is_formatted {
+            instructions.push(set_constant("0"));
+            instructions.push(set_global("$logFormatted"));
+        }
         if producer.needs_comments() {
             instructions.push(";; end of log bucket".to_string());
 	}
@@ -138,7 +149,7 @@ impl WriteC for LogBucket {
             else{
                 unreachable!();
             }
-            if index != self.argsprint.len() - 1 { 
+            if index != self.argsprint.len() - 1 && !self.is_formatted { 
                 let print_c =
                     build_call(
                         "printf".to_string(), 
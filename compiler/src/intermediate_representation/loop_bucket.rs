@@ -2,13 +2,48 @@ use super::ir_interface::*;
 use crate::translating_traits::*;
 use code_producers::c_elements::*;
 use code_producers::wasm_elements::*;
+use serde_derive::{Deserialize, Serialize};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct LoopBucket {
     pub line: usize,
     pub message_id: usize,
     pub continue_condition: InstructionPointer,
     pub body: InstructionList,
+    // the iterations only compute independent elements, it is only a hint
+    // for the C++ compiler, the wasm code is the same
+    pub vectorizable: bool,
+    // the loop is computed by a single call to the runtime
+    pub reduction: Option<Reduction>,
+}
+
+// The elements read by each iteration of a reduction, at start + stride *
+// iteration
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReductionOperand {
+    pub is_signal: bool,
+    pub start: usize,
+    pub stride: usize,
+}
+
+// A loop that adds to a variable an element of an array, or the product of
+// the elements of two arrays, in each iteration. The counter of the loop is
+// set by the instruction that follows it
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Reduction {
+    pub accumulator: usize,
+    pub operands: Vec<ReductionOperand>,
+    pub length: usize,
+}
+
+impl Reduction {
+    fn runtime_function(&self) -> &'static str {
+        if self.operands.len() == 1 {
+            "Fr_add_n"
+        } else {
+            "Fr_mul_add_n"
+        }
+    }
 }
 
 impl IntoInstruction for LoopBucket {
@@ -41,7 +76,14 @@ impl ToString for LoopBucket {
         for i in &self.body {
             body = format!("{}{};", body, i.to_string());
         }
-        format!("LOOP(line:{},template_id:{},cond:{},body:{})", line, template_id, cond, body)
+        let reduction = match &self.reduction {
+            Some(r) => format!("{}[{}]", r.runtime_function(), r.length),
+            None => "None".to_string(),
+        };
+        format!(
+            "LOOP(line:{},template_id:{},cond:{},vectorizable:{},reduction:{},body:{})",
+            line, template_id, cond, self.vectorizable, reduction, body
+        )
     }
 }
 
@@ -49,8 +91,39 @@ impl WriteWasm for LoopBucket {
     fn produce_wasm(&self, producer: &WASMProducer) -> Vec<String> {
         use code_producers::wasm_elements::wasm_code_generator::*;
         let mut instructions = vec![];
+        if let Some(reduction) = &self.reduction {
+            if producer.needs_comments() {
+                instructions.push(format!(";; fused loop bucket. Line {}", self.line));
+            }
+            let element_size = producer.get_size_32_bits_in_memory() * 4;
+            instructions.push(get_local(producer.get_lvar_tag()));
+            instructions.push(set_constant(&(reduction.accumulator * element_size).to_string()));
+            instructions.push(add32());
+            for operand in &reduction.operands {
+                if operand.is_signal {
+                    instructions.push(get_local(producer.get_signal_start_tag()));
+                } else {
+                    instructions.push(get_local(producer.get_lvar_tag()));
+                }
+                instructions.push(set_constant(&(operand.start * element_size).to_string()));
+                instructions.push(add32());
+                instructions.push(set_constant(&(operand.stride * element_size).to_string()));
+            }
+            instructions.push(set_constant(&reduction.length.to_string()));
+            if reduction.operands.len() > 1 {
+                // the products are kept in the first slot of the expressions
+                instructions.push(get_local(producer.get_expaux_tag()));
+            }
+            instructions.push(call(&format!("${}", reduction.runtime_function())));
+            return instructions;
+        }
         if producer.needs_comments() {
             instructions.push(format!(";; loop bucket. Line {}", self.line)); //.to_string()
+            // the field operations are calls to the runtime, so the
+            // iterations are still run one by one
+            if self.vectorizable {
+                instructions.push(";; vectorizable loop".to_string());
+            }
 	}
         instructions.push(add_block());
         instructions.push(add_loop());
@@ -75,7 +148,25 @@ impl WriteWasm for LoopBucket {
 
 impl WriteC for LoopBucket {
     fn produce_c(&self, producer: &CProducer, parallel: Option<bool>) -> (Vec<String>, String) {
-        use c_code_generator::merge_code;
+        use c_code_generator::*;
+        if let Some(reduction) = &self.reduction {
+            let goldilocks = producer.prime_str == "goldilocks";
+            let accumulator = lvar(reduction.accumulator.to_string());
+            let mut arguments = vec![if goldilocks { accumulator.clone() } else { format!("&{}", accumulator) }];
+            for operand in &reduction.operands {
+                let element = if operand.is_signal {
+                    signal_values(operand.start.to_string())
+                } else {
+                    lvar(operand.start.to_string())
+                };
+                arguments.push(format!("&{}", element));
+                arguments.push(operand.stride.to_string());
+            }
+            arguments.push(reduction.length.to_string());
+            let call = build_call(reduction.runtime_function().to_string(), arguments);
+            let fused = if goldilocks { format!("{} = {};", accumulator, call) } else { format!("{};", call) };
+            return (vec![fused], "".to_string());
+        }
         let (continue_code, continue_result) = self.continue_condition.produce_c(producer, parallel);
         let continue_result = format!("Fr_isTrue({})", continue_result);
         let mut body = vec![];
@@ -86,6 +177,9 @@ impl WriteC for LoopBucket {
         body.append(&mut continue_code.clone());
         let while_loop = format!("while({}){{\n{}}}", continue_result, merge_code(body));
         let mut loop_c = continue_code;
+        if self.vectorizable {
+            loop_c.push("#pragma GCC ivdep".to_string());
+        }
         loop_c.push(while_loop);
         (loop_c, "".to_string())
     }
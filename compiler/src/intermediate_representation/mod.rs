@@ -1,4 +1,5 @@
 mod address_type;
+mod array_copy_bucket;
 mod assert_bucket;
 mod branch_bucket;
 mod call_bucket;
@@ -9,10 +10,13 @@ mod location_rule;
 mod log_bucket;
 mod loop_bucket;
 mod return_bucket;
+mod select_bucket;
 mod store_bucket;
+mod switch_bucket;
 mod types;
 mod value_bucket;
 
 pub mod ir_interface;
+pub mod ir_text;
 pub mod translate;
 pub use ir_interface::InstructionList;
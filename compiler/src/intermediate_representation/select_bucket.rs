@@ -0,0 +1,87 @@
+use super::ir_interface::*;
+use crate::translating_traits::*;
+use code_producers::c_elements::*;
+use code_producers::wasm_elements::*;
+use serde_derive::{Deserialize, Serialize};
+
+// The value of if_true when cond holds and of if_false otherwise. Both
+// values are always evaluated, so they must not fail or have effects
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SelectBucket {
+    pub line: usize,
+    pub message_id: usize,
+    pub cond: InstructionPointer,
+    pub if_true: InstructionPointer,
+    pub if_false: InstructionPointer,
+}
+
+impl IntoInstruction for SelectBucket {
+    fn into_instruction(self) -> Instruction {
+        Instruction::Select(self)
+    }
+}
+
+impl Allocate for SelectBucket {
+    fn allocate(self) -> InstructionPointer {
+        InstructionPointer::new(self.into_instruction())
+    }
+}
+
+impl ObtainMeta for SelectBucket {
+    fn get_line(&self) -> usize {
+        self.line
+    }
+    fn get_message_id(&self) -> usize {
+        self.message_id
+    }
+}
+
+impl std::fmt::Display for SelectBucket {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "SELECT(line:{},template_id:{},cond:{},if_true:{},if_false:{})",
+            self.line,
+            self.message_id,
+            self.cond.to_string(),
+            self.if_true.to_string(),
+            self.if_false.to_string()
+        )
+    }
+}
+
+impl WriteWasm for SelectBucket {
+    fn produce_wasm(&self, producer: &WASMProducer) -> Vec<String> {
+        use code_producers::wasm_elements::wasm_code_generator::*;
+        let mut instructions = vec![];
+        if producer.needs_comments() {
+            instructions.push(";; select bucket".to_string());
+        }
+        // the addresses of both values are computed and one of them is kept
+        let mut instructions_true = self.if_true.produce_wasm(producer);
+        instructions.append(&mut instructions_true);
+        let mut instructions_false = self.if_false.produce_wasm(producer);
+        instructions.append(&mut instructions_false);
+        let mut instructions_cond = self.cond.produce_wasm(producer);
+        instructions.append(&mut instructions_cond);
+        instructions.push(call("$Fr_isTrue"));
+        instructions.push(select());
+        if producer.needs_comments() {
+            instructions.push(";; end of select bucket".to_string());
+        }
+        instructions
+    }
+}
+
+impl WriteC for SelectBucket {
+    fn produce_c(&self, producer: &CProducer, parallel: Option<bool>) -> (Vec<String>, String) {
+        let mut prologue = vec![];
+        let (mut cond_code, cond) = self.cond.produce_c(producer, parallel);
+        prologue.append(&mut cond_code);
+        let (mut true_code, if_true) = self.if_true.produce_c(producer, parallel);
+        prologue.append(&mut true_code);
+        let (mut false_code, if_false) = self.if_false.produce_c(producer, parallel);
+        prologue.append(&mut false_code);
+        (prologue, format!("(Fr_isTrue({}) ? {} : {})", cond, if_true, if_false))
+    }
+}
@@ -2,8 +2,9 @@ use super::ir_interface::*;
 use crate::translating_traits::*;
 use code_producers::c_elements::*;
 use code_producers::wasm_elements::*;
+use serde_derive::{Deserialize, Serialize};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct StoreBucket {
     pub line: usize,
     pub message_id: usize,
@@ -269,6 +270,10 @@ impl WriteWasm for StoreBucket {
                 }
             }
         }
+        let traces_signal = producer.traces_signals() && !matches!(self.dest_address_type, AddressType::Variable);
+        if traces_signal {
+            instructions.push(tee_local(producer.get_trace_address_tag()));
+        }
         if producer.needs_comments() {
             instructions.push(";; getting src".to_string());
 	}
@@ -355,6 +360,21 @@ impl WriteWasm for StoreBucket {
             instructions.push(add_end());
             instructions.push(add_end());
         }
+        if traces_signal {
+            if producer.needs_comments() {
+                instructions.push(";; trace the stored signals".to_string());
+            }
+            instructions.push(get_local(producer.get_trace_address_tag()));
+            if (!is_multiple_dest && size_dest == 1) || (!is_multiple_src && size_src == 1) {
+                instructions.push(set_constant("1"));
+            } else if !is_multiple_dest && !is_multiple_src {
+                instructions.push(set_constant(&std::cmp::min(&size_dest, &size_src).to_string()));
+            } else {
+                instructions.push(get_local(producer.get_result_size_tag()));
+            }
+            instructions.push(set_constant(&self.line.to_string()));
+            instructions.push(call("$traceSignals"));
+        }
         match &self.dest_address_type {
             AddressType::SubcmpSignal { .. } => {
                 // if subcomponent input check if run needed
@@ -377,58 +397,48 @@ impl WriteWasm for StoreBucket {
                 instructions.push(store32(Some(
                     &producer.get_input_counter_address_in_component().to_string(),
                 ))); // update remaining inputs to be set
+                if producer.defers_components() {
+                    if producer.needs_comments() {
+                        instructions.push(";; run when one of its outputs is read".to_string());
+                    }
+                } else {
 		if producer.needs_comments() {
-                    instructions.push(";; check if run is needed".to_string());
+                        instructions.push(";; check if run is needed".to_string());
 		}
-                instructions.push(get_local(producer.get_sub_cmp_tag()));
-                instructions.push(load32(Some(
-                    &producer.get_input_counter_address_in_component().to_string(),
-                )));
-                instructions.push(eqz32());
-                instructions.push(add_if());
+                    instructions.push(get_local(producer.get_sub_cmp_tag()));
+                    instructions.push(load32(Some(
+                        &producer.get_input_counter_address_in_component().to_string(),
+                    )));
+                    instructions.push(eqz32());
+                    instructions.push(add_if());
 		if producer.needs_comments() {
-                    instructions.push(";; run sub component".to_string());
+                        instructions.push(";; run sub component".to_string());
 		}
-                instructions.push(get_local(producer.get_sub_cmp_tag()));
-                match &self.dest {
-                    LocationRule::Indexed { .. } => {
-                        if let Some(name) = &my_template_header {
-                            instructions.push(call(&format!("${}_run", name)));
-                            instructions.push(tee_local(producer.get_merror_tag()));
-                            instructions.push(add_if());
-                            instructions.push(set_constant(&self.message_id.to_string()));
-                            instructions.push(set_constant(&self.line.to_string()));
-                            instructions.push(call("$buildBufferMessage"));
-                            instructions.push(call("$printErrorMessage"));
-                            instructions.push(get_local(producer.get_merror_tag()));    
-                            instructions.push(add_return());
-                            instructions.push(add_end());
-                        } else {
-                            assert!(false);
+                    instructions.push(get_local(producer.get_sub_cmp_tag()));
+                    match &self.dest {
+                        LocationRule::Indexed { .. } => {
+                            if let Some(name) = &my_template_header {
+                                instructions.push(call(&format!("${}_run", name)));
+                                instructions.append(&mut check_error(producer, self.message_id, self.line));
+                            } else {
+                                assert!(false);
+                            }
+                        }
+                        LocationRule::Mapped { .. } => {
+                            instructions.push(get_local(producer.get_sub_cmp_tag()));
+                            instructions.push(load32(None)); // get template id
+                            instructions.push(call_indirect(
+                                &"$runsmap".to_string(),
+                                &"(type $_t_i32ri32)".to_string(),
+                            ));
+                            instructions.append(&mut check_error(producer, self.message_id, self.line));
                         }
                     }
-                    LocationRule::Mapped { .. } => {
-                        instructions.push(get_local(producer.get_sub_cmp_tag()));
-                        instructions.push(load32(None)); // get template id
-                        instructions.push(call_indirect(
-                            &"$runsmap".to_string(),
-                            &"(type $_t_i32ri32)".to_string(),
-                        ));
-                        instructions.push(tee_local(producer.get_merror_tag()));
-                        instructions.push(add_if());
-                        instructions.push(set_constant(&self.message_id.to_string()));
-                        instructions.push(set_constant(&self.line.to_string()));
-                        instructions.push(call("$buildBufferMessage"));
-                        instructions.push(call("$printErrorMessage"));
-                        instructions.push(get_local(producer.get_merror_tag()));    
-                        instructions.push(add_return());
-                        instructions.push(add_end());
-                    }
-                }
 		if producer.needs_comments() {
-                    instructions.push(";; end run sub component".to_string());
+                        instructions.push(";; end run sub component".to_string());
 		}
-                instructions.push(add_end());
+                    instructions.push(add_end());
+                }
             }
             _ => (),
         }
@@ -541,12 +551,12 @@ impl WriteC for StoreBucket {
 				// must be last access
 				assert!(idxpos+1 == indexes.len());
 				for i in index_list.len()..dim {
-				    map_index = format!("{}*cur_def->lengths[{}]",
+				    map_index = format!("({})*cur_def->lengths[{}]",
 							map_index, (i-1).to_string());
 				} // after this we have multiplied by the remaining dimensions
 			    }
 			    // multiply the offset in the array (after multiplying by the missing dimensions) by the size of the elements
-		            map_prologue.push(format!("map_accesses_aux[{}] = {}*cur_def->size;", idxpos.to_string(), map_index));
+		            map_prologue.push(format!("map_accesses_aux[{}] = ({})*cur_def->size;", idxpos, map_index));
 			    map_prologue.push(format!("}}"));
 			} else if let AccessType::Qualified(field_no) = &indexes[idxpos] {
 			    map_prologue.push(format!("cur_def = &({}->{}[cur_def->busId].defs[{}]);",
@@ -569,7 +579,8 @@ impl WriteC for StoreBucket {
                 ((vec![], "".to_string()),Option::<String>::None)
 	    };
 	prologue.append(&mut dest_prologue);
-        // Build dest
+        // Build dest, and the position of the signal to trace
+        let mut trace_signal = None;
         let dest = match &self.dest_address_type {
             AddressType::Variable => {
                 if producer.prime_str != "goldilocks" {
@@ -579,6 +590,7 @@ impl WriteC for StoreBucket {
                 }
             }
             AddressType::Signal => {
+                trace_signal = Some(format!("{} + {}", my_signal_start(), dest_index));
                 if producer.prime_str != "goldilocks" {
                     format!("&{}", signal_values(dest_index.clone()))
                 } else {
@@ -590,6 +602,7 @@ impl WriteC for StoreBucket {
                     "{}->componentMemory[{}[{}]].signalStart",
                     CIRCOM_CALC_WIT, MY_SUBCOMPONENTS, cmp_index_ref
                 );
+                trace_signal = Some(format!("{} + {}", sub_cmp_start, dest_index));
                 if producer.prime_str != "goldilocks" {
                     format!("&{}->signalValues[{} + {}]", CIRCOM_CALC_WIT, sub_cmp_start, dest_index.clone())
                 } else {
@@ -654,6 +667,10 @@ impl WriteC for StoreBucket {
 		}
 	    }
         }
+        if let Some(signal) = trace_signal.filter(|_| producer.traces_signals()) {
+            let trace_arguments = vec![CIRCOM_CALC_WIT.to_string(), signal, size.clone(), self.line.to_string()];
+            prologue.push(format!("{};", build_call("traceSignals".to_string(), trace_arguments)));
+        }
         if producer.prime_str != "goldilocks" {
 	    prologue.push(format!("}}")); // add a close block 2 if opened // not that since all closing } are at the end it works
         }
@@ -704,10 +721,14 @@ impl WriteC for StoreBucket {
                     } else {
                         format!("(*{}[{}])", function_table(), my_template_header.unwrap())
                     };
-                    vec![format!(
-                        "{};",
-                        build_call(sub_cmp_call_name, sub_cmp_call_arguments)
-                    )]
+                    if producer.defers_components() {
+                        vec!["// run when one of its outputs is read".to_string()]
+                    } else {
+                        vec![format!(
+                            "{};",
+                            build_call(sub_cmp_call_name, sub_cmp_call_arguments)
+                        )]
+                    }
                 };
                 if let StatusInput::Unknown = status {
                     let sub_cmp_counter_decrease_andcheck = format!("!({})",sub_cmp_counter_decrease);
@@ -0,0 +1,167 @@
+use super::ir_interface::*;
+use crate::translating_traits::*;
+use code_producers::c_elements::*;
+use code_producers::wasm_elements::*;
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SwitchCase {
+    pub value: usize,
+    pub body: InstructionList,
+}
+
+// Runs the case whose value is the selector, or the default branch if there
+// is none. The selector is only converted to an integer when in_range holds,
+// that is when it is between the first and the last value of the cases,
+// which are sorted
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SwitchBucket {
+    pub line: usize,
+    pub message_id: usize,
+    pub in_range: InstructionPointer,
+    pub selector: InstructionPointer,
+    pub cases: Vec<SwitchCase>,
+    pub default_branch: InstructionList,
+}
+
+impl IntoInstruction for SwitchBucket {
+    fn into_instruction(self) -> Instruction {
+        Instruction::Switch(self)
+    }
+}
+
+impl Allocate for SwitchBucket {
+    fn allocate(self) -> InstructionPointer {
+        InstructionPointer::new(self.into_instruction())
+    }
+}
+
+impl ObtainMeta for SwitchBucket {
+    fn get_line(&self) -> usize {
+        self.line
+    }
+    fn get_message_id(&self) -> usize {
+        self.message_id
+    }
+}
+
+impl std::fmt::Display for SwitchBucket {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut cases = "".to_string();
+        for case in &self.cases {
+            let mut body = "".to_string();
+            for i in &case.body {
+                body = format!("{}{};", body, i.to_string());
+            }
+            cases = format!("{}CASE({}:{});", cases, case.value, body);
+        }
+        let mut default_body = "".to_string();
+        for i in &self.default_branch {
+            default_body = format!("{}{};", default_body, i.to_string());
+        }
+        write!(
+            f,
+            "SWITCH(line:{},template_id:{},in_range:{},selector:{},cases:{},default:{})",
+            self.line,
+            self.message_id,
+            self.in_range.to_string(),
+            self.selector.to_string(),
+            cases,
+            default_body
+        )
+    }
+}
+
+impl WriteWasm for SwitchBucket {
+    fn produce_wasm(&self, producer: &WASMProducer) -> Vec<String> {
+        use code_producers::wasm_elements::wasm_code_generator::*;
+        let mut instructions = vec![];
+        if producer.needs_comments() {
+            instructions.push(";; switch bucket".to_string());
+        }
+        // one block to leave the switch, one for the default branch and one
+        // per case, the code of each case follows the end of its block
+        let cases = self.cases.len();
+        for _ in 0..cases + 2 {
+            instructions.push(add_block());
+        }
+        let default_label = cases.to_string();
+        let mut instructions_range = self.in_range.produce_wasm(producer);
+        instructions.append(&mut instructions_range);
+        instructions.push(call("$Fr_isTrue"));
+        instructions.push(eqz32());
+        instructions.push(br_if(&default_label));
+        let mut instructions_selector = self.selector.produce_wasm(producer);
+        instructions.append(&mut instructions_selector);
+        instructions.push(call("$Fr_toInt"));
+        let first = self.cases[0].value;
+        let last = self.cases[cases - 1].value;
+        instructions.push(set_constant(&first.to_string()));
+        instructions.push(sub32());
+        let mut targets = Vec::with_capacity(last - first + 2);
+        let mut next = 0;
+        for value in first..=last {
+            if self.cases[next].value == value {
+                targets.push(next.to_string());
+                next += 1;
+            } else {
+                targets.push(default_label.clone());
+            }
+        }
+        targets.push(default_label);
+        instructions.push(br_table(&targets));
+        for (i, case) in self.cases.iter().enumerate() {
+            instructions.push(add_end());
+            for ins in &case.body {
+                let mut instructions_case = ins.produce_wasm(producer);
+                instructions.append(&mut instructions_case);
+            }
+            instructions.push(br(&(cases - i).to_string()));
+        }
+        instructions.push(add_end());
+        for ins in &self.default_branch {
+            let mut instructions_default = ins.produce_wasm(producer);
+            instructions.append(&mut instructions_default);
+        }
+        instructions.push(add_end());
+        if producer.needs_comments() {
+            instructions.push(";; end of switch bucket".to_string());
+        }
+        instructions
+    }
+}
+
+impl WriteC for SwitchBucket {
+    fn produce_c(&self, producer: &CProducer, parallel: Option<bool>) -> (Vec<String>, String) {
+        use c_code_generator::*;
+        let mut switch = vec!["{".to_string(), "bool matched_case = false;".to_string()];
+        let (mut range_code, range) = self.in_range.produce_c(producer, parallel);
+        switch.append(&mut range_code);
+        switch.push(format!("if({}){{", build_call("Fr_isTrue".to_string(), vec![range])));
+        let (mut selector_code, selector) = self.selector.produce_c(producer, parallel);
+        switch.append(&mut selector_code);
+        switch.push(format!("switch({}){{", build_call("Fr_toInt".to_string(), vec![selector])));
+        for case in &self.cases {
+            switch.push(format!("case {}: {{", case.value));
+            switch.push("matched_case = true;".to_string());
+            for ins in &case.body {
+                let (mut case_code, _) = ins.produce_c(producer, parallel);
+                switch.append(&mut case_code);
+            }
+            switch.push("break;".to_string());
+            switch.push("}".to_string());
+        }
+        switch.push("}".to_string());
+        switch.push("}".to_string());
+        let mut default_body = Vec::new();
+        for ins in &self.default_branch {
+            let (mut default_code, _) = ins.produce_c(producer, parallel);
+            default_body.append(&mut default_code);
+        }
+        if !default_body.is_empty() {
+            switch.push(format!("if(!matched_case){{\n{}}}", merge_code(default_body)));
+        }
+        switch.push("}".to_string());
+        (switch, "".to_string())
+    }
+}
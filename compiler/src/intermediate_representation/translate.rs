@@ -2,6 +2,7 @@ use super::ir_interface::*;
 use crate::hir::very_concrete_program::*;
 use crate::intermediate_representation::log_bucket::LogBucketArg;
 use crate::intermediate_representation::types::SizeOption;
+use crate::ir_processing::{Pass, PassManager, VerifyInfo};
 use constant_tracking::ConstantTracker;
 use num_bigint_dig::BigInt;
 use program_structure::ast::*;
@@ -129,6 +130,12 @@ struct State {
     signal_stack: usize,
     variable_stack: usize,
     max_stack_depth: usize,
+    // slots of the parameters and constants, they are never reused
+    fixed_variable_stack: usize,
+    // first slot and size of every variable, parameter and constant
+    declared_variables: HashMap<usize, usize>,
+    // when the slots are not reused by lifetime they are released at the end of each block
+    release_block_variables: bool,
     fresh_cmp_id: usize,
     component_address_stack: usize,
     code: InstructionList,
@@ -157,6 +164,9 @@ impl State {
             component_address_stack: 0,
             fresh_cmp_id: cmp_id_offset,
             max_stack_depth: 0,
+            fixed_variable_stack: 0,
+            declared_variables: HashMap::new(),
+            release_block_variables: false,
             code: vec![],
             string_table : HashMap::new(),
         }
@@ -194,11 +204,15 @@ struct Context<'a> {
     constraint_assert_dissabled_flag: bool,
 }
 
-fn initialize_parameters(state: &mut State, params: Vec<Param>) {
+// Returns the first slot and the size of each parameter
+fn initialize_parameters(state: &mut State, params: Vec<Param>) -> Vec<(usize, usize)> {
+    let mut parameters = Vec::with_capacity(params.len());
     for p in params {
         let lengths = p.length;
         let full_size = lengths.iter().fold(1, |p, s| p * (*s));
         let address = state.reserve_variable(full_size);
+        state.declared_variables.insert(address, full_size);
+        parameters.push((address, full_size));
         let address_instruction = ValueBucket {
             line: 0,
             message_id: 0,
@@ -218,6 +232,7 @@ fn initialize_parameters(state: &mut State, params: Vec<Param>) {
                  };
         state.environment.add_variable(&p.name, symbol_info);
     }
+    parameters
 }
 
 fn initialize_constants(state: &mut State, constants: Vec<Argument>) {
@@ -225,6 +240,7 @@ fn initialize_constants(state: &mut State, constants: Vec<Argument>) {
         let dimensions = arg.lengths;
         let size = dimensions.iter().fold(1, |p, c| p * (*c));
         let address = state.reserve_variable(size);
+        state.declared_variables.insert(address, size);
         let address_instruction = ValueBucket {
             line: 0,
             message_id: 0,
@@ -565,6 +581,8 @@ fn translate_while(stmt: Statement, state: &mut State, context: &Context) {
             message_id: state.message_id,
             continue_condition: cond_translation,
             body: loop_code,
+            vectorizable: false,
+            reduction: None,
         }
         .allocate();
         state.code.push(loop_instruction);
@@ -627,6 +645,9 @@ fn translate_declaration(stmt: Statement, state: &mut State, context: &Context)
         let dimensions = meta.get_memory_knowledge().get_concrete_dimensions().to_vec();
         let size = dimensions.iter().fold(1, |p, c| p * (*c));
         let address = state.reserve_variable(size);
+        // the slots of the variables of finished blocks may be given again
+        let declared = state.declared_variables.entry(address).or_insert(0);
+        *declared = std::cmp::max(*declared, size);
         let instruction = ValueBucket {
             line: starts_at,
             message_id: state.message_id,
@@ -652,13 +673,17 @@ fn translate_declaration(stmt: Statement, state: &mut State, context: &Context)
 fn translate_block(stmt: Statement, state: &mut State, context: &Context) {
     use Statement::Block;
     if let Block { stmts, .. } = stmt {
+        // unless slot reuse is disabled the slots of the block variables are
+        // not released here, they are reused later according to their lifetime
         let save_variable_address = state.variable_stack;
         state.environment.add_variable_block();
         for s in stmts {
             translate_statement(s, state, context);
         }
         state.environment.remove_variable_block();
-        state.variable_stack = save_variable_address;
+        if state.release_block_variables {
+            state.variable_stack = save_variable_address;
+        }
     } else {
         unreachable!()
     }
@@ -704,6 +729,16 @@ fn translate_constraint_equality(stmt: Statement, state: &mut State, context: &C
             
             let lhe_pointer = translate_expression(lhe, state, context);
             let rhe_pointer = translate_expression(rhe, state, context);
+            // the values of both sides are shown if the constraint fails
+            let is_single_load = |i: &Instruction| {
+                matches!(i, Instruction::Load(b) if b.context.size == SizeOption::Single(1))
+            };
+            let (message, values) = if is_single_load(&lhe_pointer) && is_single_load(&rhe_pointer) {
+                let message = vec![AssertMessagePart::Value(0), AssertMessagePart::Value(1)];
+                (message, vec![lhe_pointer.clone(), rhe_pointer.clone()])
+            } else {
+                (Vec::new(), Vec::new())
+            };
             let stack = vec![lhe_pointer, rhe_pointer];
             let equality = ComputeBucket {
                 line: starts_at,
@@ -714,8 +749,15 @@ fn translate_constraint_equality(stmt: Statement, state: &mut State, context: &C
             }
             .allocate();
             let assert_instruction =
-                AssertBucket { line: starts_at, message_id: state.message_id, evaluate: equality }
-                    .allocate();
+                AssertBucket {
+                    line: starts_at,
+                    message_id: state.message_id,
+                    evaluate: equality,
+                    message,
+                    values,
+                    is_constraint: true,
+                }
+                .allocate();
             state.code.push(assert_instruction);
         }
         
@@ -726,17 +768,43 @@ fn translate_constraint_equality(stmt: Statement, state: &mut State, context: &C
 
 fn translate_assert(stmt: Statement, state: &mut State, context: &Context) {
     use Statement::Assert;
-    if let Assert { meta, arg, .. } = stmt {
+    // the static asserts were already checked during the constraint generation
+    if let Assert { meta, arg, message: parts, is_static: false } = stmt {
         let line = context.files.get_line(meta.start, meta.get_file_id()).unwrap();
         let code = translate_expression(arg, state, context);
-        let assert = AssertBucket { line, message_id: state.message_id, evaluate: code }.allocate();
+        let mut message = Vec::new();
+        let mut values = Vec::new();
+        for part in parts {
+            match part {
+                LogArgument::LogExp(arg) => {
+                    message.push(AssertMessagePart::Value(values.len()));
+                    values.push(translate_expression(arg, state, context));
+                }
+                LogArgument::LogStr(string) => message.push(AssertMessagePart::Str(string_id(state, string))),
+            }
+        }
+        let assert = AssertBucket {
+            line,
+            message_id: state.message_id,
+            evaluate: code,
+            message,
+            values,
+            is_constraint: false,
+        }
+        .allocate();
         state.code.push(assert);
     }
 }
 
+// The id of the string in the table, which is added if it is not there
+fn string_id(state: &mut State, string: String) -> usize {
+    let next = state.string_table.len();
+    *state.string_table.entry(string).or_insert(next)
+}
+
 fn translate_log(stmt: Statement, state: &mut State, context: &Context) {
     use Statement::LogCall;
-    if let LogCall { meta, args, .. } = stmt {
+    if let LogCall { meta, args, is_formatted } = stmt {
         let line = context.files.get_line(meta.start, meta.get_file_id()).unwrap();
         let mut logbucket_args = Vec::new();
         for arglog in args {
@@ -745,16 +813,7 @@ fn translate_log(stmt: Statement, state: &mut State, context: &Context) {
                     let code = translate_expression(arg, state, context);
                     logbucket_args.push(LogBucketArg::LogExp(code));
                 }
-                LogArgument::LogStr(exp) => {
-                    match state.string_table.get(&exp) {
-                        Some( idx) => {logbucket_args.push(LogBucketArg::LogStr(*idx));},
-                        None => {
-                            logbucket_args.push(LogBucketArg::LogStr(state.string_table.len()));
-                            state.string_table.insert(exp, state.string_table.len());
-                        },
-                    }
-                    
-                }
+                LogArgument::LogStr(exp) => logbucket_args.push(LogBucketArg::LogStr(string_id(state, exp))),
             }
         }
         
@@ -762,6 +821,7 @@ fn translate_log(stmt: Statement, state: &mut State, context: &Context) {
             line,
             message_id: state.message_id,
             argsprint: logbucket_args,
+            is_formatted,
         }.allocate();
         state.code.push(log);
     }
@@ -1889,7 +1949,9 @@ pub struct CodeInfo<'a> {
     pub string_table: HashMap<String, usize>,
     pub signals_to_tags: HashMap<Vec<String>, BigInt>,
     pub buses: &'a Vec<BusInstance>,
-    pub constraint_assert_dissabled_flag: bool
+    pub constraint_assert_dissabled_flag: bool,
+    pub prime: &'a BigInt,
+    pub passes: &'a mut PassManager,
 }
 
 pub struct CodeOutput {
@@ -1904,6 +1966,9 @@ pub struct CodeOutput {
 
 pub fn translate_code(body: Statement, code_info: CodeInfo) -> CodeOutput {
     use crate::ir_processing;
+    let passes = code_info.passes;
+    let prime = code_info.prime;
+    let name = code_info.header.clone();
     let mut state = State::new(
         code_info.message_id,
         code_info.fresh_cmp_id,
@@ -1915,7 +1980,9 @@ pub fn translate_code(body: Statement, code_info: CodeInfo) -> CodeOutput {
     initialize_components(&mut state, code_info.components);
     initialize_signals(&mut state, code_info.wires);
     initialize_constants(&mut state, code_info.constants);
-    initialize_parameters(&mut state, code_info.params);
+    let parameters = initialize_parameters(&mut state, code_info.params);
+    state.fixed_variable_stack = state.variable_stack;
+    state.release_block_variables = !passes.is_enabled(Pass::ReuseVariableSlots);
 
     let context = Context {
         files: code_info.files,
@@ -1930,17 +1997,90 @@ pub fn translate_code(body: Statement, code_info: CodeInfo) -> CodeOutput {
     create_components(&mut state, &code_info.triggers, code_info.clusters);
     translate_statement(body, &mut state, &context);
 
-    ir_processing::build_inputs_info(&mut state.code);
+    let message_id = state.message_id;
+    let signal_depth = state.signal_stack;
+    let number_of_strings = state.string_table.len();
+    let info = |stack_depth: usize, field_tracker: &FieldTracker| VerifyInfo {
+        variable_stack_depth: stack_depth,
+        signal_stack_depth: signal_depth,
+        max_message_id: message_id,
+        number_of_constants: field_tracker.next_id(),
+        number_of_strings,
+    };
+    let fixed_slots = state.fixed_variable_stack;
+    let mut variables = std::mem::take(&mut state.declared_variables);
+    let mut max_stack_depth = state.max_stack_depth;
+    passes.verify(&name, "translation", &state.code, &info(max_stack_depth, &state.field_tracker));
+    let field_tracker = &mut state.field_tracker;
+    passes.run_on(Pass::EliminateTailCalls, &name, &mut state.code, |code| {
+        ir_processing::eliminate_tail_calls(
+            code,
+            &name,
+            &parameters,
+            &mut max_stack_depth,
+            &mut variables,
+            field_tracker,
+            prime,
+        )
+    });
+    passes.verify(&name, Pass::EliminateTailCalls.name(), &state.code, &info(max_stack_depth, &state.field_tracker));
+    let stack_depth = passes
+        .run_on(Pass::ReuseVariableSlots, &name, &mut state.code, |code| {
+            ir_processing::reuse_variable_slots(code, fixed_slots, &mut variables, max_stack_depth)
+        })
+        .unwrap_or(max_stack_depth);
+    passes.verify(&name, Pass::ReuseVariableSlots.name(), &state.code, &info(stack_depth, &state.field_tracker));
+    ir_processing::build_inputs_info(&mut state.code, &mut state.field_tracker, prime);
 
     let mut code = ir_processing::reduce_intermediate_operations(state.code);
+    let field_tracker = &mut state.field_tracker;
+    passes.verify(&name, "reduce_intermediate_operations", &code, &info(stack_depth, field_tracker));
+    // the folded code may contain new copies and the propagated copies new constants
+    for _ in 0..PassManager::MAX_ROUNDS {
+        passes.run_on(Pass::PropagateCopies, &name, &mut code, ir_processing::propagate_copies);
+        passes.verify(&name, Pass::PropagateCopies.name(), &code, &info(stack_depth, field_tracker));
+        let folded = passes.run_on(Pass::FoldConstants, &name, &mut code, |code| {
+            ir_processing::fold_constants(code, field_tracker, prime)
+        });
+        passes.verify(&name, Pass::FoldConstants.name(), &code, &info(stack_depth, field_tracker));
+        let propagated = passes.run_on(Pass::PropagateConditionalConstants, &name, &mut code, |code| {
+            ir_processing::propagate_conditional_constants(code, &variables, field_tracker, prime)
+        });
+        passes.verify(&name, Pass::PropagateConditionalConstants.name(), &code, &info(stack_depth, field_tracker));
+        if folded != Some(true) && propagated != Some(true) {
+            break;
+        }
+        ir_processing::build_inputs_info(&mut code, field_tracker, prime);
+    }
+    passes.run_on(Pass::LowerArrayCopies, &name, &mut code, ir_processing::lower_array_copies);
+    passes.verify(&name, Pass::LowerArrayCopies.name(), &code, &info(stack_depth, field_tracker));
+    passes.run_on(Pass::BuildJumpTables, &name, &mut code, |code| {
+        ir_processing::build_jump_tables(code, field_tracker, prime)
+    });
+    passes.verify(&name, Pass::BuildJumpTables.name(), &code, &info(stack_depth, field_tracker));
+    passes.run_on(Pass::SelectBranches, &name, &mut code, ir_processing::select_branches);
+    passes.verify(&name, Pass::SelectBranches.name(), &code, &info(stack_depth, field_tracker));
+    passes.run_on(Pass::ReduceStrength, &name, &mut code, |code| {
+        ir_processing::reduce_strength(code, field_tracker, prime)
+    });
+    passes.verify(&name, Pass::ReduceStrength.name(), &code, &info(stack_depth, field_tracker));
+    passes.run_on(Pass::MarkVectorizableLoops, &name, &mut code, |code| {
+        ir_processing::mark_vectorizable_loops(code, field_tracker, prime)
+    });
+    passes.verify(&name, Pass::MarkVectorizableLoops.name(), &code, &info(stack_depth, field_tracker));
+    passes.run_on(Pass::FuseReductions, &name, &mut code, |code| {
+        ir_processing::fuse_reductions(code, field_tracker, prime)
+    });
+    passes.verify(&name, Pass::FuseReductions.name(), &code, &info(stack_depth, field_tracker));
     let expression_depth = ir_processing::build_auxiliary_stack(&mut code);
+    passes.verify(&name, "build_auxiliary_stack", &code, &info(stack_depth, field_tracker));
     
 
     CodeOutput {
         code,
         expression_depth,
         next_cmp_id: state.fresh_cmp_id,
-        stack_depth: state.max_stack_depth,
+        stack_depth,
         signal_depth: state.signal_stack,
         constant_tracker: state.field_tracker,
         string_table : state.string_table
@@ -1,4 +1,6 @@
-#[derive(Copy, Clone, PartialEq, Eq)]
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ValueType {
     BigInt,
     U32,
@@ -14,13 +16,13 @@ impl ToString for ValueType {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum SizeOption{
     Single(usize),
     Multiple(Vec<(usize, usize)>) // The first value indicates the cmp_id, the second the size
 }
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct InstrContext {
     pub size: SizeOption,
 }
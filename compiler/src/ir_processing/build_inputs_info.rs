@@ -1,26 +1,175 @@
+use super::field_info::FieldInfo;
+use super::fold_constants::evaluate;
 use crate::intermediate_representation::ir_interface::*;
-use std::collections::{HashSet};
+use circom_algebra::modular_arithmetic as arith;
+use num_bigint_dig::BigInt;
+use num_traits::ToPrimitive;
+use std::collections::{HashMap, HashSet};
+
+// Positions of the subcomponents in the list of subcomponents of the template
+type ComponentsSet = HashSet<usize>;
+
+pub struct ComponentsInfo<'a, 'b> {
+    pub field: &'a FieldInfo<'b>,
+    // first position and number of positions of the arrays of subcomponents,
+    // None if the position of some array is not known
+    pub arrays: Option<Vec<(usize, usize)>>,
+}
+
+impl<'a, 'b> ComponentsInfo<'a, 'b> {
+    pub fn new(instructions: &InstructionList, field: &'a FieldInfo<'b>) -> ComponentsInfo<'a, 'b> {
+        let mut info = ComponentsInfo { field, arrays: Some(Vec::new()) };
+        info.collect_arrays(instructions);
+        info
+    }
+
+    fn collect_arrays(&mut self, instructions: &InstructionList) {
+        for instruction in instructions {
+            match &**instruction {
+                Instruction::CreateCmp(b) => {
+                    let length = b.dimensions.iter().product::<usize>().max(1);
+                    let position = constant_address(&b.sub_cmp_id, self.field);
+                    match (position, &mut self.arrays) {
+                        (Some(position), Some(arrays)) if b.number_of_cmp == length => {
+                            arrays.push((position, length));
+                        }
+                        // the components of mixed arrays are created one by one, the
+                        // array is somewhere around the created position
+                        (Some(position), Some(arrays)) => {
+                            arrays.push((position.saturating_sub(length - 1), 2 * length - 1));
+                        }
+                        _ => self.arrays = None,
+                    }
+                }
+                Instruction::Branch(b) => {
+                    self.collect_arrays(&b.if_branch);
+                    self.collect_arrays(&b.else_branch);
+                }
+                Instruction::Loop(b) => self.collect_arrays(&b.body),
+                _ => {}
+            }
+        }
+    }
+
+    // The positions that an address whose value is not known may refer to
+    fn aliased_positions(&self, cmp_address: &Instruction) -> Option<ComponentsSet> {
+        let base = match cmp_address {
+            Instruction::Compute(b) if b.op == OperatorType::AddAddress => constant_address(&b.stack[0], self.field)
+                .or_else(|| constant_address(&b.stack[1], self.field))?,
+            _ => return None,
+        };
+        let mut positions = ComponentsSet::new();
+        for (start, length) in self.arrays.as_ref()? {
+            if *start <= base && base < start + length {
+                positions.extend(*start..start + length);
+            }
+        }
+        if positions.is_empty() {
+            None
+        } else {
+            Some(positions)
+        }
+    }
+}
+
+fn constant_address(instruction: &Instruction, field: &FieldInfo) -> Option<usize> {
+    match instruction {
+        Instruction::Value(v) if v.parse_as == ValueType::U32 => Some(v.value),
+        Instruction::Compute(b) => match b.op {
+            OperatorType::AddAddress => {
+                Some(constant_address(&b.stack[0], field)? + constant_address(&b.stack[1], field)?)
+            }
+            OperatorType::MulAddress => {
+                Some(constant_address(&b.stack[0], field)? * constant_address(&b.stack[1], field)?)
+            }
+            OperatorType::ToAddress => Some(field.constant(&b.stack[0])?.to_u32()? as usize),
+            _ => None,
+        },
+        _ => None,
+    }
+}
 
-type ComponentsSet = HashSet<String>;
+// Whether the condition of the loop holds when it is reached, using the
+// constants stored in the variables right before it
+fn runs_at_least_once(bucket: &LoopBucket, previous: &[InstructionPointer], info: &ComponentsInfo) -> bool {
+    let mut variables = HashMap::new();
+    for instruction in previous.iter().rev() {
+        let store = match &**instruction {
+            Instruction::Store(b) if matches!(b.dest_address_type, AddressType::Variable) => b,
+            _ => break,
+        };
+        let slot = match (&store.dest, &store.context.size) {
+            (LocationRule::Indexed { location, .. }, SizeOption::Single(1)) => constant_address(location, info.field),
+            _ => None,
+        };
+        match (slot, info.field.constant(&store.src)) {
+            (Some(slot), Some(value)) => {
+                variables.entry(slot).or_insert(value);
+            }
+            _ => break,
+        }
+    }
+    match evaluate_condition(&bucket.continue_condition, &variables, info) {
+        Some(value) => arith::as_bool(&value, info.field.prime),
+        None => false,
+    }
+}
 
+fn evaluate_condition(
+    instruction: &Instruction,
+    variables: &HashMap<usize, BigInt>,
+    info: &ComponentsInfo,
+) -> Option<BigInt> {
+    if let Some(value) = info.field.constant(instruction) {
+        return Some(value);
+    }
+    match instruction {
+        Instruction::Load(b) if matches!(b.address_type, AddressType::Variable) && b.context.size == SizeOption::Single(1) => {
+            match &b.src {
+                LocationRule::Indexed { location, .. } => {
+                    variables.get(&constant_address(location, info.field)?).cloned()
+                }
+                LocationRule::Mapped { .. } => None,
+            }
+        }
+        Instruction::Compute(b) if !b.op.is_address_op() => {
+            let operands = b
+                .stack
+                .iter()
+                .map(|operand| evaluate_condition(operand, variables, info))
+                .collect::<Option<Vec<_>>>()?;
+            evaluate(&b.op, &operands, info.field.prime)
+        }
+        _ => None,
+    }
+}
 
 pub fn visit_list(
     instructions: &mut InstructionList, 
     known_last_component: &mut ComponentsSet, 
     unknown_last_component: &mut ComponentsSet, 
     found_unknown_address: bool,
-    inside_loop: bool
+    inside_loop: bool,
+    info: &ComponentsInfo,
 )-> bool {
     let len_instructions = instructions.len();
     let mut found_unknown_aux = found_unknown_address;
     for i in 0..instructions.len(){
-        found_unknown_aux = visit_instruction(
-            &mut instructions[len_instructions - 1 - i],
-            known_last_component,
-            unknown_last_component, 
-            found_unknown_aux,
-            inside_loop
-        );
+        let (previous, rest) = instructions.split_at_mut(len_instructions - 1 - i);
+        found_unknown_aux = match &mut *rest[0] {
+            Instruction::Loop(b) => {
+                let runs = runs_at_least_once(b, previous, info);
+                visit_loop(b, known_last_component, unknown_last_component, found_unknown_aux, runs, info)
+            }
+            instruction => visit_instruction(
+                instruction,
+                known_last_component,
+                unknown_last_component,
+                found_unknown_aux,
+                inside_loop,
+                info
+            ),
+        };
     }
     found_unknown_aux
 }
@@ -30,18 +179,24 @@ pub fn visit_instruction(
     known_last_component: &mut ComponentsSet, 
     unknown_last_component: &mut ComponentsSet, 
     found_unknown_address: bool,
-    inside_loop: bool
+    inside_loop: bool,
+    info: &ComponentsInfo,
 ) ->bool {
     use Instruction::*;
     match instruction {
-        Branch(b) => visit_branch(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop),
-        Call(b) => visit_call(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop),
+        Branch(b) => visit_branch(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop, info),
+        Switch(b) => visit_switch(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop, info),
+        Call(b) => visit_call(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop, info),
         Compute(b) => visit_compute(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop),
         Load(b) => visit_load(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop),
-        Loop(b) => visit_loop(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop),
+        // the loops of a list are visited knowing the instructions before them
+        Loop(b) => visit_loop(b, known_last_component, unknown_last_component, found_unknown_address, false, info),
         Return(b) => visit_return(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop),
-        Store(b) => visit_store(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop),
+        Store(b) => visit_store(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop, info),
         Value(b) => visit_value(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop),
+        // the copies never access the signals of subcomponents
+        ArrayCopy(_) => found_unknown_address,
+        Select(_) => found_unknown_address,
         Assert(b) => visit_assert(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop),
         CreateCmp(b) => visit_create_cmp(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop),
         Log(b) => visit_log(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop),
@@ -53,7 +208,8 @@ pub fn visit_branch(
     known_last_component: &mut ComponentsSet, 
     unknown_last_component: &mut ComponentsSet,  
     found_unknown_address: bool,
-    inside_loop: bool
+    inside_loop: bool,
+    info: &ComponentsInfo,
 ) -> bool {
   let mut known_last_component_if: ComponentsSet = known_last_component.clone();
     let mut known_last_component_else: ComponentsSet = known_last_component.clone();
@@ -65,35 +221,81 @@ pub fn visit_branch(
         &mut known_last_component_if, 
         &mut unknown_last_component_if, 
         found_unknown_address,
-        inside_loop
+        inside_loop,
+        info
     );
     let found_unknown_else :bool = visit_list(
-        &mut bucket.if_branch, 
+        &mut bucket.else_branch,
         &mut known_last_component_else, 
         &mut unknown_last_component_else, 
         found_unknown_address,
-        inside_loop
+        inside_loop,
+        info
     );
 
-    let known_component_both_branches: ComponentsSet = known_last_component_if.intersection(& known_last_component_else).map(|s| s.clone()).collect();
-    let known_component_one_branch: ComponentsSet = known_last_component_if.symmetric_difference(&known_last_component_else).map(|s| s.clone()).collect();
+    let known_component_both_branches: ComponentsSet = known_last_component_if.intersection(& known_last_component_else).copied().collect();
+    let known_component_one_branch: ComponentsSet = known_last_component_if.symmetric_difference(&known_last_component_else).copied().collect();
 
-    let mut new_unknown_component: ComponentsSet = unknown_last_component_if.union(&unknown_last_component_else).map(|s| s.clone()).collect();
-    new_unknown_component = new_unknown_component.union(&known_component_one_branch).map(|s| s.clone()).collect(); 
+    let mut new_unknown_component: ComponentsSet = unknown_last_component_if.union(&unknown_last_component_else).copied().collect();
+    new_unknown_component = new_unknown_component.union(&known_component_one_branch).copied().collect(); 
 
-    let joined_unknown_component: ComponentsSet = unknown_last_component.union(&new_unknown_component).map(|s| s.clone()).collect();
+    let joined_unknown_component: ComponentsSet = unknown_last_component.union(&new_unknown_component).copied().collect();
 
-    *known_last_component = known_last_component.union(&known_component_both_branches).map(|s| s.clone()).collect();
-    *unknown_last_component =  joined_unknown_component.difference(&known_component_both_branches).map(|s| s.clone()).collect();
+    *known_last_component = known_last_component.union(&known_component_both_branches).copied().collect();
+    *unknown_last_component =  joined_unknown_component.difference(&known_component_both_branches).copied().collect();
     found_unknown_if || found_unknown_else
 }
 
+// Like a branch with one arm per case and the default branch
+pub fn visit_switch(
+    bucket: &mut SwitchBucket,
+    known_last_component: &mut ComponentsSet,
+    unknown_last_component: &mut ComponentsSet,
+    found_unknown_address: bool,
+    inside_loop: bool,
+    info: &ComponentsInfo,
+) -> bool {
+    let mut arms: Vec<&mut InstructionList> = bucket.cases.iter_mut().map(|case| &mut case.body).collect();
+    arms.push(&mut bucket.default_branch);
+    let mut found_unknown = false;
+    let mut known_all_arms: Option<ComponentsSet> = None;
+    let mut known_some_arm = ComponentsSet::new();
+    let mut new_unknown_component = ComponentsSet::new();
+    for arm in arms {
+        let mut known_last_component_arm = known_last_component.clone();
+        let mut unknown_last_component_arm = unknown_last_component.clone();
+        found_unknown |= visit_list(
+            arm,
+            &mut known_last_component_arm,
+            &mut unknown_last_component_arm,
+            found_unknown_address,
+            inside_loop,
+            info
+        );
+        known_all_arms = Some(match known_all_arms {
+            Some(known) => known.intersection(&known_last_component_arm).copied().collect(),
+            None => known_last_component_arm.clone(),
+        });
+        known_some_arm.extend(known_last_component_arm);
+        new_unknown_component.extend(unknown_last_component_arm);
+    }
+    let known_all_arms = known_all_arms.unwrap_or_default();
+    new_unknown_component.extend(known_some_arm.difference(&known_all_arms));
+
+    let joined_unknown_component: ComponentsSet = unknown_last_component.union(&new_unknown_component).copied().collect();
+
+    *known_last_component = known_last_component.union(&known_all_arms).copied().collect();
+    *unknown_last_component = joined_unknown_component.difference(&known_all_arms).copied().collect();
+    found_unknown
+}
+
 pub fn visit_call(
     bucket: &mut  CallBucket, 
     known_last_component: &mut ComponentsSet, 
     unknown_last_component: &mut ComponentsSet,  
     found_unknown_address: bool,
-    inside_loop: bool
+    inside_loop: bool,
+    info: &ComponentsInfo,
 )-> bool {
     use ReturnType::*;
     if let Final(data) = &mut bucket.return_info {
@@ -109,7 +311,8 @@ pub fn visit_call(
                 known_last_component,
                 unknown_last_component,
                 found_unknown_address,
-                inside_loop
+                inside_loop,
+                info
             )
         } else{
             found_unknown_address
@@ -139,12 +342,15 @@ pub fn visit_load(
     found_unknown_address
 }
 
+// The components stored in every iteration are stored after the code before
+// the loop if the loop runs at least once
 pub fn visit_loop(
     bucket: &mut LoopBucket,
     known_last_component: &mut ComponentsSet, 
     unknown_last_component: &mut ComponentsSet,  
     found_unknown_address: bool,
-    _inside_loop: bool
+    runs_at_least_once: bool,
+    info: &ComponentsInfo,
 )-> bool{
     let mut known_last_component_loop: ComponentsSet = known_last_component.clone();
     let mut unknown_last_component_loop: ComponentsSet = unknown_last_component.clone();
@@ -154,13 +360,16 @@ pub fn visit_loop(
         &mut known_last_component_loop, 
         &mut unknown_last_component_loop, 
         found_unknown_address,
-        true
+        true,
+        info
     );
 
-    let new_unknown_component: ComponentsSet = known_last_component_loop.union(&unknown_last_component_loop).map(|s| s.clone()).collect();
-
-    *known_last_component = known_last_component.difference(&new_unknown_component).map(|s| s.clone()).collect();
-    *unknown_last_component = unknown_last_component.union(&new_unknown_component).map(|s| s.clone()).collect();
+    let new_unknown_component: ComponentsSet = known_last_component_loop.union(&unknown_last_component_loop).copied().collect();
+    if runs_at_least_once {
+        *known_last_component = known_last_component_loop;
+    }
+    let joined_unknown_component: ComponentsSet = unknown_last_component.union(&new_unknown_component).copied().collect();
+    *unknown_last_component = joined_unknown_component.difference(known_last_component).copied().collect();
     found_unknown_address_new
 }
 
@@ -219,7 +428,8 @@ pub fn visit_store(
     known_last_component: &mut ComponentsSet, 
     unknown_last_component: &mut ComponentsSet,  
     found_unknown_address: bool,
-    inside_loop: bool
+    inside_loop: bool,
+    info: &ComponentsInfo,
 )-> bool{
     let needs_consider = match bucket.context.size{
         SizeOption::Single(value) if value == 0 =>{
@@ -233,7 +443,8 @@ pub fn visit_store(
             known_last_component,
             unknown_last_component,
             found_unknown_address,
-            inside_loop
+            inside_loop,
+            info
         )
     } else{
         found_unknown_address
@@ -246,50 +457,50 @@ pub fn visit_address_type(
     known_last_component: &mut ComponentsSet, 
     unknown_last_component: &mut ComponentsSet,  
     found_unknown_address: bool,
-    inside_loop: bool
+    inside_loop: bool,
+    info: &ComponentsInfo,
 ) -> bool {
     use AddressType::*;
     use InputInformation::*;
     use StatusInput::*;
-    use Instruction::*;
 
     if let SubcmpSignal { cmp_address, input_information ,..} = xtype {
         if let Input {..} = input_information{
-
-            if known_last_component.contains(&cmp_address.to_string()){
-                *input_information = Input{status: NoLast};
-                //println!("Poniendo un nolast en {}", cmp_address.to_string());
-                found_unknown_address
-            }
-            else if unknown_last_component.contains(&cmp_address.to_string()){
-                *input_information = Input{status: Unknown};
-                //println!("Poniendo un unknown en {}", cmp_address.to_string());
-                found_unknown_address
-            }
-            else{
-                if let Value {..} = **cmp_address{
-                    if found_unknown_address{
+            let position = constant_address(cmp_address, info.field);
+            match position {
+                Some(position) if known_last_component.contains(&position) => {
+                    *input_information = Input{status: NoLast};
+                    found_unknown_address
+                }
+                Some(position) if unknown_last_component.contains(&position) => {
+                    *input_information = Input{status: Unknown};
+                    found_unknown_address
+                }
+                Some(position) => {
+                    if found_unknown_address || inside_loop {
                         *input_information = Input{status: Unknown};
-                        //println!("Poniendo un unknown en {}", cmp_address.to_string());
                     }
                     else{
-                        if inside_loop {
-                            *input_information = Input{status: Unknown};
-                            //println!("Poniendo un unknown en {}", cmp_address.to_string());
-                        }
-                        else{
-                            *input_information = Input{status: Last};
-                            //println!("Poniendo un last en {}", cmp_address.to_string());
-                        }
+                        *input_information = Input{status: Last};
                     }
-                    known_last_component.insert(cmp_address.to_string());
-                    unknown_last_component.remove(&cmp_address.to_string());
+                    known_last_component.insert(position);
                     found_unknown_address
                 }
-                else{
+                None => {
+                    // the previous stores to the components it may refer to are
+                    // not known to be the last ones
                     *input_information = Input{status: Unknown};
-                    //println!("Poniendo un unknown en {}", cmp_address.to_string());
-                    false
+                    match info.aliased_positions(cmp_address) {
+                        Some(positions) => {
+                            for position in positions {
+                                if !known_last_component.contains(&position) {
+                                    unknown_last_component.insert(position);
+                                }
+                            }
+                            found_unknown_address
+                        }
+                        None => true,
+                    }
                 }
             }
         }
@@ -302,6 +513,95 @@ pub fn visit_address_type(
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::intermediate_representation::ir_interface::*;
+    use crate::ir_processing::test_code::*;
+
+    // input 0 of the subcomponent at cmp_address = 1
+    fn set_input(cmp_address: InstructionPointer) -> InstructionPointer {
+        let dest_address_type = AddressType::SubcmpSignal {
+            cmp_address,
+            uniform_parallel_value: Some(false),
+            is_output: false,
+            input_information: InputInformation::Input { status: StatusInput::Unknown },
+        };
+        store(dest_address_type, value(0), value(1))
+    }
+
+    fn statuses(field: &mut Field, mut code: InstructionList) -> Vec<String> {
+        super::super::build_inputs_info(&mut code, &mut field.tracker, &field.prime);
+        let mut statuses = Vec::new();
+        collect_statuses(&code, &mut statuses);
+        statuses
+    }
+
+    fn collect_statuses(code: &InstructionList, statuses: &mut Vec<String>) {
+        for i in code {
+            match &**i {
+                Instruction::Store(b) => {
+                    if let AddressType::SubcmpSignal { input_information, .. } = &b.dest_address_type {
+                        statuses.push(input_information.to_string());
+                    }
+                }
+                Instruction::Branch(b) => {
+                    collect_statuses(&b.if_branch, statuses);
+                    collect_statuses(&b.else_branch, statuses);
+                }
+                Instruction::Loop(b) => collect_statuses(&b.body, statuses),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn only_the_last_store_runs_the_component() {
+        let mut field = Field::new();
+        let code = vec![set_input(value(0)), set_input(value(1)), set_input(value(0))];
+        assert_eq!(statuses(&mut field, code), ["NO_LAST", "LAST", "LAST"]);
+    }
 
+    #[test]
+    fn stores_before_a_loop_that_runs_are_not_the_last() {
+        let mut field = Field::new();
+        let condition = compute(OperatorType::Lesser, vec![load_var(var(0)), field.constant(2)]);
+        let code = vec![
+            set_input(value(0)),
+            set(var(0), field.constant(0)),
+            repeat(condition, vec![set_input(value(0))]),
+        ];
+        assert_eq!(statuses(&mut field, code), ["NO_LAST", "UNKNOWN"]);
+    }
 
+    #[test]
+    fn stores_before_a_loop_that_may_not_run_are_unknown() {
+        let mut field = Field::new();
+        let code = vec![set_input(value(0)), repeat(load_signal(value(1)), vec![set_input(value(0))])];
+        assert_eq!(statuses(&mut field, code), ["UNKNOWN", "UNKNOWN"]);
+    }
+
+    #[test]
+    fn stores_before_a_branch_that_stores_in_one_arm_are_unknown() {
+        let mut field = Field::new();
+        let code = vec![
+            set_input(value(0)),
+            set_input(value(1)),
+            branch(load_signal(value(1)), vec![set_input(value(0))], vec![set_input(value(1))]),
+        ];
+        assert_eq!(statuses(&mut field, code), ["UNKNOWN", "UNKNOWN", "LAST", "LAST"]);
+    }
 
+    #[test]
+    fn stores_at_unknown_positions_only_affect_their_array() {
+        let mut field = Field::new();
+        let dynamic = compute(OperatorType::AddAddress, vec![load_var(var(0)), value(0)]);
+        let code = vec![
+            create(1, "Sub_1", value(0), vec![2]),
+            create(2, "Other_2", value(2), Vec::new()),
+            set_input(value(0)),
+            set_input(value(2)),
+            set_input(dynamic),
+        ];
+        assert_eq!(statuses(&mut field, code), ["UNKNOWN", "LAST", "UNKNOWN"]);
+    }
+}
@@ -0,0 +1,252 @@
+use super::field_info::FieldInfo;
+use crate::intermediate_representation::ir_interface::*;
+use num_bigint_dig::BigInt;
+use num_traits::ToPrimitive;
+
+// Chains shorter than this are left as branches
+const MIN_CASES: usize = 4;
+
+// Replaces the chains of branches that compare the same selector with
+// constants, if the constants are close enough to build a table with them
+pub fn build_list(instructions: &mut InstructionList, info: &mut FieldInfo) {
+    let mut built = InstructionList::with_capacity(instructions.len());
+    for i in std::mem::take(instructions) {
+        let mut instruction = match *i {
+            Instruction::Branch(b) if chain_length(&b, info).is_some() => {
+                let length = chain_length(&b, info).unwrap();
+                build_switch(b, length, info).into_instruction()
+            }
+            other => other,
+        };
+        build_nested(&mut instruction, info);
+        built.push(instruction.allocate());
+    }
+    *instructions = built;
+}
+
+fn build_nested(instruction: &mut Instruction, info: &mut FieldInfo) {
+    use Instruction::*;
+    match instruction {
+        Branch(b) => {
+            build_list(&mut b.if_branch, info);
+            build_list(&mut b.else_branch, info);
+        }
+        Switch(b) => {
+            for case in &mut b.cases {
+                build_list(&mut case.body, info);
+            }
+            build_list(&mut b.default_branch, info);
+        }
+        Loop(b) => build_list(&mut b.body, info),
+        _ => {}
+    }
+}
+
+// The selector is evaluated once instead of once per comparison
+fn has_no_effects(instruction: &Instruction) -> bool {
+    use Instruction::*;
+    match instruction {
+        Value(_) => true,
+        Load(b) => {
+            let location = match &b.src {
+                LocationRule::Indexed { location, .. } => has_no_effects(location),
+                LocationRule::Mapped { indexes, .. } => indexes.iter().all(|access| match access {
+                    AccessType::Indexed(instr) => instr.indexes.iter().all(|i| has_no_effects(i)),
+                    AccessType::Qualified(_) => true,
+                }),
+            };
+            let address = match &b.address_type {
+                AddressType::SubcmpSignal { cmp_address, .. } => has_no_effects(cmp_address),
+                _ => true,
+            };
+            location && address
+        }
+        Compute(b) => b.stack.iter().all(|i| has_no_effects(i)),
+        _ => false,
+    }
+}
+
+// The selector and the constant compared by the condition of the branch
+fn case_of<'a>(bucket: &'a BranchBucket, info: &FieldInfo) -> Option<(&'a Instruction, usize)> {
+    let compute = match &*bucket.cond {
+        Instruction::Compute(c) if c.op == OperatorType::Eq(SizeOption::Single(1)) => c,
+        _ => return None,
+    };
+    let (selector, value) = match (info.constant(&compute.stack[0]), info.constant(&compute.stack[1])) {
+        (None, Some(value)) => (&*compute.stack[0], value),
+        (Some(value), None) => (&*compute.stack[1], value),
+        _ => return None,
+    };
+    let value = value.to_i32()? as usize;
+    if has_no_effects(selector) {
+        Some((selector, value))
+    } else {
+        None
+    }
+}
+
+// Each comparison of the chain keeps the line of its own condition
+fn same_selector(a: &Instruction, b: &Instruction) -> bool {
+    fn remove_lines(value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(fields) => {
+                fields.remove("line");
+                fields.values_mut().for_each(remove_lines);
+            }
+            serde_json::Value::Array(values) => values.iter_mut().for_each(remove_lines),
+            _ => {}
+        }
+    }
+    let mut a = serde_json::to_value(a).unwrap();
+    let mut b = serde_json::to_value(b).unwrap();
+    remove_lines(&mut a);
+    remove_lines(&mut b);
+    a == b
+}
+
+// The number of branches of the chain starting at the bucket, if it can be
+// turned into a switch
+fn chain_length(bucket: &BranchBucket, info: &FieldInfo) -> Option<usize> {
+    let (selector, value) = case_of(bucket, info)?;
+    let mut values = vec![value];
+    let mut current = bucket;
+    while let [next] = current.else_branch.as_slice() {
+        match &**next {
+            Instruction::Branch(next) => match case_of(next, info) {
+                Some((next_selector, value)) if same_selector(selector, next_selector) => {
+                    values.push(value);
+                    current = next;
+                }
+                _ => break,
+            },
+            _ => break,
+        }
+    }
+    let length = values.len();
+    values.sort_unstable();
+    values.dedup();
+    let spread = values[values.len() - 1] - values[0] + 1;
+    if length >= MIN_CASES && values.len() == length && spread <= 2 * length {
+        Some(length)
+    } else {
+        None
+    }
+}
+
+fn compute(line: usize, message_id: usize, op: OperatorType, stack: Vec<InstructionPointer>) -> InstructionPointer {
+    ComputeBucket { line, message_id, op, op_aux_no: 0, stack }.allocate()
+}
+
+fn build_switch(mut bucket: BranchBucket, length: usize, info: &mut FieldInfo) -> SwitchBucket {
+    let selector = Box::new(case_of(&bucket, info).unwrap().0.clone());
+    let mut cases = Vec::with_capacity(length);
+    let (line, message_id) = (bucket.line, bucket.message_id);
+    for i in 0..length {
+        let value = case_of(&bucket, info).unwrap().1;
+        cases.push(SwitchCase { value, body: std::mem::take(&mut bucket.if_branch) });
+        if i + 1 < length {
+            bucket = match *bucket.else_branch.pop().unwrap() {
+                Instruction::Branch(next) => next,
+                _ => unreachable!(),
+            };
+        }
+    }
+    cases.sort_by_key(|case| case.value);
+    let first = info.constant_bucket(&BigInt::from(cases[0].value), line, message_id);
+    let last = info.constant_bucket(&BigInt::from(cases[length - 1].value), line, message_id);
+    let lower_bound = compute(line, message_id, OperatorType::GreaterEq, vec![selector.clone(), first]);
+    let upper_bound = compute(line, message_id, OperatorType::LesserEq, vec![selector.clone(), last]);
+    let in_range = compute(line, message_id, OperatorType::BoolAnd, vec![lower_bound, upper_bound]);
+    SwitchBucket { line, message_id, in_range, selector, cases, default_branch: bucket.else_branch }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::build_list;
+    use crate::intermediate_representation::ir_interface::*;
+    use crate::ir_processing::test_code::*;
+
+    fn selector() -> InstructionPointer {
+        load_var(var(0))
+    }
+
+    // if (selector == values[0]) { signal 0 = 0 } else if ... else { signal 0 = 100 }
+    fn chain(field: &mut Field, values: &[i64]) -> InstructionPointer {
+        let mut else_branch = vec![set_signal(value(0), value(100))];
+        for (i, v) in values.iter().enumerate().rev() {
+            let cond = compute(OperatorType::Eq(SizeOption::Single(1)), vec![selector(), field.constant(*v)]);
+            else_branch = vec![branch(cond, vec![set_signal(value(0), value(i))], else_branch)];
+        }
+        else_branch.pop().unwrap()
+    }
+
+    fn built(field: &mut Field, mut code: InstructionList) -> InstructionList {
+        build_list(&mut code, &mut field.info());
+        code
+    }
+
+    // The values and bodies of the cases and the default branch of the switch
+    fn switch(instruction: &Instruction) -> (Vec<(usize, Vec<String>)>, Vec<String>) {
+        match instruction {
+            Instruction::Switch(b) => {
+                assert_eq!(b.selector.to_string(), selector().to_string());
+                let cases = b.cases.iter().map(|case| (case.value, text(&case.body))).collect();
+                (cases, text(&b.default_branch))
+            }
+            other => panic!("not a switch: {}", other.to_string()),
+        }
+    }
+
+    #[test]
+    fn long_chains_over_close_values_are_switches() {
+        let mut field = Field::new();
+        let code = vec![chain(&mut field, &[2, 0, 3, 1])];
+        let code = built(&mut field, code);
+        let (cases, default_branch) = switch(&code[0]);
+        let case = |v: usize, body: usize| (v, text(&vec![set_signal(value(0), value(body))]));
+        assert_eq!(cases, [case(0, 1), case(1, 3), case(2, 0), case(3, 2)]);
+        assert_eq!(default_branch, text(&vec![set_signal(value(0), value(100))]));
+    }
+
+    #[test]
+    fn short_chains_are_kept() {
+        let mut field = Field::new();
+        let code = vec![chain(&mut field, &[0, 1, 2])];
+        let expected = text(&code);
+        assert_eq!(text(&built(&mut field, code)), expected);
+    }
+
+    #[test]
+    fn chains_over_spread_or_repeated_values_are_kept() {
+        let mut field = Field::new();
+        for values in [[0, 10, 20, 30], [0, 1, 1, 2]] {
+            let code = vec![chain(&mut field, &values)];
+            let expected = text(&code);
+            assert_eq!(text(&built(&mut field, code)), expected);
+        }
+    }
+
+    #[test]
+    fn negative_constants_are_not_cases() {
+        let mut field = Field::new();
+        let code = vec![chain(&mut field, &[-1, 0, 1, 2])];
+        let expected = text(&code);
+        assert_eq!(text(&built(&mut field, code)), expected);
+    }
+
+    #[test]
+    fn chains_end_where_the_selector_changes() {
+        let mut field = Field::new();
+        let mut code = vec![chain(&mut field, &[4, 0, 1, 2, 3])];
+        if let Instruction::Branch(b) = &mut *code[0] {
+            if let Instruction::Compute(c) = &mut *b.cond {
+                c.stack[0] = load_var(var(1));
+            }
+        }
+        let code = built(&mut field, code);
+        match &*code[0] {
+            Instruction::Branch(b) => assert_eq!(switch(&b.else_branch[0]).0.len(), 4),
+            other => panic!("not a branch: {}", other.to_string()),
+        }
+    }
+}
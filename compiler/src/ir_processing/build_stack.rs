@@ -13,12 +13,15 @@ pub fn build_instruction(instruction: &mut Instruction, fresh: usize) -> usize {
     use Instruction::*;
     match instruction {
         Branch(b) => build_branch(b, fresh),
+        Switch(b) => build_switch(b, fresh),
         Call(b) => build_call(b, fresh),
         Compute(b) => build_compute(b, fresh),
+        Select(b) => build_select(b, fresh).0,
         Load(b) => build_load(b, fresh).0,
         Loop(b) => build_loop(b, fresh),
         Return(b) => build_return(b, fresh),
         Store(b) => build_store(b, fresh),
+        ArrayCopy(b) => build_array_copy(b, fresh),
         Value(b) => build_value(b, fresh),
         Assert(b) => build_assert(b, fresh),
         CreateCmp(b) => build_create_cmp(b, fresh),
@@ -33,6 +36,15 @@ pub fn build_branch(bucket: &mut BranchBucket, fresh: usize) -> usize {
     std::cmp::max(in_cond, std::cmp::max(in_if, in_else))
 }
 
+pub fn build_switch(bucket: &mut SwitchBucket, fresh: usize) -> usize {
+    let in_range = build_instruction(&mut bucket.in_range, fresh);
+    let mut max_stack = std::cmp::max(in_range, build_instruction(&mut bucket.selector, fresh));
+    for case in &mut bucket.cases {
+        max_stack = std::cmp::max(max_stack, build_list(&mut case.body, fresh));
+    }
+    std::cmp::max(max_stack, build_list(&mut bucket.default_branch, fresh))
+}
+
 pub fn build_call(bucket: &mut CallBucket, mut fresh: usize) -> usize {
     use ReturnType::*;
     let mut max_stack = fresh;
@@ -65,6 +77,8 @@ pub fn build_instruction_compute(instruction: &mut Instruction, fresh: usize) ->
             build_load(b, fresh), // returns the number of expaux needed
         Value(b) => 
             (build_value(b, fresh), fresh + 1), // needs 1 expaux to store the result
+        Select(b) =>
+            build_select(b, fresh), // the result is one of the values
         _ => unreachable!(), // only possible instructions inside a compute
     }
 }
@@ -91,6 +105,14 @@ pub fn build_compute(bucket: &mut ComputeBucket, mut fresh: usize) -> usize {
 
 
 
+// the condition and both values are alive when the value is selected
+pub fn build_select(bucket: &mut SelectBucket, fresh: usize) -> (usize, usize) {
+    let (d_0, fresh) = build_instruction_compute(&mut bucket.cond, fresh);
+    let (d_1, fresh) = build_instruction_compute(&mut bucket.if_true, fresh);
+    let (d_2, fresh) = build_instruction_compute(&mut bucket.if_false, fresh);
+    (std::cmp::max(d_0, std::cmp::max(d_1, d_2)), fresh)
+}
+
 pub fn build_load(bucket: &mut LoadBucket, fresh: usize) -> (usize, usize) {
     let (_v0, f0) = build_address_type(&mut bucket.address_type, fresh);
     let (v1, f1) = build_location(&mut bucket.src, f0);
@@ -126,7 +148,11 @@ pub fn build_log(bucket: &mut LogBucket, fresh: usize) -> usize {
 }
 
 pub fn build_assert(bucket: &mut AssertBucket, fresh: usize) -> usize {
-    build_instruction(&mut bucket.evaluate, fresh)
+    let mut in_assert = build_instruction(&mut bucket.evaluate, fresh);
+    for v in &mut bucket.values {
+        in_assert = std::cmp::max(in_assert, build_instruction(v, fresh));
+    }
+    in_assert
 }
 
 pub fn build_store(bucket: &mut StoreBucket, fresh: usize) -> usize {
@@ -136,6 +162,12 @@ pub fn build_store(bucket: &mut StoreBucket, fresh: usize) -> usize {
     std::cmp::max(std::cmp::max(f_0, f_1), f_2)
 }
 
+pub fn build_array_copy(bucket: &mut ArrayCopyBucket, fresh: usize) -> usize {
+    let (f_0, fresh) = build_instruction_address(&mut bucket.dest, fresh);
+    let (f_1, _) = build_instruction_address(&mut bucket.src, fresh);
+    std::cmp::max(f_0, f_1)
+}
+
 pub fn build_value(bucket: &mut ValueBucket, fresh: usize) -> usize {
     bucket.op_aux_no = fresh;
     fresh + 1
@@ -0,0 +1,184 @@
+use crate::intermediate_representation::ir_interface::*;
+
+// The subcomponents accessed by an instruction, None when some of them are
+// only known when the code is run
+type Accessed = Option<Vec<usize>>;
+
+// For each instruction of the body, the subcomponents that are not accessed
+// by the following instructions
+pub fn last_uses(body: &InstructionList, number_of_components: usize) -> Vec<Vec<usize>> {
+    let mut last_use = vec![None; number_of_components];
+    for (index, instruction) in body.iter().enumerate() {
+        let mut accessed = Some(Vec::new());
+        visit_instruction(instruction, &mut accessed);
+        match accessed {
+            Some(components) => {
+                for component in components.into_iter().filter(|c| *c < number_of_components) {
+                    last_use[component] = Some(index);
+                }
+            }
+            None => last_use.iter_mut().for_each(|l| *l = Some(index)),
+        }
+    }
+    let mut released = vec![Vec::new(); body.len()];
+    for (component, index) in last_use.into_iter().enumerate() {
+        if let Some(index) = index {
+            released[index].push(component);
+        }
+    }
+    released
+}
+
+fn access(cmp_address: &Instruction, number_of_cmp: usize, accessed: &mut Accessed) {
+    match cmp_address {
+        Instruction::Value(v) if v.parse_as == ValueType::U32 => {
+            if let Some(components) = accessed {
+                components.extend(v.value..v.value + number_of_cmp);
+            }
+        }
+        _ => *accessed = None,
+    }
+}
+
+fn visit_list(instructions: &InstructionList, accessed: &mut Accessed) {
+    for i in instructions {
+        visit_instruction(i, accessed);
+    }
+}
+
+fn visit_address(address_type: &AddressType, location: &LocationRule, accessed: &mut Accessed) {
+    if let AddressType::SubcmpSignal { cmp_address, .. } = address_type {
+        access(cmp_address, 1, accessed);
+        visit_instruction(cmp_address, accessed);
+    }
+    match location {
+        LocationRule::Indexed { location, .. } => visit_instruction(location, accessed),
+        LocationRule::Mapped { indexes, .. } => {
+            for index in indexes {
+                if let AccessType::Indexed(info) = index {
+                    visit_list(&info.indexes, accessed);
+                }
+            }
+        }
+    }
+}
+
+fn visit_instruction(instruction: &Instruction, accessed: &mut Accessed) {
+    use Instruction::*;
+    match instruction {
+        Value(_) => {}
+        Load(b) => visit_address(&b.address_type, &b.src, accessed),
+        Store(b) => {
+            visit_address(&b.dest_address_type, &b.dest, accessed);
+            visit_instruction(&b.src, accessed);
+        }
+        ArrayCopy(b) => {
+            if let AddressType::SubcmpSignal { cmp_address, .. } = &b.dest_address_type {
+                access(cmp_address, 1, accessed);
+            }
+            if let AddressType::SubcmpSignal { cmp_address, .. } = &b.src_address_type {
+                access(cmp_address, 1, accessed);
+            }
+            visit_instruction(&b.dest, accessed);
+            visit_instruction(&b.src, accessed);
+        }
+        Compute(b) => visit_list(&b.stack, accessed),
+        Select(b) => {
+            visit_instruction(&b.cond, accessed);
+            visit_instruction(&b.if_true, accessed);
+            visit_instruction(&b.if_false, accessed);
+        }
+        Call(b) => {
+            visit_list(&b.arguments, accessed);
+            if let ReturnType::Final(data) = &b.return_info {
+                visit_address(&data.dest_address_type, &data.dest, accessed);
+            }
+        }
+        Branch(b) => {
+            visit_instruction(&b.cond, accessed);
+            visit_list(&b.if_branch, accessed);
+            visit_list(&b.else_branch, accessed);
+        }
+        Switch(b) => {
+            visit_instruction(&b.selector, accessed);
+            for case in &b.cases {
+                visit_list(&case.body, accessed);
+            }
+            visit_list(&b.default_branch, accessed);
+        }
+        Return(b) => visit_instruction(&b.value, accessed),
+        Assert(b) => {
+            visit_instruction(&b.evaluate, accessed);
+            visit_list(&b.values, accessed);
+        }
+        Log(b) => {
+            for arg in &b.argsprint {
+                if let LogBucketArg::LogExp(exp) = arg {
+                    visit_instruction(exp, accessed);
+                }
+            }
+        }
+        Loop(b) => {
+            visit_instruction(&b.continue_condition, accessed);
+            visit_list(&b.body, accessed);
+        }
+        CreateCmp(b) => access(&b.sub_cmp_id, b.number_of_cmp, accessed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::last_uses;
+    use crate::intermediate_representation::ir_interface::*;
+    use crate::ir_processing::test_code::*;
+
+    fn set_input(component: usize) -> InstructionPointer {
+        store(subcomponent(value(component)), value(1), value(1))
+    }
+
+    fn read_output(component: usize) -> InstructionPointer {
+        set_signal(value(0), load(subcomponent(value(component)), value(0)))
+    }
+
+    #[test]
+    fn components_are_released_after_their_last_access() {
+        let code = vec![
+            create(1, "A_1", value(0), Vec::new()),
+            create(2, "B_2", value(1), Vec::new()),
+            set_input(0),
+            set_input(1),
+            read_output(1),
+            read_output(0),
+        ];
+        let released: Vec<Vec<usize>> = vec![vec![], vec![], vec![], vec![], vec![1], vec![0]];
+        assert_eq!(last_uses(&code, 2), released);
+    }
+
+    #[test]
+    fn each_component_of_an_array_is_released_after_its_own_last_access() {
+        let code = vec![create(1, "A_1", value(0), vec![3]), set_input(1), read_output(0)];
+        assert_eq!(last_uses(&code, 3), [vec![2], vec![1], vec![0]]);
+    }
+
+    #[test]
+    fn accesses_at_unknown_positions_keep_every_component() {
+        let dynamic = compute(OperatorType::AddAddress, vec![load_var(var(0)), value(0)]);
+        let code = vec![
+            create(1, "A_1", value(0), vec![2]),
+            read_output(0),
+            set_signal(value(0), load(subcomponent(dynamic), value(0))),
+            set_signal(value(1), value(1)),
+        ];
+        assert_eq!(last_uses(&code, 2), [vec![], vec![], vec![0, 1], vec![]]);
+    }
+
+    #[test]
+    fn components_accessed_inside_a_loop_are_released_after_it() {
+        let code = vec![
+            create(1, "A_1", value(0), Vec::new()),
+            repeat(load_var(var(0)), vec![set_input(0), read_output(0)]),
+            set_signal(value(1), value(1)),
+        ];
+        assert_eq!(last_uses(&code, 1), [vec![], vec![0], vec![]]);
+    }
+}
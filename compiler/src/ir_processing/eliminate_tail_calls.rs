@@ -0,0 +1,243 @@
+use crate::intermediate_representation::ir_interface::*;
+
+// The first slot and the size of each parameter of the function, and the
+// slots where the new values of the parameters are kept
+pub struct TailCallInfo<'a> {
+    pub header: &'a str,
+    pub parameters: &'a [(usize, usize)],
+    pub temporaries: Vec<usize>,
+}
+
+// Every path of the code ends in a return, so the loop built around it is
+// only repeated by the eliminated calls
+pub fn always_returns(instructions: &InstructionList) -> bool {
+    match instructions.last().map(|i| &**i) {
+        Some(Instruction::Return(_)) => true,
+        Some(Instruction::Branch(b)) => always_returns(&b.if_branch) && always_returns(&b.else_branch),
+        _ => false,
+    }
+}
+
+pub fn has_tail_calls(instructions: &InstructionList, info: &TailCallInfo) -> bool {
+    match instructions.last().map(|i| &**i) {
+        Some(Instruction::Branch(b)) => has_tail_calls(&b.if_branch, info) || has_tail_calls(&b.else_branch, info),
+        _ => tail_call(instructions, info).is_some(),
+    }
+}
+
+fn constant_slot(location: &LocationRule) -> Option<usize> {
+    match location {
+        LocationRule::Indexed { location, .. } => match &**location {
+            Instruction::Value(v) if v.parse_as == ValueType::U32 => Some(v.value),
+            _ => None,
+        },
+        LocationRule::Mapped { .. } => None,
+    }
+}
+
+fn returned_value(instruction: &Instruction) -> Option<&Instruction> {
+    match instruction {
+        Instruction::Return(b) => Some(&b.value),
+        _ => None,
+    }
+}
+
+fn recursive_call<'a>(instruction: &'a Instruction, info: &TailCallInfo) -> Option<&'a CallBucket> {
+    match instruction {
+        Instruction::Call(c) if c.symbol == info.header => Some(c),
+        _ => None,
+    }
+}
+
+// A call that stores its result in a variable which is then returned
+fn stored_call<'a>(call: &'a Instruction, ret: &Instruction, info: &TailCallInfo) -> Option<&'a CallBucket> {
+    let call = recursive_call(call, info)?;
+    let dest = match &call.return_info {
+        ReturnType::Final(data) if matches!(data.dest_address_type, AddressType::Variable) => constant_slot(&data.dest)?,
+        _ => return None,
+    };
+    match returned_value(ret)? {
+        Instruction::Load(b) if matches!(b.address_type, AddressType::Variable) && constant_slot(&b.src) == Some(dest) => {
+            Some(call)
+        }
+        _ => None,
+    }
+}
+
+// The recursive call whose result is returned at the end of the code, either
+// directly or through a variable, and the number of instructions it takes
+fn tail_call<'a>(instructions: &'a InstructionList, info: &TailCallInfo) -> Option<(&'a CallBucket, usize)> {
+    let returned = instructions.last().and_then(|i| returned_value(i)).and_then(|v| recursive_call(v, info));
+    if let Some(call) = returned {
+        return Some((call, 1));
+    }
+    match instructions.as_slice() {
+        [.., call, ret] => stored_call(call, ret, info).map(|call| (call, 2)),
+        _ => None,
+    }
+}
+
+// Replaces the returns of a recursive call by the update of the parameters
+pub fn eliminate_list(instructions: &mut InstructionList, info: &TailCallInfo) {
+    if let Some((call, length)) = tail_call(instructions, info) {
+        let mut updates = update_parameters(call, info);
+        instructions.truncate(instructions.len() - length);
+        instructions.append(&mut updates);
+    } else if let Some(Instruction::Branch(b)) = instructions.last_mut().map(|i| &mut **i) {
+        eliminate_list(&mut b.if_branch, info);
+        eliminate_list(&mut b.else_branch, info);
+    }
+}
+
+fn variable(slot: usize, line: usize) -> LocationRule {
+    let location = ValueBucket { line, message_id: 0, parse_as: ValueType::U32, op_aux_no: 0, value: slot };
+    LocationRule::Indexed { location: location.allocate(), template_header: None }
+}
+
+fn store(call: &CallBucket, slot: usize, size: usize, src: InstructionPointer) -> InstructionPointer {
+    StoreBucket {
+        line: call.line,
+        message_id: call.message_id,
+        context: InstrContext { size: SizeOption::Single(size) },
+        src_context: InstrContext { size: SizeOption::Single(size) },
+        dest_is_output: false,
+        dest_address_type: AddressType::Variable,
+        src_address_type: None,
+        dest: variable(slot, call.line),
+        src,
+    }
+    .allocate()
+}
+
+// With several parameters all the arguments are evaluated before the
+// parameters they may read are updated
+fn update_parameters(call: &CallBucket, info: &TailCallInfo) -> InstructionList {
+    let mut updates = InstructionList::new();
+    if let [(slot, size)] = info.parameters {
+        updates.push(store(call, *slot, *size, call.arguments[0].clone()));
+        return updates;
+    }
+    for (arg, (&temporary, &(_, size))) in call.arguments.iter().zip(info.temporaries.iter().zip(info.parameters)) {
+        updates.push(store(call, temporary, size, arg.clone()));
+    }
+    for (&temporary, &(slot, size)) in info.temporaries.iter().zip(info.parameters) {
+        let load = LoadBucket {
+            line: call.line,
+            message_id: call.message_id,
+            address_type: AddressType::Variable,
+            src: variable(temporary, call.line),
+            context: InstrContext { size: SizeOption::Single(size) },
+        };
+        updates.push(store(call, slot, size, load.allocate()));
+    }
+    updates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{always_returns, eliminate_list, has_tail_calls, TailCallInfo};
+    use crate::intermediate_representation::ir_interface::*;
+    use crate::ir_processing::test_code::*;
+
+    fn call(symbol: &str, arguments: InstructionList, return_info: ReturnType) -> InstructionPointer {
+        let argument_types = arguments.iter().map(|_| single()).collect();
+        CallBucket { line: 0, message_id: 0, symbol: symbol.to_string(), argument_types, arguments, arena_size: 2, return_info }
+            .allocate()
+    }
+
+    fn intermediate() -> ReturnType {
+        ReturnType::Intermediate { op_aux_no: 0 }
+    }
+
+    fn ret(value: InstructionPointer) -> InstructionPointer {
+        ReturnBucket { line: 0, message_id: 0, with_size: 1, value }.allocate()
+    }
+
+    fn sub(a: InstructionPointer, b: InstructionPointer) -> InstructionPointer {
+        compute(OperatorType::Sub, vec![a, b])
+    }
+
+    fn mul(a: InstructionPointer, b: InstructionPointer) -> InstructionPointer {
+        compute(OperatorType::Mul, vec![a, b])
+    }
+
+    // if (n == 0) { return acc } else { <else_branch> }, with n in 0 and acc in 1
+    fn factorial(else_branch: InstructionList) -> InstructionList {
+        let is_zero = compute(OperatorType::Eq(SizeOption::Single(1)), vec![load_var(var(0)), value(0)]);
+        vec![branch(is_zero, vec![ret(load_var(var(1)))], else_branch)]
+    }
+
+    fn recursive_call() -> InstructionPointer {
+        let arguments = vec![sub(load_var(var(0)), value(1)), mul(load_var(var(1)), load_var(var(0)))];
+        call("fact_0", arguments, intermediate())
+    }
+
+    fn info() -> TailCallInfo<'static> {
+        TailCallInfo { header: "fact_0", parameters: &[(0, 1), (1, 1)], temporaries: vec![2, 3] }
+    }
+
+    fn eliminated(mut code: InstructionList, info: &TailCallInfo) -> Vec<String> {
+        assert!(always_returns(&code));
+        assert!(has_tail_calls(&code, info));
+        eliminate_list(&mut code, info);
+        text(&code)
+    }
+
+    fn kept(code: InstructionList, info: &TailCallInfo) {
+        assert!(!has_tail_calls(&code, info));
+        let expected = text(&code);
+        let mut code = code;
+        eliminate_list(&mut code, info);
+        assert_eq!(text(&code), expected);
+    }
+
+    #[test]
+    fn returned_calls_update_the_parameters() {
+        let code = factorial(vec![ret(recursive_call())]);
+        // the arguments are evaluated before any parameter is written
+        let expected = factorial(vec![
+            set(var(2), sub(load_var(var(0)), value(1))),
+            set(var(3), mul(load_var(var(1)), load_var(var(0)))),
+            set(var(0), load_var(var(2))),
+            set(var(1), load_var(var(3))),
+        ]);
+        assert_eq!(eliminated(code, &info()), text(&expected));
+    }
+
+    #[test]
+    fn a_single_parameter_is_updated_directly() {
+        let info = TailCallInfo { header: "f_0", parameters: &[(0, 1)], temporaries: Vec::new() };
+        let code = vec![ret(call("f_0", vec![sub(load_var(var(0)), value(1))], intermediate()))];
+        let expected = vec![set(var(0), sub(load_var(var(0)), value(1)))];
+        assert_eq!(eliminated(code, &info), text(&expected));
+    }
+
+    #[test]
+    fn calls_stored_in_the_returned_variable_are_tail_calls() {
+        let stored = ReturnType::Final(FinalData {
+            context: single(),
+            dest_is_output: false,
+            dest_address_type: AddressType::Variable,
+            dest: location(var(4)),
+        });
+        let arguments = vec![sub(load_var(var(0)), value(1)), load_var(var(1))];
+        let code = factorial(vec![call("fact_0", arguments, stored), ret(load_var(var(4)))]);
+        let expected = factorial(vec![
+            set(var(2), sub(load_var(var(0)), value(1))),
+            set(var(3), load_var(var(1))),
+            set(var(0), load_var(var(2))),
+            set(var(1), load_var(var(3))),
+        ]);
+        assert_eq!(eliminated(code, &info()), text(&expected));
+    }
+
+    #[test]
+    fn calls_whose_result_is_used_are_kept() {
+        kept(factorial(vec![ret(mul(recursive_call(), value(2)))]), &info());
+    }
+
+    #[test]
+    fn calls_to_other_functions_are_kept() {
+        kept(factorial(vec![ret(call("other_0", vec![value(1), value(2)], intermediate()))]), &info());
+    }
+}
@@ -0,0 +1,31 @@
+use crate::intermediate_representation::ir_interface::*;
+use crate::intermediate_representation::translate::FieldTracker;
+use num_bigint_dig::BigInt;
+
+pub struct FieldInfo<'a> {
+    pub field_tracker: &'a mut FieldTracker,
+    pub prime: &'a BigInt,
+}
+
+impl FieldInfo<'_> {
+    // The value of a field constant, reduced to the range [0, prime)
+    pub fn constant(&self, instruction: &Instruction) -> Option<BigInt> {
+        if let Instruction::Value(v) = instruction {
+            if v.parse_as == ValueType::BigInt {
+                return self.constant_value(v.value);
+            }
+        }
+        None
+    }
+
+    pub fn constant_value(&self, id: usize) -> Option<BigInt> {
+        let constant = self.field_tracker.get_constant(id)?;
+        let value = BigInt::parse_bytes(constant.as_bytes(), 10)?;
+        Some(((value % self.prime) + self.prime) % self.prime)
+    }
+
+    pub fn constant_bucket(&mut self, value: &BigInt, line: usize, message_id: usize) -> InstructionPointer {
+        let cid = self.field_tracker.insert(value.to_str_radix(10));
+        ValueBucket { line, message_id, parse_as: ValueType::BigInt, op_aux_no: 0, value: cid }.allocate()
+    }
+}
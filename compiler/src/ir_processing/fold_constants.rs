@@ -0,0 +1,293 @@
+use super::field_info::FieldInfo;
+use crate::intermediate_representation::ir_interface::*;
+use circom_algebra::modular_arithmetic as arith;
+use num_bigint_dig::BigInt;
+use num_traits::{ToPrimitive, Zero};
+
+// Evaluates the operations whose operands are known and removes the
+// branches and loops whose condition is known. Returns true if the code
+// was modified
+pub fn fold_list(instructions: &mut InstructionList, info: &mut FieldInfo) -> bool {
+    let mut changed = false;
+    let mut folded = InstructionList::with_capacity(instructions.len());
+    for mut instruction in std::mem::take(instructions) {
+        changed |= fold_instruction(&mut instruction, info);
+        match *instruction {
+            Instruction::Branch(b) => match info.constant(&b.cond) {
+                Some(cond) => {
+                    changed = true;
+                    if arith::as_bool(&cond, info.prime) {
+                        folded.extend(b.if_branch);
+                    } else {
+                        folded.extend(b.else_branch);
+                    }
+                }
+                None => folded.push(b.allocate()),
+            },
+            Instruction::Loop(b) => match info.constant(&b.continue_condition) {
+                Some(cond) if !arith::as_bool(&cond, info.prime) => changed = true,
+                _ => folded.push(b.allocate()),
+            },
+            other => folded.push(other.allocate()),
+        }
+    }
+    *instructions = folded;
+    changed
+}
+
+fn fold_instruction(instruction: &mut Instruction, info: &mut FieldInfo) -> bool {
+    use Instruction::*;
+    match instruction {
+        Value(_) => false,
+        Load(b) => fold_location(&mut b.src, info) | fold_address_type(&mut b.address_type, info),
+        Store(b) => {
+            fold_instruction(&mut b.src, info)
+                | fold_location(&mut b.dest, info)
+                | fold_address_type(&mut b.dest_address_type, info)
+        }
+        ArrayCopy(b) => fold_instruction(&mut b.dest, info) | fold_instruction(&mut b.src, info),
+        Compute(b) => {
+            let mut changed = false;
+            for i in &mut b.stack {
+                changed |= fold_instruction(i, info);
+            }
+            if let Some(value) = fold_compute(b, info) {
+                *instruction = value;
+                changed = true;
+            }
+            changed
+        }
+        Select(b) => {
+            let changed = fold_instruction(&mut b.cond, info)
+                | fold_instruction(&mut b.if_true, info)
+                | fold_instruction(&mut b.if_false, info);
+            match info.constant(&b.cond) {
+                Some(cond) => {
+                    let value = if arith::as_bool(&cond, info.prime) { &b.if_true } else { &b.if_false };
+                    *instruction = (**value).clone();
+                    true
+                }
+                None => changed,
+            }
+        }
+        Call(b) => {
+            let mut changed = false;
+            for i in &mut b.arguments {
+                changed |= fold_instruction(i, info);
+            }
+            if let ReturnType::Final(data) = &mut b.return_info {
+                changed |= fold_location(&mut data.dest, info);
+                changed |= fold_address_type(&mut data.dest_address_type, info);
+            }
+            changed
+        }
+        Branch(b) => {
+            fold_instruction(&mut b.cond, info)
+                | fold_list(&mut b.if_branch, info)
+                | fold_list(&mut b.else_branch, info)
+        }
+        Switch(b) => {
+            let mut changed = fold_instruction(&mut b.in_range, info) | fold_instruction(&mut b.selector, info);
+            for case in &mut b.cases {
+                changed |= fold_list(&mut case.body, info);
+            }
+            changed | fold_list(&mut b.default_branch, info)
+        }
+        Loop(b) => fold_instruction(&mut b.continue_condition, info) | fold_list(&mut b.body, info),
+        Return(b) => fold_instruction(&mut b.value, info),
+        Assert(b) => {
+            let mut changed = fold_instruction(&mut b.evaluate, info);
+            for v in &mut b.values {
+                changed |= fold_instruction(v, info);
+            }
+            changed
+        }
+        CreateCmp(b) => fold_instruction(&mut b.sub_cmp_id, info),
+        Log(b) => {
+            let mut changed = false;
+            for print in &mut b.argsprint {
+                if let LogBucketArg::LogExp(exp) = print {
+                    changed |= fold_instruction(exp, info);
+                }
+            }
+            changed
+        }
+    }
+}
+
+fn fold_location(location: &mut LocationRule, info: &mut FieldInfo) -> bool {
+    let mut changed = false;
+    match location {
+        LocationRule::Indexed { location, .. } => changed |= fold_instruction(location, info),
+        LocationRule::Mapped { indexes, .. } => {
+            for access in indexes {
+                if let AccessType::Indexed(instr) = access {
+                    for i in &mut instr.indexes {
+                        changed |= fold_instruction(i, info);
+                    }
+                }
+            }
+        }
+    }
+    changed
+}
+
+fn fold_address_type(xtype: &mut AddressType, info: &mut FieldInfo) -> bool {
+    if let AddressType::SubcmpSignal { cmp_address, .. } = xtype {
+        fold_instruction(cmp_address, info)
+    } else {
+        false
+    }
+}
+
+fn address_constant(instruction: &Instruction) -> Option<usize> {
+    match instruction {
+        Instruction::Value(v) if v.parse_as == ValueType::U32 => Some(v.value),
+        _ => None,
+    }
+}
+
+fn address_bucket(bucket: &ComputeBucket, value: usize) -> Instruction {
+    ValueBucket {
+        line: bucket.line,
+        message_id: bucket.message_id,
+        parse_as: ValueType::U32,
+        op_aux_no: 0,
+        value,
+    }
+    .into_instruction()
+}
+
+fn fold_compute(bucket: &ComputeBucket, info: &mut FieldInfo) -> Option<Instruction> {
+    use OperatorType::*;
+    if bucket.op.is_address_op() {
+        let value = match bucket.op {
+            ToAddress => info.constant(&bucket.stack[0])?.to_u32()? as usize,
+            MulAddress => address_constant(&bucket.stack[0])? * address_constant(&bucket.stack[1])?,
+            _ => address_constant(&bucket.stack[0])? + address_constant(&bucket.stack[1])?,
+        };
+        return Some(address_bucket(bucket, value));
+    }
+
+    let operands: Vec<BigInt> =
+        bucket.stack.iter().map(|i| info.constant(i)).collect::<Option<Vec<_>>>()?;
+    let value = evaluate(&bucket.op, &operands, info.prime)?;
+    Some(*info.constant_bucket(&value, bucket.line, bucket.message_id))
+}
+
+// The result of a field operation with known operands, if it can be computed
+pub fn evaluate(op: &OperatorType, operands: &[BigInt], field: &BigInt) -> Option<BigInt> {
+    use OperatorType::*;
+    let value = match (op, operands) {
+        (PrefixSub, [a]) => arith::prefix_sub(a, field),
+        (BoolNot, [a]) => arith::not(a, field),
+        (Add, [a, b]) => arith::add(a, b, field),
+        (Sub, [a, b]) => arith::sub(a, b, field),
+        (Mul, [a, b]) => arith::mul(a, b, field),
+        (Pow, [a, b]) => arith::pow(a, b, field),
+        // the divisions by zero are reported at execution time
+        (Div, [_, b]) | (IntDiv, [_, b]) | (Mod, [_, b]) if b.is_zero() => return None,
+        (Div, [a, b]) => arith::div(a, b, field).ok()?,
+        (IntDiv, [a, b]) => arith::idiv(a, b, field).ok()?,
+        (Mod, [a, b]) => arith::mod_op(a, b, field).ok()?,
+        (Lesser, [a, b]) => arith::lesser(a, b, field),
+        (LesserEq, [a, b]) => arith::lesser_eq(a, b, field),
+        (Greater, [a, b]) => arith::greater(a, b, field),
+        (GreaterEq, [a, b]) => arith::greater_eq(a, b, field),
+        (Eq(SizeOption::Single(1)), [a, b]) => arith::eq(a, b, field),
+        (NotEq, [a, b]) => arith::not_eq(a, b, field),
+        (BoolOr, [a, b]) => arith::bool_or(a, b, field),
+        (BoolAnd, [a, b]) => arith::bool_and(a, b, field),
+        _ => return None,
+    };
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fold_list;
+    use crate::intermediate_representation::ir_interface::*;
+    use crate::ir_processing::test_code::*;
+    use num_bigint_dig::BigInt;
+
+    fn folded(field: &mut Field, mut code: InstructionList) -> (bool, Vec<String>) {
+        let changed = fold_list(&mut code, &mut field.info());
+        (changed, text(&code))
+    }
+
+    // The constant stored in signal 1 by the folded code
+    fn stored_constant(field: &mut Field, src: InstructionPointer) -> Option<BigInt> {
+        let mut code = vec![set_signal(value(1), src)];
+        fold_list(&mut code, &mut field.info());
+        match &*code[0] {
+            Instruction::Store(b) => field.info().constant(&b.src),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn operations_with_known_operands_are_evaluated() {
+        let mut field = Field::new();
+        let (two, three) = (field.constant(2), field.constant(3));
+        let product = compute(OperatorType::Mul, vec![two, three]);
+        assert_eq!(stored_constant(&mut field, product), Some(BigInt::from(6)));
+        let address = compute(OperatorType::MulAddress, vec![value(2), value(3)]);
+        let code = vec![set(compute(OperatorType::AddAddress, vec![value(1), address]), value(0))];
+        assert_eq!(folded(&mut field, code), (true, text(&vec![set(value(7), value(0))])));
+    }
+
+    #[test]
+    fn negative_constants_are_taken_modulo_the_prime() {
+        let mut field = Field::new();
+        let (minus_one, one) = (field.constant(-1), field.constant(1));
+        let sum = compute(OperatorType::Add, vec![minus_one.clone(), one]);
+        assert_eq!(stored_constant(&mut field, sum), Some(BigInt::from(0)));
+        let minus_two = field.constant(-2);
+        let comparison = compute(OperatorType::Lesser, vec![minus_two, minus_one]);
+        assert_eq!(stored_constant(&mut field, comparison), Some(BigInt::from(1)));
+    }
+
+    #[test]
+    fn divisions_by_zero_are_not_evaluated() {
+        let mut field = Field::new();
+        for op in [OperatorType::Div, OperatorType::IntDiv, OperatorType::Mod] {
+            let (one, zero) = (field.constant(1), field.constant(0));
+            let code = vec![set_signal(value(1), compute(op, vec![one, zero]))];
+            let expected = text(&code);
+            assert_eq!(folded(&mut field, code), (false, expected));
+        }
+    }
+
+    #[test]
+    fn branches_with_a_known_condition_are_replaced_by_the_taken_branch() {
+        let mut field = Field::new();
+        let (zero, one) = (field.constant(0), field.constant(1));
+        let taken = vec![set_signal(value(1), value(2))];
+        let not_taken = vec![set_signal(value(1), value(3))];
+        let code = vec![branch(zero, not_taken.clone(), taken.clone())];
+        assert_eq!(folded(&mut field, code), (true, text(&taken)));
+        let code = vec![branch(one, taken.clone(), not_taken)];
+        assert_eq!(folded(&mut field, code), (true, text(&taken)));
+    }
+
+    #[test]
+    fn loops_that_never_run_are_removed() {
+        let mut field = Field::new();
+        let (zero, one) = (field.constant(0), field.constant(1));
+        let code = vec![repeat(zero, vec![set_signal(value(1), value(2))])];
+        assert_eq!(folded(&mut field, code), (true, Vec::<String>::new()));
+        // the body of the loops that run is kept
+        let code = vec![repeat(one, vec![set_signal(value(1), value(2))])];
+        let expected = text(&code);
+        assert_eq!(folded(&mut field, code), (false, expected));
+    }
+
+    #[test]
+    fn unknown_operands_are_kept() {
+        let mut field = Field::new();
+        let one = field.constant(1);
+        let code = vec![set_signal(value(1), compute(OperatorType::Add, vec![load_signal(value(0)), one]))];
+        let expected = text(&code);
+        assert_eq!(folded(&mut field, code), (false, expected));
+    }
+}
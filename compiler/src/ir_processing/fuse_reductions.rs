@@ -0,0 +1,203 @@
+use super::field_info::FieldInfo;
+use super::mark_vectorizable_loops::{access, constant_store, counter_step, last_value, variable_slot};
+use crate::intermediate_representation::ir_interface::*;
+use num_bigint_dig::BigInt;
+
+// Marks the loops over a counter that only add elements, or products of
+// elements, to a variable. The loop is followed by the store of the last value
+// of the counter, so the code is the same whether the loop is fused or not
+pub fn fuse_list(instructions: &mut InstructionList, info: &mut FieldInfo) {
+    let mut fused = InstructionList::with_capacity(instructions.len());
+    let mut previous_store = None;
+    for mut i in std::mem::take(instructions) {
+        let mut counter_update = None;
+        match &mut *i {
+            Instruction::Loop(b) => {
+                if let Some((counter, start)) = previous_store.filter(|_| b.reduction.is_none()) {
+                    if let Some((reduction, update)) = reduction_of(b, counter, start, info) {
+                        b.reduction = Some(reduction);
+                        counter_update = Some(update);
+                    }
+                }
+                fuse_list(&mut b.body, info);
+            }
+            Instruction::Branch(b) => {
+                fuse_list(&mut b.if_branch, info);
+                fuse_list(&mut b.else_branch, info);
+            }
+            Instruction::Switch(b) => {
+                for case in &mut b.cases {
+                    fuse_list(&mut case.body, info);
+                }
+                fuse_list(&mut b.default_branch, info);
+            }
+            _ => {}
+        }
+        fused.push(i);
+        fused.extend(counter_update);
+        previous_store = constant_store(fused.last().unwrap(), info);
+    }
+    *instructions = fused;
+}
+
+fn loads_variable(instruction: &Instruction, slot: usize) -> bool {
+    matches!(instruction, Instruction::Load(b)
+        if b.context.size == SizeOption::Single(1) && variable_slot(&b.address_type, &b.src) == Some(slot))
+}
+
+// The value added to the accumulator by each iteration
+fn added_value(compute: &ComputeBucket, accumulator: usize) -> Option<&Instruction> {
+    match &compute.stack[..] {
+        [a, b] if compute.op == OperatorType::Add && loads_variable(a, accumulator) => Some(b),
+        [a, b] if compute.op == OperatorType::Add && loads_variable(b, accumulator) => Some(a),
+        _ => None,
+    }
+}
+
+fn reduction_of(
+    bucket: &LoopBucket,
+    counter: usize,
+    start: usize,
+    info: &mut FieldInfo,
+) -> Option<(Reduction, InstructionPointer)> {
+    let (update, step_store) = match bucket.body.as_slice() {
+        [update, step_store] => (update, step_store),
+        _ => return None,
+    };
+    let step = counter_step(step_store, counter, info)?;
+    let last_counter = last_value(&bucket.continue_condition, counter, start, step, info)?;
+    let store = match &**update {
+        Instruction::Store(b)
+            if b.context.size == SizeOption::Single(1)
+                && b.src_context.size == SizeOption::Single(1)
+                && b.src_address_type.is_none() =>
+        {
+            b
+        }
+        _ => return None,
+    };
+    let accumulator = variable_slot(&store.dest_address_type, &store.dest).filter(|slot| *slot != counter)?;
+    let added = match &*store.src {
+        Instruction::Compute(c) => added_value(c, accumulator)?,
+        _ => return None,
+    };
+    let elements = match added {
+        Instruction::Compute(c) if c.op == OperatorType::Mul => vec![&*c.stack[0], &*c.stack[1]],
+        element => vec![element],
+    };
+    let mut operands = Vec::with_capacity(elements.len());
+    for element in elements {
+        let load = match element {
+            Instruction::Load(b) if b.context.size == SizeOption::Single(1) => b,
+            _ => return None,
+        };
+        let element = access(&load.address_type, &load.src, counter)?;
+        let (first, last) = element.range(start, last_counter)?;
+        // the accumulator and the counter are not elements of the arrays
+        if !element.is_signal && [accumulator, counter].iter().any(|slot| (first..=last).contains(slot)) {
+            return None;
+        }
+        let stride = element.stride.checked_mul(step)?;
+        operands.push(ReductionOperand { is_signal: element.is_signal, start: first, stride });
+    }
+    let length = (last_counter - start) / step + 1;
+    let mut counter_update = match &**step_store {
+        Instruction::Store(b) => b.clone(),
+        _ => unreachable!(),
+    };
+    counter_update.src = info.constant_bucket(&BigInt::from(start + length * step), store.line, store.message_id);
+    Some((Reduction { accumulator, operands, length }, counter_update.allocate()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fuse_list;
+    use crate::intermediate_representation::ir_interface::*;
+    use crate::ir_processing::test_code::*;
+    use num_bigint_dig::BigInt;
+
+    fn i() -> InstructionPointer {
+        load_var(var(0))
+    }
+
+    // base + i
+    fn at(base: usize) -> InstructionPointer {
+        compute(OperatorType::AddAddress, vec![value(base), compute(OperatorType::ToAddress, vec![i()])])
+    }
+
+    fn add(a: InstructionPointer, b: InstructionPointer) -> InstructionPointer {
+        compute(OperatorType::Add, vec![a, b])
+    }
+
+    // i = 0; while (i < end) { acc = acc + added; i = i + step }, with i in
+    // 0 and acc in 1
+    fn reduction_loop(field: &mut Field, added: InstructionPointer, end: i64, step: i64) -> InstructionList {
+        let condition = compute(OperatorType::Lesser, vec![i(), field.constant(end)]);
+        let body = vec![
+            set(var(1), add(load_var(var(1)), added)),
+            set(var(0), add(i(), field.constant(step))),
+        ];
+        vec![set(var(0), field.constant(0)), repeat(condition, body)]
+    }
+
+    // The accumulator, the operands and the length of the fused loop, and the
+    // value given to the counter after it
+    type Fused = (usize, Vec<(bool, usize, usize)>, usize, BigInt);
+
+    fn fused(field: &mut Field, mut code: InstructionList) -> Option<Fused> {
+        fuse_list(&mut code, &mut field.info());
+        let reduction = match &*code[1] {
+            Instruction::Loop(b) => b.reduction.clone()?,
+            _ => unreachable!(),
+        };
+        let last_counter = match &*code[2] {
+            Instruction::Store(b) => field.info().constant(&b.src).unwrap(),
+            _ => unreachable!(),
+        };
+        let operands = reduction.operands.iter().map(|o| (o.is_signal, o.start, o.stride)).collect();
+        Some((reduction.accumulator, operands, reduction.length, last_counter))
+    }
+
+    #[test]
+    fn sums_of_elements_are_fused() {
+        let mut field = Field::new();
+        let code = reduction_loop(&mut field, load_signal(at(5)), 4, 1);
+        assert_eq!(fused(&mut field, code), Some((1, vec![(true, 5, 1)], 4, BigInt::from(4))));
+    }
+
+    #[test]
+    fn sums_of_products_are_fused() {
+        let mut field = Field::new();
+        let product = compute(OperatorType::Mul, vec![load_signal(at(5)), load_var(at(2))]);
+        let code = reduction_loop(&mut field, product, 3, 1);
+        assert_eq!(fused(&mut field, code), Some((1, vec![(true, 5, 1), (false, 2, 1)], 3, BigInt::from(3))));
+    }
+
+    #[test]
+    fn the_step_of_the_counter_is_the_stride() {
+        let mut field = Field::new();
+        let code = reduction_loop(&mut field, load_signal(at(5)), 7, 2);
+        // i takes the values 0, 2, 4 and 6
+        assert_eq!(fused(&mut field, code), Some((1, vec![(true, 5, 2)], 4, BigInt::from(8))));
+    }
+
+    #[test]
+    fn elements_that_include_the_accumulator_are_not_fused() {
+        let mut field = Field::new();
+        // the variables 0..4 include the counter and the accumulator
+        let code = reduction_loop(&mut field, load_var(at(0)), 4, 1);
+        assert_eq!(fused(&mut field, code), None);
+    }
+
+    #[test]
+    fn other_operations_are_not_fused() {
+        let mut field = Field::new();
+        let mut code = reduction_loop(&mut field, load_signal(at(5)), 4, 1);
+        if let Instruction::Loop(b) = &mut *code[1] {
+            if let Instruction::Store(s) = &mut *b.body[0] {
+                s.src = compute(OperatorType::Mul, vec![load_var(var(1)), load_signal(at(5))]);
+            }
+        }
+        assert_eq!(fused(&mut field, code), None);
+    }
+}
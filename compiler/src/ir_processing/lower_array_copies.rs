@@ -0,0 +1,151 @@
+use crate::intermediate_representation::ir_interface::*;
+
+pub fn lower_list(instructions: &mut InstructionList) {
+    for i in instructions {
+        lower_instruction(i);
+    }
+}
+
+fn lower_instruction(instruction: &mut Instruction) {
+    use Instruction::*;
+    match instruction {
+        Store(b) => {
+            if let Some(copy) = array_copy(b) {
+                *instruction = copy.into_instruction();
+            }
+        }
+        Branch(b) => {
+            lower_list(&mut b.if_branch);
+            lower_list(&mut b.else_branch);
+        }
+        Switch(b) => {
+            for case in &mut b.cases {
+                lower_list(&mut case.body);
+            }
+            lower_list(&mut b.default_branch);
+        }
+        Loop(b) => lower_list(&mut b.body),
+        _ => {}
+    }
+}
+
+fn copied_index(xtype: &AddressType, location: &LocationRule) -> Option<InstructionPointer> {
+    match (xtype, location) {
+        (AddressType::SubcmpSignal { .. }, _) => None,
+        (_, LocationRule::Indexed { location, .. }) => Some(location.clone()),
+        (_, LocationRule::Mapped { .. }) => None,
+    }
+}
+
+// A store of several elements loaded from the variables or signals of the
+// component, the stores of subcomponent signals keep their bookkeeping
+fn array_copy(bucket: &StoreBucket) -> Option<ArrayCopyBucket> {
+    let load = match &*bucket.src {
+        Instruction::Load(load) => load,
+        _ => return None,
+    };
+    let size = match (&bucket.context.size, &bucket.src_context.size) {
+        (SizeOption::Single(dest), SizeOption::Single(src)) => std::cmp::min(*dest, *src),
+        _ => return None,
+    };
+    if size <= 1 {
+        return None;
+    }
+    Some(ArrayCopyBucket {
+        line: bucket.line,
+        message_id: bucket.message_id,
+        size,
+        dest_is_output: bucket.dest_is_output,
+        dest_address_type: bucket.dest_address_type.clone(),
+        dest: copied_index(&bucket.dest_address_type, &bucket.dest)?,
+        src_address_type: load.address_type.clone(),
+        src: copied_index(&load.address_type, &load.src)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::lower_list;
+    use crate::intermediate_representation::ir_interface::*;
+    use crate::ir_processing::test_code::*;
+
+    // dest = src for the given number of elements of each side
+    fn store_of(dest_address_type: AddressType, dest: usize, src: InstructionPointer, sizes: (usize, usize)) -> InstructionPointer {
+        let mut store = store(dest_address_type, value(dest), src);
+        if let Instruction::Store(b) = &mut *store {
+            b.context = InstrContext { size: SizeOption::Single(sizes.0) };
+            b.src_context = InstrContext { size: SizeOption::Single(sizes.1) };
+        }
+        store
+    }
+
+    fn load_of(address_type: AddressType, src: usize, size: usize) -> InstructionPointer {
+        let mut load = load(address_type, value(src));
+        if let Instruction::Load(b) = &mut *load {
+            b.context = InstrContext { size: SizeOption::Single(size) };
+        }
+        load
+    }
+
+    fn array_copy(dest_address_type: AddressType, dest: usize, src_address_type: AddressType, src: usize, size: usize) -> InstructionPointer {
+        ArrayCopyBucket {
+            line: 0,
+            message_id: 0,
+            size,
+            dest_is_output: false,
+            dest_address_type,
+            dest: value(dest),
+            src_address_type,
+            src: value(src),
+        }
+        .allocate()
+    }
+
+    fn lowered(mut code: InstructionList) -> Vec<String> {
+        lower_list(&mut code);
+        text(&code)
+    }
+
+    #[test]
+    fn stores_of_several_elements_are_copies() {
+        let code = vec![store_of(AddressType::Variable, 0, load_of(AddressType::Signal, 4, 3), (3, 3))];
+        let expected = vec![array_copy(AddressType::Variable, 0, AddressType::Signal, 4, 3)];
+        assert_eq!(lowered(code), text(&expected));
+    }
+
+    #[test]
+    fn the_shorter_side_gives_the_size_of_the_copy() {
+        let code = vec![store_of(AddressType::Signal, 1, load_of(AddressType::Variable, 2, 4), (2, 4))];
+        let expected = vec![array_copy(AddressType::Signal, 1, AddressType::Variable, 2, 2)];
+        assert_eq!(lowered(code), text(&expected));
+    }
+
+    #[test]
+    fn stores_of_one_element_are_kept() {
+        let code = vec![store_of(AddressType::Variable, 0, load_of(AddressType::Variable, 1, 1), (1, 1))];
+        let expected = text(&code);
+        assert_eq!(lowered(code), expected);
+    }
+
+    #[test]
+    fn stores_in_subcomponents_are_kept() {
+        let subcomponent = AddressType::SubcmpSignal {
+            cmp_address: value(0),
+            uniform_parallel_value: Some(false),
+            is_output: false,
+            input_information: InputInformation::Input { status: StatusInput::Unknown },
+        };
+        let code = vec![store_of(subcomponent, 0, load_of(AddressType::Signal, 1, 2), (2, 2))];
+        let expected = text(&code);
+        assert_eq!(lowered(code), expected);
+    }
+
+    #[test]
+    fn stores_inside_loops_and_branches_are_lowered() {
+        let copy = || store_of(AddressType::Variable, 0, load_of(AddressType::Variable, 2, 2), (2, 2));
+        let lowered_copy = || array_copy(AddressType::Variable, 0, AddressType::Variable, 2, 2);
+        let code = vec![repeat(value(1), vec![branch(value(1), vec![copy()], vec![copy()])])];
+        let expected = vec![repeat(value(1), vec![branch(value(1), vec![lowered_copy()], vec![lowered_copy()])])];
+        assert_eq!(lowered(code), text(&expected));
+    }
+}
@@ -0,0 +1,288 @@
+use super::field_info::FieldInfo;
+use crate::intermediate_representation::ir_interface::*;
+use num_traits::ToPrimitive;
+
+// An element accessed by each iteration, at base + stride * counter
+pub struct Access {
+    pub is_signal: bool,
+    pub base: usize,
+    pub stride: usize,
+}
+
+impl Access {
+    pub fn range(&self, first: usize, last: usize) -> Option<(usize, usize)> {
+        let start = self.base.checked_add(self.stride.checked_mul(first)?)?;
+        let end = self.base.checked_add(self.stride.checked_mul(last)?)?;
+        Some((start, end))
+    }
+}
+
+// Marks the loops over a counter whose iterations store independent elements
+// computed from the elements of the same iteration
+pub fn mark_list(instructions: &mut InstructionList, info: &FieldInfo) {
+    let mut previous_store = None;
+    for i in instructions {
+        match &mut **i {
+            Instruction::Loop(b) => {
+                b.vectorizable = previous_store.is_some_and(|(slot, start)| is_vectorizable(b, slot, start, info));
+                mark_list(&mut b.body, info);
+            }
+            Instruction::Branch(b) => {
+                mark_list(&mut b.if_branch, info);
+                mark_list(&mut b.else_branch, info);
+            }
+            Instruction::Switch(b) => {
+                for case in &mut b.cases {
+                    mark_list(&mut case.body, info);
+                }
+                mark_list(&mut b.default_branch, info);
+            }
+            _ => {}
+        }
+        previous_store = constant_store(i, info);
+    }
+}
+
+pub fn variable_slot(xtype: &AddressType, location: &LocationRule) -> Option<usize> {
+    match (xtype, location) {
+        (AddressType::Variable, LocationRule::Indexed { location, .. }) => match &**location {
+            Instruction::Value(v) if v.parse_as == ValueType::U32 => Some(v.value),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+pub fn is_counter(instruction: &Instruction, counter: usize) -> bool {
+    matches!(instruction, Instruction::Load(b)
+        if b.context.size == SizeOption::Single(1) && variable_slot(&b.address_type, &b.src) == Some(counter))
+}
+
+pub fn small_constant(instruction: &Instruction, info: &FieldInfo) -> Option<usize> {
+    info.constant(instruction)?.to_u32().map(|value| value as usize)
+}
+
+// The variable set to a constant by the instruction and the constant
+pub fn constant_store(instruction: &Instruction, info: &FieldInfo) -> Option<(usize, usize)> {
+    match instruction {
+        Instruction::Store(b) if b.context.size == SizeOption::Single(1) => {
+            Some((variable_slot(&b.dest_address_type, &b.dest)?, small_constant(&b.src, info)?))
+        }
+        _ => None,
+    }
+}
+
+// The counter is increased by a constant at the end of each iteration
+pub fn counter_step(instruction: &Instruction, counter: usize, info: &FieldInfo) -> Option<usize> {
+    let store = match instruction {
+        Instruction::Store(b) if variable_slot(&b.dest_address_type, &b.dest) == Some(counter) => b,
+        _ => return None,
+    };
+    match &*store.src {
+        Instruction::Compute(c) if c.op == OperatorType::Add && is_counter(&c.stack[0], counter) => {
+            small_constant(&c.stack[1], info).filter(|step| *step > 0)
+        }
+        _ => None,
+    }
+}
+
+// The last value of the counter for which the condition holds
+pub fn last_value(cond: &Instruction, counter: usize, start: usize, step: usize, info: &FieldInfo) -> Option<usize> {
+    let (op, stack) = match cond {
+        Instruction::Compute(c) if is_counter(&c.stack[0], counter) => (&c.op, &c.stack),
+        _ => return None,
+    };
+    let end = match op {
+        OperatorType::Lesser => small_constant(&stack[1], info)?,
+        OperatorType::LesserEq => small_constant(&stack[1], info)? + 1,
+        _ => return None,
+    };
+    if start >= end {
+        return None;
+    }
+    Some(start + (end - 1 - start) / step * step)
+}
+
+// The index as base + stride * counter
+fn affine_index(index: &Instruction, counter: usize) -> Option<(usize, usize)> {
+    use OperatorType::*;
+    match index {
+        Instruction::Value(v) if v.parse_as == ValueType::U32 => Some((v.value, 0)),
+        Instruction::Compute(c) => match c.op {
+            ToAddress if is_counter(&c.stack[0], counter) => Some((0, 1)),
+            AddAddress => {
+                let (b0, s0) = affine_index(&c.stack[0], counter)?;
+                let (b1, s1) = affine_index(&c.stack[1], counter)?;
+                Some((b0.checked_add(b1)?, s0.checked_add(s1)?))
+            }
+            MulAddress => match (affine_index(&c.stack[0], counter)?, affine_index(&c.stack[1], counter)?) {
+                ((k, 0), (b, s)) | ((b, s), (k, 0)) => Some((b.checked_mul(k)?, s.checked_mul(k)?)),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+pub fn access(xtype: &AddressType, location: &LocationRule, counter: usize) -> Option<Access> {
+    let is_signal = match xtype {
+        AddressType::Variable => false,
+        AddressType::Signal => true,
+        AddressType::SubcmpSignal { .. } => return None,
+    };
+    let (base, stride) = match location {
+        LocationRule::Indexed { location, .. } => affine_index(location, counter)?,
+        LocationRule::Mapped { .. } => return None,
+    };
+    Some(Access { is_signal, base, stride })
+}
+
+// The loads of a value without effects
+fn collect_loads(instruction: &Instruction, counter: usize, loads: &mut Vec<Access>) -> bool {
+    match instruction {
+        Instruction::Value(_) => true,
+        Instruction::Load(b) if b.context.size == SizeOption::Single(1) => {
+            match access(&b.address_type, &b.src, counter) {
+                Some(load) => {
+                    loads.push(load);
+                    true
+                }
+                None => false,
+            }
+        }
+        Instruction::Compute(b) if !b.op.is_address_op() => {
+            b.stack.iter().all(|i| collect_loads(i, counter, loads))
+        }
+        _ => false,
+    }
+}
+
+fn is_vectorizable(bucket: &LoopBucket, counter: usize, start: usize, info: &FieldInfo) -> bool {
+    let (last, elements) = match bucket.body.split_last() {
+        Some((last, elements)) if !elements.is_empty() => (last, elements),
+        _ => return false,
+    };
+    let step = match counter_step(last, counter, info) {
+        Some(step) => step,
+        None => return false,
+    };
+    let last_counter = match last_value(&bucket.continue_condition, counter, start, step, info) {
+        Some(last_counter) => last_counter,
+        None => return false,
+    };
+    let mut stores = Vec::new();
+    let mut loads = vec![Access { is_signal: false, base: counter, stride: 0 }];
+    for instruction in elements {
+        let store = match &**instruction {
+            Instruction::Store(b)
+                if b.context.size == SizeOption::Single(1)
+                    && b.src_context.size == SizeOption::Single(1)
+                    && b.src_address_type.is_none() =>
+            {
+                b
+            }
+            _ => return false,
+        };
+        match access(&store.dest_address_type, &store.dest, counter) {
+            Some(dest) if dest.stride > 0 => stores.push(dest),
+            _ => return false,
+        }
+        if !collect_loads(&store.src, counter, &mut loads) {
+            return false;
+        }
+    }
+    // an element stored by an iteration is only accessed by that iteration
+    for store in &stores {
+        let stored = match store.range(start, last_counter) {
+            Some(range) => range,
+            None => return false,
+        };
+        for other in stores.iter().chain(&loads) {
+            if other.is_signal != store.is_signal || (other.base == store.base && other.stride == store.stride) {
+                continue;
+            }
+            match other.range(start, last_counter) {
+                Some((first, last)) if last < stored.0 || first > stored.1 => {}
+                _ => return false,
+            }
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mark_list;
+    use crate::intermediate_representation::ir_interface::*;
+    use crate::ir_processing::test_code::*;
+
+    fn i() -> InstructionPointer {
+        load_var(var(0))
+    }
+
+    // base + i
+    fn at(base: usize) -> InstructionPointer {
+        compute(OperatorType::AddAddress, vec![value(base), compute(OperatorType::ToAddress, vec![i()])])
+    }
+
+    // base + i - 1, read as base - 1 + i
+    fn before(base: usize) -> InstructionPointer {
+        at(base - 1)
+    }
+
+    // i = 0; while (i < 4) { body; i = i + 1 }
+    fn counted_loop(field: &mut Field, mut body: InstructionList) -> InstructionList {
+        let condition = compute(OperatorType::Lesser, vec![i(), field.constant(4)]);
+        body.push(set(var(0), compute(OperatorType::Add, vec![i(), field.constant(1)])));
+        vec![set(var(0), field.constant(0)), repeat(condition, body)]
+    }
+
+    fn is_marked(field: &mut Field, mut code: InstructionList) -> bool {
+        mark_list(&mut code, &field.info());
+        match &*code[1] {
+            Instruction::Loop(b) => b.vectorizable,
+            _ => unreachable!(),
+        }
+    }
+
+    fn mul(a: InstructionPointer, b: InstructionPointer) -> InstructionPointer {
+        compute(OperatorType::Mul, vec![a, b])
+    }
+
+    #[test]
+    fn loops_over_independent_elements_are_vectorizable() {
+        let mut field = Field::new();
+        // out[i] = a[i] * b[i], with out in 1..5, a in 5..9 and b in 9..13
+        let body = vec![set_signal(at(1), mul(load_signal(at(5)), load_signal(at(9))))];
+        let code = counted_loop(&mut field, body);
+        assert!(is_marked(&mut field, code));
+    }
+
+    #[test]
+    fn loops_that_read_the_previous_element_are_not_vectorizable() {
+        let mut field = Field::new();
+        // acc[i] = acc[i - 1] * a[i], with acc in 2..6
+        let body = vec![set(at(2), mul(load_var(before(2)), load_signal(at(5))))];
+        let code = counted_loop(&mut field, body);
+        assert!(!is_marked(&mut field, code));
+    }
+
+    #[test]
+    fn loops_that_store_overlapping_elements_are_not_vectorizable() {
+        let mut field = Field::new();
+        // out[i] = a[i] and out[i + 1] = b[i]
+        let body = vec![set_signal(at(1), load_signal(at(9))), set_signal(at(2), load_signal(at(13)))];
+        let code = counted_loop(&mut field, body);
+        assert!(!is_marked(&mut field, code));
+    }
+
+    #[test]
+    fn loops_without_a_known_start_are_not_vectorizable() {
+        let mut field = Field::new();
+        let body = vec![set_signal(at(1), load_signal(at(5)))];
+        let mut code = counted_loop(&mut field, body);
+        code[0] = set(var(0), load_signal(value(0)));
+        assert!(!is_marked(&mut field, code));
+    }
+}
@@ -1,14 +1,106 @@
 mod build_stack;
+mod eliminate_tail_calls;
 mod reduce_stack;
 mod set_arena_size;
 mod build_inputs_info;
-use crate::intermediate_representation::ir_interface::InstructionList;
+mod reuse_variable_slots;
+mod propagate_copies;
+mod reduce_strength;
+mod field_info;
+mod fold_constants;
+mod propagate_constant_inputs;
+mod ssa;
+mod propagate_conditional_constants;
+mod share_constants;
+mod lower_array_copies;
+mod build_jump_tables;
+mod select_branches;
+mod mark_vectorizable_loops;
+mod fuse_reductions;
+mod component_liveness;
+mod pass_manager;
+mod verify;
+#[cfg(test)]
+pub(crate) mod test_code;
+pub use pass_manager::{build_report, CodeSize, OptimizationLevel, Pass, PassConfig, PassEffect, PassManager, PassTiming};
+pub use verify::{verify_list, VerifyInfo};
+use crate::circuit_design::function::FunctionCode;
+use crate::circuit_design::template::TemplateCode;
+use crate::intermediate_representation::ir_interface::{Allocate, InstructionList, LoopBucket, ObtainMeta};
+use crate::intermediate_representation::translate::FieldTracker;
+use num_bigint_dig::BigInt;
 use std::collections::{HashMap, HashSet};
 
 pub fn reduce_intermediate_operations(code: InstructionList) -> InstructionList {
     reduce_stack::reduce_list(code)
 }
 
+pub fn propagate_copies(code: &mut InstructionList) {
+    propagate_copies::propagate_list(code);
+}
+
+pub fn reduce_strength(code: &mut InstructionList, field_tracker: &mut FieldTracker, prime: &BigInt) {
+    let mut info = field_info::FieldInfo { field_tracker, prime };
+    reduce_strength::reduce_list(code, &mut info);
+}
+
+pub fn fold_constants(code: &mut InstructionList, field_tracker: &mut FieldTracker, prime: &BigInt) -> bool {
+    let mut info = field_info::FieldInfo { field_tracker, prime };
+    fold_constants::fold_list(code, &mut info)
+}
+
+// Works on the SSA form of the code, that is lowered back to buckets afterwards
+pub fn propagate_conditional_constants(
+    code: &mut InstructionList,
+    variables: &HashMap<usize, usize>,
+    field_tracker: &mut FieldTracker,
+    prime: &BigInt,
+) -> bool {
+    let mut info = field_info::FieldInfo { field_tracker, prime };
+    match ssa::build(std::mem::take(code), variables) {
+        Ok(mut ssa) => {
+            let changed = propagate_conditional_constants::propagate(&mut ssa, &mut info);
+            *code = ssa.lower();
+            changed
+        }
+        Err(original) => {
+            *code = original;
+            false
+        }
+    }
+}
+
+pub fn lower_array_copies(code: &mut InstructionList) {
+    lower_array_copies::lower_list(code);
+}
+
+pub fn build_jump_tables(code: &mut InstructionList, field_tracker: &mut FieldTracker, prime: &BigInt) {
+    let mut info = field_info::FieldInfo { field_tracker, prime };
+    build_jump_tables::build_list(code, &mut info);
+}
+
+pub fn select_branches(code: &mut InstructionList) {
+    select_branches::select_list(code);
+}
+
+pub fn mark_vectorizable_loops(code: &mut InstructionList, field_tracker: &mut FieldTracker, prime: &BigInt) {
+    let info = field_info::FieldInfo { field_tracker, prime };
+    mark_vectorizable_loops::mark_list(code, &info);
+}
+
+pub fn fuse_reductions(code: &mut InstructionList, field_tracker: &mut FieldTracker, prime: &BigInt) {
+    let mut info = field_info::FieldInfo { field_tracker, prime };
+    fuse_reductions::fuse_list(code, &mut info);
+}
+
+// For each instruction of the body of a template, the subcomponents that are
+// not accessed after it. Only the C++ witness generator releases them, as the
+// wasm one places the components in a region whose size is given by the
+// compiler, so releasing them would not lower its memory
+pub fn last_uses_of_components(code: &InstructionList, number_of_components: usize) -> Vec<Vec<usize>> {
+    component_liveness::last_uses(code, number_of_components)
+}
+
 pub fn build_auxiliary_stack(code: &mut InstructionList) -> usize {
     build_stack::build_list(code, 0)
 }
@@ -20,7 +112,77 @@ pub fn set_arena_size_in_calls(
     set_arena_size::visit_list(code, function_to_arena_size);
 }
 
-pub fn build_inputs_info(code: &mut InstructionList){
-    build_inputs_info::visit_list(code, &mut HashSet::new(), &mut HashSet::new(),false, false);
+pub fn build_inputs_info(code: &mut InstructionList, field_tracker: &mut FieldTracker, prime: &BigInt){
+    let field = field_info::FieldInfo { field_tracker, prime };
+    let info = build_inputs_info::ComponentsInfo::new(code, &field);
+    build_inputs_info::visit_list(code, &mut HashSet::new(), &mut HashSet::new(),false, false, &info);
+}
+
+
+// The body of a function that returns the result of calling itself is
+// repeated by a loop instead, the arguments of the call are kept in new
+// variables until all of them are evaluated
+pub fn eliminate_tail_calls(
+    code: &mut InstructionList,
+    header: &str,
+    parameters: &[(usize, usize)],
+    stack_depth: &mut usize,
+    variables: &mut HashMap<usize, usize>,
+    field_tracker: &mut FieldTracker,
+    prime: &BigInt,
+) -> bool {
+    let mut info = eliminate_tail_calls::TailCallInfo { header, parameters, temporaries: Vec::new() };
+    if !eliminate_tail_calls::always_returns(code) || !eliminate_tail_calls::has_tail_calls(code, &info) {
+        return false;
+    }
+    if parameters.len() > 1 {
+        for &(_, size) in parameters {
+            info.temporaries.push(*stack_depth);
+            variables.insert(*stack_depth, size);
+            *stack_depth += size;
+        }
+    }
+    eliminate_tail_calls::eliminate_list(code, &info);
+    let (line, message_id) = (code[0].get_line(), code[0].get_message_id());
+    let mut field = field_info::FieldInfo { field_tracker, prime };
+    let continue_condition = field.constant_bucket(&BigInt::from(1), line, message_id);
+    let body = std::mem::take(code);
+    code.push(LoopBucket { line, message_id, continue_condition, body, vectorizable: false, reduction: None }.allocate());
+    true
+}
+
+// The first slot and size of the variables are updated with their new slots
+pub fn reuse_variable_slots(
+    code: &mut InstructionList,
+    fixed_slots: usize,
+    variables: &mut HashMap<usize, usize>,
+    stack_depth: usize,
+) -> usize {
+    let info = reuse_variable_slots::LivenessInfo::new(fixed_slots, std::mem::take(variables));
+    let (new_depth, moved) = reuse_variable_slots::reuse_slots(code, info, stack_depth);
+    *variables = moved;
+    new_depth
+}
+
+pub fn propagate_constant_inputs(
+    templates: &mut [TemplateCode],
+    main_header: &str,
+    field_tracker: &mut FieldTracker,
+    prime: &BigInt,
+) {
+    let mut info = field_info::FieldInfo { field_tracker, prime };
+    propagate_constant_inputs::propagate(templates, main_header, &mut info);
 }
 
+// Returns the constant pool of the circuit after renaming its constants
+pub fn share_constants(
+    templates: &mut [TemplateCode],
+    functions: &mut [FunctionCode],
+    field_tracker: &mut FieldTracker,
+    prime: &BigInt,
+) -> FieldTracker {
+    let info = field_info::FieldInfo { field_tracker, prime };
+    let mut codes: Vec<&mut InstructionList> = templates.iter_mut().map(|t| &mut t.body).collect();
+    codes.extend(functions.iter_mut().map(|f| &mut f.body));
+    share_constants::share(codes, &info)
+}
@@ -0,0 +1,496 @@
+use super::verify::{verify_list, VerifyInfo};
+use crate::intermediate_representation::ir_interface::*;
+use serde_derive::Serialize;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum OptimizationLevel {
+    O0,
+    O1,
+    O2,
+}
+
+impl OptimizationLevel {
+    pub fn from_name(name: &str) -> Option<OptimizationLevel> {
+        match name {
+            "0" | "O0" => Some(OptimizationLevel::O0),
+            "1" | "O1" => Some(OptimizationLevel::O1),
+            "2" | "O2" => Some(OptimizationLevel::O2),
+            _ => None,
+        }
+    }
+}
+
+// Optional passes over the IR, in the order they are applied
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Pass {
+    EliminateTailCalls,
+    ReuseVariableSlots,
+    PropagateCopies,
+    FoldConstants,
+    PropagateConditionalConstants,
+    LowerArrayCopies,
+    BuildJumpTables,
+    SelectBranches,
+    ReduceStrength,
+    MarkVectorizableLoops,
+    FuseReductions,
+    PropagateConstantInputs,
+    ShareConstants,
+    MergeInstances,
+}
+
+impl Pass {
+    pub const ALL: [Pass; 14] = [
+        Pass::EliminateTailCalls,
+        Pass::ReuseVariableSlots,
+        Pass::PropagateCopies,
+        Pass::FoldConstants,
+        Pass::PropagateConditionalConstants,
+        Pass::LowerArrayCopies,
+        Pass::BuildJumpTables,
+        Pass::SelectBranches,
+        Pass::ReduceStrength,
+        Pass::MarkVectorizableLoops,
+        Pass::FuseReductions,
+        Pass::PropagateConstantInputs,
+        Pass::ShareConstants,
+        Pass::MergeInstances,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pass::EliminateTailCalls => "eliminate_tail_calls",
+            Pass::ReuseVariableSlots => "reuse_variable_slots",
+            Pass::PropagateCopies => "propagate_copies",
+            Pass::FoldConstants => "fold_constants",
+            Pass::PropagateConditionalConstants => "propagate_conditional_constants",
+            Pass::LowerArrayCopies => "lower_array_copies",
+            Pass::BuildJumpTables => "build_jump_tables",
+            Pass::SelectBranches => "select_branches",
+            Pass::ReduceStrength => "reduce_strength",
+            Pass::MarkVectorizableLoops => "mark_vectorizable_loops",
+            Pass::FuseReductions => "fuse_reductions",
+            Pass::PropagateConstantInputs => "propagate_constant_inputs",
+            Pass::ShareConstants => "share_constants",
+            Pass::MergeInstances => "merge_instances",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Pass> {
+        Pass::ALL.iter().find(|pass| pass.name() == name).copied()
+    }
+
+    fn minimum_level(&self) -> OptimizationLevel {
+        match self {
+            Pass::EliminateTailCalls
+            | Pass::ReuseVariableSlots
+            | Pass::PropagateCopies
+            | Pass::LowerArrayCopies
+            | Pass::BuildJumpTables
+            | Pass::SelectBranches
+            | Pass::ReduceStrength
+            | Pass::MarkVectorizableLoops
+            | Pass::FuseReductions
+            | Pass::ShareConstants => OptimizationLevel::O1,
+            Pass::FoldConstants
+            | Pass::PropagateConditionalConstants
+            | Pass::PropagateConstantInputs
+            | Pass::MergeInstances => OptimizationLevel::O2,
+        }
+    }
+}
+
+pub struct PassConfig {
+    pub level: OptimizationLevel,
+    pub disabled_passes: Vec<Pass>,
+    pub report_timing: bool,
+    pub report_effects: bool,
+    // checks the invariants of the code after each stage
+    pub verify: bool,
+}
+
+impl Default for PassConfig {
+    fn default() -> Self {
+        PassConfig {
+            level: OptimizationLevel::O0,
+            disabled_passes: Vec::new(),
+            report_timing: false,
+            report_effects: false,
+            verify: cfg!(debug_assertions),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PassTiming {
+    pub pass: Pass,
+    pub runs: usize,
+    pub time: Duration,
+}
+
+#[derive(Clone, Copy, Default, Serialize)]
+pub struct CodeSize {
+    pub instructions: usize,
+    pub loops: usize,
+    pub branches: usize,
+}
+
+impl CodeSize {
+    pub fn of(code: &InstructionList) -> CodeSize {
+        let mut size = CodeSize::default();
+        for i in code {
+            size.add_instruction(i);
+        }
+        size
+    }
+
+    fn add_list(&mut self, code: &[InstructionPointer]) {
+        for i in code {
+            self.add_instruction(i);
+        }
+    }
+
+    fn add_location(&mut self, location: &LocationRule) {
+        match location {
+            LocationRule::Indexed { location, .. } => self.add_instruction(location),
+            LocationRule::Mapped { indexes, .. } => {
+                for access in indexes {
+                    if let AccessType::Indexed(instr) = access {
+                        self.add_list(&instr.indexes);
+                    }
+                }
+            }
+        }
+    }
+
+    fn add_address_type(&mut self, xtype: &AddressType) {
+        if let AddressType::SubcmpSignal { cmp_address, .. } = xtype {
+            self.add_instruction(cmp_address);
+        }
+    }
+
+    fn add_instruction(&mut self, instruction: &Instruction) {
+        use Instruction::*;
+        self.instructions += 1;
+        match instruction {
+            Value(_) => {}
+            Load(b) => {
+                self.add_location(&b.src);
+                self.add_address_type(&b.address_type);
+            }
+            Store(b) => {
+                self.add_instruction(&b.src);
+                self.add_location(&b.dest);
+                self.add_address_type(&b.dest_address_type);
+            }
+            ArrayCopy(b) => {
+                self.add_instruction(&b.dest);
+                self.add_instruction(&b.src);
+            }
+            Compute(b) => self.add_list(&b.stack),
+            Select(b) => {
+                self.add_instruction(&b.cond);
+                self.add_instruction(&b.if_true);
+                self.add_instruction(&b.if_false);
+            }
+            Call(b) => {
+                self.add_list(&b.arguments);
+                if let ReturnType::Final(data) = &b.return_info {
+                    self.add_location(&data.dest);
+                    self.add_address_type(&data.dest_address_type);
+                }
+            }
+            Branch(b) => {
+                self.branches += 1;
+                self.add_instruction(&b.cond);
+                self.add_list(&b.if_branch);
+                self.add_list(&b.else_branch);
+            }
+            Switch(b) => {
+                self.branches += 1;
+                self.add_instruction(&b.in_range);
+                self.add_instruction(&b.selector);
+                for case in &b.cases {
+                    self.add_list(&case.body);
+                }
+                self.add_list(&b.default_branch);
+            }
+            Loop(b) => {
+                self.loops += 1;
+                self.add_instruction(&b.continue_condition);
+                self.add_list(&b.body);
+            }
+            Return(b) => self.add_instruction(&b.value),
+            Assert(b) => {
+                self.add_instruction(&b.evaluate);
+                b.values.iter().for_each(|v| self.add_instruction(v));
+            }
+            CreateCmp(b) => self.add_instruction(&b.sub_cmp_id),
+            Log(b) => {
+                for print in &b.argsprint {
+                    if let LogBucketArg::LogExp(exp) = print {
+                        self.add_instruction(exp);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Size of a template or function before and after one run of a pass
+#[derive(Clone)]
+pub struct PassEffect {
+    pub code: String,
+    pub pass: Pass,
+    pub before: CodeSize,
+    pub after: CodeSize,
+}
+
+pub struct PassManager {
+    level: OptimizationLevel,
+    disabled: HashSet<Pass>,
+    timings: Vec<PassTiming>,
+    effects: Option<Vec<PassEffect>>,
+    verify: bool,
+    // checks of the code made, and the time spent in them
+    verifications: usize,
+    verify_time: Duration,
+}
+
+impl PassManager {
+    // maximum number of times the local simplifications are repeated
+    pub const MAX_ROUNDS: usize = 4;
+
+    pub fn new(config: &PassConfig) -> PassManager {
+        let timings = Pass::ALL
+            .iter()
+            .map(|pass| PassTiming { pass: *pass, runs: 0, time: Duration::ZERO })
+            .collect();
+        PassManager {
+            level: config.level,
+            disabled: config.disabled_passes.iter().copied().collect(),
+            timings,
+            effects: if config.report_effects { Some(Vec::new()) } else { None },
+            verify: config.verify,
+            verifications: 0,
+            verify_time: Duration::ZERO,
+        }
+    }
+
+    pub fn level(&self) -> OptimizationLevel {
+        self.level
+    }
+
+    pub fn is_enabled(&self, pass: Pass) -> bool {
+        self.level >= pass.minimum_level() && !self.disabled.contains(&pass)
+    }
+
+    // Runs the pass if it is enabled, returning its result
+    pub fn run<T, F: FnOnce() -> T>(&mut self, pass: Pass, f: F) -> Option<T> {
+        if !self.is_enabled(pass) {
+            return None;
+        }
+        let start = Instant::now();
+        let result = f();
+        let timing = self.timings.iter_mut().find(|timing| timing.pass == pass).unwrap();
+        timing.runs += 1;
+        timing.time += start.elapsed();
+        Some(result)
+    }
+
+    // Runs the pass over the code of a template or function, measuring its effect
+    pub fn run_on<T, F: FnOnce(&mut InstructionList) -> T>(
+        &mut self,
+        pass: Pass,
+        name: &str,
+        code: &mut InstructionList,
+        f: F,
+    ) -> Option<T> {
+        if !self.is_enabled(pass) {
+            return None;
+        }
+        let before = self.effects.as_ref().map(|_| CodeSize::of(code));
+        let result = self.run(pass, || f(code))?;
+        if let (Some(effects), Some(before)) = (&mut self.effects, before) {
+            let after = CodeSize::of(code);
+            effects.push(PassEffect { code: name.to_string(), pass, before, after });
+        }
+        Some(result)
+    }
+
+    // Stops the compilation if a stage produced code that breaks the invariants
+    // of the IR, before it is turned into silently wrong wasm or C
+    pub fn verify(&mut self, name: &str, stage: &str, code: &InstructionList, info: &VerifyInfo) {
+        if !self.verify {
+            return;
+        }
+        let start = Instant::now();
+        if let Err(error) = verify_list(code, info) {
+            panic!("Internal error in {} after {}: {}", name, stage, error);
+        }
+        self.verifications += 1;
+        self.verify_time += start.elapsed();
+    }
+
+    pub fn record_effect(&mut self, effect: PassEffect) {
+        if let Some(effects) = &mut self.effects {
+            effects.push(effect);
+        }
+    }
+
+    pub fn reports_effects(&self) -> bool {
+        self.effects.is_some()
+    }
+
+    pub fn effects(&mut self) -> Vec<PassEffect> {
+        self.effects.take().unwrap_or_default()
+    }
+
+    pub fn timings(&self) -> Vec<PassTiming> {
+        self.timings.iter().filter(|timing| timing.runs > 0).cloned().collect()
+    }
+
+    // The checks of the code made, with the time spent in them, if any
+    pub fn verify_timing(&self) -> Option<(usize, Duration)> {
+        if self.verifications > 0 {
+            Some((self.verifications, self.verify_time))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct PassReport {
+    pub pass: &'static str,
+    pub runs: usize,
+    pub instructions_removed: i64,
+    pub loops_removed: i64,
+    pub branches_removed: i64,
+}
+
+#[derive(Serialize)]
+pub struct CodeReport {
+    pub name: String,
+    pub instructions_before: usize,
+    pub instructions_after: usize,
+    pub passes: Vec<PassReport>,
+}
+
+fn removed(before: usize, after: usize) -> i64 {
+    before as i64 - after as i64
+}
+
+// Groups the effects by template or function, in the order they were produced
+pub fn build_report(effects: &[PassEffect]) -> Vec<CodeReport> {
+    let mut reports: Vec<CodeReport> = Vec::new();
+    for effect in effects {
+        let position = match reports.iter().position(|report| report.name == effect.code) {
+            Some(position) => position,
+            None => {
+                reports.push(CodeReport {
+                    name: effect.code.clone(),
+                    instructions_before: effect.before.instructions,
+                    instructions_after: effect.before.instructions,
+                    passes: Vec::new(),
+                });
+                reports.len() - 1
+            }
+        };
+        let report = &mut reports[position];
+        report.instructions_after = effect.after.instructions;
+        let pass = match report.passes.iter().position(|pass| pass.pass == effect.pass.name()) {
+            Some(position) => &mut report.passes[position],
+            None => {
+                report.passes.push(PassReport {
+                    pass: effect.pass.name(),
+                    runs: 0,
+                    instructions_removed: 0,
+                    loops_removed: 0,
+                    branches_removed: 0,
+                });
+                report.passes.last_mut().unwrap()
+            }
+        };
+        pass.runs += 1;
+        pass.instructions_removed += removed(effect.before.instructions, effect.after.instructions);
+        pass.loops_removed += removed(effect.before.loops, effect.after.loops);
+        pass.branches_removed += removed(effect.before.branches, effect.after.branches);
+    }
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OptimizationLevel, Pass, PassConfig, PassManager};
+    use crate::intermediate_representation::ir_interface::*;
+    use crate::ir_processing::test_code::*;
+    use crate::ir_processing::VerifyInfo;
+
+    fn manager(level: OptimizationLevel, disabled_passes: Vec<Pass>, verify: bool) -> PassManager {
+        PassManager::new(&PassConfig { level, disabled_passes, report_timing: true, report_effects: true, verify })
+    }
+
+    fn enabled(manager: &PassManager) -> Vec<Pass> {
+        Pass::ALL.iter().copied().filter(|pass| manager.is_enabled(*pass)).collect()
+    }
+
+    fn info() -> VerifyInfo {
+        VerifyInfo { variable_stack_depth: 1, signal_stack_depth: 1, max_message_id: 0, number_of_constants: 0, number_of_strings: 0 }
+    }
+
+    // An addition with a single operand
+    fn broken_code() -> InstructionList {
+        vec![set(var(0), compute(OperatorType::Add, vec![value(1)]))]
+    }
+
+    #[test]
+    fn the_level_selects_the_passes() {
+        assert!(enabled(&manager(OptimizationLevel::O0, Vec::new(), false)).is_empty());
+        let o1 = enabled(&manager(OptimizationLevel::O1, Vec::new(), false));
+        assert_eq!(o1.len(), 10);
+        assert!(!o1.contains(&Pass::FoldConstants) && !o1.contains(&Pass::MergeInstances));
+        assert_eq!(enabled(&manager(OptimizationLevel::O2, Vec::new(), false)), Pass::ALL);
+    }
+
+    #[test]
+    fn disabled_passes_do_not_run() {
+        let mut manager = manager(OptimizationLevel::O2, vec![Pass::PropagateCopies], false);
+        assert!(!manager.is_enabled(Pass::PropagateCopies));
+        assert_eq!(manager.run(Pass::PropagateCopies, || 1), None);
+        assert_eq!(manager.run(Pass::FoldConstants, || 1), Some(1));
+        let timings = manager.timings();
+        assert_eq!(timings.len(), 1);
+        assert_eq!((timings[0].pass, timings[0].runs), (Pass::FoldConstants, 1));
+    }
+
+    #[test]
+    fn the_effects_of_the_passes_are_recorded() {
+        let mut manager = manager(OptimizationLevel::O1, Vec::new(), false);
+        let mut code = vec![set(var(0), value(1)), set(var(0), value(2))];
+        manager.run_on(Pass::PropagateCopies, "T", &mut code, |code| code.truncate(1));
+        manager.run_on(Pass::FoldConstants, "T", &mut code, |code| code.clear());
+        let effects = manager.effects();
+        assert_eq!(effects.len(), 1);
+        assert_eq!((effects[0].before.instructions, effects[0].after.instructions), (6, 3));
+        assert_eq!(code.len(), 1);
+    }
+
+    #[test]
+    fn the_code_is_only_verified_when_asked() {
+        let mut manager = manager(OptimizationLevel::O0, Vec::new(), false);
+        manager.verify("T", "translation", &broken_code(), &info());
+        assert_eq!(manager.verify_timing(), None);
+        let mut manager = super::PassManager::new(&PassConfig { verify: true, ..PassConfig::default() });
+        manager.verify("T", "translation", &vec![set(var(0), value(1))], &info());
+        assert_eq!(manager.verify_timing().map(|(runs, _)| runs), Some(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Internal error in T after translation")]
+    fn broken_code_stops_the_verification() {
+        manager(OptimizationLevel::O0, Vec::new(), true).verify("T", "translation", &broken_code(), &info());
+    }
+}
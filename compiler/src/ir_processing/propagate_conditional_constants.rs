@@ -0,0 +1,339 @@
+use super::field_info::FieldInfo;
+use super::fold_constants::evaluate;
+use super::ssa::*;
+use crate::intermediate_representation::ir_interface::*;
+use circom_algebra::modular_arithmetic as arith;
+use num_bigint_dig::BigInt;
+
+#[derive(Clone, PartialEq)]
+enum Lattice {
+    // not reached yet
+    Unknown,
+    Constant(BigInt),
+    Varying,
+}
+
+fn meet(a: &Lattice, b: &Lattice) -> Lattice {
+    match (a, b) {
+        (Lattice::Unknown, other) | (other, Lattice::Unknown) => other.clone(),
+        (Lattice::Constant(x), Lattice::Constant(y)) if x == y => a.clone(),
+        _ => Lattice::Varying,
+    }
+}
+
+struct Propagation<'a, 'b> {
+    lattice: Vec<Lattice>,
+    tracked: &'a [bool],
+    field: &'a mut FieldInfo<'b>,
+    changed: bool,
+}
+
+impl Propagation<'_, '_> {
+    fn update(&mut self, value: ValueId, new: &Lattice) {
+        let met = meet(&self.lattice[value], new);
+        if met != self.lattice[value] {
+            self.lattice[value] = met;
+            self.changed = true;
+        }
+    }
+
+    fn truth(&self, cond: &Lattice) -> (bool, bool) {
+        match cond {
+            Lattice::Unknown => (false, false),
+            Lattice::Constant(c) => {
+                let value = arith::as_bool(c, self.field.prime);
+                (value, !value)
+            }
+            Lattice::Varying => (true, true),
+        }
+    }
+}
+
+// Replaces the reads of the variables that hold the same constant in every
+// execution, following only the branches that can be taken. Returns true
+// if the code was modified
+pub fn propagate(ssa: &mut SsaCode, field: &mut FieldInfo) -> bool {
+    let mut lattice = vec![Lattice::Unknown; ssa.values.len()];
+    for (id, value) in ssa.values.iter().enumerate() {
+        if let Definition::Entry = value.definition {
+            lattice[id] = Lattice::Varying;
+        }
+    }
+    let mut propagation = Propagation { lattice, tracked: &ssa.tracked, field, changed: true };
+    while propagation.changed {
+        propagation.changed = false;
+        sweep_region(&mut ssa.body, &ssa.values, &mut propagation);
+    }
+    rewrite_region(&mut ssa.body, &mut propagation)
+}
+
+// Evaluates the region once, returning whether its end can be reached
+fn sweep_region(region: &mut Region, values: &[Value], propagation: &mut Propagation) -> bool {
+    for item in region {
+        match item {
+            Item::Plain { instruction, uses, defs } => {
+                let value = match (&mut **instruction, defs.as_slice()) {
+                    (Instruction::Store(b), [_]) if b.context.size == SizeOption::Single(1) => {
+                        evaluate_instruction(&mut b.src, &mut uses.iter(), propagation)
+                    }
+                    _ => Lattice::Varying,
+                };
+                for def in defs.iter() {
+                    propagation.update(*def, &value);
+                }
+                if let Instruction::Return(_) = **instruction {
+                    return false;
+                }
+            }
+            Item::Branch { bucket, uses, if_branch, else_branch, phis } => {
+                let cond = evaluate_instruction(&mut bucket.cond, &mut uses.iter(), propagation);
+                let (if_taken, else_taken) = propagation.truth(&cond);
+                let if_ends = if_taken && sweep_region(if_branch, values, propagation);
+                let else_ends = else_taken && sweep_region(else_branch, values, propagation);
+                for phi in phis.iter() {
+                    if let Definition::Phi(incoming) = &values[*phi].definition {
+                        if if_ends {
+                            let value = propagation.lattice[incoming[0]].clone();
+                            propagation.update(*phi, &value);
+                        }
+                        if else_ends {
+                            let value = propagation.lattice[incoming[1]].clone();
+                            propagation.update(*phi, &value);
+                        }
+                    }
+                }
+                if !if_ends && !else_ends {
+                    return false;
+                }
+            }
+            Item::Loop { bucket, phis, uses, body } => {
+                // the values of the body are Unknown until it is reached
+                for phi in phis.iter() {
+                    if let Definition::Phi(incoming) = &values[*phi].definition {
+                        let value = meet(&propagation.lattice[incoming[0]], &propagation.lattice[incoming[1]]);
+                        propagation.update(*phi, &value);
+                    }
+                }
+                let cond = evaluate_instruction(&mut bucket.continue_condition, &mut uses.iter(), propagation);
+                let (enters, exits) = propagation.truth(&cond);
+                if enters {
+                    sweep_region(body, values, propagation);
+                }
+                if !exits {
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
+fn skip(instruction: &mut Instruction, uses: &mut std::slice::Iter<ValueId>, tracked: &[bool]) {
+    for _ in 0..count_uses(instruction, tracked) {
+        uses.next();
+    }
+}
+
+fn evaluate_instruction(
+    instruction: &mut Instruction,
+    uses: &mut std::slice::Iter<ValueId>,
+    propagation: &Propagation,
+) -> Lattice {
+    if let Some(value) = propagation.field.constant(instruction) {
+        return Lattice::Constant(value);
+    }
+    match instruction {
+        Instruction::Load(_) => match load_range(instruction) {
+            Some((start, 1)) if propagation.tracked.get(start) == Some(&true) => match uses.next() {
+                Some(value) => propagation.lattice[*value].clone(),
+                None => Lattice::Varying,
+            },
+            _ => {
+                skip(instruction, uses, propagation.tracked);
+                Lattice::Varying
+            }
+        },
+        Instruction::Compute(b) if !b.op.is_address_op() => {
+            // every operand is evaluated to consume its uses
+            let mut operands = Vec::with_capacity(b.stack.len());
+            let (mut unknown, mut varying) = (false, false);
+            for i in &mut b.stack {
+                match evaluate_instruction(i, uses, propagation) {
+                    Lattice::Constant(value) => operands.push(value),
+                    Lattice::Unknown => unknown = true,
+                    Lattice::Varying => varying = true,
+                }
+            }
+            if unknown {
+                Lattice::Unknown
+            } else if varying {
+                Lattice::Varying
+            } else {
+                match evaluate(&b.op, &operands, propagation.field.prime) {
+                    Some(value) => Lattice::Constant(value),
+                    None => Lattice::Varying,
+                }
+            }
+        }
+        _ => {
+            skip(instruction, uses, propagation.tracked);
+            Lattice::Varying
+        }
+    }
+}
+
+fn rewrite_region(region: &mut Region, propagation: &mut Propagation) -> bool {
+    let mut changed = false;
+    for item in region {
+        match item {
+            Item::Plain { instruction, uses, .. } => {
+                changed |= rewrite_uses(instruction, uses, propagation);
+            }
+            Item::Branch { bucket, uses, if_branch, else_branch, .. } => {
+                changed |= rewrite_uses(&mut bucket.cond, uses, propagation);
+                changed |= rewrite_region(if_branch, propagation);
+                changed |= rewrite_region(else_branch, propagation);
+            }
+            Item::Loop { bucket, uses, body, .. } => {
+                changed |= rewrite_uses(&mut bucket.continue_condition, uses, propagation);
+                changed |= rewrite_region(body, propagation);
+            }
+        }
+    }
+    changed
+}
+
+// Replaces the loads of constant values, removing them from the uses
+fn rewrite_uses(
+    instruction: &mut Instruction,
+    uses: &mut Vec<ValueId>,
+    propagation: &mut Propagation,
+) -> bool {
+    let old_uses = std::mem::take(uses);
+    let mut old_uses = old_uses.into_iter();
+    let tracked = propagation.tracked;
+    let lattice = &propagation.lattice;
+    let field = &mut *propagation.field;
+    let mut changed = false;
+    visit_loads(instruction, &mut |load| {
+        let (start, size) = match load_range(load) {
+            Some(range) => range,
+            None => return,
+        };
+        let count = tracked_slots(tracked, start, size).count();
+        let values: Vec<ValueId> = old_uses.by_ref().take(count).collect();
+        if let ([value], 1) = (values.as_slice(), size) {
+            if let Lattice::Constant(constant) = &lattice[*value] {
+                *load = *field.constant_bucket(constant, load.get_line(), load.get_message_id());
+                changed = true;
+                return;
+            }
+        }
+        uses.extend(values);
+    });
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::propagate;
+    use crate::intermediate_representation::ir_interface::*;
+    use crate::ir_processing::ssa;
+    use crate::ir_processing::test_code::*;
+    use num_bigint_dig::BigInt;
+    use std::collections::HashMap;
+
+    fn propagated(field: &mut Field, code: InstructionList) -> (bool, InstructionList) {
+        let mut ssa = ssa::build(code, &HashMap::new()).ok().unwrap();
+        let changed = propagate(&mut ssa, &mut field.info());
+        (changed, ssa.lower())
+    }
+
+    // The constant stored in the signal by the last instruction, if any
+    fn stored_constant(field: &mut Field, code: &InstructionList) -> Option<BigInt> {
+        match &**code.last().unwrap() {
+            Instruction::Store(b) => field.info().constant(&b.src),
+            _ => unreachable!(),
+        }
+    }
+
+    fn add(a: InstructionPointer, b: InstructionPointer) -> InstructionPointer {
+        compute(OperatorType::Add, vec![a, b])
+    }
+
+    #[test]
+    fn reads_of_constant_variables_are_replaced() {
+        let mut field = Field::new();
+        let code = vec![set(var(0), field.constant(3)), set_signal(value(0), load_var(var(0)))];
+        let (changed, code) = propagated(&mut field, code);
+        assert!(changed);
+        assert_eq!(stored_constant(&mut field, &code), Some(BigInt::from(3)));
+    }
+
+    #[test]
+    fn only_the_branches_that_can_be_taken_are_followed() {
+        let mut field = Field::new();
+        let code = vec![
+            set(var(0), field.constant(1)),
+            branch(load_var(var(0)), vec![set(var(1), field.constant(2))], vec![set(var(1), field.constant(3))]),
+            set_signal(value(0), load_var(var(1))),
+        ];
+        let (_, code) = propagated(&mut field, code);
+        assert_eq!(stored_constant(&mut field, &code), Some(BigInt::from(2)));
+    }
+
+    #[test]
+    fn values_that_depend_on_the_branch_taken_are_kept() {
+        let mut field = Field::new();
+        let code = vec![
+            branch(load_signal(value(1)), vec![set(var(0), field.constant(2))], vec![set(var(0), field.constant(3))]),
+            set_signal(value(0), load_var(var(0))),
+        ];
+        let expected = text(&code);
+        assert_eq!(text(&propagated(&mut field, code).1), expected);
+    }
+
+    #[test]
+    fn variables_rewritten_by_a_loop_are_kept() {
+        let mut field = Field::new();
+        let less = compute(OperatorType::Lesser, vec![load_var(var(0)), field.constant(3)]);
+        let code = vec![
+            set(var(0), field.constant(0)),
+            repeat(less, vec![set(var(0), add(load_var(var(0)), field.constant(1)))]),
+            set_signal(value(0), load_var(var(0))),
+        ];
+        let (_, code) = propagated(&mut field, code);
+        assert_eq!(stored_constant(&mut field, &code), None);
+    }
+
+    #[test]
+    fn loops_that_write_the_same_constant_keep_it() {
+        let mut field = Field::new();
+        let code = vec![
+            set(var(0), field.constant(5)),
+            repeat(load_signal(value(1)), vec![set(var(0), field.constant(5))]),
+            set_signal(value(0), load_var(var(0))),
+        ];
+        let (_, code) = propagated(&mut field, code);
+        assert_eq!(stored_constant(&mut field, &code), Some(BigInt::from(5)));
+    }
+
+    #[test]
+    fn divisions_by_zero_are_not_evaluated() {
+        let mut field = Field::new();
+        let division = compute(OperatorType::Div, vec![field.constant(1), field.constant(0)]);
+        let code = vec![set(var(0), division), set_signal(value(0), load_var(var(0)))];
+        let expected = text(&code);
+        let (changed, code) = propagated(&mut field, code);
+        assert!(!changed);
+        assert_eq!(text(&code), expected);
+    }
+
+    #[test]
+    fn negative_constants_are_taken_modulo_the_prime() {
+        let mut field = Field::new();
+        let code = vec![set(var(0), field.constant(-1)), set_signal(value(0), load_var(var(0)))];
+        let (_, code) = propagated(&mut field, code);
+        assert_eq!(stored_constant(&mut field, &code), Some(&field.prime - 1));
+    }
+}
@@ -0,0 +1,399 @@
+use super::field_info::FieldInfo;
+use crate::circuit_design::template::TemplateCode;
+use crate::intermediate_representation::ir_interface::*;
+use num_bigint_dig::BigInt;
+use std::collections::{HashMap, HashSet};
+
+enum InputValue {
+    Constant(BigInt),
+    Unknown,
+}
+
+// Values stored in the inputs of the subcomponents of every template
+#[derive(Default)]
+struct InputsInfo {
+    // for each template header its position in the list of templates
+    template_ids: HashMap<String, usize>,
+    values: HashMap<(usize, usize), InputValue>,
+    // templates whose inputs may be assigned in an unknown position
+    unknown_templates: HashSet<usize>,
+}
+
+impl InputsInfo {
+    fn new(templates: &[TemplateCode], main_header: &str) -> InputsInfo {
+        let mut info = InputsInfo::default();
+        for (id, template) in templates.iter().enumerate() {
+            info.template_ids.insert(template.header.clone(), id);
+            if template.header == main_header {
+                // the inputs of the main component are given by the user
+                info.unknown_templates.insert(id);
+            }
+        }
+        info
+    }
+
+    fn register(&mut self, template: usize, signal: usize, value: Option<BigInt>) {
+        let new_value = match (self.values.get(&(template, signal)), value) {
+            (None, Some(v)) => InputValue::Constant(v),
+            (Some(InputValue::Constant(old)), Some(v)) if *old == v => InputValue::Constant(v),
+            _ => InputValue::Unknown,
+        };
+        self.values.insert((template, signal), new_value);
+    }
+
+    fn known_inputs(&self, template: usize) -> HashMap<usize, &BigInt> {
+        let mut known = HashMap::new();
+        if self.unknown_templates.contains(&template) {
+            return known;
+        }
+        for ((id, signal), value) in &self.values {
+            if let (true, InputValue::Constant(v)) = (*id == template, value) {
+                known.insert(*signal, v);
+            }
+        }
+        known
+    }
+}
+
+// Replaces the loads of the inputs that receive the same constant in every
+// instance of a template and simplifies the resulting code
+pub fn propagate(templates: &mut [TemplateCode], main_header: &str, field: &mut FieldInfo) {
+    // each round may turn into constants the inputs of deeper subcomponents
+    for _ in 0..=templates.len() {
+        let mut info = InputsInfo::new(templates, main_header);
+        for template in templates.iter() {
+            collect_template(&template.body, &mut info, field);
+        }
+        let mut changed = false;
+        for (id, template) in templates.iter_mut().enumerate() {
+            let known = info.known_inputs(id);
+            if !known.is_empty() && replace_list(&mut template.body, &known, field) {
+                super::fold_constants::fold_list(&mut template.body, field);
+                super::build_inputs_info(&mut template.body, field.field_tracker, field.prime);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+}
+
+#[derive(Default)]
+struct TemplateStores {
+    created_templates: HashSet<String>,
+    has_mapped_stores: bool,
+}
+
+fn collect_template(code: &InstructionList, info: &mut InputsInfo, field: &FieldInfo) {
+    let mut stores = TemplateStores::default();
+    collect_list(code, info, &mut stores, field);
+    if stores.has_mapped_stores {
+        // the store may be done in any of the components of the template
+        for header in &stores.created_templates {
+            if let Some(id) = info.template_ids.get(header) {
+                info.unknown_templates.insert(*id);
+            }
+        }
+    }
+}
+
+fn collect_list(
+    instructions: &InstructionList,
+    info: &mut InputsInfo,
+    stores: &mut TemplateStores,
+    field: &FieldInfo,
+) {
+    for i in instructions {
+        collect_instruction(i, info, stores, field);
+    }
+}
+
+fn collect_instruction(
+    instruction: &Instruction,
+    info: &mut InputsInfo,
+    stores: &mut TemplateStores,
+    field: &FieldInfo,
+) {
+    use Instruction::*;
+    match instruction {
+        Store(b) => {
+            let value = field.constant(&b.src);
+            collect_store(&b.dest_address_type, &b.dest, &b.context, value, info, stores);
+        }
+        Call(b) => {
+            if let ReturnType::Final(data) = &b.return_info {
+                collect_store(&data.dest_address_type, &data.dest, &data.context, None, info, stores);
+            }
+        }
+        Branch(b) => {
+            collect_list(&b.if_branch, info, stores, field);
+            collect_list(&b.else_branch, info, stores, field);
+        }
+        Switch(b) => {
+            for case in &b.cases {
+                collect_list(&case.body, info, stores, field);
+            }
+            collect_list(&b.default_branch, info, stores, field);
+        }
+        Loop(b) => collect_list(&b.body, info, stores, field),
+        CreateCmp(b) => {
+            stores.created_templates.insert(b.symbol.clone());
+        }
+        _ => {}
+    }
+}
+
+fn collect_store(
+    xtype: &AddressType,
+    dest: &LocationRule,
+    context: &InstrContext,
+    value: Option<BigInt>,
+    info: &mut InputsInfo,
+    stores: &mut TemplateStores,
+) {
+    if let AddressType::SubcmpSignal { .. } = xtype {
+        match dest {
+            LocationRule::Indexed { location, template_header: Some(header) } => {
+                let id = info.template_ids[header];
+                match (&**location, &context.size) {
+                    (Instruction::Value(v), SizeOption::Single(1)) => info.register(id, v.value, value),
+                    (Instruction::Value(v), SizeOption::Single(size)) => {
+                        for signal in v.value..v.value + size {
+                            info.register(id, signal, None);
+                        }
+                    }
+                    _ => {
+                        info.unknown_templates.insert(id);
+                    }
+                }
+            }
+            _ => stores.has_mapped_stores = true,
+        }
+    }
+}
+
+fn known_signal(load: &LoadBucket, known: &HashMap<usize, &BigInt>) -> Option<BigInt> {
+    if let (AddressType::Signal, SizeOption::Single(1)) = (&load.address_type, &load.context.size) {
+        if let LocationRule::Indexed { location, .. } = &load.src {
+            if let Instruction::Value(v) = &**location {
+                return known.get(&v.value).map(|value| (*value).clone());
+            }
+        }
+    }
+    None
+}
+
+fn replace_list(
+    instructions: &mut InstructionList,
+    known: &HashMap<usize, &BigInt>,
+    field: &mut FieldInfo,
+) -> bool {
+    let mut changed = false;
+    for i in instructions {
+        changed |= replace_instruction(i, known, field);
+    }
+    changed
+}
+
+fn replace_instruction(
+    instruction: &mut Instruction,
+    known: &HashMap<usize, &BigInt>,
+    field: &mut FieldInfo,
+) -> bool {
+    use Instruction::*;
+    match instruction {
+        Value(_) => false,
+        Load(b) => {
+            if let Some(value) = known_signal(b, known) {
+                *instruction = *field.constant_bucket(&value, b.line, b.message_id);
+                return true;
+            }
+            replace_location(&mut b.src, known, field)
+                | replace_address_type(&mut b.address_type, known, field)
+        }
+        Store(b) => {
+            replace_instruction(&mut b.src, known, field)
+                | replace_location(&mut b.dest, known, field)
+                | replace_address_type(&mut b.dest_address_type, known, field)
+        }
+        ArrayCopy(b) => {
+            replace_instruction(&mut b.dest, known, field) | replace_instruction(&mut b.src, known, field)
+        }
+        Compute(b) => replace_list(&mut b.stack, known, field),
+        Select(b) => {
+            replace_instruction(&mut b.cond, known, field)
+                | replace_instruction(&mut b.if_true, known, field)
+                | replace_instruction(&mut b.if_false, known, field)
+        }
+        Call(b) => {
+            let mut changed = replace_list(&mut b.arguments, known, field);
+            if let ReturnType::Final(data) = &mut b.return_info {
+                changed |= replace_location(&mut data.dest, known, field);
+                changed |= replace_address_type(&mut data.dest_address_type, known, field);
+            }
+            changed
+        }
+        Branch(b) => {
+            replace_instruction(&mut b.cond, known, field)
+                | replace_list(&mut b.if_branch, known, field)
+                | replace_list(&mut b.else_branch, known, field)
+        }
+        Switch(b) => {
+            let mut changed = replace_instruction(&mut b.in_range, known, field)
+                | replace_instruction(&mut b.selector, known, field);
+            for case in &mut b.cases {
+                changed |= replace_list(&mut case.body, known, field);
+            }
+            changed | replace_list(&mut b.default_branch, known, field)
+        }
+        Loop(b) => {
+            replace_instruction(&mut b.continue_condition, known, field)
+                | replace_list(&mut b.body, known, field)
+        }
+        Return(b) => replace_instruction(&mut b.value, known, field),
+        Assert(b) => {
+            let mut changed = replace_instruction(&mut b.evaluate, known, field);
+            for v in &mut b.values {
+                changed |= replace_instruction(v, known, field);
+            }
+            changed
+        }
+        CreateCmp(b) => replace_instruction(&mut b.sub_cmp_id, known, field),
+        Log(b) => {
+            let mut changed = false;
+            for print in &mut b.argsprint {
+                if let LogBucketArg::LogExp(exp) = print {
+                    changed |= replace_instruction(exp, known, field);
+                }
+            }
+            changed
+        }
+    }
+}
+
+fn replace_location(
+    location: &mut LocationRule,
+    known: &HashMap<usize, &BigInt>,
+    field: &mut FieldInfo,
+) -> bool {
+    match location {
+        LocationRule::Indexed { location, .. } => replace_instruction(location, known, field),
+        LocationRule::Mapped { indexes, .. } => {
+            let mut changed = false;
+            for access in indexes {
+                if let AccessType::Indexed(instr) = access {
+                    changed |= replace_list(&mut instr.indexes, known, field);
+                }
+            }
+            changed
+        }
+    }
+}
+
+fn replace_address_type(
+    xtype: &mut AddressType,
+    known: &HashMap<usize, &BigInt>,
+    field: &mut FieldInfo,
+) -> bool {
+    if let AddressType::SubcmpSignal { cmp_address, .. } = xtype {
+        replace_instruction(cmp_address, known, field)
+    } else {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::propagate;
+    use crate::circuit_design::template::{TemplateCode, TemplateCodeInfo};
+    use crate::intermediate_representation::ir_interface::*;
+    use crate::ir_processing::test_code::*;
+    use num_bigint_dig::BigInt;
+
+    fn template(header: &str, body: InstructionList) -> TemplateCode {
+        Box::new(TemplateCodeInfo { header: header.to_string(), name: header.to_string(), body, ..Default::default() })
+    }
+
+    // input of the subcomponent 0 of template header = src
+    fn set_input(header: &str, signal: usize, src: InstructionPointer) -> InstructionPointer {
+        let dest_address_type = AddressType::SubcmpSignal {
+            cmp_address: value(0),
+            uniform_parallel_value: Some(false),
+            is_output: false,
+            input_information: InputInformation::Input { status: StatusInput::Unknown },
+        };
+        let mut store = store(dest_address_type, value(signal), src);
+        if let Instruction::Store(b) = &mut *store {
+            b.dest = LocationRule::Indexed { location: value(signal), template_header: Some(header.to_string()) };
+        }
+        store
+    }
+
+    // out = in * other, with out the signal 0, in the 1 and other the 2
+    fn sub() -> TemplateCode {
+        let product = compute(OperatorType::Mul, vec![load_signal(value(1)), load_signal(value(2))]);
+        template("Sub", vec![set_signal(value(0), product)])
+    }
+
+    // The factors of the product computed by Sub after the propagation
+    fn factors(field: &mut Field, templates: &[TemplateCode]) -> Vec<Option<BigInt>> {
+        match &*templates[1].body[0] {
+            Instruction::Store(b) => match &*b.src {
+                Instruction::Compute(c) => c.stack.iter().map(|i| field.info().constant(i)).collect(),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    fn propagated(field: &mut Field, stores: Vec<InstructionPointer>) -> Vec<Option<BigInt>> {
+        let mut templates = vec![template("Main", stores), sub()];
+        propagate(&mut templates, "Main", &mut field.info());
+        factors(field, &templates)
+    }
+
+    #[test]
+    fn inputs_that_always_get_the_same_constant_are_replaced() {
+        let mut field = Field::new();
+        let stores = vec![
+            set_input("Sub", 1, field.constant(3)),
+            set_input("Sub", 2, load_signal(value(0))),
+            set_input("Sub", 1, field.constant(3)),
+        ];
+        assert_eq!(propagated(&mut field, stores), [Some(BigInt::from(3)), None]);
+    }
+
+    #[test]
+    fn inputs_that_get_different_constants_are_kept() {
+        let mut field = Field::new();
+        let stores = vec![set_input("Sub", 1, field.constant(3)), set_input("Sub", 1, field.constant(4))];
+        assert_eq!(propagated(&mut field, stores), [None, None]);
+    }
+
+    #[test]
+    fn negative_constants_are_propagated_modulo_the_prime() {
+        let mut field = Field::new();
+        let stores = vec![set_input("Sub", 1, field.constant(-1)), set_input("Sub", 2, field.constant(2))];
+        let minus_one = &field.prime - 1;
+        // both factors are known and the product is folded
+        let mut templates = vec![template("Main", stores), sub()];
+        propagate(&mut templates, "Main", &mut field.info());
+        match &*templates[1].body[0] {
+            Instruction::Store(b) => {
+                assert_eq!(field.info().constant(&b.src), Some(minus_one * 2 % &field.prime));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn the_inputs_of_the_main_component_are_kept() {
+        let mut field = Field::new();
+        // Sub is the main component and also a subcomponent of Other
+        let mut templates = vec![sub(), template("Other", vec![set_input("Sub", 1, field.constant(3))])];
+        propagate(&mut templates, "Sub", &mut field.info());
+        let product = compute(OperatorType::Mul, vec![load_signal(value(1)), load_signal(value(2))]);
+        assert_eq!(text(&templates[0].body), text(&vec![set_signal(value(0), product)]));
+    }
+}
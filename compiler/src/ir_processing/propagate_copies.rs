@@ -0,0 +1,514 @@
+use crate::intermediate_representation::ir_interface::*;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Kind {
+    Variable,
+    Signal,
+}
+
+type Position = (Kind, usize);
+// for each position that holds a copy, the position it was copied from
+type Copies = HashMap<Position, Position>;
+
+pub fn propagate_list(instructions: &mut InstructionList) {
+    forward_list(instructions, &mut Copies::new());
+    if let Some(loaded) = loaded_variables(instructions) {
+        remove_dead_stores(instructions, &loaded);
+    }
+}
+
+fn kind_of(xtype: &AddressType) -> Option<Kind> {
+    match xtype {
+        AddressType::Variable => Some(Kind::Variable),
+        AddressType::Signal => Some(Kind::Signal),
+        AddressType::SubcmpSignal { .. } => None,
+    }
+}
+
+fn constant_index(index: &Instruction) -> Option<usize> {
+    match index {
+        Instruction::Value(v) if v.parse_as == ValueType::U32 => Some(v.value),
+        _ => None,
+    }
+}
+
+fn constant_location(location: &LocationRule) -> Option<usize> {
+    match location {
+        LocationRule::Indexed { location, .. } => constant_index(location),
+        LocationRule::Mapped { .. } => None,
+    }
+}
+
+fn single_size(context: &InstrContext) -> Option<usize> {
+    match &context.size {
+        SizeOption::Single(size) => Some(*size),
+        SizeOption::Multiple(_) => None,
+    }
+}
+
+fn copied_position(load: &LoadBucket) -> Option<Position> {
+    let kind = kind_of(&load.address_type)?;
+    if single_size(&load.context)? != 1 {
+        return None;
+    }
+    Some((kind, constant_location(&load.src)?))
+}
+
+fn invalidate(copies: &mut Copies, kind: Kind, range: Option<(usize, usize)>) {
+    let affected = |position: &Position| {
+        position.0 == kind
+            && range.is_none_or(|(start, size)| start <= position.1 && position.1 < start + size)
+    };
+    copies.retain(|dest, src| !affected(dest) && !affected(src));
+}
+
+fn invalidate_store(
+    copies: &mut Copies,
+    xtype: &AddressType,
+    location: &LocationRule,
+    context: &InstrContext,
+) {
+    if let Some(kind) = kind_of(xtype) {
+        let range = constant_location(location).zip(single_size(context));
+        invalidate(copies, kind, range);
+    }
+}
+
+fn forward_list(instructions: &mut InstructionList, copies: &mut Copies) {
+    for i in instructions {
+        forward_instruction(i, copies);
+    }
+}
+
+fn forward_instruction(instruction: &mut Instruction, copies: &mut Copies) {
+    use Instruction::*;
+    match instruction {
+        Store(b) => forward_store(b, copies),
+        ArrayCopy(b) => {
+            rewrite_instruction(&mut b.dest, copies);
+            rewrite_instruction(&mut b.src, copies);
+            if let Some(kind) = kind_of(&b.dest_address_type) {
+                invalidate(copies, kind, constant_index(&b.dest).map(|start| (start, b.size)));
+            }
+        }
+        Call(b) => {
+            rewrite_call(b, copies);
+            if let ReturnType::Final(data) = &b.return_info {
+                invalidate_store(copies, &data.dest_address_type, &data.dest, &data.context);
+            }
+        }
+        Branch(b) => {
+            rewrite_instruction(&mut b.cond, copies);
+            copies.clear();
+            forward_list(&mut b.if_branch, &mut Copies::new());
+            forward_list(&mut b.else_branch, &mut Copies::new());
+        }
+        Switch(b) => {
+            rewrite_instruction(&mut b.in_range, copies);
+            rewrite_instruction(&mut b.selector, copies);
+            copies.clear();
+            for case in &mut b.cases {
+                forward_list(&mut case.body, &mut Copies::new());
+            }
+            forward_list(&mut b.default_branch, &mut Copies::new());
+        }
+        Loop(b) => {
+            // the condition and the body are executed after the previous iteration
+            copies.clear();
+            forward_list(&mut b.body, &mut Copies::new());
+        }
+        Return(b) => rewrite_instruction(&mut b.value, copies),
+        Assert(b) => {
+            rewrite_instruction(&mut b.evaluate, copies);
+            b.values.iter_mut().for_each(|v| rewrite_instruction(v, copies));
+        }
+        CreateCmp(b) => rewrite_instruction(&mut b.sub_cmp_id, copies),
+        Log(b) => {
+            for print in &mut b.argsprint {
+                if let LogBucketArg::LogExp(exp) = print {
+                    rewrite_instruction(exp, copies);
+                }
+            }
+        }
+        Compute(_) | Select(_) | Load(_) | Value(_) => rewrite_instruction(instruction, copies),
+    }
+}
+
+fn forward_store(bucket: &mut StoreBucket, copies: &mut Copies) {
+    rewrite_instruction(&mut bucket.src, copies);
+    rewrite_location(&mut bucket.dest, copies);
+    rewrite_address_type(&mut bucket.dest_address_type, copies);
+    invalidate_store(copies, &bucket.dest_address_type, &bucket.dest, &bucket.context);
+
+    let dest = kind_of(&bucket.dest_address_type).zip(constant_location(&bucket.dest));
+    let src = match &*bucket.src {
+        Instruction::Load(load) => copied_position(load),
+        _ => None,
+    };
+    if let (Some(dest), Some(src)) = (dest, src) {
+        if single_size(&bucket.context) == Some(1) && dest != src {
+            copies.insert(dest, src);
+        }
+    }
+}
+
+// Replaces the loads of copied positions inside an expression
+fn rewrite_instruction(instruction: &mut Instruction, copies: &Copies) {
+    use Instruction::*;
+    match instruction {
+        Load(b) => rewrite_load(b, copies),
+        Compute(b) => {
+            for i in &mut b.stack {
+                rewrite_instruction(i, copies);
+            }
+        }
+        Select(b) => {
+            rewrite_instruction(&mut b.cond, copies);
+            rewrite_instruction(&mut b.if_true, copies);
+            rewrite_instruction(&mut b.if_false, copies);
+        }
+        Call(b) => rewrite_call(b, copies),
+        _ => {}
+    }
+}
+
+fn rewrite_load(bucket: &mut LoadBucket, copies: &Copies) {
+    rewrite_location(&mut bucket.src, copies);
+    rewrite_address_type(&mut bucket.address_type, copies);
+    if let Some(position) = copied_position(bucket) {
+        if let Some((kind, src)) = copies.get(&position) {
+            bucket.address_type = match kind {
+                Kind::Variable => AddressType::Variable,
+                Kind::Signal => AddressType::Signal,
+            };
+            if let LocationRule::Indexed { location, .. } = &mut bucket.src {
+                if let Instruction::Value(v) = &mut **location {
+                    v.value = *src;
+                }
+            }
+        }
+    }
+}
+
+fn rewrite_call(bucket: &mut CallBucket, copies: &Copies) {
+    for i in &mut bucket.arguments {
+        rewrite_instruction(i, copies);
+    }
+    if let ReturnType::Final(data) = &mut bucket.return_info {
+        rewrite_location(&mut data.dest, copies);
+        rewrite_address_type(&mut data.dest_address_type, copies);
+    }
+}
+
+fn rewrite_location(location: &mut LocationRule, copies: &Copies) {
+    match location {
+        LocationRule::Indexed { location, .. } => rewrite_instruction(location, copies),
+        LocationRule::Mapped { indexes, .. } => {
+            for access in indexes {
+                if let AccessType::Indexed(instr) = access {
+                    for i in &mut instr.indexes {
+                        rewrite_instruction(i, copies);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn rewrite_address_type(xtype: &mut AddressType, copies: &Copies) {
+    if let AddressType::SubcmpSignal { cmp_address, .. } = xtype {
+        rewrite_instruction(cmp_address, copies);
+    }
+}
+
+// Returns the variable slots read somewhere in the code, None if some
+// variable is read using a non constant address
+fn loaded_variables(instructions: &InstructionList) -> Option<HashSet<usize>> {
+    let mut loaded = HashSet::new();
+    let mut all_known = true;
+    for i in instructions {
+        collect_loads(i, &mut loaded, &mut all_known);
+    }
+    if all_known {
+        Some(loaded)
+    } else {
+        None
+    }
+}
+
+fn collect_loads(instruction: &Instruction, loaded: &mut HashSet<usize>, all_known: &mut bool) {
+    use Instruction::*;
+    let mut visit = |i: &Instruction| collect_loads(i, loaded, all_known);
+    match instruction {
+        Value(_) => {}
+        Load(b) => {
+            visit_location(&b.src, &mut visit);
+            if let AddressType::SubcmpSignal { cmp_address, .. } = &b.address_type {
+                visit(cmp_address);
+            }
+            if let AddressType::Variable = b.address_type {
+                match constant_location(&b.src).zip(single_size(&b.context)) {
+                    Some((start, size)) => loaded.extend(start..start + size),
+                    None => *all_known = false,
+                }
+            }
+        }
+        Store(b) => {
+            visit(&b.src);
+            visit_location(&b.dest, &mut visit);
+            if let AddressType::SubcmpSignal { cmp_address, .. } = &b.dest_address_type {
+                visit(cmp_address);
+            }
+            if let Some(address) = &b.src_address_type {
+                visit(address);
+            }
+        }
+        ArrayCopy(b) => {
+            visit(&b.dest);
+            visit(&b.src);
+            if let AddressType::Variable = b.src_address_type {
+                match constant_index(&b.src) {
+                    Some(start) => loaded.extend(start..start + b.size),
+                    None => *all_known = false,
+                }
+            }
+        }
+        Compute(b) => b.stack.iter().for_each(|i| visit(i)),
+        Select(b) => {
+            visit(&b.cond);
+            visit(&b.if_true);
+            visit(&b.if_false);
+        }
+        Call(b) => {
+            b.arguments.iter().for_each(|i| visit(i));
+            if let ReturnType::Final(data) = &b.return_info {
+                visit_location(&data.dest, &mut visit);
+                if let AddressType::SubcmpSignal { cmp_address, .. } = &data.dest_address_type {
+                    visit(cmp_address);
+                }
+            }
+        }
+        Branch(b) => {
+            visit(&b.cond);
+            b.if_branch.iter().for_each(|i| visit(i));
+            b.else_branch.iter().for_each(|i| visit(i));
+        }
+        Switch(b) => {
+            visit(&b.in_range);
+            visit(&b.selector);
+            b.cases.iter().flat_map(|case| &case.body).for_each(|i| visit(i));
+            b.default_branch.iter().for_each(|i| visit(i));
+        }
+        Loop(b) => {
+            visit(&b.continue_condition);
+            b.body.iter().for_each(|i| visit(i));
+        }
+        Return(b) => visit(&b.value),
+        Assert(b) => {
+            visit(&b.evaluate);
+            b.values.iter().for_each(|v| visit(v));
+        }
+        CreateCmp(b) => visit(&b.sub_cmp_id),
+        Log(b) => {
+            for print in &b.argsprint {
+                if let LogBucketArg::LogExp(exp) = print {
+                    visit(exp);
+                }
+            }
+        }
+    }
+}
+
+fn visit_location<F: FnMut(&Instruction)>(location: &LocationRule, visit: &mut F) {
+    match location {
+        LocationRule::Indexed { location, .. } => visit(location),
+        LocationRule::Mapped { indexes, .. } => {
+            for access in indexes {
+                if let AccessType::Indexed(instr) = access {
+                    instr.indexes.iter().for_each(|i| visit(i));
+                }
+            }
+        }
+    }
+}
+
+// Only copies whose evaluation cannot fail are discarded
+fn can_be_discarded(instruction: &Instruction) -> bool {
+    use Instruction::*;
+    match instruction {
+        Value(_) => true,
+        Load(b) => location_has_no_calls(&b.src) && address_has_no_calls(&b.address_type),
+        _ => false,
+    }
+}
+
+fn has_no_calls(instruction: &Instruction) -> bool {
+    use Instruction::*;
+    use OperatorType::*;
+    match instruction {
+        Value(_) => true,
+        Load(b) => location_has_no_calls(&b.src) && address_has_no_calls(&b.address_type),
+        Compute(b) => {
+            !matches!(b.op, Div | IntDiv | Mod) && b.stack.iter().all(|i| has_no_calls(i))
+        }
+        Select(b) => has_no_calls(&b.cond) && has_no_calls(&b.if_true) && has_no_calls(&b.if_false),
+        _ => false,
+    }
+}
+
+fn location_has_no_calls(location: &LocationRule) -> bool {
+    match location {
+        LocationRule::Indexed { location, .. } => has_no_calls(location),
+        LocationRule::Mapped { indexes, .. } => indexes.iter().all(|access| match access {
+            AccessType::Indexed(instr) => instr.indexes.iter().all(|i| has_no_calls(i)),
+            AccessType::Qualified(_) => true,
+        }),
+    }
+}
+
+fn address_has_no_calls(xtype: &AddressType) -> bool {
+    match xtype {
+        AddressType::SubcmpSignal { cmp_address, .. } => has_no_calls(cmp_address),
+        _ => true,
+    }
+}
+
+fn is_dead_store(instruction: &Instruction, loaded: &HashSet<usize>) -> bool {
+    match instruction {
+        Instruction::Store(b) if matches!(b.dest_address_type, AddressType::Variable) => {
+            if let Some((start, size)) = constant_location(&b.dest).zip(single_size(&b.context)) {
+                return (start..start + size).all(|slot| !loaded.contains(&slot))
+                    && can_be_discarded(&b.src);
+            }
+            false
+        }
+        Instruction::ArrayCopy(b) if matches!(b.dest_address_type, AddressType::Variable) => {
+            match constant_index(&b.dest) {
+                Some(start) => {
+                    (start..start + b.size).all(|slot| !loaded.contains(&slot)) && has_no_calls(&b.src)
+                }
+                None => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+fn remove_dead_stores(instructions: &mut InstructionList, loaded: &HashSet<usize>) {
+    instructions.retain(|i| !is_dead_store(i, loaded));
+    for i in instructions {
+        match &mut **i {
+            Instruction::Branch(b) => {
+                remove_dead_stores(&mut b.if_branch, loaded);
+                remove_dead_stores(&mut b.else_branch, loaded);
+            }
+            Instruction::Switch(b) => {
+                for case in &mut b.cases {
+                    remove_dead_stores(&mut case.body, loaded);
+                }
+                remove_dead_stores(&mut b.default_branch, loaded);
+            }
+            Instruction::Loop(b) => remove_dead_stores(&mut b.body, loaded),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::propagate_list;
+    use crate::intermediate_representation::ir_interface::*;
+    use crate::ir_processing::test_code::*;
+
+    fn propagated(mut code: InstructionList) -> Vec<String> {
+        propagate_list(&mut code);
+        text(&code)
+    }
+
+    fn add(a: InstructionPointer, b: InstructionPointer) -> InstructionPointer {
+        compute(OperatorType::Add, vec![a, b])
+    }
+
+    #[test]
+    fn the_loads_of_a_copy_read_the_original() {
+        let code = vec![set(var(0), load_signal(value(2))), set_signal(value(1), load_var(var(0)))];
+        // the copy is not read anymore and its store is removed
+        assert_eq!(propagated(code), text(&vec![set_signal(value(1), load_signal(value(2)))]));
+    }
+
+    #[test]
+    fn a_copy_is_forgotten_when_the_original_is_written() {
+        let code = vec![
+            set(var(1), load_var(var(0))),
+            set(var(0), value(5)),
+            set_signal(value(1), add(load_var(var(1)), load_var(var(0)))),
+        ];
+        let expected = text(&code);
+        assert_eq!(propagated(code), expected);
+    }
+
+    #[test]
+    fn a_copy_is_forgotten_when_it_is_written() {
+        let code = vec![
+            set(var(1), load_var(var(0))),
+            set(var(1), value(5)),
+            set_signal(value(1), load_var(var(1))),
+        ];
+        // the slot is read later, so its stores are kept
+        let expected = text(&code);
+        assert_eq!(propagated(code), expected);
+    }
+
+    #[test]
+    fn loops_that_rewrite_a_copy_keep_reading_it() {
+        let code = vec![
+            set(var(1), load_var(var(0))),
+            repeat(
+                load_var(var(2)),
+                vec![set_signal(value(1), load_var(var(1))), set(var(1), add(load_var(var(1)), value(1)))],
+            ),
+        ];
+        let expected = text(&code);
+        assert_eq!(propagated(code), expected);
+    }
+
+    #[test]
+    fn loops_that_rewrite_the_original_keep_the_copy() {
+        let code = vec![
+            set(var(1), load_var(var(0))),
+            repeat(load_var(var(2)), vec![set(var(0), add(load_var(var(0)), value(1)))]),
+            set_signal(value(1), load_var(var(1))),
+        ];
+        let expected = text(&code);
+        assert_eq!(propagated(code), expected);
+    }
+
+    #[test]
+    fn copies_made_inside_a_branch_do_not_leave_it() {
+        let code = vec![
+            branch(load_signal(value(0)), vec![set(var(1), load_var(var(0)))], vec![set(var(1), value(3))]),
+            set_signal(value(1), load_var(var(1))),
+        ];
+        let expected = text(&code);
+        assert_eq!(propagated(code), expected);
+    }
+
+    #[test]
+    fn stores_that_can_fail_are_not_removed() {
+        let division = compute(OperatorType::Div, vec![value(1), load_signal(value(0))]);
+        let code = vec![set(var(0), division)];
+        let expected = text(&code);
+        assert_eq!(propagated(code), expected);
+    }
+
+    #[test]
+    fn stores_are_kept_when_some_read_has_an_unknown_address() {
+        let code = vec![
+            set(var(0), value(1)),
+            set_signal(value(1), load_var(add(load_var(var(1)), value(0)))),
+        ];
+        let expected = text(&code);
+        assert_eq!(propagated(code), expected);
+    }
+}
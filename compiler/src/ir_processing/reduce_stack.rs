@@ -14,14 +14,17 @@ pub fn reduce_instruction(instr: Instruction) -> Instruction {
         Value(b) => IntoInstruction::into_instruction(b),
         Load(b) => reduce_load(b),
         Store(b) => reduce_store(b),
+        ArrayCopy(b) => reduce_array_copy(b),
         Call(b) => reduce_call(b),
         Branch(b) => reduce_branch(b),
+        Switch(b) => reduce_switch(b),
         Return(b) => reduce_return(b),
         Assert(b) => reduce_assert(b),
         Log(b) => reduce_log(b),
         Loop(b) => reduce_loop(b),
         CreateCmp(b) => reduce_crt_cmp(b),
         Compute(b) => reduce_compute(b),
+        Select(b) => reduce_select(b),
     }
 }
 
@@ -102,6 +105,7 @@ pub fn reduce_log(mut bucket: LogBucket) -> Instruction {
 
 pub fn reduce_assert(mut bucket: AssertBucket) -> Instruction {
     bucket.evaluate = Allocate::allocate(reduce_instruction(*bucket.evaluate));
+    bucket.values = bucket.values.into_iter().map(|v| Allocate::allocate(reduce_instruction(*v))).collect();
     IntoInstruction::into_instruction(bucket)
 }
 
@@ -130,6 +134,29 @@ pub fn reduce_store(mut bucket: StoreBucket) -> Instruction {
     IntoInstruction::into_instruction(bucket)
 }
 
+pub fn reduce_array_copy(mut bucket: ArrayCopyBucket) -> Instruction {
+    bucket.dest = Allocate::allocate(reduce_instruction(*bucket.dest));
+    bucket.src = Allocate::allocate(reduce_instruction(*bucket.src));
+    IntoInstruction::into_instruction(bucket)
+}
+
+pub fn reduce_select(mut bucket: SelectBucket) -> Instruction {
+    bucket.cond = Allocate::allocate(reduce_instruction(*bucket.cond));
+    bucket.if_true = Allocate::allocate(reduce_instruction(*bucket.if_true));
+    bucket.if_false = Allocate::allocate(reduce_instruction(*bucket.if_false));
+    IntoInstruction::into_instruction(bucket)
+}
+
+pub fn reduce_switch(mut bucket: SwitchBucket) -> Instruction {
+    bucket.in_range = Allocate::allocate(reduce_instruction(*bucket.in_range));
+    bucket.selector = Allocate::allocate(reduce_instruction(*bucket.selector));
+    for case in &mut bucket.cases {
+        case.body = reduce_list(std::mem::take(&mut case.body));
+    }
+    bucket.default_branch = reduce_list(bucket.default_branch);
+    IntoInstruction::into_instruction(bucket)
+}
+
 pub fn reduce_call(mut bucket: CallBucket) -> Instruction {
     bucket.arguments = reduce_list(bucket.arguments);
     if let ReturnType::Final(mut data) = bucket.return_info {
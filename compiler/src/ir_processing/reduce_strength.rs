@@ -0,0 +1,281 @@
+use super::field_info::FieldInfo;
+use crate::intermediate_representation::ir_interface::*;
+use num_bigint_dig::{BigInt, ModInverse};
+use num_traits::{One, Zero};
+
+const MAX_DOUBLINGS: usize = 2;
+
+pub fn reduce_list(instructions: &mut InstructionList, info: &mut FieldInfo) {
+    for i in instructions {
+        reduce_instruction(i, info);
+    }
+}
+
+pub fn reduce_instruction(instruction: &mut Instruction, info: &mut FieldInfo) {
+    use Instruction::*;
+    match instruction {
+        Value(_) => {}
+        Load(b) => {
+            reduce_location(&mut b.src, info);
+            reduce_address_type(&mut b.address_type, info);
+        }
+        Store(b) => {
+            reduce_instruction(&mut b.src, info);
+            reduce_location(&mut b.dest, info);
+            reduce_address_type(&mut b.dest_address_type, info);
+        }
+        Select(b) => {
+            reduce_instruction(&mut b.cond, info);
+            reduce_instruction(&mut b.if_true, info);
+            reduce_instruction(&mut b.if_false, info);
+        }
+        ArrayCopy(b) => {
+            reduce_instruction(&mut b.dest, info);
+            reduce_instruction(&mut b.src, info);
+        }
+        Compute(b) => {
+            reduce_list(&mut b.stack, info);
+            if let Some(reduced) = reduce_compute(b, info) {
+                *instruction = reduced;
+            }
+        }
+        Call(b) => {
+            reduce_list(&mut b.arguments, info);
+            if let ReturnType::Final(data) = &mut b.return_info {
+                reduce_location(&mut data.dest, info);
+                reduce_address_type(&mut data.dest_address_type, info);
+            }
+        }
+        Branch(b) => {
+            reduce_instruction(&mut b.cond, info);
+            reduce_list(&mut b.if_branch, info);
+            reduce_list(&mut b.else_branch, info);
+        }
+        Switch(b) => {
+            reduce_instruction(&mut b.in_range, info);
+            reduce_instruction(&mut b.selector, info);
+            for case in &mut b.cases {
+                reduce_list(&mut case.body, info);
+            }
+            reduce_list(&mut b.default_branch, info);
+        }
+        Loop(b) => {
+            reduce_instruction(&mut b.continue_condition, info);
+            reduce_list(&mut b.body, info);
+        }
+        Return(b) => reduce_instruction(&mut b.value, info),
+        Assert(b) => {
+            reduce_instruction(&mut b.evaluate, info);
+            for v in &mut b.values {
+                reduce_instruction(v, info);
+            }
+        }
+        CreateCmp(b) => reduce_instruction(&mut b.sub_cmp_id, info),
+        Log(b) => {
+            for print in &mut b.argsprint {
+                if let LogBucketArg::LogExp(exp) = print {
+                    reduce_instruction(exp, info);
+                }
+            }
+        }
+    }
+}
+
+fn reduce_location(location: &mut LocationRule, info: &mut FieldInfo) {
+    match location {
+        LocationRule::Indexed { location, .. } => reduce_instruction(location, info),
+        LocationRule::Mapped { indexes, .. } => {
+            for access in indexes {
+                if let AccessType::Indexed(instr) = access {
+                    reduce_list(&mut instr.indexes, info);
+                }
+            }
+        }
+    }
+}
+
+fn reduce_address_type(xtype: &mut AddressType, info: &mut FieldInfo) {
+    if let AddressType::SubcmpSignal { cmp_address, .. } = xtype {
+        reduce_instruction(cmp_address, info);
+    }
+}
+
+// Operands that can be evaluated twice without repeating a computation
+fn is_cheap_operand(instruction: &Instruction) -> bool {
+    matches!(instruction, Instruction::Value(_) | Instruction::Load(_))
+}
+
+fn compute(bucket: &ComputeBucket, op: OperatorType, stack: Vec<InstructionPointer>) -> Instruction {
+    ComputeBucket {
+        line: bucket.line,
+        message_id: bucket.message_id,
+        op_aux_no: 0,
+        op,
+        stack,
+    }
+    .into_instruction()
+}
+
+fn reduce_compute(bucket: &ComputeBucket, info: &mut FieldInfo) -> Option<Instruction> {
+    match bucket.op {
+        OperatorType::Mul => {
+            let (constant, operand) = if let Some(c) = info.constant(&bucket.stack[1]) {
+                (c, &bucket.stack[0])
+            } else {
+                (info.constant(&bucket.stack[0])?, &bucket.stack[1])
+            };
+            reduce_mul(bucket, constant, operand, info)
+        }
+        OperatorType::Div => {
+            let divisor = info.constant(&bucket.stack[1])?;
+            if divisor.is_zero() {
+                // the division by zero must be reported at execution time
+                return None;
+            }
+            if divisor.is_one() {
+                return Some(*bucket.stack[0].clone());
+            }
+            let inverse = divisor.mod_inverse(info.prime)?;
+            let inverse_value = info.constant_bucket(&inverse, bucket.line, bucket.message_id);
+            Some(compute(bucket, OperatorType::Mul, vec![bucket.stack[0].clone(), inverse_value]))
+        }
+        _ => None,
+    }
+}
+
+fn reduce_mul(
+    bucket: &ComputeBucket,
+    constant: BigInt,
+    operand: &InstructionPointer,
+    info: &FieldInfo,
+) -> Option<Instruction> {
+    let minus_one = info.prime - BigInt::one();
+    if constant.is_one() {
+        Some(*operand.clone())
+    } else if constant == minus_one {
+        Some(compute(bucket, OperatorType::PrefixSub, vec![operand.clone()]))
+    } else if is_cheap_operand(operand) {
+        // x * 2 and x * 4 become x + x and (x + x) + (x + x)
+        let doublings = (1..=MAX_DOUBLINGS).find(|k| constant == BigInt::one() << *k)?;
+        let mut result = operand.clone();
+        for _ in 0..doublings {
+            result = compute(bucket, OperatorType::Add, vec![result.clone(), result]).allocate();
+        }
+        Some(*result)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::reduce_instruction;
+    use crate::intermediate_representation::ir_interface::*;
+    use crate::ir_processing::test_code::*;
+    use num_bigint_dig::BigInt;
+
+    fn reduced(field: &mut Field, mut instruction: InstructionPointer) -> String {
+        reduce_instruction(&mut instruction, &mut field.info());
+        instruction.to_string()
+    }
+
+    fn x() -> InstructionPointer {
+        load_signal(value(0))
+    }
+
+    fn mul(a: InstructionPointer, b: InstructionPointer) -> InstructionPointer {
+        compute(OperatorType::Mul, vec![a, b])
+    }
+
+    fn div(a: InstructionPointer, b: InstructionPointer) -> InstructionPointer {
+        compute(OperatorType::Div, vec![a, b])
+    }
+
+    fn add(a: InstructionPointer, b: InstructionPointer) -> InstructionPointer {
+        compute(OperatorType::Add, vec![a, b])
+    }
+
+    // The constant that multiplies x after reducing x / divisor
+    fn inverse_of(field: &mut Field, divisor: i64) -> BigInt {
+        let mut instruction = div(x(), field.constant(divisor));
+        reduce_instruction(&mut instruction, &mut field.info());
+        match &*instruction {
+            Instruction::Compute(b) if b.op == OperatorType::Mul => {
+                assert_eq!(b.stack[0].to_string(), x().to_string());
+                field.info().constant(&b.stack[1]).unwrap()
+            }
+            other => panic!("not a multiplication: {}", other.to_string()),
+        }
+    }
+
+    #[test]
+    fn multiplications_by_one_are_removed() {
+        let mut field = Field::new();
+        let one = field.constant(1);
+        assert_eq!(reduced(&mut field, mul(x(), one.clone())), x().to_string());
+        assert_eq!(reduced(&mut field, mul(one, x())), x().to_string());
+    }
+
+    #[test]
+    fn multiplications_by_minus_one_are_negations() {
+        let mut field = Field::new();
+        let minus_one = field.constant(-1);
+        let negation = compute(OperatorType::PrefixSub, vec![x()]);
+        assert_eq!(reduced(&mut field, mul(x(), minus_one)), negation.to_string());
+    }
+
+    #[test]
+    fn multiplications_by_two_and_four_are_additions() {
+        let mut field = Field::new();
+        let two = field.constant(2);
+        let four = field.constant(4);
+        assert_eq!(reduced(&mut field, mul(two, x())), add(x(), x()).to_string());
+        let expected = add(add(x(), x()), add(x(), x()));
+        assert_eq!(reduced(&mut field, mul(x(), four)), expected.to_string());
+    }
+
+    #[test]
+    fn only_max_doublings_are_done() {
+        let mut field = Field::new();
+        let eight = field.constant(8);
+        let instruction = mul(x(), eight);
+        let expected = instruction.to_string();
+        assert_eq!(reduced(&mut field, instruction), expected);
+    }
+
+    #[test]
+    fn operands_with_computations_are_not_doubled() {
+        let mut field = Field::new();
+        let two = field.constant(2);
+        let instruction = mul(add(x(), x()), two);
+        let expected = instruction.to_string();
+        assert_eq!(reduced(&mut field, instruction), expected);
+    }
+
+    #[test]
+    fn divisions_by_a_constant_are_multiplications_by_its_inverse() {
+        let mut field = Field::new();
+        let prime = field.prime.clone();
+        assert_eq!(inverse_of(&mut field, 2) * 2 % &prime, BigInt::from(1));
+        assert_eq!(inverse_of(&mut field, -2) * (&prime - 2) % &prime, BigInt::from(1));
+        let one = field.constant(1);
+        assert_eq!(reduced(&mut field, div(x(), one)), x().to_string());
+    }
+
+    #[test]
+    fn divisions_by_zero_are_kept() {
+        let mut field = Field::new();
+        let zero = field.constant(0);
+        let instruction = div(x(), zero);
+        let expected = instruction.to_string();
+        assert_eq!(reduced(&mut field, instruction), expected);
+    }
+
+    #[test]
+    fn nested_operations_are_reduced() {
+        let mut field = Field::new();
+        let one = field.constant(1);
+        let instruction = set_signal(value(1), mul(x(), one));
+        assert_eq!(reduced(&mut field, instruction), set_signal(value(1), x()).to_string());
+    }
+}
@@ -0,0 +1,546 @@
+use crate::intermediate_representation::ir_interface::*;
+use std::collections::HashMap;
+
+// Interval of positions (in program order) in which the slots of a
+// declared variable are accessed
+#[derive(Clone, Copy)]
+struct Lifetime {
+    start: usize,
+    end: usize,
+}
+
+pub struct LivenessInfo {
+    // first slot that can be reused: parameters and constants are never moved
+    fixed_slots: usize,
+    // for each variable its first slot and its size
+    declared: HashMap<usize, usize>,
+    lifetimes: HashMap<usize, Lifetime>,
+    loops: Vec<Lifetime>,
+    position: usize,
+    // false if an access to a non declared slot is found, in that case
+    // the code is left as it is
+    is_valid: bool,
+}
+
+impl LivenessInfo {
+    pub fn new(fixed_slots: usize, declared: HashMap<usize, usize>) -> LivenessInfo {
+        LivenessInfo {
+            fixed_slots,
+            declared,
+            lifetimes: HashMap::new(),
+            loops: Vec::new(),
+            position: 0,
+            is_valid: true,
+        }
+    }
+
+    fn next_position(&mut self) -> usize {
+        self.position += 1;
+        self.position
+    }
+
+    fn register_access(&mut self, base: usize) {
+        if base < self.fixed_slots {
+            return;
+        }
+        if !self.declared.contains_key(&base) {
+            self.is_valid = false;
+            return;
+        }
+        let position = self.position;
+        self.lifetimes
+            .entry(base)
+            .and_modify(|l| l.end = position)
+            .or_insert(Lifetime { start: position, end: position });
+    }
+}
+
+// Returns the new number of variable slots needed by the code and the first
+// slot and size of its variables after moving them
+pub fn reuse_slots(
+    code: &mut InstructionList,
+    mut info: LivenessInfo,
+    stack_depth: usize,
+) -> (usize, HashMap<usize, usize>) {
+    collect_list(code, &mut info);
+    if !info.is_valid {
+        return (stack_depth, info.declared);
+    }
+    let (renaming, new_depth) = assign_slots(&info);
+    rename_list(code, &renaming);
+    let mut variables = HashMap::new();
+    for (base, size) in &info.declared {
+        // the variables that are never accessed are not given a slot
+        let slot = if *base < info.fixed_slots { Some(base) } else { renaming.get(base) };
+        if let Some(slot) = slot {
+            let known = variables.entry(*slot).or_insert(0);
+            *known = std::cmp::max(*known, *size);
+        }
+    }
+    (new_depth, variables)
+}
+
+fn extend_through_loops(lifetime: Lifetime, loops: &[Lifetime]) -> Lifetime {
+    // a variable accessed inside a loop stays alive during the whole loop,
+    // its value may be read again in the next iteration
+    let mut extended = lifetime;
+    for l in loops {
+        if l.start <= extended.end && extended.start <= l.end {
+            extended.start = std::cmp::min(extended.start, l.start);
+            extended.end = std::cmp::max(extended.end, l.end);
+        }
+    }
+    extended
+}
+
+fn assign_slots(info: &LivenessInfo) -> (HashMap<usize, usize>, usize) {
+    let mut variables: Vec<(usize, usize, Lifetime)> = info
+        .lifetimes
+        .iter()
+        .map(|(base, l)| (*base, info.declared[base], extend_through_loops(*l, &info.loops)))
+        .collect();
+    variables.sort_by_key(|(base, _, l)| (l.start, *base));
+
+    let mut assigned: Vec<(usize, usize, Lifetime)> = Vec::new();
+    let mut renaming = HashMap::new();
+    let mut depth = info.fixed_slots;
+    for (base, size, lifetime) in variables {
+        let mut slot = info.fixed_slots;
+        let mut conflict = true;
+        while conflict {
+            conflict = false;
+            for (other_slot, other_size, other) in &assigned {
+                let alive_together = other.start <= lifetime.end && lifetime.start <= other.end;
+                let share_slots = *other_slot < slot + size && slot < other_slot + other_size;
+                if alive_together && share_slots {
+                    slot = other_slot + other_size;
+                    conflict = true;
+                }
+            }
+        }
+        depth = std::cmp::max(depth, slot + size);
+        assigned.push((slot, size, lifetime));
+        renaming.insert(base, slot);
+    }
+    (renaming, depth)
+}
+
+// Before reducing the intermediate operations, the address of a variable
+// is either its first slot or AddAddress(offset, first slot)
+fn variable_base(location: &Instruction) -> Option<usize> {
+    match location {
+        Instruction::Value(v) if v.parse_as == ValueType::U32 => Some(v.value),
+        Instruction::Compute(c) if c.op == OperatorType::AddAddress && c.stack.len() == 2 => {
+            if let Instruction::Value(v) = &*c.stack[1] {
+                if v.parse_as == ValueType::U32 {
+                    return Some(v.value);
+                }
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+fn variable_base_mut(location: &mut Instruction) -> Option<&mut ValueBucket> {
+    match location {
+        Instruction::Value(v) if v.parse_as == ValueType::U32 => Some(v),
+        Instruction::Compute(c) if c.op == OperatorType::AddAddress && c.stack.len() == 2 => {
+            if let Instruction::Value(v) = &mut *c.stack[1] {
+                if v.parse_as == ValueType::U32 {
+                    return Some(v);
+                }
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+fn collect_variable_access(xtype: &AddressType, location: &LocationRule, info: &mut LivenessInfo) {
+    if let LocationRule::Indexed { location, .. } = location {
+        collect_variable_index(xtype, location, info);
+    }
+}
+
+fn collect_variable_index(xtype: &AddressType, location: &Instruction, info: &mut LivenessInfo) {
+    if let AddressType::Variable = xtype {
+        match variable_base(location) {
+            Some(base) => info.register_access(base),
+            None => info.is_valid = false,
+        }
+    }
+}
+
+fn collect_list(instructions: &InstructionList, info: &mut LivenessInfo) {
+    for i in instructions {
+        collect_instruction(i, info);
+    }
+}
+
+fn collect_instruction(instruction: &Instruction, info: &mut LivenessInfo) {
+    use Instruction::*;
+    info.next_position();
+    match instruction {
+        Branch(b) => {
+            collect_instruction(&b.cond, info);
+            collect_list(&b.if_branch, info);
+            collect_list(&b.else_branch, info);
+        }
+        Switch(b) => {
+            collect_instruction(&b.in_range, info);
+            collect_instruction(&b.selector, info);
+            for case in &b.cases {
+                collect_list(&case.body, info);
+            }
+            collect_list(&b.default_branch, info);
+        }
+        Call(b) => {
+            for i in &b.arguments {
+                collect_instruction(i, info);
+            }
+            if let ReturnType::Final(data) = &b.return_info {
+                collect_address_type(&data.dest_address_type, info);
+                collect_location(&data.dest, info);
+                collect_variable_access(&data.dest_address_type, &data.dest, info);
+            }
+        }
+        Compute(b) => {
+            for i in &b.stack {
+                collect_instruction(i, info);
+            }
+        }
+        Load(b) => {
+            collect_address_type(&b.address_type, info);
+            collect_location(&b.src, info);
+            collect_variable_access(&b.address_type, &b.src, info);
+        }
+        Loop(b) => {
+            let start = info.position;
+            collect_instruction(&b.continue_condition, info);
+            collect_list(&b.body, info);
+            let end = info.next_position();
+            info.loops.push(Lifetime { start, end });
+        }
+        Return(b) => collect_instruction(&b.value, info),
+        Store(b) => {
+            collect_instruction(&b.src, info);
+            collect_address_type(&b.dest_address_type, info);
+            collect_location(&b.dest, info);
+            collect_variable_access(&b.dest_address_type, &b.dest, info);
+        }
+        Select(b) => {
+            collect_instruction(&b.cond, info);
+            collect_instruction(&b.if_true, info);
+            collect_instruction(&b.if_false, info);
+        }
+        ArrayCopy(b) => {
+            collect_instruction(&b.src, info);
+            collect_variable_index(&b.src_address_type, &b.src, info);
+            collect_instruction(&b.dest, info);
+            collect_variable_index(&b.dest_address_type, &b.dest, info);
+        }
+        Value(_) => {}
+        Assert(b) => {
+            collect_instruction(&b.evaluate, info);
+            b.values.iter().for_each(|v| collect_instruction(v, info));
+        }
+        CreateCmp(b) => collect_instruction(&b.sub_cmp_id, info),
+        Log(b) => {
+            for print in &b.argsprint {
+                if let LogBucketArg::LogExp(exp) = print {
+                    collect_instruction(exp, info);
+                }
+            }
+        }
+    }
+}
+
+fn collect_location(location: &LocationRule, info: &mut LivenessInfo) {
+    match location {
+        LocationRule::Indexed { location, .. } => collect_instruction(location, info),
+        LocationRule::Mapped { indexes, .. } => {
+            for access in indexes {
+                if let AccessType::Indexed(instr) = access {
+                    collect_list(&instr.indexes, info);
+                }
+            }
+        }
+    }
+}
+
+fn collect_address_type(xtype: &AddressType, info: &mut LivenessInfo) {
+    if let AddressType::SubcmpSignal { cmp_address, .. } = xtype {
+        collect_instruction(cmp_address, info);
+    }
+}
+
+fn rename_variable_access(
+    xtype: &AddressType,
+    location: &mut LocationRule,
+    renaming: &HashMap<usize, usize>,
+) {
+    if let LocationRule::Indexed { location, .. } = location {
+        rename_variable_index(xtype, location, renaming);
+    }
+}
+
+fn rename_variable_index(xtype: &AddressType, location: &mut Instruction, renaming: &HashMap<usize, usize>) {
+    if let AddressType::Variable = xtype {
+        if let Some(base) = variable_base_mut(location) {
+            if let Some(slot) = renaming.get(&base.value) {
+                base.value = *slot;
+            }
+        }
+    }
+}
+
+fn rename_list(instructions: &mut InstructionList, renaming: &HashMap<usize, usize>) {
+    for i in instructions {
+        rename_instruction(i, renaming);
+    }
+}
+
+fn rename_instruction(instruction: &mut Instruction, renaming: &HashMap<usize, usize>) {
+    use Instruction::*;
+    match instruction {
+        Branch(b) => {
+            rename_instruction(&mut b.cond, renaming);
+            rename_list(&mut b.if_branch, renaming);
+            rename_list(&mut b.else_branch, renaming);
+        }
+        Switch(b) => {
+            rename_instruction(&mut b.in_range, renaming);
+            rename_instruction(&mut b.selector, renaming);
+            for case in &mut b.cases {
+                rename_list(&mut case.body, renaming);
+            }
+            rename_list(&mut b.default_branch, renaming);
+        }
+        Call(b) => {
+            for i in &mut b.arguments {
+                rename_instruction(i, renaming);
+            }
+            if let ReturnType::Final(data) = &mut b.return_info {
+                rename_address_type(&mut data.dest_address_type, renaming);
+                rename_location(&mut data.dest, renaming);
+                rename_variable_access(&data.dest_address_type, &mut data.dest, renaming);
+            }
+        }
+        Compute(b) => {
+            for i in &mut b.stack {
+                rename_instruction(i, renaming);
+            }
+        }
+        Load(b) => {
+            rename_address_type(&mut b.address_type, renaming);
+            rename_location(&mut b.src, renaming);
+            rename_variable_access(&b.address_type, &mut b.src, renaming);
+        }
+        Loop(b) => {
+            rename_instruction(&mut b.continue_condition, renaming);
+            rename_list(&mut b.body, renaming);
+        }
+        Return(b) => rename_instruction(&mut b.value, renaming),
+        Store(b) => {
+            rename_instruction(&mut b.src, renaming);
+            rename_address_type(&mut b.dest_address_type, renaming);
+            rename_location(&mut b.dest, renaming);
+            rename_variable_access(&b.dest_address_type, &mut b.dest, renaming);
+        }
+        Select(b) => {
+            rename_instruction(&mut b.cond, renaming);
+            rename_instruction(&mut b.if_true, renaming);
+            rename_instruction(&mut b.if_false, renaming);
+        }
+        ArrayCopy(b) => {
+            rename_instruction(&mut b.src, renaming);
+            rename_variable_index(&b.src_address_type, &mut b.src, renaming);
+            rename_instruction(&mut b.dest, renaming);
+            rename_variable_index(&b.dest_address_type, &mut b.dest, renaming);
+        }
+        Value(_) => {}
+        Assert(b) => {
+            rename_instruction(&mut b.evaluate, renaming);
+            b.values.iter_mut().for_each(|v| rename_instruction(v, renaming));
+        }
+        CreateCmp(b) => rename_instruction(&mut b.sub_cmp_id, renaming),
+        Log(b) => {
+            for print in &mut b.argsprint {
+                if let LogBucketArg::LogExp(exp) = print {
+                    rename_instruction(exp, renaming);
+                }
+            }
+        }
+    }
+}
+
+fn rename_location(location: &mut LocationRule, renaming: &HashMap<usize, usize>) {
+    match location {
+        LocationRule::Indexed { location, .. } => rename_instruction(location, renaming),
+        LocationRule::Mapped { indexes, .. } => {
+            for access in indexes {
+                if let AccessType::Indexed(instr) = access {
+                    rename_list(&mut instr.indexes, renaming);
+                }
+            }
+        }
+    }
+}
+
+fn rename_address_type(xtype: &mut AddressType, renaming: &HashMap<usize, usize>) {
+    if let AddressType::SubcmpSignal { cmp_address, .. } = xtype {
+        rename_instruction(cmp_address, renaming);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{reuse_slots, LivenessInfo};
+    use crate::intermediate_representation::ir_interface::*;
+    use crate::ir_processing::test_code::*;
+    use std::collections::HashMap;
+
+    // signal 1 = variable
+    fn read(at: InstructionPointer) -> InstructionPointer {
+        set_signal(value(1), load_var(at))
+    }
+
+    // The first slot of the variable
+    fn slot(location: &LocationRule) -> usize {
+        let base = match location {
+            LocationRule::Indexed { location, .. } => match &**location {
+                Instruction::Compute(c) => &*c.stack[1],
+                other => other,
+            },
+            _ => unreachable!(),
+        };
+        match base {
+            Instruction::Value(v) => v.value,
+            _ => unreachable!(),
+        }
+    }
+
+    // The first slot of the variable written by set or read by read
+    fn slot_of(instruction: &Instruction) -> usize {
+        match instruction {
+            Instruction::Store(b) => match (&b.dest_address_type, &*b.src) {
+                (AddressType::Variable, _) => slot(&b.dest),
+                (_, Instruction::Load(load)) => slot(&load.src),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    fn slots(code: &InstructionList) -> Vec<usize> {
+        code.iter().map(|i| slot_of(i)).collect()
+    }
+
+    fn body(instruction: &Instruction) -> &InstructionList {
+        match instruction {
+            Instruction::Loop(b) => &b.body,
+            _ => unreachable!(),
+        }
+    }
+
+    fn reuse(code: &mut InstructionList, fixed_slots: usize, variables: &[(usize, usize)]) -> (usize, HashMap<usize, usize>) {
+        let declared: HashMap<usize, usize> = variables.iter().cloned().collect();
+        let depth = variables.iter().map(|(base, size)| base + size).max().unwrap_or(fixed_slots);
+        reuse_slots(code, LivenessInfo::new(fixed_slots, declared), depth)
+    }
+
+    #[test]
+    fn variables_alive_one_after_the_other_share_a_slot() {
+        let mut code = vec![set(var(0), value(1)), read(var(0)), set(var(1), value(2)), read(var(1))];
+        let (depth, variables) = reuse(&mut code, 0, &[(0, 1), (1, 1)]);
+        assert_eq!(depth, 1);
+        assert_eq!(slots(&code), [0, 0, 0, 0]);
+        assert_eq!(variables, HashMap::from([(0, 1)]));
+    }
+
+    #[test]
+    fn variables_alive_together_do_not_share_a_slot() {
+        let mut code = vec![set(var(0), value(1)), set(var(1), value(2)), read(var(0)), read(var(1))];
+        let (depth, _) = reuse(&mut code, 0, &[(0, 1), (1, 1)]);
+        assert_eq!(depth, 2);
+        assert_ne!(slot_of(&code[0]), slot_of(&code[1]));
+        assert_eq!(slot_of(&code[0]), slot_of(&code[2]));
+        assert_eq!(slot_of(&code[1]), slot_of(&code[3]));
+    }
+
+    #[test]
+    fn a_slot_is_not_reused_while_a_later_read_is_pending() {
+        // the whole life of 1 and 2 is between the write and the read of 0
+        let mut code = vec![
+            set(var(0), value(1)),
+            set(var(1), value(2)),
+            read(var(1)),
+            set(var(2), value(3)),
+            read(var(2)),
+            read(var(0)),
+        ];
+        let (depth, _) = reuse(&mut code, 0, &[(0, 1), (1, 1), (2, 1)]);
+        assert_eq!(depth, 2);
+        let slots = slots(&code);
+        assert_eq!(slots[0], slots[5]);
+        assert!(slots[1..5].iter().all(|slot| *slot != slots[0]));
+    }
+
+    #[test]
+    fn arrays_alive_together_do_not_overlap() {
+        // an array of 3 slots, whose last element is read after setting a
+        // variable
+        let mut code = vec![set(element(1, 2), value(1)), set(var(5), value(2)), read(element(1, 2)), read(var(5))];
+        let (depth, variables) = reuse(&mut code, 0, &[(1, 3), (5, 1)]);
+        assert_eq!(depth, 4);
+        assert_eq!(variables, HashMap::from([(0, 3), (3, 1)]));
+        assert_eq!(slots(&code), [0, 3, 0, 3]);
+    }
+
+    #[test]
+    fn variables_read_in_a_loop_stay_alive_during_the_loop() {
+        // the value of 0 is read again in the next iteration, after 1 is set
+        let mut code = vec![set(var(0), value(1)), repeat(value(1), vec![read(var(0)), set(var(1), value(2)), read(var(1))])];
+        let (depth, _) = reuse(&mut code, 0, &[(0, 1), (1, 1)]);
+        assert_eq!(depth, 2);
+        let body = body(&code[1]);
+        assert_ne!(slot_of(&body[0]), slot_of(&body[1]));
+    }
+
+    #[test]
+    fn variables_alive_together_inside_a_loop_do_not_share_a_slot() {
+        // 0 and 1 are set and read in each iteration, with their lives
+        // overlapping, and 2 is only used after the loop
+        let mut code = vec![
+            repeat(value(1), vec![set(var(0), value(1)), set(var(1), value(2)), read(var(0)), read(var(1))]),
+            set(var(2), value(3)),
+            read(var(2)),
+        ];
+        let (depth, _) = reuse(&mut code, 0, &[(0, 1), (1, 1), (2, 1)]);
+        assert_eq!(depth, 2);
+        let body = slots(body(&code[0]));
+        assert_ne!(body[0], body[1]);
+        assert_eq!(body[0], body[2]);
+        assert_eq!(body[1], body[3]);
+        assert_eq!(slot_of(&code[1]), 0);
+    }
+
+    #[test]
+    fn fixed_slots_are_not_moved() {
+        let mut code = vec![read(var(0)), set(var(2), value(1)), read(var(2)), set(var(3), value(2)), read(var(3))];
+        let (depth, variables) = reuse(&mut code, 2, &[(2, 1), (3, 1)]);
+        assert_eq!(depth, 3);
+        assert_eq!(slots(&code), [0, 2, 2, 2, 2]);
+        assert_eq!(variables, HashMap::from([(2, 1)]));
+    }
+
+    #[test]
+    fn code_with_accesses_to_undeclared_slots_is_not_changed() {
+        let mut code = vec![set(var(0), value(1)), set(var(1), value(2)), read(var(1)), set(var(4), value(3))];
+        let (depth, variables) = reuse(&mut code, 0, &[(0, 1), (1, 1)]);
+        assert_eq!(depth, 2);
+        assert_eq!(slots(&code), [0, 1, 1, 4]);
+        assert_eq!(variables, HashMap::from([(0, 1), (1, 1)]));
+    }
+}
@@ -0,0 +1,191 @@
+use crate::intermediate_representation::ir_interface::*;
+
+// Replaces the branches whose arms store a single value in the same place by
+// a store of the selected value
+pub fn select_list(instructions: &mut InstructionList) {
+    for i in instructions {
+        select_instruction(i);
+    }
+}
+
+fn select_instruction(instruction: &mut Instruction) {
+    use Instruction::*;
+    match instruction {
+        Branch(b) => {
+            select_list(&mut b.if_branch);
+            select_list(&mut b.else_branch);
+            if let Some(store) = selected_store(b) {
+                *instruction = store.into_instruction();
+            }
+        }
+        Switch(b) => {
+            for case in &mut b.cases {
+                select_list(&mut case.body);
+            }
+            select_list(&mut b.default_branch);
+        }
+        Loop(b) => select_list(&mut b.body),
+        _ => {}
+    }
+}
+
+fn single_store(arm: &InstructionList) -> Option<&StoreBucket> {
+    match arm.as_slice() {
+        [instruction] => match &**instruction {
+            Instruction::Store(store) => Some(store),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_simple_store(store: &StoreBucket) -> bool {
+    store.context.size == SizeOption::Single(1)
+        && store.src_context.size == SizeOption::Single(1)
+        && store.src_address_type.is_none()
+        && !matches!(store.dest_address_type, AddressType::SubcmpSignal { .. })
+        && matches!(&store.dest, LocationRule::Indexed { location, .. } if is_constant_address(location))
+}
+
+fn is_constant_address(location: &Instruction) -> bool {
+    matches!(location, Instruction::Value(v) if v.parse_as == ValueType::U32)
+}
+
+fn same_dest(a: &StoreBucket, b: &StoreBucket) -> bool {
+    let dest = |s: &StoreBucket| serde_json::to_string(&(&s.dest_address_type, &s.dest)).unwrap();
+    a.dest_is_output == b.dest_is_output && dest(a) == dest(b)
+}
+
+// Both values are evaluated before one of them is selected, so they cannot
+// contain divisions, calls or loads whose address may be out of bounds
+fn can_be_speculated(instruction: &Instruction) -> bool {
+    use Instruction::*;
+    use OperatorType::*;
+    match instruction {
+        Value(_) => true,
+        Load(b) => {
+            b.context.size == SizeOption::Single(1)
+                && !matches!(b.address_type, AddressType::SubcmpSignal { .. })
+                && matches!(&b.src, LocationRule::Indexed { location, .. } if is_constant_address(location))
+        }
+        Compute(b) => {
+            !b.op.is_address_op()
+                && !matches!(b.op, Div | IntDiv | Mod)
+                && b.stack.iter().all(|i| can_be_speculated(i))
+        }
+        _ => false,
+    }
+}
+
+// The condition is evaluated in the same way as in the branch, but as an
+// operand of the select
+fn is_operand(instruction: &Instruction) -> bool {
+    matches!(instruction, Instruction::Value(_) | Instruction::Load(_) | Instruction::Compute(_))
+}
+
+fn selected_store(bucket: &BranchBucket) -> Option<StoreBucket> {
+    if !is_operand(&bucket.cond) {
+        return None;
+    }
+    let if_store = single_store(&bucket.if_branch)?;
+    if !is_simple_store(if_store) || !can_be_speculated(&if_store.src) {
+        return None;
+    }
+    let if_false = match single_store(&bucket.else_branch) {
+        Some(else_store) => {
+            if !is_simple_store(else_store) || !same_dest(if_store, else_store) || !can_be_speculated(&else_store.src) {
+                return None;
+            }
+            else_store.src.clone()
+        }
+        // a variable keeps its value when there is no else branch
+        None if bucket.else_branch.is_empty() && matches!(if_store.dest_address_type, AddressType::Variable) => {
+            LoadBucket {
+                line: if_store.line,
+                message_id: if_store.message_id,
+                address_type: AddressType::Variable,
+                src: if_store.dest.clone(),
+                context: InstrContext { size: SizeOption::Single(1) },
+            }
+            .allocate()
+        }
+        None => return None,
+    };
+    let select = SelectBucket {
+        line: bucket.line,
+        message_id: bucket.message_id,
+        cond: bucket.cond.clone(),
+        if_true: if_store.src.clone(),
+        if_false,
+    };
+    let mut store = if_store.clone();
+    store.src = select.allocate();
+    Some(store)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::select_list;
+    use crate::intermediate_representation::ir_interface::*;
+    use crate::ir_processing::test_code::*;
+
+    fn select(cond: InstructionPointer, if_true: InstructionPointer, if_false: InstructionPointer) -> InstructionPointer {
+        SelectBucket { line: 0, message_id: 0, cond, if_true, if_false }.allocate()
+    }
+
+    fn selected(mut code: InstructionList) -> Vec<String> {
+        select_list(&mut code);
+        text(&code)
+    }
+
+    fn cond() -> InstructionPointer {
+        load_signal(value(0))
+    }
+
+    #[test]
+    fn arms_that_store_in_the_same_place_are_a_select() {
+        let code = vec![branch(cond(), vec![set(var(0), value(1))], vec![set(var(0), load_signal(value(1)))])];
+        let expected = vec![set(var(0), select(cond(), value(1), load_signal(value(1))))];
+        assert_eq!(selected(code), text(&expected));
+    }
+
+    #[test]
+    fn variables_keep_their_value_without_an_else_branch() {
+        let code = vec![branch(cond(), vec![set(var(0), value(1))], Vec::new())];
+        let expected = vec![set(var(0), select(cond(), value(1), load_var(var(0))))];
+        assert_eq!(selected(code), text(&expected));
+        // a signal is only written once
+        let code = vec![branch(cond(), vec![set_signal(value(1), value(1))], Vec::new())];
+        let expected = text(&code);
+        assert_eq!(selected(code), expected);
+    }
+
+    #[test]
+    fn arms_that_store_in_different_places_are_kept() {
+        let code = vec![branch(cond(), vec![set(var(0), value(1))], vec![set(var(1), value(2))])];
+        let expected = text(&code);
+        assert_eq!(selected(code), expected);
+    }
+
+    #[test]
+    fn values_that_may_fail_are_not_speculated() {
+        let division = compute(OperatorType::Div, vec![value(1), load_signal(value(1))]);
+        let code = vec![branch(cond(), vec![set(var(0), division)], vec![set(var(0), value(2))])];
+        let expected = text(&code);
+        assert_eq!(selected(code), expected);
+        let dynamic = load_var(element(1, 0));
+        let code = vec![branch(cond(), vec![set(var(0), value(1))], vec![set(var(0), dynamic)])];
+        let expected = text(&code);
+        assert_eq!(selected(code), expected);
+    }
+
+    #[test]
+    fn inner_branches_are_selected_and_the_outer_ones_kept() {
+        let inner = branch(load_signal(value(1)), vec![set(var(0), value(1))], vec![set(var(0), value(2))]);
+        let code = vec![repeat(value(1), vec![branch(cond(), vec![inner], vec![set(var(0), value(3))])])];
+        // a select is not evaluated before knowing that it is needed
+        let inner_select = set(var(0), select(load_signal(value(1)), value(1), value(2)));
+        let outer = branch(cond(), vec![inner_select], vec![set(var(0), value(3))]);
+        assert_eq!(selected(code), text(&vec![repeat(value(1), vec![outer])]));
+    }
+}
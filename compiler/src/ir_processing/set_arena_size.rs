@@ -17,12 +17,19 @@ pub fn visit_instruction(
     use Instruction::*;
     match instruction {
         Branch(b) => visit_branch(b, function_to_arena_size),
+        Switch(b) => visit_switch(b, function_to_arena_size),
         Call(b) => visit_call(b, function_to_arena_size),
         Compute(b) => visit_compute(b, function_to_arena_size),
+        Select(b) => {
+            visit_instruction(&mut b.cond, function_to_arena_size);
+            visit_instruction(&mut b.if_true, function_to_arena_size);
+            visit_instruction(&mut b.if_false, function_to_arena_size);
+        }
         Load(b) => visit_load(b, function_to_arena_size),
         Loop(b) => visit_loop(b, function_to_arena_size),
         Return(b) => visit_return(b, function_to_arena_size),
         Store(b) => visit_store(b, function_to_arena_size),
+        ArrayCopy(b) => visit_array_copy(b, function_to_arena_size),
         Value(b) => visit_value(b, function_to_arena_size),
         Assert(b) => visit_assert(b, function_to_arena_size),
         CreateCmp(b) => visit_create_cmp(b, function_to_arena_size),
@@ -36,6 +43,15 @@ pub fn visit_branch(bucket: &mut BranchBucket, function_to_arena_size: &HashMap<
     visit_list(&mut bucket.else_branch, function_to_arena_size);
 }
 
+pub fn visit_switch(bucket: &mut SwitchBucket, function_to_arena_size: &HashMap<String, usize>) {
+    visit_instruction(&mut bucket.in_range, function_to_arena_size);
+    visit_instruction(&mut bucket.selector, function_to_arena_size);
+    for case in &mut bucket.cases {
+        visit_list(&mut case.body, function_to_arena_size);
+    }
+    visit_list(&mut bucket.default_branch, function_to_arena_size);
+}
+
 pub fn visit_call(bucket: &mut CallBucket, function_to_arena_size: &HashMap<String, usize>) {
     use ReturnType::*;
     bucket.arena_size = *function_to_arena_size.get(&bucket.symbol).unwrap();
@@ -87,6 +103,9 @@ pub fn visit_log(bucket: &mut LogBucket, function_to_arena_size: &HashMap<String
 
 pub fn visit_assert(bucket: &mut AssertBucket, function_to_arena_size: &HashMap<String, usize>) {
     visit_instruction(&mut bucket.evaluate, function_to_arena_size);
+    for v in &mut bucket.values {
+        visit_instruction(v, function_to_arena_size);
+    }
 }
 
 pub fn visit_store(bucket: &mut StoreBucket, function_to_arena_size: &HashMap<String, usize>) {
@@ -95,6 +114,11 @@ pub fn visit_store(bucket: &mut StoreBucket, function_to_arena_size: &HashMap<St
     visit_address_type(&mut bucket.dest_address_type, function_to_arena_size);
 }
 
+pub fn visit_array_copy(bucket: &mut ArrayCopyBucket, function_to_arena_size: &HashMap<String, usize>) {
+    visit_instruction(&mut bucket.dest, function_to_arena_size);
+    visit_instruction(&mut bucket.src, function_to_arena_size);
+}
+
 pub fn visit_value(_: &mut ValueBucket, _: &HashMap<String, usize>) {}
 
 pub fn visit_location(bucket: &mut LocationRule, function_to_arena_size: &HashMap<String, usize>) {
@@ -0,0 +1,186 @@
+use super::field_info::FieldInfo;
+use crate::intermediate_representation::ir_interface::*;
+use crate::intermediate_representation::translate::FieldTracker;
+use std::collections::HashMap;
+
+// Builds the constant pool of the circuit with the constants still used by
+// the code, giving a single id to the constants that are the same field
+// element, and renames the constants of the code
+pub fn share(mut codes: Vec<&mut InstructionList>, field: &FieldInfo) -> FieldTracker {
+    let mut used = Vec::new();
+    for code in codes.iter_mut() {
+        visit_list(code, &mut |id: &mut usize| used.push(*id));
+    }
+    used.sort_unstable();
+    used.dedup();
+
+    let mut pool = FieldTracker::new();
+    let mut renaming = HashMap::with_capacity(used.len());
+    for id in used {
+        let constant = match field.constant_value(id) {
+            Some(reduced) => reduced.to_str_radix(10),
+            None => field.field_tracker.get_constant(id).unwrap().clone(),
+        };
+        renaming.insert(id, pool.insert(constant));
+    }
+    for code in codes.iter_mut() {
+        visit_list(code, &mut |id: &mut usize| *id = renaming[id]);
+    }
+    pool
+}
+
+fn visit_list<F: FnMut(&mut usize)>(instructions: &mut InstructionList, f: &mut F) {
+    for i in instructions {
+        visit_instruction(i, f);
+    }
+}
+
+fn visit_instruction<F: FnMut(&mut usize)>(instruction: &mut Instruction, f: &mut F) {
+    use Instruction::*;
+    match instruction {
+        Value(b) => {
+            if b.parse_as == ValueType::BigInt {
+                f(&mut b.value);
+            }
+        }
+        Load(b) => {
+            visit_location(&mut b.src, f);
+            visit_address_type(&mut b.address_type, f);
+        }
+        Store(b) => {
+            visit_instruction(&mut b.src, f);
+            visit_location(&mut b.dest, f);
+            visit_address_type(&mut b.dest_address_type, f);
+            if let Some(address) = &mut b.src_address_type {
+                visit_instruction(address, f);
+            }
+        }
+        ArrayCopy(b) => {
+            visit_instruction(&mut b.dest, f);
+            visit_instruction(&mut b.src, f);
+        }
+        Compute(b) => visit_list(&mut b.stack, f),
+        Select(b) => {
+            visit_instruction(&mut b.cond, f);
+            visit_instruction(&mut b.if_true, f);
+            visit_instruction(&mut b.if_false, f);
+        }
+        Call(b) => {
+            visit_list(&mut b.arguments, f);
+            if let ReturnType::Final(data) = &mut b.return_info {
+                visit_location(&mut data.dest, f);
+                visit_address_type(&mut data.dest_address_type, f);
+            }
+        }
+        Branch(b) => {
+            visit_instruction(&mut b.cond, f);
+            visit_list(&mut b.if_branch, f);
+            visit_list(&mut b.else_branch, f);
+        }
+        Switch(b) => {
+            visit_instruction(&mut b.in_range, f);
+            visit_instruction(&mut b.selector, f);
+            for case in &mut b.cases {
+                visit_list(&mut case.body, f);
+            }
+            visit_list(&mut b.default_branch, f);
+        }
+        Loop(b) => {
+            visit_instruction(&mut b.continue_condition, f);
+            visit_list(&mut b.body, f);
+        }
+        Return(b) => visit_instruction(&mut b.value, f),
+        Assert(b) => {
+            visit_instruction(&mut b.evaluate, f);
+            b.values.iter_mut().for_each(|v| visit_instruction(v, f));
+        }
+        CreateCmp(b) => visit_instruction(&mut b.sub_cmp_id, f),
+        Log(b) => {
+            for print in &mut b.argsprint {
+                if let LogBucketArg::LogExp(exp) = print {
+                    visit_instruction(exp, f);
+                }
+            }
+        }
+    }
+}
+
+fn visit_location<F: FnMut(&mut usize)>(location: &mut LocationRule, f: &mut F) {
+    match location {
+        LocationRule::Indexed { location, .. } => visit_instruction(location, f),
+        LocationRule::Mapped { indexes, .. } => {
+            for access in indexes {
+                if let AccessType::Indexed(instr) = access {
+                    visit_list(&mut instr.indexes, f);
+                }
+            }
+        }
+    }
+}
+
+fn visit_address_type<F: FnMut(&mut usize)>(xtype: &mut AddressType, f: &mut F) {
+    if let AddressType::SubcmpSignal { cmp_address, .. } = xtype {
+        visit_instruction(cmp_address, f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::share;
+    use crate::intermediate_representation::ir_interface::*;
+    use crate::ir_processing::test_code::*;
+
+    // The ids of the constants stored in the signals
+    fn ids(code: &InstructionList) -> Vec<usize> {
+        code.iter()
+            .map(|i| match &**i {
+                Instruction::Store(b) => match &*b.src {
+                    Instruction::Value(v) => v.value,
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn equal_field_elements_share_an_id() {
+        let mut field = Field::new();
+        let minus_one = (&field.prime - 1u32).to_str_radix(10);
+        let (a, b) = (field.constant(-1), field.constant(5));
+        let c = {
+            let id = field.tracker.insert(minus_one.clone());
+            ValueBucket { line: 0, message_id: 0, parse_as: ValueType::BigInt, op_aux_no: 0, value: id }.allocate()
+        };
+        let mut first = vec![set_signal(value(0), a), set_signal(value(1), b)];
+        let mut second = vec![set_signal(value(0), c)];
+        let pool = share(vec![&mut first, &mut second], &field.info());
+        assert_eq!(pool.next_id(), 2);
+        assert_eq!(ids(&first)[0], ids(&second)[0]);
+        assert_eq!(pool.get_constant(ids(&first)[0]), Some(&minus_one));
+        assert_eq!(pool.get_constant(ids(&first)[1]).map(|c| c.as_str()), Some("5"));
+    }
+
+    #[test]
+    fn unused_constants_are_dropped() {
+        let mut field = Field::new();
+        for unused in 0..3 {
+            field.constant(unused);
+        }
+        let mut code = vec![set_signal(value(0), field.constant(7))];
+        let pool = share(vec![&mut code], &field.info());
+        assert_eq!(pool.next_id(), 1);
+        assert_eq!(ids(&code), [0]);
+    }
+
+    #[test]
+    fn addresses_are_not_renamed() {
+        let mut field = Field::new();
+        field.constant(9);
+        let mut code = vec![set_signal(value(3), value(4))];
+        let expected = text(&code);
+        let pool = share(vec![&mut code], &field.info());
+        assert_eq!(pool.next_id(), 0);
+        assert_eq!(text(&code), expected);
+    }
+}
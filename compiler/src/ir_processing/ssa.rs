@@ -0,0 +1,556 @@
+use crate::intermediate_representation::ir_interface::*;
+use std::collections::{BTreeSet, HashMap};
+
+// SSA form of the variables of a template or function. The code keeps its
+// buckets and its structured control flow, but every read of a tracked
+// variable slot refers to the value that reaches it, and the values that
+// meet after a branch or at the head of a loop are merged by phis.
+// The values of a slot are kept in the slot itself, so lowering the code
+// back to buckets only drops the phis. Passes working on this form must
+// keep that property: they can replace the reads of a value, but not move
+// a read past a later write of its slot
+
+pub type ValueId = usize;
+
+pub enum Definition {
+    // value of the slot when the code starts
+    Entry,
+    // written by a store or a call
+    Instruction,
+    // after a branch the values at the end of the if and else branches, at
+    // the head of a loop the values entering the loop and at the end of its body
+    Phi(Vec<ValueId>),
+}
+
+pub struct Value {
+    pub slot: usize,
+    pub definition: Definition,
+}
+
+pub enum Item {
+    // An instruction without nested code. `uses` are the values read by its
+    // variable loads, in the order they are visited by `visit_loads`, and
+    // `defs` the values it writes
+    Plain { instruction: InstructionPointer, uses: Vec<ValueId>, defs: Vec<ValueId> },
+    Branch {
+        // the branches of the bucket are kept in the regions
+        bucket: BranchBucket,
+        uses: Vec<ValueId>,
+        if_branch: Region,
+        else_branch: Region,
+        phis: Vec<ValueId>,
+    },
+    // the phis are evaluated before each evaluation of the condition
+    Loop { bucket: LoopBucket, phis: Vec<ValueId>, uses: Vec<ValueId>, body: Region },
+}
+
+pub type Region = Vec<Item>;
+
+pub struct SsaCode {
+    pub values: Vec<Value>,
+    // the slots that are never accessed through a non constant address
+    pub tracked: Vec<bool>,
+    pub body: Region,
+}
+
+// The tracked slots among start..start + size
+pub fn tracked_slots(tracked: &[bool], start: usize, size: usize) -> impl Iterator<Item = usize> + '_ {
+    (start..start + size).filter(move |slot| tracked.get(*slot) == Some(&true))
+}
+
+// `variables` gives the first slot and size of the variables of the code.
+// Returns the code unchanged when some variable is accessed in a way that
+// hides the slots it may touch
+pub fn build(
+    mut code: InstructionList,
+    variables: &HashMap<usize, usize>,
+) -> Result<SsaCode, InstructionList> {
+    let mut scan = Scan { tracked: Vec::new(), untracked: Vec::new(), variables, is_valid: true };
+    scan_list(&mut code, &mut scan);
+    if !scan.is_valid {
+        return Err(code);
+    }
+    let mut tracked = scan.tracked;
+    for (start, size) in scan.untracked {
+        for slot in start..std::cmp::min(start + size, tracked.len()) {
+            tracked[slot] = false;
+        }
+    }
+    let mut builder = Builder { values: Vec::new(), current: Vec::new(), tracked };
+    for slot in 0..builder.tracked.len() {
+        let entry = builder.new_value(slot, Definition::Entry);
+        builder.current.push(entry);
+    }
+    let body = builder.convert_list(code);
+    Ok(SsaCode { values: builder.values, tracked: builder.tracked, body })
+}
+
+impl SsaCode {
+    pub fn lower(self) -> InstructionList {
+        lower_region(self.body)
+    }
+}
+
+fn lower_region(region: Region) -> InstructionList {
+    let mut code = InstructionList::with_capacity(region.len());
+    for item in region {
+        let instruction = match item {
+            Item::Plain { instruction, .. } => instruction,
+            Item::Branch { mut bucket, if_branch, else_branch, .. } => {
+                bucket.if_branch = lower_region(if_branch);
+                bucket.else_branch = lower_region(else_branch);
+                bucket.allocate()
+            }
+            Item::Loop { mut bucket, body, .. } => {
+                bucket.body = lower_region(body);
+                bucket.allocate()
+            }
+        };
+        code.push(instruction);
+    }
+    code
+}
+
+// First slot and number of slots of a variable access with a constant address
+pub fn variable_range(
+    xtype: &AddressType,
+    location: &LocationRule,
+    context: &InstrContext,
+) -> Option<(usize, usize)> {
+    if let (AddressType::Variable, LocationRule::Indexed { location, .. }) = (xtype, location) {
+        if let (Instruction::Value(v), SizeOption::Single(size)) = (&**location, &context.size) {
+            if v.parse_as == ValueType::U32 {
+                return Some((v.value, *size));
+            }
+        }
+    }
+    None
+}
+
+pub fn load_range(instruction: &Instruction) -> Option<(usize, usize)> {
+    match instruction {
+        Instruction::Load(b) => variable_range(&b.address_type, &b.src, &b.context),
+        _ => None,
+    }
+}
+
+fn written_range(instruction: &Instruction) -> Option<(usize, usize)> {
+    match instruction {
+        Instruction::Store(b) => variable_range(&b.dest_address_type, &b.dest, &b.context),
+        Instruction::Call(b) => match &b.return_info {
+            ReturnType::Final(data) => {
+                variable_range(&data.dest_address_type, &data.dest, &data.context)
+            }
+            ReturnType::Intermediate { .. } => None,
+        },
+        _ => None,
+    }
+}
+
+// Calls `f` with every load of an instruction without nested code, after the
+// loads of its address. For a branch or a loop only its condition is visited
+pub fn visit_loads<F: FnMut(&mut Instruction)>(instruction: &mut Instruction, f: &mut F) {
+    use Instruction::*;
+    match instruction {
+        Value(_) => {}
+        Load(b) => {
+            visit_location_loads(&mut b.src, f);
+            visit_address_loads(&mut b.address_type, f);
+            f(instruction);
+        }
+        Store(b) => {
+            visit_loads(&mut b.src, f);
+            visit_location_loads(&mut b.dest, f);
+            visit_address_loads(&mut b.dest_address_type, f);
+            if let Some(address) = &mut b.src_address_type {
+                visit_loads(address, f);
+            }
+        }
+        ArrayCopy(b) => {
+            visit_loads(&mut b.dest, f);
+            visit_loads(&mut b.src, f);
+        }
+        Compute(b) => {
+            for i in &mut b.stack {
+                visit_loads(i, f);
+            }
+        }
+        Select(b) => {
+            visit_loads(&mut b.cond, f);
+            visit_loads(&mut b.if_true, f);
+            visit_loads(&mut b.if_false, f);
+        }
+        Call(b) => {
+            for i in &mut b.arguments {
+                visit_loads(i, f);
+            }
+            if let ReturnType::Final(data) = &mut b.return_info {
+                visit_location_loads(&mut data.dest, f);
+                visit_address_loads(&mut data.dest_address_type, f);
+            }
+        }
+        Branch(b) => visit_loads(&mut b.cond, f),
+        Switch(b) => {
+            visit_loads(&mut b.in_range, f);
+            visit_loads(&mut b.selector, f);
+        }
+        Loop(b) => visit_loads(&mut b.continue_condition, f),
+        Return(b) => visit_loads(&mut b.value, f),
+        Assert(b) => {
+            visit_loads(&mut b.evaluate, f);
+            b.values.iter_mut().for_each(|v| visit_loads(v, f));
+        }
+        CreateCmp(b) => visit_loads(&mut b.sub_cmp_id, f),
+        Log(b) => {
+            for print in &mut b.argsprint {
+                if let LogBucketArg::LogExp(exp) = print {
+                    visit_loads(exp, f);
+                }
+            }
+        }
+    }
+}
+
+fn visit_location_loads<F: FnMut(&mut Instruction)>(location: &mut LocationRule, f: &mut F) {
+    match location {
+        LocationRule::Indexed { location, .. } => visit_loads(location, f),
+        LocationRule::Mapped { indexes, .. } => {
+            for access in indexes {
+                if let AccessType::Indexed(instr) = access {
+                    for i in &mut instr.indexes {
+                        visit_loads(i, f);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn visit_address_loads<F: FnMut(&mut Instruction)>(xtype: &mut AddressType, f: &mut F) {
+    if let AddressType::SubcmpSignal { cmp_address, .. } = xtype {
+        visit_loads(cmp_address, f);
+    }
+}
+
+// The number of uses of the loads of an instruction
+pub fn count_uses(instruction: &mut Instruction, tracked: &[bool]) -> usize {
+    let mut count = 0;
+    visit_loads(instruction, &mut |load| {
+        if let Some((start, size)) = load_range(load) {
+            count += tracked_slots(tracked, start, size).count();
+        }
+    });
+    count
+}
+
+// A variable accessed through a non constant address is
+// AddAddress(offset, first slot), and may touch any of its slots
+struct Scan<'a> {
+    tracked: Vec<bool>,
+    untracked: Vec<(usize, usize)>,
+    variables: &'a HashMap<usize, usize>,
+    is_valid: bool,
+}
+
+impl Scan<'_> {
+    fn register(&mut self, xtype: &AddressType, location: &LocationRule, context: &InstrContext) {
+        if let AddressType::Variable = xtype {
+            if let Some((start, size)) = variable_range(xtype, location, context) {
+                if self.tracked.len() < start + size {
+                    self.tracked.resize(start + size, true);
+                }
+                return;
+            }
+            match dynamic_base(location).and_then(|base| Some((base, *self.variables.get(&base)?))) {
+                Some(range) => self.untracked.push(range),
+                None => self.is_valid = false,
+            }
+        }
+    }
+
+    // The slots moved by an array copy are not given values, they are
+    // read and written as a whole
+    fn register_copied(&mut self, xtype: &AddressType, location: &Instruction, size: usize) {
+        if let AddressType::Variable = xtype {
+            let range = match location {
+                Instruction::Value(v) if v.parse_as == ValueType::U32 => Some((v.value, size)),
+                _ => dynamic_index_base(location).and_then(|base| Some((base, *self.variables.get(&base)?))),
+            };
+            match range {
+                Some(range) => self.untracked.push(range),
+                None => self.is_valid = false,
+            }
+        }
+    }
+}
+
+fn dynamic_base(location: &LocationRule) -> Option<usize> {
+    match location {
+        LocationRule::Indexed { location, .. } => dynamic_index_base(location),
+        LocationRule::Mapped { .. } => None,
+    }
+}
+
+fn dynamic_index_base(location: &Instruction) -> Option<usize> {
+    if let Instruction::Compute(c) = location {
+        if c.op == OperatorType::AddAddress && c.stack.len() == 2 {
+            if let Instruction::Value(v) = &*c.stack[1] {
+                return Some(v.value);
+            }
+        }
+    }
+    None
+}
+
+fn scan_list(code: &mut InstructionList, scan: &mut Scan) {
+    for i in code {
+        scan_instruction(i, scan);
+    }
+}
+
+fn scan_instruction(instruction: &mut Instruction, scan: &mut Scan) {
+    visit_loads(instruction, &mut |load| {
+        if let Instruction::Load(b) = load {
+            scan.register(&b.address_type, &b.src, &b.context);
+        }
+    });
+    match instruction {
+        Instruction::Store(b) => scan.register(&b.dest_address_type, &b.dest, &b.context),
+        Instruction::ArrayCopy(b) => {
+            scan.register_copied(&b.dest_address_type, &b.dest, b.size);
+            scan.register_copied(&b.src_address_type, &b.src, b.size);
+        }
+        Instruction::Call(b) => {
+            if let ReturnType::Final(data) = &b.return_info {
+                scan.register(&data.dest_address_type, &data.dest, &data.context);
+            }
+        }
+        Instruction::Branch(b) => {
+            scan_list(&mut b.if_branch, scan);
+            scan_list(&mut b.else_branch, scan);
+        }
+        // the values that meet after a switch are not merged
+        Instruction::Switch(_) => scan.is_valid = false,
+        Instruction::Loop(b) => scan_list(&mut b.body, scan),
+        _ => {}
+    }
+}
+
+fn defined_slots(code: &InstructionList, tracked: &[bool], slots: &mut BTreeSet<usize>) {
+    for i in code {
+        if let Some((start, size)) = written_range(i) {
+            slots.extend(tracked_slots(tracked, start, size));
+        }
+        match &**i {
+            Instruction::Branch(b) => {
+                defined_slots(&b.if_branch, tracked, slots);
+                defined_slots(&b.else_branch, tracked, slots);
+            }
+            Instruction::Loop(b) => defined_slots(&b.body, tracked, slots),
+            _ => {}
+        }
+    }
+}
+
+struct Builder {
+    values: Vec<Value>,
+    // for each tracked slot the value it holds at this point of the code
+    current: Vec<ValueId>,
+    tracked: Vec<bool>,
+}
+
+impl Builder {
+    fn new_value(&mut self, slot: usize, definition: Definition) -> ValueId {
+        self.values.push(Value { slot, definition });
+        self.values.len() - 1
+    }
+
+    fn uses(&self, instruction: &mut Instruction) -> Vec<ValueId> {
+        let mut uses = Vec::new();
+        visit_loads(instruction, &mut |load| {
+            if let Some((start, size)) = load_range(load) {
+                uses.extend(tracked_slots(&self.tracked, start, size).map(|slot| self.current[slot]));
+            }
+        });
+        uses
+    }
+
+    fn convert_list(&mut self, code: InstructionList) -> Region {
+        let mut region = Region::with_capacity(code.len());
+        for i in code {
+            region.push(self.convert_instruction(*i));
+        }
+        region
+    }
+
+    fn convert_instruction(&mut self, instruction: Instruction) -> Item {
+        match instruction {
+            Instruction::Branch(mut bucket) => {
+                let uses = self.uses(&mut bucket.cond);
+                let if_code = std::mem::take(&mut bucket.if_branch);
+                let else_code = std::mem::take(&mut bucket.else_branch);
+                let start = self.current.clone();
+                let if_branch = self.convert_list(if_code);
+                let if_end = std::mem::replace(&mut self.current, start);
+                let else_branch = self.convert_list(else_code);
+                let mut phis = Vec::new();
+                for (slot, if_value) in if_end.into_iter().enumerate() {
+                    let else_value = self.current[slot];
+                    if if_value != else_value {
+                        let phi = self.new_value(slot, Definition::Phi(vec![if_value, else_value]));
+                        self.current[slot] = phi;
+                        phis.push(phi);
+                    }
+                }
+                Item::Branch { bucket, uses, if_branch, else_branch, phis }
+            }
+            Instruction::Loop(mut bucket) => {
+                let body_code = std::mem::take(&mut bucket.body);
+                let mut slots = BTreeSet::new();
+                defined_slots(&body_code, &self.tracked, &mut slots);
+                let mut phis = Vec::new();
+                for slot in slots {
+                    let entry = self.current[slot];
+                    let phi = self.new_value(slot, Definition::Phi(vec![entry, entry]));
+                    self.current[slot] = phi;
+                    phis.push(phi);
+                }
+                // the condition reads the values at the head of the loop
+                let uses = self.uses(&mut bucket.continue_condition);
+                let body = self.convert_list(body_code);
+                for phi in &phis {
+                    let slot = self.values[*phi].slot;
+                    if let Definition::Phi(incoming) = &mut self.values[*phi].definition {
+                        incoming[1] = self.current[slot];
+                    }
+                    self.current[slot] = *phi;
+                }
+                Item::Loop { bucket, phis, uses, body }
+            }
+            mut instruction => {
+                let uses = self.uses(&mut instruction);
+                let mut defs = Vec::new();
+                if let Some((start, size)) = written_range(&instruction) {
+                    let written: Vec<usize> = tracked_slots(&self.tracked, start, size).collect();
+                    for slot in written {
+                        let def = self.new_value(slot, Definition::Instruction);
+                        self.current[slot] = def;
+                        defs.push(def);
+                    }
+                }
+                Item::Plain { instruction: instruction.allocate(), uses, defs }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build, Definition, Item, SsaCode};
+    use crate::intermediate_representation::ir_interface::*;
+    use crate::ir_processing::test_code::*;
+    use std::collections::HashMap;
+
+    fn ssa(code: &InstructionList, variables: &[(usize, usize)]) -> SsaCode {
+        let variables: HashMap<usize, usize> = variables.iter().cloned().collect();
+        match build(code.clone(), &variables) {
+            Ok(ssa) => ssa,
+            Err(_) => panic!("the code has no SSA form"),
+        }
+    }
+
+    fn uses(item: &Item) -> &Vec<usize> {
+        match item {
+            Item::Plain { uses, .. } | Item::Branch { uses, .. } | Item::Loop { uses, .. } => uses,
+        }
+    }
+
+    fn defs(item: &Item) -> &Vec<usize> {
+        match item {
+            Item::Plain { defs, .. } => defs,
+            _ => unreachable!(),
+        }
+    }
+
+    fn phis(item: &Item) -> &Vec<usize> {
+        match item {
+            Item::Branch { phis, .. } | Item::Loop { phis, .. } => phis,
+            _ => unreachable!(),
+        }
+    }
+
+    fn incoming(ssa: &SsaCode, phi: usize) -> &Vec<usize> {
+        match &ssa.values[phi].definition {
+            Definition::Phi(incoming) => incoming,
+            _ => panic!("{} is not a phi", phi),
+        }
+    }
+
+    #[test]
+    fn each_read_refers_to_the_last_write() {
+        let code = vec![set(var(0), value(1)), set(var(0), value(2)), set_signal(value(0), load_var(var(0)))];
+        let ssa = ssa(&code, &[]);
+        // the entry value of the slot and one value for each store
+        assert_eq!(ssa.values.len(), 3);
+        assert!(matches!(ssa.values[0].definition, Definition::Entry));
+        assert_eq!(uses(&ssa.body[2]), defs(&ssa.body[1]));
+        assert_eq!(text(&ssa.lower()), text(&code));
+    }
+
+    #[test]
+    fn the_values_of_a_branch_meet_in_a_phi() {
+        let code = vec![
+            branch(load_signal(value(1)), vec![set(var(0), value(1))], vec![set(var(1), value(2))]),
+            set_signal(value(0), load_var(var(0))),
+        ];
+        let ssa = ssa(&code, &[]);
+        let phis = phis(&ssa.body[0]);
+        assert_eq!(phis.len(), 2);
+        let phi = phis.iter().find(|phi| ssa.values[**phi].slot == 0).unwrap();
+        let if_value = match &ssa.body[0] {
+            Item::Branch { if_branch, .. } => defs(&if_branch[0])[0],
+            _ => unreachable!(),
+        };
+        // the else branch keeps the entry value of the slot
+        assert_eq!(incoming(&ssa, *phi), &vec![if_value, 0]);
+        assert_eq!(uses(&ssa.body[1]), &vec![*phi]);
+        assert_eq!(text(&ssa.lower()), text(&code));
+    }
+
+    #[test]
+    fn the_values_written_in_a_loop_meet_at_its_head() {
+        let increment = compute(OperatorType::Add, vec![load_var(var(0)), value(1)]);
+        let code = vec![set(var(0), value(0)), repeat(load_var(var(0)), vec![set(var(0), increment)])];
+        let ssa = ssa(&code, &[]);
+        let entry = defs(&ssa.body[0])[0];
+        let phi = phis(&ssa.body[1])[0];
+        let (body_use, body_def) = match &ssa.body[1] {
+            Item::Loop { body, .. } => (uses(&body[0])[0], defs(&body[0])[0]),
+            _ => unreachable!(),
+        };
+        assert_eq!(incoming(&ssa, phi), &vec![entry, body_def]);
+        // the condition and the body read the value at the head of the loop
+        assert_eq!(uses(&ssa.body[1]), &vec![phi]);
+        assert_eq!(body_use, phi);
+        assert_eq!(text(&ssa.lower()), text(&code));
+    }
+
+    #[test]
+    fn variables_accessed_with_a_dynamic_address_are_not_tracked() {
+        let code = vec![
+            set(element(1, 0), value(1)),
+            set(var(2), value(2)),
+            set(var(0), load_var(element(1, 1))),
+        ];
+        let ssa = ssa(&code, &[(0, 1), (1, 2)]);
+        // the constant access to the second slot of the array does not track it
+        assert_eq!(ssa.tracked, [true, false, false]);
+    }
+
+    #[test]
+    fn dynamic_accesses_to_unknown_variables_have_no_ssa_form() {
+        let code = vec![set(element(1, 0), value(1))];
+        let result = build(code.clone(), &HashMap::new());
+        assert!(matches!(result, Err(original) if text(&original) == text(&code)));
+    }
+}
@@ -0,0 +1,137 @@
+// Builders of small pieces of IR for the tests of the passes
+use super::field_info::FieldInfo;
+use crate::intermediate_representation::ir_interface::*;
+use crate::intermediate_representation::translate::FieldTracker;
+use num_bigint_dig::BigInt;
+
+pub const BN128: &str = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+
+// The constants of the code and the prime of the field
+pub struct Field {
+    pub tracker: FieldTracker,
+    pub prime: BigInt,
+}
+
+impl Field {
+    pub fn new() -> Field {
+        Field { tracker: FieldTracker::new(), prime: BigInt::parse_bytes(BN128.as_bytes(), 10).unwrap() }
+    }
+
+    pub fn constant(&mut self, value: i64) -> InstructionPointer {
+        let cid = self.tracker.insert(value.to_string());
+        ValueBucket { line: 0, message_id: 0, parse_as: ValueType::BigInt, op_aux_no: 0, value: cid }.allocate()
+    }
+
+    pub fn info(&mut self) -> FieldInfo<'_> {
+        FieldInfo { field_tracker: &mut self.tracker, prime: &self.prime }
+    }
+}
+
+pub fn value(value: usize) -> InstructionPointer {
+    ValueBucket { line: 0, message_id: 0, parse_as: ValueType::U32, op_aux_no: 0, value }.allocate()
+}
+
+pub fn var(slot: usize) -> InstructionPointer {
+    value(slot)
+}
+
+// The element of an array at base + offset
+pub fn element(base: usize, offset: usize) -> InstructionPointer {
+    compute(OperatorType::AddAddress, vec![value(offset), value(base)])
+}
+
+pub fn location(location: InstructionPointer) -> LocationRule {
+    LocationRule::Indexed { location, template_header: None }
+}
+
+pub fn single() -> InstrContext {
+    InstrContext { size: SizeOption::Single(1) }
+}
+
+pub fn load(address_type: AddressType, at: InstructionPointer) -> InstructionPointer {
+    LoadBucket { line: 0, message_id: 0, address_type, src: location(at), context: single() }.allocate()
+}
+
+pub fn load_var(at: InstructionPointer) -> InstructionPointer {
+    load(AddressType::Variable, at)
+}
+
+pub fn load_signal(at: InstructionPointer) -> InstructionPointer {
+    load(AddressType::Signal, at)
+}
+
+pub fn store(dest_address_type: AddressType, at: InstructionPointer, src: InstructionPointer) -> InstructionPointer {
+    StoreBucket {
+        line: 0,
+        message_id: 0,
+        context: single(),
+        src_context: single(),
+        dest_is_output: false,
+        dest_address_type,
+        src_address_type: None,
+        dest: location(at),
+        src,
+    }
+    .allocate()
+}
+
+// variable = src
+pub fn set(at: InstructionPointer, src: InstructionPointer) -> InstructionPointer {
+    store(AddressType::Variable, at, src)
+}
+
+// signal = src
+pub fn set_signal(at: InstructionPointer, src: InstructionPointer) -> InstructionPointer {
+    store(AddressType::Signal, at, src)
+}
+
+pub fn compute(op: OperatorType, stack: Vec<InstructionPointer>) -> InstructionPointer {
+    ComputeBucket { line: 0, message_id: 0, op, op_aux_no: 0, stack }.allocate()
+}
+
+pub fn repeat(continue_condition: InstructionPointer, body: InstructionList) -> InstructionPointer {
+    LoopBucket { line: 0, message_id: 0, continue_condition, body, vectorizable: false, reduction: None }.allocate()
+}
+
+pub fn branch(cond: InstructionPointer, if_branch: InstructionList, else_branch: InstructionList) -> InstructionPointer {
+    BranchBucket { line: 0, message_id: 0, cond, if_branch, else_branch }.allocate()
+}
+
+pub fn text(code: &InstructionList) -> Vec<String> {
+    code.iter().map(|instruction| instruction.to_string()).collect()
+}
+
+// Creates the components of an instance, an array if there are dimensions
+pub fn create(template_id: usize, symbol: &str, position: InstructionPointer, dimensions: Vec<usize>) -> InstructionPointer {
+    let number_of_cmp = dimensions.iter().product();
+    CreateCmpBucket {
+        line: 0,
+        message_id: 0,
+        template_id,
+        cmp_unique_id: template_id,
+        symbol: symbol.to_string(),
+        sub_cmp_id: position,
+        name_subcomponent: "sub".to_string(),
+        defined_positions: (0..number_of_cmp).map(|i| (i, false)).collect(),
+        is_part_mixed_array_not_uniform_parallel: false,
+        uniform_parallel: Some(false),
+        dimensions,
+        signal_offset: 1,
+        signal_offset_jump: 0,
+        component_offset: 0,
+        component_offset_jump: 0,
+        number_of_cmp,
+        has_inputs: true,
+    }
+    .allocate()
+}
+
+// The input signals of the subcomponent at cmp_address
+pub fn subcomponent(cmp_address: InstructionPointer) -> AddressType {
+    AddressType::SubcmpSignal {
+        cmp_address,
+        uniform_parallel_value: Some(false),
+        is_output: false,
+        input_information: InputInformation::Input { status: StatusInput::Unknown },
+    }
+}
@@ -0,0 +1,308 @@
+use crate::intermediate_representation::ir_interface::*;
+
+// Bounds of the ids and addresses that the code of a template or function
+// may refer to
+pub struct VerifyInfo {
+    pub variable_stack_depth: usize,
+    pub signal_stack_depth: usize,
+    pub max_message_id: usize,
+    pub number_of_constants: usize,
+    pub number_of_strings: usize,
+}
+
+pub fn verify_list(instructions: &InstructionList, info: &VerifyInfo) -> Result<(), String> {
+    for i in instructions {
+        verify_instruction(i, info)?;
+    }
+    Ok(())
+}
+
+fn error<T>(line: usize, message: String) -> Result<T, String> {
+    Err(format!("line {}: {}", line, message))
+}
+
+fn verify_instruction(instruction: &Instruction, info: &VerifyInfo) -> Result<(), String> {
+    use Instruction::*;
+    let line = instruction.get_line();
+    if instruction.get_message_id() > info.max_message_id {
+        return error(line, format!("message {} does not exist", instruction.get_message_id()));
+    }
+    match instruction {
+        Value(b) => {
+            if b.parse_as == ValueType::BigInt && b.value >= info.number_of_constants {
+                return error(line, format!("constant {} does not exist", b.value));
+            }
+            Ok(())
+        }
+        Load(b) => {
+            verify_location(&b.src, &b.address_type, &b.context, line, info)?;
+            verify_address_type(&b.address_type, info)
+        }
+        Store(b) => {
+            verify_instruction(&b.src, info)?;
+            verify_location(&b.dest, &b.dest_address_type, &b.context, line, info)?;
+            verify_address_type(&b.dest_address_type, info)?;
+            if let SizeOption::Multiple(_) = b.src_context.size {
+                if b.src_address_type.is_none() {
+                    return error(line, "store of multiple sizes without the source address".to_string());
+                }
+            }
+            verify_size(&b.src_context, line)?;
+            match &b.src_address_type {
+                Some(address) => verify_instruction(address, info),
+                None => Ok(()),
+            }
+        }
+        ArrayCopy(b) => {
+            verify_copied(&b.dest, &b.dest_address_type, b.size, line, info)?;
+            verify_copied(&b.src, &b.src_address_type, b.size, line, info)
+        }
+        Compute(b) => {
+            let arity = match b.op {
+                OperatorType::PrefixSub
+                | OperatorType::BoolNot
+                | OperatorType::Complement
+                | OperatorType::ToAddress => 1,
+                _ => 2,
+            };
+            if b.stack.len() != arity {
+                return error(line, format!("{} applied to {} operands", b.op.to_string(), b.stack.len()));
+            }
+            if let OperatorType::Eq(SizeOption::Single(0)) = b.op {
+                return error(line, "comparison of empty values".to_string());
+            }
+            for operand in &b.stack {
+                if let (true, Value(v)) = (b.op.is_address_op() && b.op != OperatorType::ToAddress, &**operand) {
+                    if v.parse_as != ValueType::U32 {
+                        return error(line, format!("field value used in {}", b.op.to_string()));
+                    }
+                }
+                verify_instruction(operand, info)?;
+            }
+            Ok(())
+        }
+        Select(b) => {
+            verify_instruction(&b.cond, info)?;
+            verify_instruction(&b.if_true, info)?;
+            verify_instruction(&b.if_false, info)
+        }
+        Call(b) => {
+            verify_list(&b.arguments, info)?;
+            if let ReturnType::Final(data) = &b.return_info {
+                verify_location(&data.dest, &data.dest_address_type, &data.context, line, info)?;
+                verify_address_type(&data.dest_address_type, info)?;
+            }
+            Ok(())
+        }
+        Branch(b) => {
+            verify_instruction(&b.cond, info)?;
+            verify_list(&b.if_branch, info)?;
+            verify_list(&b.else_branch, info)
+        }
+        Switch(b) => {
+            verify_instruction(&b.in_range, info)?;
+            verify_instruction(&b.selector, info)?;
+            if b.cases.is_empty() {
+                return error(line, "switch without cases".to_string());
+            }
+            if b.cases.windows(2).any(|pair| pair[0].value >= pair[1].value) {
+                return error(line, "switch cases not sorted".to_string());
+            }
+            for case in &b.cases {
+                verify_list(&case.body, info)?;
+            }
+            verify_list(&b.default_branch, info)
+        }
+        Loop(b) => {
+            verify_instruction(&b.continue_condition, info)?;
+            verify_list(&b.body, info)
+        }
+        Return(b) => verify_instruction(&b.value, info),
+        Assert(b) => {
+            for part in &b.message {
+                match *part {
+                    AssertMessagePart::Str(id) if id >= info.number_of_strings => {
+                        return error(line, format!("string {} does not exist", id));
+                    }
+                    AssertMessagePart::Value(index) if index >= b.values.len() => {
+                        return error(line, format!("value {} of the message does not exist", index));
+                    }
+                    _ => {}
+                }
+            }
+            verify_instruction(&b.evaluate, info)?;
+            b.values.iter().try_for_each(|v| verify_instruction(v, info))
+        }
+        CreateCmp(b) => verify_instruction(&b.sub_cmp_id, info),
+        Log(b) => {
+            for print in &b.argsprint {
+                match print {
+                    LogBucketArg::LogExp(exp) => verify_instruction(exp, info)?,
+                    LogBucketArg::LogStr(id) if *id >= info.number_of_strings => {
+                        return error(line, format!("string {} does not exist", id));
+                    }
+                    LogBucketArg::LogStr(_) => {}
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+fn verify_size(context: &InstrContext, line: usize) -> Result<(), String> {
+    match &context.size {
+        SizeOption::Multiple(sizes) if sizes.is_empty() => {
+            error(line, "empty list of possible sizes".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn verify_location(
+    location: &LocationRule,
+    xtype: &AddressType,
+    context: &InstrContext,
+    line: usize,
+    info: &VerifyInfo,
+) -> Result<(), String> {
+    let is_subcmp = matches!(xtype, AddressType::SubcmpSignal { .. });
+    verify_size(context, line)?;
+    if let (SizeOption::Multiple(_), false) = (&context.size, is_subcmp) {
+        return error(line, "multiple sizes outside a subcomponent".to_string());
+    }
+    match location {
+        LocationRule::Indexed { location, template_header } => {
+            if template_header.is_some() && !is_subcmp {
+                return error(line, "template header outside a subcomponent".to_string());
+            }
+            if let (Instruction::Value(v), SizeOption::Single(size)) = (&**location, &context.size) {
+                if v.parse_as != ValueType::U32 {
+                    return error(line, "field value used as an address".to_string());
+                }
+                // the signals of the subcomponents are checked at execution time
+                let (bound, kind) = match xtype {
+                    AddressType::Variable => (info.variable_stack_depth, "variable"),
+                    AddressType::Signal => (info.signal_stack_depth, "signal"),
+                    AddressType::SubcmpSignal { .. } => (usize::MAX, "subcomponent signal"),
+                };
+                if v.value + size > bound {
+                    let end = v.value + size;
+                    return error(line, format!("{} positions {}..{} out of 0..{}", kind, v.value, end, bound));
+                }
+            }
+            verify_instruction(location, info)
+        }
+        LocationRule::Mapped { indexes, .. } => {
+            if !is_subcmp {
+                return error(line, "mapped location outside a subcomponent".to_string());
+            }
+            for access in indexes {
+                if let AccessType::Indexed(instr) = access {
+                    verify_list(&instr.indexes, info)?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+fn verify_copied(
+    location: &Instruction,
+    xtype: &AddressType,
+    size: usize,
+    line: usize,
+    info: &VerifyInfo,
+) -> Result<(), String> {
+    let (bound, kind) = match xtype {
+        AddressType::Variable => (info.variable_stack_depth, "variable"),
+        AddressType::Signal => (info.signal_stack_depth, "signal"),
+        AddressType::SubcmpSignal { .. } => {
+            return error(line, "array copy of subcomponent signals".to_string());
+        }
+    };
+    if let Instruction::Value(v) = location {
+        if v.parse_as != ValueType::U32 {
+            return error(line, "field value used as an address".to_string());
+        }
+        if v.value + size > bound {
+            let end = v.value + size;
+            return error(line, format!("{} positions {}..{} out of 0..{}", kind, v.value, end, bound));
+        }
+    }
+    verify_instruction(location, info)
+}
+
+fn verify_address_type(xtype: &AddressType, info: &VerifyInfo) -> Result<(), String> {
+    if let AddressType::SubcmpSignal { cmp_address, .. } = xtype {
+        verify_instruction(cmp_address, info)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify_list, VerifyInfo};
+    use crate::intermediate_representation::ir_interface::*;
+    use crate::ir_processing::test_code::*;
+
+    fn info() -> VerifyInfo {
+        VerifyInfo {
+            variable_stack_depth: 2,
+            signal_stack_depth: 3,
+            max_message_id: 0,
+            number_of_constants: 1,
+            number_of_strings: 0,
+        }
+    }
+
+    fn verified(code: InstructionList) -> Result<(), String> {
+        verify_list(&code, &info())
+    }
+
+    fn field_value(value: usize) -> InstructionPointer {
+        ValueBucket { line: 0, message_id: 0, parse_as: ValueType::BigInt, op_aux_no: 0, value }.allocate()
+    }
+
+    #[test]
+    fn valid_code_is_accepted() {
+        let sum = compute(OperatorType::Add, vec![load_signal(value(2)), field_value(0)]);
+        let code = vec![set(var(1), sum), repeat(load_var(var(1)), vec![set_signal(value(0), load_var(var(0)))])];
+        assert_eq!(verified(code), Ok(()));
+    }
+
+    #[test]
+    fn addresses_out_of_the_stack_are_rejected() {
+        let error = verified(vec![set(var(2), value(1))]).unwrap_err();
+        assert_eq!(error, "line 0: variable positions 2..3 out of 0..2");
+        let error = verified(vec![set(var(0), load_signal(value(3)))]).unwrap_err();
+        assert_eq!(error, "line 0: signal positions 3..4 out of 0..3");
+    }
+
+    #[test]
+    fn operations_with_a_wrong_number_of_operands_are_rejected() {
+        let error = verified(vec![set(var(0), compute(OperatorType::Add, vec![value(1)]))]).unwrap_err();
+        assert!(error.contains("applied to 1 operands"), "{}", error);
+    }
+
+    #[test]
+    fn unknown_constants_are_rejected() {
+        let error = verified(vec![set(var(0), field_value(1))]).unwrap_err();
+        assert_eq!(error, "line 0: constant 1 does not exist");
+    }
+
+    #[test]
+    fn field_values_used_as_addresses_are_rejected() {
+        let error = verified(vec![set(field_value(0), value(1))]).unwrap_err();
+        assert_eq!(error, "line 0: field value used as an address");
+        let address = compute(OperatorType::AddAddress, vec![field_value(0), value(0)]);
+        let error = verified(vec![set(address, value(1))]).unwrap_err();
+        assert!(error.contains("field value used in"), "{}", error);
+    }
+
+    #[test]
+    fn errors_inside_loops_and_branches_are_found() {
+        let wrong = || vec![set(var(5), value(1))];
+        assert!(verified(vec![repeat(value(1), wrong())]).is_err());
+        assert!(verified(vec![branch(value(1), Vec::new(), wrong())]).is_err());
+    }
+}
@@ -5,6 +5,7 @@ use compiler::hir::very_concrete_program::{Argument, TemplateInstance};
 use num_bigint::BigInt;
 use num_traits::ToPrimitive;
 use program_structure::ast::{Expression, Meta, Statement};
+use program_structure::builtin_calls::BuiltinCall;
 use program_structure::error_definition::ReportCollection;
 use program_structure::program_archive::ProgramArchive;
 use std::collections::HashMap;
@@ -22,6 +23,10 @@ pub fn manage_functions(program_archive: &mut ProgramArchive, flags: FlagsExecut
     let mut reports = vec![];
     let mut processed = HashMap::new();
     for (name, data) in program_archive.get_functions() {
+        // the compiler evaluates them, so their arrays do not need lengths
+        if data.is_compile_time() {
+            continue;
+        }
         let mut code = data.get_body().clone();
         let environment = EE::new();
         let context =
@@ -89,6 +94,11 @@ fn treat_statement(stmt: &mut Statement, context: &Context, reports: &mut Report
         treat_declaration(stmt, context, reports, flags, prime)
     } else if stmt.is_substitution(){
         treat_substitution(stmt, context, reports, flags, prime)
+    } else if let Statement::Return { value, .. } = stmt {
+        treat_expression(value, context, reports, flags, prime)
+    } else if let Statement::ConstraintEquality { lhe, rhe, .. } = stmt {
+        treat_expression(lhe, context, reports, flags, prime);
+        treat_expression(rhe, context, reports, flags, prime)
     } else{
 
     }
@@ -183,7 +193,7 @@ fn treat_substitution(stmt: &mut Statement, context: &Context, reports: &mut Rep
 fn treat_expression(
     expr: &mut Expression, context: &Context, reports: &mut ReportCollection, flags: FlagsExecution, prime: &String
 ){
-    use Expression::{Number, UniformArray};
+    use Expression::{Number, UniformArray, Call, InfixOp, PrefixOp, InlineSwitchOp, ArrayInLine};
     if let UniformArray {meta, value, dimension} = expr{
         let execution_response = treat_dimension(&dimension, context, reports, flags, prime);
         if let Option::Some(v) = execution_response {
@@ -192,6 +202,38 @@ fn treat_expression(
             report_invalid_dimension(meta, reports);
         }
         treat_expression(value, context, reports, flags, prime)
+    } else if let Call { meta, id, args } = expr {
+        // the bounds of a slice give the size of the array it returns
+        if BuiltinCall::from_id(id) == Some(BuiltinCall::Slice) {
+            let mut is_valid = true;
+            for bound in &mut args[1..] {
+                let execution_response = treat_dimension(bound, context, reports, flags, prime);
+                if let Option::Some(v) = execution_response {
+                    *bound = Number(bound.get_meta().clone(), BigInt::from(v));
+                } else {
+                    is_valid = false;
+                }
+            }
+            if !is_valid {
+                report_invalid_dimension(meta, reports);
+            }
+        }
+        for arg in args {
+            treat_expression(arg, context, reports, flags, prime);
+        }
+    } else if let InfixOp { lhe, rhe, .. } = expr {
+        treat_expression(lhe, context, reports, flags, prime);
+        treat_expression(rhe, context, reports, flags, prime)
+    } else if let PrefixOp { rhe, .. } = expr {
+        treat_expression(rhe, context, reports, flags, prime)
+    } else if let InlineSwitchOp { cond, if_true, if_false, .. } = expr {
+        treat_expression(cond, context, reports, flags, prime);
+        treat_expression(if_true, context, reports, flags, prime);
+        treat_expression(if_false, context, reports, flags, prime)
+    } else if let ArrayInLine { values, .. } = expr {
+        for value in values {
+            treat_expression(value, context, reports, flags, prime);
+        }
     } else{
     }
 }
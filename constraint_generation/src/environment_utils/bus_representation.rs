@@ -14,6 +14,8 @@ pub struct BusRepresentation {
     fields: BTreeMap<String, FieldTypes>,
     unassigned_fields: HashMap<String, SliceCapacity>,
     has_assignment: bool,
+    // the location of the assignment that set has_assignment, if known
+    assignment_meta: Option<Meta>,
 }
 
 impl Default for BusRepresentation {
@@ -23,7 +25,8 @@ impl Default for BusRepresentation {
             fields: BTreeMap::new(),
             meta: Option::None,
             unassigned_fields: HashMap::new(),
-            has_assignment: false
+            has_assignment: false,
+            assignment_meta: Option::None,
         }
     }
 }
@@ -34,7 +37,8 @@ impl Clone for BusRepresentation {
             fields: self.fields.clone(),
             meta : self.meta.clone(),
             unassigned_fields: self.unassigned_fields.clone(),
-            has_assignment: self.has_assignment
+            has_assignment: self.has_assignment,
+            assignment_meta: self.assignment_meta.clone(),
         }
     }
 }
@@ -203,9 +207,9 @@ impl BusRepresentation {
             // We only update AssignmentState if it has been assigned for sure
             if has_assignment{
                 match conditions_assignment{
-                    AssignmentState::Assigned(_) =>{
+                    AssignmentState::Assigned(meta) =>{
                         self.has_assignment = true;
-
+                        self.assignment_meta = meta.clone();
                     }
                     _ =>{}
                 }
@@ -327,7 +331,7 @@ impl BusRepresentation {
     )-> Result<(), MemoryError>{
         
         if self.has_assignment{
-            return Result::Err(MemoryError::AssignmentError(TypeAssignmentError::MultipleAssignmentsBus));
+            return Result::Err(MemoryError::AssignmentError(TypeAssignmentError::MultipleAssignmentsBus(self.assignment_meta.clone())));
         }
 
         // check that they are the same instance of buses
@@ -337,9 +341,9 @@ impl BusRepresentation {
 
         // only update if it is assigned for sure
         match conditions_assignment{
-            AssignmentState::Assigned(_) =>{
+            AssignmentState::Assigned(meta) =>{
                 self.has_assignment = true;
-
+                self.assignment_meta = meta.clone();
             }
             _ =>{}
         }
@@ -722,6 +722,10 @@ impl ComponentRepresentation {
     pub fn has_unassigned_inputs(&self) -> bool{
         !self.unassigned_tags.is_empty () || !self.unassigned_inputs.is_empty() 
     }
+
+    pub fn is_output(&self, field_name: &str) -> bool {
+        self.outputs.contains_key(field_name) || self.output_buses.contains_key(field_name)
+    }
      
 
     /*
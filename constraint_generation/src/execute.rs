@@ -22,6 +22,7 @@ use crate::{assignment_utils::*, environment_utils::slice_types::AssignmentState
 use crate::environment_utils::slice_types::BusTagInfo;
 use program_structure::constants::UsefulConstants;
 use program_structure::bus_data::BusData;
+use program_structure::builtin_calls::BuiltinCall;
 use super::execution_data::analysis::Analysis;
 use super::execution_data::{ExecutedBus, ExecutedProgram, ExecutedTemplate, PreExecutedTemplate, NodePointer};
 use super::execution_data::type_definitions::{AccessingInformationBus, AccessingInformation, TagNames, TagWire};
@@ -30,7 +31,7 @@ use super::{
     ast::*, ArithmeticError, FileID, ProgramArchive, Report, ReportCode, ReportCollection
 };
 use circom_algebra::num_bigint::BigInt;
-use std::collections::{HashMap, BTreeMap};
+use std::collections::{HashMap, HashSet, BTreeMap};
 use crate::FlagsExecution;
 type AExpr = ArithmeticExpressionGen<String>;
 type AnonymousComponentsInfo = BTreeMap<String, (Meta, Vec<Expression>)>;
@@ -41,6 +42,13 @@ enum BlockType {
     Unknown,
 }
 
+// A call of the call trace, with the code that makes it and the label of
+// that code in the reports, except for the main component
+pub struct TraceCall {
+    pub id: String,
+    pub call: Option<(Meta, String)>,
+}
+
 struct RuntimeInformation {
     pub block_type: BlockType,
     pub conditions_state: Vec<(usize, bool)>,
@@ -48,12 +56,23 @@ struct RuntimeInformation {
     pub analysis: Analysis,
     pub public_inputs: Vec<String>,
     pub constants: UsefulConstants,
-    pub call_trace: Vec<String>,
+    pub call_trace: Vec<TraceCall>,
     pub current_file: FileID,
     pub runtime_errors: ReportCollection,
     pub environment: ExecutionEnvironment,
     pub exec_program: ExecutedProgram,
     pub anonymous_components: AnonymousComponentsInfo,
+    // the templates being executed, with their parameters
+    pub template_calls: Vec<(String, Vec<AExpressionSlice>)>,
+    // the function that the compiler is evaluating, with the loop iterations
+    // and calls of its evaluation
+    pub function_steps: Option<(String, usize)>,
+    // the outputs of subcomponents read while evaluating an expression of
+    // the witness computation
+    pub witness_reads: Option<Vec<String>>,
+    // the places of the conditional assignments of the signals whose reads
+    // have already been reported
+    pub conditional_reads: HashSet<(FileID, usize)>,
 }
 impl RuntimeInformation {
     pub fn new(current_file: FileID, id_max: usize, prime: &String) -> RuntimeInformation {
@@ -70,6 +89,10 @@ impl RuntimeInformation {
             anonymous_components: AnonymousComponentsInfo::new(),
             conditions_state: Vec::new(),
             unknown_counter: 0,
+            template_calls: Vec::new(),
+            function_steps: None,
+            witness_reads: None,
+            conditional_reads: HashSet::new(),
         }
     }
 }
@@ -125,13 +148,25 @@ enum ExecutionError {
     TagAssignmentInUnknown,
     UnknownTemplate,
     NonValidTagAssignment,
-    FalseAssert,
-    ArraySizeTooBig
+    FalseAssert(String),
+    FalseStaticAssert(String),
+    UnknownStaticAssert,
+    ArraySizeTooBig,
+    InvalidSlice,
+    // the template, the limit and the parameters of the last instance
+    RecursionTooDeep(String, usize, String),
+    UnconstrainedHint(String),
+    UnconstrainedAssignment(String, String),
+    FunctionStepsExceeded(String, usize),
 }
 
 enum ExecutionWarning {
     CanBeQuadraticConstraintSingle(),
     CanBeQuadraticConstraintMultiple(Vec<String>),
+    UndeterminedSignal(String, String),
+    UnconstrainedReadOutput(String, String),
+    UnconstrainedAssignment(String, String),
+    ConditionallyAssignedRead(String, Option<Meta>),
 }
 
 
@@ -151,14 +186,17 @@ pub fn constraint_execution(
         if let Call { id, args, .. } = &program_archive.get_main_expression() {
             let mut arg_values = Vec::new();
             for arg_expression in args.iter() {
-                let f_arg = execute_expression(arg_expression, program_archive, &mut runtime_information, flags);
-                arg_values.push(safe_unwrap_to_arithmetic_slice(f_arg.unwrap(), line!()));
-                // improve
+                let f_arg = match execute_expression(arg_expression, program_archive, &mut runtime_information, flags) {
+                    Result::Ok(f_arg) => f_arg,
+                    Result::Err(_) => return Result::Err(runtime_information.runtime_errors),
+                };
+                arg_values.push(safe_unwrap_to_arithmetic_slice(f_arg, line!()));
             }
             execute_template_call_complete(
                 id,
                 arg_values,
                 HashMap::new(),
+                Option::None,
                 program_archive,
                 &mut runtime_information,
                 flags,
@@ -177,6 +215,42 @@ pub fn constraint_execution(
     }
 }
 
+// The lengths and the values of each argument of a template
+pub type ArgumentValues = Vec<(Vec<usize>, Vec<BigInt>)>;
+
+// The values of the arguments of the main component, as they are computed
+// before its template is instantiated
+pub fn execute_main_arguments(
+    program_archive: &ProgramArchive,
+    flags: FlagsExecution,
+    prime: &String,
+) -> Result<ArgumentValues, ReportCollection> {
+    let main_file_id = program_archive.get_file_id_main();
+    let mut runtime_information = RuntimeInformation::new(*main_file_id, program_archive.id_max, prime);
+    let args = match program_archive.get_main_expression() {
+        Expression::Call { args, .. } => args,
+        _ => unreachable!("The main expression should be a call."),
+    };
+    let mut arguments = Vec::new();
+    for arg_expression in args {
+        let f_arg = match execute_expression(arg_expression, program_archive, &mut runtime_information, flags) {
+            Result::Ok(f_arg) => f_arg,
+            Result::Err(_) => return Result::Err(runtime_information.runtime_errors),
+        };
+        let (lengths, expressions) = safe_unwrap_to_arithmetic_slice(f_arg, line!()).destruct();
+        let mut values = Vec::new();
+        for expression in expressions {
+            if let AExpr::Number { value } = expression {
+                values.push(value);
+            } else {
+                unreachable!("The arguments of the main component are known.");
+            }
+        }
+        arguments.push((lengths, values));
+    }
+    Result::Ok(arguments)
+}
+
 pub fn execute_constant_expression(
     expression: &Expression,
     program_archive: &ProgramArchive,
@@ -361,7 +435,11 @@ fn execute_statement(
             
             
             
-            let r_folded = execute_expression(rhe, program_archive, runtime, flags)?;
+            let r_folded = if *op == AssignOp::AssignSignal {
+                execute_witness_expression(meta, rhe, program_archive, runtime, actual_node, flags)?
+            } else {
+                execute_expression(rhe, program_archive, runtime, flags)?
+            };
             
             let mut struct_node = if actual_node.is_some(){
                 ExecutedStructure::Template(actual_node.as_mut().unwrap())
@@ -381,8 +459,27 @@ fn execute_statement(
                     runtime, 
                     program_archive, 
                     flags
-                )?;
+                ).map_err(|_| add_declaration_label(meta, var, program_archive, runtime))?;
 
+            if meta.hint {
+                if let (Option::Some(node), Option::Some(constrained)) = (actual_node.as_mut(), &possible_constraint) {
+                    for i in 0..AExpressionSlice::get_number_of_cells(&constrained.left) {
+                        if let Result::Ok(AExpr::Signal { symbol }) = AExpressionSlice::access_value_by_index(&constrained.left, i) {
+                            node.add_hinted_signal(&symbol, meta);
+                        }
+                    }
+                }
+            }
+
+            if *op == AssignOp::AssignSignal && (flags.inspect || flags.deny_unconstrained) {
+                if let (Option::Some(node), Option::Some(constrained)) = (actual_node.as_mut(), &possible_constraint) {
+                    for i in 0..AExpressionSlice::get_number_of_cells(&constrained.left) {
+                        if let Result::Ok(AExpr::Signal { symbol }) = AExpressionSlice::access_value_by_index(&constrained.left, i) {
+                            node.add_witness_signal(&symbol, meta);
+                        }
+                    }
+                }
+            }
 
             if let Option::Some(node) = actual_node {
                 if *op == AssignOp::AssignConstraintSignal || (*op == AssignOp::AssignSignal && flags.inspect){
@@ -434,7 +531,7 @@ fn execute_statement(
                                 let symbol = signal_left;
                                 let expr = AExpr::sub(&symbol, &value_right, &p);
                                 let ctr = AExpr::transform_expression_to_constraint_form(expr, &p).unwrap();
-                                node.add_constraint(ctr);
+                                node.add_constraint(ctr, meta);
                             }
                         } else if let AssignOp::AssignSignal = op {// needs fix, check case arrays
                             //debug_assert!(possible_constraint.is_some());
@@ -463,6 +560,9 @@ fn execute_statement(
                                 &mut runtime.runtime_errors,
                                 &runtime.call_trace,
                             )?;
+                            if let Some(report) = runtime.runtime_errors.last_mut() {
+                                suggest_constraint_assignment(report, meta, program_archive);
+                            }
                         } else{
                             let err : Result<(),ExecutionWarning> = 
                                 Result::Err(ExecutionWarning::CanBeQuadraticConstraintMultiple(needs_double_arrow));
@@ -622,7 +722,7 @@ fn execute_statement(
                 )
                 .unwrap();
                 if let Option::Some(node) = actual_node {
-                    node.add_constraint(constraint_expression);
+                    node.add_constraint(constraint_expression, meta);
                 }    
             }
             Option::None
@@ -651,11 +751,12 @@ fn execute_statement(
             can_be_simplified = can_simplify;
             possible_return
         }
-        While { cond, stmt, .. } => {
+        While { meta, cond, stmt } => {
             // We update the conditions state of the execution
             runtime.conditions_state.push((runtime.unknown_counter, true));
             runtime.unknown_counter+=1;
             loop {
+                count_function_step(meta, runtime, flags)?;
 
                 let (returned, can_simplify, condition_result) = execute_conditional_statement(
                     cond,
@@ -695,7 +796,7 @@ fn execute_statement(
             can_be_simplified = can_simplify_block;
             return_value
         }
-        LogCall { args, .. } => {
+        LogCall { args, is_formatted, .. } => {
             can_be_simplified = false;
             if flags.verbose{
                 let mut index = 0;
@@ -713,7 +814,7 @@ fn execute_statement(
                     else if let LogArgument::LogStr(s) = arglog {
                             print!("{}",s);
                     }
-                    if index != args.len()-1{
+                    if index != args.len()-1 && !is_formatted{
                         print!(" ");
                     }
                     index += 1;
@@ -729,13 +830,31 @@ fn execute_statement(
             }
             Option::None
         }
-        Assert { arg, meta, .. } => {
+        Assert { arg, meta, message, is_static } => {
             let f_result = execute_expression(arg, program_archive, runtime, flags)?;
             let arith = safe_unwrap_to_single_arithmetic_expression(f_result, line!());
             let possible_bool = AExpr::get_boolean_equivalence(&arith, runtime.constants.get_p());
+            // the values of the message are shown as in a log
+            let mut shown = Vec::new();
+            for arglog in message.iter() {
+                match arglog {
+                    LogArgument::LogExp(arg) => {
+                        let f_result = execute_expression(arg, program_archive, runtime, flags)?;
+                        let arith = safe_unwrap_to_single_arithmetic_expression(f_result, line!());
+                        if AExpr::is_number(&arith) {
+                            shown.push(arith.to_string());
+                        } else {
+                            shown.push("Unknown".to_string());
+                        }
+                    }
+                    LogArgument::LogStr(s) => shown.push(s.clone()),
+                }
+            }
             let result = match possible_bool {
-                Some(b) if !b => Err(ExecutionError::FalseAssert),
+                Some(b) if !b && *is_static => Err(ExecutionError::FalseStaticAssert(shown.join(" "))),
+                Some(b) if !b => Err(ExecutionError::FalseAssert(shown.join(" "))),
                 Some(b) if b => Ok(None),
+                _ if *is_static => Err(ExecutionError::UnknownStaticAssert),
                 _ => {
                     can_be_simplified = false;
                     Ok(None)
@@ -1027,11 +1146,13 @@ fn execute_expression(
         InfixOp { meta, lhe, infix_op, rhe, .. } => {
             let l_fold = execute_expression(lhe, program_archive, runtime, flags)?;
             let r_fold = execute_expression(rhe, program_archive, runtime, flags)?;
+            let (l_tags, r_tags) = (l_fold.tags.clone(), r_fold.tags.clone());
             let l_value = safe_unwrap_to_single_arithmetic_expression(l_fold, line!());
             let r_value = safe_unwrap_to_single_arithmetic_expression(r_fold, line!());
             let r_value = execute_infix_op(meta, *infix_op, &l_value, &r_value, runtime)?;
             let r_slice = AExpressionSlice::new(&r_value);
-            FoldedValue { arithmetic_slice: Option::Some(r_slice), ..FoldedValue::default() }
+            let tags = execute_tag_rules(meta, *infix_op, l_tags, r_tags, program_archive, runtime, flags)?;
+            FoldedValue { arithmetic_slice: Option::Some(r_slice), tags, ..FoldedValue::default() }
         }
         PrefixOp { prefix_op, rhe, .. } => {
             let folded_value = execute_expression(rhe, program_archive, runtime, flags)?;
@@ -1057,13 +1178,18 @@ fn execute_expression(
                 FoldedValue { arithmetic_slice, ..FoldedValue::default() }
             }
         }
+        Call { id, args, meta } if BuiltinCall::from_id(id).is_some() => {
+            let (value, can_simplify) = execute_builtin_call(id, meta, args, program_archive, runtime, flags)?;
+            can_be_simplified = can_simplify;
+            value
+        }
         Call { id, args, meta, .. } => {
             let (value, can_simplify) = execute_call(id,meta, args, program_archive, runtime, flags)?;
             can_be_simplified = can_simplify;
             value
         }
-        BusCall{id, args, ..} =>{
-            let value = execute_bus_call_complete(id, args, program_archive, runtime, flags)?;
+        BusCall{id, args, meta, ..} =>{
+            let value = execute_bus_call_complete(id, meta, args, program_archive, runtime, flags)?;
             value
         
         }
@@ -1118,34 +1244,272 @@ fn execute_call(
         }
         arg_values.push(safe_f_arg);
     }
+    if is_template {
+        let recursion = check_template_recursion(id, &arg_values, program_archive, runtime, flags);
+        treat_result_with_execution_error(recursion, meta, &mut runtime.runtime_errors, &runtime.call_trace)?;
+    }
     if program_archive.contains_function(id){ // in this case we execute
-        let new_environment = prepare_environment_for_call(id, &arg_values, program_archive);
-        let previous_environment = std::mem::replace(&mut runtime.environment, new_environment);
-        let previous_block_type = std::mem::replace(&mut runtime.block_type, BlockType::Known);
-        let previous_anonymous_components = std::mem::replace(&mut runtime.anonymous_components, AnonymousComponentsInfo::new());
+        count_function_step(meta, runtime, flags)?;
+        execute_function_with_values(id, meta, &arg_values, program_archive, runtime, flags)
+    } else { // in this case we preexecute and check if it needs tags
+        let folded_result = preexecute_template_call(id, meta, &arg_values, program_archive, runtime)?;
+        Ok((folded_result, true))
+    }
+}
 
-        let new_file_id = program_archive.get_function_data(id).get_file_id();
-        let previous_id = std::mem::replace(&mut runtime.current_file, new_file_id);
+// Calls the function of map and fold on the elements of the array one after
+// the other, and adds them up in sum
+fn execute_builtin_call(
+    id: &str,
+    meta: &Meta,
+    args: &[Expression],
+    program_archive: &ProgramArchive,
+    runtime: &mut RuntimeInformation,
+    flags: FlagsExecution,
+) -> Result<(FoldedValue, bool), ()> {
+    let builtin = BuiltinCall::from_id(id).unwrap();
+    let mut acc = if builtin == BuiltinCall::Fold {
+        let f_init = execute_expression(&args[1], program_archive, runtime, flags)?;
+        Some(safe_unwrap_to_arithmetic_slice(f_init, line!()))
+    } else {
+        None
+    };
+    let mut elements = Vec::new();
+    for array in builtin.arrays(args) {
+        let f_array = execute_expression(array, program_archive, runtime, flags)?;
+        let array = safe_unwrap_to_arithmetic_slice(f_array, line!());
+        for position in 0..array.route()[0] {
+            let element = AExpressionSlice::access_values(&array, &[position]);
+            elements.push(treat_result_with_memory_error(element, meta, &mut runtime.runtime_errors, &runtime.call_trace)?);
+        }
+    }
+    let mut bounds = Vec::new();
+    for bound in builtin.bounds(args) {
+        let f_bound = execute_expression(bound, program_archive, runtime, flags)?;
+        let bound_value = AExpressionSlice::unwrap_to_single(safe_unwrap_to_arithmetic_slice(f_bound, line!()));
+        bounds.push(cast_index(&bound_value));
+    }
+    let mut can_be_simplified = true;
+    let mut call = |arg_values: &[AExpressionSlice], runtime: &mut RuntimeInformation| {
+        let (_, function) = builtin.function(args).unwrap();
+        let (value, can_simplify) = execute_function_with_values(function, meta, arg_values, program_archive, runtime, flags)?;
+        can_be_simplified = can_be_simplified && can_simplify;
+        Ok(safe_unwrap_to_arithmetic_slice(value, line!()))
+    };
+    let arithmetic_slice = match builtin {
+        BuiltinCall::Map => {
+            let mut values = Vec::new();
+            for element in elements {
+                values.push(call(&[element], runtime)?);
+            }
+            build_array_of_values(&values, meta, runtime)?
+        }
+        BuiltinCall::Fold => {
+            for element in elements {
+                let value = call(&[acc.take().unwrap(), element], runtime)?;
+                acc = Some(value);
+            }
+            acc.unwrap()
+        }
+        BuiltinCall::Sum => {
+            let field = runtime.constants.get_p().clone();
+            let mut total = AExpr::Number { value: BigInt::from(0) };
+            for element in elements {
+                total = AExpr::add(&total, &AExpressionSlice::unwrap_to_single(element), &field);
+            }
+            AExpressionSlice::new(&total)
+        }
+        BuiltinCall::Concat => build_array_of_values(&elements, meta, runtime)?,
+        BuiltinCall::Slice => {
+            let range = match bounds[..] {
+                [Some(start), Some(end)] if start <= end && end <= elements.len() => Ok(start..end),
+                _ => Err(ExecutionError::InvalidSlice),
+            };
+            let range = treat_result_with_execution_error(range, meta, &mut runtime.runtime_errors, &runtime.call_trace)?;
+            build_array_of_values(&elements[range], meta, runtime)?
+        }
+        BuiltinCall::Reverse => {
+            elements.reverse();
+            build_array_of_values(&elements, meta, runtime)?
+        }
+        BuiltinCall::Sort => {
+            let field = runtime.constants.get_p().clone();
+            let values: Vec<_> = elements.into_iter().map(AExpressionSlice::unwrap_to_single).collect();
+            // the order of values unknown during the constraint generation
+            // phase is unknown too
+            let sorted = if values.iter().all(|v| v.is_number()) {
+                let is_lesser = |l: &AExpr, r: &AExpr| AExpr::get_boolean_equivalence(&AExpr::lesser(l, r, &field), &field) == Some(true);
+                let mut values = values;
+                values.sort_by(|l, r| {
+                    if is_lesser(l, r) {
+                        std::cmp::Ordering::Less
+                    } else if is_lesser(r, l) {
+                        std::cmp::Ordering::Greater
+                    } else {
+                        std::cmp::Ordering::Equal
+                    }
+                });
+                values
+            } else {
+                vec![AExpr::NonQuadratic; values.len()]
+            };
+            let values: Vec<_> = sorted.iter().map(AExpressionSlice::new).collect();
+            build_array_of_values(&values, meta, runtime)?
+        }
+        BuiltinCall::Len => AExpressionSlice::new(&AExpr::Number { value: BigInt::from(elements.len()) }),
+    };
+    Ok((FoldedValue { arithmetic_slice: Some(arithmetic_slice), ..FoldedValue::default() }, can_be_simplified))
+}
 
-        runtime.call_trace.push(id.clone());
-        let folded_result = execute_function_call(id, program_archive, runtime, flags)?;
+// The array whose rows are the values, which must have the same dimensions
+fn build_array_of_values(
+    values: &[AExpressionSlice],
+    meta: &Meta,
+    runtime: &mut RuntimeInformation,
+) -> Result<AExpressionSlice, ()> {
+    let mut dims = vec![values.len()];
+    if let Some(value) = values.first() {
+        dims.extend_from_slice(value.route());
+    }
+    let mut array_slice = AExpressionSlice::new_with_route(&dims, &AExpr::default());
+    for (row, value) in values.iter().enumerate() {
+        let memory_insert_result = AExpressionSlice::insert_values(&mut array_slice, &[row], value, true);
+        treat_result_with_memory_error_void(memory_insert_result, meta, &mut runtime.runtime_errors, &runtime.call_trace)?;
+    }
+    Ok(array_slice)
+}
 
-        runtime.environment = previous_environment;
-        runtime.current_file = previous_id;
-        runtime.block_type = previous_block_type;
-        runtime.anonymous_components = previous_anonymous_components;
-        runtime.call_trace.pop();
-        Ok(folded_result)
-    } else { // in this case we preexecute and check if it needs tags
-        let folded_result = preexecute_template_call(id, &arg_values, program_archive, runtime)?;
-        Ok((folded_result, true))
+fn execute_function_with_values(
+    id: &str,
+    meta: &Meta,
+    arg_values: &[AExpressionSlice],
+    program_archive: &ProgramArchive,
+    runtime: &mut RuntimeInformation,
+    flags: FlagsExecution,
+) -> Result<(FoldedValue, bool), ()> {
+    let new_environment = prepare_environment_for_call(id, arg_values, program_archive);
+    let previous_environment = std::mem::replace(&mut runtime.environment, new_environment);
+    let previous_block_type = std::mem::replace(&mut runtime.block_type, BlockType::Known);
+    let previous_anonymous_components = std::mem::take(&mut runtime.anonymous_components);
+
+    let new_file_id = program_archive.get_function_data(id).get_file_id();
+    let previous_id = std::mem::replace(&mut runtime.current_file, new_file_id);
+
+    let call = (meta.clone(), format!("{} is called here", id));
+    runtime.call_trace.push(TraceCall { id: id.to_string(), call: Option::Some(call) });
+    let is_outermost = runtime.function_steps.is_none();
+    if is_outermost {
+        runtime.function_steps = Some((id.to_string(), 0));
+    }
+    let folded_result = execute_function_call(id, program_archive, runtime, flags)?;
+    if is_outermost {
+        runtime.function_steps = None;
     }
+
+    runtime.environment = previous_environment;
+    runtime.current_file = previous_id;
+    runtime.block_type = previous_block_type;
+    runtime.anonymous_components = previous_anonymous_components;
+    runtime.call_trace.pop();
+    Ok(folded_result)
+}
+
+// The evaluation of a function by the compiler stops after the number of
+// loop iterations and calls given by --function_steps, as it may not end
+fn count_function_step(meta: &Meta, runtime: &mut RuntimeInformation, flags: FlagsExecution) -> Result<(), ()> {
+    if let Some((function, steps)) = &mut runtime.function_steps {
+        *steps += 1;
+        if *steps > flags.function_steps {
+            let err = Result::Err(ExecutionError::FunctionStepsExceeded(function.clone(), flags.function_steps));
+            treat_result_with_execution_error(err, meta, &mut runtime.runtime_errors, &runtime.call_trace)?;
+        }
+    }
+    Ok(())
+}
+
+// The tags that both operands have and that have a rule for the operator,
+// with the values given by the rules
+fn execute_tag_rules(
+    meta: &Meta,
+    infix_op: ExpressionInfixOpcode,
+    l_tags: Option<TagWire>,
+    r_tags: Option<TagWire>,
+    program_archive: &ProgramArchive,
+    runtime: &mut RuntimeInformation,
+    flags: FlagsExecution,
+) -> Result<Option<TagWire>, ()> {
+    let (l_tags, r_tags) = match (l_tags, r_tags) {
+        (Some(l_tags), Some(r_tags)) => (l_tags.tags, r_tags.tags),
+        _ => return Ok(None),
+    };
+    let mut tags = TagInfo::new();
+    for (tag, l_value) in l_tags {
+        let (r_value, rule) = match (r_tags.get(&tag), program_archive.get_tag_rule(&tag, infix_op)) {
+            (Some(r_value), Some(rule)) => (r_value, rule),
+            _ => continue,
+        };
+        let value = match (&rule.function, l_value, r_value) {
+            (None, ..) => None,
+            (Some(function), Some(l_value), Some(r_value)) => {
+                let arg_values = vec![l_value, r_value.clone()]
+                    .into_iter()
+                    .map(|value| AExpressionSlice::new(&AExpr::Number { value }))
+                    .collect::<Vec<_>>();
+                let (result, _) = execute_function_with_values(function, meta, &arg_values, program_archive, runtime, flags)?;
+                match safe_unwrap_to_single_arithmetic_expression(result, line!()) {
+                    AExpr::Number { value } => Some(value),
+                    _ => continue,
+                }
+            }
+            // the tag is dropped when the value of an operand is unknown
+            _ => continue,
+        };
+        tags.insert(tag, value);
+    }
+    if tags.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(TagWire { tags, fields: None }))
+    }
+}
+
+// A template can instantiate itself, directly or through other templates,
+// with any parameters as long as the recursion ends, which is checked by
+// limiting its instances inside itself to the ones given by --template_recursion.
+// The error gives the parameters of the instance that goes over the limit, to
+// show the ones that do not get closer to the end of the recursion
+fn check_template_recursion(
+    id: &str,
+    parameter_values: &[AExpressionSlice],
+    program_archive: &ProgramArchive,
+    runtime: &RuntimeInformation,
+    flags: FlagsExecution,
+) -> Result<(), ExecutionError> {
+    let instances = runtime.template_calls.iter().filter(|(template, _)| template == id).count();
+    if instances <= flags.template_recursion {
+        return Ok(());
+    }
+    let names = program_archive.get_template_data(id).get_name_of_params();
+    let parameters: Vec<_> = names
+        .iter()
+        .zip(parameter_values)
+        .map(|(name, value)| {
+            let numbers: Vec<_> = MemorySlice::get_reference_values(value).iter().map(|e| e.to_string()).collect();
+            match numbers.as_slice() {
+                [number] => format!("{} = {}", name, number),
+                _ => format!("{} = [{}]", name, numbers.join(", ")),
+            }
+        })
+        .collect();
+    let parameters = if parameters.is_empty() { "no parameters".to_string() } else { parameters.join(", ") };
+    Err(ExecutionError::RecursionTooDeep(id.to_string(), flags.template_recursion, parameters))
 }
 
 fn execute_template_call_complete(
     id: &String,
     arg_values: Vec<AExpressionSlice>,
     tags: HashMap<String, TagWire>,
+    call: Option<Meta>,
     program_archive: &ProgramArchive,
     runtime: &mut RuntimeInformation,
     flags: FlagsExecution,
@@ -1159,8 +1523,13 @@ fn execute_template_call_complete(
         let new_file_id = program_archive.get_template_data(id).get_file_id();
         let previous_id = std::mem::replace(&mut runtime.current_file, new_file_id);
 
-        runtime.call_trace.push(id.clone());
+        let parameters: Vec<_> = arg_values.iter().map(|value| value.to_string()).collect();
+        let label = format!("{}({}) is instantiated here", id, parameters.join(","));
+        let call = call.map(|meta| (meta, label));
+        runtime.call_trace.push(TraceCall { id: id.clone(), call });
+        runtime.template_calls.push((id.clone(), arg_values.clone()));
         let folded_result = execute_template_call(id, arg_values, tags, program_archive, runtime, flags)?;
+        runtime.template_calls.pop();
 
         runtime.environment = previous_environment;
         runtime.current_file = previous_id;
@@ -1200,6 +1569,7 @@ fn execute_anonymous_component_declaration(
 
 fn execute_bus_call_complete(
     id: &String,
+    meta: &Meta,
     args: &Vec<Expression>,
     program_archive: &ProgramArchive,
     runtime: &mut RuntimeInformation,
@@ -1221,7 +1591,8 @@ fn execute_bus_call_complete(
         let new_file_id = program_archive.get_bus_data(id).get_file_id();
         let previous_id = std::mem::replace(&mut runtime.current_file, new_file_id);
 
-        runtime.call_trace.push(id.clone());
+        let call = (meta.clone(), format!("{} is instantiated here", id));
+        runtime.call_trace.push(TraceCall { id: id.clone(), call: Option::Some(call) });
         let folded_result = execute_bus_call(id, arg_values, program_archive, runtime, flags)?;
 
         runtime.environment = previous_environment;
@@ -1643,6 +2014,7 @@ fn perform_assign(
                     pretemplate_info.template_name(),
                     pretemplate_info.parameter_instances().clone(),
                     inputs_tags,
+                    pretemplate_info.call().clone(),
                     program_archive,
                     runtime,
                     flags,
@@ -1693,6 +2065,7 @@ fn perform_assign(
                             is_parallel: component.is_parallel,
                             goes_to: node_pointer,
                             indexed_with: accessing_information.array_access.clone(),
+                            meta: component.meta.clone(),
                         };
                         node.add_arrow(full_symbol.clone(), data);
                     },
@@ -1876,6 +2249,7 @@ fn perform_assign(
                         pretemplate_info.template_name(),
                         pretemplate_info.parameter_instances().clone(),
                         inputs_tags,
+                        pretemplate_info.call().clone(),
                         program_archive,
                         runtime,
                         flags,
@@ -1926,6 +2300,7 @@ fn perform_assign(
                                 goes_to: node_pointer,
                                 is_parallel: component.is_parallel,
                                 indexed_with: accessing_information.array_access.clone(),
+                                meta: component.meta.clone(),
                             };
                             let component_symbol = create_component_symbol(symbol, &accessing_information.array_access);
                             node.add_arrow(component_symbol, data);
@@ -2228,6 +2603,16 @@ fn perform_assign(
             }
             if accessing_information.field_access.is_none(){
 
+                // We assign the original buses, which fails before the tags
+                // are propagated when some of their fields are already assigned
+                let bus_assignment_response = perform_bus_assignment(bus_slice, &accessing_information.array_access, assigned_bus_slice, false, &conditions_assignment);
+                treat_result_with_memory_error_void(
+                    bus_assignment_response,
+                    meta,
+                    &mut runtime.runtime_errors,
+                    &runtime.call_trace,
+                )?;
+
                 // Perform the tag propagation
                 // access to the field that is assigned and then propagate the tags
                 let new_tags = r_folded.tags.unwrap();
@@ -2240,15 +2625,6 @@ fn perform_assign(
                 }
                 perform_tag_propagation_bus(tag_data, &new_tags, MemorySlice::get_number_of_cells(&bus_slice));
 
-                // We assign the original buses
-                let bus_assignment_response = perform_bus_assignment(bus_slice, &accessing_information.array_access, assigned_bus_slice, false, &conditions_assignment);
-                treat_result_with_memory_error_void(
-                    bus_assignment_response,
-                    meta,
-                    &mut runtime.runtime_errors,
-                    &runtime.call_trace,
-                )?;
-
                 // Generate an arithmetic slice for the accessed buses
                 let mut signals_values_left: Vec<String> = Vec::new();
                 let mut signals_values_right = Vec::new();
@@ -2433,7 +2809,7 @@ fn execute_conditional_statement(
     actual_node: &mut Option<ExecutedTemplate>,
     flags: FlagsExecution,
 ) -> Result<(Option<FoldedValue>, bool, Option<bool>), ()> {
-    let f_cond = execute_expression(condition, program_archive, runtime, flags)?;
+    let f_cond = execute_witness_expression(condition.get_meta(), condition, program_archive, runtime, actual_node, flags)?;
     let ae_cond = safe_unwrap_to_single_arithmetic_expression(f_cond, line!());
     let possible_cond_bool_value =
         AExpr::get_boolean_equivalence(&ae_cond, runtime.constants.get_p());
@@ -2740,6 +3116,22 @@ fn execute_signal(
             &runtime.call_trace,
         )?;
         let full_symbol = create_symbol(symbol, &access_information);
+        // with --inspect, the signals that are only assigned when conditions
+        // unknown during the constraint generation hold, and are read before
+        // any assignment in the rest of the paths, at their first read
+        if flags.inspect {
+            let assignment = SignalSlice::get_reference_values(&signal_slice).iter().find_map(|state| match state {
+                AssignmentState::MightAssigned(_, assignment) => Option::Some(assignment.clone()),
+                _ => Option::None,
+            });
+            if let Option::Some(assignment) = assignment {
+                let place = assignment.as_ref().unwrap_or(meta);
+                if runtime.conditional_reads.insert((place.get_file_id(), place.start)) {
+                    let warning: Result<(), ExecutionWarning> = Result::Err(ExecutionWarning::ConditionallyAssignedRead(full_symbol.clone(), assignment.clone()));
+                    treat_result_with_execution_warning(warning, meta, &mut runtime.runtime_errors, &runtime.call_trace)?;
+                }
+            }
+        }
         let signal_access = signal_to_arith(full_symbol, signal_slice);
         let arith_slice = treat_result_with_memory_error(
             signal_access,
@@ -2785,7 +3177,6 @@ fn signal_to_arith(symbol: String, slice: SignalSlice) -> Result<AExpressionSlic
     } else {
         Result::Err(MemoryError::InvalidAccess(TypeInvalidAccess::NoInitializedSignal))
     }
-    // TODO: in case inspect return warning in case might assigned?
 }
 
 fn unfold_signals(current: String, dim: usize, lengths: &[usize], result: &mut Vec<String>) {
@@ -2987,6 +3378,28 @@ fn execute_bus(
 
 }
 
+// Evaluates an expression whose value may only be known in the witness
+// generation, recording in the template the outputs of subcomponents that it
+// reads
+fn execute_witness_expression(
+    meta: &Meta,
+    expr: &Expression,
+    program_archive: &ProgramArchive,
+    runtime: &mut RuntimeInformation,
+    actual_node: &mut Option<ExecutedTemplate>,
+    flags: FlagsExecution
+) -> Result<FoldedValue, ()> {
+    let previous_reads = runtime.witness_reads.replace(Vec::new());
+    let result = execute_expression(expr, program_archive, runtime, flags);
+    let reads = std::mem::replace(&mut runtime.witness_reads, previous_reads).unwrap();
+    if let Option::Some(node) = actual_node.as_mut() {
+        for signal in reads {
+            node.add_read_output(&signal, meta);
+        }
+    }
+    result
+}
+
 fn execute_component(
     meta: &Meta,
     symbol: &str,
@@ -3050,7 +3463,17 @@ fn execute_component(
             
             match result{
                 FoldedResult::Signal(signals) =>{
-                    let result = signal_to_arith(symbol, signals)
+                    let result = signal_to_arith(symbol, signals);
+                    if let (Option::Some(reads), Result::Ok(slice)) = (runtime.witness_reads.as_mut(), &result) {
+                        if resulting_component.is_output(signal_name) {
+                            for i in 0..AExpressionSlice::get_number_of_cells(slice) {
+                                if let Result::Ok(AExpr::Signal { symbol }) = AExpressionSlice::access_value_by_index(slice, i) {
+                                    reads.push(symbol);
+                                }
+                            }
+                        }
+                    }
+                    let result = result
                         .map(|s| FoldedValue { 
                             arithmetic_slice: Option::Some(s),
                             tags: Option::Some(tags),
@@ -3150,6 +3573,11 @@ fn execute_function_call(
     flags: FlagsExecution
 ) -> Result<(FoldedValue, bool), ()> {
     use std::mem;
+    // the value of an extern function is only known at witness time
+    if program_archive.get_function_data(id).is_extern() {
+        let arithmetic_slice = Option::Some(AExpressionSlice::new(&AExpr::NonQuadratic));
+        return Result::Ok((FoldedValue { arithmetic_slice, ..FoldedValue::default() }, false));
+    }
     let previous_block = runtime.block_type;
     let previous_conditions = mem::replace(&mut runtime.conditions_state, vec![]);
     runtime.block_type = BlockType::Known;
@@ -3247,6 +3675,56 @@ fn execute_template_call(
         }
         let mut new_node = node_wrap.unwrap();
 
+        // the signals of the hint blocks must be constrained by the template
+        let mut unconstrained_hints = false;
+        for (signal, meta) in new_node.unconstrained_hinted_signals() {
+            let err = Result::Err(ExecutionError::UnconstrainedHint(signal.clone()));
+            let result: Result<(), ()> =
+                treat_result_with_execution_error(err, meta, &mut runtime.runtime_errors, &runtime.call_trace);
+            unconstrained_hints |= result.is_err();
+        }
+        if unconstrained_hints {
+            return Result::Err(());
+        }
+
+        // the signals of <-- that appear in no constraint of the template,
+        // which stop the compilation with --deny_unconstrained
+        if (flags.inspect || flags.deny_unconstrained) && !new_node.is_custom_gate {
+            let mut unconstrained_assignments = false;
+            for (signal, meta) in new_node.unconstrained_witness_signals() {
+                let template = new_node.template_name().clone();
+                if flags.deny_unconstrained {
+                    let err = Result::Err(ExecutionError::UnconstrainedAssignment(signal.clone(), template));
+                    let result: Result<(), ()> =
+                        treat_result_with_execution_error(err, meta, &mut runtime.runtime_errors, &runtime.call_trace);
+                    unconstrained_assignments |= result.is_err();
+                } else {
+                    let warning: Result<(), ExecutionWarning> = Result::Err(ExecutionWarning::UnconstrainedAssignment(signal.clone(), template));
+                    treat_result_with_execution_warning(warning, meta, &mut runtime.runtime_errors, &runtime.call_trace)?;
+                }
+            }
+            if unconstrained_assignments {
+                return Result::Err(());
+            }
+        }
+
+        // with --inspect=2, the signals of <-- that the constraints of the
+        // template do not determine
+        if flags.inspect_witness && !new_node.is_custom_gate {
+            for (signal, meta) in new_node.undetermined_witness_signals(runtime.constants.get_p()) {
+                let warning: Result<(), ExecutionWarning> = Result::Err(ExecutionWarning::UndeterminedSignal(signal.clone(), new_node.template_name().clone()));
+                treat_result_with_execution_warning(warning, meta, &mut runtime.runtime_errors, &runtime.call_trace)?;
+            }
+        }
+
+
+        // the outputs of subcomponents that only take part in the witness
+        if !new_node.is_custom_gate {
+            for (signal, meta) in new_node.unconstrained_read_outputs() {
+                let warning: Result<(), ExecutionWarning> = Result::Err(ExecutionWarning::UnconstrainedReadOutput(signal.clone(), new_node.template_name().clone()));
+                treat_result_with_execution_warning(warning, meta, &mut runtime.runtime_errors, &runtime.call_trace)?;
+            }
+        }
 
         // we add the tags to the executed template
         // TODO: improve and remove clone
@@ -3272,6 +3750,7 @@ fn execute_template_call(
 
 fn preexecute_template_call(
     id: &str,
+    meta: &Meta,
     parameter_values: &[AExpressionSlice],
     program_archive: &ProgramArchive,
     runtime: &mut RuntimeInformation,
@@ -3360,6 +3839,7 @@ fn preexecute_template_call(
         parameter_values.to_vec(),
         inputs_to_tags,
         outputs_to_tags,
+        Option::Some(meta.clone()),
     ));
 
     let new_node = node_wrap.unwrap();
@@ -3704,7 +4184,7 @@ fn treat_result_with_arithmetic_error<C>(
     arithmetic_error: Result<C, ArithmeticError>,
     meta: &Meta,
     runtime_errors: &mut ReportCollection,
-    call_trace: &[String],
+    call_trace: &[TraceCall],
 ) -> Result<C, ()> {
     use ReportCode::RuntimeError;
     match arithmetic_error {
@@ -3728,7 +4208,7 @@ fn treat_result_with_memory_error_void(
     memory_error: Result<(), MemoryError>,
     meta: &Meta,
     runtime_errors: &mut ReportCollection,
-    call_trace: &[String],
+    call_trace: &[TraceCall],
 ) -> Result<(), ()> {
     use ReportCode::RuntimeError;
     match memory_error {
@@ -3788,10 +4268,14 @@ fn treat_result_with_memory_error_void(
                                 format!("Exception caused by invalid assignment\n The component has been assigned previously"),
                                 RuntimeError)
                         },
-                        TypeAssignmentError::MultipleAssignmentsBus =>{
-                            Report::error(
+                        TypeAssignmentError::MultipleAssignmentsBus(meta) =>{
+                            let mut rep = Report::error(
                                 format!("Exception caused by invalid assignment\n Bus contains fields that have been previously initialized"),
-                                RuntimeError)
+                                RuntimeError);
+                            if let Option::Some(meta) = meta {
+                                rep.add_secondary(meta.file_location(), meta.get_file_id(), Option::Some("This is the previous assignment to the bus".to_string()));
+                            }
+                            rep
                         },
                         TypeAssignmentError::MultipleAssignments(meta) =>{
                             let mut rep = Report::error(
@@ -3880,7 +4364,7 @@ pub fn treat_result_with_memory_error<C>(
     memory_error: Result<C, MemoryError>,
     meta: &Meta,
     runtime_errors: &mut ReportCollection,
-    call_trace: &[String],
+    call_trace: &[TraceCall],
 ) -> Result<C, ()> {
     use ReportCode::RuntimeError;
     match memory_error {
@@ -3922,10 +4406,14 @@ pub fn treat_result_with_memory_error<C>(
                                 format!("Exception caused by invalid assignment\n The component has been assigned previously"),
                                 RuntimeError)
                         },
-                        TypeAssignmentError::MultipleAssignmentsBus =>{
-                            Report::error(
+                        TypeAssignmentError::MultipleAssignmentsBus(meta) =>{
+                            let mut rep = Report::error(
                                 format!("Exception caused by invalid assignment\n Bus contains fields that have been previously initialized"),
-                                RuntimeError)
+                                RuntimeError);
+                            if let Option::Some(meta) = meta {
+                                rep.add_secondary(meta.file_location(), meta.get_file_id(), Option::Some("This is the previous assignment to the bus".to_string()));
+                            }
+                            rep
                         },
                         TypeAssignmentError::MultipleAssignments(meta) =>{
                             let mut rep = Report::error(
@@ -4015,7 +4503,7 @@ fn treat_result_with_environment_error<C>(
     environment_error: Result<C, ExecutionEnvironmentError>,
     meta: &Meta,
     runtime_errors: &mut ReportCollection,
-    call_trace: &[String],
+    call_trace: &[TraceCall],
 ) -> Result<C, ()> {
     use ReportCode::*;
     match environment_error {
@@ -4036,7 +4524,7 @@ fn treat_result_with_execution_error<C>(
     execution_error: Result<C, ExecutionError>,
     meta: &Meta,
     runtime_errors: &mut ReportCollection,
-    call_trace: &[String],
+    call_trace: &[TraceCall],
 ) -> Result<C, ()> {
     use ExecutionError::*;
     match execution_error {
@@ -4055,13 +4543,49 @@ fn treat_result_with_execution_error<C>(
                     "Tags cannot be assigned to values that can be unknown during the constraint generation phase".to_string(),
                     ReportCode::RuntimeError,
                 ),
-                FalseAssert => {
+                FalseAssert(message) if message.is_empty() => {
                     Report::error("False assert reached".to_string(), ReportCode::RuntimeError)
                 }
+                FalseAssert(message) => {
+                    Report::error(format!("False assert reached: {}", message), ReportCode::RuntimeError)
+                }
+                FalseStaticAssert(message) if message.is_empty() => {
+                    Report::error("False static assert reached".to_string(), ReportCode::RuntimeError)
+                }
+                FalseStaticAssert(message) => {
+                    Report::error(format!("False static assert reached: {}", message), ReportCode::RuntimeError)
+                }
+                UnknownStaticAssert => Report::error(
+                    "The condition of a static assert must be known during the constraint generation phase".to_string(),
+                    ReportCode::RuntimeError,
+                ),
                 ArraySizeTooBig => Report::error(
                     "The size of the array is expected to be a usize".to_string(),
                     ReportCode::RuntimeError,
                 ),
+                RecursionTooDeep(template, instances, parameters) => Report::error(
+                    format!(
+                        "The template {} is instantiated inside itself more than {} times, the limit given by --template_recursion, the last time with {}",
+                        template, instances, parameters
+                    ),
+                    ReportCode::RuntimeError,
+                ),
+                FunctionStepsExceeded(function, steps) => Report::error(
+                    format!("The evaluation of the function {} does not end after {} loop iterations and calls, the limit given by --function_steps", function, steps),
+                    ReportCode::RuntimeError,
+                ),
+                UnconstrainedHint(signal) => Report::error(
+                    format!("The signal {} is assigned in a hint block or by an extern function but it does not appear in any constraint of the template", signal),
+                    ReportCode::RuntimeError,
+                ),
+                UnconstrainedAssignment(signal, template) => Report::error(
+                    format!("In template \"{}\": the signal {} is assigned with <-- but it does not appear in any constraint of the template", template, signal),
+                    ReportCode::UnconstrainedAssignment,
+                ),
+                InvalidSlice => Report::error(
+                    "The bounds of a slice must be known during the constraint generation phase, with the start not greater than the end and the end not greater than the length of the array".to_string(),
+                    ReportCode::RuntimeError,
+                ),
                 ConstraintInUnknown => Report::error(
                     "There are constraints depending on the value of a condition that can be unknown during the constraint generation phase".to_string(),
                     ReportCode::RuntimeError,
@@ -4085,7 +4609,7 @@ fn treat_result_with_execution_warning<C>(
     execution_error: Result<C, ExecutionWarning>,
     meta: &Meta,
     runtime_errors: &mut ReportCollection,
-    call_trace: &[String],
+    call_trace: &[TraceCall],
 ) -> Result<(), ()> {
     use ExecutionWarning::*;
     match execution_error {
@@ -4116,8 +4640,40 @@ fn treat_result_with_execution_warning<C>(
                         ReportCode::RuntimeWarning,
                     )
                 }
-
-
+                UndeterminedSignal(signal, template) => {
+                    let msg = format!(
+                        "In template \"{}\": the signal {} is assigned with <-- and no constraint of the template determines its value, so a prover can give it other values",
+                        template, signal
+                    );
+                    Report::warning(msg, ReportCode::UndeterminedSignal)
+                }
+                UnconstrainedReadOutput(signal, template) => {
+                    let msg = format!(
+                        "In template \"{}\": the output {} of a subcomponent is used to compute the witness but does not appear in any constraint of the template, so its value does not constrain the signals computed from it",
+                        template, signal
+                    );
+                    Report::warning(msg, ReportCode::UnconstrainedReadOutput)
+                }
+                UnconstrainedAssignment(signal, template) => {
+                    let msg = format!(
+                        "In template \"{}\": the signal {} is assigned with <-- but it does not appear in any constraint of the template, so a prover can give it any value",
+                        template, signal
+                    );
+                    let mut report = Report::warning(msg, ReportCode::UnconstrainedAssignment);
+                    report.add_note("Add a constraint that checks the value, or use <== if the expression is quadratic.".to_string());
+                    report
+                }
+                ConditionallyAssignedRead(signal, assignment) => {
+                    let msg = format!(
+                        "The signal {} is read here, but it is only assigned when a condition that is unknown during the constraint generation holds, so the witness generation reads it before any assignment when the condition does not hold",
+                        signal
+                    );
+                    let mut report = Report::warning(msg, ReportCode::ConditionallyAssignedRead);
+                    if let Option::Some(assignment) = assignment {
+                        report.add_secondary(assignment.file_location(), assignment.get_file_id(), Option::Some("only assigned here".to_string()));
+                    }
+                    report
+                }
             };
             add_report_to_runtime(report, meta, runtime_errors, call_trace);
             Result::Ok(())
@@ -4125,11 +4681,51 @@ fn treat_result_with_execution_warning<C>(
     }
 }
 
+// The assignment with <-- whose expression can be a constraint is written
+// with <==, and the one with --> with ==>
+fn suggest_constraint_assignment(report: &mut Report, meta: &Meta, program_archive: &ProgramArchive) {
+    let file_id = meta.get_file_id();
+    let code = program_archive.get_file_library().get_source(file_id).and_then(|source| source.get(meta.location.clone()));
+    let Some(code) = code else {
+        return;
+    };
+    for (operator, replacement) in [("<--", "<=="), ("-->", "==>")] {
+        if let Some(offset) = code.find(operator) {
+            let start = meta.location.start + offset;
+            let message = format!("use {} to also add the constraint", replacement);
+            report.add_suggestion(start..start + operator.len(), file_id, operator.to_string(), replacement.to_string(), message);
+            return;
+        }
+    }
+}
+
+// Labels the declaration of the assigned name in the error of the assignment,
+// when the error is reported at it
+fn add_declaration_label(meta: &Meta, var: &str, program_archive: &ProgramArchive, runtime: &mut RuntimeInformation) {
+    let in_template = match (runtime.template_calls.last(), runtime.call_trace.last()) {
+        (Option::Some((template, _)), Option::Some(call)) => template == &call.id,
+        _ => false,
+    };
+    if !in_template {
+        return;
+    }
+    let (template, _) = runtime.template_calls.last().unwrap();
+    let body = program_archive.get_template_data(template).get_body();
+    let (Option::Some(report), Option::Some(declaration)) = (runtime.runtime_errors.last_mut(), body.find_declaration(var)) else {
+        return;
+    };
+    let declaration = declaration.get_meta();
+    let at_assignment = report.get_primary().iter().any(|label| label.file_id == meta.get_file_id() && label.range == meta.location);
+    if at_assignment && declaration.location != meta.location {
+        report.add_secondary(declaration.location.clone(), declaration.get_file_id(), Option::Some(format!("{} is declared here", var)));
+    }
+}
+
 fn add_report_to_runtime(
     report: Report,
     meta: &Meta,
     runtime_errors: &mut ReportCollection,
-    call_trace: &[String],
+    call_trace: &[TraceCall],
 ) {
     let mut report = report;
     report.add_primary(meta.location.clone(), meta.get_file_id(), "found here".to_string());
@@ -4137,11 +4733,15 @@ fn add_report_to_runtime(
     let mut trace = "call trace:\n".to_string();
     let mut spacing = "".to_string();
     for call in call_trace.iter() {
-        let msg = format!("{}->{}\n", spacing, call);
+        let msg = format!("{}->{}\n", spacing, call.id);
         trace.push_str(msg.as_str());
         spacing.push_str(" ");
     }
     report.add_note(trace);
+    // the calls that lead to the code, from the innermost one
+    for (meta, label) in call_trace.iter().rev().filter_map(|call| call.call.as_ref()) {
+        report.add_secondary(meta.location.clone(), meta.get_file_id(), Option::Some(label.clone()));
+    }
     runtime_errors.push(report);
 }
 
@@ -199,9 +199,15 @@ impl ExecutedProgram {
 
         temp_instances[dag.main_id()].is_not_parallel_component = true;
         dag.clean_constraints();
+        let mut satisfied = dag.satisfiability_analysis()?;
         if flags.inspect{
             let mut w = dag.constraint_analysis()?;
             warnings.append(&mut w);
+            warnings.append(&mut satisfied);
+            warnings.append(&mut dag.range_analysis());
+        }
+        if flags.inspect_determinism {
+            warnings.append(&mut dag.determinism_analysis());
         }
 
         let dag_stats = produce_dags_stats(&dag);
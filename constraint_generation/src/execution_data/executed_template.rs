@@ -3,9 +3,10 @@ use super::type_definitions::*;
 use super::ExecutedBus;
 use circom_algebra::algebra::ArithmeticExpression;
 use compiler::hir::very_concrete_program::*;
-use dag::DAG;
+use dag::{ConstraintOrigin, DAG};
 use num_bigint::BigInt;
-use program_structure::ast::{SignalType, Statement};
+use program_structure::ast::{Meta, SignalType, Statement};
+use program_structure::ast_shortcuts::{BINARY, MAXBIT};
 use std::collections::{HashMap, HashSet};
 use crate::execution_data::AExpressionSlice;
 
@@ -25,6 +26,8 @@ pub struct PreExecutedTemplate {
     pub parameter_instances: Vec<AExpressionSlice>,
     pub inputs: HashMap<String, TagNames>,
     pub outputs: HashMap<String, TagNames>,
+    // the call that creates the component
+    pub call: Option<Meta>,
 } 
 
 impl PreExecutedTemplate {
@@ -33,12 +36,14 @@ impl PreExecutedTemplate {
         instance: Vec<AExpressionSlice>,
         inputs: HashMap<String, TagNames>,
         outputs: HashMap<String, TagNames>,
+        call: Option<Meta>,
     ) -> PreExecutedTemplate {
         PreExecutedTemplate {
             template_name: name,
             parameter_instances: instance,
             inputs,
             outputs,
+            call,
         }
     }
 
@@ -57,6 +62,10 @@ impl PreExecutedTemplate {
     pub fn outputs(&self) -> &HashMap<String, TagNames> {
         &self.outputs
     }
+
+    pub fn call(&self) -> &Option<Meta> {
+        &self.call
+    }
 }
 
 
@@ -70,6 +79,8 @@ pub struct ExecutedTemplate {
     pub intermediates: WireCollector,
     pub ordered_signals: WireCollector,
     pub constraints: Vec<Constraint>,
+    // the statements that produced the constraints
+    pub constraint_origins: Vec<ConstraintOrigin>,
     pub components: ComponentCollector,
     pub number_of_components: usize,
     pub public_inputs: HashSet<String>,
@@ -82,6 +93,13 @@ pub struct ExecutedTemplate {
     pub has_parallel_sub_cmp: bool,
     pub is_custom_gate: bool,
     pub underscored_signals: Vec<String>,
+    // the signals assigned inside hint blocks, with their assignments
+    pub hinted_signals: Vec<(String, Meta)>,
+    // the signals assigned with <--, with their assignments
+    pub witness_signals: Vec<(String, Meta)>,
+    // the outputs of subcomponents read in the computation of the witness,
+    // with the statements that read them
+    pub read_outputs: Vec<(String, Meta)>,
     connexions: Vec<Connexion>,
     pub bus_connexions: HashMap<String, BusConnexion>,
 }
@@ -116,11 +134,15 @@ impl ExecutedTemplate {
             intermediates: WireCollector::new(),
             ordered_signals: WireCollector::new(),
             constraints: Vec::new(),
+            constraint_origins: Vec::new(),
             components: ComponentCollector::new(),
             number_of_components: 0,
             connexions: Vec::new(),
             bus_connexions: HashMap::new(),
             underscored_signals: Vec::new(),
+            hinted_signals: Vec::new(),
+            witness_signals: Vec::new(),
+            read_outputs: Vec::new(),
         }
     }
 
@@ -203,14 +225,69 @@ impl ExecutedTemplate {
         self.number_of_components += dimensions.iter().fold(1, |p, c| p * (*c));
     }
 
-    pub fn add_constraint(&mut self, constraint: Constraint) {
+    pub fn add_constraint(&mut self, constraint: Constraint, meta: &Meta) {
         self.constraints.push(constraint);
+        self.constraint_origins.push(meta.file_id.map(|file_id| (file_id, meta.location.clone())));
     }
 
     pub fn add_underscored_signal(&mut self, signal: &str) {
         self.underscored_signals.push(signal.to_string());
     }
 
+    pub fn add_hinted_signal(&mut self, signal: &str, meta: &Meta) {
+        self.hinted_signals.push((signal.to_string(), meta.clone()));
+    }
+
+    // The hinted signals that appear in no constraint of the template
+    pub fn unconstrained_hinted_signals(&self) -> Vec<&(String, Meta)> {
+        let constrained = self.constrained_signals();
+        self.hinted_signals.iter().filter(|(signal, _)| !constrained.contains(signal)).collect()
+    }
+
+    pub fn add_witness_signal(&mut self, signal: &str, meta: &Meta) {
+        self.witness_signals.push((signal.to_string(), meta.clone()));
+    }
+
+    // The signals assigned with <-- that appear in no constraint of the
+    // template
+    pub fn unconstrained_witness_signals(&self) -> Vec<&(String, Meta)> {
+        let constrained = self.constrained_signals();
+        self.witness_signals.iter().filter(|(signal, _)| !constrained.contains(signal)).collect()
+    }
+
+    // The signals assigned with <-- whose values are not determined by the
+    // constraints of the template, without the ones that appear in none
+    pub fn undetermined_witness_signals(&self, field: &BigInt) -> Vec<&(String, Meta)> {
+        let constrained = self.constrained_signals();
+        let signals: HashSet<String> = self.witness_signals.iter().map(|(signal, _)| signal.clone()).collect();
+        let undetermined = crate::undetermined_signals(&self.constraints, &signals, field);
+        self.witness_signals
+            .iter()
+            .filter(|(signal, _)| undetermined.contains(signal) && constrained.contains(signal))
+            .collect()
+    }
+
+    // The signals that appear in some constraint of the template, or that
+    // are assigned to _
+    fn constrained_signals(&self) -> HashSet<String> {
+        let mut constrained: HashSet<String> = self.underscored_signals.iter().cloned().collect();
+        for constraint in &self.constraints {
+            constrained.extend(constraint.take_cloned_signals());
+        }
+        constrained
+    }
+
+    pub fn add_read_output(&mut self, signal: &str, meta: &Meta) {
+        self.read_outputs.push((signal.to_string(), meta.clone()));
+    }
+
+    // The outputs of subcomponents read in the computation of the witness
+    // that appear in no constraint of the template, with their first reads
+    pub fn unconstrained_read_outputs(&self) -> Vec<&(String, Meta)> {
+        let mut constrained = self.constrained_signals();
+        self.read_outputs.iter().filter(|(signal, _)| constrained.insert(signal.clone())).collect()
+    }
+
     pub fn template_name(&self) -> &String {
         &self.template_name
     }
@@ -257,6 +334,7 @@ impl ExecutedTemplate {
         self.build_ordered_signals(dag, buses_info);
         self.build_connexions(dag);
         self.build_constraints(dag);
+        self.build_tag_bounds(dag);
     }
 
     fn build_wires(&self, dag: &mut DAG, buses_info : &Vec<ExecutedBus>) {
@@ -280,8 +358,28 @@ impl ExecutedTemplate {
                 }
             }
         }
+        // the public fields of the input buses go with the public inputs, so
+        // the signals of these buses are not in the order of their memory
+        let has_public_fields = |wire_data: &&WireData| {
+            wire_data.is_bus && !self.public_inputs.contains(&wire_data.name) && self.has_public_fields(&wire_data.name)
+        };
+        for wire_data in self.inputs().iter().filter(has_public_fields) {
+            let state = State { basic_name: wire_data.name.clone(), name: wire_data.name.clone(), dim: 0 };
+            let mut names = Vec::new();
+            generate_names(state, &wire_data.length, true, &self.bus_connexions, buses_info, &mut names);
+            for name in names.into_iter().filter(|name| self.is_public_field(name)) {
+                dag.add_input(name, true);
+            }
+        }
         for wire_data in self.inputs() {
-            if !self.public_inputs.contains(&wire_data.name) {
+            if has_public_fields(&wire_data) {
+                let state = State { basic_name: wire_data.name.clone(), name: wire_data.name.clone(), dim: 0 };
+                let mut names = Vec::new();
+                generate_names(state, &wire_data.length, true, &self.bus_connexions, buses_info, &mut names);
+                for name in names.into_iter().filter(|name| !self.is_public_field(name)) {
+                    dag.add_input(name, false);
+                }
+            } else if !self.public_inputs.contains(&wire_data.name) {
                 let state = State { basic_name: wire_data.name.clone(), name: wire_data.name.clone(), dim: 0 };
                 let config = SignalConfig { signal_type: 0, dimensions: &wire_data.length, is_public: false };
                 if wire_data.is_bus{
@@ -291,6 +389,16 @@ impl ExecutedTemplate {
                 }
             }
         }
+        if self.inputs().iter().any(|wire_data| has_public_fields(&wire_data)) {
+            let (public, not_public): (Vec<_>, Vec<_>) =
+                self.inputs().iter().partition(|wire_data| self.public_inputs.contains(&wire_data.name));
+            let mut memory = Vec::new();
+            for wire_data in public.into_iter().chain(not_public) {
+                let state = State { basic_name: wire_data.name.clone(), name: wire_data.name.clone(), dim: 0 };
+                generate_names(state, &wire_data.length, wire_data.is_bus, &self.bus_connexions, buses_info, &mut memory);
+            }
+            dag.set_inputs_memory(&memory);
+        }
         for wire_data in self.intermediates() {
             let state = State { basic_name: wire_data.name.clone(), name: wire_data.name.clone(), dim: 0 };
             let config = SignalConfig { signal_type: 2, dimensions: &wire_data.length, is_public: false };
@@ -302,6 +410,27 @@ impl ExecutedTemplate {
         }
     }
 
+    // Whether a field of an input bus of the main component is public, as
+    // it is in a path of the public list, as tx.nullifier, where the path of
+    // the fields of the arrays of buses has no indexes
+    fn is_public_field(&self, name: &str) -> bool {
+        let mut path = String::new();
+        let mut depth = 0;
+        for c in name.chars() {
+            match c {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                _ if depth == 0 => path.push(c),
+                _ => {}
+            }
+        }
+        self.public_inputs.iter().any(|public| path == *public || path.starts_with(&format!("{}.", public)))
+    }
+
+    fn has_public_fields(&self, name: &str) -> bool {
+        self.public_inputs.iter().any(|public| public.starts_with(&format!("{}.", name)))
+    }
+
     fn build_ordered_signals(&self, dag: &mut DAG, buses_info : &Vec<ExecutedBus>) {
         for wire_data in &self.ordered_signals {
             let state = State { basic_name: wire_data.name.clone(), name: wire_data.name.clone(), dim: 0 };
@@ -331,7 +460,8 @@ impl ExecutedTemplate {
         for cnn in &mut self.connexions {
             cnn.dag_offset = dag.get_entry().unwrap().get_out();
             cnn.dag_component_offset = dag.get_entry().unwrap().get_out_component();
-            dag.add_edge(cnn.inspect.goes_to, &cnn.full_name, cnn.inspect.is_parallel);
+            let origin = cnn.inspect.meta.as_ref().and_then(|meta| meta.file_id.map(|file_id| (file_id, meta.location.clone())));
+            dag.add_edge(cnn.inspect.goes_to, &cnn.full_name, cnn.inspect.is_parallel, origin);
             cnn.dag_jump = dag.get_entry().unwrap().get_out() - cnn.dag_offset;
             cnn.dag_component_jump = dag.get_entry().unwrap().get_out_component() - cnn.dag_component_offset;
         }
@@ -340,10 +470,10 @@ impl ExecutedTemplate {
     }
     fn build_constraints(&self, dag: &mut DAG) {
         
-        for c in &self.constraints {
+        for (c, origin) in self.constraints.iter().zip(&self.constraint_origins) {
             let correspondence = dag.get_main().unwrap().correspondence();
             let cc = Constraint::apply_correspondence(c, correspondence);
-            dag.add_constraint(cc);
+            dag.add_constraint(cc, origin.clone());
         }
         for s in &self.underscored_signals{
             let correspondence = dag.get_main().unwrap().correspondence();
@@ -352,6 +482,31 @@ impl ExecutedTemplate {
         }
     }
 
+    // The bounds of the signals given by the tags maxbit and binary of the
+    // inputs and maxbit of the outputs, which the range analysis assumes
+    fn build_tag_bounds(&self, dag: &mut DAG) {
+        use num_traits::ToPrimitive;
+        for (name, wire) in &self.tag_instances {
+            for (tag, value) in &wire.tags {
+                let bits = match (tag.as_str(), value) {
+                    (MAXBIT, Option::Some(bits)) => bits.to_usize(),
+                    (BINARY, _) => Option::Some(1),
+                    _ => Option::None,
+                };
+                if let Option::Some(bits) = bits {
+                    dag.add_tag_bound(name, bits);
+                }
+            }
+        }
+        for (path, value) in &self.signal_to_tags {
+            if let [name, tag] = path.as_slice() {
+                if let (MAXBIT, Option::Some(bits)) = (tag.as_str(), value.to_usize()) {
+                    dag.add_tag_bound(name, bits);
+                }
+            }
+        }
+    }
+
     pub fn export_to_circuit(self, instances: &mut [TemplateInstance], buses_info : &Vec<BusInstance>) -> TemplateInstance {
         use SignalType::*;
         fn build_triggers(
@@ -405,7 +560,10 @@ impl ExecutedTemplate {
             arguments
         }
 
-        let header = format!("{}_{}", self.template_name, instances.len());
+        // the buses of the instances of generic templates are not valid in the
+        // names of the generated code, the suffix keeps the header unique
+        let name: String = self.template_name.chars().map(|c| if c.is_alphanumeric() || c == '_' || c == '$' { c } else { '_' }).collect();
+        let header = format!("{}_{}", name, instances.len());
         let clusters = build_clusters(&self, instances);
         let triggers = build_triggers(instances, self.connexions);
         let components = build_components(self.components);
@@ -575,6 +733,33 @@ fn generate_symbols(dag: &mut DAG, state: State, config: &SignalConfig) {
     }
 }
 
+// The names of the signals of a wire, in the order of their memory
+fn generate_names(
+    state: State,
+    dimensions: &[usize],
+    is_bus: bool,
+    bus_connexions: &HashMap<String, BusConnexion>,
+    buses: &Vec<ExecutedBus>,
+    names: &mut Vec<String>,
+) {
+    if state.dim < dimensions.len() {
+        for index in 0..dimensions[state.dim] {
+            let new_state =
+                State { basic_name: state.basic_name.clone(), name: format!("{}[{}]", state.name, index), dim: state.dim + 1 };
+            generate_names(new_state, dimensions, is_bus, bus_connexions, buses, names);
+        }
+    } else if is_bus {
+        let bus = &buses[bus_connexions[&state.basic_name].inspect.goes_to];
+        for info_field in bus.fields() {
+            let field_state =
+                State { basic_name: info_field.name.clone(), name: format!("{}.{}", state.name, info_field.name), dim: 0 };
+            generate_names(field_state, &info_field.length, info_field.is_bus, bus.bus_connexions(), buses, names);
+        }
+    } else {
+        names.push(state.name);
+    }
+}
+
 // TODO: move to bus?
 fn generate_bus_symbols(dag: &mut DAG, state: State, config: &SignalConfig, bus_connexions: &HashMap<String, BusConnexion>, buses: &Vec<ExecutedBus>) {
     let bus_connection = bus_connexions.get(&state.basic_name).unwrap();
@@ -96,9 +96,15 @@ pub fn apply_computed(stmt: &mut Statement, analysis: &Analysis) {
                 }
             }
         }
-        Assert { arg, .. } => {
+        Assert { arg, message, .. } => {
             *arg = computed_or_original(analysis, arg);
             apply_computed_expr(arg, analysis);
+            for arglog in message {
+                if let LogArgument::LogExp(arg) = arglog {
+                    *arg = computed_or_original(analysis, arg);
+                    apply_computed_expr(arg, analysis);
+                }
+            }
         }
         UnderscoreSubstitution {  rhe, .. } => {
             *rhe = computed_or_original(analysis, rhe);
@@ -2,6 +2,7 @@ use super::AExpressionSlice;
 use super::Constraint as ConstraintGen;
 use std::collections::BTreeMap;
 use num_bigint_dig::BigInt;
+use program_structure::ast::Meta;
 use std::collections::HashSet;
 use std::collections::HashMap;
 
@@ -38,6 +39,8 @@ pub struct SubComponentData {
     pub is_parallel: bool,
     pub indexed_with: Vec<usize>,
     pub goes_to: NodePointer,
+    // the statement that creates the component
+    pub meta: Option<Meta>,
 }
 
 pub struct BusData {
@@ -11,19 +11,27 @@ use ansi_term::Colour;
 use circom_algebra::algebra::{ArithmeticError, ArithmeticExpression};
 use compiler::hir::very_concrete_program::VCP;
 use constraint_list::ConstraintList;
+use constraint_writers::graph_writer::GraphFormat;
 use constraint_writers::ConstraintExporter;
 use dag::DAG;
+use execute::ArgumentValues;
 use execution_data::executed_program::ExportResult;
 use execution_data::ExecutedProgram;
 use program_structure::ast::{self};
 use program_structure::error_code::ReportCode;
-use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::error_definition::{DeniedWarnings, Report, ReportCollection};
 use program_structure::file_definition::FileID;
 use program_structure::program_archive::ProgramArchive;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
 use std::rc::Rc;
+pub use dag::{undetermined_signals, undetermined_signals_with_components};
 
 pub struct BuildConfig {
     pub no_rounds: usize,
+    pub function_steps: usize,
+    pub template_recursion: usize,
     pub flag_json_sub: bool,
     pub json_substitutions: String,
     pub flag_s: bool,
@@ -32,6 +40,15 @@ pub struct BuildConfig {
     pub flag_verbose: bool,
     pub flag_old_heuristics: bool,
     pub inspect_constraints: bool,
+    pub inspect_witness: bool,
+    pub inspect_determinism: bool,
+    pub deny_unconstrained: bool,
+    pub suggest_parallel: bool,
+    pub flag_template_report: bool,
+    pub template_report: String,
+    pub flag_component_graph: bool,
+    pub component_graph_format: GraphFormat,
+    pub component_graph: String,
     pub prime: String,
 }
 
@@ -39,25 +56,60 @@ pub struct BuildConfig {
 pub struct FlagsExecution{
     pub verbose: bool,
     pub inspect: bool,
+    pub inspect_witness: bool,
+    pub inspect_determinism: bool,
+    pub deny_unconstrained: bool,
+    pub function_steps: usize,
+    pub template_recursion: usize,
+}
+
+// Why the constraints of the circuit are not generated. The errors of the
+// program are shown as they are found
+#[derive(Debug)]
+pub enum BuildError {
+    Program,
+    Denied(DeniedWarnings),
+    Write { file: String, error: io::Error },
+}
+
+impl BuildError {
+    // Shows the errors that are not shown yet
+    pub fn report(&self) {
+        if let BuildError::Write { .. } = self {
+            eprintln!("{}", Colour::Red.paint(self.to_string()));
+        }
+    }
+}
+
+impl From<DeniedWarnings> for BuildError {
+    fn from(denied: DeniedWarnings) -> BuildError {
+        BuildError::Denied(denied)
+    }
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::Program => write!(f, "the program has errors"),
+            BuildError::Denied(denied) => denied.fmt(f),
+            BuildError::Write { file, error } => write!(f, "Could not write {}: {}", file, error),
+        }
+    }
 }
 
 pub type ConstraintWriter = Box<dyn ConstraintExporter>;
-type BuildResponse = Result<(ConstraintWriter, VCP), ()>;
+type BuildResponse = Result<(ConstraintWriter, VCP), BuildError>;
 pub fn build_circuit(program: ProgramArchive, config: BuildConfig) -> BuildResponse {
-    let files = program.file_library.clone();
-    let flags = FlagsExecution{
-        verbose: config.flag_verbose,
-        inspect: config.inspect_constraints,
-    };
-    let (exe, warnings) = instantiation(&program, flags, &config.prime).map_err(|r| {
-        Report::print_reports(&r, &files);
-    })?;
-    Report::print_reports(&warnings, &files);
-    let (mut dag, mut vcp, warnings) = export(exe, program, flags).map_err(|r| {
-        Report::print_reports(&r, &files);
-    })?;
-    if config.inspect_constraints {
-        Report::print_reports(&warnings, &files);
+    let (mut dag, mut vcp) = generate_dag(program, &config)?;
+    if config.flag_template_report {
+        dag.generate_template_report(&config.template_report)
+            .map_err(|error| BuildError::Write { file: config.template_report.clone(), error })?;
+        println!("{} {}", Colour::Green.paint("Written successfully:"), config.template_report);
+    }
+    if config.flag_component_graph {
+        dag.generate_component_graph(&config.component_graph, config.component_graph_format)
+            .map_err(|error| BuildError::Write { file: config.component_graph.clone(), error })?;
+        println!("{} {}", Colour::Green.paint("Written successfully:"), config.component_graph);
     }
     if config.flag_f {
         sync_dag_and_vcp(&mut vcp, &mut dag);
@@ -78,6 +130,61 @@ pub fn build_circuit(program: ProgramArchive, config: BuildConfig) -> BuildRespo
     }
 }
 
+// The DAG of the circuit without simplifying its constraints, with the VCP
+// of its witness list
+pub fn build_dag(program: ProgramArchive, config: &BuildConfig) -> Result<(DAG, VCP), BuildError> {
+    let (mut dag, mut vcp) = generate_dag(program, config)?;
+    sync_dag_and_vcp(&mut vcp, &mut dag);
+    Result::Ok((dag, vcp))
+}
+
+// The values of the arguments of the main component, with the lengths of
+// each one, without instantiating the circuit
+pub fn main_arguments(program: &ProgramArchive, config: &BuildConfig) -> Result<ArgumentValues, BuildError> {
+    let flags = FlagsExecution{
+        verbose: config.flag_verbose,
+        inspect: false,
+        inspect_witness: false,
+        inspect_determinism: false,
+        deny_unconstrained: false,
+        function_steps: config.function_steps,
+        template_recursion: config.template_recursion,
+    };
+    execute::execute_main_arguments(program, flags, &config.prime).map_err(|r| {
+        Report::print_reports(&r, &program.file_library);
+        BuildError::Program
+    })
+}
+
+fn generate_dag(program: ProgramArchive, config: &BuildConfig) -> Result<(DAG, VCP), BuildError> {
+    let files = program.file_library.clone();
+    let flags = FlagsExecution{
+        verbose: config.flag_verbose,
+        inspect: config.inspect_constraints,
+        inspect_witness: config.inspect_witness,
+        inspect_determinism: config.inspect_determinism,
+        deny_unconstrained: config.deny_unconstrained,
+        function_steps: config.function_steps,
+        template_recursion: config.template_recursion,
+    };
+    let (exe, warnings) = instantiation(&program, flags, &config.prime).map_err(|r| {
+        Report::print_reports(&r, &files);
+        BuildError::Program
+    })?;
+    Report::print_warnings(&warnings, &files)?;
+    let (dag, vcp, warnings) = export(exe, program, flags).map_err(|r| {
+        Report::print_reports(&r, &files);
+        BuildError::Program
+    })?;
+    if config.inspect_constraints {
+        Report::print_warnings(&warnings, &files)?;
+    }
+    if config.suggest_parallel {
+        Report::print_warnings(&dag.parallel_analysis(), &files)?;
+    }
+    Result::Ok((dag, vcp))
+}
+
 type InstantiationResponse = Result<(ExecutedProgram, ReportCollection), ReportCollection>;
 fn instantiation(program: &ProgramArchive, flags: FlagsExecution, prime: &String) -> InstantiationResponse {
     let execution_result = execute::constraint_execution(&program, flags, prime);
@@ -98,8 +205,15 @@ fn export(exe: ExecutedProgram, program: ProgramArchive, flags: FlagsExecution)
     exported
 }
 
+// The witness list of the code gives the positions of the signals in its
+// memory, which are not their signals for the public fields of the buses
+// of the main component
+fn memory_witness(witness: Vec<usize>, memory_positions: &HashMap<usize, usize>) -> Vec<usize> {
+    witness.into_iter().map(|signal| *memory_positions.get(&signal).unwrap_or(&signal)).collect()
+}
+
 fn sync_dag_and_vcp(vcp: &mut VCP, dag: &mut DAG) {
-    let witness = Rc::new(DAG::produce_witness(dag));
+    let witness = Rc::new(memory_witness(DAG::produce_witness(dag), &dag.memory_positions));
     VCP::add_witness_list(vcp, Rc::clone(&witness));
 }
 
@@ -110,11 +224,13 @@ fn simplification_process(vcp: &mut VCP, dag: DAG, config: &BuildConfig) -> Cons
         parallel_flag: config.flag_p,
         port_substitution: config.flag_json_sub,
         json_substitutions: config.json_substitutions.clone(),
+        keep_substitutions: false,
         no_rounds: config.no_rounds,
         flag_old_heuristics: config.flag_old_heuristics,
         prime : config.prime.clone(),
     };
+    let memory_positions = dag.memory_positions.clone();
     let list = DAG::map_to_list(dag, flags);
-    VCP::add_witness_list(vcp, Rc::new(list.get_witness_as_vec()));
+    VCP::add_witness_list(vcp, Rc::new(memory_witness(list.get_witness_as_vec(), &memory_positions)));
     list
 }
@@ -1,19 +1,30 @@
 // Uncomment lines 163, 165, 336 and 338 to print cluster information
-use super::{ConstraintStorage, EncodingIterator, SEncoded, Simplifier, A, C, S};
+use super::{ConstraintSources, ConstraintStorage, EncodingIterator, SEncoded, Simplifier, A, C, S};
 use crate::SignalMap;
 use circom_algebra::num_bigint::BigInt;
 use constraint_writers::json_writer::SubstitutionJSON;
 use std::collections::{HashMap, HashSet, LinkedList, BTreeSet};
 use std::sync::Arc;
 
-fn log_substitutions(substitutions: &LinkedList<S>, writer: &mut Option<SubstitutionJSON>) {
+// The substitutions are written in the json file of the substitutions, and
+// kept in order when the constraints are explained
+#[derive(Default)]
+struct SubstitutionLog {
+    writer: Option<SubstitutionJSON>,
+    kept: Option<LinkedList<S>>,
+}
+
+fn log_substitutions(substitutions: &LinkedList<S>, log: &mut SubstitutionLog) {
     use super::json_porting::port_substitution;
-    if let Some(w) = writer {
+    if let Some(w) = &mut log.writer {
         for s in substitutions {
             let (from, to) = port_substitution(s);
             w.write_substitution(&from, &to).unwrap();
         }
     }
+    if let Some(kept) = &mut log.kept {
+        kept.extend(substitutions.iter().cloned());
+    }
 }
 
 #[derive(Default, Clone)]
@@ -200,7 +211,7 @@ fn eq_simplification(
     forbidden: Arc<HashSet<usize>>,
     no_vars: usize,
     field: &BigInt,
-    substitution_log: &mut Option<SubstitutionJSON>,
+    substitution_log: &mut SubstitutionLog,
 ) -> (LinkedList<S>, LinkedList<C>) {
     use std::sync::mpsc;
     use threadpool::ThreadPool;
@@ -254,7 +265,7 @@ fn constant_eq_simplification(
     c_eq: LinkedList<C>,
     forbidden: &HashSet<usize>,
     field: &BigInt,
-    substitution_log: &mut Option<SubstitutionJSON>,
+    substitution_log: &mut SubstitutionLog,
 ) -> (LinkedList<S>, LinkedList<C>) {
     let mut cons = LinkedList::new();
     let mut subs = LinkedList::new();
@@ -273,7 +284,7 @@ fn constant_eq_simplification(
 }
 
 fn linear_simplification(
-    log: &mut Option<SubstitutionJSON>,
+    log: &mut SubstitutionLog,
     linear: LinkedList<C>,
     forbidden: Arc<HashSet<usize>>,
     no_labels: usize,
@@ -439,18 +450,20 @@ fn remove_not_relevant(substitutions: &mut SEncoded, relevant: &HashSet<usize>)
 
 
 // returns the constraints, the assignment of the witness and the number of inputs in the witness
-pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap, usize) {
+pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap, usize, ConstraintSources, LinkedList<S>) {
     use super::non_linear_utils::obtain_and_simplify_non_linear;
     use circom_algebra::simplification_utils::build_encoded_fast_substitutions;
     use circom_algebra::simplification_utils::fast_encoded_constraint_substitution;
     use std::time::SystemTime;
 
-    let mut substitution_log =
-        if smp.port_substitution { 
+    let mut substitution_log = SubstitutionLog {
+        writer: if smp.port_substitution { 
             Some(SubstitutionJSON::new(&smp.json_substitutions).unwrap()) 
         } else {
              None 
-        };
+        },
+        kept: if smp.keep_substitutions { Some(LinkedList::new()) } else { None },
+    };
     let apply_linear = !smp.flag_s;
     let use_old_heuristics = smp.flag_old_heuristics;
     let field = smp.field.clone();
@@ -577,6 +590,7 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap, us
         HashMap::with_capacity(0)
     };
 
+    let mut sources = ConstraintSources::default();
     let (with_linear, mut constraint_storage) = {
         // println!("Building constraint storage");
         let now = SystemTime::now();
@@ -586,7 +600,7 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap, us
         LinkedList::push_back(&mut frames, linear_substitutions);
         let iter = EncodingIterator::new(&smp.dag_encoding);
         let mut storage = ConstraintStorage::new();
-        let with_linear = obtain_and_simplify_non_linear(iter, &mut storage, &frames, &field);
+        let with_linear = obtain_and_simplify_non_linear(iter, &mut storage, &frames, &field, &mut sources);
         crate::state_utils::empty_encoding_constraints(&mut smp.dag_encoding);
         let _dur = now.elapsed().unwrap().as_millis();
         // println!("Storages built in {} ms", dur);
@@ -682,17 +696,22 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap, us
         }
     }
 
-    let erased = crate::non_linear_simplification::simplify(
-        &mut constraint_storage,
-        &forbidden,
-        &field
-    );
+    let erased = if apply_linear {
+        crate::non_linear_simplification::simplify(
+            &mut constraint_storage,
+            &forbidden,
+            &field
+        )
+    } else {
+        LinkedList::new()
+    };
 
     for signal in erased {
         deleted.insert(signal);
     }
 
-    let _trash = constraint_storage.extract_with(&|c| C::is_empty(c));
+    let (_trash, kept) = constraint_storage.extract_with_ids(&|c| C::is_empty(c));
+    sources.renumber(&kept);
 
 
     let signal_map = {
@@ -720,11 +739,12 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap, us
     }
 
 
-    if let Some(w) = substitution_log {
+    if let Some(w) = substitution_log.writer {
         w.end().unwrap();
     }
     // println!("NO CONSTANTS: {}", constraint_storage.no_constants());
-    (constraint_storage, signal_map, smp.no_private_inputs - deleted_inputs)
+    let substitutions = substitution_log.kept.unwrap_or_default();
+    (constraint_storage, signal_map, smp.no_private_inputs - deleted_inputs, sources, substitutions)
 }
 
 
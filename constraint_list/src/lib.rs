@@ -3,12 +3,18 @@ use std::collections::{HashMap, HashSet, LinkedList};
 use circom_algebra::constraint_storage::ConstraintStorage;
 use circom_algebra::num_bigint::BigInt;
 use constraint_writers::debug_writer::DebugWriter;
+use constraint_writers::log_writer::Log;
+use constraint_writers::graph_writer::ConstraintGraph;
+use constraint_writers::source_map::{ConstraintSource, SourceMap};
+use program_structure::file_definition::{FileID, FileLibrary, FileLocation};
+use constraint_writers::smt_writer::SmtEncoding;
 use constraint_writers::ConstraintExporter;
 
 mod constraint_simplification;
 mod json_porting;
 mod non_linear_utils;
 mod r1cs_porting;
+mod smt_porting;
 mod state_utils;
 mod sym_porting;
 mod non_linear_simplification;
@@ -19,6 +25,7 @@ type A = circom_algebra::algebra::ArithmeticExpression<usize>;
 type SignalMap = HashMap<usize, usize>;
 type SEncoded = HashMap<usize, A>;
 type SFrames = LinkedList<SEncoded>;
+pub type ConstraintOrigin = Option<(FileID, FileLocation)>;
 
 pub struct SignalInfo {
     pub name: String,
@@ -31,6 +38,8 @@ pub struct EncodingNode {
     pub signals: Vec<SignalInfo>,
     pub ordered_signals: Vec<usize>,
     pub non_linear: LinkedList<C>,
+    // the origins of the non-linear constraints, in their order
+    pub origins: Vec<ConstraintOrigin>,
     pub is_custom_gate: bool,
 }
 
@@ -107,6 +116,43 @@ impl<'a> EncodingIterator<'a> {
     }
 }
 
+// The component and the position among the non-linear constraints of its
+// template of the constraints of the storage that come from one of them
+#[derive(Default)]
+pub struct ConstraintSources {
+    components: Vec<(String, usize)>,
+    constraints: HashMap<usize, (usize, usize)>,
+}
+
+impl ConstraintSources {
+    pub fn add_component(&mut self, path: String, node: usize) -> usize {
+        self.components.push((path, node));
+        self.components.len() - 1
+    }
+
+    pub fn add_constraint(&mut self, c_id: usize, component: usize, position: usize) {
+        self.constraints.insert(c_id, (component, position));
+    }
+
+    // the component, its node and the position of the constraint among the
+    // non-linear constraints of the node
+    pub fn get(&self, c_id: usize) -> Option<(&str, usize, usize)> {
+        let (component, position) = self.constraints.get(&c_id)?;
+        let (path, node) = &self.components[*component];
+        Some((path, *node, *position))
+    }
+
+    pub fn renumber(&mut self, kept: &[usize]) {
+        let mut constraints = HashMap::new();
+        for (c_id, old_id) in kept.iter().enumerate() {
+            if let Some(source) = self.constraints.get(old_id) {
+                constraints.insert(c_id, *source);
+            }
+        }
+        self.constraints = constraints;
+    }
+}
+
 pub struct Simplifier {
     pub field: BigInt,
     pub dag_encoding: DAGEncoding,
@@ -126,10 +172,12 @@ pub struct Simplifier {
     pub flag_old_heuristics: bool,
     pub port_substitution: bool,
     pub json_substitutions: String,
+    pub keep_substitutions: bool,
 }
 impl Simplifier {
     pub fn simplify_constraints(mut self) -> ConstraintList {
-        let (portable, map, private_inputs_witness) = constraint_simplification::simplification(&mut self);
+        let (portable, map, private_inputs_witness, sources, substitutions) =
+            constraint_simplification::simplification(&mut self);
         ConstraintList {
             field: self.field,
             dag_encoding: self.dag_encoding,
@@ -140,6 +188,8 @@ impl Simplifier {
             no_labels: self.max_signal,
             constraints: portable,
             signal_map: map,
+            sources,
+            substitutions,
         }
     }
 
@@ -163,6 +213,9 @@ pub struct ConstraintList {
     pub no_labels: usize,
     //  Signals in [witness_len, Vec::len(&signal_map)) are the ones deleted
     pub signal_map: SignalMap,
+    pub sources: ConstraintSources,
+    // the substitutions of the simplification, in order, when they are kept
+    pub substitutions: LinkedList<S>,
 }
 
 impl ConstraintExporter for ConstraintList {
@@ -177,6 +230,38 @@ impl ConstraintExporter for ConstraintList {
     fn sym(&self, out: &str) -> Result<(), ()> {
         sym_porting::port_sym(self, out)
     }
+
+    fn smt(&self, out: &str, encoding: SmtEncoding) -> std::io::Result<()> {
+        smt_porting::port_smt(self, out, encoding)
+    }
+
+    fn log(&self) -> Log {
+        r1cs_porting::log(self)
+    }
+
+    fn source_map(&self, files: &FileLibrary) -> SourceMap {
+        let mut map = SourceMap::new();
+        for c_id in self.constraints.get_ids() {
+            let source = self.sources.get(c_id).map(|(path, node, position)| {
+                let node = &self.dag_encoding.nodes[node];
+                ConstraintSource::new(path.to_string(), node.name.clone(), &node.origins[position], files)
+            });
+            map.push(source);
+        }
+        map
+    }
+
+    fn constraint_graph(&self) -> ConstraintGraph {
+        let mut graph = ConstraintGraph::new(self.no_wires());
+        for c_id in self.constraints.get_ids() {
+            let c = self.constraints.read_constraint(c_id).unwrap();
+            let c = C::apply_correspondence(&c, &self.signal_map);
+            let signals = c.a().keys().chain(c.b().keys()).chain(c.c().keys()).cloned().collect();
+            let template = self.sources.get(c_id).map(|(_, node, _)| self.dag_encoding.nodes[node].name.clone());
+            graph.push(signals, template);
+        }
+        graph
+    }
 }
 
 impl ConstraintList {
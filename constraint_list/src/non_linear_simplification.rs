@@ -1,12 +1,54 @@
+use circom_algebra::algebra::Constraint;
 use circom_algebra::num_bigint::BigInt;
 use circom_algebra::constraint_storage::ConstraintStorage;
+use circom_algebra::range_analysis::{bit_signal, Ranges, MAX_ROUNDS};
 use std::collections::{HashSet, LinkedList};
 
-
+// Removes the binary constraints x * (x - 1) = 0 whose signal the rest of
+// the constraints already force to be 0 or 1, as a repeated check of a bit
+// or the product of two bits, given the ranges that the constraints derive
+// for the signals. The ranges only follow from constraints, so the values
+// of the signals that satisfy the constraints do not change
 pub fn simplify(
-    _storage: &mut ConstraintStorage,
+    storage: &mut ConstraintStorage,
     _forbidden: &HashSet<usize>,
-    _field: &BigInt
+    field: &BigInt
 ) -> LinkedList<usize> {
+    let ids = storage.get_ids();
+    let mut ranges = Ranges::new(field);
+    for _ in 0..MAX_ROUNDS {
+        let mut changed = false;
+        for id in &ids {
+            if let Some(constraint) = storage.read_constraint(*id) {
+                changed |= ranges.derive(&constraint, 0, *id);
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    // a constraint is removed when the range of its signal does not follow
+    // from it or from a removed one, and no removed one follows from it
+    let mut removed = HashSet::new();
+    let mut used = HashSet::new();
+    for id in ids {
+        let signal = match storage.read_constraint(id).and_then(|constraint| bit_signal(&constraint, field)) {
+            Some(signal) => signal,
+            None => continue,
+        };
+        let support = match ranges.get(signal) {
+            Some(range) if range.is_binary() => match &range.support {
+                Some(support) => support,
+                None => continue,
+            },
+            _ => continue,
+        };
+        if !support.contains(&id) && support.iter().all(|constraint| !removed.contains(constraint)) && !used.contains(&id) {
+            removed.insert(id);
+            used.extend(support.iter().cloned());
+            storage.replace(id, Constraint::empty());
+        }
+    }
     LinkedList::new()
 }
@@ -1,4 +1,4 @@
-use super::{ConstraintStorage, EncodingIterator, SFrames, C};
+use super::{ConstraintSources, ConstraintStorage, EncodingIterator, SFrames, C};
 use circom_algebra::num_bigint::BigInt;
 use circom_algebra::simplification_utils::fast_encoded_constraint_substitution;
 use std::collections::LinkedList;
@@ -8,10 +8,12 @@ pub fn obtain_and_simplify_non_linear(
     storage: &mut ConstraintStorage,
     frames: &SFrames,
     field: &BigInt,
+    sources: &mut ConstraintSources,
 ) -> LinkedList<C> {
     let mut linear = LinkedList::new();
     let (_, non_linear) = EncodingIterator::take(&mut iter);
-    for mut constraint in non_linear {
+    let component = if non_linear.is_empty() { 0 } else { sources.add_component(iter.path.clone(), iter.node_id) };
+    for (position, mut constraint) in non_linear.into_iter().enumerate() {
         for frame in frames {
             fast_encoded_constraint_substitution(&mut constraint, frame, &field);
         }
@@ -19,12 +21,13 @@ pub fn obtain_and_simplify_non_linear(
         if C::is_linear(&constraint) {
             linear.push_back(constraint);
         } else {
-            storage.add_constraint(constraint);
+            let c_id = storage.add_constraint(constraint);
+            sources.add_constraint(c_id, component, position);
         }
     }
     for edge in EncodingIterator::edges(&iter) {
         let next = EncodingIterator::next(&iter, edge);
-        let mut linear_in_next = obtain_and_simplify_non_linear(next, storage, frames, field);
+        let mut linear_in_next = obtain_and_simplify_non_linear(next, storage, frames, field, sources);
         linear.append(&mut linear_in_next);
     }
     linear
@@ -1,13 +1,8 @@
 use super::{ConstraintList, C, EncodingIterator, SignalMap};
+use constraint_writers::log_writer::Log;
 use constraint_writers::r1cs_writer::{ConstraintSection, CustomGatesAppliedData, HeaderData, R1CSWriter, SignalSection};
 
-pub fn port_r1cs(list: &ConstraintList, output: &str, custom_gates: bool) -> Result<(), ()> {
-    use constraint_writers::log_writer::Log;
-    let field_size = if list.field.bits() % 64 == 0 {
-        list.field.bits() / 8
-    } else{
-        (list.field.bits() / 64 + 1) * 8
-    };
+fn signals_log(list: &ConstraintList) -> Log {
     let mut log = Log::new();
     log.no_labels = ConstraintList::no_labels(list);
     log.no_wires = ConstraintList::no_wires(list);
@@ -15,6 +10,29 @@ pub fn port_r1cs(list: &ConstraintList, output: &str, custom_gates: bool) -> Res
     log.no_private_inputs_witness = list.no_private_inputs_witness;
     log.no_public_inputs = list.no_public_inputs;
     log.no_public_outputs = list.no_public_outputs;
+    log
+}
+
+pub fn log(list: &ConstraintList) -> Log {
+    let mut log = signals_log(list);
+    for c_id in list.constraints.get_ids() {
+        let c = list.constraints.read_constraint(c_id).unwrap();
+        if C::is_linear(&c) {
+            log.no_linear += 1;
+        } else {
+            log.no_non_linear += 1;
+        }
+    }
+    log
+}
+
+pub fn port_r1cs(list: &ConstraintList, output: &str, custom_gates: bool) -> Result<(), ()> {
+    let field_size = if list.field.bits() % 64 == 0 {
+        list.field.bits() / 8
+    } else{
+        (list.field.bits() / 64 + 1) * 8
+    };
+    let mut log = signals_log(list);
 
     let r1cs = R1CSWriter::new(output.to_string(), field_size, custom_gates)?;
     let mut constraint_section = R1CSWriter::start_constraints_section(r1cs)?;
@@ -0,0 +1,40 @@
+use super::{ConstraintList, EncodingIterator, IteratorSignal, SignalMap, C};
+use constraint_writers::smt_writer::*;
+use std::collections::HashSet;
+use std::io;
+
+pub fn port_smt(list: &ConstraintList, file_name: &str, encoding: SmtEncoding) -> io::Result<()> {
+    let iter = EncodingIterator::new(&list.dag_encoding);
+    let mut smt = SmtFile::new(file_name, &list.field, encoding)?;
+    let mut declared = HashSet::new();
+    declared.insert(0);
+    signal_iteration(iter, &list.signal_map, &mut declared, &mut smt)?;
+    for c_id in list.constraints.get_ids() {
+        let constraint = list.constraints.read_constraint(c_id).unwrap();
+        let constraint = C::apply_correspondence(&constraint, &list.signal_map);
+        SmtFile::write_constraint(&mut smt, constraint.a(), constraint.b(), constraint.c())?;
+    }
+    SmtFile::finish_writing(smt)
+}
+
+// each signal of the witness is declared with the first of its names, the
+// signals removed by the simplification are not declared
+fn signal_iteration(
+    mut iter: EncodingIterator,
+    map: &SignalMap,
+    declared: &mut HashSet<usize>,
+    smt: &mut SmtFile,
+) -> io::Result<()> {
+    let (signals, _) = EncodingIterator::take(&mut iter);
+    for signal in signals {
+        let signal = IteratorSignal::new(signal, map);
+        if signal.witness != map.len() && declared.insert(signal.witness) {
+            SmtFile::declare_signal(smt, signal.witness, &signal.name)?;
+        }
+    }
+    for edge in EncodingIterator::edges(&iter) {
+        let next = EncodingIterator::next(&iter, edge);
+        signal_iteration(next, map, declared, smt)?;
+    }
+    Ok(())
+}
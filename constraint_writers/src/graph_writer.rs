@@ -0,0 +1,220 @@
+use json::JsonValue;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+// The formats of the graphs of the circuit: json, dot for graphviz, or a
+// binary format that is read without parsing by the partitioning tools
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum GraphFormat {
+    Json,
+    Dot,
+    Binary,
+}
+
+impl GraphFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            GraphFormat::Json => "json",
+            GraphFormat::Dot => "dot",
+            GraphFormat::Binary => "bin",
+        }
+    }
+}
+
+fn quoted(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+// A directed graph in dot format, written as its nodes and edges are added
+pub struct DotGraph {
+    writer: BufWriter<File>,
+}
+
+impl DotGraph {
+    pub fn new(file: &str, name: &str) -> io::Result<DotGraph> {
+        let file = File::create(file)?;
+        let mut writer = BufWriter::new(file);
+        writeln!(writer, "digraph {} {{", quoted(name))?;
+        writeln!(writer, "    node [shape=box];")?;
+        Result::Ok(DotGraph { writer })
+    }
+
+    // the attributes are given as pairs of name and value, as ("color", "red")
+    pub fn node(&mut self, id: &str, label: &str, attributes: &[(&str, &str)]) -> io::Result<()> {
+        let mut line = format!("    {} [label={}", quoted(id), quoted(label));
+        for (name, value) in attributes {
+            line.push_str(&format!(", {}={}", name, quoted(value)));
+        }
+        writeln!(self.writer, "{}];", line)
+    }
+
+    pub fn edge(&mut self, from: &str, to: &str, label: Option<&str>) -> io::Result<()> {
+        match label {
+            Some(label) => writeln!(self.writer, "    {} -> {} [label={}];", quoted(from), quoted(to), quoted(label)),
+            None => writeln!(self.writer, "    {} -> {};", quoted(from), quoted(to)),
+        }
+        
+    }
+
+    pub fn close(mut self) -> io::Result<()> {
+        writeln!(self.writer, "}}")?;
+        self.writer.flush()
+    }
+}
+
+// The signals of each constraint of the r1cs file, by their numbers in it,
+// with the template of the component that generated the constraint, which is
+// unknown for the constraints that result from the simplification
+#[derive(Default)]
+pub struct ConstraintGraph {
+    constraints: Vec<(Vec<usize>, Option<String>)>,
+    no_signals: usize,
+}
+
+const SIMPLIFICATION: &str = "simplification";
+
+// the templates with their numbers of constraints, and the shared signals of
+// each pair of templates, by their positions
+type TemplateGraph = (Vec<(String, usize)>, BTreeMap<(usize, usize), usize>);
+
+impl ConstraintGraph {
+    pub fn new(no_signals: usize) -> ConstraintGraph {
+        ConstraintGraph { constraints: Vec::new(), no_signals }
+    }
+
+    // the signal 0, the constant one, is left out, since it is in almost every
+    // constraint and it does not relate them
+    pub fn push(&mut self, signals: BTreeSet<usize>, template: Option<String>) {
+        let signals = signals.into_iter().filter(|signal| *signal != 0).collect();
+        self.constraints.push((signals, template));
+    }
+
+    pub fn len(&self) -> usize {
+        self.constraints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.constraints.is_empty()
+    }
+
+    // The templates, as Num2Bits for all the instances of Num2Bits, with
+    // their numbers of constraints, and the number of signals that the
+    // constraints of each pair of templates share
+    fn by_template(&self) -> TemplateGraph {
+        let mut templates: Vec<(String, usize)> = Vec::new();
+        let mut templates_of_signal: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+        for (signals, template) in &self.constraints {
+            let name = template.as_deref().map_or(SIMPLIFICATION, |name| name.split('(').next().unwrap_or(name));
+            let position = match templates.iter().position(|(template, _)| template == name) {
+                Some(position) => position,
+                None => {
+                    templates.push((name.to_string(), 0));
+                    templates.len() - 1
+                }
+            };
+            templates[position].1 += 1;
+            for signal in signals {
+                templates_of_signal.entry(*signal).or_default().insert(position);
+            }
+        }
+        let mut shared: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+        for templates in templates_of_signal.values() {
+            let templates: Vec<_> = templates.iter().collect();
+            for (i, first) in templates.iter().enumerate() {
+                for second in &templates[i + 1..] {
+                    *shared.entry((**first, **second)).or_default() += 1;
+                }
+            }
+        }
+        (templates, shared)
+    }
+
+    fn write_json(&self, file: &str, by_template: bool) -> io::Result<()> {
+        let graph = if by_template {
+            let (templates, shared) = self.by_template();
+            let templates: Vec<JsonValue> = templates
+                .iter()
+                .map(|(name, constraints)| json::object! { "template" => name.as_str(), "constraints" => *constraints })
+                .collect();
+            let edges: Vec<JsonValue> = shared
+                .iter()
+                .map(|((from, to), signals)| json::object! { "from" => *from, "to" => *to, "signals" => *signals })
+                .collect();
+            json::object! { "templates" => templates, "edges" => edges }
+        } else {
+            let constraints: Vec<JsonValue> = self
+                .constraints
+                .iter()
+                .map(|(signals, template)| {
+                    json::object! { "signals" => signals.clone(), "template" => template.clone() }
+                })
+                .collect();
+            json::object! { "signals" => self.no_signals, "constraints" => constraints }
+        };
+        let file = File::create(file)?;
+        let mut writer = BufWriter::new(file);
+        graph.write(&mut writer)?;
+        writer.flush()
+    }
+
+    fn write_dot(&self, file: &str, by_template: bool) -> io::Result<()> {
+        let mut graph = DotGraph::new(file, "constraints")?;
+        if by_template {
+            let (templates, shared) = self.by_template();
+            for (id, (name, constraints)) in templates.iter().enumerate() {
+                graph.node(&id.to_string(), &format!("{}\n{} constraints", name, constraints), &[])?;
+            }
+            for ((from, to), signals) in &shared {
+                graph.edge(&from.to_string(), &to.to_string(), Some(&signals.to_string()))?;
+            }
+        } else {
+            let mut used = BTreeSet::new();
+            for (id, (signals, template)) in self.constraints.iter().enumerate() {
+                let label = format!("{}\n{}", id, template.as_deref().unwrap_or(SIMPLIFICATION));
+                graph.node(&format!("c{}", id), &label, &[])?;
+                used.extend(signals.iter().cloned());
+            }
+            for signal in &used {
+                graph.node(&format!("s{}", signal), &signal.to_string(), &[("shape", "ellipse")])?;
+            }
+            for (id, (signals, _)) in self.constraints.iter().enumerate() {
+                for signal in signals {
+                    graph.edge(&format!("c{}", id), &format!("s{}", signal), None)?;
+                }
+            }
+        }
+        graph.close()
+    }
+
+    // The magic "cgr1", then the integers of 8 bytes in little endian: the
+    // number of signals, the number of constraints, the position of the
+    // signals of each constraint in the list of signals, and one more with the
+    // length of the list, and the list of the signals of all the constraints
+    fn write_binary(&self, file: &str) -> io::Result<()> {
+        let file = File::create(file)?;
+        let mut writer = BufWriter::new(file);
+        let mut numbers = vec![self.no_signals as u64, self.constraints.len() as u64];
+        let mut position = 0;
+        for (signals, _) in &self.constraints {
+            numbers.push(position);
+            position += signals.len() as u64;
+        }
+        numbers.push(position);
+        numbers.extend(self.constraints.iter().flat_map(|(signals, _)| signals.iter().map(|signal| *signal as u64)));
+        writer.write_all(b"cgr1")?;
+        for number in numbers {
+            writer.write_all(&number.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    // the binary format only has the graph of the constraints
+    pub fn write(&self, file: &str, format: GraphFormat, by_template: bool) -> io::Result<()> {
+        match format {
+            GraphFormat::Json => self.write_json(file, by_template),
+            GraphFormat::Dot => self.write_dot(file, by_template),
+            GraphFormat::Binary => self.write_binary(file),
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use super::source_map::SourceMap;
+use program_structure::file_definition::FileLibrary;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// The code of a file with the number of constraints of the r1cs file that
+// each of its lines generates, in every component
+struct AnnotatedFile {
+    name: String,
+    lines: Vec<(String, usize)>,
+}
+
+// The number of constraints that each line of the code generates after the
+// simplification, written as the code of the files with constraints, with
+// the lines colored by their numbers of constraints
+pub struct Heatmap {
+    files: Vec<AnnotatedFile>,
+    total: usize,
+    // the constraints without a line, as the linear ones that result from
+    // the simplification
+    without_line: usize,
+}
+
+impl Heatmap {
+    pub fn new(map: &SourceMap, files: &FileLibrary) -> Heatmap {
+        let mut counts: HashMap<(&str, usize), usize> = HashMap::new();
+        let mut without_line = 0;
+        for constraint in 0..map.len() {
+            match map.get(constraint).and_then(|source| source.file.as_deref().zip(source.line)) {
+                Some(line) => *counts.entry(line).or_insert(0) += 1,
+                None => without_line += 1,
+            }
+        }
+        let mut annotated = Vec::new();
+        let mut file_id = 0;
+        while let (Some(name), Some(source)) = (files.get_file_name(file_id), files.get_source(file_id)) {
+            let name = name.trim_matches('"');
+            let lines: Vec<_> = source
+                .lines()
+                .enumerate()
+                .map(|(index, code)| (code.to_string(), counts.get(&(name, index + 1)).cloned().unwrap_or(0)))
+                .collect();
+            if lines.iter().any(|(_, count)| *count > 0) {
+                annotated.push(AnnotatedFile { name: name.to_string(), lines });
+            }
+            file_id += 1;
+        }
+        Heatmap { files: annotated, total: map.len(), without_line }
+    }
+
+    pub fn write(&self, out: &str) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(out)?);
+        writer.write_all(self.to_html().as_bytes())?;
+        writer.flush()
+    }
+
+    fn to_html(&self) -> String {
+        let max = self.files.iter().flat_map(|file| file.lines.iter().map(|(_, count)| *count)).max().unwrap_or(0);
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Constraints by line</title>\n");
+        html.push_str("<style>\n");
+        html.push_str("table { border-collapse: collapse; font-family: monospace; white-space: pre; }\n");
+        html.push_str("td { padding: 0 8px; }\n");
+        html.push_str("td.line, td.count { text-align: right; color: #666; }\n");
+        html.push_str("</style>\n</head>\n<body>\n");
+        html.push_str(&format!("<h1>Constraints by line</h1>\n<p>{} constraints", self.total));
+        if self.without_line > 0 {
+            html.push_str(&format!(", {} of them without a line, as the linear ones that result from the simplification", self.without_line));
+        }
+        html.push_str("</p>\n");
+        for file in &self.files {
+            let constraints: usize = file.lines.iter().map(|(_, count)| *count).sum();
+            html.push_str(&format!("<h2>{}</h2>\n<p>{} constraints</p>\n<table>\n", escape(&file.name), constraints));
+            for (index, (code, count)) in file.lines.iter().enumerate() {
+                let (style, count) = if *count > 0 {
+                    (format!(" style=\"background-color: rgba(255, 0, 0, {:.2})\"", heat(*count, max)), count.to_string())
+                } else {
+                    (String::new(), String::new())
+                };
+                html.push_str(&format!(
+                    "<tr{}><td class=\"line\">{}</td><td class=\"count\">{}</td><td>{}</td></tr>\n",
+                    style,
+                    index + 1,
+                    count,
+                    escape(code)
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+// The opacity of the color of a line, in a logarithmic scale so that the
+// lines with few constraints are seen next to the ones with many
+fn heat(count: usize, max: usize) -> f64 {
+    0.1 + 0.6 * (count as f64).ln_1p() / (max as f64).ln_1p()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
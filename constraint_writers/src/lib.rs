@@ -1,12 +1,23 @@
 pub mod debug_writer;
+pub mod graph_writer;
+pub mod heatmap_writer;
 pub mod json_writer;
 pub mod log_writer;
 pub mod r1cs_writer;
+pub mod smt_writer;
 pub mod sym_writer;
 pub mod r1cs_reader;
+pub mod sym_reader;
+pub mod source_map;
 
 pub trait ConstraintExporter {
     fn r1cs(&self, out: &str, custom_gates: bool) -> Result<(), ()>;
     fn json_constraints(&self, writer: &debug_writer::DebugWriter) -> Result<(), ()>;
     fn sym(&self, out: &str) -> Result<(), ()>;
+    fn smt(&self, out: &str, encoding: smt_writer::SmtEncoding) -> std::io::Result<()>;
+    // the numbers of constraints and signals of the r1cs file, without writing it
+    fn log(&self) -> log_writer::Log;
+    fn source_map(&self, files: &program_structure::file_definition::FileLibrary) -> source_map::SourceMap;
+    // the signals of each constraint, to see how the constraints are related
+    fn constraint_graph(&self) -> graph_writer::ConstraintGraph;
 }
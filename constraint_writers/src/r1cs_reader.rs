@@ -126,9 +126,9 @@ pub struct HeaderData {
 }
 
 
-type Constraint = HashMap<usize, BigInt>;
-type ConstraintList = Vec<(Constraint, Constraint, Constraint)>;
-type SignalList = Vec<usize>;
+pub type Constraint = HashMap<usize, BigInt>;
+pub type ConstraintList = Vec<(Constraint, Constraint, Constraint)>;
+pub type SignalList = Vec<usize>;
 pub struct ConstraintSection {
     reader: BufReader<File>,
     number_of_constraints: usize,
@@ -447,6 +447,23 @@ impl R1CSData {
             custom_gates_applied_data: None,
         }
     }
+
+    pub fn header(&self) -> &HeaderData {
+        &self.header_data
+    }
+
+    pub fn constraints(&self) -> &ConstraintList {
+        &self.constraints
+    }
+
+    // the label of each wire, as in the sym file
+    pub fn signals(&self) -> &SignalList {
+        &self.signals
+    }
+
+    pub fn has_custom_gates(&self) -> bool {
+        self.custom_gates
+    }
 }
 
 
@@ -0,0 +1,131 @@
+use circom_algebra::num_bigint::BigInt;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+// A * B - C = 0
+pub type Constraint = (HashMap<usize, BigInt>, HashMap<usize, BigInt>, HashMap<usize, BigInt>);
+
+// The finite fields of SMT-LIB are only supported by some solvers, as cvc5.
+// The rest get the constraints over the integers modulo the prime
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum SmtEncoding {
+    FiniteField,
+    Integer,
+}
+
+pub struct SmtFile {
+    writer: BufWriter<File>,
+    field: BigInt,
+    encoding: SmtEncoding,
+    // the names of the signals declared, where the signal 0 is the constant 1
+    names: HashMap<usize, String>,
+}
+
+impl SmtFile {
+    pub fn new(file: &str, field: &BigInt, encoding: SmtEncoding) -> io::Result<SmtFile> {
+        let file = File::create(file)?;
+        let mut smt = SmtFile { writer: BufWriter::new(file), field: field.clone(), encoding, names: HashMap::new() };
+        let header = match encoding {
+            SmtEncoding::FiniteField => format!("(set-logic QF_FF)\n(define-sort F () (_ FiniteField {}))\n", field),
+            SmtEncoding::Integer => "(set-logic QF_NIA)\n".to_string(),
+        };
+        smt.write(&header)?;
+        Result::Ok(smt)
+    }
+
+    pub fn declare_signal(smt: &mut SmtFile, signal: usize, name: &str) -> io::Result<()> {
+        let symbol = format!("|{}|", name);
+        let declaration = match smt.encoding {
+            SmtEncoding::FiniteField => format!("(declare-const {} F)\n", symbol),
+            SmtEncoding::Integer => {
+                format!("(declare-const {} Int)\n(assert (and (<= 0 {}) (< {} {})))\n", symbol, symbol, symbol, smt.field)
+            }
+        };
+        smt.names.insert(signal, symbol);
+        smt.write(&declaration)
+    }
+
+    // A * B - C = 0, where the signals must be declared before
+    pub fn write_constraint(
+        smt: &mut SmtFile,
+        a: &HashMap<usize, BigInt>,
+        b: &HashMap<usize, BigInt>,
+        c: &HashMap<usize, BigInt>,
+    ) -> io::Result<()> {
+        let assertion = format!("(assert {})\n", smt.constraint(a, b, c));
+        smt.write(&assertion)
+    }
+
+    // Asserts that the constraints of the first list and the ones of the
+    // second list do not hold for the same values of the signals
+    pub fn write_distinct_constraints(smt: &mut SmtFile, first: &[Constraint], second: &[Constraint]) -> io::Result<()> {
+        let assertion = format!("(assert (not (= {} {})))\n", smt.conjunction(first), smt.conjunction(second));
+        smt.write(&assertion)
+    }
+
+    pub fn write_check_sat(smt: &mut SmtFile) -> io::Result<()> {
+        smt.write("(check-sat)\n")
+    }
+
+    pub fn finish_writing(mut smt: SmtFile) -> io::Result<()> {
+        smt.writer.flush()
+    }
+
+    fn write(&mut self, text: &str) -> io::Result<()> {
+        self.writer.write_all(text.as_bytes())
+    }
+
+    fn constraint(&self, a: &HashMap<usize, BigInt>, b: &HashMap<usize, BigInt>, c: &HashMap<usize, BigInt>) -> String {
+        let is_linear = a.is_empty() || b.is_empty();
+        let (a, b, c) = (self.linear_expression(a), self.linear_expression(b), self.linear_expression(c));
+        match (self.encoding, is_linear) {
+            (SmtEncoding::FiniteField, true) => format!("(= {} (as ff0 F))", c),
+            (SmtEncoding::FiniteField, false) => format!("(= (ff.mul {} {}) {})", a, b, c),
+            (SmtEncoding::Integer, true) => format!("(= (mod {} {}) 0)", c, self.field),
+            (SmtEncoding::Integer, false) => format!("(= (mod (- (* {} {}) {}) {}) 0)", a, b, c, self.field),
+        }
+    }
+
+    fn conjunction(&self, constraints: &[Constraint]) -> String {
+        let mut formulas: Vec<String> = constraints.iter().map(|(a, b, c)| self.constraint(a, b, c)).collect();
+        match formulas.len() {
+            0 => "true".to_string(),
+            1 => formulas.pop().unwrap(),
+            _ => format!("(and {})", formulas.join(" ")),
+        }
+    }
+
+    fn constant(&self, value: &BigInt) -> String {
+        let value = ((value % &self.field) + &self.field) % &self.field;
+        match self.encoding {
+            SmtEncoding::FiniteField => format!("(as ff{} F)", value),
+            SmtEncoding::Integer => value.to_string(),
+        }
+    }
+
+    fn linear_expression(&self, coefficients: &HashMap<usize, BigInt>) -> String {
+        let mut order: Vec<&usize> = coefficients.keys().collect();
+        order.sort();
+        let mut terms = Vec::new();
+        let one = BigInt::from(1);
+        for signal in order {
+            let value = ((&coefficients[signal] % &self.field) + &self.field) % &self.field;
+            let coefficient = self.constant(&value);
+            let name = self.names.get(signal).cloned().unwrap_or_else(|| format!("|{}|", signal));
+            let term = match self.encoding {
+                _ if *signal == 0 => coefficient,
+                _ if value == one => name,
+                SmtEncoding::FiniteField => format!("(ff.mul {} {})", coefficient, name),
+                SmtEncoding::Integer => format!("(* {} {})", coefficient, name),
+            };
+            terms.push(term);
+        }
+        match (terms.len(), self.encoding) {
+            (0, _) => self.constant(&BigInt::from(0)),
+            (1, _) => terms.pop().unwrap(),
+            (_, SmtEncoding::FiniteField) => format!("(ff.add {})", terms.join(" ")),
+            (_, SmtEncoding::Integer) => format!("(+ {})", terms.join(" ")),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use json::JsonValue;
+use program_structure::file_definition::{FileID, FileLibrary, FileLocation};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+// The origin of a constraint of the r1cs file: the component that generated
+// it, the instance of its template, and the file and line of the constraint
+// in the code when they are known
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ConstraintSource {
+    pub component: String,
+    pub template: String,
+    pub file: Option<String>,
+    pub line: Option<usize>,
+}
+
+impl ConstraintSource {
+    pub fn new(
+        component: String,
+        template: String,
+        origin: &Option<(FileID, FileLocation)>,
+        files: &FileLibrary,
+    ) -> ConstraintSource {
+        let (file, line) = match origin {
+            Some((file_id, location)) => {
+                let file = files.get_file_name(*file_id).map(|name| name.trim_matches('"').to_string());
+                (file, files.get_line(location.start, *file_id))
+            }
+            None => (None, None),
+        };
+        ConstraintSource { component, template, file, line }
+    }
+}
+
+// The sources of the constraints of the r1cs file, by their number, where
+// the linear constraints that result from the simplification have none
+#[derive(Default)]
+pub struct SourceMap {
+    sources: Vec<Option<ConstraintSource>>,
+}
+
+impl SourceMap {
+    pub fn new() -> SourceMap {
+        SourceMap::default()
+    }
+
+    pub fn push(&mut self, source: Option<ConstraintSource>) {
+        self.sources.push(source);
+    }
+
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    pub fn get(&self, constraint: usize) -> Option<&ConstraintSource> {
+        self.sources.get(constraint).and_then(|source| source.as_ref())
+    }
+
+    // the constraints generated by the line of the file, in every component
+    pub fn constraints_of_line(&self, file: &str, line: usize) -> Vec<usize> {
+        self.constraints_where(|source| source.file.as_deref() == Some(file) && source.line == Some(line))
+    }
+
+    pub fn constraints_of_component(&self, component: &str) -> Vec<usize> {
+        self.constraints_where(|source| source.component == component)
+    }
+
+    fn constraints_where(&self, condition: impl Fn(&ConstraintSource) -> bool) -> Vec<usize> {
+        let mut constraints = Vec::new();
+        for (constraint, source) in self.sources.iter().enumerate() {
+            if source.as_ref().is_some_and(&condition) {
+                constraints.push(constraint);
+            }
+        }
+        constraints
+    }
+
+    // Each constraint is written in a line as [component, template, file, line]
+    pub fn write(&self, out: &str) -> io::Result<()> {
+        let file = File::create(out)?;
+        let mut writer = BufWriter::new(file);
+        writer.write_all(b"{\n\"constraints\": [\n")?;
+        for (constraint, source) in self.sources.iter().enumerate() {
+            let entry = match source {
+                Some(source) => json::array![
+                    source.component.as_str(),
+                    source.template.as_str(),
+                    source.file.as_deref(),
+                    source.line
+                ],
+                None => JsonValue::Null,
+            };
+            let separator = if constraint + 1 < self.sources.len() { ",\n" } else { "\n" };
+            writer.write_all(entry.dump().as_bytes())?;
+            writer.write_all(separator.as_bytes())?;
+        }
+        writer.write_all(b"]\n}\n")?;
+        writer.flush()
+    }
+
+    pub fn read(input: &str) -> Result<SourceMap, std::io::Error> {
+        let invalid = |message: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string());
+        let content = std::fs::read_to_string(input)?;
+        let parsed = json::parse(&content).map_err(|err| invalid(&err.to_string()))?;
+        let mut map = SourceMap::new();
+        for entry in parsed["constraints"].members() {
+            if entry.is_null() {
+                map.push(None);
+                continue;
+            }
+            match (entry[0].as_str(), entry[1].as_str()) {
+                (Some(component), Some(template)) => map.push(Some(ConstraintSource {
+                    component: component.to_string(),
+                    template: template.to_string(),
+                    file: entry[2].as_str().map(|file| file.to_string()),
+                    line: entry[3].as_usize(),
+                })),
+                _ => return Err(invalid(&format!("Invalid constraint {}", entry.dump()))),
+            }
+        }
+        Ok(map)
+    }
+}
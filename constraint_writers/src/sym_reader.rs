@@ -0,0 +1,22 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+// The name of each signal of a sym file, by its number in the r1cs
+pub fn read_sym(input: &str) -> Result<HashMap<usize, String>, std::io::Error> {
+    let reader = File::open(input).map(BufReader::new)?;
+    let mut names = HashMap::new();
+    for line in reader.lines() {
+        let line = line?;
+        let fields: Vec<&str> = line.splitn(4, ',').collect();
+        match (fields.first().and_then(|original| original.parse::<usize>().ok()), fields.get(3)) {
+            (Some(original), Some(symbol)) => {
+                names.insert(original, symbol.to_string());
+            }
+            _ => {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("Invalid line \"{}\"", line)));
+            }
+        }
+    }
+    Ok(names)
+}
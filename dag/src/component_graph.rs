@@ -0,0 +1,114 @@
+use super::{Node, DAG};
+use constraint_writers::graph_writer::{DotGraph, GraphFormat};
+use json::JsonValue;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+pub(crate) fn instance_name(node: &Node) -> String {
+    // the names of the instances carry their parameters, as Num2Bits(254)
+    if node.template_name.contains('(') || node.parameters.is_empty() {
+        return node.template_name.clone();
+    }
+    let parameters: Vec<_> = node.parameters.iter().map(|p| p.to_str_radix(10)).collect();
+    format!("{}({})", node.template_name, parameters.join(", "))
+}
+
+fn instance_as_json(id: usize, node: &Node) -> JsonValue {
+    let parameters: Vec<JsonValue> = node.parameters.iter().map(|p| p.to_str_radix(10).into()).collect();
+    json::object! {
+        "id" => id,
+        "template" => node.template_name.split('(').next().unwrap_or_default(),
+        "parameters" => parameters,
+        "parallel" => node.is_parallel,
+        "custom_gate" => node.is_custom_gate,
+        "inputs" => node.inputs_length,
+        "outputs" => node.outputs_length,
+        "intermediates" => node.intermediates_length,
+        "total_signals" => node.number_of_signals,
+        "constraints" => node.constraints.len(),
+    }
+}
+
+// The tree of the components, where each component refers to the instance of
+// its template, from the main component down
+fn component_as_json(dag: &DAG, name: &str, node: usize) -> JsonValue {
+    let components: Vec<JsonValue> =
+        dag.adjacency[node].iter().map(|edge| component_as_json(dag, &edge.label, edge.goes_to)).collect();
+    json::object! {
+        "name" => name,
+        "instance" => node,
+        "components" => components,
+    }
+}
+
+fn write_json(dag: &DAG, file: &str) -> io::Result<()> {
+    let instances: Vec<_> = dag.nodes.iter().enumerate().map(|(id, node)| instance_as_json(id, node)).collect();
+    let graph = json::object! {
+        "instances" => instances,
+        "components" => component_as_json(dag, "main", dag.main_id()),
+    };
+    let file = File::create(file)?;
+    let mut writer = BufWriter::new(file);
+    graph.write(&mut writer)?;
+    writer.flush()
+}
+
+// The components of an array are grouped in a single edge, as m[2] for m[0]
+// and m[1], so that the graph keeps its size for large circuits
+fn edge_labels(dag: &DAG, node: usize) -> Vec<(usize, String)> {
+    let mut groups: Vec<(usize, String, usize)> = Vec::new();
+    for edge in &dag.adjacency[node] {
+        let name = edge.label.split('[').next().unwrap_or_default();
+        match groups.iter_mut().find(|(goes_to, group, _)| *goes_to == edge.goes_to && group == name) {
+            Some(group) => group.2 += 1,
+            None => groups.push((edge.goes_to, name.to_string(), 1)),
+        }
+    }
+    let mut labels: Vec<(usize, String)> = Vec::new();
+    for (goes_to, name, count) in groups {
+        let name = if count > 1 { format!("{}[{}]", name, count) } else { name };
+        match labels.iter_mut().find(|(to, _)| *to == goes_to) {
+            Some((_, label)) => label.push_str(&format!(", {}", name)),
+            None => labels.push((goes_to, name)),
+        }
+    }
+    labels
+}
+
+// The instances of the templates, with an edge to the instance of each of
+// their components
+fn write_dot(dag: &DAG, file: &str) -> io::Result<()> {
+    let mut graph = DotGraph::new(file, "components")?;
+    for (id, node) in dag.nodes.iter().enumerate() {
+        let signals = node.inputs_length + node.outputs_length + node.intermediates_length;
+        let label = format!("{}\n{} signals, {} constraints", instance_name(node), signals, node.constraints.len());
+        let mut attributes = Vec::new();
+        if node.is_parallel {
+            attributes.push(("style", "bold"));
+        }
+        if node.is_custom_gate {
+            attributes.push(("shape", "ellipse"));
+        }
+        if id == dag.main_id() {
+            attributes.push(("color", "blue"));
+        }
+        graph.node(&id.to_string(), &label, &attributes)?;
+    }
+    for node in 0..dag.nodes.len() {
+        for (goes_to, label) in edge_labels(dag, node) {
+            graph.edge(&node.to_string(), &goes_to.to_string(), Some(&label))?;
+        }
+    }
+    graph.close()
+}
+
+pub fn write_component_graph(dag: &DAG, file: &str, format: GraphFormat) -> io::Result<()> {
+    match format {
+        GraphFormat::Json => write_json(dag, file),
+        GraphFormat::Dot => write_dot(dag, file),
+        // the binary format is only for the graph of the constraints
+        GraphFormat::Binary => {
+            Result::Err(io::Error::new(io::ErrorKind::InvalidInput, "the binary format is only for the graph of the constraints"))
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use super::constraint_correctness_analysis::split_signal_name_index;
+use super::witness_analysis::undetermined_signals_with_components;
+use super::DAG;
+use program_structure::constants::UsefulConstants;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use std::collections::{HashMap, HashSet};
+
+struct UndeterminedSignal;
+impl UndeterminedSignal {
+    pub fn report(signal: &str, template: &str, examples: &[String]) -> Report {
+        if examples.len() == 1 {
+            let msg = format!("In template \"{}\": the constraints of the template do not determine the value of the signal {} from its inputs, so it may have several values", template, examples[0]);
+            Report::warning(msg, ReportCode::NondeterministicSignal)
+        } else {
+            let msg = format!("In template \"{}\": the constraints of the template do not determine the values of {} signals of the array {} from its inputs, so they may have several values", template, examples.len(), signal);
+            let mut report = Report::warning(msg, ReportCode::NondeterministicSignal);
+            let ex = format!("For example: {}, {}.", examples[0], examples[1]);
+            report.add_note(ex);
+            report
+        }
+    }
+}
+
+// The outputs, intermediates and inputs of subcomponents of each template
+// whose values are not determined by the inputs of the template, taking the
+// outputs of its subcomponents as determined by their inputs
+pub fn analyse(dag: &DAG) -> ReportCollection {
+    let field = UsefulConstants::new(&dag.prime).get_p().clone();
+    let mut reports = ReportCollection::new();
+    let mut visited = HashSet::new();
+    for (node_id, node) in dag.nodes.iter().enumerate() {
+        if node.is_custom_gate() || !visited.insert(node.template_name.clone()) {
+            continue;
+        }
+        let inputs = (node.outputs_length + 1)..(node.outputs_length + node.inputs_length + 1);
+        let mut unknown: HashSet<usize> = node.reachables.iter().filter(|signal| !inputs.contains(signal)).cloned().collect();
+        for signal in &node.underscored_signals {
+            unknown.remove(signal);
+        }
+        let mut components = Vec::new();
+        for edge in &dag.adjacency[node_id] {
+            let child = &dag.nodes[edge.goes_to];
+            let outputs: Vec<_> = (1..child.outputs_length + 1).map(|signal| signal + edge.in_number).collect();
+            let inputs: Vec<_> = (child.outputs_length + 1..child.outputs_length + child.inputs_length + 1).map(|signal| signal + edge.in_number).collect();
+            components.push((inputs, outputs));
+        }
+        let undetermined = undetermined_signals_with_components(&node.constraints, &unknown, &components, &field);
+        let outputs_of_components: HashSet<_> = components.iter().flat_map(|(_, outputs)| outputs.iter()).collect();
+
+        let mut names: Vec<_> = node
+            .signal_correspondence
+            .iter()
+            .filter(|(_, id)| undetermined.contains(id) && !outputs_of_components.contains(id))
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        let mut arrays: HashMap<String, Vec<String>> = HashMap::new();
+        let mut order = Vec::new();
+        for name in names {
+            let array = split_signal_name_index(&name);
+            if !arrays.contains_key(&array) {
+                order.push(array.clone());
+            }
+            arrays.entry(array).or_default().push(name);
+        }
+        for array in order {
+            reports.push(UndeterminedSignal::report(&array, &node.template_name, &arrays[&array]));
+        }
+    }
+    reports
+}
@@ -1,20 +1,38 @@
+mod component_graph;
 mod constraint_correctness_analysis;
+mod determinism_analysis;
+mod satisfiability_analysis;
 mod json_porting;
 mod map_to_constraint_list;
+mod parallel_analysis;
+mod range_analysis;
 mod r1cs_porting;
+mod smt_porting;
 mod sym_porting;
+mod template_report;
 mod witness_producer;
+mod witness_analysis;
 use circom_algebra::num_bigint::BigInt;
 use constraint_list::ConstraintList;
 use constraint_writers::debug_writer::DebugWriter;
+use constraint_writers::graph_writer::GraphFormat;
+use constraint_writers::log_writer::Log;
+use constraint_writers::graph_writer::ConstraintGraph;
+use constraint_writers::source_map::SourceMap;
+use constraint_writers::smt_writer::SmtEncoding;
 use constraint_writers::ConstraintExporter;
 use program_structure::constants::UsefulConstants;
 use program_structure::error_definition::ReportCollection;
+use program_structure::file_definition::{FileID, FileLibrary, FileLocation};
 use std::collections::{HashMap, HashSet};
+pub use template_report::InstanceConstraints;
+pub use witness_analysis::{bit_signal, is_binary_decomposition, undetermined_signals, undetermined_signals_with_components};
 type Signal = usize;
 type Constraint = circom_algebra::algebra::Constraint<usize>;
 type Substitution = circom_algebra::algebra::Substitution<usize>;
 type Range = std::ops::Range<usize>;
+// the statement of the template that produced a constraint
+pub type ConstraintOrigin = Option<(FileID, FileLocation)>;
 
 pub type FastSubAccess = HashMap<usize, Substitution>;
 
@@ -84,18 +102,22 @@ impl<'a> Tree<'a> {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Edge {
     label: String,
     goes_to: usize,
     in_number: usize,
     out_number: usize,
     in_component_number: usize,
-    out_component_number: usize
+    out_component_number: usize,
+    // whether the component is created with parallel, and the statement
+    // that creates it
+    is_parallel: bool,
+    origin: ConstraintOrigin,
 }
 impl Edge {
     fn new_entry(id: usize) -> Edge {
-        Edge { label: "main".to_string(), goes_to: id, in_number: 0, out_number: 0, in_component_number: 0, out_component_number: 0  }
+        Edge { label: "main".to_string(), goes_to: id, ..Edge::default() }
     }
 
     pub fn get_goes_to(&self) -> usize {
@@ -131,7 +153,7 @@ impl Edge {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Node {
     entry: Edge,
     template_name: String,
@@ -149,7 +171,10 @@ pub struct Node {
     forbidden_if_main: HashSet<usize>,
     io_signals: Vec<usize>,
     constraints: Vec<Constraint>,
+    constraint_origins: Vec<ConstraintOrigin>,
     underscored_signals: Vec<usize>,
+    // the signals with the number of bits that their tags bound them to
+    tag_bounds: Vec<(usize, usize)>,
     is_parallel: bool,
     has_parallel_sub_cmp: bool,
     is_custom_gate: bool,
@@ -217,18 +242,33 @@ impl Node {
         self.ordered_signals.push(name);
     }
 
-    fn add_constraint(&mut self, constraint: Constraint) {
-        self.constraints.push(constraint)
+    fn add_constraint(&mut self, constraint: Constraint, origin: ConstraintOrigin) {
+        self.constraints.push(constraint);
+        self.constraint_origins.push(origin);
     }
 
     fn add_underscored_signal(&mut self, signal: usize) {
         self.underscored_signals.push(signal)
     }
 
+    // the positions of the signal, or of its array
+    fn add_tag_bound(&mut self, name: &str, bits: usize) {
+        let array = format!("{}[", name);
+        for (signal_name, signal) in &self.signal_correspondence {
+            if signal_name == name || signal_name.starts_with(&array) {
+                self.tag_bounds.push((*signal, bits));
+            }
+        }
+    }
+
     fn set_number_of_subcomponents_indexes(&mut self, number_scmp: usize) {
         self.number_of_subcomponents_indexes = number_scmp
     }
 
+    pub fn template_name(&self) -> &str {
+        &self.template_name
+    }
+
     pub fn parameters(&self) -> &Vec<BigInt> {
         &self.parameters
     }
@@ -253,6 +293,14 @@ impl Node {
         &self.constraints
     }
 
+    pub fn constraint_origins(&self) -> &[ConstraintOrigin] {
+        &self.constraint_origins
+    }
+
+    pub fn tag_bounds(&self) -> &[(usize, usize)] {
+        &self.tag_bounds
+    }
+
     pub fn io_signals(&self) -> &Vec<usize> {
         &self.io_signals
     }
@@ -265,6 +313,10 @@ impl Node {
         self.public_inputs_length
     }
 
+    pub fn is_public_input(&self, signal: usize) -> bool {
+        signal > self.outputs_length && signal <= self.outputs_length + self.inputs_length && self.forbidden_if_main.contains(&signal)
+    }
+
     pub fn number_of_private_inputs(&self) -> usize {
         self.inputs_length - self.public_inputs_length
     }
@@ -294,11 +346,16 @@ impl Node {
     }
 }
 
+#[derive(Clone)]
 pub struct DAG {
     pub one_signal: usize,
     pub nodes: Vec<Node>,
     pub adjacency: Vec<Vec<Edge>>,
     pub prime: String,
+    // the positions in memory of the inputs of the main component that are
+    // not their signals, as the public fields of its buses go with the
+    // public inputs while the buses are contiguous in memory
+    pub memory_positions: HashMap<usize, usize>,
 }
 
 impl ConstraintExporter for DAG {
@@ -313,6 +370,22 @@ impl ConstraintExporter for DAG {
     fn sym(&self, out: &str) -> Result<(), ()> {
         DAG::generate_sym_output(self, out)
     }
+
+    fn smt(&self, out: &str, encoding: SmtEncoding) -> std::io::Result<()> {
+        DAG::generate_smt_output(self, out, encoding)
+    }
+
+    fn log(&self) -> Log {
+        r1cs_porting::log(self)
+    }
+
+    fn source_map(&self, files: &FileLibrary) -> SourceMap {
+        r1cs_porting::source_map(self, files)
+    }
+
+    fn constraint_graph(&self) -> ConstraintGraph {
+        r1cs_porting::constraint_graph(self)
+    }
 }
 
 impl DAG {
@@ -322,10 +395,11 @@ impl DAG {
             one_signal: 0,
             nodes: Vec::new(),
             adjacency: Vec::new(),
+            memory_positions: HashMap::new(),
         }
     }
 
-    pub fn add_edge(&mut self, to: usize, label: &str, is_parallel: bool) -> Option<&Edge> {
+    pub fn add_edge(&mut self, to: usize, label: &str, is_parallel: bool, origin: ConstraintOrigin) -> Option<&Edge> {
         if to < self.main_id() {
             // create arrow
             let from = self.main_id();
@@ -345,6 +419,8 @@ impl DAG {
                 out_number: out_num,
                 in_component_number: in_component_num,
                 out_component_number: out_component_num,
+                is_parallel,
+                origin,
             };
             // add correspondence to current node
             let mut correspondence = std::mem::take(&mut self.nodes[from].signal_correspondence);
@@ -403,15 +479,29 @@ impl DAG {
         }
     }
 
+    // The inputs of the main component in the order of their memory, after
+    // its outputs
+    pub fn set_inputs_memory(&mut self, inputs: &[String]) {
+        if let Option::Some(node) = self.nodes.last() {
+            let first = node.outputs_length + 1;
+            for (position, name) in inputs.iter().enumerate() {
+                let signal = node.signal_correspondence[name];
+                if signal != first + position {
+                    self.memory_positions.insert(signal, first + position);
+                }
+            }
+        }
+    }
+
     pub fn add_ordered_signal(&mut self, name: String) {
         if let Option::Some(node) = self.get_mut_main() {
             node.add_ordered_signal(name);
         }
     }
 
-    pub fn add_constraint(&mut self, constraint: Constraint) {
+    pub fn add_constraint(&mut self, constraint: Constraint, origin: ConstraintOrigin) {
         if let Option::Some(node) = self.get_mut_main() {
-            node.add_constraint(constraint);
+            node.add_constraint(constraint, origin);
         }
     }
 
@@ -421,6 +511,12 @@ impl DAG {
         }
     }
 
+    pub fn add_tag_bound(&mut self, name: &str, bits: usize) {
+        if let Option::Some(node) = self.get_mut_main() {
+            node.add_tag_bound(name, bits);
+        }
+    }
+
     pub fn set_number_of_subcomponents_indexes(&mut self, number_scmp: usize){
         if let Option::Some(node) = self.get_mut_main() {
             node.set_number_of_subcomponents_indexes(number_scmp);
@@ -478,6 +574,31 @@ impl DAG {
         constraint_correctness_analysis::clean_constraints(&mut self.nodes);
     }
 
+    pub fn determinism_analysis(&self) -> ReportCollection {
+        determinism_analysis::analyse(self)
+    }
+
+    pub fn parallel_analysis(&self) -> ReportCollection {
+        parallel_analysis::analyse(self)
+    }
+
+    pub fn satisfiability_analysis(&self) -> Result<ReportCollection, ReportCollection> {
+        let reports = satisfiability_analysis::analyse(self);
+        if reports.errors.is_empty() {
+            Ok(reports.warnings)
+        } else {
+            Err(reports.errors)
+        }
+    }
+
+    pub fn range_analysis(&self) -> ReportCollection {
+        range_analysis::analyse(self)
+    }
+
+    pub fn signal_ranges(&self) -> HashMap<usize, HashMap<usize, circom_algebra::range_analysis::Range>> {
+        range_analysis::node_ranges(self)
+    }
+
     pub fn generate_r1cs_output(&self, output_file: &str, custom_gates: bool) -> Result<(), ()> {
         r1cs_porting::write(self, output_file, custom_gates)
     }
@@ -486,10 +607,26 @@ impl DAG {
         sym_porting::write(self, output_file)
     }
 
+    pub fn generate_smt_output(&self, output_file: &str, encoding: SmtEncoding) -> std::io::Result<()> {
+        smt_porting::write(self, output_file, encoding)
+    }
+
     pub fn generate_json_constraints(&self, debug: &DebugWriter) -> Result<(), ()> {
         json_porting::port_constraints(self, debug)
     }
 
+    pub fn generate_template_report(&self, output_file: &str) -> std::io::Result<()> {
+        template_report::write_template_report(self, output_file)
+    }
+
+    pub fn generate_component_graph(&self, output_file: &str, format: GraphFormat) -> std::io::Result<()> {
+        component_graph::write_component_graph(self, output_file, format)
+    }
+
+    pub fn instance_constraints(&self) -> Vec<InstanceConstraints> {
+        template_report::instance_constraints(self)
+    }
+
     pub fn produce_witness(&self) -> Vec<usize> {
         witness_producer::produce_witness(self)
     }
@@ -549,6 +686,7 @@ pub struct SimplificationFlags {
     pub parallel_flag: bool,
     pub port_substitution: bool,
     pub json_substitutions: String,
+    pub keep_substitutions: bool,
     pub flag_old_heuristics: bool,
     pub prime : String,
 }
@@ -73,9 +73,11 @@ fn map_node_to_encoding(id: usize, node: Node) -> EncodingNode {
     let mut ordered_signals = Vec::new();
     let locals = node.locals;
     let mut non_linear = LinkedList::new();
-    for c in node.constraints {
+    let mut origins = Vec::new();
+    for (c, origin) in node.constraints.into_iter().zip(node.constraint_origins) {
         if !Constraint::is_linear(&c) {
             LinkedList::push_back(&mut non_linear, c);
+            origins.push(origin);
         }
     }
 
@@ -99,6 +101,7 @@ fn map_node_to_encoding(id: usize, node: Node) -> EncodingNode {
         signals,
         ordered_signals,
         non_linear,
+        origins,
         is_custom_gate: node.is_custom_gate,
     }
 }
@@ -142,6 +145,7 @@ pub fn map(dag: DAG, flags: SimplificationFlags) -> ConstraintList {
         flag_old_heuristics: flags.flag_old_heuristics,
         port_substitution: flags.port_substitution,
         json_substitutions: flags.json_substitutions,
+        keep_substitutions: flags.keep_substitutions,
     }
     .simplify_constraints()
 }
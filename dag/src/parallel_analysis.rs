@@ -0,0 +1,273 @@
+use super::component_graph::instance_name;
+use super::DAG;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// A component with fewer constraints, counting the ones of its subcomponents,
+// computes its witness in less time than it takes to start the thread that
+// computes it in parallel
+const MIN_PARALLEL_CONSTRAINTS: usize = 1000;
+
+// The components of a template created by the same statement with the same
+// instance, as the ones of an array
+struct Group {
+    name: String,
+    instance: usize,
+    is_parallel: bool,
+    components: Vec<usize>,
+}
+
+// The constraints of each instance, counting the ones of its subcomponents
+fn total_constraints(dag: &DAG) -> Vec<usize> {
+    let mut totals: Vec<usize> = dag.nodes.iter().map(|node| node.constraints.len()).collect();
+    // the subcomponents of a node have lower ids, so they are counted before it
+    for node in 0..dag.nodes.len() {
+        for edge in &dag.adjacency[node] {
+            totals[node] += totals[edge.goes_to];
+        }
+    }
+    totals
+}
+
+fn find(classes: &mut HashMap<usize, usize>, signal: usize) -> usize {
+    let mut root = signal;
+    while let Some(&next) = classes.get(&root) {
+        root = next;
+    }
+    // the signals of the path go to the root
+    let mut current = signal;
+    while current != root {
+        current = classes.insert(current, root).unwrap();
+    }
+    root
+}
+
+// The components of the node that each of its components depends on: the
+// ones whose outputs are in a constraint of the node with its inputs, or
+// with the signals of the node that are not inputs and that are in the
+// constraints with its inputs
+fn dependencies(dag: &DAG, node_id: usize) -> Vec<HashSet<usize>> {
+    let node = &dag.nodes[node_id];
+    let edges = &dag.adjacency[node_id];
+    // the component of each input and output of the components, and whether
+    // it is an output
+    let mut ports: HashMap<usize, (usize, bool)> = HashMap::new();
+    for (component, edge) in edges.iter().enumerate() {
+        let child = &dag.nodes[edge.goes_to];
+        for signal in 1..=(child.outputs_length + child.inputs_length) {
+            ports.insert(edge.in_number + signal, (component, signal <= child.outputs_length));
+        }
+    }
+    let inputs = (node.outputs_length + 1)..(node.outputs_length + node.inputs_length + 1);
+    let links = |signals: &HashSet<usize>| -> Vec<usize> {
+        signals.iter().filter(|signal| **signal != 0 && !inputs.contains(signal) && !ports.contains_key(signal)).cloned().collect()
+    };
+
+    let mut classes: HashMap<usize, usize> = HashMap::new();
+    for constraint in &node.constraints {
+        let signals = links(&constraint.take_cloned_signals());
+        for pair in signals.windows(2) {
+            let (left, right) = (find(&mut classes, pair[0]), find(&mut classes, pair[1]));
+            if left != right {
+                classes.insert(left, right);
+            }
+        }
+    }
+    let mut dependencies = vec![HashSet::new(); edges.len()];
+    let mut connected: HashMap<usize, (HashSet<usize>, HashSet<usize>)> = HashMap::new();
+    for constraint in &node.constraints {
+        let signals = constraint.take_cloned_signals();
+        let mut outputs = HashSet::new();
+        let mut inputs = HashSet::new();
+        for (component, is_output) in signals.iter().filter_map(|signal| ports.get(signal)) {
+            if *is_output {
+                outputs.insert(*component);
+            } else {
+                inputs.insert(*component);
+            }
+        }
+        match links(&signals).first() {
+            Some(signal) => {
+                let class = connected.entry(find(&mut classes, *signal)).or_default();
+                class.0.extend(outputs);
+                class.1.extend(inputs);
+            }
+            None => add_dependencies(&mut dependencies, &outputs, &inputs),
+        }
+    }
+    for (outputs, inputs) in connected.values() {
+        add_dependencies(&mut dependencies, outputs, inputs);
+    }
+    dependencies
+}
+
+fn add_dependencies(dependencies: &mut [HashSet<usize>], outputs: &HashSet<usize>, inputs: &HashSet<usize>) {
+    for input in inputs {
+        dependencies[*input].extend(outputs.iter().filter(|output| *output != input));
+    }
+}
+
+// The components that depend on the component, directly or through other
+// components, and the ones that it depends on
+fn related(dependencies: &[HashSet<usize>], dependents: &[HashSet<usize>], component: usize) -> HashSet<usize> {
+    let mut related = HashSet::new();
+    for graph in [dependencies, dependents] {
+        let mut pending = VecDeque::from([component]);
+        let mut visited = HashSet::from([component]);
+        while let Some(current) = pending.pop_front() {
+            for next in &graph[current] {
+                if visited.insert(*next) {
+                    related.insert(*next);
+                    pending.push_back(*next);
+                }
+            }
+        }
+    }
+    related
+}
+
+fn groups(dag: &DAG, node_id: usize) -> Vec<Group> {
+    let mut groups: Vec<Group> = Vec::new();
+    for (component, edge) in dag.adjacency[node_id].iter().enumerate() {
+        let name = edge.label.split('[').next().unwrap_or_default();
+        let is_parallel = edge.is_parallel || dag.nodes[edge.goes_to].is_parallel;
+        match groups.iter_mut().find(|g| g.name == name && g.instance == edge.goes_to && g.is_parallel == is_parallel) {
+            Some(group) => group.components.push(component),
+            None => groups.push(Group {
+                name: name.to_string(),
+                instance: edge.goes_to,
+                is_parallel,
+                components: vec![component],
+            }),
+        }
+    }
+    groups
+}
+
+impl Group {
+    // the word for a single component or for several ones
+    fn verb<'a>(&self, single: &'a str, several: &'a str) -> &'a str {
+        if self.components.len() > 1 {
+            several
+        } else {
+            single
+        }
+    }
+}
+
+fn template_name<'a>(dag: &'a DAG, group: &Group) -> &'a str {
+    dag.nodes[group.instance].template_name().split('(').next().unwrap_or_default()
+}
+
+fn number_of_constraints(constraints: usize) -> String {
+    if constraints == 1 {
+        "1 constraint".to_string()
+    } else {
+        format!("{} constraints", constraints)
+    }
+}
+
+fn subject(dag: &DAG, group: &Group) -> String {
+    let instance = instance_name(&dag.nodes[group.instance]);
+    if group.components.len() > 1 {
+        format!("the {} components {} of {}", group.components.len(), group.name, instance)
+    } else {
+        format!("the component {} of {}", group.name, instance)
+    }
+}
+
+fn group_report(dag: &DAG, node_id: usize, group: &Group, message: String, code: ReportCode) -> Report {
+    let mut report = Report::warning(message, code);
+    let edge = &dag.adjacency[node_id][group.components[0]];
+    if let Option::Some((file_id, location)) = &edge.origin {
+        report.add_primary(location.clone(), *file_id, "created here".to_string());
+    }
+    report
+}
+
+// The components of each template that have enough constraints to be
+// computed in parallel and do not depend on other components with enough
+// constraints, and the parallel components that have too few constraints or
+// that depend on the other components with enough constraints, or the other
+// way around, so they are computed one after the other
+pub fn analyse(dag: &DAG) -> ReportCollection {
+    let totals = total_constraints(dag);
+    let mut reports = ReportCollection::new();
+    for (node_id, node) in dag.nodes.iter().enumerate() {
+        let edges = &dag.adjacency[node_id];
+        let groups = groups(dag, node_id);
+        let relevant = |group: &Group| group.is_parallel || totals[group.instance] >= MIN_PARALLEL_CONSTRAINTS;
+        if node.is_custom_gate() || !groups.iter().any(relevant) {
+            continue;
+        }
+        let dependencies = dependencies(dag, node_id);
+        let mut dependents = vec![HashSet::new(); edges.len()];
+        for (component, depends_on) in dependencies.iter().enumerate() {
+            for other in depends_on {
+                dependents[*other].insert(component);
+            }
+        }
+        let large: Vec<usize> =
+            (0..edges.len()).filter(|component| totals[edges[*component].goes_to] >= MIN_PARALLEL_CONSTRAINTS).collect();
+        // whether another component with enough constraints can be computed
+        // at the same time as the component
+        let concurrent = |component: usize| {
+            let related = related(&dependencies, &dependents, component);
+            large.iter().any(|other| *other != component && !related.contains(other))
+        };
+
+        for group in groups.iter().filter(|group| relevant(group)) {
+            let constraints = totals[group.instance];
+            let report = if !group.is_parallel && group.components.iter().any(|component| concurrent(*component)) {
+                let message = format!(
+                    "In template \"{}\": {} {} {}{} and {} independent of other components of the template with at least {} constraints, so the C++ witness generator can compute {} in parallel",
+                    node.template_name, subject(dag, group), group.verb("has", "have"), number_of_constraints(constraints),
+                    group.verb("", " each"), group.verb("is", "are"), MIN_PARALLEL_CONSTRAINTS, group.verb("it", "them")
+                );
+                let mut report = group_report(dag, node_id, group, message, ReportCode::ParallelCandidate);
+                let component = if group.components.len() > 1 { format!("{}[i]", group.name) } else { group.name.clone() };
+                report.add_note(format!("Create {} with parallel, as {} = parallel {}(...).", group.verb("it", "them"), component, template_name(dag, group)));
+                report
+            } else if group.is_parallel && constraints < MIN_PARALLEL_CONSTRAINTS {
+                let message = format!(
+                    "In template \"{}\": {} {} parallel but {} only {}{}, so starting the thread that computes {} takes longer than computing {}",
+                    node.template_name, subject(dag, group), group.verb("is", "are"), group.verb("has", "have"), number_of_constraints(constraints),
+                    group.verb("", " each"), group.verb("it", "them"), group.verb("it", "them")
+                );
+                let mut report = group_report(dag, node_id, group, message, ReportCode::UselessParallel);
+                report.add_note(parallel_note(dag, node_id, group));
+                report
+            } else if group.is_parallel && !group.components.iter().any(|component| concurrent(*component)) {
+                let alone = large.iter().all(|component| group.components == [*component]);
+                let reason = if alone {
+                    format!("the template has no other components with at least {} constraints to compute at the same time", MIN_PARALLEL_CONSTRAINTS)
+                } else {
+                    format!(
+                        "{} and the other components of the template with at least {} constraints depend on one another, so the C++ witness generator computes them one after the other",
+                        group.verb("it", "they"), MIN_PARALLEL_CONSTRAINTS
+                    )
+                };
+                let message = format!(
+                    "In template \"{}\": {} {} parallel but {}",
+                    node.template_name, subject(dag, group), group.verb("is", "are"), reason
+                );
+                let mut report = group_report(dag, node_id, group, message, ReportCode::UselessParallel);
+                report.add_note(parallel_note(dag, node_id, group));
+                report
+            } else {
+                continue;
+            };
+            reports.push(report);
+        }
+    }
+    reports
+}
+
+fn parallel_note(dag: &DAG, node_id: usize, group: &Group) -> String {
+    if group.components.iter().any(|component| dag.adjacency[node_id][*component].is_parallel) {
+        format!("Remove parallel from the call of the template that creates {}.", group.verb("it", "them"))
+    } else {
+        format!("The template {} is declared parallel, so all its components are.", template_name(dag, group))
+    }
+}
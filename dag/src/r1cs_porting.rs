@@ -1,6 +1,9 @@
 use super::{Constraint, Tree, DAG};
+use constraint_writers::graph_writer::ConstraintGraph;
 use constraint_writers::log_writer::Log;
 use constraint_writers::r1cs_writer::{ConstraintSection, CustomGatesAppliedData, HeaderData, R1CSWriter};
+use constraint_writers::source_map::{ConstraintSource, SourceMap};
+use program_structure::file_definition::FileLibrary;
 
 pub fn write(dag: &DAG, output: &str, custom_gates: bool) -> Result<(), ()> {
     let tree = Tree::new(dag);
@@ -112,6 +115,78 @@ pub fn write(dag: &DAG, output: &str, custom_gates: bool) -> Result<(), ()> {
     Result::Ok(())
 }
 
+// the unsimplified circuit has all its signals in the witness
+pub fn log(dag: &DAG) -> Log {
+    let tree = Tree::new(dag);
+    let mut log = Log::new();
+    let wires = count_constraints(&mut log, &tree) + 1;
+    log.no_wires = wires;
+    log.no_labels = wires;
+    log.no_public_outputs = dag.public_outputs();
+    log.no_public_inputs = dag.public_inputs();
+    log.no_private_inputs = dag.private_inputs();
+    log.no_private_inputs_witness = dag.private_inputs();
+    log
+}
+
+fn count_constraints(log: &mut Log, tree: &Tree) -> usize {
+    let mut no_signals = tree.signals.len();
+    for c in &tree.constraints {
+        if Constraint::is_linear(c) {
+            log.no_linear += 1;
+        } else {
+            log.no_non_linear += 1;
+        }
+    }
+    for edge in Tree::get_edges(tree) {
+        let subtree = Tree::go_to_subtree(tree, edge);
+        no_signals += count_constraints(log, &subtree);
+    }
+    no_signals
+}
+
+// the sources of the constraints in the order of the r1cs file, where the
+// subcomponents skip their empty constraints as in the tree
+pub fn source_map(dag: &DAG, files: &FileLibrary) -> SourceMap {
+    let mut map = SourceMap::new();
+    map_sources(&Tree::new(dag), files, &mut map);
+    map
+}
+
+fn map_sources(tree: &Tree, files: &FileLibrary, map: &mut SourceMap) {
+    let node = &tree.dag.nodes[tree.node_id];
+    let is_main = tree.node_id == tree.dag.main_id();
+    for (constraint, origin) in node.constraints.iter().zip(&node.constraint_origins) {
+        if is_main || !constraint.is_empty() {
+            map.push(Some(ConstraintSource::new(tree.path.clone(), node.template_name.clone(), origin, files)));
+        }
+    }
+    for edge in Tree::get_edges(tree) {
+        let subtree = Tree::go_to_subtree(tree, edge);
+        map_sources(&subtree, files, map);
+    }
+}
+
+// the signals of the constraints in the order of the r1cs file, with the
+// template of the component of each one
+pub fn constraint_graph(dag: &DAG) -> ConstraintGraph {
+    let mut graph = ConstraintGraph::new(log(dag).no_wires);
+    graph_constraints(&Tree::new(dag), &mut graph);
+    graph
+}
+
+fn graph_constraints(tree: &Tree, graph: &mut ConstraintGraph) {
+    let template = &tree.dag.nodes[tree.node_id].template_name;
+    for c in &tree.constraints {
+        let signals = c.a().keys().chain(c.b().keys()).chain(c.c().keys()).cloned().collect();
+        graph.push(signals, Some(template.clone()));
+    }
+    for edge in Tree::get_edges(tree) {
+        let subtree = Tree::go_to_subtree(tree, edge);
+        graph_constraints(&subtree, graph);
+    }
+}
+
 fn write_constraint_section(
     constraint_section: &mut ConstraintSection,
     log: &mut Log,
@@ -0,0 +1,230 @@
+use super::{is_binary_decomposition, ConstraintOrigin, DAG};
+use circom_algebra::num_bigint::BigInt;
+use circom_algebra::num_traits::{One, Signed, Zero};
+use circom_algebra::range_analysis::{bit_signal, Range, Ranges, MAX_ROUNDS};
+use program_structure::constants::UsefulConstants;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use std::collections::{HashMap, HashSet};
+
+type Constraint = circom_algebra::algebra::Constraint<usize>;
+// a constraint of an instance of a node: the node, its position in the node
+// and the offset of the signals of the instance
+type Occurrence = (usize, usize, usize);
+
+// The comparisons of the circuit that may overflow the field. A comparison
+// computes the difference of two expressions, as in[0] + (1 << n) - in[1],
+// and decomposes it in bits, which only gives its result when the value of
+// the difference fits in them. The ranges of the signals of the difference
+// follow from the binary constraints, the bit decompositions and the tags
+// maxbit and binary, and are propagated through the constraints that
+// compute a signal from others
+pub fn analyse(dag: &DAG) -> ReportCollection {
+    let field = UsefulConstants::new(&dag.prime).get_p().clone();
+    let (_, occurrences, ranges) = propagate(dag, &field);
+    let decomposed = decomposed_signals(dag, &occurrences, &field);
+    let mut reports = ReportCollection::new();
+    let mut reported = HashSet::new();
+    for (node, index, offset) in occurrences.iter().cloned() {
+        let constraint = &dag.nodes[node].constraints[index];
+        if reported.contains(&(node, index)) || !Constraint::is_linear(constraint) {
+            continue;
+        }
+        if let Option::Some((bits, unknown)) = overflowing_comparison(constraint, offset, &decomposed, &ranges, &field) {
+            reported.insert((node, index));
+            reports.push(overflow_report(dag, node, index, offset, bits, &unknown));
+        }
+    }
+    reports
+}
+
+// The ranges of the signals of each node, by their ids in the node, that
+// hold in all its instances
+pub fn node_ranges(dag: &DAG) -> HashMap<usize, HashMap<usize, Range>> {
+    let field = UsefulConstants::new(&dag.prime).get_p().clone();
+    let (instances, _, ranges) = propagate(dag, &field);
+    let mut nodes: HashMap<usize, HashMap<usize, Option<Range>>> = HashMap::new();
+    for (node, offset) in instances {
+        let known = nodes.entry(node).or_default();
+        let is_first = known.is_empty();
+        for signal in dag.nodes[node].signal_correspondence.values() {
+            let range = ranges.get(signal + offset);
+            let hull = match (known.get(signal), range) {
+                (Option::None, Option::Some(range)) if is_first => Option::Some(range.clone()),
+                (Option::Some(Option::Some(current)), Option::Some(range)) => Option::Some(Range {
+                    min: std::cmp::min(&current.min, &range.min).clone(),
+                    max: std::cmp::max(&current.max, &range.max).clone(),
+                    support: Option::None,
+                }),
+                _ => Option::None,
+            };
+            known.insert(*signal, hull);
+        }
+    }
+    nodes
+        .into_iter()
+        .map(|(node, known)| (node, known.into_iter().filter_map(|(signal, range)| range.map(|range| (signal, range))).collect()))
+        .collect()
+}
+
+// The instances of the nodes, with the offsets of their signals, and the
+// ranges that the constraints of all of them give to the signals
+fn propagate(dag: &DAG, field: &BigInt) -> (Vec<(usize, usize)>, Vec<Occurrence>, Ranges) {
+    let mut instances = Vec::new();
+    let entry = dag.get_entry().unwrap();
+    collect_instances(dag, entry.goes_to, entry.in_number, &mut instances);
+    let mut occurrences: Vec<Occurrence> = Vec::new();
+    let mut ranges = Ranges::new(field);
+    for (node, offset) in instances.iter().cloned() {
+        for (signal, bits) in dag.nodes[node].tag_bounds() {
+            let max = (BigInt::one() << *bits) - BigInt::one();
+            if max < *field {
+                ranges.bound(signal + offset, Range { min: BigInt::zero(), max, support: Option::None });
+            }
+        }
+        for index in 0..dag.nodes[node].constraints.len() {
+            occurrences.push((node, index, offset));
+        }
+    }
+    for _ in 0..MAX_ROUNDS {
+        let mut changed = false;
+        for (position, (node, index, offset)) in occurrences.iter().enumerate() {
+            changed |= ranges.derive(&dag.nodes[*node].constraints[*index], *offset, position);
+        }
+        if !changed {
+            break;
+        }
+    }
+    (instances, occurrences, ranges)
+}
+
+fn collect_instances(dag: &DAG, node: usize, offset: usize, instances: &mut Vec<(usize, usize)>) {
+    if dag.nodes[node].is_custom_gate() {
+        return;
+    }
+    instances.push((node, offset));
+    for edge in &dag.adjacency[node] {
+        collect_instances(dag, edge.goes_to, offset + edge.in_number, instances);
+    }
+}
+
+// the representative of the value between -p/2 and p/2
+fn signed(value: &BigInt, field: &BigInt) -> BigInt {
+    let value = ((value % field) + field) % field;
+    if value > field / 2 {
+        value - field
+    } else {
+        value
+    }
+}
+
+// The signals that a linear constraint decomposes in bits, as the input of
+// Num2Bits, with the number of bits and the largest value that they give
+fn decomposed_signals(dag: &DAG, occurrences: &[Occurrence], field: &BigInt) -> HashMap<usize, (usize, BigInt)> {
+    let mut bits = HashSet::new();
+    for (node, index, offset) in occurrences {
+        if let Option::Some(signal) = bit_signal(&dag.nodes[*node].constraints[*index], field) {
+            bits.insert(signal + offset);
+        }
+    }
+    let mut decomposed = HashMap::new();
+    for (node, index, offset) in occurrences {
+        let constraint = &dag.nodes[*node].constraints[*index];
+        if !Constraint::is_linear(constraint) {
+            continue;
+        }
+        let signals: Vec<usize> = constraint.take_cloned_signals().into_iter().collect();
+        let values: Vec<usize> = signals.iter().cloned().filter(|signal| !bits.contains(&(signal + offset))).collect();
+        let value = match values.as_slice() {
+            [value] if signals.len() > 2 => *value,
+            _ => continue,
+        };
+        let coefficient = signed(&constraint.c()[&value], field);
+        if coefficient.abs() != BigInt::one() {
+            continue;
+        }
+        let coefficients: Vec<&BigInt> = signals.iter().filter(|signal| **signal != value).map(|signal| &constraint.c()[signal]).collect();
+        if is_binary_decomposition(coefficients.iter().cloned(), field) {
+            let max = coefficients.iter().map(|coefficient| signed(coefficient, field).abs()).sum();
+            decomposed.insert(value + offset, (coefficients.len(), max));
+        }
+    }
+    decomposed
+}
+
+// The number of bits of the comparison computed by the linear constraint,
+// when its difference may not fit in them, with the signals of the
+// difference whose ranges are not known
+fn overflowing_comparison(
+    constraint: &Constraint,
+    offset: usize,
+    decomposed: &HashMap<usize, (usize, BigInt)>,
+    ranges: &Ranges,
+    field: &BigInt,
+) -> Option<(usize, Vec<usize>)> {
+    let constant = Constraint::constant_coefficient();
+    for (signal, coefficient) in constraint.c() {
+        let (bits, max) = match decomposed.get(&(signal + offset)) {
+            Option::Some(decomposition) if *signal != constant => decomposition,
+            _ => continue,
+        };
+        let coefficient = signed(coefficient, field);
+        if coefficient.abs() != BigInt::one() {
+            continue;
+        }
+        // the signs of the terms of the difference, as signal = -rest
+        let mut signs = HashSet::new();
+        let mut unknown = Vec::new();
+        for (other, value) in constraint.c() {
+            if other == signal || *other == constant {
+                continue;
+            }
+            signs.insert((-&coefficient * signed(value, field)) > BigInt::zero());
+            if ranges.get(other + offset).is_none() {
+                unknown.push(*other);
+            }
+        }
+        if signs.len() < 2 {
+            continue;
+        }
+        let fits = match ranges.linear_range(constraint.c(), offset, Option::Some(*signal)) {
+            Option::Some(rest) => {
+                let (min, max_value) = if coefficient == BigInt::one() { (-rest.max, -rest.min) } else { (rest.min, rest.max) };
+                min >= BigInt::zero() && max_value <= *max
+            }
+            Option::None => false,
+        };
+        if !fits {
+            unknown.sort();
+            return Option::Some((*bits, unknown));
+        }
+    }
+    Option::None
+}
+
+fn overflow_report(dag: &DAG, node: usize, index: usize, offset: usize, bits: usize, unknown: &[usize]) -> Report {
+    let node_data = &dag.nodes[node];
+    let message = format!(
+        "In template \"{}\": the difference that the comparison decomposes in {} bits may not fit in them, so its result can be wrong for values that overflow the field",
+        node_data.template_name, bits
+    );
+    let mut report = Report::warning(message, ReportCode::ComparisonOverflow);
+    let origin: &ConstraintOrigin = &node_data.constraint_origins[index];
+    if let Option::Some((file_id, location)) = origin {
+        report.add_primary(location.clone(), *file_id, "may overflow".to_string());
+    }
+    if !unknown.is_empty() {
+        let mut names: HashMap<usize, &String> = HashMap::new();
+        for (name, signal) in &node_data.signal_correspondence {
+            if names.get(signal).is_none_or(|current| (name.len(), name) < (current.len(), *current)) {
+                names.insert(*signal, name);
+            }
+        }
+        let unknown: Vec<String> = unknown.iter().map(|signal| names.get(signal).map_or(format!("signal {}", signal + offset), |name| name.to_string())).collect();
+        report.add_note(format!(
+            "The ranges of {} are not known: bound them with a bit decomposition or the tag maxbit.",
+            unknown.join(", ")
+        ));
+    }
+    report
+}
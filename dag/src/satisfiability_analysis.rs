@@ -0,0 +1,369 @@
+use super::{ConstraintOrigin, DAG};
+use circom_algebra::modular_arithmetic::{add, div, mul, sub};
+use circom_algebra::num_bigint::BigInt;
+use program_structure::constants::UsefulConstants;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+type Constraint = circom_algebra::algebra::Constraint<usize>;
+// a constraint of an instance of a node: the node, its position in the node
+// and the offset of the signals of the instance
+type Occurrence = (usize, usize, usize);
+// the constraint of a node, by the node and its position in it
+type ConstraintId = (usize, usize);
+
+pub struct AnalysisResult {
+    pub errors: ReportCollection,
+    pub warnings: ReportCollection,
+}
+
+// The constraints of the circuit that can never be satisfied, since they
+// are false for the values that the rest of the constraints force on their
+// signals, which are errors, and the constraints that are always satisfied,
+// since they follow from the rest of the constraints or repeat another
+// constraint of their template. The linear constraints of two signals relate
+// their values, as the connections of the subcomponents, and the rest of the
+// constraints force the value of their only signal that is not known yet when
+// it is linear in it
+pub fn analyse(dag: &DAG) -> AnalysisResult {
+    let field = UsefulConstants::new(&dag.prime).get_p().clone();
+    let mut occurrences = Vec::new();
+    let entry = dag.get_entry().unwrap();
+    collect_occurrences(dag, entry.goes_to, entry.in_number, &mut occurrences);
+    let duplicates = duplicated_constraints(dag, &field);
+
+    let mut values = Values::new(field);
+    let mut pending = Vec::new();
+    for (node, index, offset) in occurrences.iter().cloned() {
+        let constraint = &dag.nodes[node].constraints[index];
+        if duplicates.contains(&(node, index)) {
+            values.satisfied.push((node, index));
+        } else if !values.relate(constraint, offset, (node, index)) {
+            pending.push((node, index, offset));
+        }
+    }
+
+    let mut watchers: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (position, (node, index, offset)) in pending.iter().enumerate() {
+        for signal in dag.nodes[*node].constraints[*index].take_cloned_signals() {
+            let (root, ..) = values.find(global_signal(signal, *offset));
+            watchers.entry(root).or_default().push(position);
+        }
+    }
+    let mut queue: VecDeque<usize> = (0..pending.len()).collect();
+    let mut done = vec![false; pending.len()];
+    while let Option::Some(position) = queue.pop_front() {
+        if done[position] {
+            continue;
+        }
+        let (node, index, offset) = pending[position];
+        match values.evaluate(&dag.nodes[node].constraints[index], offset, (node, index)) {
+            Evaluation::Pending => {}
+            Evaluation::Done => done[position] = true,
+            Evaluation::Fixed(root) => {
+                done[position] = true;
+                if let Option::Some(positions) = watchers.remove(&root) {
+                    queue.extend(positions);
+                }
+            }
+        }
+    }
+
+    let mut errors = ReportCollection::new();
+    let mut reported = HashSet::new();
+    for (constraint, causes) in values.contradictions {
+        if reported.insert(constraint) {
+            errors.push(contradiction_report(dag, constraint, &causes));
+        }
+    }
+    let warnings = satisfied_reports(dag, &occurrences, &values.satisfied);
+    AnalysisResult { errors, warnings }
+}
+
+// The constraints of the nodes that repeat a previous constraint of the
+// node, up to the products of its sides by constants
+fn duplicated_constraints(dag: &DAG, field: &BigInt) -> HashSet<ConstraintId> {
+    // the side divided by its coefficient of the smallest signal, with it
+    let normalized = |side: &HashMap<usize, BigInt>| {
+        let mut side: Vec<_> = side.iter().map(|(signal, value)| (*signal, value.clone())).collect();
+        side.sort();
+        let first = side.first().map_or(BigInt::from(1), |(_, value)| value.clone());
+        for (_, value) in &mut side {
+            *value = div(value, &first, field).ok().unwrap();
+        }
+        (side, first)
+    };
+    let mut duplicates = HashSet::new();
+    for (node_id, node) in dag.nodes.iter().enumerate() {
+        let mut seen = HashSet::new();
+        for (index, constraint) in node.constraints.iter().enumerate() {
+            let ((a, first_a), (b, first_b)) = (normalized(constraint.a()), normalized(constraint.b()));
+            let (c, first_c) = normalized(constraint.c());
+            // the constant that multiplies c when a and b are normalized
+            let factor = if a.is_empty() || c.is_empty() { BigInt::from(1) } else { div(&first_c, &mul(&first_a, &first_b, field), field).ok().unwrap() };
+            let (a, b) = if a <= b { (a, b) } else { (b, a) };
+            if !seen.insert((a, b, c, factor)) {
+                duplicates.insert((node_id, index));
+            }
+        }
+    }
+    duplicates
+}
+
+fn collect_occurrences(dag: &DAG, node: usize, offset: usize, occurrences: &mut Vec<Occurrence>) {
+    if dag.nodes[node].is_custom_gate() {
+        return;
+    }
+    for index in 0..dag.nodes[node].constraints.len() {
+        occurrences.push((node, index, offset));
+    }
+    for edge in &dag.adjacency[node] {
+        collect_occurrences(dag, edge.goes_to, offset + edge.in_number, occurrences);
+    }
+}
+
+fn global_signal(signal: usize, offset: usize) -> usize {
+    if signal == Constraint::constant_coefficient() {
+        signal
+    } else {
+        signal + offset
+    }
+}
+
+// The values of the signals, as affine maps of the values of the roots of
+// their classes, and the values of the roots that the constraints force
+struct Values {
+    field: BigInt,
+    // the value of a signal is a * value(parent) + b
+    parents: HashMap<usize, (usize, BigInt, BigInt)>,
+    fixed: HashMap<usize, (BigInt, ConstraintId)>,
+    contradictions: Vec<(ConstraintId, Vec<ConstraintId>)>,
+    // the constraints of the instances that are always satisfied
+    satisfied: Vec<ConstraintId>,
+}
+
+enum Evaluation {
+    Pending,
+    Done,
+    Fixed(usize),
+}
+
+impl Values {
+    fn new(field: BigInt) -> Values {
+        Values { field, parents: HashMap::new(), fixed: HashMap::new(), contradictions: Vec::new(), satisfied: Vec::new() }
+    }
+
+    // the root of the class of the signal, with the map from its value to
+    // the value of the signal
+    fn find(&mut self, signal: usize) -> (usize, BigInt, BigInt) {
+        let mut path = Vec::new();
+        let mut current = signal;
+        while let Option::Some((parent, ..)) = self.parents.get(&current) {
+            path.push(current);
+            current = *parent;
+        }
+        let root = current;
+        let (mut a, mut b) = (BigInt::from(1), BigInt::from(0));
+        for signal in path.into_iter().rev() {
+            let (_, pa, pb) = self.parents.get(&signal).unwrap();
+            b = add(&mul(pa, &b, &self.field), pb, &self.field);
+            a = mul(pa, &a, &self.field);
+            self.parents.insert(signal, (root, a.clone(), b.clone()));
+        }
+        (root, a, b)
+    }
+
+    // relates the classes of the signals of a linear constraint of two
+    // signals, returning false for the rest of the constraints
+    fn relate(&mut self, constraint: &Constraint, offset: usize, id: ConstraintId) -> bool {
+        let constant = Constraint::constant_coefficient();
+        let signals: Vec<_> = constraint.c().keys().filter(|signal| **signal != constant).cloned().collect();
+        if !Constraint::is_linear(constraint) || signals.len() != 2 {
+            return false;
+        }
+        let c = constraint.c().get(&constant).cloned().unwrap_or_default();
+        let (rx, ax, bx) = self.find(global_signal(signals[0], offset));
+        let (ry, ay, by) = self.find(global_signal(signals[1], offset));
+        let kx = &constraint.c()[&signals[0]];
+        let ky = &constraint.c()[&signals[1]];
+        // alpha * rx + beta * ry + gamma = 0
+        let alpha = mul(kx, &ax, &self.field);
+        let beta = mul(ky, &ay, &self.field);
+        let gamma = add(&add(&mul(kx, &bx, &self.field), &mul(ky, &by, &self.field), &self.field), &c, &self.field);
+        let zero = BigInt::from(0);
+        if alpha == zero || beta == zero {
+            return false;
+        }
+        if rx == ry {
+            self.solve(rx, &add(&alpha, &beta, &self.field), &gamma, id);
+        } else {
+            // rx = a * ry + b
+            let a = div(&sub(&zero, &beta, &self.field), &alpha, &self.field).ok().unwrap();
+            let b = div(&sub(&zero, &gamma, &self.field), &alpha, &self.field).ok().unwrap();
+            self.parents.insert(rx, (ry, a.clone(), b.clone()));
+            if let Option::Some((value, cause)) = self.fixed.remove(&rx) {
+                self.fix(ry, &sub(&value, &b, &self.field), &a, cause);
+            }
+        }
+        true
+    }
+
+    // forces linear * root + constant = 0, with the constraint as its cause
+    fn solve(&mut self, root: usize, linear: &BigInt, constant: &BigInt, id: ConstraintId) {
+        let zero = BigInt::from(0);
+        if *linear != zero {
+            self.fix(root, &sub(&zero, constant, &self.field), linear, id);
+        } else if *constant != zero {
+            self.contradictions.push((id, Vec::new()));
+        } else {
+            self.satisfied.push(id);
+        }
+    }
+
+    // forces the value of the root to numerator / denominator
+    fn fix(&mut self, root: usize, numerator: &BigInt, denominator: &BigInt, cause: ConstraintId) {
+        let value = div(numerator, denominator, &self.field).ok().unwrap();
+        match self.fixed.get(&root) {
+            Option::Some((previous, previous_cause)) if *previous != value => {
+                self.contradictions.push((cause, vec![*previous_cause]));
+            }
+            Option::Some(_) => {}
+            Option::None => {
+                self.fixed.insert(root, (value, cause));
+            }
+        }
+    }
+
+    // checks a constraint with the known values, forcing the value of its
+    // only unknown class when it is linear in it
+    fn evaluate(&mut self, constraint: &Constraint, offset: usize, id: ConstraintId) -> Evaluation {
+        let mut unknown = Option::None;
+        let mut causes = Vec::new();
+        let mut sides = Vec::new();
+        for side in [constraint.a(), constraint.b(), constraint.c()] {
+            // the side is known + linear * root
+            let (mut known, mut linear) = (BigInt::from(0), BigInt::from(0));
+            for (signal, coefficient) in side {
+                if *signal == Constraint::constant_coefficient() {
+                    known = add(&known, coefficient, &self.field);
+                    continue;
+                }
+                let (root, a, b) = self.find(global_signal(*signal, offset));
+                if let Option::Some((value, cause)) = self.fixed.get(&root) {
+                    let value = add(&mul(&a, value, &self.field), &b, &self.field);
+                    known = add(&known, &mul(coefficient, &value, &self.field), &self.field);
+                    causes.push(*cause);
+                } else if unknown.is_none_or(|other| other == root) {
+                    unknown = Option::Some(root);
+                    known = add(&known, &mul(coefficient, &b, &self.field), &self.field);
+                    linear = add(&linear, &mul(coefficient, &a, &self.field), &self.field);
+                } else {
+                    return Evaluation::Pending;
+                }
+            }
+            sides.push((known, linear));
+        }
+        let (a0, a1) = &sides[0];
+        let (b0, b1) = &sides[1];
+        let (c0, c1) = &sides[2];
+        let zero = BigInt::from(0);
+        // a1 * b1 * root^2 + (a0 * b1 + a1 * b0 - c1) * root + a0 * b0 - c0 = 0
+        let quadratic = mul(a1, b1, &self.field);
+        let linear = sub(&add(&mul(a0, b1, &self.field), &mul(a1, b0, &self.field), &self.field), c1, &self.field);
+        let constant = sub(&mul(a0, b0, &self.field), c0, &self.field);
+        match unknown {
+            Option::Some(root) if quadratic == zero && linear != zero => {
+                self.solve(root, &linear, &constant, id);
+                Evaluation::Fixed(root)
+            }
+            Option::Some(_) if quadratic != zero => Evaluation::Pending,
+            _ if constant != zero => {
+                self.contradictions.push((id, causes));
+                Evaluation::Done
+            }
+            _ => {
+                self.satisfied.push(id);
+                Evaluation::Done
+            }
+        }
+    }
+}
+
+// The statements whose constraints are always satisfied in all the instances
+// of their templates, and the number of these constraints in the circuit
+fn satisfied_reports(dag: &DAG, occurrences: &[Occurrence], satisfied: &[ConstraintId]) -> ReportCollection {
+    let mut instances: HashMap<ConstraintId, usize> = HashMap::new();
+    for (node, index, _) in occurrences {
+        *instances.entry((*node, *index)).or_default() += 1;
+    }
+    let mut satisfied_instances: HashMap<ConstraintId, usize> = HashMap::new();
+    for constraint in satisfied {
+        *satisfied_instances.entry(*constraint).or_default() += 1;
+    }
+    let mut reports = ReportCollection::new();
+    if satisfied.is_empty() {
+        return reports;
+    }
+    // the statements of the templates with their constraints always satisfied
+    let mut statements: Vec<((usize, &ConstraintOrigin), usize)> = Vec::new();
+    let mut positions: HashMap<(usize, &ConstraintOrigin), usize> = HashMap::new();
+    for (id, count) in &satisfied_instances {
+        if instances[id] == *count {
+            let statement = (id.0, &dag.nodes[id.0].constraint_origins[id.1]);
+            match positions.get(&statement) {
+                Option::Some(position) => statements[*position].1 += 1,
+                Option::None => {
+                    positions.insert(statement, statements.len());
+                    statements.push((statement, 1));
+                }
+            }
+        }
+    }
+    statements.sort_by_key(|((node, origin), _)| (*node, origin.as_ref().map(|(file, location)| (*file, location.start))));
+    for ((node, origin), count) in statements {
+        let message = if count == 1 {
+            format!("In template \"{}\": the constraint is always satisfied, since it follows from other constraints", dag.nodes[node].template_name)
+        } else {
+            format!("In template \"{}\": {} constraints of the statement are always satisfied, since they follow from other constraints", dag.nodes[node].template_name, count)
+        };
+        let mut report = Report::warning(message, ReportCode::SatisfiedConstraint);
+        if let Option::Some((file_id, location)) = origin {
+            report.add_primary(location.clone(), *file_id, "always satisfied".to_string());
+        }
+        reports.push(report);
+    }
+    let message = format!(
+        "{} of the {} constraints of the circuit are always satisfied, since they follow from other constraints",
+        satisfied.len(),
+        occurrences.len()
+    );
+    reports.push(Report::warning(message, ReportCode::SatisfiedConstraint));
+    reports
+}
+
+fn contradiction_report(dag: &DAG, constraint: ConstraintId, causes: &[ConstraintId]) -> Report {
+    let origin = |(node, index): ConstraintId| -> &ConstraintOrigin { &dag.nodes[node].constraint_origins[index] };
+    let template = &dag.nodes[constraint.0].template_name;
+    let message = if causes.is_empty() {
+        format!("In template \"{}\": the constraint can never be satisfied, so the circuit has no valid witness", template)
+    } else {
+        format!(
+            "In template \"{}\": the constraint can never be satisfied with the values that other constraints force on its signals, so the circuit has no valid witness",
+            template
+        )
+    };
+    let mut report = Report::error(message, ReportCode::ContradictoryConstraint);
+    if let Option::Some((file_id, location)) = origin(constraint) {
+        report.add_primary(location.clone(), *file_id, "this constraint is never satisfied".to_string());
+    }
+    let mut shown = HashSet::new();
+    for cause in causes {
+        if let Option::Some((file_id, location)) = origin(*cause) {
+            if *cause != constraint && shown.insert(*cause) {
+                report.add_secondary(location.clone(), *file_id, Option::Some("forces the value of a signal".to_string()));
+            }
+        }
+    }
+    report
+}
@@ -0,0 +1,35 @@
+use super::{Tree, DAG};
+use constraint_writers::smt_writer::*;
+use std::collections::HashMap;
+use std::io;
+
+pub fn write(dag: &DAG, file_name: &str, encoding: SmtEncoding) -> io::Result<()> {
+    let tree = Tree::new(dag);
+    let mut smt = SmtFile::new(file_name, &tree.field, encoding)?;
+    declare_signals(&tree, &mut smt)?;
+    write_constraints(&tree, &mut smt)?;
+    SmtFile::finish_writing(smt)
+}
+
+fn declare_signals(tree: &Tree, smt: &mut SmtFile) -> io::Result<()> {
+    for signal in &tree.signals {
+        let name = HashMap::get(&tree.id_to_name, signal).unwrap();
+        SmtFile::declare_signal(smt, *signal, &format!("{}.{}", tree.path, name))?;
+    }
+    for edge in Tree::get_edges(tree) {
+        let subtree = Tree::go_to_subtree(tree, edge);
+        declare_signals(&subtree, smt)?;
+    }
+    Ok(())
+}
+
+fn write_constraints(tree: &Tree, smt: &mut SmtFile) -> io::Result<()> {
+    for constraint in &tree.constraints {
+        SmtFile::write_constraint(smt, constraint.a(), constraint.b(), constraint.c())?;
+    }
+    for edge in Tree::get_edges(tree) {
+        let subtree = Tree::go_to_subtree(tree, edge);
+        write_constraints(&subtree, smt)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use super::DAG;
+use circom_algebra::algebra::Constraint;
+use circom_algebra::num_bigint::BigInt;
+use json::JsonValue;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+// The constraints and signals of an instance of a template, its own ones and
+// the ones of its subtree of components, before the simplification
+#[derive(Default)]
+struct InstanceCount {
+    components: usize,
+    signals: usize,
+    linear: usize,
+    non_linear: usize,
+    total_signals: usize,
+    total_linear: usize,
+    total_non_linear: usize,
+}
+
+#[derive(Default)]
+struct TemplateCount {
+    name: String,
+    instances: usize,
+    components: usize,
+    signals: usize,
+    linear: usize,
+    non_linear: usize,
+}
+
+impl TemplateCount {
+    fn constraints(&self) -> usize {
+        self.linear + self.non_linear
+    }
+}
+
+fn count_instances(dag: &DAG) -> Vec<InstanceCount> {
+    let mut counts: Vec<InstanceCount> = Vec::with_capacity(dag.nodes.len());
+    for node in &dag.nodes {
+        let linear = node.constraints().iter().filter(|c| Constraint::is_linear(c)).count();
+        let non_linear = node.constraints().len() - linear;
+        let signals = node.number_of_inputs() + node.number_of_outputs() + node.number_of_intermediates();
+        counts.push(InstanceCount {
+            signals,
+            linear,
+            non_linear,
+            total_signals: node.number_of_signals(),
+            total_linear: linear,
+            total_non_linear: non_linear,
+            ..InstanceCount::default()
+        });
+    }
+    // the subcomponents of a node have lower ids, so they are counted before it
+    for node in 0..dag.nodes.len() {
+        for edge in &dag.adjacency[node] {
+            let (linear, non_linear) = (counts[edge.goes_to].total_linear, counts[edge.goes_to].total_non_linear);
+            counts[node].total_linear += linear;
+            counts[node].total_non_linear += non_linear;
+        }
+    }
+    // the number of components of each instance, from the main component down
+    if let Some(main) = counts.last_mut() {
+        main.components = 1;
+    }
+    for node in (0..dag.nodes.len()).rev() {
+        for edge in &dag.adjacency[node] {
+            counts[edge.goes_to].components += counts[node].components;
+        }
+    }
+    counts
+}
+
+fn count_templates(dag: &DAG, instances: &[InstanceCount]) -> Vec<TemplateCount> {
+    let mut templates: Vec<TemplateCount> = Vec::new();
+    for (node, instance) in dag.nodes.iter().zip(instances) {
+        // the names of the instances carry their parameters, as Num2Bits(254)
+        let name = node.template_name().split('(').next().unwrap_or_default();
+        let position = match templates.iter().position(|t| t.name == name) {
+            Some(position) => position,
+            None => {
+                templates.push(TemplateCount { name: name.to_string(), ..TemplateCount::default() });
+                templates.len() - 1
+            }
+        };
+        let template = &mut templates[position];
+        template.instances += 1;
+        template.components += instance.components;
+        template.signals += instance.components * instance.signals;
+        template.linear += instance.components * instance.linear;
+        template.non_linear += instance.components * instance.non_linear;
+    }
+    templates.sort_by(|t0, t1| t1.constraints().cmp(&t0.constraints()).then(t0.name.cmp(&t1.name)));
+    templates
+}
+
+// The constraints of an instance of a template, the ones it generates and
+// the ones of its subtree of components, with its number of components
+pub struct InstanceConstraints {
+    pub template: String,
+    pub parameters: Vec<BigInt>,
+    pub components: usize,
+    pub constraints: usize,
+    pub total_constraints: usize,
+}
+
+pub fn instance_constraints(dag: &DAG) -> Vec<InstanceConstraints> {
+    let instances = count_instances(dag);
+    dag.nodes
+        .iter()
+        .zip(instances)
+        .map(|(node, count)| InstanceConstraints {
+            template: node.template_name().split('(').next().unwrap_or_default().to_string(),
+            parameters: node.parameters().clone(),
+            components: count.components,
+            constraints: count.linear + count.non_linear,
+            total_constraints: count.total_linear + count.total_non_linear,
+        })
+        .collect()
+}
+
+fn instance_as_json(dag: &DAG, node: usize, count: &InstanceCount) -> JsonValue {
+    let parameters: Vec<JsonValue> =
+        dag.nodes[node].parameters().iter().map(|p| p.to_str_radix(10).into()).collect();
+    json::object! {
+        "template" => dag.nodes[node].template_name(),
+        "parameters" => parameters,
+        "components" => count.components,
+        "signals" => count.signals,
+        "linear" => count.linear,
+        "non_linear" => count.non_linear,
+        "total_signals" => count.total_signals,
+        "total_linear" => count.total_linear,
+        "total_non_linear" => count.total_non_linear,
+    }
+}
+
+fn template_as_json(count: &TemplateCount) -> JsonValue {
+    json::object! {
+        "template" => count.name.as_str(),
+        "instances" => count.instances,
+        "components" => count.components,
+        "signals" => count.signals,
+        "linear" => count.linear,
+        "non_linear" => count.non_linear,
+    }
+}
+
+// The constraints and signals that each template adds to the circuit, over
+// all its components, and the ones of each instance of a template, that is,
+// of each template with concrete parameters
+pub fn write_template_report(dag: &DAG, file: &str) -> io::Result<()> {
+    let instances = count_instances(dag);
+    let templates = count_templates(dag, &instances);
+    let mut order: Vec<usize> = (0..instances.len()).collect();
+    order.sort_by_key(|node| {
+        let count = &instances[*node];
+        std::cmp::Reverse(count.components * (count.linear + count.non_linear))
+    });
+    let report = json::object! {
+        "templates" => templates.iter().map(template_as_json).collect::<Vec<_>>(),
+        "instances" => order.iter().map(|node| instance_as_json(dag, *node, &instances[*node])).collect::<Vec<_>>(),
+    };
+    let file = File::create(file)?;
+    let mut writer = BufWriter::new(file);
+    writer.write_all(report.pretty(4).as_bytes())?;
+    writer.flush()
+}
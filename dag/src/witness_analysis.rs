@@ -0,0 +1,223 @@
+use circom_algebra::algebra::Constraint;
+use circom_algebra::modular_arithmetic::{div, mul, sub};
+use circom_algebra::num_bigint::BigInt;
+use circom_algebra::num_traits::Zero;
+pub use circom_algebra::range_analysis::bit_signal;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Display;
+use std::hash::Hash;
+
+// The largest system of linear constraints that is reduced to find the
+// signals that it determines
+const MAX_ELIMINATION_CONSTRAINTS: usize = 256;
+
+// The signals assigned with <-- whose values are not determined by the
+// constraints, given the rest of the signals of the template. A constraint
+// A * B - C = 0 determines its only signal that is not determined yet unless
+// the signal is in A and in B, as in x * (x - 1) === 0. A linear constraint
+// also determines several signals that are only 0 or 1 when the absolute
+// values of their coefficients are greater than the sum of the smaller ones,
+// as the bits of Num2Bits
+pub fn undetermined_signals<C>(constraints: &[Constraint<C>], witness_signals: &HashSet<C>, field: &BigInt) -> HashSet<C>
+where
+    C: Default + Clone + Display + Hash + Eq,
+{
+    undetermined_signals_with_components(constraints, witness_signals, &[], field)
+}
+
+// The same, where the outputs of each component, given with its inputs, are
+// determined when all its inputs are. When the rest of the rules determine
+// no more signals, the linear constraints of the remaining signals are
+// reduced as a system of equations, if it is small enough
+pub fn undetermined_signals_with_components<C>(
+    constraints: &[Constraint<C>],
+    unknown_signals: &HashSet<C>,
+    components: &[(Vec<C>, Vec<C>)],
+    field: &BigInt,
+) -> HashSet<C>
+where
+    C: Default + Clone + Display + Hash + Eq,
+{
+    let bits: HashSet<C> = constraints.iter().filter_map(|constraint| bit_signal(constraint, field)).collect();
+    let mut analysis = Analysis {
+        undetermined: unknown_signals.clone(),
+        watchers: HashMap::new(),
+        component_watchers: HashMap::new(),
+        pending_inputs: Vec::new(),
+        queue: (0..constraints.len()).collect(),
+    };
+    for (position, constraint) in constraints.iter().enumerate() {
+        for signal in constraint.take_cloned_signals() {
+            if analysis.undetermined.contains(&signal) {
+                analysis.watchers.entry(signal).or_default().push(position);
+            }
+        }
+    }
+    let mut settled = Vec::new();
+    for (position, (inputs, outputs)) in components.iter().enumerate() {
+        let pending: Vec<_> = inputs.iter().filter(|signal| analysis.undetermined.contains(signal)).collect();
+        for signal in &pending {
+            analysis.component_watchers.entry((*signal).clone()).or_default().push(position);
+        }
+        analysis.pending_inputs.push(pending.len());
+        if pending.is_empty() {
+            settled.extend(outputs.iter().cloned());
+        }
+    }
+    analysis.settle(settled, components);
+
+    loop {
+        while let Option::Some(position) = analysis.queue.pop_front() {
+            let constraint = &constraints[position];
+            let unknowns = analysis.unknowns(constraint, field);
+            let is_determined = match unknowns.as_slice() {
+                [] => false,
+                [signal] => !(appears(constraint.a(), signal, field) && appears(constraint.b(), signal, field)),
+                _ => unknowns.iter().all(|signal| {
+                        bits.contains(signal) && !appears(constraint.a(), signal, field) && !appears(constraint.b(), signal, field)
+                    }) && is_binary_decomposition(unknowns.iter().map(|signal| &constraint.c()[signal]), field),
+            };
+            if is_determined {
+                analysis.settle(unknowns, components);
+            }
+        }
+        let determined = analysis.eliminate(constraints, field);
+        if determined.is_empty() {
+            break;
+        }
+        analysis.settle(determined, components);
+    }
+    analysis.undetermined
+}
+
+struct Analysis<C> {
+    undetermined: HashSet<C>,
+    // the constraints and the components of each signal
+    watchers: HashMap<C, Vec<usize>>,
+    component_watchers: HashMap<C, Vec<usize>>,
+    // the number of undetermined inputs of each component
+    pending_inputs: Vec<usize>,
+    queue: VecDeque<usize>,
+}
+
+impl<C> Analysis<C>
+where
+    C: Default + Clone + Display + Hash + Eq,
+{
+    fn unknowns(&self, constraint: &Constraint<C>, field: &BigInt) -> Vec<C> {
+        constraint
+            .take_cloned_signals()
+            .into_iter()
+            .filter(|signal| self.undetermined.contains(signal))
+            .filter(|signal| {
+                appears(constraint.a(), signal, field) || appears(constraint.b(), signal, field) || appears(constraint.c(), signal, field)
+            })
+            .collect()
+    }
+
+    // marks the signals as determined, with the outputs of the components
+    // whose inputs are all determined then
+    fn settle(&mut self, mut signals: Vec<C>, components: &[(Vec<C>, Vec<C>)]) {
+        while let Option::Some(signal) = signals.pop() {
+            if !self.undetermined.remove(&signal) {
+                continue;
+            }
+            if let Option::Some(positions) = self.watchers.remove(&signal) {
+                self.queue.extend(positions);
+            }
+            for component in self.component_watchers.remove(&signal).unwrap_or_default() {
+                self.pending_inputs[component] -= 1;
+                if self.pending_inputs[component] == 0 {
+                    signals.extend(components[component].1.iter().cloned());
+                }
+            }
+        }
+    }
+
+    // the signals that the linear constraints of several undetermined signals
+    // determine together, reducing them as a system of equations
+    fn eliminate(&self, constraints: &[Constraint<C>], field: &BigInt) -> Vec<C> {
+        let zero = BigInt::zero();
+        let mut system = Vec::new();
+        for constraint in constraints.iter().filter(|constraint| Constraint::is_linear(constraint)) {
+            let row: HashMap<C, BigInt> = constraint
+                .c()
+                .iter()
+                .filter(|(signal, value)| self.undetermined.contains(signal) && (*value % field) != zero)
+                .map(|(signal, value)| (signal.clone(), value.clone()))
+                .collect();
+            if row.len() > 1 {
+                system.push(row);
+            }
+        }
+        if system.len() > MAX_ELIMINATION_CONSTRAINTS {
+            return Vec::new();
+        }
+        // the rows of the reduced system, by their pivots
+        let mut reduced: Vec<(C, HashMap<C, BigInt>)> = Vec::new();
+        for mut row in system {
+            for (pivot, pivot_row) in &reduced {
+                if let Option::Some(factor) = row.get(pivot).cloned() {
+                    subtract_row(&mut row, pivot_row, &factor, field);
+                }
+            }
+            let pivot = match row.keys().next() {
+                Option::Some(pivot) => pivot.clone(),
+                Option::None => continue,
+            };
+            let inverse = div(&BigInt::from(1), &row[&pivot], field).ok().unwrap();
+            for value in row.values_mut() {
+                *value = mul(value, &inverse, field);
+            }
+            for (_, other) in &mut reduced {
+                if let Option::Some(factor) = other.get(&pivot).cloned() {
+                    subtract_row(other, &row, &factor, field);
+                }
+            }
+            reduced.push((pivot, row));
+        }
+        reduced.into_iter().filter(|(_, row)| row.len() == 1).map(|(pivot, _)| pivot).collect()
+    }
+}
+
+// row = row - factor * other, without the signals that get a zero coefficient
+fn subtract_row<C>(row: &mut HashMap<C, BigInt>, other: &HashMap<C, BigInt>, factor: &BigInt, field: &BigInt)
+where
+    C: Clone + Hash + Eq,
+{
+    for (signal, value) in other {
+        let current = row.get(signal).cloned().unwrap_or_default();
+        let result = sub(&current, &mul(factor, value, field), field);
+        if result.is_zero() {
+            row.remove(signal);
+        } else {
+            row.insert(signal.clone(), result);
+        }
+    }
+}
+
+fn appears<C: Hash + Eq>(coefficients: &HashMap<C, BigInt>, signal: &C, field: &BigInt) -> bool {
+    coefficients.get(signal).is_some_and(|value| !(value % field).is_zero())
+}
+
+// Whether the sums of different subsets of the coefficients are different,
+// as each one is greater than the sum of the smaller ones and they add up to
+// less than the prime
+pub fn is_binary_decomposition<'a>(coefficients: impl Iterator<Item = &'a BigInt>, field: &BigInt) -> bool {
+    let half = field / 2;
+    let mut values: Vec<BigInt> = coefficients
+        .map(|value| {
+            let value = ((value % field) + field) % field;
+            if value > half { field - value } else { value }
+        })
+        .collect();
+    values.sort();
+    let mut sum = BigInt::zero();
+    for value in values {
+        if value <= sum {
+            return false;
+        }
+        sum += value;
+    }
+    sum < *field
+}
@@ -0,0 +1,451 @@
+use super::tokens::{Kind, Token};
+
+const INDENT: &str = "    ";
+
+// the keywords that are followed by a space before a parenthesis, and that
+// do not end an operand, as return in return -x
+const KEYWORDS: [&str; 21] = [
+    "if",
+    "else",
+    "for",
+    "while",
+    "return",
+    "var",
+    "signal",
+    "input",
+    "output",
+    "component",
+    "template",
+    "function",
+    "bus",
+    "include",
+    "pragma",
+    "const",
+    "enum",
+    "tag",
+    "operator",
+    "extern",
+    "parallel",
+];
+const SPACED_KEYWORDS: [&str; 9] =
+    ["if", "for", "while", "return", "var", "signal", "input", "output", "component"];
+const ASSIGNMENTS: [&str; 18] = [
+    "=", "<==", "==>", "<--", "-->", "===", "+=", "-=", "*=", "/=", "\\=", "%=", "**=", "<<=",
+    ">>=", "&=", "|=", "^=",
+];
+// the binary operators by their precedence, from the lowest one
+const PRECEDENCE: [&[&str]; 8] = [
+    &["?", ":"],
+    &["||"],
+    &["&&"],
+    &["==", "!=", "<", ">", "<=", ">="],
+    &["|", "^", "&"],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "\\", "%", "**"],
+];
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Role {
+    Operand,
+    Keyword,
+    Open,
+    Close,
+    Block,
+    Comma,
+    Semicolon,
+    Dot,
+    Hash,
+    Prefix,
+    Postfix,
+    Binary,
+    Comment,
+}
+
+#[derive(Clone)]
+struct Item {
+    text: String,
+    role: Role,
+    space: bool,
+}
+
+#[derive(Clone, Default)]
+struct Line {
+    indent: usize,
+    items: Vec<Item>,
+}
+
+impl Line {
+    fn width(&self) -> usize {
+        let text: usize = self.items.iter().map(|item| item.text.len() + item.space as usize).sum();
+        self.indent * INDENT.len() + text
+    }
+
+    fn render(&self) -> String {
+        let mut text = INDENT.repeat(self.indent);
+        for (position, item) in self.items.iter().enumerate() {
+            if position > 0 && item.space {
+                text.push(' ');
+            }
+            text.push_str(&item.text);
+        }
+        text
+    }
+}
+
+// Whether each brace opens a block of statements, as the body of a template,
+// or a list that stays in the line, as the tags of a signal or the fields
+// of a bus. A block has statements, other blocks or is an empty body
+fn find_blocks(tokens: &[Token]) -> Vec<bool> {
+    struct Group {
+        open: usize,
+        statements: bool,
+    }
+    let mut blocks = vec![false; tokens.len()];
+    let mut groups: Vec<Group> = Vec::new();
+    let mut previous: Option<&Token> = None;
+    for (position, token) in tokens.iter().enumerate() {
+        if token.is_comment() {
+            continue;
+        }
+        if token.is("(") || token.is("[") || token.is("{") {
+            groups.push(Group { open: position, statements: false });
+        } else if token.is(";") {
+            if let Some(group) = groups.last_mut() {
+                group.statements = true;
+            }
+        } else if token.is(")") || token.is("]") || token.is("}") {
+            if let Some(group) = groups.pop() {
+                let open = &tokens[group.open];
+                let before = tokens[..group.open].iter().rev().find(|token| !token.is_comment());
+                let empty = previous.is_some_and(|previous| std::ptr::eq(previous, open));
+                let is_block = open.is("{")
+                    && (group.statements
+                        || blocks[group.open + 1..position].iter().any(|block| *block)
+                        || before.is_some_and(|before| {
+                            before.is(")") || before.is_word("else") || before.is_word("hint")
+                        }) && empty);
+                if is_block {
+                    blocks[group.open] = true;
+                    blocks[position] = true;
+                }
+                if let Some(parent) = groups.last_mut() {
+                    parent.statements |= is_block;
+                }
+            }
+        }
+        previous = Some(token);
+    }
+    blocks
+}
+
+fn role(token: &Token, is_block: bool, previous: Option<&Item>) -> Role {
+    // the operator of an operator definition, as operator + (Point, Point)
+    let after_operand = previous.is_some_and(|item| {
+        matches!(item.role, Role::Operand | Role::Close | Role::Postfix) || item.text == "operator"
+    });
+    match token.kind {
+        Kind::LineComment | Kind::BlockComment => Role::Comment,
+        Kind::Word if KEYWORDS.contains(&token.text.as_str()) => Role::Keyword,
+        Kind::Word | Kind::Number | Kind::Str => Role::Operand,
+        Kind::Symbol => match token.text.as_str() {
+            "{" | "}" if is_block => Role::Block,
+            "(" | "[" | "{" => Role::Open,
+            ")" | "]" | "}" => Role::Close,
+            "," => Role::Comma,
+            ";" => Role::Semicolon,
+            "." => Role::Dot,
+            "#" => Role::Hash,
+            "++" | "--" if after_operand => Role::Postfix,
+            "++" | "--" | "!" | "~" => Role::Prefix,
+            "+" | "-" if !after_operand => Role::Prefix,
+            _ => Role::Binary,
+        },
+    }
+}
+
+fn space_before(previous: &Item, text: &str, role: Role) -> bool {
+    if matches!(role, Role::Comma | Role::Semicolon | Role::Close | Role::Dot | Role::Postfix) {
+        return false;
+    }
+    if matches!(previous.role, Role::Dot | Role::Open | Role::Prefix | Role::Hash)
+        || previous.role == Role::Block && role == Role::Block
+    {
+        return false;
+    }
+    match (text, role) {
+        ("(", Role::Open) => match previous.role {
+            Role::Keyword => SPACED_KEYWORDS.contains(&previous.text.as_str()),
+            Role::Operand | Role::Close => false,
+            _ => true,
+        },
+        ("[", Role::Open) => {
+            previous.text == "public" || !matches!(previous.role, Role::Operand | Role::Close)
+        }
+        _ => true,
+    }
+}
+
+struct Layout {
+    lines: Vec<Line>,
+    current: Line,
+    indent: usize,
+    // the brackets that are open in the statement, and whether each one is
+    // the one of an attribute, as #[cfg(feature = "debug")]
+    open: Vec<bool>,
+    in_statement: bool,
+    break_pending: bool,
+}
+
+impl Layout {
+    fn finish_line(&mut self) {
+        if !self.current.items.is_empty() {
+            let line = std::mem::take(&mut self.current);
+            self.lines.push(line);
+        }
+        self.break_pending = false;
+    }
+
+    fn start_line(&mut self, token: &Token, role: Role) {
+        self.finish_line();
+        let after_open = self
+            .lines
+            .last()
+            .and_then(|line| line.items.last())
+            .is_some_and(|item| item.role == Role::Block && item.text == "{");
+        let closes = role == Role::Block && token.text == "}";
+        if token.newlines >= 2 && !after_open && !closes && !self.lines.is_empty() {
+            self.lines.push(Line::default());
+        }
+        self.current.indent = self.indent + if self.in_statement { 1 } else { 0 };
+    }
+
+    fn push(&mut self, text: &str, role: Role) {
+        let space =
+            self.current.items.last().is_some_and(|previous| space_before(previous, text, role));
+        self.current.items.push(Item { text: text.to_string(), role, space });
+    }
+
+    fn add(&mut self, token: &Token, is_block: bool) {
+        let role = role(token, is_block, self.current.items.last());
+        let previous_is_line_comment =
+            self.current.items.last().is_some_and(|item| item.text.starts_with("//"));
+        if self.break_pending {
+            let stays = (token.is_comment() && token.newlines == 0 && !previous_is_line_comment)
+                || (token.is_word("else")
+                    && self.current.items.last().is_some_and(|item| item.role == Role::Block))
+                || (role == Role::Block
+                    && token.text == "{"
+                    && self.current.items.last().is_some_and(|item| item.text == "]"));
+            if !stays {
+                self.start_line(token, role);
+            } else if !token.is_comment() {
+                self.break_pending = false;
+            }
+        } else if token.is_comment() && token.newlines > 0 {
+            self.start_line(token, role);
+        }
+        if role == Role::Block && token.text == "}" {
+            self.finish_line();
+            self.indent = self.indent.saturating_sub(1);
+            self.in_statement = false;
+            let empty = self
+                .lines
+                .last()
+                .and_then(|line| line.items.last())
+                .is_some_and(|item| item.role == Role::Block && item.text == "{");
+            if empty {
+                // an empty block is closed in the line that opens it
+                self.current = self.lines.pop().unwrap();
+            } else {
+                self.start_line(token, role);
+            }
+        }
+        if self.current.items.is_empty() {
+            self.current.indent =
+                self.indent + if self.in_statement && role != Role::Comment { 1 } else { 0 };
+        }
+        self.push(&token.text, role);
+        match role {
+            Role::Comment => {
+                // a comment in its own line is left alone in it
+                if token.kind == Kind::LineComment || self.current.items.len() == 1 {
+                    self.break_pending = true;
+                }
+            }
+            Role::Block if token.text == "{" => {
+                self.indent += 1;
+                self.in_statement = false;
+                self.break_pending = true;
+            }
+            Role::Block => {
+                self.break_pending = true;
+            }
+            Role::Open => {
+                let is_attribute = token.text == "["
+                    && self
+                        .current
+                        .items
+                        .iter()
+                        .rev()
+                        .nth(1)
+                        .is_some_and(|item| item.role == Role::Hash);
+                self.open.push(is_attribute);
+                self.in_statement = true;
+            }
+            Role::Close => {
+                // the attributes and the enums end without a semicolon
+                let is_attribute = self.open.pop() == Some(true);
+                let is_enum = self.open.is_empty()
+                    && token.text == "}"
+                    && self.current.items[0].text == "enum";
+                if is_attribute || is_enum {
+                    self.in_statement = false;
+                    self.break_pending = true;
+                }
+            }
+            Role::Semicolon if self.open.is_empty() => {
+                self.in_statement = false;
+                self.break_pending = true;
+            }
+            _ => self.in_statement = true,
+        }
+    }
+}
+
+// The items of a line at each depth of brackets, where the brackets are at
+// the depth of the items outside them
+fn depths(line: &Line) -> Vec<usize> {
+    let mut depth = 0;
+    let mut depths = Vec::with_capacity(line.items.len());
+    for item in &line.items {
+        if item.role == Role::Close {
+            depth -= 1;
+        }
+        depths.push(depth);
+        if item.role == Role::Open {
+            depth += 1;
+        }
+    }
+    depths
+}
+
+fn line_of(indent: usize, items: &[Item]) -> Line {
+    let mut items = items.to_vec();
+    if let Some(first) = items.first_mut() {
+        first.space = false;
+    }
+    Line { indent, items }
+}
+
+// The elements of the outermost list of the line are written in a line each,
+// the longest one if there are several lists at the same depth
+fn wrap_list(line: &Line, depths: &[usize]) -> Option<Vec<Line>> {
+    let commas: Vec<usize> = (0..line.items.len())
+        .filter(|position| line.items[*position].role == Role::Comma)
+        .collect();
+    let depth = commas.iter().map(|position| depths[*position]).min()?;
+    let (open, close) = commas
+        .iter()
+        .filter(|position| depths[**position] == depth)
+        .filter_map(|comma| {
+            let open = (0..*comma).rev().find(|position| {
+                line.items[*position].role == Role::Open && depths[*position] == depth - 1
+            })?;
+            let close = (*comma..line.items.len()).find(|position| {
+                line.items[*position].role == Role::Close && depths[*position] == depth - 1
+            })?;
+            Some((open, close))
+        })
+        .max_by_key(|(open, close)| (close - open, std::cmp::Reverse(*open)))?;
+    let mut lines = vec![line_of(line.indent, &line.items[..=open])];
+    let mut start = open + 1;
+    for (position, item) in line.items.iter().enumerate().take(close).skip(open + 1) {
+        if item.role == Role::Comma && depths[position] == depth {
+            lines.push(line_of(line.indent + 1, &line.items[start..=position]));
+            start = position + 1;
+        }
+    }
+    lines.push(line_of(line.indent + 1, &line.items[start..close]));
+    lines.push(line_of(line.indent, &line.items[close..]));
+    Some(lines)
+}
+
+// The line is broken before the operators of the lowest precedence that are
+// outside brackets, after the assignment
+fn wrap_operators(line: &Line, depths: &[usize]) -> Option<Vec<Line>> {
+    let assignment = line
+        .items
+        .iter()
+        .position(|item| item.role == Role::Binary && ASSIGNMENTS.contains(&item.text.as_str()));
+    let first = assignment.map_or(1, |position| position + 2);
+    for operators in PRECEDENCE.iter() {
+        let breaks: Vec<usize> = (first..line.items.len())
+            .filter(|position| {
+                let item = &line.items[*position];
+                depths[*position] == 0
+                    && item.role == Role::Binary
+                    && operators.contains(&item.text.as_str())
+            })
+            .collect();
+        if breaks.is_empty() {
+            continue;
+        }
+        let mut lines = Vec::new();
+        let mut start = 0;
+        for position in breaks {
+            let indent = if start == 0 { line.indent } else { line.indent + 1 };
+            lines.push(line_of(indent, &line.items[start..position]));
+            start = position;
+        }
+        lines.push(line_of(line.indent + 1, &line.items[start..]));
+        return Some(lines);
+    }
+    None
+}
+
+fn wrap(line: Line, width: usize, lines: &mut Vec<Line>) {
+    if line.width() <= width || line.items.iter().any(|item| item.role == Role::Comment) {
+        lines.push(line);
+        return;
+    }
+    let depths = depths(&line);
+    match wrap_list(&line, &depths).or_else(|| wrap_operators(&line, &depths)) {
+        Some(wrapped) => {
+            for part in wrapped {
+                if part.items.len() < line.items.len() {
+                    wrap(part, width, lines);
+                } else {
+                    lines.push(part);
+                }
+            }
+        }
+        None => lines.push(line),
+    }
+}
+
+pub fn layout(tokens: &[Token], width: usize) -> String {
+    let blocks = find_blocks(tokens);
+    let mut layout = Layout {
+        lines: Vec::new(),
+        current: Line::default(),
+        indent: 0,
+        open: Vec::new(),
+        in_statement: false,
+        break_pending: false,
+    };
+    for (token, is_block) in tokens.iter().zip(blocks) {
+        layout.add(token, is_block);
+    }
+    layout.finish_line();
+    let mut lines = Vec::new();
+    for line in layout.lines {
+        wrap(line, width, &mut lines);
+    }
+    let mut text = String::new();
+    for line in lines {
+        text.push_str(line.render().trim_end());
+        text.push('\n');
+    }
+    text
+}
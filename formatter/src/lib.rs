@@ -0,0 +1,40 @@
+mod layout;
+mod tokens;
+
+pub struct FormatConfig {
+    pub line_width: usize,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        FormatConfig { line_width: 100 }
+    }
+}
+
+fn same_tokens(original: &[tokens::Token], formatted: &[tokens::Token]) -> bool {
+    original.len() == formatted.len()
+        && original.iter().zip(formatted).all(|(original, formatted)| {
+            original.kind == formatted.kind && original.text == formatted.text
+        })
+}
+
+// The code formatted with the style of circom: the statements are written in a
+// line each and indented by their blocks, the operators are surrounded by
+// spaces and the lines longer than the width are broken by their lists or
+// their operators. The comments are kept where they were written.
+// The formatting fails instead of changing the tokens of the code, or if
+// formatting the result again would change it
+pub fn format_source(source: &str, config: &FormatConfig) -> Result<String, String> {
+    let original = tokens::tokenize(source)?;
+    let formatted = layout::layout(&original, config.line_width);
+    if !same_tokens(&original, &tokens::tokenize(&formatted)?) {
+        return Result::Err(
+            "the formatted code does not have the tokens of the original one".to_string(),
+        );
+    }
+    let again = layout::layout(&tokens::tokenize(&formatted)?, config.line_width);
+    if again != formatted {
+        return Result::Err("the formatted code changes when it is formatted again".to_string());
+    }
+    Result::Ok(formatted)
+}
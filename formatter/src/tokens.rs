@@ -0,0 +1,119 @@
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Kind {
+    Word,
+    Number,
+    Str,
+    Symbol,
+    LineComment,
+    BlockComment,
+}
+
+// A token with the number of line breaks before it in the code
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub kind: Kind,
+    pub text: String,
+    pub newlines: usize,
+}
+
+impl Token {
+    pub fn is(&self, text: &str) -> bool {
+        self.kind == Kind::Symbol && self.text == text
+    }
+
+    pub fn is_word(&self, text: &str) -> bool {
+        self.kind == Kind::Word && self.text == text
+    }
+
+    pub fn is_comment(&self) -> bool {
+        self.kind == Kind::LineComment || self.kind == Kind::BlockComment
+    }
+}
+
+// the longest symbols first, so that each symbol is read whole
+const SYMBOLS: [&str; 55] = [
+    "<<=", ">>=", "**=", "\\=", "<==", "==>", "<--", "-->", "===", "...", "==", "!=", "<=", ">=",
+    "&&", "||", "<<", ">>", "**", "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "+",
+    "-", "*", "/", "\\", "%", "^", "&", "|", "~", "!", "<", ">", "=", "?", ":", ";", ",", ".", "(",
+    ")", "[", "]", "{", "}", "#",
+];
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '$'
+}
+
+// The names of generic templates are read with their parameters, as in
+// Mux<N, M>(, since the parser reads them as a single token
+fn generic_name(rest: &str) -> Option<usize> {
+    let name = rest.find(|c: char| !is_word_char(c))?;
+    let after = &rest[name..];
+    if !after.starts_with('<') {
+        return None;
+    }
+    let close = after.find('>')?;
+    let parameters = &after[1..close];
+    let valid = parameters.split(',').all(|parameter| {
+        let parameter = parameter.trim();
+        !parameter.is_empty() && parameter.chars().all(is_word_char)
+    });
+    if valid && after[close + 1..].starts_with('(') {
+        Some(name + close + 1)
+    } else {
+        None
+    }
+}
+
+pub fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    let mut newlines = 0;
+    while !rest.is_empty() {
+        let c = rest.chars().next().unwrap();
+        if c.is_whitespace() {
+            if c == '\n' {
+                newlines += 1;
+            }
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        let (kind, length) = if rest.starts_with("//") {
+            (Kind::LineComment, rest.find('\n').unwrap_or(rest.len()))
+        } else if let Some(body) = rest.strip_prefix("/*") {
+            match body.find("*/") {
+                Some(end) => (Kind::BlockComment, end + 4),
+                None => return Err("unterminated comment".to_string()),
+            }
+        } else if c == '"' {
+            match rest[1..].find(['"', '\n']) {
+                Some(end) if rest[1 + end..].starts_with('"') => (Kind::Str, end + 2),
+                _ => return Err("unterminated string".to_string()),
+            }
+        } else if c.is_ascii_digit() {
+            (Kind::Number, rest.find(|c: char| !is_word_char(c) && c != '.').unwrap_or(rest.len()))
+        } else if is_word_char(c) {
+            let length = generic_name(rest)
+                .unwrap_or_else(|| rest.find(|c: char| !is_word_char(c)).unwrap_or(rest.len()));
+            (Kind::Word, length)
+        } else {
+            match SYMBOLS.iter().find(|symbol| rest.starts_with(*symbol)) {
+                Some(symbol) => (Kind::Symbol, symbol.len()),
+                None => return Err(format!("unexpected character {}", c)),
+            }
+        };
+        let mut text = rest[..length].to_string();
+        if kind == Kind::LineComment {
+            text = text.trim_end().to_string();
+        }
+        if kind == Kind::Word && text.contains('<') {
+            // the parameters of a generic name are written as Mux<N, M>
+            let (name, parameters) = text.split_once('<').unwrap();
+            let parameters: Vec<_> =
+                parameters.trim_end_matches('>').split(',').map(|p| p.trim()).collect();
+            text = format!("{}<{}>", name, parameters.join(", "));
+        }
+        tokens.push(Token { kind, text, newlines });
+        newlines = 0;
+        rest = &rest[length..];
+    }
+    Ok(tokens)
+}
@@ -0,0 +1,27 @@
+use formatter::{format_source, FormatConfig};
+use std::fs;
+use std::path::PathBuf;
+
+// Each file of the golden folder must be formatted as its .formatted version,
+// which must not change when it is formatted again
+fn check(name: &str, line_width: usize) -> String {
+    let golden = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden");
+    let source = fs::read_to_string(golden.join(format!("{}.circom", name))).unwrap();
+    let expected = fs::read_to_string(golden.join(format!("{}.formatted.circom", name))).unwrap();
+    let config = FormatConfig { line_width };
+    assert_eq!(format_source(&source, &config).unwrap(), expected);
+    assert_eq!(format_source(&expected, &config).unwrap(), expected);
+    expected
+}
+
+#[test]
+fn the_comments_are_kept() {
+    let formatted = check("comments", 100);
+    assert!(formatted.contains("signal input in; // the value"));
+}
+
+#[test]
+fn the_long_lines_are_wrapped() {
+    let formatted = check("wrapping", 60);
+    assert!(formatted.lines().all(|line| line.len() <= 60));
+}
@@ -0,0 +1,22 @@
+pragma circom 2.0.0;
+
+// The square of the input
+template Square() {
+    signal input in; // the value
+    signal output out;
+    /* the constraint
+     of the square */
+    out <== in * in;
+}
+
+template Sum(n) {
+    signal input in[n];
+    signal output out;
+    var total = 0;
+    for (var i = 0; i < n; i++) {
+        total += in[i]; // accumulated
+    }
+    out <== total;
+}
+
+component main {public [in]} = Sum(3);
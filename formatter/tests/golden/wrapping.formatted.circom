@@ -0,0 +1,35 @@
+pragma circom 2.0.0;
+
+function combine(
+    first_value,
+    second_value,
+    third_value,
+    fourth_value,
+    fifth_value,
+    sixth_value
+) {
+    return first_value * second_value
+        + third_value * fourth_value
+        + fifth_value * sixth_value
+        + first_value * sixth_value;
+}
+
+template Wide(
+    first_parameter,
+    second_parameter,
+    third_parameter,
+    fourth_parameter
+) {
+    signal input in;
+    signal output out;
+    out <== in * combine(
+        first_parameter,
+        second_parameter,
+        third_parameter,
+        fourth_parameter,
+        first_parameter,
+        second_parameter
+    );
+}
+
+component main = Wide(1, 2, 3, 4);
@@ -0,0 +1,94 @@
+use super::protocol::range_of;
+use super::workspace::Workspace;
+use json::JsonValue;
+use parser::ParserConfig;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::file_definition::FileLibrary;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use type_analysis::check_types::check_types;
+
+// The diagnostics of the files of a program, by their canonical path
+pub type Diagnostics = HashMap<PathBuf, Vec<JsonValue>>;
+
+fn file_path(files: &FileLibrary, file_id: usize) -> Option<PathBuf> {
+    let name = files.get_file_name(file_id)?.trim_matches('"');
+    Some(std::fs::canonicalize(name).unwrap_or_else(|_| PathBuf::from(name)))
+}
+
+fn to_diagnostic(report: &Report, files: &FileLibrary, paths: &[Option<PathBuf>]) -> Option<(PathBuf, JsonValue)> {
+    let primary = report.get_primary().first()?;
+    let path = paths.get(primary.file_id)?.clone()?;
+    let source = files.get_source(primary.file_id)?;
+    let mut message = report.get_message().clone();
+    if !primary.message.is_empty() {
+        message = format!("{}\n{}", message, primary.message);
+    }
+    for note in report.get_notes() {
+        message = format!("{}\n{}", message, note);
+    }
+    let mut related = JsonValue::new_array();
+    for label in report.get_secondary() {
+        if let (Some(Some(label_path)), Some(label_source)) = (paths.get(label.file_id), files.get_source(label.file_id)) {
+            let location = json::object! {
+                "uri" => super::protocol::path_to_uri(label_path),
+                "range" => range_of(label_source, label.range.start, label.range.end),
+            };
+            let _ = related.push(json::object! { "location" => location, "message" => label.message.clone() });
+        }
+    }
+    let diagnostic = json::object! {
+        "range" => range_of(source, primary.range.start, primary.range.end),
+        "severity" => if report.is_warning() { 2 } else { 1 },
+        "code" => report.get_code().to_string(),
+        "source" => "circom",
+        "message" => message,
+        "relatedInformation" => related,
+    };
+    Some((path, diagnostic))
+}
+
+fn check_program(workspace: &Workspace, root: &Path, version: &str) -> (FileLibrary, ReportCollection) {
+    let config = ParserConfig {
+        version,
+        link_libraries: workspace.libraries.clone(),
+        prime: &workspace.prime,
+        flag_prime: false,
+        flag_no_init: false,
+        flag_enforce_tags: false,
+        features: &[],
+    };
+    let result = parser::run_parser_with_sources(root.display().to_string(), &config, &workspace.documents);
+    match result {
+        Err((files, reports)) => (files, reports),
+        Ok((mut program_archive, mut reports)) => {
+            match check_types(&mut program_archive) {
+                Ok(mut warnings) => reports.append(&mut warnings),
+                Err(mut errors) => reports.append(&mut errors),
+            }
+            (program_archive.file_library, reports)
+        }
+    }
+}
+
+// The errors and warnings of the program of the root file, as the compiler
+// reports them until the type analysis, for each of its files. A file without
+// a main component is only parsed
+pub fn check(workspace: &Workspace, root: &Path, version: &str, diagnostics: &mut Diagnostics) -> HashSet<PathBuf> {
+    let (files, reports) = check_program(workspace, root, version);
+    let paths: Vec<_> =
+        (0..).map_while(|file_id| files.get_file_name(file_id).map(|_| file_path(&files, file_id))).collect();
+    for report in Report::apply_lint_levels(&reports, &files) {
+        if let ReportCode::NoMainFoundInProject = report.get_code() {
+            continue;
+        }
+        if let Some((path, diagnostic)) = to_diagnostic(&report, &files, &paths) {
+            let found = diagnostics.entry(path).or_default();
+            if !found.contains(&diagnostic) {
+                found.push(diagnostic);
+            }
+        }
+    }
+    paths.into_iter().flatten().collect()
+}
@@ -0,0 +1,270 @@
+mod analysis;
+mod navigation;
+mod protocol;
+mod workspace;
+
+use json::JsonValue;
+use protocol::{error_response, notification, offset_of, path_to_uri, range_of, response, uri_to_path};
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use workspace::Workspace;
+
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_REQUEST: i32 = -32600;
+const SERVER_NOT_INITIALIZED: i32 = -32002;
+
+// Why the server stops without a clean exit
+pub enum ServerError {
+    Io(io::Error),
+    // the client asked the server to exit before shutting it down
+    NotShutDown,
+}
+
+impl From<io::Error> for ServerError {
+    fn from(error: io::Error) -> ServerError {
+        ServerError::Io(error)
+    }
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ServerError::Io(error) => write!(f, "could not write to the client: {}", error),
+            ServerError::NotShutDown => write!(f, "the client exited without shutting down the server"),
+        }
+    }
+}
+
+pub struct ServerConfig {
+    pub version: String,
+    pub libraries: Vec<PathBuf>,
+    pub prime: String,
+}
+
+struct Server {
+    workspace: Workspace,
+    version: String,
+    // the documents of the last diagnostics, which are cleared once closed
+    with_diagnostics: HashSet<PathBuf>,
+}
+
+impl Server {
+    // The diagnostics of every open document are computed again after each
+    // change, since a change in a file can affect the ones that include it.
+    // Each document is checked as part of the program of an open document
+    // with a main component that includes it, or on its own
+    fn publish_diagnostics(&mut self, output: &mut impl Write) -> io::Result<()> {
+        let mut diagnostics = analysis::Diagnostics::new();
+        let mut checked = HashSet::new();
+        let mut documents: Vec<_> = self.workspace.documents.keys().cloned().collect();
+        documents.sort();
+        for document in documents.iter().filter(|document| self.workspace.has_main_component(document)) {
+            checked.extend(analysis::check(&self.workspace, document, &self.version, &mut diagnostics));
+        }
+        for document in &documents {
+            if !checked.contains(document) {
+                checked.extend(analysis::check(&self.workspace, document, &self.version, &mut diagnostics));
+            }
+        }
+        let mut published = HashSet::new();
+        for document in documents.iter().chain(self.with_diagnostics.iter()) {
+            if !published.insert(document.clone()) {
+                continue;
+            }
+            let found = diagnostics.remove(document).unwrap_or_default();
+            let params = json::object! { "uri" => path_to_uri(document), "diagnostics" => found };
+            protocol::write_message(output, &notification("textDocument/publishDiagnostics", params))?;
+        }
+        self.with_diagnostics = documents.into_iter().collect();
+        Result::Ok(())
+    }
+
+    fn definition(&self, params: &JsonValue) -> JsonValue {
+        let document = match uri_to_path(params["textDocument"]["uri"].as_str().unwrap_or_default()) {
+            Some(document) => document,
+            None => return JsonValue::Null,
+        };
+        let index = self.workspace.index(&document);
+        if index.files.is_empty() {
+            return JsonValue::Null;
+        }
+        let offset = offset_of(&index.files[0].source, &params["position"]);
+        match navigation::resolve(&index, offset) {
+            Some(symbol) => {
+                let file = &index.files[symbol.file];
+                json::object! {
+                    "uri" => path_to_uri(&file.path),
+                    "range" => range_of(&file.source, symbol.location.start, symbol.location.end),
+                }
+            }
+            None => JsonValue::Null,
+        }
+    }
+
+    fn hover(&self, params: &JsonValue) -> JsonValue {
+        let document = match uri_to_path(params["textDocument"]["uri"].as_str().unwrap_or_default()) {
+            Some(document) => document,
+            None => return JsonValue::Null,
+        };
+        let index = self.workspace.index(&document);
+        if index.files.is_empty() {
+            return JsonValue::Null;
+        }
+        let offset = offset_of(&index.files[0].source, &params["position"]);
+        match navigation::resolve(&index, offset) {
+            Some(symbol) => {
+                let (description, location) = match symbol.description.split_once("\n\n") {
+                    Some((description, location)) => (description, format!("\n\n{}", location)),
+                    None => (symbol.description.as_str(), String::new()),
+                };
+                json::object! {
+                    "contents" => json::object! {
+                        "kind" => "markdown",
+                        "value" => format!("```circom\n{}\n```{}", description, location),
+                    },
+                }
+            }
+            None => JsonValue::Null,
+        }
+    }
+
+    fn signature_help(&self, params: &JsonValue) -> JsonValue {
+        let document = match uri_to_path(params["textDocument"]["uri"].as_str().unwrap_or_default()) {
+            Some(document) => document,
+            None => return JsonValue::Null,
+        };
+        let index = self.workspace.index(&document);
+        if index.files.is_empty() {
+            return JsonValue::Null;
+        }
+        let offset = offset_of(&index.files[0].source, &params["position"]);
+        match navigation::signature(&index, offset) {
+            Some(signature) => {
+                let parameters: Vec<JsonValue> = signature
+                    .parameters
+                    .iter()
+                    .map(|range| json::object! { "label" => json::array![range.start, range.end] })
+                    .collect();
+                let signature_information = json::object! { "label" => signature.label, "parameters" => parameters };
+                json::object! {
+                    "signatures" => vec![signature_information],
+                    "activeSignature" => 0,
+                    "activeParameter" => signature.active,
+                }
+            }
+            None => JsonValue::Null,
+        }
+    }
+
+    fn open_document(&mut self, params: &JsonValue) {
+        if let Some(document) = uri_to_path(params["textDocument"]["uri"].as_str().unwrap_or_default()) {
+            let text = params["textDocument"]["text"].as_str().unwrap_or_default().to_string();
+            self.workspace.documents.insert(document, text);
+        }
+    }
+
+    // the documents are synchronized with their full text in each change
+    fn change_document(&mut self, params: &JsonValue) {
+        if let Some(document) = uri_to_path(params["textDocument"]["uri"].as_str().unwrap_or_default()) {
+            if let Some(text) = params["contentChanges"].members().last().and_then(|change| change["text"].as_str()) {
+                self.workspace.documents.insert(document, text.to_string());
+            }
+        }
+    }
+
+    fn close_document(&mut self, params: &JsonValue) {
+        if let Some(document) = uri_to_path(params["textDocument"]["uri"].as_str().unwrap_or_default()) {
+            self.workspace.documents.remove(&document);
+        }
+    }
+}
+
+fn capabilities() -> JsonValue {
+    json::object! {
+        "capabilities" => json::object! {
+            "textDocumentSync" => 1,
+            "definitionProvider" => true,
+            "hoverProvider" => true,
+            "signatureHelpProvider" => json::object! { "triggerCharacters" => json::array!["(", ","] },
+        },
+        "serverInfo" => json::object! { "name" => "circom" },
+    }
+}
+
+// The libraries and the prime can also be given by the client in the
+// options of the initialization
+fn initialize(server: &mut Server, params: &JsonValue) {
+    let options = &params["initializationOptions"];
+    for library in options["libraries"].members().filter_map(|library| library.as_str()) {
+        server.workspace.libraries.push(PathBuf::from(library));
+    }
+    if let Some(prime) = options["prime"].as_str() {
+        server.workspace.prime = prime.to_string();
+    }
+}
+
+// The language server of circom, which reads the messages of the client from
+// the standard input and writes its answers in the standard output
+pub fn run(config: ServerConfig) -> Result<(), ServerError> {
+    serve(config, &mut BufReader::new(std::io::stdin()), &mut std::io::stdout())
+}
+
+// Answers the messages of the client read from input in output, until the
+// client asks it to exit. It fails if it was not shut down before
+pub fn serve(config: ServerConfig, input: &mut impl BufRead, output: &mut impl Write) -> Result<(), ServerError> {
+    let mut server = Server {
+        workspace: Workspace::new(config.libraries, config.prime),
+        version: config.version,
+        with_diagnostics: HashSet::new(),
+    };
+    let mut initialized = false;
+    let mut shut_down = false;
+    while let Some(message) = protocol::read_message(input) {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let id = &message["id"];
+        if method == "exit" {
+            break;
+        }
+        // the requests have an id, and the notifications do not
+        if !id.is_null() {
+            let answer = match method {
+                "initialize" => {
+                    initialize(&mut server, params);
+                    initialized = true;
+                    response(id, capabilities())
+                }
+                _ if !initialized => error_response(id, SERVER_NOT_INITIALIZED, "The server is not initialized"),
+                _ if shut_down => error_response(id, INVALID_REQUEST, "The server is shut down"),
+                "shutdown" => {
+                    shut_down = true;
+                    response(id, JsonValue::Null)
+                }
+                "textDocument/definition" => response(id, server.definition(params)),
+                "textDocument/hover" => response(id, server.hover(params)),
+                "textDocument/signatureHelp" => response(id, server.signature_help(params)),
+                _ => error_response(id, METHOD_NOT_FOUND, &format!("Unknown method {}", method)),
+            };
+            protocol::write_message(output, &answer)?;
+            continue;
+        }
+        if !initialized || shut_down {
+            continue;
+        }
+        match method {
+            "textDocument/didOpen" => server.open_document(params),
+            "textDocument/didChange" => server.change_document(params),
+            "textDocument/didClose" => server.close_document(params),
+            "textDocument/didSave" => {}
+            _ => continue,
+        }
+        server.publish_diagnostics(output)?;
+    }
+    if shut_down {
+        Result::Ok(())
+    } else {
+        Result::Err(ServerError::NotShutDown)
+    }
+}
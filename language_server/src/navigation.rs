@@ -0,0 +1,300 @@
+use super::workspace::Index;
+use program_structure::ast::{Definition, Expression, Meta, SignalType, Statement, VariableType};
+use std::ops::Range;
+
+// What a name refers to: where it is defined and how it is shown
+pub struct Symbol {
+    pub file: usize,
+    pub location: Range<usize>,
+    pub description: String,
+}
+
+// The signature of a template, a function or a bus, with the range of each
+// parameter in its label, and the parameter that is being written
+pub struct Signature {
+    pub label: String,
+    pub parameters: Vec<Range<usize>>,
+    pub active: usize,
+}
+
+struct Header<'a> {
+    kind: &'static str,
+    name: &'a str,
+    args: &'a [String],
+    arg_location: &'a Range<usize>,
+    body: &'a Statement,
+}
+
+fn header(definition: &Definition) -> Option<Header<'_>> {
+    match definition {
+        Definition::Template { name, args, arg_location, body, .. } => {
+            Some(Header { kind: "template", name, args, arg_location, body })
+        }
+        Definition::Function { name, args, arg_location, body, .. } => {
+            Some(Header { kind: "function", name, args, arg_location, body })
+        }
+        Definition::Bus { name, args, arg_location, body, .. } => {
+            Some(Header { kind: "bus", name, args, arg_location, body })
+        }
+        _ => None,
+    }
+}
+
+fn definition_name(definition: &Definition) -> Option<&str> {
+    match definition {
+        Definition::Template { name, .. }
+        | Definition::Function { name, .. }
+        | Definition::Bus { name, .. }
+        | Definition::Constant { name, .. }
+        | Definition::Enum { name, .. }
+        | Definition::Tag { name, .. } => Some(name),
+        Definition::Operator { .. } => None,
+    }
+}
+
+fn definition_meta(definition: &Definition) -> &Meta {
+    match definition {
+        Definition::Template { meta, .. }
+        | Definition::Function { meta, .. }
+        | Definition::Bus { meta, .. }
+        | Definition::Constant { meta, .. }
+        | Definition::Enum { meta, .. }
+        | Definition::Tag { meta, .. }
+        | Definition::Operator { meta, .. } => meta,
+    }
+}
+
+fn is_identifier(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '$'
+}
+
+// The identifier under the cursor, which may also be right after it
+fn identifier_at(source: &str, offset: usize) -> Option<Range<usize>> {
+    let offset = std::cmp::min(offset, source.len());
+    let start = source[..offset].rfind(|c| !is_identifier(c)).map_or(0, |position| position + 1);
+    let end = source[offset..].find(|c| !is_identifier(c)).map_or(source.len(), |position| offset + position);
+    if start < end && !source[start..].starts_with(|c: char| c.is_ascii_digit()) {
+        Some(start..end)
+    } else {
+        None
+    }
+}
+
+// The name before the dot of an access as m[i].a, without its indexes
+fn qualifier(source: &str, start: usize) -> Option<&str> {
+    let before = source[..start].trim_end();
+    let mut end = before.strip_suffix('.')?.trim_end().len();
+    let bytes = source.as_bytes();
+    let mut depth = 0;
+    while end > 0 && (depth > 0 || bytes[end - 1] == b']') {
+        match bytes[end - 1] {
+            b']' => depth += 1,
+            b'[' => depth -= 1,
+            _ => {}
+        }
+        end -= 1;
+    }
+    let range = identifier_at(source, end)?;
+    if range.end == end {
+        Some(&source[range])
+    } else {
+        None
+    }
+}
+
+// The template of a component, from the call that is assigned to it
+fn component_template(statement: &Statement, component: &str) -> Option<String> {
+    match statement {
+        Statement::Substitution { var, rhe: Expression::Call { id, .. }, .. } if var == component => Some(id.clone()),
+        Statement::IfThenElse { if_case, else_case, .. } => component_template(if_case, component)
+            .or_else(|| else_case.as_ref().and_then(|else_case| component_template(else_case, component))),
+        Statement::While { stmt, .. } => component_template(stmt, component),
+        Statement::InitializationBlock { initializations: stmts, .. } | Statement::Block { stmts, .. } => {
+            stmts.iter().find_map(|statement| component_template(statement, component))
+        }
+        _ => None,
+    }
+}
+
+// The names of the definitions of an included file with an alias are written
+// as alias.name
+fn find_global<'a>(index: &'a Index, name: &str) -> Option<(usize, &'a Definition)> {
+    let name = name.rsplit('.').next().unwrap_or(name);
+    for (file, source) in index.files.iter().enumerate() {
+        if let Some(ast) = &source.ast {
+            for definition in &ast.definitions {
+                if definition_name(definition) == Some(name) {
+                    return Some((file, definition));
+                }
+            }
+        }
+    }
+    None
+}
+
+fn enclosing_definition(index: &Index, offset: usize) -> Option<&Definition> {
+    let ast = index.files[0].ast.as_ref()?;
+    ast.definitions.iter().find(|definition| {
+        let meta = definition_meta(definition);
+        meta.start <= offset && offset <= meta.end
+    })
+}
+
+fn signal_kind(signal_type: &SignalType) -> &'static str {
+    match signal_type {
+        SignalType::Input => "input ",
+        SignalType::Output => "output ",
+        SignalType::Intermediate => "",
+    }
+}
+
+fn tags(tags: &[String]) -> String {
+    if tags.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}} ", tags.join(", "))
+    }
+}
+
+fn describe_declaration(source: &str, declaration: &Statement, template: Option<String>) -> String {
+    let (xtype, name, dimensions) = match declaration {
+        Statement::Declaration { xtype, name, dimensions, .. } => (xtype, name, dimensions),
+        _ => return String::new(),
+    };
+    let dimensions: String = dimensions
+        .iter()
+        .map(|dimension| {
+            let meta = dimension.get_meta();
+            format!("[{}]", source.get(meta.start..meta.end).unwrap_or("?"))
+        })
+        .collect();
+    match xtype {
+        VariableType::Var => format!("var {}{}", name, dimensions),
+        VariableType::Signal(signal_type, signal_tags) => {
+            format!("signal {}{}{}{}", signal_kind(signal_type), tags(signal_tags), name, dimensions)
+        }
+        VariableType::Bus(bus, signal_type, bus_tags) => {
+            format!("{} {}{}{}{}", bus, signal_kind(signal_type), tags(bus_tags), name, dimensions)
+        }
+        VariableType::Component | VariableType::AnonymousComponent => match template {
+            Some(template) => format!("component {}{} = {}(...)", name, dimensions, template),
+            None => format!("component {}{}", name, dimensions),
+        },
+    }
+}
+
+fn describe_definition(definition: &Definition) -> String {
+    if let Some(header) = header(definition) {
+        let prefix = match definition {
+            Definition::Template { parallel: true, .. } => "template parallel ",
+            Definition::Template { is_custom_gate: true, .. } => "template custom ",
+            Definition::Function { is_extern: true, .. } => "extern function ",
+            _ => "",
+        };
+        let kind = if prefix.is_empty() { format!("{} ", header.kind) } else { prefix.to_string() };
+        return format!("{}{}({})", kind, header.name, header.args.join(", "));
+    }
+    match definition {
+        Definition::Constant { name, .. } => format!("const {}", name),
+        Definition::Enum { name, members, .. } => {
+            let members: Vec<_> = members.iter().map(|(member, _)| member.as_str()).collect();
+            format!("enum {} {{{}}}", name, members.join(", "))
+        }
+        Definition::Tag { name, .. } => format!("tag {}", name),
+        _ => String::new(),
+    }
+}
+
+fn global_symbol(index: &Index, name: &str) -> Option<Symbol> {
+    let (file, definition) = find_global(index, name)?;
+    let meta = definition_meta(definition);
+    let mut description = describe_definition(definition);
+    if file != 0 {
+        description = format!("{}\n\n{}", description, index.files[file].path.display());
+    }
+    Some(Symbol { file, location: meta.start..meta.end, description })
+}
+
+fn declaration_symbol(index: &Index, file: usize, body: &Statement, name: &str) -> Option<Symbol> {
+    let declaration = body.find_declaration(name)?;
+    let meta = declaration.get_meta();
+    let template = component_template(body, name);
+    let description = describe_declaration(&index.files[file].source, declaration, template);
+    Some(Symbol { file, location: meta.start..meta.end, description })
+}
+
+// A signal of a component, or of a bus, accessed as m[i].a
+fn member_symbol(index: &Index, body: &Statement, base: &str, name: &str) -> Option<Symbol> {
+    let declaration = body.find_declaration(base)?;
+    let definition = match declaration {
+        Statement::Declaration { xtype: VariableType::Component | VariableType::AnonymousComponent, .. } => {
+            component_template(body, base)?
+        }
+        Statement::Declaration { xtype: VariableType::Bus(bus, ..), .. } => bus.clone(),
+        _ => return None,
+    };
+    let (file, definition) = find_global(index, &definition)?;
+    declaration_symbol(index, file, header(definition)?.body, name)
+}
+
+pub fn resolve(index: &Index, offset: usize) -> Option<Symbol> {
+    let source = &index.files[0].source;
+    let range = identifier_at(source, offset)?;
+    let name = &source[range.clone()];
+    let enclosing = enclosing_definition(index, offset).and_then(header);
+    if let Some(base) = qualifier(source, range.start) {
+        let member = enclosing.as_ref().and_then(|header| member_symbol(index, header.body, base, name));
+        // otherwise the base is the alias of an include
+        return member.or_else(|| global_symbol(index, name));
+    }
+    if let Some(header) = &enclosing {
+        if let Some(symbol) = declaration_symbol(index, 0, header.body, name) {
+            return Some(symbol);
+        }
+        if header.args.iter().any(|arg| arg == name) {
+            let description = format!("{}: parameter of {} {}", name, header.kind, header.name);
+            return Some(Symbol { file: 0, location: header.arg_location.clone(), description });
+        }
+    }
+    global_symbol(index, name)
+}
+
+// The call whose arguments are being written, with the number of arguments
+// before the cursor
+fn open_call(source: &str, offset: usize) -> Option<(&str, usize)> {
+    let before = &source[..std::cmp::min(offset, source.len())];
+    let mut depth = 0;
+    let mut commas = 0;
+    for (position, c) in before.char_indices().rev() {
+        match c {
+            ')' | ']' => depth += 1,
+            '(' if depth == 0 => {
+                let name_end = before[..position].trim_end().len();
+                let range = identifier_at(source, name_end)?;
+                return if range.end == name_end { Some((&source[range], commas)) } else { None };
+            }
+            '(' | '[' if depth > 0 => depth -= 1,
+            '[' | ';' | '{' | '}' => return None,
+            ',' if depth == 0 => commas += 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+pub fn signature(index: &Index, offset: usize) -> Option<Signature> {
+    let (name, active) = open_call(&index.files[0].source, offset)?;
+    let (_, definition) = find_global(index, name)?;
+    let header = header(definition)?;
+    let mut label = format!("{} {}(", header.kind, header.name);
+    let mut parameters = Vec::new();
+    for (position, arg) in header.args.iter().enumerate() {
+        if position > 0 {
+            label.push_str(", ");
+        }
+        parameters.push(label.len()..label.len() + arg.len());
+        label.push_str(arg);
+    }
+    label.push(')');
+    Some(Signature { label, parameters, active })
+}
@@ -0,0 +1,120 @@
+use json::JsonValue;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+// The messages of the protocol are JSON objects preceded by a header with
+// their length in bytes
+pub fn read_message(input: &mut impl BufRead) -> Option<JsonValue> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let mut body = vec![0; length?];
+    input.read_exact(&mut body).ok()?;
+    json::parse(std::str::from_utf8(&body).ok()?).ok()
+}
+
+pub fn write_message(output: &mut impl Write, message: &JsonValue) -> io::Result<()> {
+    let body = message.dump();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+pub fn response(id: &JsonValue, result: JsonValue) -> JsonValue {
+    json::object! { "jsonrpc" => "2.0", "id" => id.clone(), "result" => result }
+}
+
+pub fn error_response(id: &JsonValue, code: i32, message: &str) -> JsonValue {
+    json::object! {
+        "jsonrpc" => "2.0",
+        "id" => id.clone(),
+        "error" => json::object! { "code" => code, "message" => message },
+    }
+}
+
+pub fn notification(method: &str, params: JsonValue) -> JsonValue {
+    json::object! { "jsonrpc" => "2.0", "method" => method, "params" => params }
+}
+
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte)
+}
+
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?.as_bytes();
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+    while i < encoded.len() {
+        let escaped = encoded.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (encoded[i], escaped) {
+            (b'%', Some(byte)) => {
+                bytes.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                bytes.push(byte);
+                i += 1;
+            }
+        }
+    }
+    let path = PathBuf::from(String::from_utf8(bytes).ok()?);
+    // the paths are compared once they are canonical, as the ones of the includes
+    Some(std::fs::canonicalize(&path).unwrap_or(path))
+}
+
+pub fn path_to_uri(path: &Path) -> String {
+    let mut uri = "file://".to_string();
+    for byte in path.to_string_lossy().bytes() {
+        if is_unreserved(byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    uri
+}
+
+// The positions of the protocol count the characters of a line in UTF-16
+// code units, and the ones of the compiler are offsets in bytes
+pub fn offset_of(source: &str, position: &JsonValue) -> usize {
+    let line = position["line"].as_usize().unwrap_or(0);
+    let character = position["character"].as_usize().unwrap_or(0);
+    let mut offset = 0;
+    for (number, text) in source.split_inclusive('\n').enumerate() {
+        if number == line {
+            let mut units = 0;
+            for (index, c) in text.char_indices() {
+                if units >= character || c == '\n' || c == '\r' {
+                    return offset + index;
+                }
+                units += c.len_utf16();
+            }
+            return offset + text.len();
+        }
+        offset += text.len();
+    }
+    source.len()
+}
+
+pub fn position_of(source: &str, offset: usize) -> JsonValue {
+    let offset = std::cmp::min(offset, source.len());
+    let before = &source[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |position| position + 1);
+    let character: usize = before[line_start..].chars().map(|c| c.len_utf16()).sum();
+    json::object! { "line" => line, "character" => character }
+}
+
+pub fn range_of(source: &str, start: usize, end: usize) -> JsonValue {
+    json::object! { "start" => position_of(source, start), "end" => position_of(source, end) }
+}
@@ -0,0 +1,69 @@
+use program_structure::ast::AST;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// A file of the program as it is written, with its definitions when it parses
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub source: String,
+    pub ast: Option<AST>,
+}
+
+// The files that a document can refer to: the document itself, which is the
+// first one, and the files that it includes, directly or not
+pub struct Index {
+    pub files: Vec<SourceFile>,
+}
+
+pub struct Workspace {
+    pub documents: HashMap<PathBuf, String>,
+    pub libraries: Vec<PathBuf>,
+    pub prime: String,
+}
+
+impl Workspace {
+    pub fn new(libraries: Vec<PathBuf>, prime: String) -> Workspace {
+        Workspace { documents: HashMap::new(), libraries, prime }
+    }
+
+    // the open documents are read as they are being edited
+    pub fn source(&self, path: &Path) -> Option<String> {
+        match self.documents.get(path) {
+            Some(source) => Some(source.clone()),
+            None => std::fs::read_to_string(path).ok(),
+        }
+    }
+
+    pub fn parse(&self, source: &str, file_id: usize) -> Option<AST> {
+        parser::parse_definitions(source, file_id, &self.prime, &[]).ok()
+    }
+
+    pub fn has_main_component(&self, path: &Path) -> bool {
+        let source = self.source(path).unwrap_or_default();
+        self.parse(&source, 0).is_some_and(|ast| !ast.main_components.is_empty())
+    }
+
+    pub fn index(&self, document: &Path) -> Index {
+        let mut files: Vec<SourceFile> = Vec::new();
+        let mut pending = vec![document.to_path_buf()];
+        while let Some(path) = pending.pop() {
+            if files.iter().any(|file| file.path == path) {
+                continue;
+            }
+            let source = match self.source(&path) {
+                Some(source) => source,
+                None => continue,
+            };
+            let ast = self.parse(&source, files.len());
+            if let Some(ast) = &ast {
+                for include in ast.includes.iter().rev() {
+                    if let Some(included) = parser::resolve_include(&path, &include.path, &self.libraries) {
+                        pending.push(included);
+                    }
+                }
+            }
+            files.push(SourceFile { path, source, ast });
+        }
+        Index { files }
+    }
+}
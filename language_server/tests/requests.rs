@@ -0,0 +1,163 @@
+use json::JsonValue;
+use language_server::{serve, ServerConfig};
+use std::fs;
+use std::io::{BufRead, Cursor, Read};
+use std::path::{Path, PathBuf};
+
+const LIBRARY: &str = "pragma circom 2.0.0;
+
+template Square() {
+    signal input in;
+    signal output out;
+    out <== in * in;
+}
+";
+
+const MAIN: &str = "pragma circom 2.0.0;
+include \"library.circom\";
+
+template Main() {
+    signal input in[2];
+    signal output out;
+    component square = Square();
+    square.in <== in[0];
+    out <== square.out + in[1];
+}
+
+component main = Main();
+";
+
+// A folder with the library on disk, where the main file is only open in
+// the client
+fn folder(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("circom_lsp_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("library.circom"), LIBRARY).unwrap();
+    fs::canonicalize(dir).unwrap()
+}
+
+fn uri(path: &Path) -> String {
+    format!("file://{}", path.display())
+}
+
+fn frame(message: JsonValue) -> String {
+    let body = message.dump();
+    format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+}
+
+fn request(id: usize, method: &str, params: JsonValue) -> String {
+    frame(json::object! { "jsonrpc" => "2.0", "id" => id, "method" => method, "params" => params })
+}
+
+fn notify(method: &str, params: JsonValue) -> String {
+    frame(json::object! { "jsonrpc" => "2.0", "method" => method, "params" => params })
+}
+
+fn open(document: &Path, text: &str) -> String {
+    let item = json::object! { "uri" => uri(document), "languageId" => "circom", "version" => 1, "text" => text };
+    notify("textDocument/didOpen", json::object! { "textDocument" => item })
+}
+
+fn at(document: &Path, line: usize, character: usize) -> JsonValue {
+    json::object! {
+        "textDocument" => json::object! { "uri" => uri(document) },
+        "position" => json::object! { "line" => line, "character" => character },
+    }
+}
+
+// Runs a session with the messages between the initialization and the
+// shutdown, and gives the messages of the server
+fn session(messages: &[String]) -> Vec<JsonValue> {
+    let mut input = request(0, "initialize", json::object! {});
+    for message in messages {
+        input.push_str(message);
+    }
+    input.push_str(&request(1000, "shutdown", JsonValue::Null));
+    input.push_str(&notify("exit", JsonValue::Null));
+    let config = ServerConfig { version: "2.2.2".to_string(), libraries: Vec::new(), prime: "bn128".to_string() };
+    let mut output = Vec::new();
+    if serve(config, &mut Cursor::new(input), &mut output).is_err() {
+        panic!("the server was not shut down");
+    }
+    let mut output = Cursor::new(output);
+    let mut messages = Vec::new();
+    loop {
+        let mut header = String::new();
+        if output.read_line(&mut header).unwrap() == 0 {
+            break;
+        }
+        let length: usize = header.trim().strip_prefix("Content-Length:").unwrap().trim().parse().unwrap();
+        output.read_line(&mut header).unwrap();
+        let mut body = vec![0; length];
+        output.read_exact(&mut body).unwrap();
+        messages.push(json::parse(std::str::from_utf8(&body).unwrap()).unwrap());
+    }
+    messages
+}
+
+fn result(messages: &[JsonValue], id: usize) -> &JsonValue {
+    &messages.iter().find(|message| message["id"] == id).unwrap()["result"]
+}
+
+fn diagnostics<'a>(messages: &'a [JsonValue], document: &Path) -> Vec<&'a JsonValue> {
+    messages
+        .iter()
+        .filter(|message| message["method"] == "textDocument/publishDiagnostics" && message["params"]["uri"] == uri(document))
+        .map(|message| &message["params"]["diagnostics"])
+        .collect()
+}
+
+#[test]
+fn the_errors_of_a_document_are_published_as_it_changes() {
+    let dir = folder("diagnostics");
+    let document = dir.join("main.circom");
+    let wrong = MAIN.replace("square.out + in[1]", "square.out + missing");
+    let change = json::object! {
+        "textDocument" => json::object! { "uri" => uri(&document), "version" => 2 },
+        "contentChanges" => json::array![json::object! { "text" => MAIN }],
+    };
+    let messages = session(&[open(&document, &wrong), notify("textDocument/didChange", change)]);
+    let published = diagnostics(&messages, &document);
+    assert_eq!(published.len(), 2);
+    assert_eq!(published[0].len(), 1);
+    let error = &published[0][0];
+    assert_eq!(error["severity"], 1);
+    assert_eq!(error["range"]["start"]["line"], 8);
+    assert_eq!(error["range"]["start"]["character"], 25);
+    assert!(error["message"].as_str().unwrap().starts_with("Undeclared symbol"), "{}", error.dump());
+    assert!(published[1].is_empty(), "{}", published[1].dump());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn hover_gives_the_declaration_of_a_signal() {
+    let dir = folder("hover");
+    let document = dir.join("main.circom");
+    let messages = session(&[open(&document, MAIN), request(1, "textDocument/hover", at(&document, 8, 25))]);
+    let contents = result(&messages, 1)["contents"]["value"].as_str().unwrap().to_string();
+    assert!(contents.contains("signal input in[2]"), "{}", contents);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn the_definition_of_a_template_is_found_in_an_included_file() {
+    let dir = folder("definition");
+    let document = dir.join("main.circom");
+    let messages = session(&[open(&document, MAIN), request(1, "textDocument/definition", at(&document, 6, 24))]);
+    let location = result(&messages, 1);
+    assert_eq!(location["uri"], uri(&dir.join("library.circom")));
+    assert_eq!(location["range"]["start"]["line"], 2);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn the_requests_before_the_initialization_are_rejected() {
+    let mut input = request(1, "textDocument/hover", json::object! {});
+    input.push_str(&notify("exit", JsonValue::Null));
+    let config = ServerConfig { version: "2.2.2".to_string(), libraries: Vec::new(), prime: "bn128".to_string() };
+    let mut output = Vec::new();
+    assert!(serve(config, &mut Cursor::new(input), &mut output).is_err());
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("\"code\":-32002"), "{}", output);
+}
@@ -0,0 +1,410 @@
+use num_bigint::BigInt;
+use crate::template_defaults::{visit_expression, visit_statement};
+use program_structure::ast::*;
+use program_structure::ast_shortcuts;
+use program_structure::builtin_calls::BuiltinCall;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::expression_builders::*;
+use program_structure::statement_builders::*;
+use program_structure::file_definition::FileID;
+use std::collections::{HashMap, HashSet};
+
+fn builtin_error(message: String, meta: &Meta, file_id: FileID) -> Report {
+    let mut report = Report::error(message, ReportCode::BuiltinCallError);
+    report.add_primary(meta.location.clone(), file_id, "here".to_string());
+    report
+}
+
+fn expected_args(builtin: BuiltinCall) -> &'static str {
+    match builtin {
+        BuiltinCall::Map => "a function and an array",
+        BuiltinCall::Fold => "a function, an initial value and an array",
+        BuiltinCall::Concat => "two arrays",
+        BuiltinCall::Slice => "an array, a start and an end",
+        BuiltinCall::Sum | BuiltinCall::Reverse | BuiltinCall::Sort | BuiltinCall::Len => "an array",
+    }
+}
+
+// The name of a function, which can be qualified by the alias of an include,
+// as in L.f
+fn function_name(expression: &Expression) -> Option<String> {
+    let (name, access) = match expression {
+        Expression::Variable { name, access, .. } => (name, access),
+        _ => return None,
+    };
+    let mut function = name.clone();
+    for acc in access {
+        match acc {
+            Access::ComponentAccess(member) => function = format!("{}.{}", function, member),
+            Access::ArrayAccess(_) => return None,
+        }
+    }
+    Some(function)
+}
+
+// Renames a call to a builtin, as map or len, to the id of the builtin, and turns the
+// name of its function into a call without arguments
+fn rename_call(expression: &mut Expression, defined: &HashSet<String>, file_id: FileID, reports: &mut ReportCollection) {
+    let (meta, id, args) = match expression {
+        Expression::Call { meta, id, args } if !defined.contains(id.as_str()) => (meta, id, args),
+        _ => return,
+    };
+    let builtin = match BuiltinCall::from_name(id) {
+        Some(builtin) => builtin,
+        None => return,
+    };
+    if args.len() != builtin.num_of_args() {
+        let message = format!("{} takes {}, but it is given {} arguments", id, expected_args(builtin), args.len());
+        reports.push(builtin_error(message, meta, file_id));
+        return;
+    }
+    if builtin.num_of_params() > 0 {
+        match function_name(&args[0]) {
+            Some(name) => args[0] = build_call(args[0].get_meta().clone(), name, Vec::new()),
+            None => {
+                let message = format!("The first argument of {} must be the name of a function", id);
+                reports.push(builtin_error(message, args[0].get_meta(), file_id));
+                return;
+            }
+        }
+    }
+    *id = builtin.id();
+}
+
+// The signed interpretation of a value, where the values greater than p/2
+// are the negative ones, as in the comparisons. sign(x) is (0 < x) - (x < 0)
+// and abs(x) is x < 0 ? -x : x. When the call gives the number of bits n of
+// |x| in a template, it is the anonymous component of a template that
+// constrains the result with n + 1 bits, which is also the range check of x
+fn lower_signed_call(
+    expression: &mut Expression,
+    defined: &HashSet<String>,
+    in_template: bool,
+    uses_templates: &mut bool,
+    file_id: FileID,
+    reports: &mut ReportCollection,
+) {
+    let (meta, id, args) = match expression {
+        Expression::Call { meta, id, args } if (id == "sign" || id == "abs") && !defined.contains(id.as_str()) => {
+            (meta.clone(), id.clone(), std::mem::take(args))
+        }
+        _ => return,
+    };
+    let infix = |lhe, op, rhe| build_infix(meta.clone(), lhe, op, rhe);
+    let zero = || build_number_without_field(meta.clone(), BigInt::from(0));
+    *expression = match (args.len(), in_template) {
+        (2, true) => {
+            *uses_templates = true;
+            let template = if id == "sign" { "$Sign" } else { "$Abs" };
+            let mut args = args;
+            let bits = args.pop().unwrap();
+            build_anonymous_component(meta.clone(), template.to_string(), vec![bits], args, None, false)
+        }
+        (1, _) | (2, false) => {
+            let x = args[0].clone();
+            let is_negative = infix(x.clone(), ExpressionInfixOpcode::Lesser, zero());
+            if id == "sign" {
+                infix(infix(zero(), ExpressionInfixOpcode::Lesser, x), ExpressionInfixOpcode::Sub, is_negative)
+            } else {
+                let minus_x = build_prefix(meta.clone(), ExpressionPrefixOpcode::Sub, x.clone());
+                build_inline_switch_op(meta.clone(), is_negative, minus_x, x)
+            }
+        }
+        _ => {
+            let message = format!("{} takes a value and, in templates, the number of bits of its absolute value, but it is given {} arguments", id, args.len());
+            reports.push(builtin_error(message, &meta, file_id));
+            build_call(meta.clone(), id, args)
+        }
+    };
+}
+
+// The templates of the calls to sign and abs with a number of bits and of
+// the powers of signals
+pub fn builtin_templates(field: &BigInt) -> String {
+    include_str!("builtin_templates.circom").replace("FIELD_BITS", &field.bits().to_string())
+}
+
+// What the lowering of the expressions of a template knows of it: the
+// declarations of its signals, buses and components, its parameters and
+// the number of loops around the statement
+struct Lowering<'a> {
+    signals: HashMap<String, (VariableType, Vec<Expression>)>,
+    params: &'a [String],
+    loops: usize,
+    file_id: FileID,
+    warnings: &'a mut ReportCollection,
+}
+
+fn collect_signals(statement: &Statement, signals: &mut HashMap<String, (VariableType, Vec<Expression>)>) {
+    use Statement::*;
+    match statement {
+        Declaration { name, xtype, dimensions, .. } if *xtype != VariableType::Var => {
+            signals.insert(name.clone(), (xtype.clone(), dimensions.clone()));
+        }
+        IfThenElse { if_case, else_case, .. } => {
+            collect_signals(if_case, signals);
+            if let Some(else_case) = else_case {
+                collect_signals(else_case, signals);
+            }
+        }
+        While { stmt, .. } => collect_signals(stmt, signals),
+        Block { stmts, .. } | InitializationBlock { initializations: stmts, .. } => {
+            for stmt in stmts {
+                collect_signals(stmt, signals);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn mentions_signal(expression: &Expression, signals: &HashMap<String, (VariableType, Vec<Expression>)>) -> bool {
+    let mut expression = expression.clone();
+    let mut mentions = false;
+    visit_expression(&mut expression, &mut |e| {
+        mentions |= matches!(e, Expression::Variable { name, .. } if signals.contains_key(name));
+    });
+    mentions
+}
+
+// Whether the value of a length is the same in the whole template, as it
+// only depends on its parameters
+fn is_parametric(expression: &Expression, params: &[String]) -> bool {
+    let mut expression = expression.clone();
+    let mut parametric = true;
+    visit_expression(&mut expression, &mut |e| {
+        if let Expression::Variable { name, access, .. } = e {
+            parametric &= access.is_empty() && params.contains(name);
+        }
+    });
+    parametric
+}
+
+// A variable declared before the statement, with the result of an anonymous
+// component of the builtin templates
+fn lower_to_variable(meta: Meta, name: String, value: Expression, lowering: &mut Lowering, before: &mut Vec<Statement>) -> Expression {
+    let declaration = build_declaration(meta.clone(), VariableType::Var, name.clone(), Vec::new());
+    let substitution = build_substitution(meta.clone(), name.clone(), Vec::new(), AssignOp::AssignVar, value);
+    before.push(build_initialization_block(meta.clone(), VariableType::Var, vec![declaration, substitution]));
+    lowering.signals.insert(name.clone(), (VariableType::Var, Vec::new()));
+    build_variable(meta, name, Vec::new())
+}
+
+// The accesses to arrays of signals or components by indexes that depend on
+// signals, as in arr[idx] or c[idx].out, are the multiplexers $Mux of the
+// elements of the arrays. They are the array itself when the access ends in
+// the index, and otherwise the copies of the elements in an array of
+// signals declared before the statement, which can not be in a loop. The
+// lengths of the arrays must only depend on the parameters of the template
+fn lower_indexes(expression: &mut Expression, lowering: &mut Lowering, before: &mut Vec<Statement>) {
+    visit_expression(expression, &mut |e| {
+        let (meta, name, access) = match e {
+            Expression::Variable { meta, name, access } => (meta.clone(), name.clone(), access.clone()),
+            _ => return,
+        };
+        let (xtype, dimensions) = match lowering.signals.get(&name) {
+            Some(declaration) => declaration.clone(),
+            None => return,
+        };
+        let dynamic = |acc: &Access| matches!(acc, Access::ArrayAccess(index) if mentions_signal(index, &lowering.signals));
+        let position = match access.iter().position(dynamic) {
+            Some(position) => position,
+            None => return,
+        };
+        let (prefix, rest) = (&access[..position], &access[position + 1..]);
+        let is_array_access = |acc: &Access| matches!(acc, Access::ArrayAccess(_));
+        if position >= dimensions.len()
+            || !prefix.iter().all(is_array_access)
+            || rest.iter().any(dynamic)
+            || !is_parametric(&dimensions[position], lowering.params)
+        {
+            return;
+        }
+        let index = match &access[position] {
+            Access::ArrayAccess(index) => index.clone(),
+            Access::ComponentAccess(_) => unreachable!(),
+        };
+        let length = dimensions[position].clone();
+        let is_signal = matches!(xtype, VariableType::Signal(..));
+        let candidates = if is_signal && rest.is_empty() && position + 1 == dimensions.len() {
+            build_variable(meta.clone(), name.clone(), prefix.to_vec())
+        } else if lowering.loops == 0 && (!is_signal || rest.iter().all(is_array_access) && position + 1 + rest.len() == dimensions.len()) {
+            let copies = format!("$copies_{}_{}", meta.start, meta.end);
+            let i = format!("$copy_{}_{}", meta.start, meta.end);
+            let variable = |name: &str, access| build_variable(meta.clone(), name.to_string(), access);
+            let number = |value| build_number_without_field(meta.clone(), BigInt::from(value));
+            let xtype = VariableType::Signal(SignalType::Intermediate, Vec::new());
+            let declaration = build_declaration(meta.clone(), xtype.clone(), copies.clone(), vec![length.clone()]);
+            before.push(build_initialization_block(meta.clone(), xtype, vec![declaration]));
+            let mut element = prefix.to_vec();
+            element.push(Access::ArrayAccess(variable(&i, Vec::new())));
+            element.extend(rest.iter().cloned());
+            let init = build_initialization_block(
+                meta.clone(),
+                VariableType::Var,
+                vec![
+                    build_declaration(meta.clone(), VariableType::Var, i.clone(), Vec::new()),
+                    build_substitution(meta.clone(), i.clone(), Vec::new(), AssignOp::AssignVar, number(0)),
+                ],
+            );
+            let cond = build_infix(meta.clone(), variable(&i, Vec::new()), ExpressionInfixOpcode::Lesser, length.clone());
+            let next = build_infix(meta.clone(), variable(&i, Vec::new()), ExpressionInfixOpcode::Add, number(1));
+            let step = build_substitution(meta.clone(), i.clone(), Vec::new(), AssignOp::AssignVar, next);
+            let copy = build_substitution(
+                meta.clone(),
+                copies.clone(),
+                vec![Access::ArrayAccess(variable(&i, Vec::new()))],
+                AssignOp::AssignConstraintSignal,
+                variable(&name, element),
+            );
+            before.push(ast_shortcuts::for_into_while(meta.clone(), init, cond, step, copy));
+            variable(&copies, Vec::new())
+        } else {
+            return;
+        };
+        let mut warning = Report::warning(
+            format!(
+                "The index of {} depends on signals, so the access is a multiplexer of the elements of the array, which adds 2 non-linear constraints for each of them",
+                name
+            ),
+            ReportCode::SignalIndexWarning,
+        );
+        warning.add_primary(meta.location.clone(), lowering.file_id, "here".to_string());
+        lowering.warnings.push(warning);
+        let mux = build_anonymous_component(meta.clone(), "$Mux".to_string(), vec![length], vec![candidates, index], None, false);
+        *e = lower_to_variable(meta.clone(), format!("$index_{}_{}", meta.start, meta.end), mux, lowering, before);
+    });
+}
+
+// The powers of values that depend on signals to exponents that do not, as
+// in ** 5, are the anonymous components of $Pow. The powers to 0, 1 and 2
+// are already quadratic
+fn lower_powers(expression: &mut Expression, lowering: &mut Lowering, before: &mut Vec<Statement>) {
+    visit_expression(expression, &mut |e| {
+        let (meta, lhe, rhe) = match e {
+            Expression::InfixOp { meta, lhe, infix_op: ExpressionInfixOpcode::Pow, rhe } => (meta.clone(), lhe, rhe),
+            _ => return,
+        };
+        let is_quadratic = matches!(rhe.as_ref(), Expression::Number(_, value) if *value <= BigInt::from(2));
+        if is_quadratic || !mentions_signal(lhe, &lowering.signals) || mentions_signal(rhe, &lowering.signals) {
+            return;
+        }
+        let power = build_anonymous_component(meta.clone(), "$Pow".to_string(), vec![*rhe.clone()], vec![*lhe.clone()], None, false);
+        *e = lower_to_variable(meta.clone(), format!("$pow_{}_{}", meta.start, meta.end), power, lowering, before);
+    });
+}
+
+fn lower_expression(expression: &mut Expression, lowering: &mut Lowering, before: &mut Vec<Statement>) {
+    lower_indexes(expression, lowering, before);
+    lower_powers(expression, lowering, before);
+}
+
+fn lower_statement(statement: &mut Statement, lowering: &mut Lowering, before: &mut Vec<Statement>) {
+    use Statement::*;
+    match statement {
+        Substitution { op, rhe, .. } if *op != AssignOp::AssignSignal => lower_expression(rhe, lowering, before),
+        ConstraintEquality { lhe, rhe, .. } => {
+            lower_expression(lhe, lowering, before);
+            lower_expression(rhe, lowering, before);
+        }
+        InitializationBlock { initializations, .. } => {
+            for initialization in initializations {
+                lower_statement(initialization, lowering, before);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn lower_block(stmts: &mut Vec<Statement>, lowering: &mut Lowering) {
+    for stmt in std::mem::take(stmts) {
+        let mut stmt = stmt;
+        let mut before = Vec::new();
+        match &mut stmt {
+            Statement::Block { stmts, .. } => lower_block(stmts, lowering),
+            Statement::IfThenElse { if_case, else_case, .. } => {
+                lower_branch(if_case, lowering);
+                if let Some(else_case) = else_case {
+                    lower_branch(else_case, lowering);
+                }
+            }
+            Statement::While { stmt, .. } => {
+                lowering.loops += 1;
+                lower_branch(stmt, lowering);
+                lowering.loops -= 1;
+            }
+            _ => lower_statement(&mut stmt, lowering, &mut before),
+        }
+        stmts.append(&mut before);
+        stmts.push(stmt);
+    }
+}
+
+fn lower_branch(branch: &mut Statement, lowering: &mut Lowering) {
+    if let Statement::Block { stmts, .. } = branch {
+        lower_block(stmts, lowering);
+    } else {
+        let meta = branch.get_meta().clone();
+        let mut stmts = vec![branch.clone()];
+        lower_block(&mut stmts, lowering);
+        if stmts.len() > 1 {
+            *branch = build_block(meta, stmts);
+        }
+    }
+}
+
+// Lowers the powers of signals and their accesses by indexes that depend on
+// signals, and tells whether some statement of the template uses them
+fn lower_template(body: &mut Statement, params: &[String], file_id: FileID, warnings: &mut ReportCollection) -> bool {
+    let mut signals = HashMap::new();
+    collect_signals(body, &mut signals);
+    let known = signals.len();
+    let mut lowering = Lowering { signals, params, loops: 0, file_id, warnings };
+    lower_branch(body, &mut lowering);
+    lowering.signals.len() > known
+}
+
+// Finds the calls to the builtins, unless the program defines a template
+// or a function with that name, lowers the powers of signals and their
+// accesses by indexes that depend on signals, and tells whether the program
+// uses the builtin templates, together with the warnings of the accesses
+pub fn apply_builtin_calls(
+    definitions: &mut [(FileID, Vec<Definition>)],
+    main_id: FileID,
+    main_component: &mut Expression,
+) -> Result<(bool, ReportCollection), ReportCollection> {
+    let mut defined = HashSet::new();
+    for (_, file_definitions) in definitions.iter() {
+        for definition in file_definitions {
+            if let Definition::Template { name, .. } | Definition::Function { name, .. } = definition {
+                defined.insert(name.clone());
+            }
+        }
+    }
+    let mut reports = Vec::new();
+    let mut warnings = Vec::new();
+    let mut uses_templates = false;
+    for (file_id, file_definitions) in definitions.iter_mut() {
+        for definition in file_definitions {
+            let in_template = matches!(definition, Definition::Template { .. });
+            if let Definition::Template { body, args, .. } = definition {
+                uses_templates |= lower_template(body, args, *file_id, &mut warnings);
+            }
+            if let Definition::Template { body, .. } | Definition::Function { body, .. } = definition {
+                visit_statement(body, &mut |e| {
+                    rename_call(e, &defined, *file_id, &mut reports);
+                    lower_signed_call(e, &defined, in_template, &mut uses_templates, *file_id, &mut reports);
+                });
+            }
+        }
+    }
+    visit_expression(main_component, &mut |e| {
+        rename_call(e, &defined, main_id, &mut reports);
+        lower_signed_call(e, &defined, false, &mut uses_templates, main_id, &mut reports);
+    });
+    if reports.is_empty() {
+        Ok((uses_templates, warnings))
+    } else {
+        Err(reports)
+    }
+}
@@ -0,0 +1,94 @@
+// The templates that the compiler uses for some operations of the language.
+// FIELD_BITS is replaced by the number of bits of the prime
+
+// The templates of sign(x, n) and abs(x, n), which take x as a signed value
+// with |x| < 2^n. As x + 2^n is in [0, 2^(n + 1)), its bit n tells whether
+// x is not negative
+
+template $NonNegative(n) {
+    signal input in;
+    signal output out;
+    signal bits[n + 1];
+    assert(n + 1 < FIELD_BITS);
+    var sum = 0;
+    for (var k = 0; k <= n; k++) {
+        bits[k] <-- ((in + 2 ** n) >> k) & 1;
+        bits[k] * (bits[k] - 1) === 0;
+        sum += bits[k] * 2 ** k;
+    }
+    sum === in + 2 ** n;
+    out <== bits[n];
+}
+
+template $Sign(n) {
+    signal input in;
+    signal output out;
+    signal inv;
+    signal nonzero;
+    inv <-- in != 0 ? 1 / in : 0;
+    nonzero <== in * inv;
+    in * (1 - nonzero) === 0;
+    signal non_negative <== $NonNegative(n)(in);
+    out <== nonzero * (2 * non_negative - 1);
+}
+
+template $Abs(n) {
+    signal input in;
+    signal output out;
+    signal non_negative <== $NonNegative(n)(in);
+    out <== in * (2 * non_negative - 1);
+}
+
+// The power of a signal to e, known at compilation time, by squaring it for
+// each bit of e after the most significant one and multiplying it by in for
+// each bit that is 1
+template $Pow(e) {
+    signal input in;
+    signal output out;
+    var bits = 0;
+    var ones = 0;
+    while ((e >> bits) > 0) {
+        ones += (e >> bits) & 1;
+        bits++;
+    }
+    if (bits == 0) {
+        out <== 1;
+    } else {
+        signal steps[bits + ones - 2];
+        var acc = in;
+        var k = 0;
+        for (var i = bits - 2; i >= 0; i--) {
+            steps[k] <== acc * acc;
+            acc = steps[k];
+            k++;
+            if ((e >> i) & 1 == 1) {
+                steps[k] <== acc * in;
+                acc = steps[k];
+                k++;
+            }
+        }
+        out <== acc;
+    }
+}
+
+// The element of in at index, selected by the products of the elements and
+// the selectors, where only the selector of index can be other than 0 and
+// they add up to 1, so an index out of the array has no witness
+template $Mux(n) {
+    signal input in[n];
+    signal input index;
+    signal output out;
+    signal selectors[n];
+    signal products[n];
+    var count = 0;
+    var selected = 0;
+    for (var i = 0; i < n; i++) {
+        selectors[i] <-- index == i ? 1 : 0;
+        selectors[i] * (index - i) === 0;
+        count += selectors[i];
+        products[i] <== selectors[i] * in[i];
+        selected += products[i];
+    }
+    count === 1;
+    out <== selected;
+}
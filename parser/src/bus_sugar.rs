@@ -0,0 +1,376 @@
+use crate::template_defaults::visit_statement;
+use num_bigint::BigInt;
+use program_structure::ast::*;
+use program_structure::error_code::ReportCode;
+use program_structure::expression_builders::{build_tuple, build_variable};
+use program_structure::statement_builders::BUS_FIELDS_ID;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::file_definition::FileID;
+use std::collections::HashMap;
+
+fn sugar_error(message: String, meta: &Meta, file_id: FileID) -> Report {
+    let mut report = Report::error(message, ReportCode::BusSugarError);
+    report.add_primary(meta.location.clone(), file_id, "here".to_string());
+    report
+}
+
+// The kind of a symbol, with its number of dimensions
+#[derive(Clone)]
+enum Kind {
+    Bus(String, usize),
+    Component(String, usize),
+    Other,
+}
+
+// The symbols declared in a body, together with the templates assigned to
+// the components
+fn collect_symbols(statement: &Statement, symbols: &mut HashMap<String, Kind>) {
+    use Statement::*;
+    match statement {
+        Declaration { xtype, name, dimensions, .. } => {
+            let kind = match xtype {
+                VariableType::Bus(bus, ..) => Kind::Bus(bus.clone(), dimensions.len()),
+                VariableType::Component => Kind::Component(String::new(), dimensions.len()),
+                _ => Kind::Other,
+            };
+            symbols.entry(name.clone()).or_insert(kind);
+        }
+        Substitution { var, rhe: Expression::Call { id, .. }, .. } => {
+            if let Some(Kind::Component(template, _)) = symbols.get_mut(var) {
+                if template.is_empty() {
+                    *template = id.clone();
+                }
+            }
+        }
+        IfThenElse { if_case, else_case, .. } => {
+            collect_symbols(if_case, symbols);
+            if let Some(else_case) = else_case {
+                collect_symbols(else_case, symbols);
+            }
+        }
+        While { stmt, .. } => collect_symbols(stmt, symbols),
+        InitializationBlock { initializations: stmts, .. } | Block { stmts, .. } => {
+            stmts.iter().for_each(|s| collect_symbols(s, symbols))
+        }
+        _ => {}
+    }
+}
+
+// The inputs or the outputs of a template, in the order they are declared
+fn collect_wires(statement: &Statement, wire: SignalType, wires: &mut Vec<String>) {
+    use Statement::*;
+    match statement {
+        Declaration { xtype: VariableType::Signal(signal, _), name, .. }
+        | Declaration { xtype: VariableType::Bus(_, signal, _), name, .. }
+            if *signal == wire =>
+        {
+            wires.push(name.clone())
+        }
+        IfThenElse { if_case, else_case, .. } => {
+            collect_wires(if_case, wire, wires);
+            if let Some(else_case) = else_case {
+                collect_wires(else_case, wire, wires);
+            }
+        }
+        While { stmt, .. } => collect_wires(stmt, wire, wires),
+        InitializationBlock { initializations: stmts, .. } | Block { stmts, .. } => {
+            stmts.iter().for_each(|s| collect_wires(s, wire, wires))
+        }
+        _ => {}
+    }
+}
+
+// The signals and buses of a bus, in the order they are declared
+fn collect_fields(statement: &Statement, fields: &mut Vec<String>) {
+    use Statement::*;
+    match statement {
+        Declaration { xtype: VariableType::Signal(..), name, .. }
+        | Declaration { xtype: VariableType::Bus(..), name, .. } => fields.push(name.clone()),
+        InitializationBlock { initializations: stmts, .. } | Block { stmts, .. } => {
+            stmts.iter().for_each(|s| collect_fields(s, fields))
+        }
+        _ => {}
+    }
+}
+
+fn is_destructuring(expression: &Expression) -> bool {
+    matches!(expression, Expression::Call { id, .. } if id == BUS_FIELDS_ID)
+}
+
+struct BusSugar {
+    operators: HashMap<(String, String), Vec<(ExpressionInfixOpcode, String)>>,
+    // the signals and buses declared in each template and bus
+    fields: HashMap<String, HashMap<String, Kind>>,
+    // the inputs and the outputs of each template
+    inputs: HashMap<String, Vec<String>>,
+    outputs: HashMap<String, Vec<String>>,
+    // the fields of each bus
+    bus_fields: HashMap<String, Vec<String>>,
+    reports: ReportCollection,
+}
+
+impl BusSugar {
+    fn template(&self, op: ExpressionInfixOpcode, left: &str, right: &str) -> Option<&String> {
+        let operators = self.operators.get(&(left.to_string(), right.to_string()))?;
+        operators.iter().find(|(o, _)| *o == op).map(|(_, template)| template)
+    }
+
+    // The bus of an expression, when it is known from the declarations
+    fn bus_of(&self, expression: &Expression, symbols: &HashMap<String, Kind>) -> Option<String> {
+        match expression {
+            Expression::Variable { name, access, .. } => {
+                let mut kind = symbols.get(name)?.clone();
+                for acc in access {
+                    kind = match (kind, acc) {
+                        (Kind::Bus(bus, dims), Access::ArrayAccess(_)) if dims > 0 => Kind::Bus(bus, dims - 1),
+                        (Kind::Component(t, dims), Access::ArrayAccess(_)) if dims > 0 => Kind::Component(t, dims - 1),
+                        (Kind::Bus(name, 0), Access::ComponentAccess(field))
+                        | (Kind::Component(name, 0), Access::ComponentAccess(field)) => {
+                            self.fields.get(&name)?.get(field)?.clone()
+                        }
+                        _ => return None,
+                    };
+                }
+                match kind {
+                    Kind::Bus(bus, 0) => Some(bus),
+                    _ => None,
+                }
+            }
+            Expression::AnonymousComp { id, .. } => match self.outputs.get(id)?.as_slice() {
+                [output] => match self.fields.get(id)?.get(output)? {
+                    Kind::Bus(bus, 0) => Some(bus.clone()),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // The bus variable of a call to a member, as in p.norm(), together with
+    // the template of the member
+    fn member_of(&mut self, id: &str, meta: &Meta, symbols: &HashMap<String, Kind>, file_id: FileID) -> Option<(String, String)> {
+        let (variable, member) = id.split_once('.')?;
+        let bus = match symbols.get(variable)? {
+            Kind::Bus(bus, 0) => bus,
+            _ => return None,
+        };
+        let template = format!("{}.{}", bus, member);
+        if !self.inputs.contains_key(&template) {
+            let message = format!("The bus {} has no member {}", bus, member);
+            self.reports.push(sugar_error(message, meta, file_id));
+            return None;
+        }
+        Some((variable.to_string(), template))
+    }
+
+    // Replaces the calls to the members of a bus by an anonymous component of
+    // the template of the member, whose first input is the bus
+    fn lower_member(&mut self, expression: &mut Expression, symbols: &HashMap<String, Kind>, file_id: FileID) {
+        let (meta, id, args) = match expression {
+            Expression::Call { meta, id, args } => (meta, id, args),
+            Expression::AnonymousComp { meta, id, signals, names, .. } => {
+                if let Some((variable, template)) = self.member_of(id, meta, symbols, file_id) {
+                    signals.insert(0, build_variable(meta.clone(), variable, Vec::new()));
+                    if let (Some(names), Some(input)) = (names, self.inputs[&template].first()) {
+                        names.insert(0, (AssignOp::AssignConstraintSignal, input.clone()));
+                    }
+                    *id = template;
+                }
+                return;
+            }
+            _ => return,
+        };
+        if let Some((variable, template)) = self.member_of(id, meta, symbols, file_id) {
+            let signals = vec![build_variable(meta.clone(), variable, Vec::new())];
+            let params = std::mem::take(args);
+            *expression =
+                Expression::AnonymousComp { meta: meta.clone(), id: template, is_parallel: false, params, signals, names: None };
+        }
+    }
+
+    // Replaces the bus of a destructuring, as {x, y} <== p, by the tuple of
+    // its fields, (p.x, p.y)
+    fn lower_destructuring(&mut self, statement: &mut Statement, symbols: &HashMap<String, Kind>, file_id: FileID) {
+        use Statement::*;
+        match statement {
+            MultSubstitution { lhe: Expression::Tuple { values, .. }, rhe, .. } if is_destructuring(rhe) => {
+                let bus = match rhe {
+                    Expression::Call { args, .. } => args.remove(0),
+                    _ => unreachable!(),
+                };
+                let (bus_name, fields) = match (&bus, self.bus_of(&bus, symbols)) {
+                    (Expression::Variable { .. }, Some(name)) => {
+                        let fields = self.bus_fields.get(&name).cloned().unwrap_or_default();
+                        (name, fields)
+                    }
+                    _ => {
+                        let message = "Only a bus signal can be destructured with {x, y}".to_string();
+                        self.reports.push(sugar_error(message, bus.get_meta(), file_id));
+                        return;
+                    }
+                };
+                if fields.len() != values.len() {
+                    let message = format!("The bus {} has {} fields, but {} signals take them", bus_name, fields.len(), values.len());
+                    self.reports.push(sugar_error(message, bus.get_meta(), file_id));
+                    return;
+                }
+                let (meta, name, access) = match bus {
+                    Expression::Variable { meta, name, access } => (meta, name, access),
+                    _ => unreachable!(),
+                };
+                let fields = fields.into_iter().map(|field| {
+                    let mut access = access.clone();
+                    access.push(Access::ComponentAccess(field));
+                    build_variable(meta.clone(), name.clone(), access)
+                });
+                *rhe = build_tuple(meta.clone(), fields.collect());
+            }
+            IfThenElse { if_case, else_case, .. } => {
+                self.lower_destructuring(if_case, symbols, file_id);
+                if let Some(else_case) = else_case {
+                    self.lower_destructuring(else_case, symbols, file_id);
+                }
+            }
+            While { stmt, .. } => self.lower_destructuring(stmt, symbols, file_id),
+            InitializationBlock { initializations: stmts, .. } | Block { stmts, .. } => {
+                stmts.iter_mut().for_each(|s| self.lower_destructuring(s, symbols, file_id))
+            }
+            _ => {}
+        }
+    }
+
+    // Replaces the operations between buses that have an operator by an
+    // anonymous component of the template of the operator
+    fn lower_operator(&self, expression: &mut Expression, symbols: &HashMap<String, Kind>) {
+        let template = match expression {
+            Expression::InfixOp { lhe, infix_op, rhe, .. } => {
+                match (self.bus_of(lhe, symbols), self.bus_of(rhe, symbols)) {
+                    (Some(left), Some(right)) => self.template(*infix_op, &left, &right).cloned(),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        if let Some(template) = template {
+            let placeholder = Expression::Number(Meta::new(0, 0), BigInt::from(0));
+            if let Expression::InfixOp { mut meta, lhe, rhe, .. } = std::mem::replace(expression, placeholder) {
+                // the anonymous components are named after their start, which
+                // is shared by the nested operations of a + b + c
+                meta.start = rhe.get_meta().start;
+                let signals = vec![*lhe, *rhe];
+                *expression =
+                    Expression::AnonymousComp { meta, id: template, is_parallel: false, params: Vec::new(), signals, names: None };
+            }
+        }
+    }
+}
+
+// Removes the operator definitions, replaces the operations between buses
+// and the calls to the members of buses by the templates that compute them,
+// and the buses of the destructurings by their fields
+pub fn apply_bus_sugar(definitions: &mut [(FileID, Vec<Definition>)]) -> Result<(), ReportCollection> {
+    let mut sugar = BusSugar {
+        operators: HashMap::new(),
+        fields: HashMap::new(),
+        inputs: HashMap::new(),
+        outputs: HashMap::new(),
+        bus_fields: HashMap::new(),
+        reports: Vec::new(),
+    };
+    let mut declared = Vec::new();
+    for (file_id, file_definitions) in definitions.iter_mut() {
+        for definition in std::mem::take(file_definitions) {
+            match definition {
+                Definition::Operator { meta, op, left, right, template } => {
+                    let entry = sugar.operators.entry((left.clone(), right.clone())).or_default();
+                    if entry.iter().any(|(o, _)| *o == op) {
+                        let message = format!("The operator for the buses {} and {} is defined more than once", left, right);
+                        sugar.reports.push(sugar_error(message, &meta, *file_id));
+                    } else {
+                        entry.push((op, template.clone()));
+                    }
+                    declared.push((*file_id, meta, template));
+                }
+                other => file_definitions.push(other),
+            }
+        }
+    }
+    // the names of the members, as Point.norm, are the only ones with a dot
+    // besides the names of the files included with an alias
+    let has_members = definitions.iter().flat_map(|(_, d)| d).any(|d| match d {
+        Definition::Template { name, .. } => name.contains('.'),
+        _ => false,
+    });
+    let mut has_destructurings = false;
+    for (_, file_definitions) in definitions.iter_mut() {
+        for definition in file_definitions {
+            if let Definition::Template { body, .. } | Definition::Function { body, .. } = definition {
+                visit_statement(body, &mut |e| has_destructurings |= is_destructuring(e));
+            }
+        }
+    }
+    if declared.is_empty() && !has_members && !has_destructurings {
+        return Ok(());
+    }
+    for (_, file_definitions) in definitions.iter() {
+        for definition in file_definitions {
+            let (name, body) = match definition {
+                Definition::Template { name, body, .. } | Definition::Bus { name, body, .. } => (name, body),
+                _ => continue,
+            };
+            let mut fields = HashMap::new();
+            collect_symbols(body, &mut fields);
+            if let Definition::Template { .. } = definition {
+                let (mut inputs, mut outputs) = (Vec::new(), Vec::new());
+                collect_wires(body, SignalType::Input, &mut inputs);
+                collect_wires(body, SignalType::Output, &mut outputs);
+                sugar.inputs.insert(name.clone(), inputs);
+                sugar.outputs.insert(name.clone(), outputs);
+            }
+            if let Definition::Bus { .. } = definition {
+                let mut bus_fields = Vec::new();
+                collect_fields(body, &mut bus_fields);
+                sugar.bus_fields.insert(name.clone(), bus_fields);
+            }
+            sugar.fields.insert(name.clone(), fields);
+        }
+    }
+    for (file_id, meta, template) in declared {
+        let message = match definitions.iter().flat_map(|(_, d)| d).find_map(|d| match d {
+            Definition::Template { name, args, defaults, .. } if *name == template => Some(args.len() - defaults.len()),
+            _ => None,
+        }) {
+            None => format!("The operator uses {}, which is not a template", template),
+            Some(required) if required > 0 => {
+                format!("The template {} of the operator must not require parameters", template)
+            }
+            Some(_) => continue,
+        };
+        sugar.reports.push(sugar_error(message, &meta, file_id));
+    }
+    if !sugar.reports.is_empty() {
+        return Err(sugar.reports);
+    }
+    for (file_id, file_definitions) in definitions.iter_mut() {
+        for definition in file_definitions {
+            if let Definition::Function { body, .. } = definition {
+                sugar.lower_destructuring(body, &HashMap::new(), *file_id);
+            }
+            if let Definition::Template { body, .. } = definition {
+                let mut symbols = HashMap::new();
+                collect_symbols(body, &mut symbols);
+                sugar.lower_destructuring(body, &symbols, *file_id);
+                visit_statement(body, &mut |e| {
+                    sugar.lower_member(e, &symbols, *file_id);
+                    sugar.lower_operator(e, &symbols)
+                });
+            }
+        }
+    }
+    if sugar.reports.is_empty() {
+        Ok(())
+    } else {
+        Err(sugar.reports)
+    }
+}
@@ -0,0 +1,99 @@
+use crate::template_defaults::{visit_expression, visit_statement};
+use program_structure::ast::*;
+use program_structure::ast::produce_report;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::ReportCollection;
+use program_structure::file_definition::FileID;
+use std::collections::HashSet;
+
+fn is_extern_call(expression: &Expression, externs: &HashSet<String>) -> bool {
+    matches!(expression, Expression::Call { id, .. } if externs.contains(id))
+}
+
+fn reject_extern_call(expression: &Expression, externs: &HashSet<String>, file_id: FileID, reports: &mut ReportCollection) {
+    if is_extern_call(expression, externs) {
+        reports.push(produce_report(ReportCode::ExternFunctionError, expression.get_meta().location.clone(), file_id));
+    }
+}
+
+fn reject_extern_calls(expression: &mut Expression, externs: &HashSet<String>, file_id: FileID, reports: &mut ReportCollection) {
+    visit_expression(expression, &mut |e| reject_extern_call(e, externs, file_id, reports));
+}
+
+// The assignments with <-- that call an extern function are hints, so the
+// signals they assign must be constrained by the template
+fn mark_extern_hints(statement: &mut Statement, externs: &HashSet<String>, file_id: FileID, reports: &mut ReportCollection) {
+    use Statement::*;
+    match statement {
+        Substitution { meta, access, op: AssignOp::AssignSignal, rhe, .. } => {
+            for acc in access {
+                if let Access::ArrayAccess(index) = acc {
+                    reject_extern_calls(index, externs, file_id, reports);
+                }
+            }
+            let mut calls_extern = false;
+            visit_expression(rhe, &mut |e| calls_extern |= is_extern_call(e, externs));
+            meta.hint |= calls_extern;
+        }
+        IfThenElse { cond, if_case, else_case, .. } => {
+            reject_extern_calls(cond, externs, file_id, reports);
+            mark_extern_hints(if_case, externs, file_id, reports);
+            if let Some(else_case) = else_case {
+                mark_extern_hints(else_case, externs, file_id, reports);
+            }
+        }
+        While { cond, stmt, .. } => {
+            reject_extern_calls(cond, externs, file_id, reports);
+            mark_extern_hints(stmt, externs, file_id, reports);
+        }
+        Block { stmts, .. } | InitializationBlock { initializations: stmts, .. } => {
+            for stmt in stmts {
+                mark_extern_hints(stmt, externs, file_id, reports);
+            }
+        }
+        _ => visit_statement(statement, &mut |e| reject_extern_call(e, externs, file_id, reports)),
+    }
+}
+
+// The extern functions can only be called in the assignments of signals with
+// <-- of the templates, as their values are only known at witness time
+pub fn apply_extern_functions(
+    definitions: &mut [(FileID, Vec<Definition>)],
+    main_id: FileID,
+    main_component: &mut Expression,
+) -> Result<(), ReportCollection> {
+    let mut externs = HashSet::new();
+    for (_, file_definitions) in definitions.iter() {
+        for definition in file_definitions {
+            if let Definition::Function { name, is_extern: true, .. } = definition {
+                externs.insert(name.clone());
+            }
+        }
+    }
+    if externs.is_empty() {
+        return Ok(());
+    }
+    let mut reports = Vec::new();
+    for (file_id, file_definitions) in definitions.iter_mut() {
+        for definition in file_definitions {
+            match definition {
+                Definition::Template { body, defaults, .. } => {
+                    for default in defaults {
+                        reject_extern_calls(default, &externs, *file_id, &mut reports);
+                    }
+                    mark_extern_hints(body, &externs, *file_id, &mut reports);
+                }
+                Definition::Function { body, .. } | Definition::Bus { body, .. } => {
+                    visit_statement(body, &mut |e| reject_extern_call(e, &externs, *file_id, &mut reports))
+                }
+                _ => {}
+            }
+        }
+    }
+    reject_extern_calls(main_component, &externs, main_id, &mut reports);
+    if reports.is_empty() {
+        Ok(())
+    } else {
+        Err(reports)
+    }
+}
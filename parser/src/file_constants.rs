@@ -0,0 +1,507 @@
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use program_structure::ast::*;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::file_definition::FileID;
+use program_structure::statement_builders::{build_declaration, build_initialization_block, build_substitution};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+
+struct Constant {
+    file_id: FileID,
+    meta: Meta,
+    dimensions: Vec<Expression>,
+    value: Expression,
+}
+
+struct Enum {
+    members: Vec<(String, BigInt)>,
+}
+
+// The variables read by the code, and the ones it declares or assigns
+#[derive(Default)]
+struct Names {
+    read: HashSet<String>,
+    declared: HashSet<String>,
+    assigned: Vec<(String, Meta)>,
+}
+
+fn constant_error(message: String, meta: &Meta, file_id: FileID) -> Report {
+    let mut report = Report::error(message, ReportCode::FileConstantError);
+    report.add_primary(meta.location.clone(), file_id, "here".to_string());
+    report
+}
+
+fn collect_access(access: &[Access], names: &mut Names) {
+    for acc in access {
+        if let Access::ArrayAccess(index) = acc {
+            collect_expression(index, names);
+        }
+    }
+}
+
+fn collect_expressions(expressions: &[Expression], names: &mut Names) {
+    for e in expressions {
+        collect_expression(e, names);
+    }
+}
+
+fn collect_expression(expression: &Expression, names: &mut Names) {
+    use Expression::*;
+    match expression {
+        InfixOp { lhe, rhe, .. } => {
+            collect_expression(lhe, names);
+            collect_expression(rhe, names);
+        }
+        PrefixOp { rhe, .. } | ParallelOp { rhe, .. } => collect_expression(rhe, names),
+        InlineSwitchOp { cond, if_true, if_false, .. } => {
+            collect_expression(cond, names);
+            collect_expression(if_true, names);
+            collect_expression(if_false, names);
+        }
+        Variable { name, access, .. } => {
+            names.read.insert(name.clone());
+            collect_access(access, names);
+        }
+        Number(..) => {}
+        Call { args, .. } | BusCall { args, .. } => collect_expressions(args, names),
+        AnonymousComp { params, signals, .. } => {
+            collect_expressions(params, names);
+            collect_expressions(signals, names);
+        }
+        ArrayInLine { values, .. } | Tuple { values, .. } => collect_expressions(values, names),
+        UniformArray { value, dimension, .. } => {
+            collect_expression(value, names);
+            collect_expression(dimension, names);
+        }
+    }
+}
+
+// The variables of a tuple on the left of an assignment are assigned by it
+fn collect_assigned(lhe: &Expression, names: &mut Names) {
+    match lhe {
+        Expression::Tuple { values, .. } => values.iter().for_each(|v| collect_assigned(v, names)),
+        Expression::Variable { meta, name, access } => {
+            names.assigned.push((name.clone(), meta.clone()));
+            collect_access(access, names);
+        }
+        other => collect_expression(other, names),
+    }
+}
+
+fn collect_statement(statement: &Statement, names: &mut Names) {
+    use Statement::*;
+    match statement {
+        IfThenElse { cond, if_case, else_case, .. } => {
+            collect_expression(cond, names);
+            collect_statement(if_case, names);
+            if let Some(else_case) = else_case {
+                collect_statement(else_case, names);
+            }
+        }
+        While { cond, stmt, .. } => {
+            collect_expression(cond, names);
+            collect_statement(stmt, names);
+        }
+        Return { value, .. } => collect_expression(value, names),
+        InitializationBlock { initializations, .. } => {
+            for initialization in initializations {
+                collect_statement(initialization, names);
+            }
+        }
+        Declaration { name, dimensions, .. } => {
+            names.declared.insert(name.clone());
+            collect_expressions(dimensions, names);
+        }
+        Substitution { meta, var, access, rhe, .. } => {
+            names.assigned.push((var.clone(), meta.clone()));
+            collect_access(access, names);
+            collect_expression(rhe, names);
+        }
+        MultSubstitution { lhe, rhe, .. } => {
+            collect_assigned(lhe, names);
+            collect_expression(rhe, names);
+        }
+        ConstraintEquality { lhe, rhe, .. } => {
+            collect_expression(lhe, names);
+            collect_expression(rhe, names);
+        }
+        UnderscoreSubstitution { rhe, .. } => collect_expression(rhe, names),
+        LogCall { args, .. } => {
+            for arg in args {
+                if let LogArgument::LogExp(e) = arg {
+                    collect_expression(e, names);
+                }
+            }
+        }
+        Block { stmts, .. } => {
+            for stmt in stmts {
+                collect_statement(stmt, names);
+            }
+        }
+        Assert { arg, message, .. } => {
+            collect_expression(arg, names);
+            for m in message {
+                if let LogArgument::LogExp(e) = m {
+                    collect_expression(e, names);
+                }
+            }
+        }
+    }
+}
+
+fn constants_used(constant: &Constant, constants: &HashMap<String, Constant>) -> Vec<String> {
+    let mut names = Names::default();
+    collect_expressions(&constant.dimensions, &mut names);
+    collect_expression(&constant.value, &mut names);
+    let mut used: Vec<_> = names.read.into_iter().filter(|name| constants.contains_key(name)).collect();
+    used.sort();
+    used
+}
+
+struct Constants {
+    constants: HashMap<String, Constant>,
+    enums: HashMap<String, Enum>,
+    // the constants used by each constant
+    uses: HashMap<String, Vec<String>>,
+    reports: ReportCollection,
+}
+
+impl Constants {
+    // Adds the constant after the constants it uses, or fails with a constant
+    // whose value depends on itself
+    fn add_in_order(&self, name: &str, visiting: &mut Vec<String>, ordered: &mut Vec<String>) -> Result<(), String> {
+        if ordered.iter().any(|n| n == name) {
+            return Ok(());
+        }
+        if visiting.iter().any(|n| n == name) {
+            return Err(name.to_string());
+        }
+        visiting.push(name.to_string());
+        for used in &self.uses[name] {
+            self.add_in_order(used, visiting, ordered)?;
+        }
+        visiting.pop();
+        ordered.push(name.to_string());
+        Ok(())
+    }
+
+    // The constants used by the code, after the constants they use
+    fn required(&self, used: &[String]) -> Vec<String> {
+        let mut ordered = Vec::new();
+        for name in used {
+            // the cycles are reported before
+            let _ = self.add_in_order(name, &mut Vec::new(), &mut ordered);
+        }
+        ordered
+    }
+
+    // Declares the constants read by the body at its start, unless the body
+    // declares variables with the same names
+    fn declare_in_body(&mut self, body: &mut Statement, args: &[String], file_id: FileID) {
+        let mut names = Names::default();
+        collect_statement(body, &mut names);
+        let mut used: Vec<_> = names
+            .read
+            .iter()
+            .filter(|name| self.constants.contains_key(*name) && !names.declared.contains(*name) && !args.contains(name))
+            .cloned()
+            .collect();
+        used.sort();
+        for (name, meta) in &names.assigned {
+            if used.contains(name) {
+                let message = format!("The constant {} cannot be assigned", name);
+                self.reports.push(constant_error(message, meta, file_id));
+            }
+        }
+        let required = self.required(&used);
+        if let Statement::Block { stmts, .. } = body {
+            let declarations = required.iter().map(|name| {
+                let constant = &self.constants[name];
+                let meta = constant.meta.clone();
+                let declaration = build_declaration(meta.clone(), VariableType::Var, name.clone(), constant.dimensions.clone());
+                let substitution = build_substitution(meta.clone(), name.clone(), Vec::new(), AssignOp::AssignVar, constant.value.clone());
+                build_initialization_block(meta, VariableType::Var, vec![declaration, substitution])
+            });
+            stmts.splice(0..0, declarations);
+        }
+    }
+
+    // The value of a constant read by an expression that has no variables,
+    // with the constants it uses replaced by their values
+    fn value_of(&mut self, meta: &Meta, name: &str, access: &[Access], file_id: FileID) -> Option<Expression> {
+        let mut value = self.constants[name].value.clone();
+        self.replace_in_expression(&mut value, &[], file_id);
+        for acc in access {
+            let index = match acc {
+                Access::ArrayAccess(Expression::Number(_, index)) => index.to_usize(),
+                _ => None,
+            };
+            value = match (value, index) {
+                (Expression::ArrayInLine { mut values, .. }, Some(index)) if index < values.len() => values.swap_remove(index),
+                _ => {
+                    let message = format!("The constant {} can only be indexed by numbers here", name);
+                    self.reports.push(constant_error(message, meta, file_id));
+                    return None;
+                }
+            };
+        }
+        Some(value)
+    }
+
+    // The value of a member of an enum, read as Name.MEMBER
+    fn member_value(&mut self, meta: &Meta, name: &str, access: &[Access], file_id: FileID) -> Option<Expression> {
+        let member = match access {
+            [Access::ComponentAccess(member)] => member,
+            _ => {
+                let message = format!("The enum {} can only be used as {}.MEMBER", name, name);
+                self.reports.push(constant_error(message, meta, file_id));
+                return None;
+            }
+        };
+        match self.enums[name].members.iter().find(|(m, _)| m == member) {
+            Some((_, value)) => Some(Expression::Number(meta.clone(), value.clone())),
+            None => {
+                let message = format!("The enum {} has no member {}", name, member);
+                self.reports.push(constant_error(message, meta, file_id));
+                None
+            }
+        }
+    }
+
+    fn replace_in_expressions(&mut self, expressions: &mut [Expression], args: &[String], file_id: FileID) {
+        for e in expressions {
+            self.replace_in_expression(e, args, file_id);
+        }
+    }
+
+    // Replaces the constants and the members of enums read by the expression
+    // by their values
+    fn replace_in_expression(&mut self, expression: &mut Expression, args: &[String], file_id: FileID) {
+        self.replace_names(expression, args, true, file_id);
+    }
+
+    fn replace_names(&mut self, expression: &mut Expression, args: &[String], constants: bool, file_id: FileID) {
+        use Expression::*;
+        match expression {
+            InfixOp { lhe, rhe, .. } => {
+                self.replace_names(lhe, args, constants, file_id);
+                self.replace_names(rhe, args, constants, file_id);
+            }
+            PrefixOp { rhe, .. } | ParallelOp { rhe, .. } => self.replace_names(rhe, args, constants, file_id),
+            InlineSwitchOp { cond, if_true, if_false, .. } => {
+                self.replace_names(cond, args, constants, file_id);
+                self.replace_names(if_true, args, constants, file_id);
+                self.replace_names(if_false, args, constants, file_id);
+            }
+            Variable { meta, name, access } if self.enums.contains_key(name) && !args.contains(name) => {
+                if let Some(value) = self.member_value(meta, name, access, file_id) {
+                    *expression = value;
+                }
+            }
+            Variable { meta, name, access } if constants && self.constants.contains_key(name) && !args.contains(name) => {
+                if let Some(value) = self.value_of(meta, name, access, file_id) {
+                    *expression = value;
+                }
+            }
+            Variable { access, .. } => {
+                for acc in access {
+                    if let Access::ArrayAccess(index) = acc {
+                        self.replace_names(index, args, constants, file_id);
+                    }
+                }
+            }
+            Number(..) => {}
+            Call { args: values, .. } | BusCall { args: values, .. } => {
+                values.iter_mut().for_each(|v| self.replace_names(v, args, constants, file_id))
+            }
+            AnonymousComp { params, signals, .. } => {
+                params.iter_mut().chain(signals).for_each(|v| self.replace_names(v, args, constants, file_id))
+            }
+            ArrayInLine { values, .. } | Tuple { values, .. } => {
+                values.iter_mut().for_each(|v| self.replace_names(v, args, constants, file_id))
+            }
+            UniformArray { value, dimension, .. } => {
+                self.replace_names(value, args, constants, file_id);
+                self.replace_names(dimension, args, constants, file_id);
+            }
+        }
+    }
+
+    // Replaces the members of enums read by the body by their values, unless
+    // the body declares variables with the names of the enums
+    fn replace_members_in_body(&mut self, body: &mut Statement, args: &[String], file_id: FileID) {
+        if self.enums.is_empty() {
+            return;
+        }
+        let mut names = Names::default();
+        collect_statement(body, &mut names);
+        let mut excluded = args.to_vec();
+        excluded.extend(names.declared);
+        self.replace_members(body, &excluded, file_id);
+    }
+
+    fn replace_members(&mut self, statement: &mut Statement, excluded: &[String], file_id: FileID) {
+        use Statement::*;
+        let replace = |e: &mut Expression, this: &mut Self| this.replace_names(e, excluded, false, file_id);
+        match statement {
+            IfThenElse { cond, if_case, else_case, .. } => {
+                replace(cond, self);
+                self.replace_members(if_case, excluded, file_id);
+                if let Some(else_case) = else_case {
+                    self.replace_members(else_case, excluded, file_id);
+                }
+            }
+            While { cond, stmt, .. } => {
+                replace(cond, self);
+                self.replace_members(stmt, excluded, file_id);
+            }
+            Return { value, .. } => replace(value, self),
+            InitializationBlock { initializations, .. } => {
+                initializations.iter_mut().for_each(|s| self.replace_members(s, excluded, file_id))
+            }
+            Declaration { dimensions, .. } => dimensions.iter_mut().for_each(|d| replace(d, self)),
+            Substitution { access, rhe, .. } => {
+                for acc in access {
+                    if let Access::ArrayAccess(index) = acc {
+                        replace(index, self);
+                    }
+                }
+                replace(rhe, self);
+            }
+            MultSubstitution { lhe, rhe, .. } | ConstraintEquality { lhe, rhe, .. } => {
+                replace(lhe, self);
+                replace(rhe, self);
+            }
+            UnderscoreSubstitution { rhe, .. } => replace(rhe, self),
+            LogCall { args, .. } => {
+                for arg in args {
+                    if let LogArgument::LogExp(e) = arg {
+                        replace(e, self);
+                    }
+                }
+            }
+            Block { stmts, .. } => stmts.iter_mut().for_each(|s| self.replace_members(s, excluded, file_id)),
+            Assert { arg, message, .. } => {
+                replace(arg, self);
+                for m in message {
+                    if let LogArgument::LogExp(e) = m {
+                        replace(e, self);
+                    }
+                }
+            }
+        }
+    }
+
+    // Buses only declare signals, so the constants are replaced by their values
+    fn replace_in_bus(&mut self, body: &mut Statement, args: &[String], file_id: FileID) {
+        match body {
+            Statement::Block { stmts, .. } => stmts.iter_mut().for_each(|s| self.replace_in_bus(s, args, file_id)),
+            Statement::InitializationBlock { initializations, .. } => {
+                initializations.iter_mut().for_each(|s| self.replace_in_bus(s, args, file_id))
+            }
+            Statement::Declaration { dimensions, .. } => self.replace_in_expressions(dimensions, args, file_id),
+            Statement::Substitution { rhe, .. } | Statement::UnderscoreSubstitution { rhe, .. } => {
+                self.replace_in_expression(rhe, args, file_id)
+            }
+            _ => {}
+        }
+    }
+}
+
+// Removes the constants declared at the scope of the files, and makes them
+// available to the definitions of every file and to the main component
+pub fn apply_file_constants(
+    definitions: &mut [(FileID, Vec<Definition>)],
+    main_id: FileID,
+    main_component: &mut Expression,
+) -> Result<(), ReportCollection> {
+    let mut constants =
+        Constants { constants: HashMap::new(), enums: HashMap::new(), uses: HashMap::new(), reports: Vec::new() };
+    for (file_id, file_definitions) in definitions.iter_mut() {
+        for definition in std::mem::take(file_definitions) {
+            match definition {
+                Definition::Constant { meta, name, dimensions, value } => {
+                    match constants.constants.entry(name) {
+                        Entry::Occupied(entry) => {
+                            let message = format!("The constant {} is declared more than once", entry.key());
+                            constants.reports.push(constant_error(message, &meta, *file_id));
+                        }
+                        Entry::Vacant(entry) => {
+                            entry.insert(Constant { file_id: *file_id, meta, dimensions, value });
+                        }
+                    }
+                }
+                Definition::Enum { meta, name, members } => {
+                    if let Some((member, _)) = members.iter().enumerate().find_map(|(i, (m, _))| {
+                        members[..i].iter().find(|(other, _)| other == m)
+                    }) {
+                        let message = format!("The member {} of the enum {} is declared more than once", member, name);
+                        constants.reports.push(constant_error(message, &meta, *file_id));
+                    }
+                    match constants.enums.entry(name) {
+                        Entry::Occupied(entry) => {
+                            let message = format!("The enum {} is declared more than once", entry.key());
+                            constants.reports.push(constant_error(message, &meta, *file_id));
+                        }
+                        Entry::Vacant(entry) => {
+                            entry.insert(Enum { members });
+                        }
+                    }
+                }
+                other => file_definitions.push(other),
+            }
+        }
+    }
+    for (name, constant) in &constants.constants {
+        if constants.enums.contains_key(name) {
+            let message = format!("The constant {} has the name of an enum", name);
+            constants.reports.push(constant_error(message, &constant.meta, constant.file_id));
+        }
+    }
+    if !constants.reports.is_empty() {
+        return Err(constants.reports);
+    }
+    if constants.constants.is_empty() && constants.enums.is_empty() {
+        return Ok(());
+    }
+    let uses = constants.constants.iter().map(|(name, c)| (name.clone(), constants_used(c, &constants.constants))).collect();
+    constants.uses = uses;
+    let mut names: Vec<_> = constants.constants.keys().cloned().collect();
+    names.sort();
+    let mut ordered = Vec::new();
+    for name in &names {
+        if let Err(name) = constants.add_in_order(name, &mut Vec::new(), &mut ordered) {
+            let constant = &constants.constants[&name];
+            let message = format!("The value of the constant {} depends on itself", name);
+            constants.reports.push(constant_error(message, &constant.meta, constant.file_id));
+            return Err(constants.reports);
+        }
+    }
+
+    for (file_id, file_definitions) in definitions.iter_mut() {
+        for definition in file_definitions {
+            match definition {
+                Definition::Template { body, args, defaults, .. } => {
+                    constants.replace_in_expressions(defaults, args, *file_id);
+                    constants.declare_in_body(body, args, *file_id);
+                    constants.replace_members_in_body(body, args, *file_id);
+                }
+                Definition::Function { body, args, .. } => {
+                    constants.declare_in_body(body, args, *file_id);
+                    constants.replace_members_in_body(body, args, *file_id);
+                }
+                Definition::Bus { body, args, .. } => constants.replace_in_bus(body, args, *file_id),
+                Definition::Tag { .. } | Definition::Operator { .. } => {}
+                Definition::Constant { .. } | Definition::Enum { .. } => unreachable!(),
+            }
+        }
+    }
+    constants.replace_in_expression(main_component, &[], main_id);
+    if constants.reports.is_empty() {
+        Ok(())
+    } else {
+        Err(constants.reports)
+    }
+}
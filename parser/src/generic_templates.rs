@@ -0,0 +1,278 @@
+use program_structure::ast::*;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::file_definition::FileID;
+use std::collections::{HashMap, HashSet};
+
+// The name of the instance of a generic template for a list of buses, as in
+// Hash<Point,Point>
+pub fn generic_call_name((name, buses): (String, Vec<String>)) -> String {
+    format!("{}<{}>", name, buses.join(","))
+}
+
+pub fn split_call_name(id: &str) -> Option<(&str, Vec<&str>)> {
+    let (name, buses) = id.strip_suffix('>')?.split_once('<')?;
+    Some((name, buses.split(',').collect()))
+}
+
+pub enum Name {
+    Bus,
+    Template,
+}
+
+pub type Visitor<'a> = dyn FnMut(Name, &mut String, &Meta) + 'a;
+
+fn visit_type(xtype: &mut VariableType, meta: &Meta, f: &mut Visitor) {
+    if let VariableType::Bus(id, ..) = xtype {
+        f(Name::Bus, id, meta);
+    }
+}
+
+fn visit_access(access: &mut [Access], f: &mut Visitor) {
+    for acc in access {
+        if let Access::ArrayAccess(index) = acc {
+            visit_expression(index, f);
+        }
+    }
+}
+
+fn visit_expressions(expressions: &mut [Expression], f: &mut Visitor) {
+    for e in expressions {
+        visit_expression(e, f);
+    }
+}
+
+pub fn visit_expression(expression: &mut Expression, f: &mut Visitor) {
+    use Expression::*;
+    match expression {
+        InfixOp { lhe, rhe, .. } => {
+            visit_expression(lhe, f);
+            visit_expression(rhe, f);
+        }
+        PrefixOp { rhe, .. } | ParallelOp { rhe, .. } => visit_expression(rhe, f),
+        InlineSwitchOp { cond, if_true, if_false, .. } => {
+            visit_expression(cond, f);
+            visit_expression(if_true, f);
+            visit_expression(if_false, f);
+        }
+        Variable { access, .. } => visit_access(access, f),
+        Number(..) => {}
+        Call { meta, id, args } => {
+            f(Name::Template, id, meta);
+            visit_expressions(args, f);
+        }
+        BusCall { meta, id, args } => {
+            f(Name::Bus, id, meta);
+            visit_expressions(args, f);
+        }
+        AnonymousComp { meta, id, params, signals, .. } => {
+            f(Name::Template, id, meta);
+            visit_expressions(params, f);
+            visit_expressions(signals, f);
+        }
+        ArrayInLine { values, .. } | Tuple { values, .. } => visit_expressions(values, f),
+        UniformArray { value, dimension, .. } => {
+            visit_expression(value, f);
+            visit_expression(dimension, f);
+        }
+    }
+}
+
+pub fn visit_statement(statement: &mut Statement, f: &mut Visitor) {
+    use Statement::*;
+    match statement {
+        IfThenElse { cond, if_case, else_case, .. } => {
+            visit_expression(cond, f);
+            visit_statement(if_case, f);
+            if let Some(else_case) = else_case {
+                visit_statement(else_case, f);
+            }
+        }
+        While { cond, stmt, .. } => {
+            visit_expression(cond, f);
+            visit_statement(stmt, f);
+        }
+        Return { value, .. } => visit_expression(value, f),
+        InitializationBlock { meta, xtype, initializations } => {
+            visit_type(xtype, meta, f);
+            for initialization in initializations {
+                visit_statement(initialization, f);
+            }
+        }
+        Declaration { meta, xtype, dimensions, .. } => {
+            visit_type(xtype, meta, f);
+            visit_expressions(dimensions, f);
+        }
+        Substitution { access, rhe, .. } => {
+            visit_access(access, f);
+            visit_expression(rhe, f);
+        }
+        MultSubstitution { lhe, rhe, .. } | ConstraintEquality { lhe, rhe, .. } => {
+            visit_expression(lhe, f);
+            visit_expression(rhe, f);
+        }
+        UnderscoreSubstitution { rhe, .. } => visit_expression(rhe, f),
+        LogCall { args, .. } => {
+            for arg in args {
+                if let LogArgument::LogExp(e) = arg {
+                    visit_expression(e, f);
+                }
+            }
+        }
+        Block { stmts, .. } => {
+            for stmt in stmts {
+                visit_statement(stmt, f);
+            }
+        }
+        Assert { arg, message, .. } => {
+            visit_expression(arg, f);
+            for m in message {
+                if let LogArgument::LogExp(e) = m {
+                    visit_expression(e, f);
+                }
+            }
+        }
+    }
+}
+
+fn generic_error(message: String, meta: &Meta, file_id: FileID) -> Report {
+    let mut report = Report::error(message, ReportCode::GenericTemplateError);
+    report.add_primary(meta.location.clone(), file_id, "here".to_string());
+    report
+}
+
+// A call to an instance of a generic template, and where it was found
+struct PendingInstance {
+    name: String,
+    file_id: FileID,
+    meta: Meta,
+}
+
+struct Generics {
+    templates: HashMap<String, (FileID, Definition)>,
+    // the templates and functions that are not generic
+    others: HashSet<String>,
+    pending: Vec<PendingInstance>,
+    reports: ReportCollection,
+}
+
+impl Generics {
+    // The calls to generic templates found in the code, after replacing the bus
+    // parameters of the template that contains it
+    fn visit_name(&mut self, kind: Name, id: &mut String, meta: &Meta, file_id: FileID, buses: &HashMap<String, String>) {
+        if let Name::Bus = kind {
+            if let Some(bus) = buses.get(id.as_str()) {
+                *id = bus.clone();
+            }
+            return;
+        }
+        if let Some((name, params)) = split_call_name(id) {
+            let params = params.iter().map(|p| buses.get(*p).map_or(*p, |b| b.as_str()).to_string()).collect();
+            *id = generic_call_name((name.to_string(), params));
+            self.pending.push(PendingInstance { name: id.clone(), file_id, meta: meta.clone() });
+        } else if self.templates.contains_key(id.as_str()) && !self.others.contains(id.as_str()) {
+            let message = format!("The generic template {} is called without buses", id);
+            self.reports.push(generic_error(message, meta, file_id));
+        }
+    }
+
+    fn visit_body(&mut self, body: &mut Statement, file_id: FileID, buses: &HashMap<String, String>) {
+        visit_statement(body, &mut |kind, id, meta| self.visit_name(kind, id, meta, file_id, buses));
+    }
+
+    fn build_instance(&mut self, pending: PendingInstance) -> Option<(FileID, Definition)> {
+        let (name, buses) = split_call_name(&pending.name).unwrap();
+        let (file_id, generic) = match self.templates.get(name) {
+            Some(generic) => generic.clone(),
+            None => {
+                let message = format!("There is no generic template called {}", name);
+                self.reports.push(generic_error(message, &pending.meta, pending.file_id));
+                return None;
+            }
+        };
+        if let Definition::Template { meta, args, arg_location, mut body, parallel, is_custom_gate, bus_params, defaults, .. } = generic {
+            if bus_params.len() != buses.len() {
+                let message = format!(
+                    "The generic template {} expects {} buses but it is called with {}",
+                    name,
+                    bus_params.len(),
+                    buses.len()
+                );
+                self.reports.push(generic_error(message, &pending.meta, pending.file_id));
+                return None;
+            }
+            let buses = bus_params.into_iter().zip(buses.into_iter().map(String::from)).collect();
+            self.visit_body(&mut body, file_id, &buses);
+            let parameters = TemplateParameters { args, arg_location, bus_params: Vec::new(), defaults };
+            let instance = build_template(meta, pending.name, parameters, body, parallel, is_custom_gate);
+            Some((file_id, instance))
+        } else {
+            unreachable!()
+        }
+    }
+}
+
+// Replaces the generic templates by an instance for each list of buses they
+// are called with, starting from the calls of the main component and of the
+// templates that are not generic
+pub fn build_generic_instances(
+    definitions: &mut [(FileID, Vec<Definition>)],
+    main_id: FileID,
+    main_component: &mut Expression,
+) -> Result<(), ReportCollection> {
+    let mut generics = Generics {
+        templates: HashMap::new(),
+        others: HashSet::new(),
+        pending: Vec::new(),
+        reports: Vec::new(),
+    };
+    for (file_id, file_definitions) in definitions.iter_mut() {
+        for definition in std::mem::take(file_definitions) {
+            match &definition {
+                Definition::Template { name, meta, bus_params, .. } if !bus_params.is_empty() => {
+                    if generics.templates.contains_key(name) {
+                        let message = format!("The generic template {} is defined more than once", name);
+                        generics.reports.push(generic_error(message, meta, *file_id));
+                    } else {
+                        generics.templates.insert(name.clone(), (*file_id, definition));
+                    }
+                }
+                Definition::Template { name, .. } | Definition::Function { name, .. } => {
+                    generics.others.insert(name.clone());
+                    file_definitions.push(definition);
+                }
+                Definition::Bus { .. }
+                | Definition::Constant { .. }
+                | Definition::Enum { .. }
+                | Definition::Tag { .. }
+                | Definition::Operator { .. } => {
+                    file_definitions.push(definition)
+                }
+            }
+        }
+    }
+    let no_buses = HashMap::new();
+    for (file_id, file_definitions) in definitions.iter_mut() {
+        for definition in file_definitions {
+            if let Definition::Template { body, .. } = definition {
+                generics.visit_body(body, *file_id, &no_buses);
+            }
+        }
+    }
+    visit_expression(main_component, &mut |kind, id, meta| generics.visit_name(kind, id, meta, main_id, &no_buses));
+
+    let mut built = HashSet::new();
+    while let Some(pending) = generics.pending.pop() {
+        if built.insert(pending.name.clone()) {
+            if let Some((file_id, instance)) = generics.build_instance(pending) {
+                let position = definitions.iter().position(|(id, _)| *id == file_id).unwrap();
+                definitions[position].1.push(instance);
+            }
+        }
+    }
+    if generics.reports.is_empty() {
+        Ok(())
+    } else {
+        Err(generics.reports)
+    }
+}
@@ -0,0 +1,74 @@
+use crate::generic_templates::{generic_call_name, split_call_name, visit_expression, visit_statement, Name};
+use program_structure::ast::*;
+use program_structure::file_definition::FileID;
+use std::collections::{HashMap, HashSet};
+
+fn definition_name(definition: &mut Definition) -> Option<&mut String> {
+    match definition {
+        Definition::Template { name, .. } | Definition::Function { name, .. } | Definition::Bus { name, .. } => {
+            Some(name)
+        }
+        Definition::Constant { .. } | Definition::Enum { .. } | Definition::Tag { .. } | Definition::Operator { .. } => None,
+    }
+}
+
+// The name used for a definition of the file, which also qualifies
+// the buses of the calls to generic templates
+fn qualify(id: &str, local: &HashSet<String>, alias: &str) -> Option<String> {
+    let qualified = |name: &str| {
+        if local.contains(name) {
+            format!("{}.{}", alias, name)
+        } else {
+            name.to_string()
+        }
+    };
+    match split_call_name(id) {
+        Some((name, buses)) => Some(generic_call_name((qualified(name), buses.into_iter().map(qualified).collect()))),
+        None if local.contains(id) => Some(qualified(id)),
+        None => None,
+    }
+}
+
+// Renames the templates, functions and buses of the files included with an
+// alias to alias.name, together with the uses of them inside those files
+pub fn apply_include_aliases(definitions: &mut [(FileID, Vec<Definition>)], aliases: &HashMap<FileID, String>) {
+    for (file_id, file_definitions) in definitions.iter_mut() {
+        let alias = match aliases.get(file_id) {
+            Some(alias) => alias,
+            None => continue,
+        };
+        let local: HashSet<_> = file_definitions.iter_mut().filter_map(|d| definition_name(d).cloned()).collect();
+        for definition in file_definitions.iter_mut() {
+            if let Some(name) = definition_name(definition) {
+                *name = format!("{}.{}", alias, name);
+            }
+            let mut rename = |_: Name, id: &mut String, _: &Meta| {
+                if let Some(qualified) = qualify(id, &local, alias) {
+                    *id = qualified;
+                }
+            };
+            match definition {
+                Definition::Template { body, defaults, .. } => {
+                    defaults.iter_mut().for_each(|d| visit_expression(d, &mut rename));
+                    visit_statement(body, &mut rename);
+                }
+                Definition::Function { body, .. } | Definition::Bus { body, .. } => visit_statement(body, &mut rename),
+                Definition::Tag { rules, .. } => {
+                    for function in rules.iter_mut().filter_map(|r| r.function.as_mut()) {
+                        if let Some(qualified) = qualify(function, &local, alias) {
+                            *function = qualified;
+                        }
+                    }
+                }
+                Definition::Operator { left, right, template, .. } => {
+                    for name in [left, right, template] {
+                        if let Some(qualified) = qualify(name, &local, alias) {
+                            *name = qualified;
+                        }
+                    }
+                }
+                Definition::Constant { .. } | Definition::Enum { .. } => {}
+            }
+        }
+    }
+}
@@ -7,18 +7,32 @@ extern crate lalrpop_util;
 
 lalrpop_mod!(pub lang);
 
+mod builtin_calls;
+mod bus_sugar;
+mod extern_functions;
+mod file_constants;
+mod generic_templates;
+mod include_aliases;
 mod include_logic;
+mod parse_cache;
 mod parser_logic;
 mod syntax_sugar_remover;
+mod tag_enforcement;
+mod template_defaults;
 
 use include_logic::{FileStack, IncludesGraph};
+pub use parse_cache::ParseCache;
 use num_bigint::BigInt;
-use program_structure::ast::{produce_compiler_version_report, produce_report, produce_report_with_message, produce_version_warning_report, Expression};
+use program_structure::ast::{AST, MainComponent, Meta, produce_compiler_version_report, produce_report, produce_report_with_message, produce_version_warning_report, Expression};
 use program_structure::error_code::ReportCode;
 use program_structure::error_definition::ReportCollection;
 use program_structure::error_definition::Report;
-use program_structure::file_definition::{FileLibrary};
+use program_structure::expression_builders::build_array_in_line;
+use program_structure::constants::UsefulConstants;
+use program_structure::file_definition::{FileID, FileLibrary};
 use program_structure::program_archive::ProgramArchive;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
 use std::path::{PathBuf, Path};
 use syntax_sugar_remover::{apply_syntactic_sugar};
 
@@ -26,9 +40,20 @@ use std::str::FromStr;
 
 pub type Version = (usize, usize, usize);
 
+// the file, the name, the main component and whether the file allows custom templates
+type MainEntry = (FileID, Option<String>, MainComponent, bool);
+
 pub fn find_file(
     crr_file: PathBuf,
     ext_link_libraries: Vec<PathBuf>,
+) -> (bool, String, String, PathBuf, Vec<Report>) {
+    find_file_in(crr_file, ext_link_libraries, &HashMap::new())
+}
+
+fn find_file_in(
+    crr_file: PathBuf,
+    ext_link_libraries: Vec<PathBuf>,
+    sources: &HashMap<PathBuf, String>,
 ) -> (bool, String, String, PathBuf, Vec<Report>) {
     let mut found = false;
     let mut path = "".to_string();
@@ -42,7 +67,7 @@ pub fn find_file(
         p.push(aux);
         p.push(crr_file.clone());
         crr_str_file = p;
-        match open_file(crr_str_file.clone()) {
+        match open_file(crr_str_file.clone(), sources) {
             Ok((new_path, new_src)) => {
                 path = new_path;
                 src = new_src;
@@ -57,13 +82,61 @@ pub fn find_file(
     (found, path, src, crr_str_file, reports)
 }
 
+// The options of the parser that are the same for every file of the program
+pub struct ParserConfig<'a> {
+    pub version: &'a str,
+    pub link_libraries: Vec<PathBuf>,
+    pub prime: &'a str,
+    pub flag_prime: bool,
+    pub flag_no_init: bool,
+    pub flag_enforce_tags: bool,
+    pub features: &'a [String],
+}
+
 pub fn run_parser(
     file: String,
-    version: &str,
-    link_libraries: Vec<PathBuf>,
-    field: &BigInt,     
-    flag_no_init: bool
+    config: &ParserConfig,
+) -> Result<(ProgramArchive, ReportCollection), (FileLibrary, ReportCollection)> {
+    run_parser_with_sources(file, config, &HashMap::new())
+}
+
+// The parser of the program where the files in sources, by their canonical
+// path, are read from them instead of the disk, as the files being edited.
+// A file that is not on the disk, as the ones typed by the user, is given
+// by the path used to open it
+pub fn run_parser_with_sources(
+    file: String,
+    config: &ParserConfig,
+    sources: &HashMap<PathBuf, String>,
+) -> Result<(ProgramArchive, ReportCollection), (FileLibrary, ReportCollection)> {
+    run_parser_in(file, config, sources, &mut ParseCache::new())
+}
+
+// The parser of the program that only parses again the files whose code
+// changes since the previous runs with the same cache, with the files in
+// sources read from them as in run_parser_with_sources
+pub fn run_parser_with_cache(
+    file: String,
+    config: &ParserConfig,
+    sources: &HashMap<PathBuf, String>,
+    cache: &mut ParseCache,
 ) -> Result<(ProgramArchive, ReportCollection), (FileLibrary, ReportCollection)> {
+    run_parser_in(file, config, sources, cache)
+}
+
+fn run_parser_in(
+    file: String,
+    config: &ParserConfig,
+    sources: &HashMap<PathBuf, String>,
+    cache: &mut ParseCache,
+) -> Result<(ProgramArchive, ReportCollection), (FileLibrary, ReportCollection)> {
+    let version = config.version;
+    let link_libraries = &config.link_libraries;
+    let flag_prime = config.flag_prime;
+    let flag_no_init = config.flag_no_init;
+    let flag_enforce_tags = config.flag_enforce_tags;
+    let features = config.features;
+    cache.start_run();
     let mut file_library = FileLibrary::new();
     let mut definitions = Vec::new();
     let mut main_components = Vec::new();
@@ -73,30 +146,63 @@ pub fn run_parser(
     let mut link_libraries2 = link_libraries.clone();
     let mut ext_link_libraries = vec![Path::new("").to_path_buf()];
     ext_link_libraries.append(&mut link_libraries2);
+    // the alias of each included file, and the id of each file
+    let mut include_aliases = HashMap::new();
+    let mut file_ids = HashMap::new();
+    let mut prime = config.prime.to_string();
+    let mut field = UsefulConstants::new(&prime).get_p().clone();
+    let mut is_main_file = true;
     while let Some(crr_file) = FileStack::take_next(&mut file_stack) {
+        let crr_path = crr_file.clone();
         let (found, path, src, crr_str_file, reports) =
-            find_file(crr_file, ext_link_libraries.clone());
+            find_file_in(crr_file, ext_link_libraries.clone(), sources);
         if !found {
             return Result::Err((file_library.clone(), reports));
         }
         let file_id = file_library.add_file(path.clone(), src.clone());
-        let program =
-            parser_logic::parse_file(&src, file_id, field, flag_no_init).map_err(|e| (file_library.clone(), e))?;
-        if let Some(main) = program.main_component {
-            main_components.push((file_id, main, program.custom_gates));
+        file_ids.insert(crr_path, file_id);
+        let mut program =
+            cache.parse_file(&src, file_id, &field, flag_no_init, features).map_err(|e| (file_library.clone(), e))?;
+        if let Some(new_prime) = check_prime_pragma(&program.prime, &prime, is_main_file && !flag_prime, file_id)
+            .map_err(|e| (file_library.clone(), e))?
+        {
+            // the numbers of the main file are parsed again in the field of its pragma
+            prime = new_prime;
+            field = UsefulConstants::new(&prime).get_p().clone();
+            program =
+                cache.parse_file(&src, file_id, &field, flag_no_init, features).map_err(|e| (file_library.clone(), e))?;
+        }
+        is_main_file = false;
+        file_library.set_file_lint_levels(file_id, program.lint_levels.clone());
+        for (name, main) in program.main_components {
+            main_components.push((file_id, name, main, program.custom_gates));
         }
         includes_graph.add_node(crr_str_file, program.custom_gates, program.custom_gates_declared);
         let includes = program.includes;
         definitions.push((file_id, program.definitions));
         for include in includes {
             let path_include =
-                FileStack::add_include(&mut file_stack, include.clone(), &link_libraries.clone())
+                FileStack::add_include(&mut file_stack, include.path.clone(), &link_libraries.clone())
                     .map_err(|e| (file_library.clone(), vec![e]))?;
+            match include_aliases.entry(path_include.clone()) {
+                Entry::Occupied(entry) if *entry.get() != include.alias => {
+                    let report = Report::error(
+                        format!("The file {} is included with different aliases", include.path),
+                        ReportCode::IncludeAliasError,
+                    );
+                    return Err((file_library, vec![report]));
+                }
+                Entry::Occupied(_) => {}
+                Entry::Vacant(entry) => {
+                    entry.insert(include.alias);
+                }
+            }
             includes_graph.add_edge(path_include).map_err(|e| (file_library.clone(), vec![e]))?;
         }
         warnings.append(
             &mut check_number_version(
                 path.clone(),
+                file_id,
                 program.compiler_version,
                 parse_number_version(version),
             )
@@ -116,10 +222,13 @@ pub fn run_parser(
         let report = produce_report(ReportCode::NoMainFoundInProject,0..0, 0);
         warnings.push(report);
         Err((file_library, warnings))
-    } else if main_components.len() > 1 {
+    } else if !are_entry_components(&main_components) {
         let report = produce_report_with_main_components(main_components);
         warnings.push(report);
         Err((file_library, warnings))
+    } else if let Some(report) = produce_report_with_repeated_names(&main_components) {
+        warnings.push(report);
+        Err((file_library, warnings))
     } else {
         let mut errors: ReportCollection = includes_graph.get_problematic_paths().iter().map(|path|
             Report::error(
@@ -135,13 +244,75 @@ pub fn run_parser(
             warnings.append(& mut errors);
             Err((file_library, warnings))
         } else {
-            let (main_id, main_component, custom_gates) = main_components.pop().unwrap();
+            let (main_id, custom_gates) = (main_components[0].0, main_components[0].3);
+            // the calls of all the main components are transformed together,
+            // as the elements of an array
+            let mut names = Vec::new();
+            let mut publics = Vec::new();
+            let mut calls = Vec::new();
+            for (_, name, (public, call), _) in main_components {
+                names.push(name);
+                publics.push(public);
+                calls.push(call);
+            }
+            let mut main_component = (Vec::<String>::new(), build_array_in_line(calls[0].get_meta().clone(), calls));
+            let aliases = include_aliases
+                .into_iter()
+                .filter_map(|(path, alias)| Some((file_ids[&PathBuf::from(path)], alias?)))
+                .collect();
+            match builtin_calls::apply_builtin_calls(&mut definitions, main_id, &mut main_component.1) {
+                Ok((uses_templates, mut reports)) => {
+                    warnings.append(&mut reports);
+                    if uses_templates {
+                        // the templates of sign, abs, the powers and the multiplexers, as a file of the program
+                        let src = builtin_calls::builtin_templates(&field);
+                        let file_id = file_library.add_file("builtin_templates.circom".to_string(), src.clone());
+                        let program = cache.parse_file(&src, file_id, &field, flag_no_init, features)
+                            .map_err(|e| (file_library.clone(), e))?;
+                        definitions.push((file_id, program.definitions));
+                    }
+                }
+                Err(mut rep) => {
+                    warnings.append(&mut rep);
+                    return Err((file_library, warnings));
+                }
+            }
+            include_aliases::apply_include_aliases(&mut definitions, &aliases);
+            if let Err(mut rep) = file_constants::apply_file_constants(&mut definitions, main_id, &mut main_component.1) {
+                warnings.append(&mut rep);
+                return Err((file_library, warnings));
+            }
+            if let Err(mut rep) = generic_templates::build_generic_instances(&mut definitions, main_id, &mut main_component.1) {
+                warnings.append(&mut rep);
+                return Err((file_library, warnings));
+            }
+            if let Err(mut rep) = bus_sugar::apply_bus_sugar(&mut definitions) {
+                warnings.append(&mut rep);
+                return Err((file_library, warnings));
+            }
+            if let Err(mut rep) = template_defaults::apply_template_defaults(&mut definitions, main_id, &mut main_component.1) {
+                warnings.append(&mut rep);
+                return Err((file_library, warnings));
+            }
+            if let Err(mut rep) = extern_functions::apply_extern_functions(&mut definitions, main_id, &mut main_component.1) {
+                warnings.append(&mut rep);
+                return Err((file_library, warnings));
+            }
+            if flag_enforce_tags {
+                tag_enforcement::apply_tag_enforcement(&mut definitions, &field);
+            }
+            let calls = match main_component.1 {
+                Expression::ArrayInLine { values, .. } => values,
+                _ => unreachable!(),
+            };
+            let main_components = names.into_iter().zip(publics.into_iter().zip(calls)).collect();
             let result_program_archive = ProgramArchive::new(
                 file_library,
                 main_id,
-                main_component,
+                main_components,
                 definitions,
                 custom_gates,
+                prime,
             );
             match result_program_archive {
                 Err((lib, mut rep)) => {
@@ -163,24 +334,74 @@ pub fn run_parser(
     }
 }
 
-fn produce_report_with_main_components(main_components: Vec<(usize, (Vec<String>, Expression), bool)>) -> Report {
-    let mut j = 0;
+// The definitions of a file as they are written, before the transformations
+// of the program, with the field of the prime
+pub fn parse_definitions(
+    src: &str,
+    file_id: FileID,
+    prime: &str,
+    features: &[String],
+) -> Result<AST, ReportCollection> {
+    let field = UsefulConstants::new(&prime.to_string()).get_p().clone();
+    parser_logic::parse_file(src, file_id, &field, false, features)
+}
+
+// The file that an include of the given file refers to
+pub fn resolve_include(file: &Path, include: &str, link_libraries: &[PathBuf]) -> Option<PathBuf> {
+    let mut file_stack = FileStack::new(file.to_path_buf());
+    FileStack::take_next(&mut file_stack);
+    FileStack::add_include(&mut file_stack, include.to_string(), &link_libraries.to_vec()).ok().map(PathBuf::from)
+}
+
+// A program can have several main components when all of them are in the
+// same file and at most one of them has no name
+fn are_entry_components(main_components: &[MainEntry]) -> bool {
+    let main_id = main_components[0].0;
+    main_components.iter().all(|(file_id, ..)| *file_id == main_id)
+        && main_components.iter().filter(|(_, name, ..)| name.is_none()).count() <= 1
+}
+
+fn produce_report_with_repeated_names(main_components: &[MainEntry]) -> Option<Report> {
+    let mut seen = HashMap::new();
+    for (file_id, name, (_, call), _) in main_components {
+        let name = match name {
+            Some(name) => name,
+            None => continue,
+        };
+        if let Some(first) = seen.insert(name, call.get_meta().location.clone()) {
+            let mut report = Report::error(
+                format!("There are several main components named {}", name),
+                ReportCode::MultipleMain,
+            );
+            report.add_primary(call.get_meta().location.clone(), *file_id, "This is a main component".to_string());
+            report.add_secondary(first, *file_id, Option::Some("Here it is another main component".to_string()));
+            return Some(report);
+        }
+    }
+    None
+}
+
+fn produce_report_with_main_components(main_components: Vec<MainEntry>) -> Report {
     let mut r = produce_report(ReportCode::MultipleMain, 0..0, 0);
-    for (i,exp,_) in main_components{
+    for (j, (i,_,exp,_)) in main_components.into_iter().enumerate() {
         if j > 0 {
             r.add_secondary(exp.1.get_meta().location.clone(), i, Option::Some("Here it is another main component".to_string()));
         }
         else {
             r.add_primary(exp.1.get_meta().location.clone(), i, "This is a main component".to_string());
         }
-        j+=1;
     }
     r
 }
 
-fn open_file(path: PathBuf) -> Result<(String, String), Report> /* path, src */ {
+fn open_file(path: PathBuf, sources: &HashMap<PathBuf, String>) -> Result<(String, String), Report> /* path, src */ {
     use std::fs::read_to_string;
     let path_str = format!("{:?}", path);
+    // the files that are not on the disk are given by the path as it is
+    let source = sources.get(&path).or_else(|| std::fs::canonicalize(&path).ok().and_then(|path| sources.get(&path)));
+    if let Some(src) = source {
+        return Ok((path_str, src.clone()));
+    }
     read_to_string(path)
         .map(|contents| (path_str.clone(), contents))
         .map_err(|_| produce_report_with_message(ReportCode::FileOs, path_str.clone()))
@@ -195,8 +416,36 @@ fn parse_number_version(version: &str) -> Version {
     )
 }
 
+// The prime of the pragma prime of a file must be the one of the circuit, which
+// the pragma of the main file can choose when --prime is not given
+fn check_prime_pragma(
+    pragma: &Option<(Meta, BigInt)>,
+    prime: &str,
+    can_choose: bool,
+    file_id: FileID,
+) -> Result<Option<String>, ReportCollection> {
+    let (meta, p) = match pragma {
+        Some(pragma) => pragma,
+        None => return Ok(None),
+    };
+    let message = match UsefulConstants::prime_name(p) {
+        Some(name) if name == prime => return Ok(None),
+        Some(name) if can_choose => return Ok(Some(name.to_string())),
+        Some(name) => format!("The prime of the pragma is the one of {}, but the circuit uses the prime of {}", name, prime),
+        // a modulus that is not the one of a curve is named by its value
+        None if !UsefulConstants::is_prime(p) => format!("The modulus of the pragma, {}, is not a prime", p),
+        None if p.to_string() == prime => return Ok(None),
+        None if can_choose => return Ok(Some(p.to_string())),
+        None => format!("The prime of the pragma is {}, but the circuit uses the prime of {}", p, prime),
+    };
+    let mut report = Report::error(message, ReportCode::PrimePragmaError);
+    report.add_primary(meta.location.clone(), file_id, "This pragma".to_string());
+    Err(vec![report])
+}
+
 fn check_number_version(
     file_path: String,
+    file_id: FileID,
     version_file: Option<Version>,
     version_compiler: Version,
 ) -> Result<ReportCollection, Report> {
@@ -211,8 +460,10 @@ fn check_number_version(
             Err(produce_compiler_version_report(file_path, required_version, version_compiler))
         }
     } else {
-        let report =
+        let mut report =
             produce_version_warning_report(file_path, version_compiler);
+        let pragma = format!("pragma circom {}.{}.{};", version_compiler.0, version_compiler.1, version_compiler.2);
+        report.add_suggestion(0..0, file_id, String::new(), format!("{}\n", pragma), format!("add {} at the beginning of the file", pragma));
         Ok(vec![report])
     }
 }
@@ -263,4 +514,30 @@ fn check_custom_gates_version(
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::check_prime_pragma;
+    use num_bigint::BigInt;
+    use program_structure::ast::Meta;
+
+    fn pragma(p: u64) -> Option<(Meta, BigInt)> {
+        Some((Meta::new(0, 0), BigInt::from(p)))
+    }
+
+    #[test]
+    fn pragma_prime_chooses_a_prime_modulus() {
+        let goldilocks = 0xffffffff00000001;
+        assert_eq!(check_prime_pragma(&pragma(goldilocks), "bn128", true, 0).ok(), Some(Some("goldilocks".to_string())));
+        assert_eq!(check_prime_pragma(&pragma(0xfffffffb), "bn128", true, 0).ok(), Some(Some("4294967291".to_string())));
+        assert_eq!(check_prime_pragma(&pragma(0xfffffffb), "4294967291", false, 0).ok(), Some(None));
+    }
+
+    #[test]
+    fn pragma_prime_rejects_other_moduli() {
+        assert!(check_prime_pragma(&pragma(0xfffffffa), "bn128", true, 0).is_err());
+        assert!(check_prime_pragma(&pragma(4294967297), "bn128", true, 0).is_err());
+        assert!(check_prime_pragma(&pragma(1), "bn128", true, 0).is_err());
+        assert!(check_prime_pragma(&pragma(0xfffffffb), "bn128", false, 0).is_err());
+    }
+}
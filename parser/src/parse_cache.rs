@@ -0,0 +1,71 @@
+use super::parser_logic;
+use num_bigint::BigInt;
+use program_structure::ast::AST;
+use program_structure::error_definition::ReportCollection;
+use program_structure::file_definition::FileID;
+use std::collections::HashMap;
+
+// The code of a file and the options it was parsed with, which give its
+// tree again while the code does not change
+struct ParsedFile {
+    src: String,
+    field: BigInt,
+    flag_no_init: bool,
+    features: Vec<String>,
+    program: AST,
+}
+
+// The trees of the files parsed by the previous runs of the parser, by the
+// ids of the files, as in the compilations of --watch, where most of the
+// files are the same in each run. The metas of a tree have the id of its
+// file, so a file is only reused with the same id
+#[derive(Default)]
+pub struct ParseCache {
+    files: HashMap<FileID, ParsedFile>,
+    reused: usize,
+}
+
+impl ParseCache {
+    pub fn new() -> ParseCache {
+        ParseCache::default()
+    }
+
+    // The number of files that the last run of the parser did not parse again
+    pub fn reused_files(&self) -> usize {
+        self.reused
+    }
+
+    pub(crate) fn start_run(&mut self) {
+        self.reused = 0;
+    }
+
+    pub(crate) fn parse_file(
+        &mut self,
+        src: &str,
+        file_id: FileID,
+        field: &BigInt,
+        flag_no_init: bool,
+        features: &[String],
+    ) -> Result<AST, ReportCollection> {
+        if let Some(parsed) = self.files.get(&file_id) {
+            if parsed.src == src
+                && parsed.field == *field
+                && parsed.flag_no_init == flag_no_init
+                && parsed.features == features
+            {
+                self.reused += 1;
+                return Ok(parsed.program.clone());
+            }
+        }
+        let program = parser_logic::parse_file(src, file_id, field, flag_no_init, features)?;
+        let parsed = ParsedFile {
+            src: src.to_string(),
+            field: field.clone(),
+            flag_no_init,
+            features: features.to_vec(),
+            program: program.clone(),
+        };
+        self.files.insert(file_id, parsed);
+        Ok(program)
+    }
+}
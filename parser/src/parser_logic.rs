@@ -84,14 +84,21 @@ pub fn preprocess(expr: &str, file_id: FileID) -> Result<String, ReportCollectio
     }
 }
 
-pub fn parse_file(src: &str, file_id: FileID, field: &BigInt, flag_no_init: bool) -> Result<AST, ReportCollection> {
+pub fn parse_file(
+    src: &str,
+    file_id: FileID,
+    field: &BigInt,
+    flag_no_init: bool,
+    features: &[String],
+) -> Result<AST, ReportCollection> {
     use lalrpop_util::ParseError::*;
 
     let mut errors = Vec::new();
+    let mut range_checks = Vec::new();
     let preprocess = preprocess(src, file_id)?;
 
     let ast = lang::ParseAstParser::new()
-        .parse(file_id, &mut errors, field, flag_no_init, &preprocess)
+        .parse(file_id, &mut errors, field, flag_no_init, features, &mut range_checks, &preprocess)
         // TODO: is this always fatal?
         .map_err(|parse_error| match parse_error {
             InvalidToken { location } => 
@@ -116,15 +123,163 @@ pub fn parse_file(src: &str, file_id: FileID, field: &BigInt, flag_no_init: bool
         .map_err(|e| vec![e])?;
 
     if !errors.is_empty() {
+        for error in errors.iter_mut() {
+            suggest_semicolon(error, &preprocess, file_id);
+        }
         return Err(errors.into_iter().collect());
     }
 
     Ok(ast)
 }
 
+// The semicolon that is missing goes right after the code before the token
+// that was found instead, skipping the spaces and the comments
+fn suggest_semicolon(report: &mut Report, preprocess: &str, file_id: FileID) {
+    if !matches!(report.get_code(), ReportCode::MissingSemicolon) {
+        return;
+    }
+    let found = match report.get_primary().first() {
+        Some(label) => label.range.start,
+        None => return,
+    };
+    if let Some(code) = preprocess.get(..found) {
+        let end = code.trim_end().len();
+        report.add_suggestion(end..end, file_id, String::new(), ";".to_string(), "add a semicolon".to_string());
+    }
+}
+
 fn produce_generic_report(format: String, token: std::ops::Range<usize>, file_id: usize) -> Report {
     let mut report = Report::error(format, ReportCode::IllegalExpression);
     report.add_primary(token, file_id, "here".to_string());
     report
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::parse_file;
+    use num_bigint::BigInt;
+    use program_structure::ast::{Definition, Expression, LogArgument, Statement, AST};
+    use program_structure::error_definition::ReportCollection;
+
+    fn parse(src: &str) -> Result<AST, ReportCollection> {
+        let field = BigInt::parse_bytes(
+            b"21888242871839275222246405745257275088548364400416034343698204186575808495617",
+            10,
+        )
+        .unwrap();
+        parse_file(src, 0, &field, false, &[])
+    }
+
+    // The statements of the body of the first template of the program
+    fn template_body(src: &str) -> Vec<Statement> {
+        let ast = parse(src).unwrap_or_else(|_| panic!("the program does not parse"));
+        match ast.definitions.into_iter().next() {
+            Some(Definition::Template { body: Statement::Block { stmts, .. }, .. }) => stmts,
+            _ => panic!("the program has no template"),
+        }
+    }
+
+    // The strings and the number of expressions of the log of the template, and
+    // whether it is formatted
+    fn log_of(code: &str) -> (Vec<String>, usize, bool) {
+        let src = format!("template A() {{ var x = 1; {} }}", code);
+        let log = template_body(&src).into_iter().find(|stmt| matches!(stmt, Statement::LogCall { .. }));
+        match log {
+            Some(Statement::LogCall { args, is_formatted, .. }) => {
+                let strings = args.iter().filter_map(|arg| match arg {
+                    LogArgument::LogStr(s) => Some(s.clone()),
+                    LogArgument::LogExp(_) => None,
+                });
+                let expressions = args.iter().filter(|arg| matches!(arg, LogArgument::LogExp(_))).count();
+                (strings.collect(), expressions, is_formatted)
+            }
+            _ => panic!("the template has no log"),
+        }
+    }
+
+    #[test]
+    fn log_formats_the_values_into_the_braces() {
+        let (strings, expressions, is_formatted) = log_of(r#"log("x = {}, y = {}", x, x + 1);"#);
+        assert_eq!(strings, vec!["x = ", ", y = "]);
+        assert_eq!(expressions, 2);
+        assert!(is_formatted);
+    }
+
+    #[test]
+    fn log_prints_plain_strings_as_they_are() {
+        let (strings, expressions, is_formatted) = log_of(r#"log("{}");"#);
+        assert_eq!(strings, vec!["{}"]);
+        assert_eq!(expressions, 0);
+        assert!(!is_formatted);
+
+        let (strings, expressions, is_formatted) = log_of(r#"log("{} =", x, x);"#);
+        assert_eq!(strings, vec!["{} ="]);
+        assert_eq!(expressions, 2);
+        assert!(!is_formatted);
+    }
+
+    // The value of the number assigned to the variable of the template
+    fn number(literal: &str) -> Option<BigInt> {
+        let src = format!("template A() {{ var x = {}; }}", literal);
+        let ast = parse(&src).ok()?;
+        match ast.definitions.into_iter().next() {
+            Some(Definition::Template { body: Statement::Block { stmts, .. }, .. }) => {
+                stmts.into_iter().find_map(|stmt| match stmt {
+                    Statement::InitializationBlock { initializations, .. } => initializations.into_iter().find_map(|init| match init {
+                        Statement::Substitution { rhe: Expression::Number(_, value), .. } => Some(value),
+                        _ => None,
+                    }),
+                    _ => None,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn numbers_separate_their_digits_with_underscores() {
+        assert_eq!(number("1_000_000"), Some(BigInt::from(1_000_000)));
+        assert_eq!(number("0xdead_beef"), Some(BigInt::from(0xdead_beef_u64)));
+        assert_eq!(number("0b10_10"), Some(BigInt::from(0b1010)));
+        assert_eq!(number("7"), Some(BigInt::from(7)));
+    }
+
+    #[test]
+    fn underscores_are_only_between_digits() {
+        for literal in ["1_", "1__0", "0x_1", "0x1_", "0b_1", "0b1__0", "0x"] {
+            assert!(number(literal).is_none(), "{} is a number", literal);
+        }
+    }
+
+    #[test]
+    fn const_defines_constants_and_names_variables() {
+        let ast = parse("const N = 3; template A() { var const = N; }").unwrap_or_else(|_| panic!("the program does not parse"));
+        assert_eq!(ast.definitions.len(), 2);
+        assert!(parse("konst N = 3;").is_err());
+    }
+
+    #[test]
+    fn enum_defines_constants_and_names_variables() {
+        let src = "enum Color { RED, GREEN = 3 } tag empty {} template A() { var enum = Color.GREEN; }";
+        let ast = parse(src).unwrap_or_else(|_| panic!("the program does not parse"));
+        assert_eq!(ast.definitions.len(), 3);
+        assert!(parse("enums Color { RED }").is_err());
+    }
+
+    #[test]
+    fn hint_groups_statements_and_names_variables() {
+        let stmts = template_body("template A() { signal input a; signal b; hint { b <-- a; } b * b === a; }");
+        assert!(stmts.iter().any(|stmt| matches!(stmt, Statement::Block { .. })));
+        let stmts = template_body("template A() { var hint = 3; hint = hint + 1; }");
+        assert_eq!(stmts.len(), 2);
+        assert!(parse("template A() { signal a; hunt { a <-- 1; } }").is_err());
+    }
+
+    #[test]
+    fn extern_declares_functions_and_names_variables() {
+        let ast = parse("extern function f(a, b); template A() { var extern = f(1, 2); }").unwrap_or_else(|_| panic!("the program does not parse"));
+        assert_eq!(ast.definitions.len(), 2);
+        assert!(parse("external function f(a);").is_err());
+    }
+}
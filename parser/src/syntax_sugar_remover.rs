@@ -4,7 +4,7 @@ use program_structure::error_definition::Report;
 use program_structure::expression_builders::{build_call, build_tuple, build_parallel_op};
 use program_structure::file_definition::FileLibrary;
 use program_structure::program_archive::ProgramArchive;
-use program_structure::statement_builders::{build_declaration, build_log_call, build_initialization_block};
+use program_structure::statement_builders::{build_declaration, build_initialization_block};
 use program_structure::template_data::TemplateData;
 use std::collections::{HashMap, BTreeMap};
 use num_bigint::BigInt;
@@ -12,9 +12,10 @@ use num_bigint::BigInt;
 
 
 pub fn apply_syntactic_sugar(program_archive : &mut  ProgramArchive) -> Result<(), Report> {
-    if program_archive.get_main_expression().is_anonymous_comp() {
-        return Result::Err(anonymous_general_error(program_archive.get_main_expression().get_meta().clone(),"The main component cannot contain an anonymous call  ".to_string()));
-     
+    for (_, (_, main_expression)) in program_archive.get_main_components() {
+        if main_expression.is_anonymous_comp() {
+            return Result::Err(anonymous_general_error(main_expression.get_meta().clone(),"The main component cannot contain an anonymous call  ".to_string()));
+        }
     }
     let old_templates = program_archive.templates.clone();
 
@@ -90,7 +91,7 @@ fn check_anonymous_components_statement(
                 check_anonymous_components_statement(stmt)
             }
         }     
-        Statement::LogCall {meta, args } => {
+        Statement::LogCall {meta, args, .. } => {
             for arg in args {
                 if let program_structure::ast::LogArgument::LogExp( exp ) = arg {
                     if exp.contains_anonymous_comp() {
@@ -100,8 +101,9 @@ fn check_anonymous_components_statement(
             }
             Result::Ok(())
         }  
-        Statement::Assert { meta, arg}   => {
-            if arg.contains_anonymous_comp() {
+        Statement::Assert { meta, arg, message, .. }   => {
+            let in_message = message.iter().any(|m| matches!(m, LogArgument::LogExp(e) if e.contains_anonymous_comp()));
+            if arg.contains_anonymous_comp() || in_message {
                 Result::Err(anonymous_general_error(meta.clone(), "An anonymous component cannot be used inside an assert".to_string()))
             } else{
                 Result::Ok(())
@@ -636,8 +638,9 @@ fn check_tuples_statement(stm: &Statement)-> Result<(), Report>{
             }
             Result::Ok(())
         }  
-        Statement::Assert { meta, arg}   => { 
-            if arg.contains_tuple(){
+        Statement::Assert { meta, arg, message, .. }   => { 
+            let in_message = message.iter().any(|m| matches!(m, LogArgument::LogExp(e) if e.contains_tuple()));
+            if arg.contains_tuple() || in_message {
                 Result::Err(tuple_general_error(meta.clone(),"A tuple cannot be used in a return ".to_string()))       
             }
             else{ 
@@ -851,7 +854,7 @@ fn remove_tuples_from_statement(stm: Statement) -> Result<Statement, Report> {
             let b_while = Box::new(while_ok);
             Result::Ok(Statement::While { meta : meta, cond : cond, stmt : b_while})
         }     
-        Statement::LogCall {meta, args } => {
+        Statement::LogCall {meta, args, is_formatted } => {
             let mut newargs = Vec::new();
             for arg in args {
                 match arg {
@@ -864,7 +867,7 @@ fn remove_tuples_from_statement(stm: Statement) -> Result<Statement, Report> {
                     },
                 }
             }
-            Result::Ok(build_log_call(meta, newargs))
+            Result::Ok(Statement::LogCall { meta, args: newargs, is_formatted })
         }  
         Statement::InitializationBlock { meta, xtype, initializations } =>
         {
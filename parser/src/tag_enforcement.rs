@@ -0,0 +1,53 @@
+use num_bigint::BigInt;
+use program_structure::ast::*;
+use program_structure::ast_shortcuts::{build_range_check, RangeCheck, BINARY, MAXBIT};
+use program_structure::expression_builders::{build_number, build_variable};
+use program_structure::file_definition::FileID;
+
+// The range of the input, from its tags binary or maxbit
+fn tag_range_check(meta: &Meta, name: &str, dimensions: &[Expression], tags: &[String], field: &BigInt) -> Option<RangeCheck> {
+    let bits = if tags.iter().any(|tag| tag == BINARY) {
+        build_number(meta.clone(), BigInt::from(1), field)
+    } else if tags.iter().any(|tag| tag == MAXBIT) {
+        build_variable(meta.clone(), name.to_string(), vec![build_component_access(MAXBIT.to_string())])
+    } else {
+        return None;
+    };
+    Some(RangeCheck { meta: meta.clone(), name: name.to_string(), dimensions: dimensions.to_vec(), bits })
+}
+
+// The inputs declared in the blocks of the template, which are not inside
+// conditions or loops
+fn collect_tag_range_checks(statement: &Statement, field: &BigInt, checks: &mut Vec<RangeCheck>) {
+    use Statement::*;
+    match statement {
+        Declaration { meta, xtype: VariableType::Signal(SignalType::Input, tags), name, dimensions, .. } => {
+            checks.extend(tag_range_check(meta, name, dimensions, tags, field));
+        }
+        Block { stmts, .. } | InitializationBlock { initializations: stmts, .. } => {
+            for stmt in stmts {
+                collect_tag_range_checks(stmt, field, checks);
+            }
+        }
+        _ => {}
+    }
+}
+
+// With --enforce_tags, the tags binary and maxbit of the inputs of the
+// templates are checked by a bit decomposition at the end of the template,
+// as the signals declared with uint<n>, instead of being assumed
+pub fn apply_tag_enforcement(definitions: &mut [(FileID, Vec<Definition>)], field: &BigInt) {
+    for (_, file_definitions) in definitions.iter_mut() {
+        for definition in file_definitions {
+            if let Definition::Template { body, is_custom_gate: false, .. } = definition {
+                let mut checks = Vec::new();
+                collect_tag_range_checks(body, field, &mut checks);
+                if let Statement::Block { stmts, .. } = body {
+                    for check in checks {
+                        stmts.append(&mut build_range_check(check, field));
+                    }
+                }
+            }
+        }
+    }
+}
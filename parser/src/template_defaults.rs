@@ -0,0 +1,247 @@
+use program_structure::ast::*;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::file_definition::FileID;
+use std::collections::HashMap;
+
+// The parameters of a template whose last parameters have a default value
+struct Parameters {
+    names: Vec<String>,
+    defaults: Vec<Expression>,
+}
+
+fn default_error(message: String, meta: &Meta, file_id: FileID) -> Report {
+    let mut report = Report::error(message, ReportCode::DefaultParameterError);
+    report.add_primary(meta.location.clone(), file_id, "here".to_string());
+    report
+}
+
+pub type Visitor<'a> = dyn FnMut(&mut Expression) + 'a;
+
+fn visit_expressions(expressions: &mut [Expression], f: &mut Visitor) {
+    for e in expressions {
+        visit_expression(e, f);
+    }
+}
+
+// Calls the function on the expression after calling it on the expressions
+// it contains
+pub fn visit_expression(expression: &mut Expression, f: &mut Visitor) {
+    use Expression::*;
+    match expression {
+        InfixOp { lhe, rhe, .. } => {
+            visit_expression(lhe, f);
+            visit_expression(rhe, f);
+        }
+        PrefixOp { rhe, .. } | ParallelOp { rhe, .. } => visit_expression(rhe, f),
+        InlineSwitchOp { cond, if_true, if_false, .. } => {
+            visit_expression(cond, f);
+            visit_expression(if_true, f);
+            visit_expression(if_false, f);
+        }
+        Variable { access, .. } => {
+            for acc in access {
+                if let Access::ArrayAccess(index) = acc {
+                    visit_expression(index, f);
+                }
+            }
+        }
+        Number(..) => {}
+        Call { args, .. } | BusCall { args, .. } => visit_expressions(args, f),
+        AnonymousComp { params, signals, .. } => {
+            visit_expressions(params, f);
+            visit_expressions(signals, f);
+        }
+        ArrayInLine { values, .. } | Tuple { values, .. } => visit_expressions(values, f),
+        UniformArray { value, dimension, .. } => {
+            visit_expression(value, f);
+            visit_expression(dimension, f);
+        }
+    }
+    f(expression);
+}
+
+pub fn visit_statement(statement: &mut Statement, f: &mut Visitor) {
+    use Statement::*;
+    match statement {
+        IfThenElse { cond, if_case, else_case, .. } => {
+            visit_expression(cond, f);
+            visit_statement(if_case, f);
+            if let Some(else_case) = else_case {
+                visit_statement(else_case, f);
+            }
+        }
+        While { cond, stmt, .. } => {
+            visit_expression(cond, f);
+            visit_statement(stmt, f);
+        }
+        Return { value, .. } => visit_expression(value, f),
+        InitializationBlock { initializations, .. } => {
+            for initialization in initializations {
+                visit_statement(initialization, f);
+            }
+        }
+        Declaration { dimensions, .. } => visit_expressions(dimensions, f),
+        Substitution { access, rhe, .. } => {
+            for acc in access {
+                if let Access::ArrayAccess(index) = acc {
+                    visit_expression(index, f);
+                }
+            }
+            visit_expression(rhe, f);
+        }
+        MultSubstitution { lhe, rhe, .. } | ConstraintEquality { lhe, rhe, .. } => {
+            visit_expression(lhe, f);
+            visit_expression(rhe, f);
+        }
+        UnderscoreSubstitution { rhe, .. } => visit_expression(rhe, f),
+        LogCall { args, .. } => {
+            for arg in args {
+                if let LogArgument::LogExp(e) = arg {
+                    visit_expression(e, f);
+                }
+            }
+        }
+        Block { stmts, .. } => {
+            for stmt in stmts {
+                visit_statement(stmt, f);
+            }
+        }
+        Assert { arg, message, .. } => {
+            visit_expression(arg, f);
+            for m in message {
+                if let LogArgument::LogExp(e) = m {
+                    visit_expression(e, f);
+                }
+            }
+        }
+    }
+}
+
+// The parameters read by a default value, which must be before its parameter
+fn check_default(default: &Expression, position: usize, names: &[String]) -> Result<(), String> {
+    let mut unknown = None;
+    let mut default = default.clone();
+    visit_expression(&mut default, &mut |e| {
+        if let Expression::Variable { name, .. } = e {
+            if !names[..position].contains(name) && unknown.is_none() {
+                unknown = Some(name.clone());
+            }
+        }
+    });
+    match unknown {
+        Some(name) => Err(format!(
+            "The default value of the parameter {} uses {}, but it can only use numbers, file constants and the parameters before it",
+            names[position], name
+        )),
+        None => Ok(()),
+    }
+}
+
+// Replaces the parameters read by a default value by the arguments of the call,
+// and places it at the call
+fn instantiate(default: &mut Expression, names: &[String], args: &[Expression], meta: &Meta) -> Result<(), String> {
+    let mut error = None;
+    visit_expression(default, &mut |e| {
+        if let Expression::Variable { name, access, .. } = e {
+            if let Some(position) = names.iter().position(|n| n == name) {
+                let mut value = args[position].clone();
+                if !access.is_empty() {
+                    match &mut value {
+                        Expression::Variable { access: value_access, .. } => value_access.append(access),
+                        _ => {
+                            error = Some(format!("The parameter {} is indexed by a default value, so its argument must be a variable", name));
+                            return;
+                        }
+                    }
+                }
+                *e = value;
+                return;
+            }
+        }
+        e.get_mut_meta().location = meta.location.clone();
+    });
+    error.map_or(Ok(()), Err)
+}
+
+struct Defaults {
+    templates: HashMap<String, Parameters>,
+    reports: ReportCollection,
+}
+
+impl Defaults {
+    // Adds the default values of the parameters that are not given by a call
+    // to a template
+    fn complete_call(&mut self, expression: &mut Expression, file_id: FileID) {
+        let (meta, id, args) = match expression {
+            Expression::Call { meta, id, args } => (meta, id, args),
+            Expression::AnonymousComp { meta, id, params, .. } => (meta, id, params),
+            _ => return,
+        };
+        let parameters = match self.templates.get(id.as_str()) {
+            Some(parameters) => parameters,
+            None => return,
+        };
+        let required = parameters.names.len() - parameters.defaults.len();
+        if args.len() < required || args.len() >= parameters.names.len() {
+            return;
+        }
+        for position in args.len()..parameters.names.len() {
+            let mut default = parameters.defaults[position - required].clone();
+            if let Err(message) = instantiate(&mut default, &parameters.names[..position], args, meta) {
+                self.reports.push(default_error(message, meta, file_id));
+                return;
+            }
+            args.push(default);
+        }
+    }
+
+    fn complete_body(&mut self, body: &mut Statement, file_id: FileID) {
+        visit_statement(body, &mut |e| self.complete_call(e, file_id));
+    }
+}
+
+// Completes the calls to templates that do not give the parameters with a
+// default value
+pub fn apply_template_defaults(
+    definitions: &mut [(FileID, Vec<Definition>)],
+    main_id: FileID,
+    main_component: &mut Expression,
+) -> Result<(), ReportCollection> {
+    let mut defaults = Defaults { templates: HashMap::new(), reports: Vec::new() };
+    for (file_id, file_definitions) in definitions.iter() {
+        for definition in file_definitions {
+            if let Definition::Template { meta, name, args, defaults: values, .. } = definition {
+                if values.is_empty() {
+                    continue;
+                }
+                let first = args.len() - values.len();
+                for (position, default) in values.iter().enumerate() {
+                    if let Err(message) = check_default(default, first + position, args) {
+                        defaults.reports.push(default_error(message, meta, *file_id));
+                    }
+                }
+                defaults.templates.insert(name.clone(), Parameters { names: args.clone(), defaults: values.clone() });
+            }
+        }
+    }
+    if !defaults.reports.is_empty() {
+        return Err(defaults.reports);
+    }
+    if defaults.templates.is_empty() {
+        return Ok(());
+    }
+    for (file_id, file_definitions) in definitions.iter_mut() {
+        for definition in file_definitions {
+            if let Definition::Template { body, .. } | Definition::Function { body, .. } = definition {
+                defaults.complete_body(body, *file_id);
+            }
+        }
+    }
+    visit_expression(main_component, &mut |e| defaults.complete_call(e, main_id));
+    if defaults.reports.is_empty() {
+        Ok(())
+    } else {
+        Err(defaults.reports)
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{file_definition::{FileLocation, FileID}, error_definition::Report, error_code::{ReportCode}};
+use crate::lint::{is_lint, lint_names, LintLevel, LintLevels};
 use num_bigint::BigInt;
 use serde_derive::{Deserialize, Serialize};
 
@@ -6,6 +7,8 @@ use serde_derive::{Deserialize, Serialize};
 pub enum Pragma {
     Version(Meta, FileID, Version),
     CustomGates(Meta ,FileID),
+    Prime(Meta, FileID, BigInt),
+    Lints(Meta, FileID, LintLevel, Vec<String>),
     Unrecognized,
 }
 
@@ -14,13 +17,15 @@ pub trait FillMeta {
 }
 
 pub type MainComponent = (Vec<String>, Expression);
+// a main component with the name of its circuit, when it has one
+pub type NamedMainComponent = (Option<String>, MainComponent);
 pub fn build_main_component(public: Vec<String>, call: Expression) -> MainComponent {
     (public, call)
 }
 
 pub type Version = (usize, usize, usize);
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub struct Meta {
     pub elem_id: usize,
     pub start: usize,
@@ -28,6 +33,8 @@ pub struct Meta {
     pub location: FileLocation,
     pub file_id: Option<usize>,
     pub component_inference: Option<String>,
+    // whether the statement is an assignment of a hint block
+    pub hint: bool,
     type_knowledge: TypeKnowledge,
     memory_knowledge: MemoryKnowledge,
 }
@@ -40,6 +47,7 @@ impl Meta {
             location: start..end,
             file_id: Option::None,
             component_inference: None,
+            hint: false,
             type_knowledge: TypeKnowledge::default(),
             memory_knowledge: MemoryKnowledge::default(),
         }
@@ -87,21 +95,34 @@ pub struct AST {
     pub compiler_version: Option<Version>,
     pub custom_gates: bool,
     pub custom_gates_declared: bool,
-    pub includes: Vec<String>,
+    pub prime: Option<(Meta, BigInt)>,
+    pub lint_levels: LintLevels,
+    pub includes: Vec<Include>,
     pub definitions: Vec<Definition>,
-    pub main_component: Option<MainComponent>,
+    pub tests: Vec<TestBlock>,
+    pub main_components: Vec<NamedMainComponent>,
+}
+
+// The definitions of a file included with an alias are used as alias.name
+#[derive(Clone)]
+pub struct Include {
+    pub path: String,
+    pub alias: Option<String>,
 }
 
 impl AST {
     pub fn new(
         meta: Meta,
         pragmas: Vec<Pragma>,
-        includes: Vec<String>,
+        includes: Vec<Include>,
         definitions: Vec<Definition>,
-        main_component: Option<MainComponent>,
+        tests: Vec<TestBlock>,
+        main_components: Vec<NamedMainComponent>,
     ) -> (AST,Vec<Report>) {
         let mut custom_gates = None;
         let mut compiler_version = None;
+        let mut prime = None;
+        let mut lint_levels = LintLevels::new();
         let mut reports = Vec::new();
         for p in pragmas {
             match p {
@@ -116,6 +137,22 @@ impl AST {
                         ReportCode::MultiplePragma, location.start..location.end, file_id)),
                     None => custom_gates = Some(true),
                 },
+                Pragma::Prime(location, file_id, p) => match prime {
+                    Some(_) => reports.push(produce_report(
+                        ReportCode::MultiplePragma, location.start..location.end, file_id)),
+                    None => prime = Some((location, p)),
+                },
+                Pragma::Lints(meta, file_id, level, lints) => {
+                    for lint in lints {
+                        if !is_lint(&lint) {
+                            let mut report = Report::error(format!("Unknown lint {}", lint), ReportCode::UnknownLint);
+                            report.add_primary(meta.file_location(), file_id, "This pragma".to_string());
+                            report.add_note(format!("The lints are {}", lint_names().join(", ")));
+                            reports.push(report);
+                        }
+                        lint_levels.set(lint, level);
+                    }
+                },
                 Pragma::Unrecognized => {}, //This error is previously handled, and the
                                             //parsing continues to catch more parsing errors.
             }
@@ -130,13 +167,39 @@ impl AST {
             compiler_version,
             custom_gates: custom_gates.unwrap_or(false),
             custom_gates_declared,
+            prime,
+            lint_levels,
             includes,
             definitions,
-            main_component,
+            tests,
+            main_components,
         }, reports)
     }
 }
 
+// A test of a template, as
+// test "name" for Num2Bits(4) { inputs { in = 11; } expect { out = [1, 1, 0, 1]; } }
+// which is run by circom test with the values of the inputs, and checks the
+// values of the outputs and that the constraints hold. The compilation of
+// the program ignores it
+#[derive(Clone)]
+pub struct TestBlock {
+    pub meta: Meta,
+    pub name: String,
+    pub call: Expression,
+    pub inputs: Vec<(String, Expression)>,
+    pub expect: Vec<(String, Expression)>,
+    // the values that replace the ones of the witness, which the constraints
+    // must not accept
+    pub reject: Vec<(String, Expression)>,
+}
+
+// The definitions and the tests of a file, as they are parsed
+pub enum Item {
+    Definition(Box<Definition>),
+    Test(Box<TestBlock>),
+}
+
 #[derive(Clone)]
 pub enum Definition {
     Template {
@@ -147,6 +210,9 @@ pub enum Definition {
         body: Statement,
         parallel: bool,
         is_custom_gate: bool,
+        bus_params: Vec<String>,
+        // the default values of the last parameters
+        defaults: Vec<Expression>,
     },
     Function {
         meta: Meta,
@@ -154,6 +220,8 @@ pub enum Definition {
         args: Vec<String>,
         arg_location: FileLocation,
         body: Statement,
+        // the body is given by the host when the witness is computed
+        is_extern: bool,
     },
     Bus {
         meta: Meta,
@@ -162,17 +230,61 @@ pub enum Definition {
         arg_location: FileLocation,
         body: Statement,
     },
+    Constant {
+        meta: Meta,
+        name: String,
+        dimensions: Vec<Expression>,
+        value: Expression,
+    },
+    Enum {
+        meta: Meta,
+        name: String,
+        members: Vec<(String, BigInt)>,
+    },
+    Tag {
+        meta: Meta,
+        name: String,
+        rules: Vec<TagRule>,
+    },
+    // the template that computes the operation between two buses
+    Operator {
+        meta: Meta,
+        op: ExpressionInfixOpcode,
+        left: String,
+        right: String,
+        template: String,
+    },
 }
+
+// The tag is kept in the result of the operation when both operands have it,
+// with the value given by the function for the values of the operands, or
+// without a value if there is no function
+#[derive(Clone)]
+pub struct TagRule {
+    pub meta: Meta,
+    pub op: ExpressionInfixOpcode,
+    pub function: Option<String>,
+}
+
+// The parameters of a template, with the default values of the last ones and
+// the buses of a generic template
+pub struct TemplateParameters {
+    pub args: Vec<String>,
+    pub arg_location: FileLocation,
+    pub bus_params: Vec<String>,
+    pub defaults: Vec<Expression>,
+}
+
 pub fn build_template(
     meta: Meta,
     name: String,
-    args: Vec<String>,
-    arg_location: FileLocation,
+    parameters: TemplateParameters,
     body: Statement,
     parallel: bool,
     is_custom_gate: bool,
 ) -> Definition {
-    Definition::Template { meta, name, args, arg_location, body, parallel, is_custom_gate }
+    let TemplateParameters { args, arg_location, bus_params, defaults } = parameters;
+    Definition::Template { meta, name, args, arg_location, body, parallel, is_custom_gate, bus_params, defaults }
 }
 
 pub fn build_function(
@@ -182,7 +294,15 @@ pub fn build_function(
     arg_location: FileLocation,
     body: Statement,
 ) -> Definition {
-    Definition::Function { meta, name, args, arg_location, body }
+    Definition::Function { meta, name, args, arg_location, body, is_extern: false }
+}
+
+// The compiler only knows that an extern function returns a value, so its
+// body is return 0
+pub fn build_extern_function(meta: Meta, name: String, args: Vec<String>, arg_location: FileLocation) -> Definition {
+    let value = Expression::Number(meta.clone(), BigInt::from(0));
+    let body = Statement::Block { meta: meta.clone(), stmts: vec![Statement::Return { meta: meta.clone(), value }] };
+    Definition::Function { meta, name, args, arg_location, body, is_extern: true }
 }
 
 pub fn build_bus(
@@ -195,7 +315,32 @@ pub fn build_bus(
     Definition::Bus { meta, name, args, arg_location, body }
 }
 
-#[derive(Clone)]
+pub fn build_constant(meta: Meta, name: String, dimensions: Vec<Expression>, value: Expression) -> Definition {
+    Definition::Constant { meta, name, dimensions, value }
+}
+
+// The members without a value take the value of the previous member plus one,
+// starting from 0
+pub fn build_enum(meta: Meta, name: String, members: Vec<(String, Option<BigInt>)>) -> Definition {
+    let mut next = BigInt::from(0);
+    let mut values = Vec::new();
+    for (member, value) in members {
+        let value = value.unwrap_or(next);
+        next = &value + 1;
+        values.push((member, value));
+    }
+    Definition::Enum { meta, name, members: values }
+}
+
+pub fn build_tag(meta: Meta, name: String, rules: Vec<TagRule>) -> Definition {
+    Definition::Tag { meta, name, rules }
+}
+
+pub fn build_operator(meta: Meta, op: ExpressionInfixOpcode, left: String, right: String, template: String) -> Definition {
+    Definition::Operator { meta, op, left, right, template }
+}
+
+#[derive(Clone, Serialize)]
 pub enum Statement {
     IfThenElse {
         meta: Meta,
@@ -250,6 +395,9 @@ pub enum Statement {
     LogCall {
         meta: Meta,
         args: Vec<LogArgument>,
+        // the arguments come from a format string and are printed without
+        // spaces between them
+        is_formatted: bool,
     },
     Block {
         meta: Meta,
@@ -258,6 +406,9 @@ pub enum Statement {
     Assert {
         meta: Meta,
         arg: Expression,
+        message: Vec<LogArgument>,
+        // checked during the constraint generation, without code in the witness generation
+        is_static: bool,
     },
 }
 
@@ -271,7 +422,7 @@ pub enum SignalType {
 pub type TagList = Vec<String>;
 
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Serialize)]
 pub enum VariableType {
     Var,
     Signal(SignalType, TagList),
@@ -280,7 +431,7 @@ pub enum VariableType {
     Bus(String, SignalType, TagList),
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub enum Expression {
     InfixOp {
         meta: Meta,
@@ -342,7 +493,7 @@ pub enum Expression {
     },
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub enum Access {
     ComponentAccess(String),
     ArrayAccess(Expression),
@@ -354,14 +505,14 @@ pub fn build_array_access(expr: Expression) -> Access {
     Access::ArrayAccess(expr)
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Eq, PartialEq, Serialize)]
 pub enum AssignOp {
     AssignVar,
     AssignSignal,
     AssignConstraintSignal,
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Serialize)]
 pub enum ExpressionInfixOpcode {
     Mul,
     Div,
@@ -385,7 +536,7 @@ pub enum ExpressionInfixOpcode {
     BitXor,
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Serialize)]
 pub enum ExpressionPrefixOpcode {
     Sub,
     BoolNot,
@@ -394,7 +545,7 @@ pub enum ExpressionPrefixOpcode {
 
 // Knowledge buckets
 
-#[derive(Clone, PartialOrd, PartialEq, Ord, Eq)]
+#[derive(Clone, PartialOrd, PartialEq, Ord, Eq, Serialize)]
 pub enum TypeReduction {
     Variable,
     Component(Option<String>),
@@ -403,7 +554,7 @@ pub enum TypeReduction {
     Tag,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub enum LogArgument {
     LogStr(String),
     LogExp(Expression),
@@ -416,7 +567,7 @@ pub fn build_log_expression(expr: Expression) -> LogArgument {
 }
 
 
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Serialize)]
 pub struct TypeKnowledge {
     reduces_to: Option<TypeReduction>,
 }
@@ -465,7 +616,7 @@ impl TypeKnowledge {
     }
 }
 
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Serialize)]
 pub struct MemoryKnowledge {
     concrete_dimensions: Option<Vec<usize>>,
     full_length: Option<usize>,
@@ -568,6 +719,54 @@ impl MemoryKnowledge {
             report.add_primary(location, file_id, "This should be an identifier".to_string());
             report
             },
+            WrongNumberOfMessageValues => {
+                let mut report =
+                Report::error("The message must have a {} for each value".to_string(), ReportCode::WrongNumberOfMessageValues);
+            report.add_primary(location, file_id, "This message".to_string());
+            report
+            },
+            DefaultParameterError => {
+                let mut report =
+                Report::error("The parameters after a parameter with a default value must have a default value too".to_string(), ReportCode::DefaultParameterError);
+            report.add_primary(location, file_id, "This parameter".to_string());
+            report
+            },
+            UnrecognizedCfg => {
+                let mut report =
+                Report::error("unrecognized condition, expected feature = \"name\", not(..), all(..) or any(..)".to_string(), ReportCode::UnrecognizedCfg);
+            report.add_primary(location, file_id, "This condition".to_string());
+            report
+            },
+            UnrecognizedSignalType => {
+                let mut report =
+                Report::error("unrecognized type of signal, expected uint<n>".to_string(), ReportCode::UnrecognizedSignalType);
+            report.add_primary(location, file_id, "This type".to_string());
+            report
+            },
+            UnrecognizedDefinition => {
+                let mut report =
+                Report::error("unrecognized definition, expected const, enum, tag, operator or test".to_string(), ReportCode::UnrecognizedDefinition);
+            report.add_primary(location, file_id, "This definition".to_string());
+            report
+            },
+            UnrecognizedTestSection => {
+                let mut report =
+                Report::error("unrecognized section of a test, expected inputs, expect or reject".to_string(), ReportCode::UnrecognizedTestSection);
+            report.add_primary(location, file_id, "This section".to_string());
+            report
+            },
+            ExternFunctionError => {
+                let mut report =
+                Report::error("extern functions can only be called in the assignments of signals with <-- or -->".to_string(), ReportCode::ExternFunctionError);
+            report.add_primary(location, file_id, "This call".to_string());
+            report
+            },
+            IllegalHintStatement => {
+                let mut report =
+                Report::error("only the assignments of signals with <-- or --> are allowed inside a hint block".to_string(), ReportCode::IllegalHintStatement);
+            report.add_primary(location, file_id, "This statement".to_string());
+            report
+            },
             _ => unreachable!(),    
     };
     report
@@ -210,3 +210,124 @@ pub fn split_bus_declaration_into_single_nodes_and_multisubstitution(
     }
     build_initialization_block(meta, xtype, initializations)
 }
+
+// A signal declared as uint<n>, whose range is checked at the end of the
+// block where it is declared
+pub struct RangeCheck {
+    pub meta: Meta,
+    pub name: String,
+    pub dimensions: Vec<Expression>,
+    pub bits: Expression,
+}
+
+pub const MAXBIT: &str = "maxbit";
+pub const BINARY: &str = "binary";
+
+pub fn split_ranged_declaration_into_single_nodes(
+    meta: Meta,
+    xtype: VariableType,
+    symbols: Vec<(Symbol, Option<Expression>)>,
+    op: AssignOp,
+    checks: &mut Vec<RangeCheck>,
+) -> Statement {
+    let mut initializations = Vec::new();
+    for (symbol, bits) in symbols {
+        let mut has_type = xtype.clone();
+        let mut bound = None;
+        if let Option::Some(bits) = bits {
+            // the signals that are not inputs receive the bound as the tag maxbit
+            if let VariableType::Signal(signal_type, tags) = &mut has_type {
+                if *signal_type != SignalType::Input {
+                    if !tags.iter().any(|t| t == MAXBIT) {
+                        tags.push(MAXBIT.to_string());
+                    }
+                    bound = Option::Some(bits.clone());
+                }
+            }
+            checks.push(RangeCheck { meta: meta.clone(), name: symbol.name.clone(), dimensions: symbol.is_array.clone(), bits });
+        }
+        initializations.push(build_declaration(meta.clone(), has_type, symbol.name.clone(), symbol.is_array));
+        if let Option::Some(bits) = bound {
+            let access = vec![build_component_access(MAXBIT.to_string())];
+            initializations.push(build_substitution(meta.clone(), symbol.name.clone(), access, AssignOp::AssignVar, bits));
+        }
+        if let Option::Some(init) = symbol.init {
+            initializations.push(build_substitution(meta.clone(), symbol.name, vec![], op, init));
+        }
+    }
+    build_initialization_block(meta, xtype, initializations)
+}
+
+// The constraints that decompose each position of the signal in its bits,
+// which ensure that it is smaller than 2^n:
+//     x.bits[k] <-- (x >> k) & 1;
+//     x.bits[k] * (x.bits[k] - 1) === 0;
+//     x.bits[0] * 1 + ... + x.bits[n - 1] * 2^(n - 1) === x;
+pub fn build_range_check(check: RangeCheck, field: &BigInt) -> Vec<Statement> {
+    use ExpressionInfixOpcode::*;
+    let RangeCheck { meta, name: signal, dimensions: signal_dimensions, bits: bound } = check;
+    let name = |suffix: &str| format!("{}.{}", signal, suffix);
+    let number = |value: usize| build_number(meta.clone(), BigInt::from(value), field);
+    let var = |name: &str, access: Vec<Access>| build_variable(meta.clone(), name.to_string(), access);
+    let infix = |lhe, op, rhe| build_infix(meta.clone(), lhe, op, rhe);
+    let assign = |name: &str, access: Vec<Access>, op, rhe| build_substitution(meta.clone(), name.to_string(), access, op, rhe);
+    let declare = |xtype: VariableType, name: &str, dimensions| {
+        let declaration = build_declaration(meta.clone(), xtype.clone(), name.to_string(), dimensions);
+        build_initialization_block(meta.clone(), xtype, vec![declaration])
+    };
+    let (bits, sum, pow, k) = (name("bits"), name("sum"), name("pow"), name("k"));
+    let indexes: Vec<_> = (0..signal_dimensions.len()).map(|i| name(&format!("i{}", i))).collect();
+    let mut access: Vec<_> = indexes.iter().map(|i| Access::ArrayAccess(var(i, Vec::new()))).collect();
+    let element = var(&signal, access.clone());
+    access.push(Access::ArrayAccess(var(&k, Vec::new())));
+    let bit = var(&bits, access.clone());
+
+    let field_bits = field.bits();
+    let message = vec![LogArgument::LogStr(format!(
+        "The number of bits of {} must be smaller than {}, the number of bits of the prime",
+        signal, field_bits
+    ))];
+    let mut stmts = vec![build_static_assert(meta.clone(), infix(bound.clone(), Lesser, number(field_bits)), message)];
+    let mut dimensions = signal_dimensions.clone();
+    dimensions.push(bound.clone());
+    stmts.push(declare(VariableType::Signal(SignalType::Intermediate, Vec::new()), &bits, dimensions));
+    stmts.push(declare(VariableType::Var, &sum, Vec::new()));
+    stmts.push(declare(VariableType::Var, &pow, Vec::new()));
+
+    let bit_of = infix(infix(element.clone(), ShiftR, var(&k, Vec::new())), BitAnd, number(1));
+    let is_bit = infix(bit.clone(), Mul, infix(bit.clone(), Sub, number(1)));
+    let loop_body = build_block(
+        meta.clone(),
+        vec![
+            assign(&bits, access.clone(), AssignOp::AssignSignal, bit_of),
+            build_constraint_equality(meta.clone(), is_bit, number(0)),
+            assign(&sum, Vec::new(), AssignOp::AssignVar, infix(var(&sum, Vec::new()), Add, infix(bit, Mul, var(&pow, Vec::new())))),
+            assign(&pow, Vec::new(), AssignOp::AssignVar, infix(var(&pow, Vec::new()), Add, var(&pow, Vec::new()))),
+        ],
+    );
+    let for_loop = |index: &str, bound: Expression, body: Statement| {
+        let declaration = build_declaration(meta.clone(), VariableType::Var, index.to_string(), Vec::new());
+        let init = build_initialization_block(
+            meta.clone(),
+            VariableType::Var,
+            vec![declaration, assign(index, Vec::new(), AssignOp::AssignVar, number(0))],
+        );
+        let cond = infix(var(index, Vec::new()), Lesser, bound);
+        let step = plusplus(meta.clone(), (index.to_string(), Vec::new()), field);
+        for_into_while(meta.clone(), init, cond, step, body)
+    };
+    let mut check_element = build_block(
+        meta.clone(),
+        vec![
+            assign(&sum, Vec::new(), AssignOp::AssignVar, number(0)),
+            assign(&pow, Vec::new(), AssignOp::AssignVar, number(1)),
+            for_loop(&k, bound, loop_body),
+            build_constraint_equality(meta.clone(), var(&sum, Vec::new()), element),
+        ],
+    );
+    for (index, dimension) in indexes.iter().zip(signal_dimensions).rev() {
+        check_element = for_loop(index, dimension, check_element);
+    }
+    stmts.push(check_element);
+    stmts
+}
@@ -0,0 +1,222 @@
+use super::ast::*;
+use super::expression_builders::*;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use std::ops::RangeInclusive;
+
+// The functions over arrays given by the language. The parser renames their
+// calls to ids that no definition can have, as @map, and gives the function
+// of map and fold as a call without arguments in the first position
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinCall {
+    Map,
+    Fold,
+    Sum,
+    Concat,
+    Slice,
+    Reverse,
+    Sort,
+    Len,
+}
+
+impl BuiltinCall {
+    pub fn from_name(name: &str) -> Option<BuiltinCall> {
+        match name {
+            "map" => Some(BuiltinCall::Map),
+            "fold" => Some(BuiltinCall::Fold),
+            "sum" => Some(BuiltinCall::Sum),
+            "concat" => Some(BuiltinCall::Concat),
+            "slice" => Some(BuiltinCall::Slice),
+            "reverse" => Some(BuiltinCall::Reverse),
+            "sort" => Some(BuiltinCall::Sort),
+            "len" => Some(BuiltinCall::Len),
+            _ => None,
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<BuiltinCall> {
+        BuiltinCall::from_name(id.strip_prefix('@')?)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BuiltinCall::Map => "map",
+            BuiltinCall::Fold => "fold",
+            BuiltinCall::Sum => "sum",
+            BuiltinCall::Concat => "concat",
+            BuiltinCall::Slice => "slice",
+            BuiltinCall::Reverse => "reverse",
+            BuiltinCall::Sort => "sort",
+            BuiltinCall::Len => "len",
+        }
+    }
+
+    pub fn id(&self) -> String {
+        format!("@{}", self.name())
+    }
+
+    // The number of arguments of a call, counting the function
+    pub fn num_of_args(&self) -> usize {
+        match self {
+            BuiltinCall::Map | BuiltinCall::Concat => 2,
+            BuiltinCall::Fold | BuiltinCall::Slice => 3,
+            BuiltinCall::Sum | BuiltinCall::Reverse | BuiltinCall::Sort | BuiltinCall::Len => 1,
+        }
+    }
+
+    // The number of parameters of the function given to the call
+    pub fn num_of_params(&self) -> usize {
+        match self {
+            BuiltinCall::Map => 1,
+            BuiltinCall::Fold => 2,
+            _ => 0,
+        }
+    }
+
+    // The dimensions that the arrays of the call can have
+    pub fn dims(&self) -> RangeInclusive<usize> {
+        match self {
+            BuiltinCall::Sum | BuiltinCall::Sort => 1..=1,
+            _ => 1..=usize::MAX,
+        }
+    }
+
+    // Whether the call returns an array, which other calls can take
+    pub fn returns_array(&self) -> bool {
+        !matches!(self, BuiltinCall::Fold | BuiltinCall::Sum | BuiltinCall::Len)
+    }
+
+    pub fn function<'a>(&self, args: &'a [Expression]) -> Option<(&'a Meta, &'a str)> {
+        match args.first() {
+            Some(Expression::Call { meta, id, .. }) if self.num_of_params() > 0 => Some((meta, id)),
+            _ => None,
+        }
+    }
+
+    pub fn arrays<'a>(&self, args: &'a [Expression]) -> &'a [Expression] {
+        match self {
+            BuiltinCall::Map => &args[1..],
+            BuiltinCall::Fold => &args[2..],
+            BuiltinCall::Concat => args,
+            _ => &args[..1],
+        }
+    }
+
+    // The start and the end of a slice
+    pub fn bounds<'a>(&self, args: &'a [Expression]) -> &'a [Expression] {
+        match self {
+            BuiltinCall::Slice => &args[1..],
+            _ => &[],
+        }
+    }
+}
+
+// The arrays that a call can unroll without evaluating them
+pub fn is_unrollable_array(array: &Expression) -> bool {
+    match array {
+        Expression::Variable { .. } | Expression::ArrayInLine { .. } => true,
+        Expression::Call { id, .. } => BuiltinCall::from_id(id).is_some_and(|b| b.returns_array()),
+        _ => false,
+    }
+}
+
+// The expression that a call stands for once the length of its array is
+// known, so over an array of length 2 map(f, a) is [f(a[0]), f(a[1])],
+// fold(f, x, a) is f(f(x, a[0]), a[1]) and sum(a) is a[0] + a[1]. The length
+// of the arrays that are variables is given by length. The bounds of a slice
+// are numbers once the program is executed; before, the slice is unrolled as
+// the whole array, which has the same type
+pub fn unroll_builtin_call(
+    meta: &Meta,
+    id: &str,
+    args: &[Expression],
+    length: &mut dyn FnMut(&Expression) -> usize,
+) -> Expression {
+    let builtin = BuiltinCall::from_id(id).unwrap();
+    let mut elements = Vec::new();
+    for array in builtin.arrays(args) {
+        elements.append(&mut unroll_array(array, length));
+    }
+    let call = |arguments: Vec<Expression>| {
+        let (function_meta, function) = builtin.function(args).unwrap();
+        build_call(function_meta.clone(), function.to_string(), arguments)
+    };
+    match builtin {
+        BuiltinCall::Map => {
+            build_array_in_line(meta.clone(), elements.into_iter().map(|e| call(vec![e])).collect())
+        }
+        BuiltinCall::Fold => elements.into_iter().fold(args[1].clone(), |acc, e| call(vec![acc, e])),
+        BuiltinCall::Sum => elements
+            .into_iter()
+            .reduce(|acc, e| build_infix(meta.clone(), acc, ExpressionInfixOpcode::Add, e))
+            .unwrap_or_else(|| build_number_without_field(meta.clone(), BigInt::from(0))),
+        BuiltinCall::Concat => build_array_in_line(meta.clone(), elements),
+        BuiltinCall::Slice => {
+            let bounds: Option<Vec<_>> = builtin.bounds(args).iter().map(constant_bound).collect();
+            match bounds.as_deref() {
+                Some([start, end]) if start <= end && *end <= elements.len() => {
+                    build_array_in_line(meta.clone(), elements[*start..*end].to_vec())
+                }
+                _ => build_array_in_line(meta.clone(), elements),
+            }
+        }
+        BuiltinCall::Reverse => build_array_in_line(meta.clone(), elements.into_iter().rev().collect()),
+        BuiltinCall::Sort => build_array_in_line(meta.clone(), unroll_sort(meta, &elements)),
+        BuiltinCall::Len => build_number_without_field(meta.clone(), BigInt::from(elements.len())),
+    }
+}
+
+fn constant_bound(bound: &Expression) -> Option<usize> {
+    match bound {
+        Expression::Number(_, value) => value.to_usize(),
+        _ => None,
+    }
+}
+
+// The position of a[i] in the sorted array is the number of elements lesser
+// than it, together with the equal elements before it, so the element k of
+// the result is the sum of (rank(a[i]) == k) * a[i]
+fn unroll_sort(meta: &Meta, elements: &[Expression]) -> Vec<Expression> {
+    let number = |value: usize| build_number_without_field(meta.clone(), BigInt::from(value));
+    let infix = |lhe: Expression, op: ExpressionInfixOpcode, rhe: Expression| build_infix(meta.clone(), lhe, op, rhe);
+    let add = |terms: Vec<Expression>| {
+        terms.into_iter().reduce(|acc, e| infix(acc, ExpressionInfixOpcode::Add, e)).unwrap_or_else(|| number(0))
+    };
+    let ranks: Vec<_> = (0..elements.len())
+        .map(|i| {
+            let before = (0..elements.len()).filter(|j| *j != i).map(|j| {
+                let op = if j < i { ExpressionInfixOpcode::LesserEq } else { ExpressionInfixOpcode::Lesser };
+                infix(elements[j].clone(), op, elements[i].clone())
+            });
+            add(before.collect())
+        })
+        .collect();
+    (0..elements.len())
+        .map(|k| {
+            let terms = elements.iter().zip(&ranks).map(|(element, rank)| {
+                let is_k = infix(rank.clone(), ExpressionInfixOpcode::Eq, number(k));
+                infix(is_k, ExpressionInfixOpcode::Mul, element.clone())
+            });
+            add(terms.collect())
+        })
+        .collect()
+}
+
+fn unroll_array(array: &Expression, length: &mut dyn FnMut(&Expression) -> usize) -> Vec<Expression> {
+    match array {
+        Expression::ArrayInLine { values, .. } => values.clone(),
+        Expression::Call { meta, id, args } => match unroll_builtin_call(meta, id, args, length) {
+            Expression::ArrayInLine { values, .. } => values,
+            _ => unreachable!(),
+        },
+        Expression::Variable { meta, name, access } => (0..length(array))
+            .map(|position| {
+                let index = build_number_without_field(meta.clone(), BigInt::from(position));
+                let mut access = access.clone();
+                access.push(Access::ArrayAccess(index));
+                build_variable(meta.clone(), name.clone(), access)
+            })
+            .collect(),
+        _ => unreachable!(),
+    }
+}
@@ -235,6 +235,47 @@ impl Expression {
             ParallelOp { rhe, .. } => {rhe.contains_tuple()},
          }
     }
+
+    // The calls of functions in the expression, the outer ones first
+    pub fn collect_calls<'a>(&'a self, calls: &mut Vec<&'a Expression>) {
+        use Expression::*;
+        match &self {
+            InfixOp { lhe, rhe, .. } | UniformArray { value: lhe, dimension: rhe, .. } => {
+                lhe.collect_calls(calls);
+                rhe.collect_calls(calls);
+            }
+            PrefixOp { rhe, .. } | ParallelOp { rhe, .. } => rhe.collect_calls(calls),
+            InlineSwitchOp { cond, if_true, if_false, .. } => {
+                cond.collect_calls(calls);
+                if_true.collect_calls(calls);
+                if_false.collect_calls(calls);
+            }
+            Call { args, .. } => {
+                calls.push(self);
+                for arg in args {
+                    arg.collect_calls(calls);
+                }
+            }
+            BusCall { args, .. } | Tuple { values: args, .. } | ArrayInLine { values: args, .. } => {
+                for arg in args {
+                    arg.collect_calls(calls);
+                }
+            }
+            AnonymousComp { params, signals, .. } => {
+                for arg in params.iter().chain(signals) {
+                    arg.collect_calls(calls);
+                }
+            }
+            Variable { access, .. } => {
+                for ac in access {
+                    if let Access::ArrayAccess(exp) = ac {
+                        exp.collect_calls(calls);
+                    }
+                }
+            }
+            Number(_, _) => {}
+        }
+    }
 }
 
 impl FillMeta for Expression {
@@ -1,4 +1,5 @@
 use super::ast::*;
+use crate::file_definition::FileLocation;
 use Statement::*;
 
 pub fn build_conditional_block(
@@ -61,7 +62,47 @@ pub fn build_log_call(meta: Meta, args: Vec<LogArgument>) -> Statement {
             LogArgument::LogStr(str) => { new_args.append(&mut split_string(str));}
         }
     }
-    LogCall { meta, args: new_args }
+    LogCall { meta, args: new_args, is_formatted: false }
+}
+
+// A log whose first argument is a string with a {} for each of the rest of
+// the arguments, as log("x = {}", x). Otherwise the arguments are printed
+// as they are, separated by spaces
+pub fn is_log_format(args: &[LogArgument]) -> bool {
+    match args.first() {
+        Some(LogArgument::LogStr(format)) => args.len() > 1 && format.matches("{}").count() == args.len() - 1,
+        _ => false,
+    }
+}
+
+// The log of a format string, with each {} replaced by one of the rest of
+// the arguments
+pub fn build_log_format(meta: Meta, args: Vec<LogArgument>) -> Statement {
+    let mut args = args.into_iter();
+    let format = match args.next() {
+        Some(LogArgument::LogStr(format)) => format,
+        _ => unreachable!(),
+    };
+    let mut new_args = Vec::new();
+    for piece in format.split("{}") {
+        new_args.append(&mut split_string(piece.to_string()));
+        new_args.extend(args.next());
+    }
+    LogCall { meta, args: new_args, is_formatted: true }
+}
+
+// The block of a hint, whose statements can only be assignments of signals
+// with <--, or the location of the first statement that is not one of them
+pub fn build_hint_block(meta: Meta, stmts: Vec<Statement>) -> Result<Statement, FileLocation> {
+    let mut hints = Vec::new();
+    for mut stmt in stmts {
+        match &mut stmt {
+            Substitution { meta, op: AssignOp::AssignSignal, .. } => meta.hint = true,
+            _ => return Err(stmt.get_meta().location.clone()),
+        }
+        hints.push(stmt);
+    }
+    Ok(Block { meta, stmts: hints })
 }
 
 fn split_string(str: String) -> Vec<LogArgument> {
@@ -76,14 +117,47 @@ fn split_string(str: String) -> Vec<LogArgument> {
     v
 }
 
-pub fn build_assert(meta: Meta, arg: Expression) -> Statement {
-    Assert { meta, arg }
+pub fn build_assert(meta: Meta, arg: Expression, message: Vec<LogArgument>) -> Statement {
+    Assert { meta, arg, message, is_static: false }
+}
+
+pub fn build_static_assert(meta: Meta, arg: Expression, message: Vec<LogArgument>) -> Statement {
+    Assert { meta, arg, message, is_static: true }
+}
+
+// The message of a failed assertion, with each {} replaced by one of the
+// values. The spaces around the values are left to the printing of the message
+pub fn build_assert_message(message: &str, values: Vec<Expression>) -> Option<Vec<LogArgument>> {
+    let pieces: Vec<_> = message.split("{}").collect();
+    if pieces.len() != values.len() + 1 {
+        return None;
+    }
+    let mut arguments = Vec::new();
+    for (i, piece) in pieces.iter().enumerate() {
+        let start = if i > 0 { piece.trim_start() } else { piece };
+        let piece = if i < values.len() { start.trim_end() } else { start };
+        arguments.append(&mut split_string(piece.to_string()));
+        if i < values.len() {
+            arguments.push(LogArgument::LogExp(values[i].clone()));
+        }
+    }
+    Some(arguments)
 }
 
 pub fn build_mult_substitution(meta: Meta, lhe: Expression, op : AssignOp, rhe: Expression) -> Statement {
     MultSubstitution { meta: meta.clone(), lhe, op, rhe }
 }
 
+// The assignment of the fields of a bus to several signals, as {x, y} <== p,
+// which is a tuple assignment once the fields of the bus are known
+pub fn build_bus_destructuring(meta: Meta, fields: Vec<Expression>, op: AssignOp, bus: Expression) -> Statement {
+    let lhe = crate::expression_builders::build_tuple(meta.clone(), fields);
+    let rhe = crate::expression_builders::build_call(bus.get_meta().clone(), BUS_FIELDS_ID.to_string(), vec![bus]);
+    MultSubstitution { meta, lhe, op, rhe }
+}
+
+pub const BUS_FIELDS_ID: &str = "@fields";
+
 pub fn build_anonymous_component_statement(meta: Meta, arg: Expression) -> Statement {
     MultSubstitution { meta: meta.clone(), lhe: crate::expression_builders::build_tuple(meta, Vec::new()), op: AssignOp::AssignConstraintSignal, rhe: arg }
 }
\ No newline at end of file
@@ -126,6 +126,22 @@ impl Statement {
         }
     }
 
+    // The declaration of the name in the statement or in its blocks
+    pub fn find_declaration(&self, name: &str) -> Option<&Statement> {
+        use Statement::*;
+        match self {
+            Declaration { name: declared, .. } if declared == name => Some(self),
+            IfThenElse { if_case, else_case, .. } => if_case
+                .find_declaration(name)
+                .or_else(|| else_case.as_ref().and_then(|else_case| else_case.find_declaration(name))),
+            While { stmt, .. } => stmt.find_declaration(name),
+            InitializationBlock { initializations: stmts, .. } | Block { stmts, .. } => {
+                stmts.iter().find_map(|stmt| stmt.find_declaration(name))
+            }
+            _ => None,
+        }
+    }
+
     pub fn contains_anonymous_comp(&self) -> bool {
         use Statement::*;
         match self {
@@ -192,8 +208,9 @@ impl Statement {
                 }
                 false
             }
-            Assert {  arg, .. } => {
-                arg.contains_anonymous_comp() 
+            Assert {  arg, message, .. } => {
+                arg.contains_anonymous_comp()
+                    || message.iter().any(|m| matches!(m, LogArgument::LogExp(e) if e.contains_anonymous_comp()))
             }
             UnderscoreSubstitution { rhe, .. } => {
                 rhe.contains_anonymous_comp() 
@@ -232,7 +249,7 @@ impl FillMeta for Statement {
             }
             LogCall { meta, args, .. } => fill_log_call(meta, args, file_id, element_id),
             Block { meta, stmts, .. } => fill_block(meta, stmts, file_id, element_id),
-            Assert { meta, arg, .. } => fill_assert(meta, arg, file_id, element_id),
+            Assert { meta, arg, message, .. } => fill_assert(meta, arg, message, file_id, element_id),
             UnderscoreSubstitution { meta, rhe, .. } => {
                 fill_underscore_substitution(meta, rhe, file_id, element_id);
             },
@@ -355,9 +372,14 @@ fn fill_block(meta: &mut Meta, stmts: &mut [Statement], file_id: usize, element_
     }
 }
 
-fn fill_assert(meta: &mut Meta, arg: &mut Expression, file_id: usize, element_id: &mut usize) {
+fn fill_assert(meta: &mut Meta, arg: &mut Expression, message: &mut Vec<LogArgument>, file_id: usize, element_id: &mut usize) {
     meta.set_file_id(file_id);
     arg.fill(file_id, element_id);
+    for m in message {
+        if let LogArgument::LogExp(e) = m {
+            e.fill(file_id, element_id);
+        }
+    }
 }
 
 fn fill_underscore_substitution(meta: &mut Meta, rhe: &mut Expression, file_id: usize, element_id: &mut usize) {
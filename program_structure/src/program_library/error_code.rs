@@ -13,6 +13,19 @@ pub enum ReportCode {
     UnrecognizedVersion,
     UnrecognizedPragma,
     ExpectedIdentifier,
+    WrongNumberOfMessageValues,
+    DefaultParameterError,
+    IncludeAliasError,
+    UnrecognizedCfg,
+    UnrecognizedSignalType,
+    UnrecognizedDefinition,
+    BuiltinCallError,
+    IllegalHintStatement,
+    ExternFunctionError,
+    PrimePragmaError,
+    SignalIndexWarning,
+    UnknownLint,
+    UnrecognizedTestSection,
     IncludeNotFound,
     IllegalExpression,
     MultiplePragma,
@@ -56,6 +69,9 @@ pub enum ReportCode {
     NonExistentSymbol,
     MainComponentWithTags,
     IllegalMainExpression,
+    WrongTagRule,
+    WrongBuiltinCall,
+    CompileTimeFunctionCall,
     TemplateCallAsArgument,
     TemplateWrongNumberOfArguments,
     TemplateWithReturnStatement,
@@ -108,6 +124,20 @@ pub enum ReportCode {
     UnconstrainedIOSignal,
     UnusedInput,
     UnusedOutput,
+    UndeterminedSignal,
+    UnconstrainedReadOutput,
+    ContradictoryConstraint,
+    SatisfiedConstraint,
+    NondeterministicSignal,
+    UnimpliedWitnessValue,
+    ViolatedWitnessValue,
+    ComparisonOverflow,
+    WitnessOverflow,
+    UnconstrainedAssignment,
+    PossiblyZeroDivisor,
+    ConditionallyAssignedRead,
+    ParallelCandidate,
+    UselessParallel,
 
     ErrorWat2Wasm,
     CustomGateIntermediateSignalWarning,
@@ -124,6 +154,9 @@ pub enum ReportCode {
     InvalidSignalAccessInBus,
     MustBeSameBus,
     MustBeBus,
+    GenericTemplateError,
+    FileConstantError,
+    BusSugarError,
 }
 
 impl fmt::Display for ReportCode {
@@ -144,6 +177,19 @@ impl fmt::Display for ReportCode {
             MultiplePragma => "P1013",
             IncludeNotFound => "P1014",
             ExpectedIdentifier => "P1015",
+            WrongNumberOfMessageValues => "P1016",
+            DefaultParameterError => "P1017",
+            IncludeAliasError => "P1018",
+            UnrecognizedCfg => "P1019",
+            UnrecognizedSignalType => "P1020",
+            UnrecognizedDefinition => "P1021",
+            BuiltinCallError => "P1022",
+            IllegalHintStatement => "P1023",
+            ExternFunctionError => "P1024",
+            PrimePragmaError => "P1025",
+            SignalIndexWarning => "P1026",
+            UnknownLint => "P1027",
+            UnrecognizedTestSection => "P1028",
             UndefinedFunction => "T2001",
             UndefinedTemplate => "T2002",
             UninitializedSymbolInExpression => "T2003",
@@ -218,6 +264,9 @@ impl fmt::Display for ReportCode {
             WrongTypesInAssignOperationDims(..) => "T2061",
             NonValidTagAssignment => "T2062",
             IllegalMainExpression => "T2063",
+            WrongTagRule => "T2064",
+            WrongBuiltinCall => "T2065",
+            CompileTimeFunctionCall => "T2066",
             RuntimeError => "T3001",
             RuntimeWarning => "T3002",
             UnknownDimension => "T20460",
@@ -234,6 +283,20 @@ impl fmt::Display for ReportCode {
             UnconstrainedIOSignal => "CA02",
             UnusedInput => "CA03",
             UnusedOutput => "CA04",
+            UndeterminedSignal => "CA05",
+            UnconstrainedReadOutput => "CA06",
+            ContradictoryConstraint => "CA07",
+            SatisfiedConstraint => "CA08",
+            NondeterministicSignal => "CA09",
+            UnimpliedWitnessValue => "CA10",
+            ViolatedWitnessValue => "CA11",
+            ComparisonOverflow => "CA12",
+            WitnessOverflow => "CA13",
+            UnconstrainedAssignment => "CA14",
+            PossiblyZeroDivisor => "CA15",
+            ConditionallyAssignedRead => "CA16",
+            ParallelCandidate => "CA17",
+            UselessParallel => "CA18",
             ErrorWat2Wasm => "W01",
             CustomGateIntermediateSignalWarning => "CG01",
             CustomGateConstraintError => "CG02",
@@ -249,6 +312,9 @@ impl fmt::Display for ReportCode {
             InvalidSignalAccessInBus => "BU04",
             MustBeSameBus => "BU05",
             MustBeBus => "BU06",
+            GenericTemplateError => "BU07",
+            FileConstantError => "FC01",
+            BusSugarError => "BU08",
         };
         f.write_str(string_format)
     }
@@ -1,13 +1,33 @@
 use super::error_code::ReportCode;
+use super::error_explanation::has_explanation;
 use super::file_definition::{FileID, FileLibrary, FileLocation};
-use codespan_reporting::diagnostic::{Diagnostic, Label};
+use super::lint::{is_lint, lint_name, LintLevel};
+use codespan_reporting::diagnostic::{Diagnostic, Label, LabelStyle};
+use codespan_reporting::files::Files;
 use codespan_reporting::term;
+use json::JsonValue;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::sync::Mutex;
 
 pub type ReportCollection = Vec<Report>;
 pub type DiagnosticCode = String;
 type ReportLabel = Label<FileID>;
 type ReportNote = String;
 
+// The warnings that the lints turn into errors, which are shown with the
+// other warnings
+#[derive(Debug)]
+pub struct DeniedWarnings {
+    pub count: usize,
+}
+
+impl fmt::Display for DeniedWarnings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} warnings are denied by the lints", self.count)
+    }
+}
+
 #[derive(Copy, Clone)]
 pub enum MessageCategory {
     Error,
@@ -30,6 +50,56 @@ impl MessageCategory {
     }
 }
 
+// The reports are shown for people, as a JSON object in each line, or in a
+// SARIF log written when the compiler ends
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum MessageFormat {
+    Human,
+    Json,
+    Sarif,
+}
+
+// A change of the code that fixes a report: the text of the location, which
+// must still be the original one for the change to apply, is replaced
+#[derive(Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub file_id: FileID,
+    pub location: FileLocation,
+    pub original: String,
+    pub replacement: String,
+    pub message: String,
+}
+impl Suggestion {
+    pub fn applies(&self, file_library: &FileLibrary) -> bool {
+        file_library
+            .get_source(self.file_id)
+            .and_then(|source| source.get(self.location.clone()))
+            .is_some_and(|text| text == self.original)
+    }
+}
+
+// The tool, the rules and the results of the SARIF log, kept from the start
+// of the compilation until it ends
+struct SarifLog {
+    version: String,
+    // the codes of the reports, with the names of their lints
+    rules: Vec<(String, String)>,
+    results: Vec<JsonValue>,
+}
+static SARIF_LOG: Mutex<Option<SarifLog>> = Mutex::new(None);
+
+// The suggestions of the reports printed while the code is being fixed, by
+// the names of their files, with the code of the files they apply to
+pub type Fixes = BTreeMap<String, (String, Vec<Suggestion>)>;
+static FIXES: Mutex<Option<Fixes>> = Mutex::new(None);
+
+// The warnings printed while a circuit is audited, with the ones whose lint
+// is allowed, in the entries of the audit report
+static AUDIT_LOG: Mutex<Option<Vec<JsonValue>>> = Mutex::new(None);
+
+// The codes of the errors printed to the user that circom --explain explains
+static EXPLAINED_CODES: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
 #[derive(Clone)]
 pub struct Report {
     category: MessageCategory,
@@ -38,6 +108,7 @@ pub struct Report {
     primary: Vec<ReportLabel>,
     secondary: Vec<ReportLabel>,
     notes: Vec<ReportNote>,
+    suggestions: Vec<Suggestion>,
 }
 impl Report {
     fn new(category: MessageCategory, error_message: String, error_code: ReportCode) -> Report {
@@ -48,10 +119,68 @@ impl Report {
             primary: Vec::new(),
             secondary: Vec::new(),
             notes: Vec::new(),
+            suggestions: Vec::new(),
         }
     }
     pub fn print_reports(reports: &[Report], file_library: &FileLibrary) {
         use codespan_reporting::term::termcolor::{ColorChoice, StandardStream};
+        if let Some(audit) = AUDIT_LOG.lock().unwrap().as_mut() {
+            for report in reports.iter().filter(|report| report.is_warning()) {
+                let entry = report.to_audit_entry(file_library);
+                if !audit.contains(&entry) {
+                    audit.push(entry);
+                }
+            }
+        }
+        let mut reports = Report::apply_lint_levels(reports, file_library);
+        // the suggestions of the code before its transformations, as the
+        // names of the includes with aliases, do not apply to the files
+        for report in reports.iter_mut() {
+            report.suggestions.retain(|suggestion| suggestion.applies(file_library));
+        }
+        if let Some(fixes) = FIXES.lock().unwrap().as_mut() {
+            for suggestion in reports.iter().flat_map(|report| report.suggestions.iter()) {
+                let (Some(name), Some(source)) =
+                    (file_library.get_file_name(suggestion.file_id), file_library.get_source(suggestion.file_id))
+                else {
+                    continue;
+                };
+                let name = name.trim_matches('"').to_string();
+                let (_, suggestions) = fixes.entry(name).or_insert_with(|| (source.to_string(), Vec::new()));
+                if !suggestions.contains(suggestion) {
+                    suggestions.push(suggestion.clone());
+                }
+            }
+        }
+        match file_library.get_message_format() {
+            MessageFormat::Json => {
+                for report in reports.iter() {
+                    eprintln!("{}", report.to_json(file_library).dump());
+                }
+                return;
+            }
+            MessageFormat::Sarif => {
+                let mut log = SARIF_LOG.lock().unwrap();
+                if let Some(log) = log.as_mut() {
+                    for report in reports.iter() {
+                        let rule = (report.get_code().to_string(), lint_name(report.get_code()));
+                        if !log.rules.contains(&rule) {
+                            log.rules.push(rule);
+                        }
+                        log.results.push(report.to_sarif(file_library));
+                    }
+                }
+                return;
+            }
+            MessageFormat::Human => {}
+        }
+        let mut explained = EXPLAINED_CODES.lock().unwrap();
+        for report in reports.iter().filter(|report| report.is_error()) {
+            let code = report.get_code().to_string();
+            if has_explanation(&code) {
+                explained.insert(code);
+            }
+        }
         let writer = StandardStream::stderr(ColorChoice::Always);
         let config = term::Config::default();
         let mut diagnostics = Vec::new();
@@ -66,6 +195,38 @@ impl Report {
             }
         }
     }
+    // Prints the warnings, which fail when a lint denies any of them
+    pub fn print_warnings(reports: &[Report], file_library: &FileLibrary) -> Result<(), DeniedWarnings> {
+        Report::print_reports(reports, file_library);
+        let count = Report::apply_lint_levels(reports, file_library).iter().filter(|report| report.is_error()).count();
+        if count > 0 {
+            Result::Err(DeniedWarnings { count })
+        } else {
+            Result::Ok(())
+        }
+    }
+    // The allowed warnings are not shown, and the denied ones are errors
+    pub fn apply_lint_levels(reports: &[Report], file_library: &FileLibrary) -> Vec<Report> {
+        let mut shown = Vec::new();
+        for report in reports {
+            if !report.is_warning() {
+                shown.push(report.clone());
+                continue;
+            }
+            let file_id = report.get_primary().first().map(|label| label.file_id);
+            match file_library.get_lint_levels(file_id).level(report.get_code()) {
+                Some((LintLevel::Allow, _)) => {}
+                Some((LintLevel::Deny, name)) => {
+                    let mut report = report.clone();
+                    report.category = MessageCategory::Error;
+                    report.add_note(format!("The lint {} is denied by deny({})", lint_name(report.get_code()), name));
+                    shown.push(report);
+                }
+                _ => shown.push(report.clone()),
+            }
+        }
+        shown
+    }
     pub fn error_code_to_diagnostic_code(error_code: &ReportCode) -> DiagnosticCode {
         error_code.to_string()
     }
@@ -102,17 +263,226 @@ impl Report {
         self.get_mut_notes().push(note);
         self
     }
+    pub fn add_suggestion(
+        &mut self,
+        location: FileLocation,
+        file_id: FileID,
+        original: String,
+        replacement: String,
+        message: String,
+    ) -> &mut Self {
+        let suggestion = Suggestion { file_id, location, original, replacement, message };
+        self.suggestions.push(suggestion);
+        self
+    }
+
+    // The reports are collected in a SARIF log from here, instead of being
+    // printed, until the log is written
+    pub fn start_sarif_log(version: &str) {
+        let log = SarifLog { version: version.to_string(), rules: Vec::new(), results: Vec::new() };
+        *SARIF_LOG.lock().unwrap() = Some(log);
+    }
+
+    // The suggestions of the reports are kept from here, to fix the code with
+    // them, until they are taken
+    pub fn start_fixes() {
+        *FIXES.lock().unwrap() = Some(Fixes::new());
+    }
+
+    pub fn take_fixes() -> Fixes {
+        FIXES.lock().unwrap().take().unwrap_or_default()
+    }
+
+    // The warnings are kept from here, to be written in the audit report of
+    // the circuit, until they are taken
+    pub fn start_audit() {
+        *AUDIT_LOG.lock().unwrap() = Some(Vec::new());
+    }
+
+    pub fn take_audit() -> Vec<JsonValue> {
+        AUDIT_LOG.lock().unwrap().as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    // The codes of the errors printed since the last call that have an
+    // explanation
+    pub fn take_explained_codes() -> Vec<String> {
+        std::mem::take(&mut *EXPLAINED_CODES.lock().unwrap()).into_iter().collect()
+    }
+
+    // Prints the SARIF log of the reports collected since it was started, and
+    // starts a new one, if it was started
+    pub fn write_sarif_log() -> bool {
+        let mut log = SARIF_LOG.lock().unwrap();
+        let Some(sarif) = log.as_mut() else {
+            return false;
+        };
+        let rules: Vec<_> = sarif
+            .rules
+            .iter()
+            .map(|(code, name)| {
+                let mut json_rule = json::object! { "id" => code.clone() };
+                if name != code {
+                    json_rule["name"] = name.clone().into();
+                }
+                json_rule
+            })
+            .collect();
+        let driver = json::object! {
+            "name" => "circom",
+            "version" => sarif.version.clone(),
+            "informationUri" => "https://docs.circom.io",
+            "rules" => rules,
+        };
+        let run = json::object! {
+            "tool" => json::object! { "driver" => driver },
+            "results" => std::mem::take(&mut sarif.results),
+        };
+        let sarif_log = json::object! {
+            "version" => "2.1.0",
+            "$schema" => "https://json.schemastore.org/sarif-2.1.0.json",
+            "runs" => vec![run],
+        };
+        sarif.rules.clear();
+        eprintln!("{}", sarif_log.pretty(2));
+        true
+    }
 
     pub fn to_diagnostic(&self) -> Diagnostic<FileID> {
         let mut labels = self.get_primary().clone();
         let mut secondary = self.get_secondary().clone();
         labels.append(&mut secondary);
+        let mut notes = self.get_notes().clone();
+        for suggestion in self.get_suggestions() {
+            notes.push(format!("Suggestion: {}", suggestion.message));
+        }
 
         if self.is_warning() { Diagnostic::warning() } else { Diagnostic::error() }
             .with_message(self.get_message())
             .with_code(Report::error_code_to_diagnostic_code(self.get_code()))
             .with_labels(labels)
-            .with_notes(self.get_notes().clone())
+            .with_notes(notes)
+    }
+
+    pub fn to_json(&self, file_library: &FileLibrary) -> JsonValue {
+        let files = file_library.to_storage();
+        let mut labels = JsonValue::new_array();
+        for label in self.get_primary().iter().chain(self.get_secondary().iter()) {
+            let mut json_label = JsonValue::new_object();
+            json_label["file"] = files.name(label.file_id).map(|name| name.trim_matches('"').to_string()).into();
+            add_json_range(&mut json_label, file_library, label.file_id, &label.range);
+            json_label["message"] = label.message.clone().into();
+            json_label["primary"] = (label.style == LabelStyle::Primary).into();
+            labels.push(json_label).unwrap();
+        }
+        let mut suggestions = JsonValue::new_array();
+        for suggestion in self.get_suggestions() {
+            let mut json_suggestion = JsonValue::new_object();
+            json_suggestion["file"] = files.name(suggestion.file_id).map(|name| name.trim_matches('"').to_string()).into();
+            add_json_range(&mut json_suggestion, file_library, suggestion.file_id, &suggestion.location);
+            json_suggestion["replacement"] = suggestion.replacement.clone().into();
+            json_suggestion["message"] = suggestion.message.clone().into();
+            suggestions.push(json_suggestion).unwrap();
+        }
+        json::object! {
+            "level" => if self.is_warning() { "warning" } else { "error" },
+            "code" => self.get_code().to_string(),
+            "lint" => if self.is_warning() || is_lint(&self.get_code().to_string()) {
+                JsonValue::from(lint_name(self.get_code()))
+            } else {
+                JsonValue::Null
+            },
+            "message" => self.get_message().clone(),
+            "labels" => labels,
+            "notes" => self.get_notes().clone(),
+            "suggestions" => suggestions,
+        }
+    }
+
+    // The code, the message and the place of a warning, by its file and its
+    // line, so that the reports can be compared between versions of the code
+    fn to_audit_entry(&self, file_library: &FileLibrary) -> JsonValue {
+        let files = file_library.to_storage();
+        let label = self.get_primary().first();
+        let location = label.and_then(|label| {
+            let name = files.name(label.file_id)?;
+            let line = files.location(label.file_id, label.range.start)?.line_number;
+            Some(format!("{}:{}", name.trim_matches('"'), line))
+        });
+        let file_id = label.map(|label| label.file_id);
+        let allowed = matches!(file_library.get_lint_levels(file_id).level(self.get_code()), Some((LintLevel::Allow, _)));
+        json::object! {
+            "code" => self.get_code().to_string(),
+            "lint" => lint_name(self.get_code()),
+            "message" => self.get_message().clone(),
+            "location" => location,
+            "allowed" => allowed,
+        }
+    }
+
+    // A result of a SARIF log, with the primary labels as its locations, the
+    // secondary ones as its related locations and the suggestions as its fixes
+    pub fn to_sarif(&self, file_library: &FileLibrary) -> JsonValue {
+        let files = file_library.to_storage();
+        let artifact = |file_id: FileID| {
+            let uri = files.name(file_id).map(|name| name.trim_matches('"').to_string()).unwrap_or_default();
+            json::object! { "uri" => uri }
+        };
+        let region = |file_id: FileID, range: &FileLocation| {
+            let mut region = json::object! { "byteOffset" => range.start, "byteLength" => range.end - range.start };
+            if let (Some(start), Some(end)) = (files.location(file_id, range.start), files.location(file_id, range.end)) {
+                region["startLine"] = start.line_number.into();
+                region["startColumn"] = start.column_number.into();
+                region["endLine"] = end.line_number.into();
+                region["endColumn"] = end.column_number.into();
+            }
+            region
+        };
+        let location = |label: &ReportLabel| {
+            let mut location = json::object! {
+                "physicalLocation" => json::object! {
+                    "artifactLocation" => artifact(label.file_id),
+                    "region" => region(label.file_id, &label.range),
+                }
+            };
+            if !label.message.is_empty() {
+                location["message"] = json::object! { "text" => label.message.clone() };
+            }
+            location
+        };
+        let mut text = self.get_message().clone();
+        for note in self.get_notes() {
+            text.push('\n');
+            text.push_str(note);
+        }
+        let mut result = json::object! {
+            "ruleId" => self.get_code().to_string(),
+            "level" => if self.is_warning() { "warning" } else { "error" },
+            "message" => json::object! { "text" => text },
+            "locations" => self.get_primary().iter().map(location).collect::<Vec<_>>(),
+        };
+        if !self.get_secondary().is_empty() {
+            result["relatedLocations"] = self.get_secondary().iter().map(location).collect::<Vec<_>>().into();
+        }
+        if !self.get_suggestions().is_empty() {
+            let fixes: Vec<_> = self
+                .get_suggestions()
+                .iter()
+                .map(|suggestion| {
+                    json::object! {
+                        "description" => json::object! { "text" => suggestion.message.clone() },
+                        "artifactChanges" => vec![json::object! {
+                            "artifactLocation" => artifact(suggestion.file_id),
+                            "replacements" => vec![json::object! {
+                                "deletedRegion" => region(suggestion.file_id, &suggestion.location),
+                                "insertedContent" => json::object! { "text" => suggestion.replacement.clone() },
+                            }],
+                        }],
+                    }
+                })
+                .collect();
+            result["fixes"] = fixes.into();
+        }
+        result
     }
 
     pub fn is_error(&self) -> bool {
@@ -148,4 +518,45 @@ impl Report {
     fn get_mut_notes(&mut self) -> &mut Vec<ReportNote> {
         &mut self.notes
     }
+    pub fn get_suggestions(&self) -> &Vec<Suggestion> {
+        &self.suggestions
+    }
+}
+
+// The lines and columns of the start and the end of a range, starting at 1,
+// with their byte offsets
+fn add_json_range(json: &mut JsonValue, file_library: &FileLibrary, file_id: FileID, range: &FileLocation) {
+    let files = file_library.to_storage();
+    for (key, byte) in [("start", range.start), ("end", range.end)].iter() {
+        if let Some(location) = files.location(file_id, *byte) {
+            json[*key] = json::object! { "line" => location.line_number, "column" => location.column_number, "byte" => *byte };
+        }
+    }
+}
+
+// The name closest to the given one of the names that are defined, as the
+// suggestion of a name that is not defined, when it differs in at most a
+// third of its characters
+pub fn closest_name<'a>(name: &str, candidates: impl Iterator<Item = &'a String>) -> Option<&'a String> {
+    let max_distance = std::cmp::max(1, name.chars().count() / 3);
+    candidates
+        .filter(|candidate| !candidate.starts_with('$') && candidate.as_str() != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == *cb { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
 }
@@ -0,0 +1,17 @@
+// The explanations of the codes of the reports, with examples of the code
+// that produces them, printed by circom --explain
+const EXPLANATIONS: &str = include_str!("error_explanations.md");
+
+// The explanation of a code, as P1008 or t2021
+pub fn explanation(code: &str) -> Option<&'static str> {
+    let code = code.trim().to_uppercase();
+    let mut sections = EXPLANATIONS.split("\n## ").skip(1);
+    sections.find_map(|section| {
+        let (name, text) = section.split_once('\n')?;
+        (name.trim() == code).then(|| text.trim())
+    })
+}
+
+pub fn has_explanation(code: &str) -> bool {
+    explanation(code).is_some()
+}
@@ -1,4 +1,7 @@
+use super::error_definition::MessageFormat;
+use super::lint::LintLevels;
 use codespan_reporting::files::{Files, SimpleFiles};
+use std::collections::HashMap;
 use std::ops::Range;
 
 pub type FileSource = String;
@@ -10,11 +13,21 @@ type FileStorage = SimpleFiles<FilePath, FileSource>;
 #[derive(Clone)]
 pub struct FileLibrary {
     files: FileStorage,
+    // the levels of the lints given in the command line, and the ones of the
+    // pragmas of each file, which take precedence in the reports of the file
+    lint_levels: LintLevels,
+    file_lint_levels: HashMap<FileID, LintLevels>,
+    message_format: MessageFormat,
 }
 
 impl Default for FileLibrary {
     fn default() -> Self {
-        FileLibrary { files: FileStorage::new() }
+        FileLibrary {
+            files: FileStorage::new(),
+            lint_levels: LintLevels::new(),
+            file_lint_levels: HashMap::new(),
+            message_format: MessageFormat::Human,
+        }
     }
 }
 
@@ -31,6 +44,42 @@ impl FileLibrary {
             None => None,
         }
     }
+    pub fn get_line_location(&self, line: usize, file_id: FileID) -> Option<FileLocation> {
+        let range = self.files.line_range(file_id, line.checked_sub(1)?)?;
+        let text = &self.get_source(file_id)?[range.clone()];
+        if text.trim().is_empty() {
+            return None;
+        }
+        let start = range.start + text.len() - text.trim_start().len();
+        let end = range.start + text.trim_end().len();
+        Some(generate_file_location(start, end))
+    }
+    pub fn get_file_name(&self, file_id: FileID) -> Option<&str> {
+        self.files.get(file_id).map(|file| file.name().as_str())
+    }
+    pub fn get_source(&self, file_id: FileID) -> Option<&str> {
+        self.files.get(file_id).map(|file| file.source().as_str())
+    }
+    pub fn set_lint_levels(&mut self, lint_levels: LintLevels) {
+        self.lint_levels = lint_levels;
+    }
+    pub fn set_file_lint_levels(&mut self, file_id: FileID, lint_levels: LintLevels) {
+        self.file_lint_levels.insert(file_id, lint_levels);
+    }
+    // the levels of the lints in the reports of a file
+    pub fn get_lint_levels(&self, file_id: Option<FileID>) -> LintLevels {
+        let mut lint_levels = self.lint_levels.clone();
+        if let Some(file_levels) = file_id.and_then(|file_id| self.file_lint_levels.get(&file_id)) {
+            lint_levels.extend(file_levels);
+        }
+        lint_levels
+    }
+    pub fn set_message_format(&mut self, message_format: MessageFormat) {
+        self.message_format = message_format;
+    }
+    pub fn get_message_format(&self) -> MessageFormat {
+        self.message_format
+    }
     pub fn to_storage(&self) -> &FileStorage {
         &self.get_files()
     }
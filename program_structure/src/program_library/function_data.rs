@@ -13,6 +13,10 @@ pub struct FunctionData {
     name_of_params: Vec<String>,
     param_location: FileLocation,
     body: Statement,
+    is_extern: bool,
+    // the lengths of its arrays depend on its parameters, so it is only
+    // evaluated by the compiler
+    is_compile_time: bool,
 }
 
 impl FunctionData {
@@ -20,13 +24,14 @@ impl FunctionData {
         name: String,
         file_id: FileID,
         mut body: Statement,
-        num_of_params: usize,
         name_of_params: Vec<String>,
         param_location: FileLocation,
+        is_extern: bool,
         elem_id: &mut usize,
     ) -> FunctionData {
         body.fill(file_id, elem_id);
-        FunctionData { name, file_id, body, name_of_params, param_location, num_of_params }
+        let num_of_params = name_of_params.len();
+        FunctionData { name, file_id, body, name_of_params, param_location, num_of_params, is_extern, is_compile_time: false }
     }
     pub fn get_file_id(&self) -> FileID {
         self.file_id
@@ -67,4 +72,13 @@ impl FunctionData {
     pub fn get_name(&self) -> &str {
         &self.name
     }
+    pub fn is_extern(&self) -> bool {
+        self.is_extern
+    }
+    pub fn is_compile_time(&self) -> bool {
+        self.is_compile_time
+    }
+    pub fn set_compile_time(&mut self, is_compile_time: bool) {
+        self.is_compile_time = is_compile_time;
+    }
 }
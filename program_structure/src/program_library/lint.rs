@@ -0,0 +1,110 @@
+use super::error_code::ReportCode;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl LintLevel {
+    pub fn from_name(name: &str) -> Option<LintLevel> {
+        match name {
+            "allow" => Some(LintLevel::Allow),
+            "warn" => Some(LintLevel::Warn),
+            "deny" => Some(LintLevel::Deny),
+            _ => None,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            LintLevel::Allow => "allow",
+            LintLevel::Warn => "warn",
+            LintLevel::Deny => "deny",
+        }
+    }
+}
+
+// The warnings of the compiler, by their code and their name
+const LINTS: [(&str, &str); 19] = [
+    ("CA01", "unconstrained_signal"),
+    ("CA02", "unconstrained_io_signal"),
+    ("CA05", "undetermined_signal"),
+    ("CA06", "unconstrained_read_output"),
+    ("CA08", "satisfied_constraint"),
+    ("CA09", "nondeterministic_signal"),
+    ("CA10", "unimplied_witness_value"),
+    ("CA11", "violated_witness_value"),
+    ("CA12", "comparison_overflow"),
+    ("CA13", "witness_overflow"),
+    ("CA14", "unconstrained_assignment"),
+    ("CA15", "possibly_zero_divisor"),
+    ("CA16", "conditionally_assigned_read"),
+    ("CA17", "parallel_candidate"),
+    ("CA18", "useless_parallel"),
+    ("P1004", "missing_compiler_version"),
+    ("P1026", "signal_index"),
+    ("T3002", "runtime_warning"),
+    ("CG01", "custom_gate_intermediate_signal"),
+];
+
+// The group all contains every warning
+const GROUPS: [(&str, &[&str]); 5] = [
+    ("unconstrained", &["CA01", "CA02", "CA06", "CA14"]),
+    ("determinism", &["CA05", "CA09", "CA16"]),
+    ("witness", &["CA10", "CA11"]),
+    ("overflow", &["CA12", "CA13", "CA15"]),
+    ("parallel", &["CA17", "CA18"]),
+];
+pub const ALL: &str = "all";
+
+// The name of the lint of a warning, or its code when it has no name
+pub fn lint_name(code: &ReportCode) -> String {
+    let code = code.to_string();
+    LINTS.iter().find(|(lint_code, _)| *lint_code == code).map_or(code, |(_, name)| name.to_string())
+}
+
+// Whether the name is a lint, by its code or its name, or a group of lints
+pub fn is_lint(name: &str) -> bool {
+    name == ALL
+        || LINTS.iter().any(|(code, lint)| *code == name || *lint == name)
+        || GROUPS.iter().any(|(group, _)| *group == name)
+}
+
+pub fn lint_names() -> Vec<&'static str> {
+    let mut names: Vec<_> = LINTS.iter().map(|(_, name)| *name).collect();
+    names.extend(GROUPS.iter().map(|(group, _)| *group));
+    names.push(ALL);
+    names
+}
+
+// Whether the lint, the group or the code selects the warning of the code
+pub fn selects(name: &str, code: &str) -> bool {
+    name == ALL
+        || name == code
+        || LINTS.iter().any(|(lint_code, lint)| *lint_code == code && *lint == name)
+        || GROUPS.iter().any(|(group, codes)| *group == name && codes.contains(&code))
+}
+
+// The levels given to lints and groups of lints, where the last one that
+// selects a warning decides its level
+#[derive(Clone, Default)]
+pub struct LintLevels {
+    levels: Vec<(String, LintLevel)>,
+}
+
+impl LintLevels {
+    pub fn new() -> LintLevels {
+        LintLevels::default()
+    }
+    pub fn set(&mut self, name: String, level: LintLevel) {
+        self.levels.push((name, level));
+    }
+    pub fn extend(&mut self, other: &LintLevels) {
+        self.levels.extend(other.levels.iter().cloned());
+    }
+    pub fn level(&self, code: &ReportCode) -> Option<(LintLevel, &str)> {
+        let code = code.to_string();
+        self.levels.iter().rev().find(|(name, _)| selects(name, &code)).map(|(name, level)| (*level, name.as_str()))
+    }
+}
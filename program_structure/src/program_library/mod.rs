@@ -2,8 +2,10 @@ use super::ast;
 pub mod bus_data;
 pub mod error_code;
 pub mod error_definition;
+pub mod error_explanation;
 pub mod file_definition;
 pub mod function_data;
+pub mod lint;
 pub mod program_archive;
 pub mod program_merger;
 pub mod template_data;
@@ -1,12 +1,13 @@
-use super::ast::{Definition, Expression, MainComponent};
+use super::ast::{Definition, Expression, ExpressionInfixOpcode, NamedMainComponent, TagRule};
 use super::file_definition::{FileID, FileLibrary};
 use super::function_data::{FunctionData, FunctionInfo};
-use super::program_merger::Merger;
+use super::program_merger::{Merger, TagRules};
 use super::template_data::{TemplateData, TemplateInfo};
 use super::bus_data::{BusData, BusInfo};
 use crate::abstract_syntax_tree::ast::FillMeta;
 use std::collections::HashSet;
 use crate::error_definition::Report;
+use crate::error_code::ReportCode;
 
 type Contents = Vec<(FileID, Vec<Definition>)>;
 
@@ -23,15 +24,23 @@ pub struct ProgramArchive {
     pub bus_keys: HashSet<String>,
     pub public_inputs: Vec<String>,
     pub initial_template_call: Expression,
+    // the main components of the program, with their names when it has
+    // several of them. The public inputs and the call of the main component
+    // are the ones of the first, or of the one that is selected
+    pub main_components: Vec<NamedMainComponent>,
     pub custom_gates: bool,
+    // the name of the prime of the field, as bn128
+    pub prime: String,
+    pub tag_rules: TagRules,
 }
 impl ProgramArchive {
     pub fn new(
         file_library: FileLibrary,
         file_id_main: FileID,
-        main_component: MainComponent,
+        mut main_components: Vec<NamedMainComponent>,
         program_contents: Contents,
         custom_gates: bool,
+        prime: String,
     ) -> Result<ProgramArchive, (FileLibrary, Vec<Report>)> {
         let mut merger = Merger::new();
         let mut reports = vec![];
@@ -40,7 +49,25 @@ impl ProgramArchive {
                 reports.append(&mut errs);
             }
         }
-        let (mut fresh_id, functions, templates, buses) = merger.decompose();
+        let (mut fresh_id, functions, templates, buses, tag_rules) = merger.decompose();
+        for (tag, rules) in &tag_rules {
+            for (file_id, rule) in rules {
+                let function = match &rule.function {
+                    Some(function) => function,
+                    None => continue,
+                };
+                let message = match functions.get(function) {
+                    None => format!("The rule of the tag {} uses {}, which is not a function", tag, function),
+                    Some(data) if data.get_num_of_params() != 2 => {
+                        format!("The function {} of the rule of the tag {} must have two parameters", function, tag)
+                    }
+                    Some(_) => continue,
+                };
+                let mut report = Report::error(message, ReportCode::WrongTagRule);
+                report.add_primary(rule.meta.file_location(), *file_id, "This rule".to_string());
+                reports.push(report);
+            }
+        }
         let mut function_keys = HashSet::new();
         let mut template_keys = HashSet::new();
         let mut bus_keys = HashSet::new();
@@ -54,8 +81,10 @@ impl ProgramArchive {
         for key in buses.keys() {
             bus_keys.insert(key.clone());
         }
-        let (public_inputs, mut initial_template_call) = main_component;
-        initial_template_call.fill(file_id_main, &mut fresh_id);
+        for (_, (_, call)) in &mut main_components {
+            call.fill(file_id_main, &mut fresh_id);
+        }
+        let (public_inputs, initial_template_call) = main_components[0].1.clone();
         if reports.is_empty() {
             Ok(ProgramArchive {
                 id_max: fresh_id,
@@ -66,10 +95,13 @@ impl ProgramArchive {
                 buses,
                 public_inputs,
                 initial_template_call,
+                main_components,
                 function_keys,
                 template_keys,
                 bus_keys,
                 custom_gates,
+                prime,
+                tag_rules,
             })
         } else {
             Err((file_library, reports))
@@ -166,6 +198,26 @@ impl ProgramArchive {
     pub fn get_main_expression(&self) -> &Expression {
         &self.initial_template_call
     }
+    pub fn get_main_components(&self) -> &Vec<NamedMainComponent> {
+        &self.main_components
+    }
+    pub fn select_main_component(&mut self, index: usize) {
+        let (public_inputs, initial_template_call) = self.main_components[index].1.clone();
+        self.public_inputs = public_inputs;
+        self.initial_template_call = initial_template_call;
+    }
+    // Keeps the main component with the name as the only one, selected
+    pub fn keep_main_component(&mut self, name: &str) {
+        self.main_components.retain(|(main, _)| main.as_deref() == Some(name));
+        self.select_main_component(0);
+    }
+    // tag functions
+    pub fn get_tag_rules(&self) -> &TagRules {
+        &self.tag_rules
+    }
+    pub fn get_tag_rule(&self, tag: &str, op: ExpressionInfixOpcode) -> Option<&TagRule> {
+        self.tag_rules.get(tag)?.iter().map(|(_, rule)| rule).find(|rule| rule.op == op)
+    }
     // FileLibrary functions
     pub fn get_file_library(&self) -> &FileLibrary {
         &self.file_library
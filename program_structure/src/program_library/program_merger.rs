@@ -1,4 +1,5 @@
-use super::ast::Definition;
+use super::ast::{Definition, TagRule};
+use std::collections::HashMap;
 use super::error_code::ReportCode;
 use super::error_definition::Report;
 use super::file_definition::FileID;
@@ -6,11 +7,15 @@ use super::function_data::{FunctionData, FunctionInfo};
 use super::template_data::{TemplateData, TemplateInfo};
 use super::bus_data::{BusData, BusInfo};
 
+// The rules declared for each tag, with the files they are declared in
+pub type TagRules = HashMap<String, Vec<(FileID, TagRule)>>;
+
 pub struct Merger {
     fresh_id: usize,
     function_info: FunctionInfo,
     template_info: TemplateInfo,
     bus_info: BusInfo,
+    tag_rules: TagRules,
 }
 impl Default for Merger {
     fn default() -> Self {
@@ -18,7 +23,8 @@ impl Default for Merger {
             fresh_id: 0,
             function_info: FunctionInfo::new(),
             template_info: TemplateInfo::new(),
-            bus_info: BusInfo::new()
+            bus_info: BusInfo::new(),
+            tag_rules: TagRules::new(),
         }
     }
 }
@@ -32,7 +38,7 @@ impl Merger {
         let mut reports = vec![];
         for definition in definitions {
             let (name, meta) = match definition {
-                Definition::Template { name, args, arg_location, body, meta, parallel, is_custom_gate } => {
+                Definition::Template { name, args, arg_location, body, meta, parallel, is_custom_gate, .. } => {
                     if self.contains_function(&name) || self.contains_template(&name) || self.contains_bus(&name) {
                         (Option::Some(name), meta)
                     } else {
@@ -51,7 +57,7 @@ impl Merger {
                         (Option::None, meta)
                     }
                 }
-                Definition::Function { name, body, args, arg_location, meta } => {
+                Definition::Function { name, body, args, arg_location, meta, is_extern } => {
                     if self.contains_function(&name) || self.contains_template(&name) || self.contains_bus(&name) {
                         (Option::Some(name), meta)
                     } else {
@@ -59,9 +65,9 @@ impl Merger {
                             name.clone(),
                             file_id,
                             body,
-                            args.len(),
                             args,
                             arg_location,
+                            is_extern,
                             &mut self.fresh_id,
                         );
                         self.get_mut_function_info().insert(name.clone(), new_data);
@@ -85,6 +91,24 @@ impl Merger {
                         (Option::None, meta)
                     }
                 }
+                Definition::Tag { name, rules, .. } => {
+                    let tag_rules = self.tag_rules.entry(name.clone()).or_default();
+                    for rule in rules {
+                        if tag_rules.iter().any(|(_, r)| r.op == rule.op) {
+                            let mut report = Report::error(
+                                format!("The tag {} has more than one rule for the same operator", name),
+                                ReportCode::WrongTagRule,
+                            );
+                            report.add_primary(rule.meta.file_location(), file_id, "This rule".to_string());
+                            reports.push(report);
+                        } else {
+                            tag_rules.push((file_id, rule));
+                        }
+                    }
+                    continue;
+                }
+                // the parser replaces the constants and the operators in the bodies that use them
+                Definition::Constant { .. } | Definition::Enum { .. } | Definition::Operator { .. } => continue,
             };
             if let Option::Some(definition_name) = name {
                 let mut report = Report::error(
@@ -132,7 +156,7 @@ impl Merger {
     }
 
 
-    pub fn decompose(self) -> (usize, FunctionInfo, TemplateInfo, BusInfo) {
-        (self.fresh_id, self.function_info, self.template_info, self.bus_info)
+    pub fn decompose(self) -> (usize, FunctionInfo, TemplateInfo, BusInfo, TagRules) {
+        (self.fresh_id, self.function_info, self.template_info, self.bus_info, self.tag_rules)
     }
 }
\ No newline at end of file
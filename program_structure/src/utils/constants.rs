@@ -1,4 +1,5 @@
 use num_bigint::BigInt;
+use num_traits::Zero;
 
 const P_BN128: &str =
     "21888242871839275222246405745257275088548364400416034343698204186575808495617";
@@ -12,6 +13,17 @@ const P_VESTA : &str = "28948022309329048855892746252171976963363056481941647379
 const P_SECQ256R1 : &str = "115792089210356248762697446949407573530086143415290314195533631308867097853951";
 const P_BLS12377 : &str = "8444461749428370424248824938781546531375899335154063827935233455917409239041";
 
+const PRIMES: [(&str, &str); 8] = [
+    ("bn128", P_BN128),
+    ("bls12381", P_BLS12381),
+    ("goldilocks", P_GOLDILOCKS),
+    ("grumpkin", P_GRUMPKIN),
+    ("pallas", P_PALLAS),
+    ("vesta", P_VESTA),
+    ("secq256r1", P_SECQ256R1),
+    ("bls12377", P_BLS12377),
+];
+
 //const P_STR: &str = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
 
 pub struct UsefulConstants {
@@ -25,19 +37,69 @@ impl Clone for UsefulConstants {
 }
 
 impl UsefulConstants {
+    // The prime is the name of a curve, or the decimal value of the modulus
+    // of a pragma prime that is not the one of a curve
     pub fn new(possible_prime: &String) -> UsefulConstants {
-        let prime_to_use = if possible_prime.eq("bn128") {P_BN128} 
-          else if possible_prime.eq("bls12381") { P_BLS12381} 
-          else if possible_prime.eq("goldilocks") { P_GOLDILOCKS} 
-          else if possible_prime.eq("grumpkin") { P_GRUMPKIN} 
-          else if possible_prime.eq("pallas") { P_PALLAS} 
-          else if possible_prime.eq("vesta") { P_VESTA} 
-          else if possible_prime.eq("secq256r1") { P_SECQ256R1}
-          else if possible_prime.eq("bls12377") { P_BLS12377}
-          else {unreachable!()};
+        let prime_to_use = match PRIMES.iter().find(|(name, _)| name == possible_prime) {
+            Some((_, prime)) => prime,
+            None => possible_prime.as_str(),
+        };
 
         UsefulConstants { p: BigInt::parse_bytes(prime_to_use.as_bytes(), 10).expect("can not parse p") }
     }
+
+    // The name of the curve of a prime, as bn128
+    pub fn prime_name(p: &BigInt) -> Option<&'static str> {
+        PRIMES.iter().find(|(_, prime)| BigInt::parse_bytes(prime.as_bytes(), 10).as_ref() == Some(p)).map(|(name, _)| *name)
+    }
+
+    pub fn prime_names() -> Vec<&'static str> {
+        PRIMES.iter().map(|(name, _)| *name).collect()
+    }
+
+    pub fn is_prime_name(prime: &str) -> bool {
+        PRIMES.iter().any(|(name, _)| *name == prime)
+    }
+
+    // Miller-Rabin with the first primes as bases, which is exact for the
+    // numbers below 2^64 and almost sure for the larger ones
+    pub fn is_prime(n: &BigInt) -> bool {
+        let bases = [2u32, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71];
+        let one = BigInt::from(1);
+        if *n < BigInt::from(2) {
+            return false;
+        }
+        for base in bases {
+            let base = BigInt::from(base);
+            if *n == base {
+                return true;
+            }
+            if (n % &base).is_zero() {
+                return false;
+            }
+        }
+        let minus_one = n - &one;
+        let mut odd = minus_one.clone();
+        let mut twos = 0;
+        while (&odd % 2u32).is_zero() {
+            odd >>= 1;
+            twos += 1;
+        }
+        'bases: for base in bases {
+            let mut x = BigInt::from(base).modpow(&odd, n);
+            if x == one || x == minus_one {
+                continue;
+            }
+            for _ in 1..twos {
+                x = x.modpow(&BigInt::from(2), n);
+                if x == minus_one {
+                    continue 'bases;
+                }
+            }
+            return false;
+        }
+        true
+    }
     
     pub fn get_p(&self) -> &BigInt {
         &self.p
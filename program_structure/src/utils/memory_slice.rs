@@ -12,7 +12,7 @@ pub enum TypeInvalidAccess {
 
 pub enum TypeAssignmentError {
     MultipleAssignmentsComponent,
-    MultipleAssignmentsBus,
+    MultipleAssignmentsBus(Option<Meta>),
     MultipleAssignments(Meta),
     AssignmentOutput,
     NoInitializedComponent,
@@ -0,0 +1,157 @@
+use super::interpreter::PathState;
+use super::polynomial::{Algebra, AtomId, Poly};
+use circom_algebra::num_bigint::BigInt;
+use circom_algebra::num_traits::Zero;
+use dag::{bit_signal, is_binary_decomposition, DAG};
+use std::collections::{HashMap, HashSet};
+
+type Constraint = circom_algebra::algebra::Constraint<usize>;
+
+// The differences between the values that a path computes and the
+// constraints of its template
+pub enum Divergence {
+    // the constraints do not force the value computed for the output, so a
+    // prover can give it other values
+    Unimplied(usize),
+    // the values computed do not satisfy the constraint, given by its
+    // position in the node
+    Violated(usize),
+}
+
+pub fn divergences(algebra: &mut Algebra, path: &PathState, dag: &DAG, node_id: usize) -> Vec<Divergence> {
+    let node = &dag.nodes[node_id];
+    let mut witness: HashMap<AtomId, Poly> = path.facts.substitution.clone();
+    let mut computed: Vec<usize> = path.signals.keys().cloned().collect();
+    computed.sort();
+    for signal in &computed {
+        let value = algebra.substitute(&path.signals[signal], &path.facts.substitution).unwrap_or_default();
+        let atom = signal_atom(algebra, *signal);
+        witness.insert(atom, value);
+    }
+
+    let mut found = Vec::new();
+    let mut polys = Vec::new();
+    for (position, constraint) in node.constraints().iter().enumerate() {
+        let poly = constraint_poly(algebra, constraint);
+        let value = algebra.substitute(&poly, &witness).ok().and_then(|value| value.as_constant());
+        if value.is_some_and(|value| !value.is_zero()) {
+            found.push(Divergence::Violated(position));
+        }
+        polys.push(algebra.substitute(&poly, &path.facts.substitution).unwrap_or(poly));
+    }
+
+    let inputs = (node.number_of_outputs() + 1)..(node.number_of_outputs() + node.number_of_inputs() + 1);
+    let determined = determined_signals(algebra, path, dag, node_id, &polys, &witness, inputs.collect());
+    for signal in computed {
+        if signal <= node.number_of_outputs() && !determined.contains(&signal) {
+            found.push(Divergence::Unimplied(signal));
+        }
+    }
+    found
+}
+
+fn signal_atom(algebra: &mut Algebra, signal: usize) -> AtomId {
+    let poly = algebra.signal(signal);
+    poly.atoms().into_iter().next().unwrap()
+}
+
+// A * B - C
+fn constraint_poly(algebra: &mut Algebra, constraint: &Constraint) -> Poly {
+    let mut linear = |coefficients: &HashMap<usize, BigInt>| {
+        let mut poly = Poly::default();
+        for (signal, value) in coefficients {
+            let term = if *signal == Constraint::constant_coefficient() {
+                algebra.constant(value)
+            } else {
+                let atom = algebra.signal(*signal);
+                algebra.mul(&atom, &algebra.constant(value))
+            };
+            poly = algebra.add(&poly, &term);
+        }
+        poly
+    };
+    let (a, b, c) = (linear(constraint.a()), linear(constraint.b()), linear(constraint.c()));
+    algebra.sub(&algebra.mul(&a, &b), &c)
+}
+
+// The signals whose values the constraints force on the path, from the
+// inputs of the template. A constraint forces its only signal that is not
+// known yet when it is linear in it with a coefficient that is not 0 on the
+// path, the outputs of a component are forced by its inputs, and a linear
+// constraint forces the bits of a binary decomposition
+fn determined_signals(
+    algebra: &mut Algebra,
+    path: &PathState,
+    dag: &DAG,
+    node_id: usize,
+    polys: &[Poly],
+    witness: &HashMap<AtomId, Poly>,
+    mut determined: HashSet<usize>,
+) -> HashSet<usize> {
+    let field = algebra.field.clone();
+    let constraints = dag.nodes[node_id].constraints();
+    let bits: HashSet<usize> = constraints.iter().filter_map(|constraint| bit_signal(constraint, &field)).collect();
+    let mut components = Vec::new();
+    for edge in &dag.adjacency[node_id] {
+        let child = &dag.nodes[edge.get_goes_to()];
+        let outputs: Vec<usize> = (1..child.number_of_outputs() + 1).map(|signal| signal + edge.get_in()).collect();
+        let inputs: Vec<usize> = (child.number_of_outputs() + 1..child.number_of_outputs() + child.number_of_inputs() + 1)
+            .map(|signal| signal + edge.get_in())
+            .collect();
+        components.push((inputs, outputs));
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for poly in polys {
+            let unknowns: Vec<usize> = algebra.signals(poly).into_iter().filter(|signal| !determined.contains(signal)).collect();
+            let forced = match unknowns.as_slice() {
+                [] => false,
+                [signal] => {
+                    let atom = signal_atom(algebra, *signal);
+                    match poly.linear_coefficient(atom) {
+                        Some((coefficient, rest)) => {
+                            !algebra.signals(&coefficient).contains(signal)
+                                && !algebra.signals(&rest).contains(signal)
+                                && is_nonzero(algebra, &coefficient, path, witness)
+                        }
+                        None => false,
+                    }
+                }
+                _ => match algebra.linear_terms(poly) {
+                    Some((terms, _)) if unknowns.iter().all(|signal| bits.contains(signal)) => {
+                        let atoms: HashSet<AtomId> = unknowns.iter().map(|signal| signal_atom(algebra, *signal)).collect();
+                        let coefficients: Vec<BigInt> =
+                            terms.into_iter().filter(|(atom, _)| atoms.contains(atom)).map(|(_, value)| value).collect();
+                        coefficients.len() == unknowns.len() && is_binary_decomposition(coefficients.iter(), &field)
+                    }
+                    _ => false,
+                },
+            };
+            if forced {
+                determined.extend(unknowns);
+                changed = true;
+            }
+        }
+        for (inputs, outputs) in &components {
+            if inputs.iter().all(|signal| determined.contains(signal)) && !outputs.iter().all(|signal| determined.contains(signal)) {
+                determined.extend(outputs.iter().cloned());
+                changed = true;
+            }
+        }
+    }
+    determined
+}
+
+// whether the coefficient is not 0 on the path, by itself or with the values
+// computed for its signals
+fn is_nonzero(algebra: &mut Algebra, coefficient: &Poly, path: &PathState, witness: &HashMap<AtomId, Poly>) -> bool {
+    if algebra.is_nonzero(coefficient, &path.facts.nonzero) {
+        return true;
+    }
+    match algebra.substitute(coefficient, witness) {
+        Ok(value) => algebra.is_nonzero(&value, &path.facts.nonzero),
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,143 @@
+use super::{signal_names, SymbolicTemplate};
+use dag::DAG;
+use std::collections::{BTreeSet, HashMap};
+
+// A public signal of the main component, with the private inputs of the
+// circuit that it depends on
+pub struct Flow {
+    pub signal: String,
+    pub is_input: bool,
+    pub private_inputs: Vec<String>,
+}
+
+// What the signals of a template depend on, by the ids of its inputs: the
+// inputs that the value computed for each output depends on, and the inputs
+// that the constraints of the template and its subcomponents relate with
+// each input
+#[derive(Default)]
+struct Summary {
+    outputs: HashMap<usize, BTreeSet<usize>>,
+    ties: HashMap<usize, BTreeSet<usize>>,
+}
+
+struct Analysis<'a> {
+    dag: &'a DAG,
+    templates: HashMap<usize, &'a SymbolicTemplate>,
+    summaries: HashMap<usize, Summary>,
+}
+
+// The private inputs that each public output of the circuit depends on, in
+// the witness generation, and the ones that the constraints relate with
+// each public input. The dependencies of a template go through the values
+// of all its paths and the conditions of their branches, and a template
+// whose paths are not complete is taken to compute each signal from all
+// its inputs
+pub fn information_flows(templates: &[SymbolicTemplate], dag: &DAG) -> Vec<Flow> {
+    let mut analysis = Analysis {
+        dag,
+        templates: templates.iter().map(|template| (template.node, template)).collect(),
+        summaries: HashMap::new(),
+    };
+    let main_id = dag.main_id();
+    analysis.summarize(main_id);
+    let main = &dag.nodes[main_id];
+    let summary = &analysis.summaries[&main_id];
+    let names = signal_names(main.correspondence());
+    let name = |signal: &usize| names.get(signal).cloned().unwrap_or_else(|| format!("signal {}", signal));
+    let inputs = (main.number_of_outputs() + 1)..(main.number_of_outputs() + main.number_of_inputs() + 1);
+    let private: BTreeSet<usize> = inputs.clone().filter(|signal| !main.is_public_input(*signal)).collect();
+    let private_inputs = |signals: Option<&BTreeSet<usize>>| match signals {
+        Some(signals) => signals.intersection(&private).map(name).collect(),
+        None => Vec::new(),
+    };
+
+    let mut flows = Vec::new();
+    for signal in 1..main.number_of_outputs() + 1 {
+        let private_inputs = private_inputs(summary.outputs.get(&signal));
+        flows.push(Flow { signal: name(&signal), is_input: false, private_inputs });
+    }
+    for signal in inputs.filter(|signal| main.is_public_input(*signal)) {
+        let private_inputs = private_inputs(summary.ties.get(&signal));
+        flows.push(Flow { signal: name(&signal), is_input: true, private_inputs });
+    }
+    flows
+}
+
+impl Analysis<'_> {
+    fn summarize(&mut self, node_id: usize) {
+        if self.summaries.contains_key(&node_id) {
+            return;
+        }
+        for edge in &self.dag.adjacency[node_id] {
+            self.summarize(edge.get_goes_to());
+        }
+        let node = &self.dag.nodes[node_id];
+        let mut known = HashMap::new();
+        let mut summary = Summary::default();
+        for signal in 1..node.number_of_outputs() + 1 {
+            let dependencies = self.dependencies(node_id, signal, &mut known);
+            summary.outputs.insert(signal, dependencies);
+        }
+        for constraint in node.constraints() {
+            let mut related = BTreeSet::new();
+            for signal in constraint.take_cloned_signals_ordered() {
+                related.extend(self.dependencies(node_id, signal, &mut known));
+            }
+            tie(&mut summary.ties, &related);
+        }
+        for edge in &self.dag.adjacency[node_id] {
+            for (input, tied) in &self.summaries[&edge.get_goes_to()].ties {
+                let mut related = self.dependencies(node_id, input + edge.get_in(), &mut known);
+                for other in tied {
+                    related.extend(self.dependencies(node_id, other + edge.get_in(), &mut known));
+                }
+                tie(&mut summary.ties, &related);
+            }
+        }
+        self.summaries.insert(node_id, summary);
+    }
+
+    // The inputs of the template that the value of the signal depends on.
+    // The outputs of a subcomponent depend on its inputs as its summary
+    // says, and the rest of the signals on the ones that their values are
+    // computed from
+    fn dependencies(&self, node_id: usize, signal: usize, known: &mut HashMap<usize, BTreeSet<usize>>) -> BTreeSet<usize> {
+        if let Some(dependencies) = known.get(&signal) {
+            return dependencies.clone();
+        }
+        known.insert(signal, BTreeSet::new());
+        let node = &self.dag.nodes[node_id];
+        let inputs = (node.number_of_outputs() + 1)..(node.number_of_outputs() + node.number_of_inputs() + 1);
+        let component = self.dag.adjacency[node_id].iter().find(|edge| {
+            let outputs = self.dag.nodes[edge.get_goes_to()].number_of_outputs();
+            signal > edge.get_in() && signal <= edge.get_in() + outputs
+        });
+        let mut dependencies = BTreeSet::new();
+        if inputs.contains(&signal) {
+            dependencies.insert(signal);
+        } else if let Some(edge) = component {
+            let summary = &self.summaries[&edge.get_goes_to()];
+            for input in summary.outputs.get(&(signal - edge.get_in())).into_iter().flatten() {
+                dependencies.extend(self.dependencies(node_id, input + edge.get_in(), known));
+            }
+        } else {
+            match self.templates.get(&node_id) {
+                Some(template) if template.stopped.is_none() => {
+                    for source in template.dependencies.get(&signal).into_iter().flatten() {
+                        dependencies.extend(self.dependencies(node_id, *source, known));
+                    }
+                }
+                _ => dependencies.extend(inputs),
+            }
+        }
+        known.insert(signal, dependencies.clone());
+        dependencies
+    }
+}
+
+// each input of the set is related with all of them
+fn tie(ties: &mut HashMap<usize, BTreeSet<usize>>, related: &BTreeSet<usize>) {
+    for input in related {
+        ties.entry(*input).or_default().extend(related.iter().cloned());
+    }
+}
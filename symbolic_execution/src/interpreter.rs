@@ -0,0 +1,692 @@
+use super::overflows::{self, Overflow};
+use super::polynomial::{Algebra, Atom, AtomId, Operator, Poly};
+use circom_algebra::num_bigint::BigInt;
+use circom_algebra::num_traits::{One, Zero};
+use circom_algebra::range_analysis::Range;
+use compiler::circuit_design::circuit::Circuit;
+use compiler::circuit_design::function::FunctionCodeInfo;
+use compiler::circuit_design::template::TemplateCodeInfo;
+use compiler::intermediate_representation::ir_interface::*;
+use std::collections::HashMap;
+
+// The deepest chain of calls to functions that is followed
+const MAX_CALL_DEPTH: usize = 64;
+// The most branches of a path whose conditions depend on the inputs, so
+// that a loop bounded by an input ends
+const MAX_DECISIONS: usize = 256;
+
+pub struct Limits {
+    pub max_paths: usize,
+    pub max_steps: usize,
+}
+
+// What a path ends with when it is not completed
+pub enum Stop {
+    // the conditions taken cannot hold together, or the witness generation
+    // fails on them
+    Infeasible,
+    Unsupported(String),
+}
+
+type Execution<T> = Result<T, Stop>;
+
+enum Flow {
+    Next,
+    Return(Vec<Poly>),
+}
+
+// The polynomials known about a path: the values of some atoms and the ones
+// that are 0 or not 0
+#[derive(Clone, Default)]
+pub struct Facts {
+    pub substitution: HashMap<AtomId, Poly>,
+    pub nonzero: Vec<Poly>,
+    pub zero: Vec<Poly>,
+}
+
+// A path of the witness generation of a template, with the conditions of
+// the branches it takes and the values it computes for the signals, by
+// their ids in the node of the template
+pub struct PathState {
+    pub conditions: Vec<(Poly, bool)>,
+    pub facts: Facts,
+    pub signals: HashMap<usize, Poly>,
+}
+
+pub struct TemplateExecution {
+    pub algebra: Algebra,
+    pub paths: Vec<PathState>,
+    pub stopped: Option<String>,
+    pub overflows: Vec<Overflow>,
+}
+
+enum Place {
+    Variable(usize),
+    Signal(usize),
+    // the component and the position of the signal in it
+    Subcomponent(usize, usize),
+}
+
+// the template of a subcomponent and the position of its first signal in
+// the memory of the template
+struct Subcomponent {
+    template_id: usize,
+    signal_start: usize,
+}
+
+// Runs the template with symbolic values for its inputs and the outputs of
+// its subcomponents, given the ranges of its signals. The paths are found
+// running the template again for each choice of the branches whose
+// conditions depend on the inputs, depth first
+pub fn execute_template(
+    circuit: &Circuit,
+    template: &TemplateCodeInfo,
+    memory_positions: &HashMap<usize, usize>,
+    ranges: &HashMap<usize, Range>,
+    field: &BigInt,
+    limits: &Limits,
+) -> TemplateExecution {
+    let mut algebra = Algebra::new(field);
+    let memory_signals: HashMap<usize, usize> = memory_positions.iter().map(|(signal, position)| (*position, *signal)).collect();
+    let mut paths = Vec::new();
+    let mut stopped = None;
+    let mut overflows = Vec::new();
+    let mut pending = vec![Vec::new()];
+    while let Some(choices) = pending.pop() {
+        if paths.len() >= limits.max_paths {
+            stopped = Some(format!("it has more than {} paths", limits.max_paths));
+            break;
+        }
+        let mut run = Run {
+            circuit,
+            algebra: &mut algebra,
+            memory_signals: &memory_signals,
+            ranges,
+            definition: (&template.name, false),
+            overflows: &mut overflows,
+            max_steps: limits.max_steps,
+            steps: 0,
+            depth: 0,
+            choices: &choices,
+            taken: Vec::new(),
+            vars: HashMap::new(),
+            subcomponents: HashMap::new(),
+            state: PathState { conditions: Vec::new(), facts: Facts::default(), signals: HashMap::new() },
+        };
+        let result = run.execute_block(&template.body);
+        for position in choices.len()..run.taken.len() {
+            let mut alternative = run.taken[..position].to_vec();
+            alternative.push(!run.taken[position]);
+            pending.push(alternative);
+        }
+        match result {
+            Ok(_) => paths.push(run.state),
+            Err(Stop::Infeasible) => {}
+            Err(Stop::Unsupported(reason)) => {
+                stopped = Some(reason);
+                break;
+            }
+        }
+    }
+    TemplateExecution { algebra, paths, stopped, overflows }
+}
+
+struct Run<'a> {
+    circuit: &'a Circuit,
+    algebra: &'a mut Algebra,
+    // the signals of the main component that are not in the position of
+    // their ids in its memory, by their positions
+    memory_signals: &'a HashMap<usize, usize>,
+    ranges: &'a HashMap<usize, Range>,
+    // the template or the function that is running, and whether it is a
+    // function
+    definition: (&'a str, bool),
+    overflows: &'a mut Vec<Overflow>,
+    max_steps: usize,
+    steps: usize,
+    depth: usize,
+    // the branches to take in the decisions, and the ones taken
+    choices: &'a [bool],
+    taken: Vec<bool>,
+    vars: HashMap<usize, Poly>,
+    subcomponents: HashMap<usize, Subcomponent>,
+    state: PathState,
+}
+
+impl<'a> Run<'a> {
+    fn execute_block(&mut self, block: &'a [InstructionPointer]) -> Execution<Flow> {
+        for instruction in block {
+            if let Flow::Return(values) = self.execute(instruction)? {
+                return Ok(Flow::Return(values));
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn execute(&mut self, instruction: &'a Instruction) -> Execution<Flow> {
+        use Instruction::*;
+        self.steps += 1;
+        if self.steps > self.max_steps {
+            return Err(Stop::Unsupported(format!("it runs more than {} instructions", self.max_steps)));
+        }
+        match instruction {
+            Store(bucket) => {
+                let values = self.evaluate(&bucket.src)?;
+                let place = self.place(&bucket.dest_address_type, &bucket.dest)?;
+                let size = self.size(&bucket.context.size, &place);
+                self.write(&place, size, values);
+                Ok(Flow::Next)
+            }
+            ArrayCopy(bucket) => {
+                let source = self.address(&bucket.src_address_type, &bucket.src)?;
+                let values = self.read(&source, bucket.size)?;
+                let destination = self.address(&bucket.dest_address_type, &bucket.dest)?;
+                self.write(&destination, bucket.size, values);
+                Ok(Flow::Next)
+            }
+            Call(bucket) => {
+                let values = self.call(bucket)?;
+                if let ReturnType::Final(data) = &bucket.return_info {
+                    let place = self.place(&data.dest_address_type, &data.dest)?;
+                    let size = self.size(&data.context.size, &place);
+                    self.write(&place, size, values);
+                }
+                Ok(Flow::Next)
+            }
+            Branch(bucket) => {
+                let condition = self.evaluate_single(&bucket.cond)?;
+                if self.decide(condition)? {
+                    self.execute_block(&bucket.if_branch)
+                } else {
+                    self.execute_block(&bucket.else_branch)
+                }
+            }
+            Switch(bucket) => {
+                let selector = self.evaluate_single(&bucket.selector)?;
+                for case in &bucket.cases {
+                    let value = self.algebra.constant(&BigInt::from(case.value));
+                    let condition = self.operate(Operator::Eq, vec![selector.clone(), value])?;
+                    if self.decide(condition)? {
+                        return self.execute_block(&case.body);
+                    }
+                }
+                self.execute_block(&bucket.default_branch)
+            }
+            Loop(bucket) => {
+                loop {
+                    let condition = self.evaluate_single(&bucket.continue_condition)?;
+                    if !self.decide(condition)? {
+                        return Ok(Flow::Next);
+                    }
+                    if let Flow::Return(values) = self.execute_block(&bucket.body)? {
+                        return Ok(Flow::Return(values));
+                    }
+                    self.steps += 1;
+                    if self.steps > self.max_steps {
+                        return Err(Stop::Unsupported(format!("it runs more than {} instructions", self.max_steps)));
+                    }
+                }
+            }
+            Return(bucket) => {
+                let mut values = self.evaluate(&bucket.value)?;
+                values.resize(bucket.with_size, Poly::default());
+                Ok(Flow::Return(values))
+            }
+            // the constraints are checked against the values computed once
+            // the path is completed, and the rest of the assertions restrict
+            // the values of the path
+            Assert(bucket) if bucket.is_constraint => Ok(Flow::Next),
+            Assert(bucket) => {
+                let value = self.evaluate_single(&bucket.evaluate)?;
+                self.assume(value, true)?;
+                Ok(Flow::Next)
+            }
+            CreateCmp(bucket) => {
+                let start = self.evaluate_index(&bucket.sub_cmp_id)?;
+                let positions: Vec<usize> = if bucket.defined_positions.len() == bucket.number_of_cmp {
+                    (0..bucket.number_of_cmp).collect()
+                } else {
+                    bucket.defined_positions.iter().map(|(position, _)| *position).collect()
+                };
+                for (iteration, position) in positions.into_iter().enumerate() {
+                    let subcomponent = Subcomponent {
+                        template_id: bucket.template_id,
+                        signal_start: bucket.signal_offset + iteration * bucket.signal_offset_jump,
+                    };
+                    self.subcomponents.insert(start + position, subcomponent);
+                }
+                Ok(Flow::Next)
+            }
+            Log(_) => Ok(Flow::Next),
+            Value(_) | Load(_) | Compute(_) | Select(_) => {
+                self.evaluate(instruction)?;
+                Ok(Flow::Next)
+            }
+        }
+    }
+
+    fn evaluate(&mut self, instruction: &'a Instruction) -> Execution<Vec<Poly>> {
+        use Instruction::*;
+        match instruction {
+            Value(bucket) => {
+                let value = match bucket.parse_as {
+                    ValueType::U32 => BigInt::from(bucket.value),
+                    ValueType::BigInt => self.circuit.c_producer.field_tracking[bucket.value].parse().unwrap(),
+                };
+                Ok(vec![self.algebra.constant(&value)])
+            }
+            Load(bucket) => {
+                let place = self.place(&bucket.address_type, &bucket.src)?;
+                let size = self.size(&bucket.context.size, &place);
+                self.read(&place, size)
+            }
+            Compute(bucket) => self.compute(bucket),
+            Select(bucket) => {
+                let condition = self.evaluate_single(&bucket.cond)?;
+                if self.decide(condition)? {
+                    self.evaluate(&bucket.if_true)
+                } else {
+                    self.evaluate(&bucket.if_false)
+                }
+            }
+            Call(bucket) => self.call(bucket),
+            _ => Err(Stop::Unsupported("an instruction is used as an expression".to_string())),
+        }
+    }
+
+    fn evaluate_single(&mut self, instruction: &'a Instruction) -> Execution<Poly> {
+        Ok(self.evaluate(instruction)?.into_iter().next().unwrap_or_default())
+    }
+
+    fn evaluate_index(&mut self, instruction: &'a Instruction) -> Execution<usize> {
+        self.evaluate_single(instruction)?
+            .as_usize()
+            .ok_or_else(|| Stop::Unsupported("it accesses a position that depends on its inputs".to_string()))
+    }
+
+    fn compute(&mut self, bucket: &'a ComputeBucket) -> Execution<Vec<Poly>> {
+        use OperatorType::*;
+        let mut operands = Vec::new();
+        for operand in &bucket.stack {
+            operands.push(self.evaluate(operand)?);
+        }
+        let first = |position: usize| operands[position].first().cloned().unwrap_or_default();
+        let values: Vec<Poly> = (0..operands.len()).map(first).collect();
+        let mut found: Vec<Overflow> = overflows::find(self.algebra, self.ranges, &bucket.op, &values).into_iter().collect();
+        if matches!(bucket.op, Div | IntDiv | Mod) {
+            let divisor = self.substitute(&values[1])?;
+            if !self.algebra.is_nonzero(&divisor, &self.state.facts.nonzero) {
+                found.extend(overflows::zero_divisor(self.algebra, self.ranges, &bucket.op, &divisor));
+            }
+        }
+        for mut overflow in found {
+            overflow.definition = self.definition.0.to_string();
+            overflow.is_function = self.definition.1;
+            overflow.line = bucket.line;
+            if !self.overflows.iter().any(|found| found.is_at(&overflow)) {
+                self.overflows.push(overflow);
+            }
+        }
+        let result = match &bucket.op {
+            Add | AddAddress => self.algebra.add(&first(0), &first(1)),
+            Sub => self.algebra.sub(&first(0), &first(1)),
+            Mul | MulAddress => self.algebra.mul(&first(0), &first(1)),
+            Div => {
+                let inverse = self.algebra.inverse(&first(1)).map_err(|_| Stop::Infeasible)?;
+                self.algebra.mul(&first(0), &inverse)
+            }
+            Pow => self.algebra.pow(&first(0), &first(1)).map_err(|_| Stop::Infeasible)?,
+            PrefixSub => self.algebra.neg(&first(0)),
+            ToAddress => first(0),
+            Eq(_) => {
+                // the arrays are equal when all their positions are
+                let pairs: Vec<(Poly, Poly)> = operands[0].iter().cloned().zip(operands[1].iter().cloned()).collect();
+                let mut result = self.algebra.constant(&BigInt::one());
+                for (left, right) in pairs {
+                    let equal = self.operate(Operator::Eq, vec![left, right])?;
+                    result = self.operate(Operator::BoolAnd, vec![result, equal])?;
+                }
+                result
+            }
+            operator => {
+                let operator = match operator {
+                    IntDiv => Operator::IntDiv,
+                    Mod => Operator::Mod,
+                    ShiftL => Operator::ShiftL,
+                    ShiftR => Operator::ShiftR,
+                    LesserEq => Operator::LesserEq,
+                    GreaterEq => Operator::GreaterEq,
+                    Lesser => Operator::Lesser,
+                    Greater => Operator::Greater,
+                    NotEq => Operator::NotEq,
+                    BoolOr => Operator::BoolOr,
+                    BoolAnd => Operator::BoolAnd,
+                    BitOr => Operator::BitOr,
+                    BitAnd => Operator::BitAnd,
+                    BitXor => Operator::BitXor,
+                    BoolNot => Operator::BoolNot,
+                    _ => Operator::Complement,
+                };
+                self.operate(operator, values)?
+            }
+        };
+        Ok(vec![result])
+    }
+
+    fn operate(&mut self, operator: Operator, operands: Vec<Poly>) -> Execution<Poly> {
+        // the witness generation fails on the operations that the field
+        // cannot compute, as the divisions by 0
+        self.algebra.operate(operator, operands).map_err(|_| Stop::Infeasible)
+    }
+
+    fn call(&mut self, bucket: &'a CallBucket) -> Execution<Vec<Poly>> {
+        let function = match self.circuit.functions.iter().find(|function| function.header == bucket.symbol) {
+            Some(function) => function,
+            None => return Err(Stop::Unsupported(format!("it calls the unknown function {}", bucket.symbol))),
+        };
+        let mut arguments = Vec::new();
+        for (argument, context) in bucket.arguments.iter().zip(&bucket.argument_types) {
+            let mut values = self.evaluate(argument)?;
+            let size = match &context.size {
+                SizeOption::Single(size) => *size,
+                SizeOption::Multiple(_) => values.len(),
+            };
+            values.resize(size, Poly::default());
+            arguments.extend(values);
+        }
+        let size = match &bucket.return_info {
+            ReturnType::Final(data) => match &data.context.size {
+                SizeOption::Single(size) => *size,
+                SizeOption::Multiple(sizes) => sizes.iter().map(|(_, size)| *size).max().unwrap_or(1),
+            },
+            ReturnType::Intermediate { .. } => 1,
+        };
+        if function.is_extern {
+            let mut results = Vec::new();
+            for position in 0..size {
+                results.push(self.operate(Operator::Extern(function.name.clone(), position), arguments.clone())?);
+            }
+            return Ok(results);
+        }
+        self.run_function(function, arguments)
+    }
+
+    fn run_function(&mut self, function: &'a FunctionCodeInfo, arguments: Vec<Poly>) -> Execution<Vec<Poly>> {
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(Stop::Unsupported(format!("it nests more than {} calls", MAX_CALL_DEPTH)));
+        }
+        let vars = arguments.into_iter().enumerate().collect();
+        let caller = std::mem::replace(&mut self.vars, vars);
+        let definition = std::mem::replace(&mut self.definition, (&function.name, true));
+        self.depth += 1;
+        let flow = self.execute_block(&function.body);
+        self.depth -= 1;
+        self.vars = caller;
+        self.definition = definition;
+        match flow? {
+            Flow::Return(values) => Ok(values),
+            Flow::Next => Ok(Vec::new()),
+        }
+    }
+
+    fn place(&mut self, address: &'a AddressType, location: &'a LocationRule) -> Execution<Place> {
+        let component = match address {
+            AddressType::SubcmpSignal { cmp_address, .. } => Some(self.evaluate_index(cmp_address)?),
+            _ => None,
+        };
+        let index = match location {
+            LocationRule::Indexed { location, .. } => self.evaluate_index(location)?,
+            LocationRule::Mapped { signal_code, indexes } => {
+                let template_id = match component.and_then(|component| self.subcomponents.get(&component)) {
+                    Some(subcomponent) => subcomponent.template_id,
+                    None => return Err(Stop::Unsupported("it accesses a component that is not created".to_string())),
+                };
+                let definition = &self.circuit.c_producer.get_io_map()[&template_id][*signal_code];
+                let mut offset = definition.offset;
+                for access in indexes {
+                    let info = match access {
+                        AccessType::Indexed(info) => info,
+                        AccessType::Qualified(_) => {
+                            return Err(Stop::Unsupported("it accesses the fields of a bus of a component".to_string()))
+                        }
+                    };
+                    let mut position = 0;
+                    for (dimension, index) in info.indexes.iter().enumerate() {
+                        position = position * definition.lengths[dimension] + self.evaluate_index(index)?;
+                    }
+                    for dimension in info.indexes.len()..info.symbol_dim {
+                        position *= definition.lengths[dimension];
+                    }
+                    offset += position * definition.size;
+                }
+                offset
+            }
+        };
+        Ok(match (address, component) {
+            (AddressType::Variable, _) => Place::Variable(index),
+            (_, Some(component)) => Place::Subcomponent(component, index),
+            _ => Place::Signal(index),
+        })
+    }
+
+    fn address(&mut self, address: &'a AddressType, location: &'a Instruction) -> Execution<Place> {
+        let index = self.evaluate_index(location)?;
+        match address {
+            AddressType::Variable => Ok(Place::Variable(index)),
+            AddressType::Signal => Ok(Place::Signal(index)),
+            AddressType::SubcmpSignal { .. } => Err(Stop::Unsupported("it copies the signals of a component".to_string())),
+        }
+    }
+
+    fn size(&self, size: &SizeOption, place: &Place) -> usize {
+        match (size, place) {
+            (SizeOption::Single(size), _) => *size,
+            (SizeOption::Multiple(sizes), Place::Subcomponent(component, _)) => {
+                let template_id = self.subcomponents.get(component).map(|subcomponent| subcomponent.template_id);
+                sizes.iter().find(|(id, _)| Some(*id) == template_id).map_or(1, |(_, size)| *size)
+            }
+            (SizeOption::Multiple(_), _) => 1,
+        }
+    }
+
+    // the id in the node of a signal given by its position in the memory
+    fn signal_id(&self, place: &Place, offset: usize) -> Option<usize> {
+        match place {
+            Place::Signal(index) => {
+                let position = index + offset + 1;
+                Some(*self.memory_signals.get(&position).unwrap_or(&position))
+            }
+            Place::Subcomponent(component, index) => {
+                self.subcomponents.get(component).map(|subcomponent| subcomponent.signal_start + index + offset + 1)
+            }
+            Place::Variable(_) => None,
+        }
+    }
+
+    fn read(&mut self, place: &Place, size: usize) -> Execution<Vec<Poly>> {
+        let mut values = Vec::new();
+        for offset in 0..size {
+            let value = match (place, self.signal_id(place, offset)) {
+                (Place::Variable(index), _) => self.vars.get(&(index + offset)).cloned().unwrap_or_default(),
+                (_, Some(signal)) => match self.state.signals.get(&signal) {
+                    Some(value) => value.clone(),
+                    None => self.algebra.signal(signal),
+                },
+                (_, None) => return Err(Stop::Unsupported("it accesses a component that is not created".to_string())),
+            };
+            values.push(self.substitute(&value)?);
+        }
+        Ok(values)
+    }
+
+    fn write(&mut self, place: &Place, size: usize, mut values: Vec<Poly>) {
+        values.resize(size.max(values.len()), Poly::default());
+        for (offset, value) in values.into_iter().take(size).enumerate() {
+            match (place, self.signal_id(place, offset)) {
+                (Place::Variable(index), _) => {
+                    self.vars.insert(index + offset, value);
+                }
+                (_, Some(signal)) => {
+                    self.state.signals.insert(signal, value);
+                }
+                (_, None) => {}
+            }
+        }
+    }
+
+    fn substitute(&mut self, poly: &Poly) -> Execution<Poly> {
+        if self.state.facts.substitution.is_empty() {
+            return Ok(poly.clone());
+        }
+        self.algebra.substitute(poly, &self.state.facts.substitution).map_err(|_| Stop::Infeasible)
+    }
+
+    // The branch taken for the condition. When both are possible the
+    // branch is the next choice of the run, or the true one after them
+    fn decide(&mut self, condition: Poly) -> Execution<bool> {
+        let condition = self.substitute(&condition)?;
+        if let Some(value) = condition.as_constant() {
+            return Ok(!value.is_zero());
+        }
+        let facts = &self.state.facts;
+        let can_be_true = !facts.zero.contains(&self.algebra.monic(&condition));
+        let can_be_false = !self.algebra.is_nonzero(&condition, &facts.nonzero);
+        let branch = match (can_be_true, can_be_false) {
+            (true, true) => {
+                if self.taken.len() >= MAX_DECISIONS {
+                    return Err(Stop::Unsupported(format!(
+                        "it takes more than {} branches that depend on its inputs",
+                        MAX_DECISIONS
+                    )));
+                }
+                let branch = self.choices.get(self.taken.len()).cloned().unwrap_or(true);
+                self.taken.push(branch);
+                branch
+            }
+            (true, false) => true,
+            (false, true) => false,
+            (false, false) => return Err(Stop::Infeasible),
+        };
+        self.state.conditions.push((condition.clone(), branch));
+        self.assume(condition, branch)?;
+        Ok(branch)
+    }
+
+    // Adds to the facts of the path that the polynomial is not 0, or that
+    // it is 0
+    fn assume(&mut self, poly: Poly, nonzero: bool) -> Execution<()> {
+        let poly = self.substitute(&poly)?;
+        if let Some(value) = poly.as_constant() {
+            return if value.is_zero() != nonzero { Ok(()) } else { Err(Stop::Infeasible) };
+        }
+        if nonzero {
+            if self.state.facts.zero.contains(&self.algebra.monic(&poly)) {
+                return Err(Stop::Infeasible);
+            }
+            // a condition c * b + d on an atom b that is 0 or 1 fixes b when
+            // one of its values makes it 0
+            if let Some((coefficient, atom, constant)) = poly.as_linear_atom() {
+                if self.algebra.is_boolean(atom) {
+                    let one = self.algebra.constant(&BigInt::one());
+                    let atom_poly = self.algebra.atom_poly(atom);
+                    if constant.is_zero() {
+                        return self.assume(self.algebra.sub(&atom_poly, &one), false);
+                    }
+                    if self.algebra.add(&self.algebra.constant(&coefficient), &self.algebra.constant(&constant)).is_zero() {
+                        return self.assume(atom_poly, false);
+                    }
+                    return Ok(());
+                }
+            }
+            let monic = self.algebra.monic(&poly);
+            if !self.state.facts.nonzero.contains(&monic) {
+                self.state.facts.nonzero.push(monic);
+            }
+            return Ok(());
+        }
+        if self.algebra.is_nonzero(&poly, &self.state.facts.nonzero) {
+            return Err(Stop::Infeasible);
+        }
+        match self.solve(&poly) {
+            Some((atom, value)) => self.bind(atom, value),
+            None => {
+                let monic = self.algebra.monic(&poly);
+                if !self.state.facts.zero.contains(&monic) {
+                    self.state.facts.zero.push(monic);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // an atom of the polynomial that appears only in a product with a
+    // constant, and the value that makes the polynomial 0. The atoms that
+    // are 0 or 1 only get constants, and no atom gets a value that depends
+    // on it
+    fn solve(&mut self, poly: &Poly) -> Option<(AtomId, Poly)> {
+        for atom in poly.atoms().into_iter().rev() {
+            let (coefficient, rest) = match poly.linear_coefficient(atom) {
+                Some(parts) => parts,
+                None => continue,
+            };
+            let value = match coefficient.as_constant() {
+                Some(value) => value,
+                None => continue,
+            };
+            let inverse = self.algebra.inverse(&self.algebra.constant(&value)).ok()?;
+            let solution = self.algebra.neg(&self.algebra.mul(&rest, &inverse));
+            let is_boolean = self.algebra.is_boolean(atom);
+            if (is_boolean && solution.as_constant().is_none()) || self.algebra.depends_on(&solution, atom) {
+                continue;
+            }
+            return Some((atom, solution));
+        }
+        None
+    }
+
+    // Adds the value of the atom to the facts, with what it implies on the
+    // operands of the comparisons and the logical operations
+    fn bind(&mut self, atom: AtomId, value: Poly) -> Execution<()> {
+        let constant = value.as_constant();
+        if self.algebra.is_boolean(atom) && !constant.as_ref().is_some_and(|value| value.is_zero() || value.is_one()) {
+            return Err(Stop::Infeasible);
+        }
+        let mut binding = HashMap::new();
+        binding.insert(atom, value.clone());
+        let mut substitution = std::mem::take(&mut self.state.facts.substitution);
+        for bound in substitution.values_mut() {
+            *bound = self.algebra.substitute(bound, &binding).map_err(|_| Stop::Infeasible)?;
+        }
+        substitution.insert(atom, value);
+        self.state.facts.substitution = substitution;
+
+        let nonzero = std::mem::take(&mut self.state.facts.nonzero);
+        let zero = std::mem::take(&mut self.state.facts.zero);
+        for fact in nonzero {
+            self.assume(fact, true)?;
+        }
+        for fact in zero {
+            self.assume(fact, false)?;
+        }
+
+        let truth = match constant {
+            Some(value) => !value.is_zero(),
+            None => return Ok(()),
+        };
+        use Operator::*;
+        match self.algebra.atom(atom).clone() {
+            Atom::Operation(Eq, operands) => self.assume(self.algebra.sub(&operands[0], &operands[1]), !truth),
+            Atom::Operation(NotEq, operands) => self.assume(self.algebra.sub(&operands[0], &operands[1]), truth),
+            Atom::Operation(BoolNot, operands) => self.assume(operands[0].clone(), !truth),
+            Atom::Operation(BoolAnd, operands) if truth => {
+                self.assume(operands[0].clone(), true)?;
+                self.assume(operands[1].clone(), true)
+            }
+            Atom::Operation(BoolOr, operands) if !truth => {
+                self.assume(operands[0].clone(), false)?;
+                self.assume(operands[1].clone(), false)
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,316 @@
+mod divergences;
+mod information_flow;
+mod interpreter;
+mod overflows;
+mod polynomial;
+
+use circom_algebra::num_bigint::BigInt;
+use circom_algebra::num_traits::{Signed, Zero};
+use compiler::circuit_design::circuit::Circuit;
+use dag::DAG;
+use divergences::Divergence;
+use interpreter::{Limits, PathState};
+use polynomial::Algebra;
+use program_structure::constants::UsefulConstants;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::file_definition::{FileID, FileLibrary};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+pub use information_flow::{information_flows, Flow};
+pub use overflows::{Overflow, OverflowKind};
+
+pub struct SymexConfig {
+    pub max_paths: usize,
+    pub max_steps: usize,
+}
+
+// A path of the witness generation of a template, with the conditions on
+// its inputs of the branches it takes, the expressions that it computes for
+// the outputs and the ways they diverge from the constraints
+pub struct SymbolicPath {
+    pub conditions: Vec<String>,
+    pub witness: Vec<(String, String)>,
+    pub unimplied: Vec<(String, String)>,
+    pub violated: Vec<usize>,
+}
+
+pub struct SymbolicTemplate {
+    pub node: usize,
+    pub name: String,
+    pub paths: Vec<SymbolicPath>,
+    // why the paths are not complete, if they are not
+    pub stopped: Option<String>,
+    // the signals that the value computed for each signal depends on in any
+    // of the paths, with the ones of the conditions of the path
+    pub dependencies: HashMap<usize, BTreeSet<usize>>,
+    // the operations of the integer code that may not compute the integers
+    pub overflows: Vec<Overflow>,
+}
+
+// Runs the code of each template of the circuit, given the IR of its
+// witness generation without optimizations, with symbolic values for its
+// inputs and the outputs of its subcomponents, whose ranges are the ones
+// that the constraints give them
+pub fn execute_circuit(circuit: &Circuit, dag: &DAG, config: &SymexConfig) -> Vec<SymbolicTemplate> {
+    let field = UsefulConstants::new(&dag.prime).get_p().clone();
+    let limits = Limits { max_paths: config.max_paths, max_steps: config.max_steps };
+    let no_positions = HashMap::new();
+    let no_ranges = HashMap::new();
+    let ranges = dag.signal_ranges();
+    let mut templates = Vec::new();
+    for template in &circuit.templates {
+        let node = match dag.get_node(template.id) {
+            Some(node) if !node.is_custom_gate() => node,
+            _ => continue,
+        };
+        let memory_positions = if template.id == dag.main_id() { &dag.memory_positions } else { &no_positions };
+        let node_ranges = ranges.get(&template.id).unwrap_or(&no_ranges);
+        let mut execution = interpreter::execute_template(circuit, template, memory_positions, node_ranges, &field, &limits);
+        let names = signal_names(node.correspondence());
+        let name = |signal: usize| names.get(&signal).cloned().unwrap_or_else(|| format!("signal {}", signal));
+        let mut paths = Vec::new();
+        let mut dependencies: HashMap<usize, BTreeSet<usize>> = HashMap::new();
+        for path in &execution.paths {
+            let mut conditions = BTreeSet::new();
+            for (condition, _) in &path.conditions {
+                conditions.extend(execution.algebra.signals(condition));
+            }
+            for (signal, value) in &path.signals {
+                let entry = dependencies.entry(*signal).or_default();
+                entry.extend(execution.algebra.signals(value));
+                entry.extend(conditions.iter().cloned());
+            }
+            let found = divergences::divergences(&mut execution.algebra, path, dag, template.id);
+            paths.push(describe_path(&mut execution.algebra, path, node.number_of_outputs(), found, &name));
+        }
+        templates.push(SymbolicTemplate {
+            node: template.id,
+            name: node.template_name().to_string(),
+            paths,
+            stopped: execution.stopped,
+            dependencies,
+            overflows: execution.overflows,
+        });
+    }
+    templates
+}
+
+// the shortest name of each signal, as the first one of its arrays
+fn signal_names(correspondence: &HashMap<String, usize>) -> HashMap<usize, String> {
+    let mut names: HashMap<usize, String> = HashMap::new();
+    for (name, signal) in correspondence {
+        let is_better = names.get(signal).is_none_or(|current| (name.len(), name) < (current.len(), current));
+        if is_better {
+            names.insert(*signal, name.clone());
+        }
+    }
+    names
+}
+
+fn describe_path(
+    algebra: &mut Algebra,
+    path: &PathState,
+    outputs: usize,
+    found: Vec<Divergence>,
+    name: &dyn Fn(usize) -> String,
+) -> SymbolicPath {
+    let conditions = path
+        .conditions
+        .iter()
+        .map(|(condition, holds)| algebra.render_condition(condition, *holds, name))
+        .collect();
+    let mut value = |signal: usize| {
+        let computed = path.signals.get(&signal).cloned().unwrap_or_else(|| algebra.signal(signal));
+        let computed = algebra.substitute(&computed, &path.facts.substitution).unwrap_or(computed);
+        algebra.render(&computed, name)
+    };
+    let witness = (1..outputs + 1).map(|signal| (name(signal), value(signal))).collect();
+    let mut unimplied = Vec::new();
+    let mut violated = Vec::new();
+    for divergence in found {
+        match divergence {
+            Divergence::Unimplied(signal) => unimplied.push((name(signal), value(signal))),
+            Divergence::Violated(constraint) => violated.push(constraint),
+        }
+    }
+    SymbolicPath { conditions, witness, unimplied, violated }
+}
+
+impl SymbolicPath {
+    fn conditions_note(&self) -> Option<String> {
+        if self.conditions.is_empty() {
+            None
+        } else {
+            Some(format!("It happens when {}.", self.conditions.join(", ")))
+        }
+    }
+}
+
+// The reports of the divergences of the paths, once for each template
+pub fn divergence_reports(templates: &[SymbolicTemplate], dag: &DAG) -> ReportCollection {
+    let mut reports = ReportCollection::new();
+    let mut reported = HashSet::new();
+    for template in templates {
+        for path in &template.paths {
+            for (signal, value) in &path.unimplied {
+                if !reported.insert((template.name.clone(), signal.clone(), Option::None)) {
+                    continue;
+                }
+                let msg = format!(
+                    "In template \"{}\": the witness computes {} for the signal {}, but the constraints do not force this value, so a prover can give it others",
+                    template.name, value, signal
+                );
+                let mut report = Report::warning(msg, ReportCode::UnimpliedWitnessValue);
+                if let Some(note) = path.conditions_note() {
+                    report.add_note(note);
+                }
+                reports.push(report);
+            }
+            for constraint in &path.violated {
+                if !reported.insert((template.name.clone(), String::new(), Some(*constraint))) {
+                    continue;
+                }
+                let msg = format!(
+                    "In template \"{}\": the values that the witness computes do not satisfy a constraint, so the witness generation fails",
+                    template.name
+                );
+                let mut report = Report::warning(msg, ReportCode::ViolatedWitnessValue);
+                if let Some((file_id, location)) = &dag.nodes[template.node].constraint_origins()[*constraint] {
+                    report.add_primary(location.clone(), *file_id, "this constraint is not satisfied".to_string());
+                }
+                if let Some(note) = path.conditions_note() {
+                    report.add_note(note);
+                }
+                reports.push(report);
+            }
+        }
+    }
+    reports
+}
+
+// The reports of the overflows of the integer code, once for each place of
+// the code, given the files of the templates and the functions by their
+// names
+pub fn overflow_reports(
+    templates: &[SymbolicTemplate],
+    dag: &DAG,
+    files: &FileLibrary,
+    definitions: &HashMap<String, FileID>,
+) -> ReportCollection {
+    let field = UsefulConstants::new(&dag.prime).get_p().clone();
+    let mut reports = ReportCollection::new();
+    let mut reported: Vec<&Overflow> = Vec::new();
+    for template in templates {
+        let names = signal_names(dag.nodes[template.node].correspondence());
+        let name = |signal: &usize| names.get(signal).cloned().unwrap_or_else(|| format!("signal {}", signal));
+        for overflow in &template.overflows {
+            if reported.iter().any(|found| found.is_at(overflow)) {
+                continue;
+            }
+            reported.push(overflow);
+            let place = if overflow.is_function {
+                format!("In function \"{}\" called by template \"{}\"", overflow.definition, template.name)
+            } else {
+                format!("In template \"{}\"", template.name)
+            };
+            let operator = overflow.operator;
+            let (msg, label) = match &overflow.kind {
+                OverflowKind::Negative => (
+                    format!("{}: the operand of {} can be negative, and {} takes it as the prime minus its absolute value instead of as a negative integer", place, operator, operator),
+                    "may be negative",
+                ),
+                OverflowKind::ExceedsField => (
+                    format!("{}: the operand of {} can exceed the prime, and {} takes its remainder modulo the prime instead of the integer", place, operator, operator),
+                    "may exceed the prime",
+                ),
+                OverflowKind::ExceedsHalf => (
+                    format!("{}: the operands of {} can be further from 0 than half the prime, and the comparison takes their values modulo the prime, between -p/2 and p/2, instead of the integers", place, operator),
+                    "may exceed half the prime",
+                ),
+                OverflowKind::FieldDivision(divisor) => (
+                    format!("{}: the division by {} is in the field, so it only gives the integer quotient when the value is a multiple of {}, and \\ computes the integer division", place, divisor, divisor),
+                    "division in the field",
+                ),
+                OverflowKind::ZeroDivisor => (
+                    format!("{}: the divisor of {} can be 0, and then the witness generation fails", place, operator),
+                    "divisor may be 0",
+                ),
+            };
+            let code = if overflow.kind == OverflowKind::ZeroDivisor { ReportCode::PossiblyZeroDivisor } else { ReportCode::WitnessOverflow };
+            let mut report = Report::warning(msg, code);
+            let location = definitions
+                .get(&overflow.definition)
+                .and_then(|file_id| files.get_line_location(overflow.line, *file_id).map(|location| (*file_id, location)));
+            if let Some((file_id, location)) = location {
+                report.add_primary(location, file_id, label.to_string());
+            }
+            let bounded: Vec<String> = overflow
+                .signals
+                .iter()
+                .filter_map(|(signal, range)| range.as_ref().map(|(min, max)| format!("{} in [{}, {}]", name(signal), render_bound(min, &field), render_bound(max, &field))))
+                .collect();
+            let unknown: Vec<String> = overflow.signals.iter().filter(|(_, range)| range.is_none()).map(|(signal, _)| name(signal)).collect();
+            let operand = if overflow.kind == OverflowKind::ZeroDivisor { "divisor" } else { "operand" };
+            let bounds = format!(
+                "The {} takes the integers between {} and {}",
+                operand,
+                render_bound(&overflow.min, &field),
+                render_bound(&overflow.max, &field)
+            );
+            if bounded.is_empty() {
+                report.add_note(format!("{}.", bounds));
+            } else {
+                report.add_note(format!("{}, given the ranges {}.", bounds, bounded.join(", ")));
+            }
+            if !unknown.is_empty() {
+                report.add_note(format!(
+                    "The ranges of {} are not known: bound them with a bit decomposition or the tag maxbit.",
+                    unknown.join(", ")
+                ));
+            }
+            if overflow.kind == OverflowKind::ZeroDivisor {
+                report.add_note(
+                    "Divide only when the divisor is not 0, as IsZero does with inv <-- in != 0 ? 1 / in : 0, and constrain the result so that the 0 case is also checked."
+                        .to_string(),
+                );
+            }
+            reports.push(report);
+        }
+    }
+    reports
+}
+
+// the integer as a multiple of the prime and a small integer, when it is
+// close to one, as p - 1
+fn render_bound(value: &BigInt, field: &BigInt) -> String {
+    let small = BigInt::from(u64::MAX);
+    if value.abs() <= small {
+        return value.to_string();
+    }
+    let half: BigInt = field / 2;
+    let mut multiple = value / field;
+    let mut rest = value - &multiple * field;
+    if rest > half {
+        multiple += 1;
+        rest -= field;
+    } else if rest < -half {
+        multiple -= 1;
+        rest += field;
+    }
+    if rest.abs() > small {
+        return value.to_string();
+    }
+    let multiple = match multiple.to_string().as_str() {
+        "1" => "p".to_string(),
+        "-1" => "-p".to_string(),
+        text => format!("{}p", text),
+    };
+    if rest.is_zero() {
+        multiple
+    } else if rest > BigInt::zero() {
+        format!("{} + {}", multiple, rest)
+    } else {
+        format!("{} - {}", multiple, -rest)
+    }
+}
@@ -0,0 +1,253 @@
+use super::polynomial::{Algebra, Atom, AtomId, Operator, Poly};
+use circom_algebra::num_bigint::BigInt;
+use circom_algebra::num_traits::{One, Signed, Zero};
+use circom_algebra::range_analysis::Range;
+use compiler::intermediate_representation::ir_interface::OperatorType;
+use std::collections::HashMap;
+
+// The largest shift whose result is bounded, beyond which a shift to the
+// right gives 0
+const MAX_SHIFT: usize = 1024;
+
+// The ways the operations of the language on integers can compute a value
+// other than the one of the integers that the code is written for
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum OverflowKind {
+    // an operand of \, %, the shifts or the bitwise operations can be
+    // negative, when they take the value between 0 and the prime
+    Negative,
+    // or greater than the prime
+    ExceedsField,
+    // an operand of a comparison can be further from 0 than half the prime
+    ExceedsHalf,
+    // a division in the field of an integer by the constant
+    FieldDivision(BigInt),
+    // a divisor that depends on the signals and can be 0
+    ZeroDivisor,
+}
+
+// An operation of the witness whose operand may not be the integer that it
+// computes, with the integers it takes given the ranges of its signals
+pub struct Overflow {
+    pub kind: OverflowKind,
+    pub operator: &'static str,
+    // the template or the function where it is, and its line
+    pub definition: String,
+    pub is_function: bool,
+    pub line: usize,
+    pub min: BigInt,
+    pub max: BigInt,
+    // the signals of the operand, with their ranges when they are known
+    pub signals: Vec<(usize, Option<(BigInt, BigInt)>)>,
+}
+
+impl Overflow {
+    pub fn is_at(&self, other: &Overflow) -> bool {
+        self.kind == other.kind
+            && self.operator == other.operator
+            && self.definition == other.definition
+            && self.line == other.line
+    }
+}
+
+// The overflow of the operation on its operands, if any. Only the operands
+// that are computed by the operations of the field are checked, since the
+// rest, as the constants and the signals, are the values that the code
+// gets. The integer that an operand computes follows from the ranges of
+// its signals and the coefficients of its polynomial taken as the integers
+// closest to 0
+pub fn find(algebra: &Algebra, ranges: &HashMap<usize, Range>, operator: &OperatorType, operands: &[Poly]) -> Option<Overflow> {
+    use OperatorType::*;
+    let (symbol, checked, is_comparison) = match operator {
+        IntDiv => ("\\", 2, false),
+        Mod => ("%", 2, false),
+        BitAnd => ("&", 2, false),
+        BitOr => ("|", 2, false),
+        BitXor => ("^", 2, false),
+        ShiftL => ("<<", 1, false),
+        ShiftR => (">>", 1, false),
+        Complement => ("~", 1, false),
+        Lesser => ("<", 2, true),
+        LesserEq => ("<=", 2, true),
+        Greater => (">", 2, true),
+        GreaterEq => (">=", 2, true),
+        Div => return field_division(algebra, ranges, operands),
+        _ => return None,
+    };
+    let field = &algebra.field;
+    let half = (field - BigInt::one()) / 2;
+    for operand in operands.iter().take(checked) {
+        let is_value = match operand.as_linear_atom() {
+            Some((coefficient, _, constant)) => coefficient.is_one() && constant.is_zero(),
+            None => false,
+        };
+        if operand.as_constant().is_some() || is_value {
+            continue;
+        }
+        let (min, max) = interval(algebra, ranges, operand, &mut HashMap::new());
+        let kind = if is_comparison {
+            if min >= -&half && max <= half {
+                continue;
+            }
+            OverflowKind::ExceedsHalf
+        } else if min < BigInt::zero() {
+            OverflowKind::Negative
+        } else if max >= *field {
+            OverflowKind::ExceedsField
+        } else {
+            continue;
+        };
+        return Some(overflow(kind, symbol, algebra, ranges, operand, min, max));
+    }
+    None
+}
+
+// The division by the divisor, when it depends on the signals and its
+// integers, given the ranges of its signals, contain a multiple of the
+// prime. The divisors that the conditions of the path make nonzero are
+// left out by the caller
+pub fn zero_divisor(algebra: &Algebra, ranges: &HashMap<usize, Range>, operator: &OperatorType, divisor: &Poly) -> Option<Overflow> {
+    use OperatorType::*;
+    let symbol = match operator {
+        Div => "/",
+        IntDiv => "\\",
+        Mod => "%",
+        _ => return None,
+    };
+    if divisor.as_constant().is_some() || algebra.signals(divisor).is_empty() {
+        return None;
+    }
+    let field = &algebra.field;
+    let (min, max) = interval(algebra, ranges, divisor, &mut HashMap::new());
+    let is_excluded = (min > BigInt::zero() && max < *field) || (max < BigInt::zero() && min > -field);
+    if is_excluded {
+        return None;
+    }
+    Some(overflow(OverflowKind::ZeroDivisor, symbol, algebra, ranges, divisor, min, max))
+}
+
+// a division by a constant of an operand whose signals have known ranges,
+// which are integers
+fn field_division(algebra: &Algebra, ranges: &HashMap<usize, Range>, operands: &[Poly]) -> Option<Overflow> {
+    let divisor = signed(&operands.get(1)?.as_constant()?, &algebra.field);
+    let dividend = operands.first()?;
+    let signals = algebra.signals(dividend);
+    if divisor.abs() <= BigInt::one() || signals.is_empty() || !signals.iter().all(|signal| ranges.contains_key(signal)) {
+        return None;
+    }
+    let (min, max) = interval(algebra, ranges, dividend, &mut HashMap::new());
+    Some(overflow(OverflowKind::FieldDivision(divisor), "/", algebra, ranges, dividend, min, max))
+}
+
+fn overflow(
+    kind: OverflowKind,
+    operator: &'static str,
+    algebra: &Algebra,
+    ranges: &HashMap<usize, Range>,
+    operand: &Poly,
+    min: BigInt,
+    max: BigInt,
+) -> Overflow {
+    let signals = algebra
+        .signals(operand)
+        .into_iter()
+        .map(|signal| (signal, ranges.get(&signal).map(|range| (range.min.clone(), range.max.clone()))))
+        .collect();
+    Overflow { kind, operator, definition: String::new(), is_function: false, line: 0, min, max, signals }
+}
+
+// the representative of the value between -p/2 and p/2
+fn signed(value: &BigInt, field: &BigInt) -> BigInt {
+    if *value > field / 2 {
+        value - field
+    } else {
+        value.clone()
+    }
+}
+
+fn multiply(a: &(BigInt, BigInt), b: &(BigInt, BigInt)) -> (BigInt, BigInt) {
+    let products = [&a.0 * &b.0, &a.0 * &b.1, &a.1 * &b.0, &a.1 * &b.1];
+    (products.iter().min().unwrap().clone(), products.iter().max().unwrap().clone())
+}
+
+// The integers that the polynomial takes
+fn interval(
+    algebra: &Algebra,
+    ranges: &HashMap<usize, Range>,
+    poly: &Poly,
+    cache: &mut HashMap<AtomId, (BigInt, BigInt)>,
+) -> (BigInt, BigInt) {
+    let mut result = (BigInt::zero(), BigInt::zero());
+    for (atoms, coefficient) in poly.terms() {
+        let coefficient = signed(coefficient, &algebra.field);
+        let mut product = (coefficient.clone(), coefficient);
+        for (atom, exponent) in atoms {
+            // the inverses take any value of the field
+            let factor = if *exponent < 0 {
+                (BigInt::zero(), &algebra.field - BigInt::one())
+            } else {
+                atom_interval(algebra, ranges, *atom, cache)
+            };
+            for _ in 0..exponent.unsigned_abs() {
+                product = multiply(&product, &factor);
+            }
+        }
+        result = (result.0 + product.0, result.1 + product.1);
+    }
+    result
+}
+
+// The values of the atom, between 0 and the prime
+fn atom_interval(
+    algebra: &Algebra,
+    ranges: &HashMap<usize, Range>,
+    atom: AtomId,
+    cache: &mut HashMap<AtomId, (BigInt, BigInt)>,
+) -> (BigInt, BigInt) {
+    if let Some(bounds) = cache.get(&atom) {
+        return bounds.clone();
+    }
+    use Operator::*;
+    let field = &algebra.field;
+    let full = (BigInt::zero(), field - BigInt::one());
+    let within = |(min, max): &(BigInt, BigInt)| *min >= BigInt::zero() && max < field;
+    let bounds = match algebra.atom(atom) {
+        Atom::Signal(signal) => ranges.get(signal).map_or(full, |range| (range.min.clone(), range.max.clone())),
+        Atom::Operation(Lesser | LesserEq | Greater | GreaterEq | Eq | NotEq | BoolOr | BoolAnd | BoolNot, _) => {
+            (BigInt::zero(), BigInt::one())
+        }
+        Atom::Operation(operator, operands) if operands.len() == 2 => {
+            let left = interval(algebra, ranges, &operands[0], cache);
+            let right = interval(algebra, ranges, &operands[1], cache);
+            let shift = operands[1].as_usize();
+            match operator {
+                IntDiv if within(&left) && within(&right) && right.0 > BigInt::zero() => (&left.0 / &right.1, &left.1 / &right.0),
+                Mod if within(&right) && right.0 > BigInt::zero() => {
+                    let max = &right.1 - BigInt::one();
+                    (BigInt::zero(), if within(&left) { std::cmp::min(max, left.1) } else { max })
+                }
+                ShiftR if within(&left) => match shift {
+                    Some(shift) if shift <= MAX_SHIFT => (left.0 >> shift, left.1 >> shift),
+                    Some(_) => (BigInt::zero(), BigInt::zero()),
+                    None => full,
+                },
+                ShiftL if within(&left) => match shift {
+                    Some(shift) if shift <= MAX_SHIFT && (&left.1 << shift) < *field => (left.0 << shift, left.1 << shift),
+                    _ => full,
+                },
+                BitAnd if within(&left) || within(&right) => {
+                    let max = [&left, &right].iter().filter(|bounds| within(bounds)).map(|bounds| bounds.1.clone()).min().unwrap();
+                    (BigInt::zero(), max)
+                }
+                BitOr | BitXor if within(&left) && within(&right) => {
+                    let bits = std::cmp::max(&left.1, &right.1).bits();
+                    (BigInt::zero(), std::cmp::min((BigInt::one() << bits) - BigInt::one(), full.1.clone()))
+                }
+                _ => full,
+            }
+        }
+        _ => full,
+    };
+    cache.insert(atom, bounds.clone());
+    bounds
+}
@@ -0,0 +1,597 @@
+use circom_algebra::modular_arithmetic::{self, ArithmeticError};
+use circom_algebra::num_bigint::BigInt;
+use circom_algebra::num_traits::{One, ToPrimitive, Zero};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+pub type AtomId = usize;
+
+// The largest exponent of a power of a symbolic value that is expanded
+const MAX_EXPANDED_EXPONENT: usize = 256;
+
+// The operations of the witness that are not polynomials, kept as atoms
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Operator {
+    Inverse,
+    IntDiv,
+    Mod,
+    Pow,
+    ShiftL,
+    ShiftR,
+    Lesser,
+    LesserEq,
+    Greater,
+    GreaterEq,
+    Eq,
+    NotEq,
+    BoolOr,
+    BoolAnd,
+    BoolNot,
+    BitOr,
+    BitAnd,
+    BitXor,
+    Complement,
+    // the value of the given position of the result of a function given by
+    // the host
+    Extern(String, usize),
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum Atom {
+    // the value given to a signal that is not computed by the template, as
+    // its inputs and the outputs of its subcomponents
+    Signal(usize),
+    Operation(Operator, Vec<Poly>),
+}
+
+// The atoms of a product with their exponents, that are negative for the
+// inverses of the atoms
+#[derive(Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Monomial(Vec<(AtomId, i32)>);
+
+// A polynomial over the field whose variables are atoms
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct Poly {
+    terms: BTreeMap<Monomial, BigInt>,
+}
+
+impl Poly {
+    pub fn is_zero(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn as_constant(&self) -> Option<BigInt> {
+        match self.terms.len() {
+            0 => Some(BigInt::zero()),
+            1 => self.terms.get(&Monomial::default()).cloned(),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        self.as_constant().and_then(|value| value.to_usize())
+    }
+
+    // The atoms of its products, without the ones inside other atoms
+    pub fn atoms(&self) -> BTreeSet<AtomId> {
+        self.terms.keys().flat_map(|monomial| monomial.0.iter().map(|(atom, _)| *atom)).collect()
+    }
+
+    // (c, atom, d) when it is c * atom + d
+    pub fn as_linear_atom(&self) -> Option<(BigInt, AtomId, BigInt)> {
+        let mut atom = None;
+        let mut constant = BigInt::zero();
+        for (monomial, coefficient) in &self.terms {
+            match monomial.0.as_slice() {
+                [] => constant = coefficient.clone(),
+                [(id, 1)] if atom.is_none() => atom = Some((coefficient.clone(), *id)),
+                _ => return None,
+            }
+        }
+        atom.map(|(coefficient, id)| (coefficient, id, constant))
+    }
+
+    // The products of atoms of the polynomial, as the atoms with their
+    // exponents, with their coefficients
+    pub fn terms(&self) -> impl Iterator<Item = (&[(AtomId, i32)], &BigInt)> {
+        self.terms.iter().map(|(monomial, coefficient)| (monomial.0.as_slice(), coefficient))
+    }
+
+    // The coefficient of the atom, when the polynomial is linear in it
+    pub fn linear_coefficient(&self, atom: AtomId) -> Option<(Poly, Poly)> {
+        let mut coefficient = Poly::default();
+        let mut rest = Poly::default();
+        for (monomial, value) in &self.terms {
+            match monomial.0.iter().position(|(id, _)| *id == atom) {
+                None => {
+                    rest.terms.insert(monomial.clone(), value.clone());
+                }
+                Some(position) if monomial.0[position].1 == 1 => {
+                    let mut factors = monomial.0.clone();
+                    factors.remove(position);
+                    coefficient.terms.insert(Monomial(factors), value.clone());
+                }
+                Some(_) => return None,
+            }
+        }
+        Some((coefficient, rest))
+    }
+}
+
+// The atoms of the polynomials of a template, with the field they are over
+pub struct Algebra {
+    pub field: BigInt,
+    atoms: Vec<Atom>,
+    ids: HashMap<Atom, AtomId>,
+}
+
+impl Algebra {
+    pub fn new(field: &BigInt) -> Algebra {
+        Algebra { field: field.clone(), atoms: Vec::new(), ids: HashMap::new() }
+    }
+
+    pub fn atom(&self, id: AtomId) -> &Atom {
+        &self.atoms[id]
+    }
+
+    fn intern(&mut self, atom: Atom) -> AtomId {
+        if let Some(id) = self.ids.get(&atom) {
+            return *id;
+        }
+        let id = self.atoms.len();
+        self.atoms.push(atom.clone());
+        self.ids.insert(atom, id);
+        id
+    }
+
+    pub fn atom_poly(&self, id: AtomId) -> Poly {
+        let mut terms = BTreeMap::new();
+        terms.insert(Monomial(vec![(id, 1)]), BigInt::one());
+        Poly { terms }
+    }
+
+    pub fn signal(&mut self, signal: usize) -> Poly {
+        let id = self.intern(Atom::Signal(signal));
+        self.atom_poly(id)
+    }
+
+    // The atoms whose only values are 0 and 1
+    pub fn is_boolean(&self, id: AtomId) -> bool {
+        use Operator::*;
+        match &self.atoms[id] {
+            Atom::Operation(Lesser | LesserEq | Greater | GreaterEq | Eq | NotEq | BoolOr | BoolAnd | BoolNot, _) => true,
+            Atom::Operation(BitAnd, operands) => operands.iter().any(|operand| operand.as_constant() == Some(BigInt::one())),
+            _ => false,
+        }
+    }
+
+    fn boolean_atom(&self, poly: &Poly) -> Option<AtomId> {
+        match poly.as_linear_atom() {
+            Some((coefficient, id, constant)) if coefficient.is_one() && constant.is_zero() && self.is_boolean(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    pub fn constant(&self, value: &BigInt) -> Poly {
+        let value = modular_arithmetic::add(value, &BigInt::zero(), &self.field);
+        let mut terms = BTreeMap::new();
+        if !value.is_zero() {
+            terms.insert(Monomial::default(), value);
+        }
+        Poly { terms }
+    }
+
+    pub fn add(&self, left: &Poly, right: &Poly) -> Poly {
+        let mut terms = left.terms.clone();
+        for (monomial, value) in &right.terms {
+            let sum = match terms.get(monomial) {
+                Some(current) => modular_arithmetic::add(current, value, &self.field),
+                None => value.clone(),
+            };
+            if sum.is_zero() {
+                terms.remove(monomial);
+            } else {
+                terms.insert(monomial.clone(), sum);
+            }
+        }
+        Poly { terms }
+    }
+
+    pub fn neg(&self, poly: &Poly) -> Poly {
+        let terms = poly
+            .terms
+            .iter()
+            .map(|(monomial, value)| (monomial.clone(), modular_arithmetic::prefix_sub(value, &self.field)))
+            .collect();
+        Poly { terms }
+    }
+
+    pub fn sub(&self, left: &Poly, right: &Poly) -> Poly {
+        self.add(left, &self.neg(right))
+    }
+
+    fn scale(&self, poly: &Poly, factor: &BigInt) -> Poly {
+        let mut scaled = Poly::default();
+        for (monomial, value) in &poly.terms {
+            let value = modular_arithmetic::mul(value, factor, &self.field);
+            if !value.is_zero() {
+                scaled.terms.insert(monomial.clone(), value);
+            }
+        }
+        scaled
+    }
+
+    // The product of the atoms, where x * 1/x is 1, as the witness cannot
+    // compute the inverse of 0, and x * x is x for the atoms whose values
+    // are 0 or 1
+    fn multiply_monomials(&self, left: &Monomial, right: &Monomial) -> Monomial {
+        let mut exponents: BTreeMap<AtomId, i32> = left.0.iter().cloned().collect();
+        for (id, exponent) in &right.0 {
+            *exponents.entry(*id).or_insert(0) += exponent;
+        }
+        let factors = exponents
+            .into_iter()
+            .filter(|(_, exponent)| *exponent != 0)
+            .map(|(id, exponent)| if self.is_boolean(id) { (id, 1) } else { (id, exponent) })
+            .collect();
+        Monomial(factors)
+    }
+
+    pub fn mul(&self, left: &Poly, right: &Poly) -> Poly {
+        let mut product = Poly::default();
+        for (left_monomial, left_value) in &left.terms {
+            for (right_monomial, right_value) in &right.terms {
+                let mut terms = BTreeMap::new();
+                terms.insert(
+                    self.multiply_monomials(left_monomial, right_monomial),
+                    modular_arithmetic::mul(left_value, right_value, &self.field),
+                );
+                product = self.add(&product, &Poly { terms });
+            }
+        }
+        product
+    }
+
+    pub fn inverse(&mut self, poly: &Poly) -> Result<Poly, ArithmeticError> {
+        if let Some(value) = poly.as_constant() {
+            return modular_arithmetic::div(&BigInt::one(), &value, &self.field).map(|value| self.constant(&value));
+        }
+        if poly.terms.len() == 1 {
+            let (monomial, value) = poly.terms.iter().next().unwrap();
+            let value = modular_arithmetic::div(&BigInt::one(), value, &self.field)?;
+            let factors = monomial.0.iter().map(|(id, exponent)| (*id, -exponent)).collect();
+            let mut terms = BTreeMap::new();
+            terms.insert(Monomial(factors), value);
+            return Ok(Poly { terms });
+        }
+        let id = self.intern(Atom::Operation(Operator::Inverse, vec![poly.clone()]));
+        Ok(self.atom_poly(id))
+    }
+
+    pub fn pow(&mut self, base: &Poly, exponent: &Poly) -> Result<Poly, ArithmeticError> {
+        match (base.as_constant(), exponent.as_usize()) {
+            (Some(base), _) if exponent.as_constant().is_some() => {
+                Ok(self.constant(&modular_arithmetic::pow(&base, &exponent.as_constant().unwrap(), &self.field)))
+            }
+            (_, Some(exponent)) if exponent <= MAX_EXPANDED_EXPONENT => {
+                let mut result = self.constant(&BigInt::one());
+                for _ in 0..exponent {
+                    result = self.mul(&result, base);
+                }
+                Ok(result)
+            }
+            _ => self.operate(Operator::Pow, vec![base.clone(), exponent.clone()]),
+        }
+    }
+
+    // The result of the operation, that is computed when its operands are
+    // constants
+    pub fn operate(&mut self, operator: Operator, operands: Vec<Poly>) -> Result<Poly, ArithmeticError> {
+        use modular_arithmetic::*;
+        use Operator::*;
+        let field = self.field.clone();
+        let constants: Option<Vec<BigInt>> = operands.iter().map(|operand| operand.as_constant()).collect();
+        if let (Some(values), false) = (&constants, matches!(operator, Extern(..))) {
+            let value = match (&operator, values.as_slice()) {
+                (Inverse, [x]) => div(&BigInt::one(), x, &field)?,
+                (BoolNot, [x]) => not(x, &field),
+                (Complement, [x]) => complement(x, &field),
+                (IntDiv, [x, y]) => idiv(x, y, &field)?,
+                (Mod, [x, y]) => mod_op(x, y, &field)?,
+                (Pow, [x, y]) => pow(x, y, &field),
+                (ShiftL, [x, y]) => shift_l(x, y, &field)?,
+                (ShiftR, [x, y]) => shift_r(x, y, &field)?,
+                (Lesser, [x, y]) => lesser(x, y, &field),
+                (LesserEq, [x, y]) => lesser_eq(x, y, &field),
+                (Greater, [x, y]) => greater(x, y, &field),
+                (GreaterEq, [x, y]) => greater_eq(x, y, &field),
+                (Eq, [x, y]) => eq(x, y, &field),
+                (NotEq, [x, y]) => not_eq(x, y, &field),
+                (BoolOr, [x, y]) => bool_or(x, y, &field),
+                (BoolAnd, [x, y]) => bool_and(x, y, &field),
+                (BitOr, [x, y]) => bit_or(x, y, &field),
+                (BitAnd, [x, y]) => bit_and(x, y, &field),
+                (BitXor, [x, y]) => bit_xor(x, y, &field),
+                _ => unreachable!(),
+            };
+            return Ok(self.constant(&value));
+        }
+        let one = self.constant(&BigInt::one());
+        let zero = Poly::default();
+        match (&operator, operands.as_slice()) {
+            (Eq | NotEq, [x, y]) => {
+                if let Some(difference) = self.sub(x, y).as_constant() {
+                    let equal = difference.is_zero() == (operator == Eq);
+                    return Ok(if equal { one } else { zero });
+                }
+            }
+            (BoolNot, [x]) if self.boolean_atom(x).is_some() => {
+                return Ok(self.sub(&one, x));
+            }
+            (BoolAnd | BoolOr, [x, y]) => {
+                let (constant, other) = match (x.as_constant(), y.as_constant()) {
+                    (Some(value), _) => (Some(value), y),
+                    (_, Some(value)) => (Some(value), x),
+                    _ => (None, x),
+                };
+                if let Some(value) = constant {
+                    let absorbs = value.is_zero() == (operator == BoolAnd);
+                    if absorbs {
+                        return Ok(if operator == BoolAnd { zero } else { one });
+                    }
+                    if self.boolean_atom(other).is_some() {
+                        return Ok(other.clone());
+                    }
+                    return self.operate(NotEq, vec![other.clone(), zero]);
+                }
+            }
+            _ => {}
+        }
+        let id = self.intern(Atom::Operation(operator, operands));
+        Ok(self.atom_poly(id))
+    }
+
+    // The polynomial with the values given to some atoms, computing again the
+    // operations whose operands change
+    pub fn substitute(&mut self, poly: &Poly, values: &HashMap<AtomId, Poly>) -> Result<Poly, ArithmeticError> {
+        let mut cache = HashMap::new();
+        self.substitute_cached(poly, values, &mut cache)
+    }
+
+    fn substitute_cached(
+        &mut self,
+        poly: &Poly,
+        values: &HashMap<AtomId, Poly>,
+        cache: &mut HashMap<AtomId, Poly>,
+    ) -> Result<Poly, ArithmeticError> {
+        let mut result = Poly::default();
+        for (monomial, coefficient) in &poly.terms {
+            let mut term = self.constant(coefficient);
+            for (id, exponent) in &monomial.0 {
+                let value = self.substitute_atom(*id, values, cache)?;
+                let factor = if *exponent < 0 { self.inverse(&value)? } else { value };
+                for _ in 0..exponent.unsigned_abs() {
+                    term = self.mul(&term, &factor);
+                }
+            }
+            result = self.add(&result, &term);
+        }
+        Ok(result)
+    }
+
+    fn substitute_atom(
+        &mut self,
+        id: AtomId,
+        values: &HashMap<AtomId, Poly>,
+        cache: &mut HashMap<AtomId, Poly>,
+    ) -> Result<Poly, ArithmeticError> {
+        if let Some(value) = values.get(&id).or_else(|| cache.get(&id)) {
+            return Ok(value.clone());
+        }
+        let value = match self.atoms[id].clone() {
+            Atom::Signal(_) => self.atom_poly(id),
+            Atom::Operation(operator, operands) => {
+                let mut substituted = Vec::new();
+                for operand in &operands {
+                    substituted.push(self.substitute_cached(operand, values, cache)?);
+                }
+                if substituted == operands {
+                    self.atom_poly(id)
+                } else if operator == Operator::Inverse {
+                    self.inverse(&substituted[0])?
+                } else if operator == Operator::Pow {
+                    self.pow(&substituted[0], &substituted[1])?
+                } else {
+                    self.operate(operator, substituted)?
+                }
+            }
+        };
+        cache.insert(id, value.clone());
+        Ok(value)
+    }
+
+    // The polynomial divided by its coefficient of its first product, so
+    // that the multiples of a polynomial are the same
+    pub fn monic(&self, poly: &Poly) -> Poly {
+        match poly.terms.iter().next_back() {
+            Some((_, value)) => {
+                let inverse = modular_arithmetic::div(&BigInt::one(), value, &self.field).ok().unwrap();
+                self.scale(poly, &inverse)
+            }
+            None => Poly::default(),
+        }
+    }
+
+    // Whether the polynomial cannot be 0 when the facts are not 0
+    pub fn is_nonzero(&self, poly: &Poly, facts: &[Poly]) -> bool {
+        if let Some(value) = poly.as_constant() {
+            return !value.is_zero();
+        }
+        if facts.contains(&self.monic(poly)) {
+            return true;
+        }
+        // a product of atoms that are not 0
+        match poly.terms.iter().next() {
+            Some((monomial, _)) if poly.terms.len() == 1 => monomial.0.iter().all(|(id, exponent)| {
+                *exponent < 0 || matches!(self.atoms[*id], Atom::Operation(Operator::Inverse, _)) || facts.contains(&self.atom_poly(*id))
+            }),
+            _ => false,
+        }
+    }
+
+    // The signals of the atoms of the polynomial, with the ones inside other
+    // atoms
+    pub fn signals(&self, poly: &Poly) -> BTreeSet<usize> {
+        let mut signals = BTreeSet::new();
+        let mut pending: Vec<AtomId> = poly.atoms().into_iter().collect();
+        while let Some(id) = pending.pop() {
+            match &self.atoms[id] {
+                Atom::Signal(signal) => {
+                    signals.insert(*signal);
+                }
+                Atom::Operation(_, operands) => pending.extend(operands.iter().flat_map(|operand| operand.atoms())),
+            }
+        }
+        signals
+    }
+
+    // Whether the atom is in the polynomial, or inside one of its atoms
+    pub fn depends_on(&self, poly: &Poly, atom: AtomId) -> bool {
+        let mut pending: Vec<AtomId> = poly.atoms().into_iter().collect();
+        while let Some(id) = pending.pop() {
+            if id == atom {
+                return true;
+            }
+            if let Atom::Operation(_, operands) = &self.atoms[id] {
+                pending.extend(operands.iter().flat_map(|operand| operand.atoms()));
+            }
+        }
+        false
+    }
+
+    // The coefficients of the atoms of a linear polynomial, and its constant
+    pub fn linear_terms(&self, poly: &Poly) -> Option<(Vec<(AtomId, BigInt)>, BigInt)> {
+        let mut terms = Vec::new();
+        let mut constant = BigInt::zero();
+        for (monomial, value) in &poly.terms {
+            match monomial.0.as_slice() {
+                [] => constant = value.clone(),
+                [(id, 1)] => terms.push((*id, value.clone())),
+                _ => return None,
+            }
+        }
+        Some((terms, constant))
+    }
+
+    pub fn render(&self, poly: &Poly, names: &dyn Fn(usize) -> String) -> String {
+        if poly.is_zero() {
+            return "0".to_string();
+        }
+        let half = &self.field / 2;
+        let mut rendered = String::new();
+        for (monomial, value) in poly.terms.iter().rev() {
+            let negative = *value > half;
+            let magnitude = if negative { &self.field - value } else { value.clone() };
+            let sign = match (rendered.is_empty(), negative) {
+                (true, true) => "-",
+                (true, false) => "",
+                (false, true) => " - ",
+                (false, false) => " + ",
+            };
+            let factors = self.render_monomial(monomial, names);
+            let term = match (factors.is_empty(), magnitude.is_one()) {
+                (true, _) => magnitude.to_string(),
+                (false, true) => factors,
+                (false, false) => format!("{}*{}", magnitude, factors),
+            };
+            rendered.push_str(sign);
+            rendered.push_str(&term);
+        }
+        rendered
+    }
+
+    // The condition of a branch, that holds when the polynomial is not 0 or
+    // when it is 0
+    pub fn render_condition(&self, poly: &Poly, holds: bool, names: &dyn Fn(usize) -> String) -> String {
+        use Operator::*;
+        let id = match self.boolean_atom(poly) {
+            Some(id) => id,
+            None => return format!("{} {} 0", self.render(poly, names), if holds { "!=" } else { "==" }),
+        };
+        let rendered = self.render_atom(id, names);
+        let (operator, operands) = match &self.atoms[id] {
+            Atom::Operation(operator, operands) if !holds => (operator, operands),
+            _ => return rendered.strip_prefix('(').and_then(|inner| inner.strip_suffix(')')).unwrap_or(&rendered).to_string(),
+        };
+        let negated = match operator {
+            Eq => "!=",
+            NotEq => "==",
+            Lesser => ">=",
+            LesserEq => ">",
+            Greater => "<=",
+            GreaterEq => "<",
+            _ => return format!("!{}", rendered),
+        };
+        format!("{} {} {}", self.render_operand(&operands[0], names), negated, self.render_operand(&operands[1], names))
+    }
+
+    fn render_monomial(&self, monomial: &Monomial, names: &dyn Fn(usize) -> String) -> String {
+        let mut numerator = Vec::new();
+        let mut denominator = Vec::new();
+        for (id, exponent) in &monomial.0 {
+            let atom = self.render_atom(*id, names);
+            let factor = if exponent.abs() == 1 { atom } else { format!("{}^{}", atom, exponent.abs()) };
+            if *exponent < 0 {
+                denominator.push(factor);
+            } else {
+                numerator.push(factor);
+            }
+        }
+        match (numerator.is_empty(), denominator.is_empty()) {
+            (_, true) => numerator.join("*"),
+            (true, false) => format!("1/{}", denominator.join("/")),
+            (false, false) => format!("{}/{}", numerator.join("*"), denominator.join("/")),
+        }
+    }
+
+    fn render_atom(&self, id: AtomId, names: &dyn Fn(usize) -> String) -> String {
+        use Operator::*;
+        let (operator, operands) = match &self.atoms[id] {
+            Atom::Signal(signal) => return names(*signal),
+            Atom::Operation(operator, operands) => (operator, operands),
+        };
+        let operands: Vec<String> = operands.iter().map(|operand| self.render_operand(operand, names)).collect();
+        let symbol = match operator {
+            Inverse => return format!("1/{}", operands[0]),
+            BoolNot => return format!("!{}", operands[0]),
+            Complement => return format!("~{}", operands[0]),
+            Extern(name, 0) => return format!("{}({})", name, operands.join(", ")),
+            Extern(name, position) => return format!("{}({})[{}]", name, operands.join(", "), position),
+            IntDiv => "\\",
+            Mod => "%",
+            Pow => "**",
+            ShiftL => "<<",
+            ShiftR => ">>",
+            Lesser => "<",
+            LesserEq => "<=",
+            Greater => ">",
+            GreaterEq => ">=",
+            Eq => "==",
+            NotEq => "!=",
+            BoolOr => "||",
+            BoolAnd => "&&",
+            BitOr => "|",
+            BitAnd => "&",
+            BitXor => "^",
+        };
+        format!("({} {} {})", operands[0], symbol, operands[1])
+    }
+
+    fn render_operand(&self, poly: &Poly, names: &dyn Fn(usize) -> String) -> String {
+        let rendered = self.render(poly, names);
+        if poly.terms.len() > 1 || rendered.starts_with('-') {
+            format!("({})", rendered)
+        } else {
+            rendered
+        }
+    }
+}
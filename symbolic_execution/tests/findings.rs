@@ -0,0 +1,184 @@
+use compiler::compiler_interface::{self, Config, OptimizationLevel, Pass, PassConfig};
+use constraint_generation::{build_dag, BuildConfig};
+use constraint_writers::graph_writer::GraphFormat;
+use dag::DAG;
+use parser::ParserConfig;
+use program_structure::error_code::ReportCode;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use symbolic_execution::{SymexConfig, SymbolicTemplate};
+use type_analysis::check_types::check_types;
+
+const VERSION: &str = "2.2.2";
+
+fn build_config() -> BuildConfig {
+    BuildConfig {
+        no_rounds: 0,
+        function_steps: 10_000_000,
+        template_recursion: 100,
+        flag_json_sub: false,
+        json_substitutions: String::new(),
+        flag_s: false,
+        flag_f: true,
+        flag_p: false,
+        flag_verbose: false,
+        inspect_constraints: false,
+        inspect_witness: false,
+        inspect_determinism: false,
+        deny_unconstrained: false,
+        suggest_parallel: false,
+        flag_template_report: false,
+        template_report: String::new(),
+        flag_component_graph: false,
+        component_graph_format: GraphFormat::Json,
+        component_graph: String::new(),
+        flag_old_heuristics: false,
+        prime: "bn128".to_string(),
+    }
+}
+
+// The paths of the templates of the circuit, run as circom symex does
+fn execute(source: &str) -> (Vec<SymbolicTemplate>, DAG) {
+    let path = PathBuf::from("main.circom");
+    let sources = HashMap::from([(path.clone(), source.to_string())]);
+    let config = ParserConfig {
+        version: VERSION,
+        link_libraries: Vec::new(),
+        prime: "bn128",
+        flag_prime: false,
+        flag_no_init: false,
+        flag_enforce_tags: false,
+        features: &[],
+    };
+    let result = parser::run_parser_with_sources(path.display().to_string(), &config, &sources);
+    let (mut program, _) = result.unwrap_or_else(|_| panic!("the circuit does not parse"));
+    check_types(&mut program).unwrap_or_else(|_| panic!("the circuit is not well typed"));
+    let (dag, vcp) = build_dag(program, &build_config()).unwrap();
+    let config = Config {
+        debug_output: false,
+        produce_input_log: false,
+        wat_flag: false,
+        no_asm_flag: false,
+        constraint_assert_disabled_flag: false,
+        passes: PassConfig {
+            level: OptimizationLevel::O0,
+            disabled_passes: Pass::ALL.to_vec(),
+            report_timing: false,
+            report_effects: false,
+            verify: false,
+        },
+        lazy_components_flag: false,
+        trace_signals_flag: false,
+        profile_templates_flag: false,
+        ir_cache: None,
+        prime: "bn128".to_string(),
+    };
+    let circuit = compiler_interface::run_compiler(vcp, config, VERSION).unwrap();
+    let templates = symbolic_execution::execute_circuit(&circuit, &dag, &SymexConfig { max_paths: 64, max_steps: 1_000_000 });
+    (templates, dag)
+}
+
+fn divergence_codes(source: &str) -> Vec<ReportCode> {
+    let (templates, dag) = execute(source);
+    symbolic_execution::divergence_reports(&templates, &dag).iter().map(|report| *report.get_code()).collect()
+}
+
+#[test]
+fn a_signal_that_the_constraints_do_not_fix_is_reported() {
+    let codes = divergence_codes(
+        "pragma circom 2.0.0;
+
+template Bit() {
+    signal input in;
+    signal output out;
+    out <-- in > 5 ? 1 : 0;
+    out * (out - 1) === 0;
+}
+
+component main = Bit();
+",
+    );
+    assert_eq!(codes.len(), 1);
+    assert!(matches!(codes[0], ReportCode::UnimpliedWitnessValue));
+}
+
+#[test]
+fn a_circuit_whose_constraints_fix_the_witness_has_no_finding() {
+    let codes = divergence_codes(
+        "pragma circom 2.0.0;
+
+template IsZero() {
+    signal input in;
+    signal output out;
+    signal inv;
+    inv <-- in != 0 ? 1 / in : 0;
+    out <== -in * inv + 1;
+    in * out === 0;
+}
+
+component main = IsZero();
+",
+    );
+    assert!(codes.is_empty());
+}
+
+#[test]
+fn values_that_break_a_constraint_are_reported() {
+    let codes = divergence_codes(
+        "pragma circom 2.0.0;
+
+template Next() {
+    signal input in;
+    signal output out;
+    out <-- in + 1;
+    out === in;
+}
+
+component main = Next();
+",
+    );
+    assert!(codes.iter().any(|code| matches!(code, ReportCode::ViolatedWitnessValue)));
+}
+
+#[test]
+fn the_outputs_depend_on_the_private_inputs_they_are_computed_from() {
+    let (templates, dag) = execute(
+        "pragma circom 2.0.0;
+
+template Mix() {
+    signal input a;
+    signal input b;
+    signal input c;
+    signal output out;
+    out <== a * b;
+}
+
+component main {public [c]} = Mix();
+",
+    );
+    let flows = symbolic_execution::information_flows(&templates, &dag);
+    let out = flows.iter().find(|flow| flow.signal.ends_with("out")).unwrap();
+    assert!(!out.is_input);
+    assert_eq!(out.private_inputs.len(), 2);
+    assert!(out.private_inputs.iter().all(|input| input.ends_with('a') || input.ends_with('b')));
+}
+
+#[test]
+fn a_divisor_that_can_be_zero_is_reported() {
+    let (templates, _) = execute(
+        "pragma circom 2.0.0;
+
+template Divide() {
+    signal input a;
+    signal input b;
+    signal output out;
+    out <-- a \\ b;
+    out * b === a;
+}
+
+component main = Divide();
+",
+    );
+    let overflows: Vec<_> = templates.iter().flat_map(|template| &template.overflows).collect();
+    assert!(overflows.iter().any(|overflow| overflow.kind == symbolic_execution::OverflowKind::ZeroDivisor));
+}
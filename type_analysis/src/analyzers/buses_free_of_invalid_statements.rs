@@ -1,4 +1,5 @@
 use program_structure::ast::*;
+use program_structure::builtin_calls::BuiltinCall;
 use program_structure::program_library::bus_data::BusData;
 use program_structure::error_code::ReportCode;
 use program_structure::error_definition::{Report, ReportCollection};
@@ -125,7 +126,7 @@ fn analyse_expression(
         Variable { meta, access, .. } => analyse_access(access, meta, function_names, reports),
         Number(..) => {}
         Call { meta, id, args, .. } => {
-            if !function_names.contains(id) {
+            if !function_names.contains(id) && BuiltinCall::from_id(id).is_none() {
                 report_undefined_bus_error(meta, "Unknown call in bus", file_id, Some("Is not a function call"), reports)
             }
             for arg in args.iter() {
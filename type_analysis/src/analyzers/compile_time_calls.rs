@@ -0,0 +1,143 @@
+use program_structure::ast::*;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::program_archive::ProgramArchive;
+use std::collections::HashSet;
+
+// The functions whose arrays have lengths that depend on their parameters
+// have no code in the witness generation, so they can only be called where
+// the compiler evaluates the calls: in the parameters of templates and
+// buses, in the lengths of arrays, in static assertions and in the bodies of
+// other functions like them
+pub fn compile_time_calls_analysis(program_archive: &ProgramArchive) -> Result<(), ReportCollection> {
+    let functions: HashSet<_> = program_archive
+        .get_functions()
+        .values()
+        .filter(|f| f.is_compile_time())
+        .map(|f| f.get_name().to_string())
+        .collect();
+    if functions.is_empty() {
+        return Result::Ok(());
+    }
+    let compile_time = CompileTime { functions, templates: program_archive.get_template_names() };
+    let mut reports = ReportCollection::new();
+    for template in program_archive.get_templates().values() {
+        analyse_statement(template.get_body(), &compile_time, &mut reports);
+    }
+    for function in program_archive.get_functions().values().filter(|f| !f.is_compile_time()) {
+        analyse_statement(function.get_body(), &compile_time, &mut reports);
+    }
+    if reports.is_empty() {
+        Result::Ok(())
+    } else {
+        Result::Err(reports)
+    }
+}
+
+struct CompileTime<'a> {
+    functions: HashSet<String>,
+    templates: &'a HashSet<String>,
+}
+
+fn analyse_statement(stmt: &Statement, compile_time: &CompileTime, reports: &mut ReportCollection) {
+    use Statement::*;
+    match stmt {
+        IfThenElse { cond, if_case, else_case, .. } => {
+            analyse_expression(cond, compile_time, reports);
+            analyse_statement(if_case, compile_time, reports);
+            if let Option::Some(else_case) = else_case {
+                analyse_statement(else_case, compile_time, reports);
+            }
+        }
+        While { cond, stmt, .. } => {
+            analyse_expression(cond, compile_time, reports);
+            analyse_statement(stmt, compile_time, reports);
+        }
+        Block { stmts, .. } | InitializationBlock { initializations: stmts, .. } => {
+            for stmt in stmts {
+                analyse_statement(stmt, compile_time, reports);
+            }
+        }
+        Substitution { access, rhe, .. } => {
+            analyse_access(access, compile_time, reports);
+            analyse_expression(rhe, compile_time, reports);
+        }
+        MultSubstitution { lhe, rhe, .. } => {
+            analyse_expression(lhe, compile_time, reports);
+            analyse_expression(rhe, compile_time, reports);
+        }
+        ConstraintEquality { lhe, rhe, .. } => {
+            analyse_expression(lhe, compile_time, reports);
+            analyse_expression(rhe, compile_time, reports);
+        }
+        Return { value: rhe, .. } | UnderscoreSubstitution { rhe, .. } => {
+            analyse_expression(rhe, compile_time, reports);
+        }
+        Assert { arg, message, is_static: false, .. } => {
+            analyse_expression(arg, compile_time, reports);
+            analyse_log_arguments(message, compile_time, reports);
+        }
+        LogCall { args, .. } => analyse_log_arguments(args, compile_time, reports),
+        Declaration { .. } | Assert { is_static: true, .. } => {}
+    }
+}
+
+fn analyse_log_arguments(args: &[LogArgument], compile_time: &CompileTime, reports: &mut ReportCollection) {
+    for arg in args {
+        if let LogArgument::LogExp(expr) = arg {
+            analyse_expression(expr, compile_time, reports);
+        }
+    }
+}
+
+fn analyse_access(access: &[Access], compile_time: &CompileTime, reports: &mut ReportCollection) {
+    for acc in access {
+        if let Access::ArrayAccess(index) = acc {
+            analyse_expression(index, compile_time, reports);
+        }
+    }
+}
+
+fn analyse_expression(expr: &Expression, compile_time: &CompileTime, reports: &mut ReportCollection) {
+    use Expression::*;
+    match expr {
+        Call { meta, id, .. } if compile_time.functions.contains(id) => {
+            let message = format!(
+                "The lengths of the arrays of the function {} depend on its parameters, so it can only be called in the parameters of templates and buses and in the lengths of arrays",
+                id
+            );
+            let mut report = Report::error(message, ReportCode::CompileTimeFunctionCall);
+            report.add_primary(meta.location.clone(), meta.get_file_id(), "called here".to_string());
+            reports.push(report);
+        }
+        Call { id, .. } if compile_time.templates.contains(id) => {}
+        Call { args, .. } => {
+            for arg in args {
+                analyse_expression(arg, compile_time, reports);
+            }
+        }
+        AnonymousComp { signals, .. } => {
+            for signal in signals {
+                analyse_expression(signal, compile_time, reports);
+            }
+        }
+        InfixOp { lhe, rhe, .. } => {
+            analyse_expression(lhe, compile_time, reports);
+            analyse_expression(rhe, compile_time, reports);
+        }
+        PrefixOp { rhe, .. } | ParallelOp { rhe, .. } => analyse_expression(rhe, compile_time, reports),
+        InlineSwitchOp { cond, if_true, if_false, .. } => {
+            analyse_expression(cond, compile_time, reports);
+            analyse_expression(if_true, compile_time, reports);
+            analyse_expression(if_false, compile_time, reports);
+        }
+        Variable { access, .. } => analyse_access(access, compile_time, reports),
+        ArrayInLine { values, .. } | Tuple { values, .. } => {
+            for value in values {
+                analyse_expression(value, compile_time, reports);
+            }
+        }
+        UniformArray { value, .. } => analyse_expression(value, compile_time, reports),
+        Number(..) | BusCall { .. } => {}
+    }
+}
@@ -1,4 +1,5 @@
 use program_structure::ast::*;
+use program_structure::builtin_calls::BuiltinCall;
 use program_structure::error_code::ReportCode;
 use program_structure::error_definition::{Report, ReportCollection};
 use program_structure::file_definition;
@@ -109,8 +110,13 @@ fn analyse_statement(
                 }
             }
         }
-        Assert { arg, .. } => {
+        Assert { arg, message, .. } => {
             analyse_expression(arg, function_names, reports);
+            for logarg in message {
+                if let LogArgument::LogExp(arg) = logarg {
+                    analyse_expression(arg, function_names, reports);
+                }
+            }
         }
         Return { value, .. } => {
             analyse_expression(value, function_names, reports);
@@ -180,7 +186,7 @@ fn analyse_expression(
         Variable { meta, access, .. } => analyse_access(access, meta, function_names, reports),
         Number(..) => {}
         Call { meta, id, args, .. } => {
-            if !function_names.contains(id) {
+            if !function_names.contains(id) && BuiltinCall::from_id(id).is_none() {
                 let mut report = Report::error(
                     format!("Unknown call in function"),
                     ReportCode::UndefinedFunction,
@@ -1,13 +1,16 @@
 use program_structure::ast::{Access, Expression, LogArgument, Meta, Statement, VariableType};
+use program_structure::builtin_calls::BuiltinCall;
 use program_structure::error_code::ReportCode;
-use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::error_definition::{closest_name, Report, ReportCollection};
 use program_structure::file_definition::{self, FileID, FileLocation};
 use program_structure::program_archive::ProgramArchive;
 use program_structure::function_data::FunctionInfo;
 use program_structure::template_data::TemplateInfo;
 use program_structure::bus_data::BusInfo;
-use std::collections::HashSet;
-type Block = HashSet<String>;
+use program_structure::wire_data::{WireInfo, WireType};
+use std::collections::HashMap;
+// The symbols of the block, with the locations of their declarations
+type Block = HashMap<String, FileLocation>;
 type Environment = Vec<Block>;
 
 pub fn check_naming_correctness(program_archive: &ProgramArchive) -> Result<(), ReportCollection> {
@@ -43,8 +46,10 @@ pub fn check_naming_correctness(program_archive: &ProgramArchive) -> Result<(),
         );
         instances.push(instance);
     }
-    if let Err(mut r) = analyze_main(program_archive) {
-        reports.append(&mut r);
+    for (_, (signals, call)) in program_archive.get_main_components() {
+        if let Err(mut r) = analyze_main(program_archive, call, signals) {
+            reports.append(&mut r);
+        }
     }
     for (file_id, param_location, params_names, body) in instances {
         let res = analyze_symbols(
@@ -67,9 +72,27 @@ pub fn check_naming_correctness(program_archive: &ProgramArchive) -> Result<(),
     }
 }
 
-fn analyze_main(program: &ProgramArchive) -> Result<(), Vec<Report>> {
-    let call = program.get_main_expression();
-    let signals = program.get_public_inputs_main_component();
+// An input of the main component, or a field of one of its input buses, as
+// tx.nullifier, which can be an array or another bus
+fn is_public_input(path: &str, inputs: &WireInfo, bus_info: &BusInfo) -> bool {
+    let mut names = path.split('.');
+    let mut wire = match names.next().and_then(|name| inputs.get(name)) {
+        Some(wire) => wire,
+        None => return false,
+    };
+    for field in names {
+        wire = match wire.get_type() {
+            WireType::Bus(bus) => match bus_info.get(&bus).and_then(|bus| bus.get_field_info(field)) {
+                Some(wire) => wire,
+                None => return false,
+            },
+            WireType::Signal => return false,
+        };
+    }
+    true
+}
+
+fn analyze_main(program: &ProgramArchive, call: &Expression, signals: &[String]) -> Result<(), Vec<Report>> {
     let template_info = program.get_templates();
     let function_info = program.get_functions();
     let bus_info = program.get_buses();
@@ -79,7 +102,7 @@ fn analyze_main(program: &ProgramArchive) -> Result<(), Vec<Report>> {
         if program.contains_template(id) {
             let inputs = program.get_template_data(id).get_inputs();
             for signal in signals {
-                if !inputs.contains_key(signal) {
+                if !is_public_input(signal, inputs, bus_info) {
                     let mut report = Report::error(
                         format!("Invalid public list"),
                         ReportCode::SameSymbolDeclaredTwice,
@@ -87,7 +110,7 @@ fn analyze_main(program: &ProgramArchive) -> Result<(), Vec<Report>> {
                     report.add_primary(
                         call.get_meta().location.clone(),
                         call.get_meta().get_file_id(),
-                        format!("{} is not an input signal", signal),
+                        format!("{} is not an input signal or a field of an input bus", signal),
                     );
                     reports.push(report);
                 }
@@ -122,7 +145,7 @@ pub fn analyze_symbols(
     let mut environment = Environment::new();
     environment.push(Block::new());
     for param in params_names.iter() {
-        let success = add_symbol_to_block(&mut environment, param);
+        let success = add_symbol_to_block(&mut environment, param, &param_location).is_ok();
         param_name_collision = param_name_collision || !success;
     }
     if param_name_collision {
@@ -153,22 +176,38 @@ pub fn analyze_symbols(
     }
 }
 
+// The name of the code that starts at the meta, replaced by the closest one
+// that is defined
+fn add_name_suggestion<'a>(
+    report: &mut Report,
+    meta: &Meta,
+    name: &str,
+    file_id: FileID,
+    candidates: impl Iterator<Item = &'a String>,
+) {
+    if let Some(closest) = closest_name(name, candidates) {
+        let location = meta.get_start()..meta.get_start() + name.len();
+        report.add_suggestion(location, file_id, name.to_string(), closest.clone(), format!("replace {} with {}", name, closest));
+    }
+}
+
 fn symbol_in_environment(environment: &Environment, symbol: &String) -> bool {
     for block in environment.iter() {
-        if block.contains(symbol) {
+        if block.contains_key(symbol) {
             return true;
         }
     }
     false
 }
 
-fn add_symbol_to_block(environment: &mut Environment, symbol: &String) -> bool {
+// Fails with the location of the previous declaration of the symbol in the block
+fn add_symbol_to_block(environment: &mut Environment, symbol: &String, location: &FileLocation) -> Result<(), FileLocation> {
     let last_block = environment.last_mut().unwrap();
-    if last_block.contains(symbol) {
-        return false;
+    if let Some(previous) = last_block.get(symbol) {
+        return Result::Err(previous.clone());
     }
-    last_block.insert(symbol.clone());
-    true
+    last_block.insert(symbol.clone(), location.clone());
+    Result::Ok(())
 }
 
 fn analyze_statement(
@@ -231,7 +270,7 @@ fn analyze_statement(
                     environment,
                 );
             }
-            if !add_symbol_to_block(environment, name) {
+            if let Result::Err(previous) = add_symbol_to_block(environment, name, &meta.location) {
                 let mut report = Report::error(
                     format!("Symbol declared twice"),
                     ReportCode::SameSymbolDeclaredTwice,
@@ -241,6 +280,7 @@ fn analyze_statement(
                     file_id.clone(),
                     format!("Declaring same symbol twice"),
                 );
+                report.add_secondary(previous, file_id.clone(), Option::Some(format!("{} is first declared here", name)));
                 reports.push(report);
             }
             if let VariableType::Bus(b_name,_,tags ) = xtype {
@@ -269,8 +309,13 @@ fn analyze_statement(
                 }
             }
         }
-        Statement::Assert { arg, .. } => {
-            analyze_expression(arg, file_id, function_info, template_info, bus_info, reports, environment)
+        Statement::Assert { arg, message, .. } => {
+            analyze_expression(arg, file_id, function_info, template_info, bus_info, reports, environment);
+            for logarg in message {
+                if let LogArgument::LogExp(arg) = logarg {
+                    analyze_expression(arg, file_id, function_info, template_info, bus_info, reports, environment);
+                }
+            }
         }
         Statement::Block { stmts, .. } => {
             environment.push(Block::new());
@@ -327,6 +372,7 @@ fn treat_variable(
             file_id.clone(),
             format!("Using unknown symbol"),
         );
+        add_name_suggestion(&mut report, meta, name, file_id, environment.iter().flat_map(|block| block.keys()));
         reports.push(report);
     }
     for acc in access.iter() {
@@ -339,6 +385,44 @@ fn treat_variable(
     }
 }
 
+// The function given to map or fold must take the values that the call gives
+fn analyze_builtin_function(
+    builtin: BuiltinCall,
+    meta: &Meta,
+    function: &str,
+    file_id: FileID,
+    function_info: &FunctionInfo,
+    reports: &mut ReportCollection,
+) {
+    let location = file_definition::generate_file_location(meta.get_start(), meta.get_end());
+    match function_info.get(function) {
+        None => {
+            let mut report = Report::error("Calling symbol".to_string(), ReportCode::NonExistentSymbol);
+            report.add_primary(location, file_id, format!("{} is not a function", function));
+            reports.push(report);
+        }
+        Some(data) if data.get_num_of_params() != builtin.num_of_params() => {
+            let mut report = Report::error(
+                "Calling function with wrong number of arguments".to_string(),
+                ReportCode::FunctionWrongNumberOfArguments,
+            );
+            report.add_primary(
+                location,
+                file_id,
+                format!(
+                    "{} calls {} with {} arguments, but it has {} params",
+                    builtin.name(),
+                    function,
+                    builtin.num_of_params(),
+                    data.get_num_of_params()
+                ),
+            );
+            reports.push(report);
+        }
+        Some(_) => {}
+    }
+}
+
 fn analyze_expression(
     expression: &Expression,
     file_id: FileID,
@@ -391,6 +475,23 @@ fn analyze_expression(
             reports,
             environment,
         ),
+        Expression::Call { id, args, .. } if BuiltinCall::from_id(id).is_some() => {
+            let builtin = BuiltinCall::from_id(id).unwrap();
+            if let Some((meta, function)) = builtin.function(args) {
+                analyze_builtin_function(builtin, meta, function, file_id, function_info, reports);
+            }
+            for arg in args.iter().skip(if builtin.num_of_params() > 0 { 1 } else { 0 }) {
+                analyze_expression(
+                    arg,
+                    file_id,
+                    function_info,
+                    template_info,
+                    bus_info,
+                    reports,
+                    environment,
+                );
+            }
+        }
         Expression::Call { meta, id, args, .. } => {
             if !function_info.contains_key(id) && !template_info.contains_key(id) {
                 let mut report =
@@ -400,6 +501,7 @@ fn analyze_expression(
                     file_id.clone(),
                     format!("Calling unknown symbol"),
                 );
+                add_name_suggestion(&mut report, meta, id, file_id, function_info.keys().chain(template_info.keys()));
                 reports.push(report);
                 return;
             }
@@ -476,6 +578,7 @@ fn analyze_expression(
                     file_id.clone(),
                     format!("Calling unknown symbol"),
                 );
+                add_name_suggestion(&mut report, meta, id, file_id, bus_info.keys());
                 reports.push(report);
                 return;
             }
@@ -2,13 +2,16 @@ use super::type_given_function::type_given_function;
 use super::type_register::TypeRegister;
 use program_structure::ast::*;
 use program_structure::ast::Expression::Call;
+use program_structure::builtin_calls::{is_unrollable_array, unroll_builtin_call, BuiltinCall};
 use program_structure::environment::CircomEnvironment;
 use program_structure::error_code::ReportCode;
-use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::error_definition::{closest_name, Report, ReportCollection};
 use program_structure::file_definition::{generate_file_location, FileID};
 use program_structure::program_archive::ProgramArchive;
 use program_structure::wire_data::WireType;
 use std::collections::HashSet;
+use num_bigint::BigInt;
+use program_structure::expression_builders::{build_call, build_number_without_field};
 
 
 type ArithmeticType = usize;
@@ -87,14 +90,18 @@ pub fn type_check(program_archive: &ProgramArchive) -> Result<OutInfo, ReportCol
         environment: TypingEnvironment::new(),
         return_type: Option::None,
     };
-    let initial_expression = program_archive.get_main_expression();
-    let type_analysis_response =
-        type_expression(initial_expression, program_archive, &mut analysis_information);
-    if type_analysis_response.is_err(){
-        return Result::Err(analysis_information.reports);
-    }
+    // the main components are typed together, so the templates they share
+    // are typed once and the ones reached by any of them are kept
+    for (_, (_, initial_expression)) in program_archive.get_main_components() {
+        let type_analysis_response =
+            type_expression(initial_expression, program_archive, &mut analysis_information);
+        if type_analysis_response.is_err(){
+            return Result::Err(analysis_information.reports);
+        }
 
-    check_main_has_tags(initial_expression, program_archive, &mut analysis_information.reports);
+        check_main_has_tags(initial_expression, program_archive, &mut analysis_information.reports);
+    }
+    check_tag_rules(program_archive, &mut analysis_information);
 
 
     if analysis_information.reports.is_empty() {
@@ -104,6 +111,30 @@ pub fn type_check(program_archive: &ProgramArchive) -> Result<OutInfo, ReportCol
     }
 }
 
+// The functions of the tag rules are called with the values of the tags of
+// the operands, so they are typed as a call with two numbers
+fn check_tag_rules(program_archive: &ProgramArchive, analysis_information: &mut AnalysisInformation) {
+    for rules in program_archive.get_tag_rules().values() {
+        for (file_id, rule) in rules {
+            let function = match &rule.function {
+                Some(function) => function,
+                None => continue,
+            };
+            let mut meta = rule.meta.clone();
+            meta.set_file_id(*file_id);
+            let number = || build_number_without_field(meta.clone(), BigInt::from(0));
+            let call = build_call(meta.clone(), function.clone(), vec![number(), number()]);
+            analysis_information.file_id = *file_id;
+            match type_expression(&call, program_archive, analysis_information) {
+                Ok(folded) if folded.is_template() || folded.is_bus() || folded.dim() != 0 => {
+                    add_report(ReportCode::WrongTagRule, &meta, &mut analysis_information.reports)
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
 fn check_main_has_tags(initial_expression: &Expression, program_archive: &ProgramArchive, reports: &mut ReportCollection) {    if let Call { id, .. } = initial_expression {
         if program_archive.contains_template(id){
             let inputs = program_archive.get_template_data(id).get_inputs();
@@ -480,38 +511,8 @@ fn type_statement(
                 );
             }
         }
-        LogCall { args, meta } => {
-            for arglog in args {
-                if let LogArgument::LogExp(arg) = arglog{
-                    let arg_response = type_expression(&arg, program_archive, analysis_information);
-                    let arg_type = if let Result::Ok(t) = arg_response {
-                        t
-                    } else {
-                        return;
-                    };
-                    if arg_type.is_template() {
-                        add_report(
-                            ReportCode::MustBeSingleArithmeticT,
-                            meta,
-                            &mut analysis_information.reports,
-                        )
-                    } else if arg_type.is_bus() {
-                        add_report(
-                            ReportCode::MustBeSingleArithmeticB,
-                            meta,
-                            &mut analysis_information.reports,
-                        )
-                    } else if arg_type.dim() > 0 {
-                        add_report(
-                            ReportCode::MustBeSingleArithmetic(arg_type.dim()),
-                            meta,
-                            &mut analysis_information.reports,
-                        )
-                    }
-                }
-            }
-        }
-        Assert { arg, meta } => {
+        LogCall { args, meta, .. } => type_log_arguments(args, meta, program_archive, analysis_information),
+        Assert { arg, meta, message, .. } => {
             let arg_response = type_expression(arg, program_archive, analysis_information);
             let arg_type = if let Result::Ok(t) = arg_response {
                 t
@@ -537,6 +538,7 @@ fn type_statement(
                     &mut analysis_information.reports,
                 )
             }
+            type_log_arguments(message, meta, program_archive, analysis_information);
         }
         Return { value, meta } => {
             debug_assert!(analysis_information.return_type.is_some());
@@ -641,6 +643,46 @@ fn type_statement(
     }
 }
 
+
+// The values shown by a log, or by the message of an assert, are single
+// arithmetic values
+fn type_log_arguments(
+    args: &[LogArgument],
+    meta: &Meta,
+    program_archive: &ProgramArchive,
+    analysis_information: &mut AnalysisInformation,
+) {
+    for arglog in args {
+        if let LogArgument::LogExp(arg) = arglog{
+            let arg_response = type_expression(arg, program_archive, analysis_information);
+            let arg_type = if let Result::Ok(t) = arg_response {
+                t
+            } else {
+                return;
+            };
+            if arg_type.is_template() {
+                add_report(
+                    ReportCode::MustBeSingleArithmeticT,
+                    meta,
+                    &mut analysis_information.reports,
+                )
+            } else if arg_type.is_bus() {
+                add_report(
+                    ReportCode::MustBeSingleArithmeticB,
+                    meta,
+                    &mut analysis_information.reports,
+                )
+            } else if arg_type.dim() > 0 {
+                add_report(
+                    ReportCode::MustBeSingleArithmetic(arg_type.dim()),
+                    meta,
+                    &mut analysis_information.reports,
+                )
+            }
+        }
+    }
+}
+
 fn type_expression(
     expression: &Expression,
     program_archive: &ProgramArchive,
@@ -847,6 +889,24 @@ fn type_expression(
                 _ => unreachable!(),
             }
         }
+        Call { id, args, meta } if BuiltinCall::from_id(id).is_some() => {
+            // the call is typed as if its array had two elements
+            let builtin = BuiltinCall::from_id(id).unwrap();
+            for array in builtin.arrays(args) {
+                let array_type = type_expression(array, program_archive, analysis_information)?;
+                if !is_unrollable_array(array) || !builtin.dims().contains(&array_type.dim()) || array_type.is_template() || array_type.is_bus() {
+                    return add_report_and_end(ReportCode::WrongBuiltinCall, array.get_meta(), &mut analysis_information.reports);
+                }
+            }
+            for bound in builtin.bounds(args) {
+                let bound_type = type_expression(bound, program_archive, analysis_information)?;
+                if bound_type.dim() > 0 || bound_type.is_template() || bound_type.is_bus() {
+                    return add_report_and_end(ReportCode::WrongBuiltinCall, bound.get_meta(), &mut analysis_information.reports);
+                }
+            }
+            let unrolled = unroll_builtin_call(meta, id, args, &mut |_| 2);
+            type_expression(&unrolled, program_archive, analysis_information)
+        }
         Call { id, args, meta } => {
             analysis_information.reached.insert(id.clone());
             let typing_response =
@@ -1083,7 +1143,9 @@ fn check_if_it_is_a_tag(
         } else if let Some(wire_data) = output {
             (wire_data.get_type(), wire_data.get_tags())
         } else {
-            return add_report_and_end(ReportCode::InvalidSignalAccess, meta, reports);
+            let template = program_archive.get_template_data(&template_name);
+            let signals = template.get_inputs().keys().chain(template.get_outputs().keys());
+            return add_access_report_and_end(ReportCode::InvalidSignalAccess, meta, reports, accessed_element, signals, program_archive);
         };
         (kind, atags.clone())
     } 
@@ -1215,7 +1277,9 @@ fn apply_access_to_symbol(
                 (Option::Some(wire_data), _) | (_, Option::Some(wire_data)) =>
                     (wire_data.get_dimension(), wire_data.get_type(), wire_data.get_tags()),
                 _ => {
-                    return add_report_and_end(ReportCode::InvalidSignalAccess, meta, reports);
+                    let template = program_archive.get_template_data(&template_name);
+                    let signals = template.get_inputs().keys().chain(template.get_outputs().keys());
+                    return add_access_report_and_end(ReportCode::InvalidSignalAccess, meta, reports, accessed_element, signals, program_archive);
                 }
             };
             
@@ -1298,7 +1362,8 @@ fn apply_access_to_symbol(
                                 }
                             }
                             else{
-                                return add_report_and_end(ReportCode::InvalidTagAccess, meta, reports);
+                                let fields = program_archive.get_bus_data(&b_name).get_fields().keys().chain(tags.iter());
+                                return add_access_report_and_end(ReportCode::InvalidTagAccess, meta, reports, &accessed_element, fields, program_archive);
                             }
                         },
                 }
@@ -1440,6 +1505,49 @@ fn add_report_and_end<Ok>(
     Result::Err(())
 }
 
+// Reports an access to a signal, field or tag that does not exist, with the
+// suggestion of the closest one that does, as c.out for c.outt
+fn add_access_report_and_end<'a, Ok>(
+    error_code: ReportCode,
+    meta: &Meta,
+    reports: &mut ReportCollection,
+    name: &str,
+    candidates: impl Iterator<Item = &'a String>,
+    program_archive: &ProgramArchive,
+) -> Result<Ok, ()> {
+    add_report(error_code, meta, reports);
+    if let Some(report) = reports.last_mut() {
+        suggest_access_name(report, meta, name, candidates, program_archive);
+    }
+    Result::Err(())
+}
+
+// Suggests the closest of the candidates for the name accessed in the code of meta
+pub fn suggest_access_name<'a>(
+    report: &mut Report,
+    meta: &Meta,
+    name: &str,
+    candidates: impl Iterator<Item = &'a String>,
+    program_archive: &ProgramArchive,
+) {
+    let file_id = meta.get_file_id();
+    let location = generate_file_location(meta.start, meta.end);
+    let code = program_archive.get_file_library().get_source(file_id).and_then(|source| source.get(location));
+    let (Some(code), Some(closest)) = (code, closest_name(name, candidates)) else {
+        return;
+    };
+    // the name follows the dot of its access
+    let access = format!(".{}", name);
+    let offset = code.match_indices(&access).map(|(offset, _)| offset).find(|offset| {
+        !code[offset + access.len()..].starts_with(|c: char| c.is_alphanumeric() || c == '_')
+    });
+    if let Some(offset) = offset {
+        let start = meta.start + offset + 1;
+        let message = format!("replace {} with {}", name, closest);
+        report.add_suggestion(start..start + name.len(), file_id, name.to_string(), closest.clone(), message);
+    }
+}
+
 fn add_report(error_code: ReportCode, meta: &Meta, reports: &mut ReportCollection) {
     use ReportCode::*;
     let file_id = meta.get_file_id();
@@ -1527,6 +1635,8 @@ fn add_report(error_code: ReportCode, meta: &Meta, reports: &mut ReportCollectio
         MustBeBus => "Expected to be a bus".to_string(),
         InvalidSignalAccessInBus => format!("Field not defined in bus"),
         IllegalMainExpression => "Invalid main component: the main component should be a template, not a function call or expression".to_string(),
+        WrongTagRule => "The function of a tag rule must return a single value".to_string(),
+        WrongBuiltinCall => "The arrays of the builtin calls must be variables, inline arrays or builtin calls that return arrays, sum and sort only take arrays of single values, and the bounds of slice must be single values".to_string(),
         e => panic!("Unimplemented error code: {}", e),
     };
     report.add_primary(location, file_id, message);
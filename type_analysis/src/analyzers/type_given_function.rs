@@ -1,4 +1,5 @@
 use program_structure::ast::{Expression, Statement};
+use program_structure::builtin_calls::{unroll_builtin_call, BuiltinCall};
 use program_structure::function_data::FunctionData;
 use std::collections::{HashMap, HashSet};
 
@@ -298,6 +299,14 @@ fn look_for_type_in_expression(
             }
             
         }
+        Expression::Call { meta, id, args } if BuiltinCall::from_id(id).is_some() => look_for_type_in_expression(
+            function_name,
+            environment,
+            explored_functions,
+            function_data,
+            function_info,
+            &unroll_builtin_call(meta, id, args, &mut |_| 1),
+        ),
         Expression::Call { id, args, .. } => {
             if explored_functions.contains(id) {
                 return Option::None;
@@ -40,6 +40,10 @@ pub fn check_types(
     if !errors.is_empty() {
         return Result::Err(errors);
     }
+    if let Result::Err(mut compile_time_reports) = compile_time_calls_analysis(program_archive) {
+        errors.append(&mut compile_time_reports);
+        return Result::Err(errors);
+    }
 
     bus_level_decorators(program_archive, &mut errors);
     if !errors.is_empty() {
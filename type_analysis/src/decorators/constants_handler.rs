@@ -1,5 +1,6 @@
 use num_bigint::BigInt;
 use program_structure::ast::*;
+use program_structure::builtin_calls::BuiltinCall;
 use program_structure::error_code::ReportCode;
 use program_structure::error_definition::{Report, ReportCollection};
 use program_structure::expression_builders::*;
@@ -12,16 +13,37 @@ use std::collections::HashSet;
 type Constants = VarEnvironment<bool>;
 type ExpressionHolder = VarEnvironment<Expression>;
 
+// The lengths of the arrays of a function can depend on its parameters, as
+// in the templates, but then the function is only evaluated by the compiler
 pub fn handle_function_constants(function: &mut FunctionData) -> ReportCollection {
     let mut environment = Constants::new();
     let mut expression_holder = ExpressionHolder::new();
     for p in function.get_name_of_params() {
         environment.add_variable(p, false);
     }
+    let mut body = function.get_body().clone();
+    statement_constant_inference(&mut body, &mut environment);
+    let reports = statement_invariant_check(&body, &mut environment);
+    if reports.is_empty() {
+        expand_statement(&mut body, &mut expression_holder);
+        function.set_body(body);
+        return reports;
+    }
+    let mut environment = Constants::new();
+    for p in function.get_name_of_params() {
+        environment.add_variable(p, true);
+        let meta = function.get_body().get_meta().clone();
+        expression_holder.add_variable(p, build_variable(meta, p.clone(), vec![]));
+    }
     statement_constant_inference(function.get_mut_body(), &mut environment);
-    let reports = statement_invariant_check(function.get_body(), &mut environment);
+    let parametric_reports = statement_invariant_check(function.get_body(), &mut environment);
     expand_statement(function.get_mut_body(), &mut expression_holder);
-    reports
+    if parametric_reports.is_empty() {
+        function.set_compile_time(true);
+        parametric_reports
+    } else {
+        reports
+    }
 }
 pub fn _handle_template_constants(template: &mut TemplateData) -> ReportCollection {
     let mut environment = Constants::new();
@@ -163,10 +185,40 @@ fn statement_invariant_check(stmt: &Statement, environment: &mut Constants) -> R
         While { stmt, .. } => while_invariant_check(stmt, environment),
         Block { stmts, .. } => block_invariant_check(stmts, environment),
         MultSubstitution { .. } => unreachable!(),
+        Substitution { rhe, .. } | UnderscoreSubstitution { rhe, .. } | Return { value: rhe, .. } => {
+            slice_invariant_check(rhe, environment)
+        }
         _ => ReportCollection::new(),
     }
 }
 
+// The bounds of a slice give the length of the array it returns
+fn slice_invariant_check(expr: &Expression, environment: &Constants) -> ReportCollection {
+    use Expression::*;
+    let mut reports = ReportCollection::new();
+    let sub_expressions: Vec<&Expression> = match expr {
+        Call { id, args, .. } => {
+            if BuiltinCall::from_id(id) == Some(BuiltinCall::Slice) {
+                for bound in &args[1..] {
+                    if !has_constant_value(bound, environment) {
+                        broken_invariant_error(bound.get_meta(), &mut reports);
+                    }
+                }
+            }
+            args.iter().collect()
+        }
+        InfixOp { lhe, rhe, .. } => vec![lhe, rhe],
+        PrefixOp { rhe, .. } => vec![rhe],
+        InlineSwitchOp { cond, if_true, if_false, .. } => vec![cond, if_true, if_false],
+        ArrayInLine { values, .. } => values.iter().collect(),
+        _ => vec![],
+    };
+    for sub_expression in sub_expressions {
+        reports.append(&mut slice_invariant_check(sub_expression, environment));
+    }
+    reports
+}
+
 fn declaration_invariant_check(
     dimensions: &[Expression],
     environment: &mut Constants,
@@ -184,7 +236,7 @@ fn initialization_invariant_check(
     initializations: &[Statement],
     environment: &mut Constants,
 ) -> ReportCollection {
-    use Statement::Declaration;
+    use Statement::{Declaration, Substitution};
     let mut reports = ReportCollection::new();
     for init in initializations {
         if let Declaration { dimensions, .. } = init {
@@ -197,6 +249,11 @@ fn initialization_invariant_check(
             environment.add_variable(name, *is_constant);
         }
     }
+    for init in initializations {
+        if let Substitution { rhe, .. } = init {
+            reports.append(&mut slice_invariant_check(rhe, environment));
+        }
+    }
     reports
 }
 
@@ -309,7 +366,7 @@ fn expand_statement(stmt: &mut Statement, environment: &mut ExpressionHolder) {
         Substitution { access, rhe, .. } => expand_substitution(access, rhe, environment),
         ConstraintEquality { lhe, rhe, .. } => expand_constraint_equality(lhe, rhe, environment),
         LogCall { args, .. } => expand_log_call(args, environment),
-        Assert { arg, .. } => expand_assert(arg, environment),
+        Assert { arg, message, .. } => expand_assert(arg, message, environment),
         Block { stmts, .. } => expand_block(stmts, environment),
         MultSubstitution { .. } => unreachable!(),
         UnderscoreSubstitution { rhe, .. } => expand_underscore_substitution(rhe, environment),
@@ -407,8 +464,9 @@ fn expand_log_call(args: &mut Vec<LogArgument>, environment: &ExpressionHolder)
     }
 }
 
-fn expand_assert(arg: &mut Expression, environment: &ExpressionHolder) {
+fn expand_assert(arg: &mut Expression, message: &mut Vec<LogArgument>, environment: &ExpressionHolder) {
     *arg = expand_expression(arg.clone(), environment);
+    expand_log_call(message, environment);
 }
 
 fn expand_block(stmts: &mut [Statement], environment: &mut ExpressionHolder) {
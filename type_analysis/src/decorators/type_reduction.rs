@@ -1,3 +1,4 @@
+use crate::analyzers::type_check::suggest_access_name;
 use program_structure::error_code::ReportCode;
 use program_structure::error_definition::{Report, ReportCollection};
 use program_structure::program_archive::ProgramArchive;
@@ -57,7 +58,11 @@ fn reduce_types_in_statement(stmt: &mut Statement, environment: &mut Environment
                 reduce_types_in_log_call(args, environment,program_archive)
             
         },
-        Assert { arg, .. } => reduce_types_in_expression(arg, environment,program_archive),
+        Assert { arg, message, .. } => {
+            let mut reports = reduce_types_in_expression(arg, environment,program_archive);
+            reports.append(&mut reduce_types_in_log_call(message, environment,program_archive));
+            reports
+        }
         Return { value, .. } => reduce_types_in_expression(value, environment,program_archive),
         ConstraintEquality { lhe, rhe, .. } => {
             reduce_types_in_constraint_equality(lhe, rhe, environment,program_archive)
@@ -252,7 +257,11 @@ fn reduce_types_in_variable(
                                     WireType::Bus(new_name) => reduction = Bus(Some(new_name)),
                              }//If it is not a signal or a bus, it is expected to be tag. 
                             } else {//Then, type_check will finally check it.                          
+                                let candidates = template.get_inputs().keys().chain(template.get_outputs().keys());
                                 name_not_found_in_component_error(name.clone(), oname.to_string(), meta,&mut  reports); 
+                                if let Some(report) = reports.last_mut() {
+                                    suggest_access_name(report, meta, name, candidates, program_archive);
+                                }
                                 return reports;  
                             }
                         }
@@ -0,0 +1,576 @@
+use circom_algebra::modular_arithmetic::{self as field_ops, ArithmeticError};
+use circom_algebra::num_bigint::BigInt;
+use circom_algebra::num_traits::{One, ToPrimitive, Zero};
+use compiler::circuit_design::circuit::Circuit;
+use compiler::circuit_design::function::FunctionCodeInfo;
+use compiler::intermediate_representation::ir_interface::*;
+use std::collections::HashMap;
+
+// Why the witness generation stopped, with the template or function that
+// was running, the component, by its full name as in the sym file, and the
+// line of the code
+pub struct Failure {
+    pub message: String,
+    pub definition: String,
+    pub component: String,
+    pub line: usize,
+}
+
+// The values of the signals by their positions in the memory of the
+// circuit, where the signal 0 is the constant 1 and the ones that were not
+// assigned have no value, with the lines written by the logs
+pub struct Calculation {
+    pub signals: Vec<Option<BigInt>>,
+    pub logs: Vec<String>,
+    pub failure: Option<Failure>,
+}
+
+// What a debugger sees of the calculation: each instruction before it is
+// executed, the templates and functions that start and end running, and the
+// stores in the signals, by their positions in the memory
+pub trait Observer {
+    // The calculation stops when it returns false
+    fn instruction(&mut self, _line: usize, _signals: &[Option<BigInt>]) -> bool {
+        true
+    }
+    fn enter(&mut self, _definition: &str, _component: &str, _is_template: bool) {}
+    fn leave(&mut self) {}
+    fn stored(&mut self, _position: usize, _signals: &[Option<BigInt>]) {}
+}
+
+struct Unobserved;
+
+impl Observer for Unobserved {}
+
+type Execution<T> = Result<T, Failure>;
+
+enum Flow {
+    Next,
+    Return(Vec<BigInt>),
+}
+
+enum Place {
+    Variable(usize),
+    // the position of the signal in the memory of the circuit
+    Signal(usize),
+}
+
+// A subcomponent created by a template, with the position of its first
+// signal and the number of inputs that are not assigned yet
+struct Subcomponent {
+    template_id: usize,
+    signal_start: usize,
+    name: String,
+    inputs_left: usize,
+}
+
+// The template or the function that is running
+struct Frame<'a> {
+    definition: &'a str,
+    component: String,
+    signal_start: usize,
+    vars: HashMap<usize, BigInt>,
+    subcomponents: HashMap<usize, Subcomponent>,
+}
+
+// Runs the code of the circuit for the inputs of its main component, given
+// by their positions in the memory, as the witness calculators do. The
+// assertions and the constraints checked by the code must hold, and the
+// values of the signals computed until a failure are kept
+pub fn calculate_witness(circuit: &Circuit, inputs: &[(usize, BigInt)]) -> Calculation {
+    calculate_observed_witness(circuit, inputs, &mut Unobserved)
+}
+
+pub fn calculate_observed_witness(circuit: &Circuit, inputs: &[(usize, BigInt)], observer: &mut dyn Observer) -> Calculation {
+    let producer = &circuit.c_producer;
+    let field: BigInt = producer.prime.parse().unwrap();
+    let mut signals = vec![None; producer.get_total_number_of_signals()];
+    signals[0] = Some(BigInt::one());
+    for (position, value) in inputs {
+        signals[*position] = Some(field_ops::add(value, &BigInt::zero(), &field));
+    }
+    let mut calculator = Calculator {
+        circuit,
+        field,
+        signals,
+        logs: Vec::new(),
+        line: 0,
+        observer,
+        frame: Frame {
+            definition: "",
+            component: String::new(),
+            signal_start: 0,
+            vars: HashMap::new(),
+            subcomponents: HashMap::new(),
+        },
+    };
+    let main = circuit.templates.iter().position(|template| template.header == producer.main_header);
+    let failure = match main {
+        Some(main) => calculator.run_template(main, producer.get_main_signal_offset(), "main".to_string()).err(),
+        None => Some(calculator.fail("the circuit has no main component")),
+    };
+    Calculation { signals: calculator.signals, logs: calculator.logs, failure }
+}
+
+struct Calculator<'a> {
+    circuit: &'a Circuit,
+    field: BigInt,
+    signals: Vec<Option<BigInt>>,
+    logs: Vec<String>,
+    line: usize,
+    observer: &'a mut dyn Observer,
+    frame: Frame<'a>,
+}
+
+impl<'a> Calculator<'a> {
+    fn fail(&self, message: &str) -> Failure {
+        Failure {
+            message: message.to_string(),
+            definition: self.frame.definition.to_string(),
+            component: self.frame.component.clone(),
+            line: self.line,
+        }
+    }
+
+    fn run_template(&mut self, template_id: usize, signal_start: usize, component: String) -> Execution<()> {
+        let template = self.circuit.get_template(template_id);
+        let frame = Frame {
+            definition: &template.name,
+            component,
+            signal_start,
+            vars: HashMap::new(),
+            subcomponents: HashMap::new(),
+        };
+        self.observer.enter(&template.name, &frame.component, true);
+        let parent = std::mem::replace(&mut self.frame, frame);
+        let line = self.line;
+        self.execute_block(&template.body)?;
+        self.observer.leave();
+        self.frame = parent;
+        self.line = line;
+        Ok(())
+    }
+
+    fn run_function(&mut self, function: &'a FunctionCodeInfo, arguments: Vec<BigInt>) -> Execution<Vec<BigInt>> {
+        let frame = Frame {
+            definition: &function.name,
+            component: self.frame.component.clone(),
+            signal_start: self.frame.signal_start,
+            vars: arguments.into_iter().enumerate().collect(),
+            subcomponents: HashMap::new(),
+        };
+        self.observer.enter(&function.name, &frame.component, false);
+        let caller = std::mem::replace(&mut self.frame, frame);
+        let line = self.line;
+        let flow = self.execute_block(&function.body)?;
+        self.observer.leave();
+        self.frame = caller;
+        self.line = line;
+        match flow {
+            Flow::Return(values) => Ok(values),
+            Flow::Next => Ok(Vec::new()),
+        }
+    }
+
+    fn execute_block(&mut self, block: &'a [InstructionPointer]) -> Execution<Flow> {
+        for instruction in block {
+            if let Flow::Return(values) = self.execute(instruction)? {
+                return Ok(Flow::Return(values));
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn execute(&mut self, instruction: &'a Instruction) -> Execution<Flow> {
+        use Instruction::*;
+        self.line = instruction.get_line();
+        if !self.observer.instruction(self.line, &self.signals) {
+            return Err(self.fail("the witness calculation is stopped"));
+        }
+        match instruction {
+            Store(bucket) => {
+                let values = self.evaluate(&bucket.src)?;
+                self.store(&bucket.dest_address_type, &bucket.dest, &bucket.context.size, values)?;
+                Ok(Flow::Next)
+            }
+            ArrayCopy(bucket) => {
+                let source = self.address(&bucket.src_address_type, &bucket.src)?;
+                let values = self.read(&source, bucket.size)?;
+                let destination = self.address(&bucket.dest_address_type, &bucket.dest)?;
+                self.write(&destination, bucket.size, values);
+                Ok(Flow::Next)
+            }
+            Call(bucket) => {
+                let values = self.call(bucket)?;
+                if let ReturnType::Final(data) = &bucket.return_info {
+                    self.store(&data.dest_address_type, &data.dest, &data.context.size, values)?;
+                }
+                Ok(Flow::Next)
+            }
+            Branch(bucket) => {
+                if self.evaluate_condition(&bucket.cond)? {
+                    self.execute_block(&bucket.if_branch)
+                } else {
+                    self.execute_block(&bucket.else_branch)
+                }
+            }
+            Switch(bucket) => {
+                let selector = self.evaluate_single(&bucket.selector)?;
+                match bucket.cases.iter().find(|case| BigInt::from(case.value) == selector) {
+                    Some(case) => self.execute_block(&case.body),
+                    None => self.execute_block(&bucket.default_branch),
+                }
+            }
+            Loop(bucket) => {
+                while self.evaluate_condition(&bucket.continue_condition)? {
+                    if let Flow::Return(values) = self.execute_block(&bucket.body)? {
+                        return Ok(Flow::Return(values));
+                    }
+                }
+                Ok(Flow::Next)
+            }
+            Return(bucket) => {
+                let mut values = self.evaluate(&bucket.value)?;
+                values.resize(bucket.with_size, BigInt::zero());
+                Ok(Flow::Return(values))
+            }
+            Assert(bucket) => {
+                if self.evaluate_condition(&bucket.evaluate)? {
+                    return Ok(Flow::Next);
+                }
+                let mut parts = Vec::new();
+                for part in &bucket.message {
+                    match part {
+                        AssertMessagePart::Str(id) => parts.push(self.circuit.c_producer.get_string_table()[*id].clone()),
+                        AssertMessagePart::Value(position) => {
+                            let value = self.evaluate_single(&bucket.values[*position])?;
+                            parts.push(value.to_string());
+                        }
+                    }
+                }
+                // the message of a constraint is the values of its sides
+                let message = match (bucket.is_constraint, parts.as_slice()) {
+                    (true, [left, right]) => format!("the constraint does not hold, {} != {}", left, right),
+                    (true, _) => "the constraint does not hold".to_string(),
+                    (false, []) => "the assertion does not hold".to_string(),
+                    (false, _) => parts.join(" "),
+                };
+                Err(self.fail(&message))
+            }
+            Log(bucket) => {
+                let mut parts = Vec::new();
+                for argument in &bucket.argsprint {
+                    match argument {
+                        LogBucketArg::LogStr(id) => parts.push(self.circuit.c_producer.get_string_table()[*id].clone()),
+                        LogBucketArg::LogExp(expression) => parts.push(self.evaluate_single(expression)?.to_string()),
+                    }
+                }
+                let separator = if bucket.is_formatted { "" } else { " " };
+                self.logs.push(parts.join(separator));
+                Ok(Flow::Next)
+            }
+            CreateCmp(bucket) => {
+                self.create(bucket)?;
+                Ok(Flow::Next)
+            }
+            Value(_) | Load(_) | Compute(_) | Select(_) => {
+                self.evaluate(instruction)?;
+                Ok(Flow::Next)
+            }
+        }
+    }
+
+    fn evaluate(&mut self, instruction: &'a Instruction) -> Execution<Vec<BigInt>> {
+        use Instruction::*;
+        match instruction {
+            Value(bucket) => {
+                let value = match bucket.parse_as {
+                    ValueType::U32 => BigInt::from(bucket.value),
+                    ValueType::BigInt => self.circuit.c_producer.field_tracking[bucket.value].parse().unwrap(),
+                };
+                Ok(vec![value])
+            }
+            Load(bucket) => {
+                let (place, size) = self.place(&bucket.address_type, &bucket.src, &bucket.context.size)?;
+                self.read(&place, size)
+            }
+            Compute(bucket) => self.compute(bucket),
+            Select(bucket) => {
+                if self.evaluate_condition(&bucket.cond)? {
+                    self.evaluate(&bucket.if_true)
+                } else {
+                    self.evaluate(&bucket.if_false)
+                }
+            }
+            Call(bucket) => self.call(bucket),
+            _ => Err(self.fail("an instruction is used as an expression")),
+        }
+    }
+
+    fn evaluate_single(&mut self, instruction: &'a Instruction) -> Execution<BigInt> {
+        Ok(self.evaluate(instruction)?.into_iter().next().unwrap_or_default())
+    }
+
+    fn evaluate_condition(&mut self, instruction: &'a Instruction) -> Execution<bool> {
+        Ok(!self.evaluate_single(instruction)?.is_zero())
+    }
+
+    fn evaluate_index(&mut self, instruction: &'a Instruction) -> Execution<usize> {
+        let value = self.evaluate_single(instruction)?;
+        value.to_usize().ok_or_else(|| self.fail(&format!("the position {} is out of the memory", value)))
+    }
+
+    fn compute(&mut self, bucket: &'a ComputeBucket) -> Execution<Vec<BigInt>> {
+        use OperatorType::*;
+        let mut operands = Vec::new();
+        for operand in &bucket.stack {
+            operands.push(self.evaluate(operand)?);
+        }
+        let values: Vec<BigInt> = operands.iter().map(|values| values.first().cloned().unwrap_or_default()).collect();
+        let field = &self.field;
+        let (a, b) = (&values[0], values.get(1).unwrap_or(&values[0]));
+        let result = match &bucket.op {
+            Add => field_ops::add(a, b, field),
+            Sub => field_ops::sub(a, b, field),
+            Mul => field_ops::mul(a, b, field),
+            Pow => field_ops::pow(a, b, field),
+            PrefixSub => field_ops::prefix_sub(a, field),
+            Complement => field_ops::complement(a, field),
+            BoolNot => field_ops::not(a, field),
+            BoolOr => field_ops::bool_or(a, b, field),
+            BoolAnd => field_ops::bool_and(a, b, field),
+            BitOr => field_ops::bit_or(a, b, field),
+            BitAnd => field_ops::bit_and(a, b, field),
+            BitXor => field_ops::bit_xor(a, b, field),
+            Lesser => field_ops::lesser(a, b, field),
+            LesserEq => field_ops::lesser_eq(a, b, field),
+            Greater => field_ops::greater(a, b, field),
+            GreaterEq => field_ops::greater_eq(a, b, field),
+            NotEq => field_ops::not_eq(a, b, field),
+            Eq(_) => {
+                // the arrays are equal when all their positions are
+                let equal = operands[0].iter().zip(&operands[1]).all(|(left, right)| field_ops::eq(left, right, field).is_one());
+                if equal {
+                    BigInt::one()
+                } else {
+                    BigInt::zero()
+                }
+            }
+            Div | IntDiv | Mod | ShiftL | ShiftR => {
+                let result = match &bucket.op {
+                    Div => field_ops::div(a, b, field),
+                    IntDiv => field_ops::idiv(a, b, field),
+                    Mod if field_ops::add(b, &BigInt::zero(), field).is_zero() => Err(ArithmeticError::DivisionByZero),
+                    Mod => field_ops::mod_op(a, b, field),
+                    ShiftL => field_ops::shift_l(a, b, field),
+                    _ => field_ops::shift_r(a, b, field),
+                };
+                match result {
+                    Ok(value) => value,
+                    Err(ArithmeticError::DivisionByZero) => return Err(self.fail("it divides by 0")),
+                    Err(ArithmeticError::BitOverFlowInShift) => return Err(self.fail("the shift is too large")),
+                }
+            }
+            // the operations on the positions of the memory are the ones of
+            // the integers
+            ToAddress => a.clone(),
+            AddAddress => a + b,
+            MulAddress => a * b,
+        };
+        Ok(vec![result])
+    }
+
+    fn call(&mut self, bucket: &'a CallBucket) -> Execution<Vec<BigInt>> {
+        let circuit = self.circuit;
+        let function = match circuit.functions.iter().find(|function| function.header == bucket.symbol) {
+            Some(function) => function,
+            None => return Err(self.fail(&format!("it calls the unknown function {}", bucket.symbol))),
+        };
+        if function.is_extern {
+            return Err(self.fail(&format!("it calls the extern function {}, which is only defined in the generated code", function.name)));
+        }
+        let mut arguments = Vec::new();
+        for (argument, context) in bucket.arguments.iter().zip(&bucket.argument_types) {
+            let mut values = self.evaluate(argument)?;
+            let size = match &context.size {
+                SizeOption::Single(size) => *size,
+                SizeOption::Multiple(_) => values.len(),
+            };
+            values.resize(size, BigInt::zero());
+            arguments.extend(values);
+        }
+        self.run_function(function, arguments)
+    }
+
+    fn create(&mut self, bucket: &'a CreateCmpBucket) -> Execution<()> {
+        let start = self.evaluate_index(&bucket.sub_cmp_id)?;
+        let positions: Vec<usize> = if bucket.defined_positions.len() == bucket.number_of_cmp {
+            (0..bucket.number_of_cmp).collect()
+        } else {
+            bucket.defined_positions.iter().map(|(position, _)| *position).collect()
+        };
+        let inputs = self.circuit.get_template(bucket.template_id).number_of_inputs;
+        for (iteration, position) in positions.into_iter().enumerate() {
+            // the position in the array of components, as in c[1][0]
+            let mut indexes = Vec::new();
+            let mut rest = position;
+            for length in bucket.dimensions.iter().rev() {
+                indexes.push(format!("[{}]", rest % length));
+                rest /= length;
+            }
+            indexes.reverse();
+            let subcomponent = Subcomponent {
+                template_id: bucket.template_id,
+                signal_start: self.frame.signal_start + bucket.signal_offset + iteration * bucket.signal_offset_jump,
+                name: format!("{}{}", bucket.name_subcomponent, indexes.concat()),
+                inputs_left: inputs,
+            };
+            self.frame.subcomponents.insert(start + position, subcomponent);
+            // the components without inputs run once they are created
+            if !bucket.has_inputs {
+                self.run_subcomponent(start + position)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn run_subcomponent(&mut self, id: usize) -> Execution<()> {
+        let subcomponent = &self.frame.subcomponents[&id];
+        let (template_id, signal_start) = (subcomponent.template_id, subcomponent.signal_start);
+        let component = format!("{}.{}", self.frame.component, subcomponent.name);
+        self.run_template(template_id, signal_start, component)
+    }
+
+    fn subcomponent(&self, id: usize) -> Execution<&Subcomponent> {
+        self.frame.subcomponents.get(&id).ok_or_else(|| self.fail("it accesses a component that is not created"))
+    }
+
+    // Writes the values to the place, and runs the subcomponent when the
+    // values are its last inputs
+    fn store(
+        &mut self,
+        address: &'a AddressType,
+        location: &'a LocationRule,
+        size: &SizeOption,
+        values: Vec<BigInt>,
+    ) -> Execution<()> {
+        let (place, size) = self.place(address, location, size)?;
+        self.write(&place, size, values);
+        if let AddressType::SubcmpSignal { cmp_address, input_information: InputInformation::Input { status }, .. } = address {
+            let id = self.evaluate_index(cmp_address)?;
+            self.subcomponent(id)?;
+            let subcomponent = self.frame.subcomponents.get_mut(&id).unwrap();
+            subcomponent.inputs_left = subcomponent.inputs_left.saturating_sub(size);
+            let runs = match status {
+                StatusInput::Last => true,
+                StatusInput::NoLast => false,
+                StatusInput::Unknown => subcomponent.inputs_left == 0,
+            };
+            if runs {
+                self.run_subcomponent(id)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn place(&mut self, address: &'a AddressType, location: &'a LocationRule, size: &SizeOption) -> Execution<(Place, usize)> {
+        let component = match address {
+            AddressType::SubcmpSignal { cmp_address, .. } => {
+                let id = self.evaluate_index(cmp_address)?;
+                let subcomponent = self.subcomponent(id)?;
+                Some((subcomponent.template_id, subcomponent.signal_start))
+            }
+            _ => None,
+        };
+        let index = match location {
+            LocationRule::Indexed { location, .. } => self.evaluate_index(location)?,
+            LocationRule::Mapped { signal_code, indexes } => {
+                let template_id = match component {
+                    Some((template_id, _)) => template_id,
+                    None => return Err(self.fail("it accesses by name a signal that is not of a component")),
+                };
+                let producer = &self.circuit.c_producer;
+                let definition = &producer.get_io_map()[&template_id][*signal_code];
+                let mut offset = definition.offset;
+                let (mut lengths, mut element_size, mut bus_id) = (&definition.lengths, definition.size, definition.bus_id);
+                for access in indexes {
+                    match access {
+                        AccessType::Indexed(info) => {
+                            let mut position = 0;
+                            for (dimension, index) in info.indexes.iter().enumerate() {
+                                position = position * lengths[dimension] + self.evaluate_index(index)?;
+                            }
+                            for length in &lengths[info.indexes.len()..info.symbol_dim] {
+                                position *= length;
+                            }
+                            offset += position * element_size;
+                        }
+                        AccessType::Qualified(field) => {
+                            let data = &producer.get_busid_field_info()[bus_id.unwrap()][*field];
+                            offset += data.offset;
+                            lengths = &data.dimensions;
+                            element_size = data.size;
+                            bus_id = data.bus_id;
+                        }
+                    }
+                }
+                offset
+            }
+        };
+        let size = match size {
+            SizeOption::Single(size) => *size,
+            SizeOption::Multiple(sizes) => {
+                let template_id = component.map(|(template_id, _)| template_id);
+                sizes.iter().find(|(id, _)| Some(*id) == template_id).map_or(1, |(_, size)| *size)
+            }
+        };
+        let place = match (address, component) {
+            (AddressType::Variable, _) => Place::Variable(index),
+            (_, Some((_, signal_start))) => Place::Signal(signal_start + index),
+            _ => Place::Signal(self.frame.signal_start + index),
+        };
+        Ok((place, size))
+    }
+
+    fn address(&mut self, address: &'a AddressType, location: &'a Instruction) -> Execution<Place> {
+        let index = self.evaluate_index(location)?;
+        match address {
+            AddressType::Variable => Ok(Place::Variable(index)),
+            AddressType::Signal => Ok(Place::Signal(self.frame.signal_start + index)),
+            AddressType::SubcmpSignal { .. } => Err(self.fail("it copies the signals of a component")),
+        }
+    }
+
+    fn read(&self, place: &Place, size: usize) -> Execution<Vec<BigInt>> {
+        let mut values = Vec::new();
+        for offset in 0..size {
+            let value = match place {
+                Place::Variable(index) => self.frame.vars.get(&(index + offset)).cloned().unwrap_or_default(),
+                Place::Signal(position) => match self.signals.get(position + offset) {
+                    Some(Some(value)) => value.clone(),
+                    Some(None) => return Err(self.fail("it reads a signal that is not assigned yet")),
+                    None => return Err(self.fail("it reads a signal out of the memory")),
+                },
+            };
+            values.push(value);
+        }
+        Ok(values)
+    }
+
+    fn write(&mut self, place: &Place, size: usize, mut values: Vec<BigInt>) {
+        values.resize(size.max(values.len()), BigInt::zero());
+        for (offset, value) in values.into_iter().take(size).enumerate() {
+            match place {
+                Place::Variable(index) => {
+                    self.frame.vars.insert(index + offset, value);
+                }
+                Place::Signal(position) => {
+                    if let Some(signal) = self.signals.get_mut(position + offset) {
+                        *signal = Some(value);
+                        self.observer.stored(position + offset, &self.signals);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use circom_algebra::num_bigint::BigInt;
+use compiler::circuit_design::circuit::Circuit;
+use serde_json::Value;
+use std::collections::HashMap;
+
+// Reads the inputs of the main component from a json object, as the one of
+// the witness calculators, and gives their positions in the memory of the
+// circuit. The value of each input is a number, given as a string when it
+// is large, or a list of them for an array, and the buses are objects with
+// their fields, which can also be given by their qualified names, as "p.x"
+pub fn read_inputs(circuit: &Circuit, json: &str) -> Result<Vec<(usize, BigInt)>, String> {
+    let value: Value = serde_json::from_str(json).map_err(|error| format!("the inputs are not valid json: {}", error))?;
+    let object = match &value {
+        Value::Object(object) => object,
+        _ => return Err("the inputs must be a json object".to_string()),
+    };
+    let mut given = Vec::new();
+    for (name, value) in object {
+        collect(name.clone(), value, &mut given)?;
+    }
+    place_inputs(circuit, given)
+}
+
+// The positions in the memory of the circuit of the values of each input of
+// the main component, given by its name or the qualified name of a field of
+// a bus, as "p.x", with the values of its arrays in a list
+pub fn place_inputs(circuit: &Circuit, given: Vec<(String, Vec<BigInt>)>) -> Result<Vec<(usize, BigInt)>, String> {
+    let list = circuit.c_producer.get_main_input_list();
+    let by_name: HashMap<&str, (usize, usize)> = list.iter().map(|input| (input.name.as_str(), (input.start, input.size))).collect();
+    let mut inputs = Vec::new();
+    let mut assigned = HashMap::new();
+    for (name, values) in given {
+        let (start, size) = match by_name.get(name.as_str()) {
+            Some(input) => *input,
+            None => return Err(format!("the main component has no input {}", name)),
+        };
+        if values.len() != size {
+            return Err(format!("the input {} has {} values, and {} were given", name, size, values.len()));
+        }
+        for (offset, value) in values.into_iter().enumerate() {
+            assigned.insert(start + offset, name.clone());
+            inputs.push((start + offset, value));
+        }
+    }
+    // the qualified names of the fields of a bus are also in the list
+    for input in list {
+        let missing = (input.start..input.start + input.size).any(|position| !assigned.contains_key(&position));
+        if missing && input.bus_id.is_none() {
+            return Err(format!("the input {} is not given", input.name));
+        }
+    }
+    Ok(inputs)
+}
+
+// The values of each input of the object, where the objects nested in it
+// are buses, in lists for the arrays of buses
+fn collect(name: String, value: &Value, given: &mut Vec<(String, Vec<BigInt>)>) -> Result<(), String> {
+    match value {
+        Value::Object(fields) => {
+            for (field, value) in fields {
+                collect(format!("{}.{}", name, field), value, given)?;
+            }
+        }
+        Value::Array(values) if values.iter().any(has_objects) => {
+            for (index, value) in values.iter().enumerate() {
+                collect(format!("{}[{}]", name, index), value, given)?;
+            }
+        }
+        _ => {
+            let mut values = Vec::new();
+            flatten(&name, value, &mut values)?;
+            given.push((name, values));
+        }
+    }
+    Ok(())
+}
+
+fn has_objects(value: &Value) -> bool {
+    match value {
+        Value::Object(_) => true,
+        Value::Array(values) => values.iter().any(has_objects),
+        _ => false,
+    }
+}
+
+fn flatten(name: &str, value: &Value, values: &mut Vec<BigInt>) -> Result<(), String> {
+    let number = match value {
+        Value::Array(elements) => {
+            for element in elements {
+                flatten(name, element, values)?;
+            }
+            return Ok(());
+        }
+        Value::Number(number) => number.to_string(),
+        Value::String(text) => text.trim().to_string(),
+        Value::Bool(value) => (*value as u8).to_string(),
+        Value::Null | Value::Object(_) => String::new(),
+    };
+    let parsed = match number.strip_prefix("0x") {
+        Some(hex) => BigInt::parse_bytes(hex.as_bytes(), 16),
+        None => number.parse().ok(),
+    };
+    parsed.ok_or_else(|| format!("the value {} of the input {} is not an integer", value, name)).map(|value| values.push(value))
+}
@@ -0,0 +1,5 @@
+mod calculator;
+mod inputs;
+
+pub use calculator::{calculate_observed_witness, calculate_witness, Calculation, Failure, Observer};
+pub use inputs::{place_inputs, read_inputs};
@@ -0,0 +1,137 @@
+use circom_algebra::num_bigint::BigInt;
+use compiler::circuit_design::circuit::Circuit;
+use compiler::compiler_interface::{self, Config, OptimizationLevel, Pass, PassConfig};
+use constraint_generation::{build_dag, BuildConfig};
+use constraint_writers::graph_writer::GraphFormat;
+use parser::ParserConfig;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use type_analysis::check_types::check_types;
+
+const VERSION: &str = "2.2.2";
+
+// Variables alive one after the other, alive together, arrays and loops, in
+// a function and in a template
+const CIRCUIT: &str = "pragma circom 2.0.0;
+
+function mix(a, b) {
+    var s = 0;
+    for (var i = 0; i < 4; i++) {
+        var t = a * i;
+        s += t;
+        var u = b + t;
+        s += u * u;
+    }
+    var w[3] = [a, b, s];
+    var r = 0;
+    for (var j = 0; j < 3; j++) {
+        r = r * 7 + w[j];
+    }
+    return r;
+}
+
+template Mix(n) {
+    signal input in[n];
+    signal output out[n];
+    signal output total;
+    var acc = 0;
+    for (var i = 0; i < n; i++) {
+        var x = i * 3;
+        out[i] <-- mix(in[i], x);
+        acc += in[i];
+    }
+    total <== acc * acc;
+}
+
+component main = Mix(4);
+";
+
+const INPUTS: &str = r#"{"in": ["3", "5", "11", "0"]}"#;
+
+fn build_config() -> BuildConfig {
+    BuildConfig {
+        no_rounds: 0,
+        function_steps: 10_000_000,
+        template_recursion: 100,
+        flag_json_sub: false,
+        json_substitutions: String::new(),
+        flag_s: false,
+        flag_f: true,
+        flag_p: false,
+        flag_verbose: false,
+        inspect_constraints: false,
+        inspect_witness: false,
+        inspect_determinism: false,
+        deny_unconstrained: false,
+        suggest_parallel: false,
+        flag_template_report: false,
+        template_report: String::new(),
+        flag_component_graph: false,
+        component_graph_format: GraphFormat::Json,
+        component_graph: String::new(),
+        flag_old_heuristics: false,
+        prime: "bn128".to_string(),
+    }
+}
+
+fn compile(source: &str, passes: PassConfig) -> Circuit {
+    let path = PathBuf::from("main.circom");
+    let sources = HashMap::from([(path.clone(), source.to_string())]);
+    let config = ParserConfig {
+        version: VERSION,
+        link_libraries: Vec::new(),
+        prime: "bn128",
+        flag_prime: false,
+        flag_no_init: false,
+        flag_enforce_tags: false,
+        features: &[],
+    };
+    let result = parser::run_parser_with_sources(path.display().to_string(), &config, &sources);
+    let (mut program, _) = result.unwrap_or_else(|_| panic!("the circuit does not parse"));
+    check_types(&mut program).unwrap_or_else(|_| panic!("the circuit is not well typed"));
+    let (_, vcp) = build_dag(program, &build_config()).unwrap();
+    let config = Config {
+        debug_output: false,
+        produce_input_log: false,
+        wat_flag: false,
+        no_asm_flag: false,
+        constraint_assert_disabled_flag: false,
+        passes,
+        lazy_components_flag: false,
+        trace_signals_flag: false,
+        profile_templates_flag: false,
+        ir_cache: None,
+        prime: "bn128".to_string(),
+    };
+    compiler_interface::run_compiler(vcp, config, VERSION).unwrap()
+}
+
+fn witness(passes: PassConfig) -> Vec<Option<BigInt>> {
+    let circuit = compile(CIRCUIT, passes);
+    let inputs = witness_calculation::read_inputs(&circuit, INPUTS).unwrap();
+    let calculation = witness_calculation::calculate_witness(&circuit, &inputs);
+    assert!(calculation.failure.is_none());
+    calculation.signals
+}
+
+fn passes(level: OptimizationLevel, disabled_passes: Vec<Pass>) -> PassConfig {
+    PassConfig { level, disabled_passes, report_timing: false, report_effects: false, verify: true }
+}
+
+#[test]
+fn the_optimization_levels_do_not_change_the_witness() {
+    let unoptimized = witness(passes(OptimizationLevel::O0, Vec::new()));
+    assert!(unoptimized.iter().all(|signal| signal.is_some()));
+    for level in [OptimizationLevel::O1, OptimizationLevel::O2] {
+        assert!(witness(passes(level, Vec::new())) == unoptimized);
+    }
+}
+
+#[test]
+fn each_pass_alone_does_not_change_the_witness() {
+    let unoptimized = witness(passes(OptimizationLevel::O0, Vec::new()));
+    for pass in Pass::ALL {
+        let others = Pass::ALL.iter().copied().filter(|other| *other != pass).collect();
+        assert!(witness(passes(OptimizationLevel::O2, others)) == unoptimized, "{}", pass.name());
+    }
+}